@@ -0,0 +1,167 @@
+#!/usr/bin/env python3
+"""
+Deterministic stand-in for system_stt_daemon.py
+
+Speaks the same JSON-RPC protocol (docs/daemon-protocol.md) but replays a script instead of running a
+model, so the desktop app can be exercised without audio hardware or weights.
+
+Script format:
+    {"devices": ["Mock Input"],
+     "steps": [{"delay_ms": 100, "text": "hello", "confidence": 0.9, "speaker": "Speaker 1"},
+               {"delay_ms": 50, "exit": 3}]}
+
+Once the steps run out the daemon idles until stopped (or until stdin or the
+ring closes in --input stdin / shm mode), like a live daemon with nobody speaking. With
+--input file it exits cleanly instead, as a finished simulated input would.
+"""
+import argparse
+import json
+import mmap
+import os
+import signal
+import struct
+import sys
+import threading
+import time
+
+PROTOCOL_VERSION = 1
+
+# Responses are written from their own thread.
+emit_lock = threading.Lock()
+rpc = False
+started = threading.Event()
+
+
+def write(message):
+    with emit_lock:
+        sys.stdout.write(json.dumps(message) + "\n")
+        sys.stdout.flush()
+
+
+def emit(message):
+    if rpc:
+        params = dict(message)
+        message = {"jsonrpc": "2.0", "method": params.pop("type"), "params": params}
+    write(message)
+
+
+def serve_rpc():
+    for line in sys.stdin:
+        try:
+            request = json.loads(line)
+        except ValueError:
+            continue
+        method, params = request.get("method"), request.get("params") or {}
+        response = {"jsonrpc": "2.0", "id": request.get("id"), "result": {}}
+        if method == "configure":
+            if "protocol" in params:
+                response["result"] = {"daemon": "mock_stt_daemon",
+                                      "protocol": min(params["protocol"], PROTOCOL_VERSION)}
+        elif method == "start":
+            started.set()
+        elif method == "stop":
+            write(response)
+            os._exit(0)
+        elif method != "ping":
+            del response["result"]
+            response["error"] = {"code": -32601, "message": f"Unknown method {method}"}
+        write(response)
+    os._exit(0)
+
+
+def run_steps(steps):
+    position = 0.0
+    for step in steps:
+        time.sleep(step.get("delay_ms", 0) / 1000)
+        if "exit" in step:
+            sys.stderr.write(step.get("error", "mock daemon exiting") + "\n")
+            sys.exit(step["exit"])
+        duration = step.get("duration", 1.0)
+        emit({"type": "transcript", "text": step["text"],
+              "confidence": step.get("confidence"),
+              "start": position, "end": position + duration,
+              "processing_ms": step.get("delay_ms", 0),
+              "speaker": step.get("speaker")})
+        position += duration
+
+
+def idle(input_mode, ring_path):
+    if input_mode == "stdin":
+        while sys.stdin.buffer.read(4096):
+            pass
+        return
+    if input_mode == "shm":
+        with open(ring_path, "r+b") as f:
+            ring = mmap.mmap(f.fileno(), 0)
+        # Keeps up with any rate by discarding everything written so far.
+        while not struct.unpack_from("<I", ring, 16)[0]:
+            struct.pack_into("<Q", ring, 128, struct.unpack_from("<Q", ring, 64)[0])
+            time.sleep(0.05)
+        return
+    while True:
+        time.sleep(1)
+
+
+if __name__ == "__main__":
+    parser = argparse.ArgumentParser(description="Scripted mock STT daemon")
+    parser.add_argument("--script", required=True, help="JSON script to replay")
+    parser.add_argument("--input", choices=["mic", "stdin", "shm", "file"], default="mic")
+    parser.add_argument("--ring", metavar="PATH")
+    parser.add_argument("--input-file", metavar="PATH")
+    parser.add_argument("--speed", type=float, default=1.0)
+    parser.add_argument("--json", action="store_true")
+    parser.add_argument("--model", default="mock")
+    parser.add_argument("--language", default="en")
+    parser.add_argument("--transcribe-file", metavar="PATH")
+    parser.add_argument("--idle-unload", type=float)
+    parser.add_argument("--pre-roll", type=float)
+    parser.add_argument("--silence-timeout", type=float)
+    parser.add_argument("--max-utterance", type=float)
+    parser.add_argument("--benchmark", metavar="PATH")
+    parser.add_argument("--runs", type=int, default=5)
+    parser.add_argument("--no-type", action="store_true")
+    parser.add_argument("--list-devices", action="store_true")
+    parser.add_argument("--prefetch", action="store_true")
+    parser.add_argument("--check-model", action="store_true")
+    parser.add_argument("--input-device")
+    parser.add_argument("--device", default="cpu")
+    parser.add_argument("--compute-type", default="int8")
+    parser.add_argument("--threads", type=int, default=0)
+    parser.add_argument("--beam-size", type=int, default=5)
+    parser.add_argument("--batch-size", type=int, default=1)
+    parser.add_argument("--rpc", action="store_true")
+    parser.add_argument("--save-audio", metavar="DIR")
+    parser.add_argument("--initial-prompt", metavar="TEXT")
+    parser.add_argument("--diarize", action="store_true")
+    args = parser.parse_args()
+
+    # Nothing is ever queued, so a graceful stop can exit at once.
+    signal.signal(signal.SIGTERM, lambda *_: sys.exit(0))
+
+    with open(args.script) as f:
+        script = json.load(f)
+    steps = script.get("steps", [])
+    if args.rpc:
+        rpc = True
+        threading.Thread(target=serve_rpc, daemon=True).start()
+
+    if args.list_devices:
+        print(json.dumps(script.get("devices", ["Mock Input"])))
+    elif args.prefetch:
+        pass
+    elif args.check_model:
+        print(args.model)
+    elif args.benchmark:
+        emit({"type": "benchmark_load", "ms": 0})
+        text = " ".join(step["text"] for step in steps if "text" in step)
+        for _ in range(args.runs):
+            emit({"type": "benchmark_run", "stt_ms": sum(s.get("delay_ms", 0) for s in steps),
+                  "text": text, "duration": float(len(steps))})
+    elif args.transcribe_file or args.input == "file":
+        # Simulated input ends with the file, like the real daemon.
+        run_steps(steps)
+    else:
+        if rpc:
+            started.wait()
+        run_steps(steps)
+        idle(args.input, args.ring)
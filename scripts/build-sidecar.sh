@@ -0,0 +1,22 @@
+#!/usr/bin/env bash
+# Freezes system_stt_daemon.py into binaries/stt-daemon-<target triple>, the
+# sidecar Tauri bundles from `externalBin`. Run once before `cargo tauri dev`
+# or `cargo tauri build`; needs the daemon's requirements and PyInstaller.
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+python="${PYTHON:-python3}"
+triple="${TARGET_TRIPLE:-$(rustc -vV | sed -n 's/^host: //p')}"
+ext=""
+case "$triple" in
+  *windows*) ext=".exe" ;;
+esac
+
+"$python" -m PyInstaller --noconfirm --onefile --name stt-daemon \
+  --collect-all faster_whisper --collect-all ctranslate2 \
+  --distpath build/sidecar --workpath build/pyinstaller --specpath build \
+  system_stt_daemon.py
+
+mkdir -p binaries
+cp "build/sidecar/stt-daemon$ext" "binaries/stt-daemon-$triple$ext"
+echo "Built binaries/stt-daemon-$triple$ext"
@@ -1,3 +1,4 @@
 fn main() {
+    tonic_build::compile_protos("proto/voice_agent.proto").expect("failed to compile protos");
     tauri_build::build()
-}
\ No newline at end of file
+}
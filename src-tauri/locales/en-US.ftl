@@ -0,0 +1,78 @@
+## Tray menu
+
+tray-start = Start STT
+tray-stop = Stop STT
+tray-pause = Pause STT
+tray-resume = Resume STT
+tray-show = Show
+tray-check-updates = Check for Updates
+tray-install-update = Install Update { $version }
+tray-open-logs = Open Log Folder
+tray-quit = Quit
+tray-recent = Recent
+tray-recent-empty = No transcripts yet
+tray-agents = Agents
+tray-tooltip = Voice MCP Agent ({ $backend })
+tray-tooltip-last = Last: { $text }
+tray-backend-local = local daemon
+tray-backend-openai = OpenAI Whisper
+tray-backend-deepgram = Deepgram
+
+## Notifications
+
+notification-meeting-title = { $title } started
+notification-meeting-body = Begin transcription?
+notification-daemon-crashed-title = STT daemon crashed
+notification-daemon-crashed-body = It exited with code { $code }
+notification-daemon-crashed-signal = It was stopped by the system
+notification-wake-word-title = Heard "{ $name }"
+notification-wake-word-body = Listening for dictation
+notification-transcript-title = Transcript
+
+## STT daemon
+
+daemon-started = STT daemon started successfully
+daemon-stopped = STT daemon stopped successfully
+daemon-killed = STT daemon did not exit in time and was killed
+daemon-already-running = STT daemon is already running
+daemon-not-running = STT daemon is not running
+daemon-unresponsive = STT daemon has stopped responding
+daemon-restart-gave-up = STT daemon kept crashing; gave up after { $attempts } restarts
+stt-paused = STT paused; the daemon keeps running
+stt-resumed = STT resumed
+
+## Permissions and onboarding
+
+permission-microphone-denied = Microphone access is off for this app. Turn it on in System Settings > Privacy & Security > Microphone
+permission-microphone-denied-windows = Microphone access is off for desktop apps. Turn it on in Settings > Privacy & security > Microphone
+permission-needed-microphone = capturing speech
+permission-needed-accessibility = typing transcripts into other apps
+permission-needed-input-monitoring = global hotkeys
+
+diagnostics-fix-python = Install Python 3, or choose a working interpreter.
+diagnostics-fix-packages = Install the STT requirements into this interpreter.
+diagnostics-fix-daemon-script = Reinstall the app, or point daemon_script in config.toml at system_stt_daemon.py.
+diagnostics-fix-microphone = Turn on microphone access for this app in the system privacy settings.
+diagnostics-fix-microphone-ask = Allow microphone access when asked.
+diagnostics-fix-audio-devices = Connect a microphone, or check that the system can see it.
+diagnostics-fix-model = Start listening once while online to download the model.
+diagnostics-fix-api-key = Store an API key for the selected backend.
+diagnostics-fix-network = Check the connection, a proxy or firewall, and the server address.
+
+## Error hints
+
+error-hint-already-running = Stop it first, or keep using the running one.
+error-hint-not-running = Start it first.
+error-hint-permission-denied = Grant access in the system privacy settings, then try again.
+error-hint-daemon-unavailable = Install Python 3 and the STT requirements with setup_system_stt.sh.
+error-hint-storage = Check that the data directory is writable.
+error-hint-network = Check the connection and the server address.
+
+## Screen reader announcements
+
+a11y-listening-started = Listening
+a11y-listening-stopped = Stopped listening
+a11y-inserted = Transcript inserted
+a11y-inserted-text = Inserted: { $text }
+a11y-daemon-crashed = Speech recognition stopped unexpectedly
+a11y-error = Error: { $message }
@@ -0,0 +1,78 @@
+## Tray menu
+
+tray-start = Iniciar STT
+tray-stop = Detener STT
+tray-pause = Pausar STT
+tray-resume = Reanudar STT
+tray-show = Mostrar
+tray-check-updates = Buscar actualizaciones
+tray-install-update = Instalar actualización { $version }
+tray-open-logs = Abrir carpeta de registros
+tray-quit = Salir
+tray-recent = Recientes
+tray-recent-empty = Aún no hay transcripciones
+tray-agents = Agentes
+tray-tooltip = Voice MCP Agent ({ $backend })
+tray-tooltip-last = Último: { $text }
+tray-backend-local = daemon local
+tray-backend-openai = OpenAI Whisper
+tray-backend-deepgram = Deepgram
+
+## Notifications
+
+notification-meeting-title = Ha empezado { $title }
+notification-meeting-body = ¿Comenzar la transcripción?
+notification-daemon-crashed-title = El demonio STT se bloqueó
+notification-daemon-crashed-body = Terminó con el código { $code }
+notification-daemon-crashed-signal = El sistema lo detuvo
+notification-wake-word-title = Se oyó «{ $name }»
+notification-wake-word-body = Escuchando para dictar
+notification-transcript-title = Transcripción
+
+## STT daemon
+
+daemon-started = El demonio STT se inició correctamente
+daemon-stopped = El demonio STT se detuvo correctamente
+daemon-killed = El demonio STT no terminó a tiempo y se forzó su cierre
+daemon-already-running = El demonio STT ya está en ejecución
+daemon-not-running = El demonio STT no está en ejecución
+daemon-unresponsive = El demonio STT ha dejado de responder
+daemon-restart-gave-up = El demonio STT siguió fallando; se abandonó tras { $attempts } reinicios
+stt-paused = STT en pausa; el demonio sigue en ejecución
+stt-resumed = STT reanudado
+
+## Permissions and onboarding
+
+permission-microphone-denied = El acceso al micrófono está desactivado para esta aplicación. Actívalo en Ajustes del Sistema > Privacidad y seguridad > Micrófono
+permission-microphone-denied-windows = El acceso al micrófono está desactivado para las aplicaciones de escritorio. Actívalo en Configuración > Privacidad y seguridad > Micrófono
+permission-needed-microphone = capturar la voz
+permission-needed-accessibility = escribir transcripciones en otras aplicaciones
+permission-needed-input-monitoring = atajos de teclado globales
+
+diagnostics-fix-python = Instala Python 3 o elige un intérprete que funcione.
+diagnostics-fix-packages = Instala los requisitos de STT en este intérprete.
+diagnostics-fix-daemon-script = Reinstala la aplicación o apunta daemon_script en config.toml a system_stt_daemon.py.
+diagnostics-fix-microphone = Activa el acceso al micrófono para esta aplicación en los ajustes de privacidad del sistema.
+diagnostics-fix-microphone-ask = Permite el acceso al micrófono cuando se solicite.
+diagnostics-fix-audio-devices = Conecta un micrófono o comprueba que el sistema lo detecta.
+diagnostics-fix-model = Empieza a escuchar una vez con conexión para descargar el modelo.
+diagnostics-fix-api-key = Guarda una clave de API para el backend seleccionado.
+diagnostics-fix-network = Comprueba la conexión, el proxy o el cortafuegos y la dirección del servidor.
+
+## Error hints
+
+error-hint-already-running = Detenlo primero o sigue usando el que está en ejecución.
+error-hint-not-running = Inícialo primero.
+error-hint-permission-denied = Concede el acceso en los ajustes de privacidad del sistema y vuelve a intentarlo.
+error-hint-daemon-unavailable = Instala Python 3 y los requisitos de STT con setup_system_stt.sh.
+error-hint-storage = Comprueba que se puede escribir en el directorio de datos.
+error-hint-network = Comprueba la conexión y la dirección del servidor.
+
+## Screen reader announcements
+
+a11y-listening-started = Escuchando
+a11y-listening-stopped = Se dejó de escuchar
+a11y-inserted = Transcripción insertada
+a11y-inserted-text = Insertado: { $text }
+a11y-daemon-crashed = El reconocimiento de voz se detuvo inesperadamente
+a11y-error = Error: { $message }
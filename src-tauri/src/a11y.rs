@@ -0,0 +1,175 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::AgentEvent;
+use crate::error::CommandResult;
+use crate::i18n;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "a11y.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct A11yConfig {
+    pub announcements: bool,
+    // Off by default: dictation can be private, and screen readers usually
+    // echo typed text already.
+    pub read_transcripts: bool,
+}
+
+impl Default for A11yConfig {
+    fn default() -> Self {
+        Self {
+            announcements: true,
+            read_transcripts: false,
+        }
+    }
+}
+
+// Mirrors `aria-live`, so the UI can drop it straight into a live region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Politeness {
+    Polite,
+    Assertive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Announcement {
+    pub message: String,
+    pub politeness: Politeness,
+}
+
+static CONFIG: Lazy<Mutex<A11yConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::ffi::c_void;
+
+    use objc::runtime::{Class, Object};
+    use objc::{msg_send, sel, sel_impl};
+
+    // NSAccessibilityPriorityLevel
+    const PRIORITY_MEDIUM: isize = 50;
+    const PRIORITY_HIGH: isize = 90;
+    const UTF8_ENCODING: usize = 4;
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {
+        static NSAccessibilityAnnouncementRequestedNotification: *mut Object;
+        static NSAccessibilityAnnouncementKey: *mut Object;
+        static NSAccessibilityPriorityKey: *mut Object;
+        fn NSAccessibilityPostNotificationWithUserInfo(
+            element: *mut Object,
+            notification: *mut Object,
+            user_info: *mut Object,
+        );
+    }
+
+    // VoiceOver speaks these without moving focus, and drops them when it is
+    // not running.
+    pub fn announce(message: &str, assertive: bool) {
+        let classes = (
+            Class::get("NSApplication"),
+            Class::get("NSString"),
+            Class::get("NSNumber"),
+            Class::get("NSDictionary"),
+        );
+        let (application, string, number, dictionary) = match classes {
+            (Some(a), Some(s), Some(n), Some(d)) => (a, s, n, d),
+            _ => return,
+        };
+        let priority = if assertive {
+            PRIORITY_HIGH
+        } else {
+            PRIORITY_MEDIUM
+        };
+        unsafe {
+            let app: *mut Object = msg_send![application, sharedApplication];
+            let text: *mut Object = msg_send![string, alloc];
+            let text: *mut Object = msg_send![text,
+                initWithBytes: message.as_ptr() as *const c_void
+                length: message.len()
+                encoding: UTF8_ENCODING];
+            let priority: *mut Object = msg_send![number, numberWithInteger: priority];
+            let keys = [NSAccessibilityAnnouncementKey, NSAccessibilityPriorityKey];
+            let values = [text, priority];
+            let info: *mut Object = msg_send![dictionary,
+                dictionaryWithObjects: values.as_ptr()
+                forKeys: keys.as_ptr()
+                count: values.len()];
+            NSAccessibilityPostNotificationWithUserInfo(
+                app,
+                NSAccessibilityAnnouncementRequestedNotification,
+                info,
+            );
+            let _: () = msg_send![text, release];
+        }
+    }
+}
+
+// macOS posts a native VoiceOver announcement. Elsewhere the webview's live
+// region carries it, which Narrator, NVDA and Orca pick up through WebView2
+// and WebKitGTK.
+pub fn announce(state: &AppState, message: String, politeness: Politeness) {
+    if !CONFIG.lock().announcements {
+        return;
+    }
+    #[cfg(target_os = "macos")]
+    macos::announce(&message, politeness == Politeness::Assertive);
+    state.bus.publish(AgentEvent::Announcement(Announcement {
+        message,
+        politeness,
+    }));
+}
+
+// Called by whatever typed a transcript into another app.
+pub fn transcript_inserted(state: &AppState, text: &str) {
+    let message = if CONFIG.lock().read_transcripts {
+        i18n::t_args("a11y-inserted-text", &[("text", text)])
+    } else {
+        i18n::t("a11y-inserted")
+    };
+    announce(state, message, Politeness::Polite);
+}
+
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let (message, politeness) = match events.recv().await {
+                Ok(AgentEvent::ListeningChanged(true)) => {
+                    (i18n::t("a11y-listening-started"), Politeness::Polite)
+                }
+                Ok(AgentEvent::ListeningChanged(false)) => {
+                    (i18n::t("a11y-listening-stopped"), Politeness::Polite)
+                }
+                Ok(AgentEvent::DaemonCrashed { .. }) => {
+                    (i18n::t("a11y-daemon-crashed"), Politeness::Assertive)
+                }
+                Ok(AgentEvent::Error { error, .. }) => (
+                    i18n::t_args("a11y-error", &[("message", &error.message)]),
+                    Politeness::Assertive,
+                ),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            announce(&state, message, politeness);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_a11y_config() -> A11yConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_a11y_config(config: A11yConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
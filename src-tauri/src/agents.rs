@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{CustomMenuItem, SystemTrayMenu, SystemTraySubmenu};
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::children::{ChildGuard, Shutdown};
+use crate::config;
+use crate::daemon::{self, InputSource};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::i18n;
+use crate::profiles;
+use crate::store;
+use crate::transcript::Transcript;
+use crate::AppState;
+
+const STORE_FILE: &str = "agents.json";
+// The listening daemon that the rest of the app starts, stops and pauses.
+pub const DEFAULT_AGENT: &str = "default";
+// Tray item ids are this prefix and the agent name.
+pub const MENU_PREFIX: &str = "agent:";
+
+// Unset fields fall back to the default agent's settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    pub name: String,
+    pub model: Option<String>,
+    pub language: Option<String>,
+    pub input_device: Option<String>,
+    // Off, the agent's transcripts are published and stored but never typed
+    // or taken as voice commands.
+    pub inject: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentStatus {
+    #[serde(flatten)]
+    pub config: AgentConfig,
+    pub running: bool,
+    pub session_id: Option<String>,
+    pub uptime_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Agents {
+    agents: Vec<AgentConfig>,
+}
+
+struct Running {
+    child: ChildGuard,
+    session_id: String,
+    inject: bool,
+    started_at: Instant,
+}
+
+// The daemons of the named agents, next to the default one in
+// `AppState::stt_process`.
+#[derive(Clone, Default)]
+pub struct AgentRegistry {
+    running: Arc<Mutex<BTreeMap<String, Running>>>,
+}
+
+static AGENTS: Lazy<Mutex<Agents>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+fn find(name: &str) -> Option<AgentConfig> {
+    AGENTS
+        .lock()
+        .agents
+        .iter()
+        .find(|agent| agent.name == name)
+        .cloned()
+}
+
+fn not_found(name: &str) -> AppError {
+    AppError::new(ErrorCode::NotFound, format!("No agent named {}", name))
+}
+
+fn default_status(state: &AppState) -> AgentStatus {
+    let status = daemon::daemon_status(state);
+    AgentStatus {
+        config: AgentConfig {
+            name: DEFAULT_AGENT.to_string(),
+            model: Some(daemon::current_model(state)),
+            language: profiles::stt_language(state),
+            input_device: config::current().input_device,
+            inject: true,
+        },
+        running: backends::is_running(state),
+        session_id: status.session_id,
+        uptime_ms: status.uptime_ms,
+    }
+}
+
+fn status(state: &AppState, config: AgentConfig) -> AgentStatus {
+    let running = state.agents.running.lock();
+    let agent = running.get(&config.name);
+    AgentStatus {
+        running: agent.is_some(),
+        session_id: agent.map(|agent| agent.session_id.clone()),
+        uptime_ms: agent.map(|agent| agent.started_at.elapsed().as_millis() as u64),
+        config,
+    }
+}
+
+fn publish(state: &AppState, config: AgentConfig) {
+    let status = status(state, config);
+    state.bus.publish(AgentEvent::AgentChanged(status));
+}
+
+pub fn list(state: &AppState) -> Vec<AgentStatus> {
+    let configs = AGENTS.lock().agents.clone();
+    std::iter::once(default_status(state))
+        .chain(configs.into_iter().map(|config| status(state, config)))
+        .collect()
+}
+
+// Transcripts of the default agent, and of named agents that inject.
+pub fn dictates(state: &AppState, transcript: &Transcript) -> bool {
+    let session_id = match &transcript.session_id {
+        Some(session_id) => session_id,
+        None => return true,
+    };
+    state
+        .agents
+        .running
+        .lock()
+        .values()
+        .find(|agent| &agent.session_id == session_id)
+        .map_or(true, |agent| agent.inject)
+}
+
+pub fn start(state: &AppState, name: &str) -> Result<String, AppError> {
+    if name == DEFAULT_AGENT {
+        return backends::start(state, InputSource::Microphone);
+    }
+    let config = find(name).ok_or_else(|| not_found(name))?;
+    {
+        let mut running = state.agents.running.lock();
+        if let Some(agent) = running.get(name) {
+            return Err(AppError::new(
+                ErrorCode::AlreadyRunning,
+                i18n::t("daemon-already-running"),
+            )
+            .with_context("agent", name.to_string())
+            .with_context("session_id", agent.session_id.clone()));
+        }
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let child = daemon::spawn_agent(state, &config, &session_id)
+            .map_err(|error| error.with_context("agent", name.to_string()))?;
+        running.insert(
+            name.to_string(),
+            Running {
+                child,
+                session_id,
+                inject: config.inject,
+                started_at: Instant::now(),
+            },
+        );
+    }
+    tracing::info!(target: "daemon", agent = name, "Agent started");
+    publish(state, config);
+    Ok(i18n::t("daemon-started"))
+}
+
+pub fn stop(state: &AppState, name: &str) -> Result<String, AppError> {
+    if name == DEFAULT_AGENT {
+        return backends::stop(state);
+    }
+    // Taken out first, so its reader does not report the exit as a crash.
+    let agent = state.agents.running.lock().remove(name);
+    let mut agent = match agent {
+        Some(agent) => agent,
+        None if find(name).is_none() => return Err(not_found(name)),
+        None => {
+            return Err(
+                AppError::new(ErrorCode::NotRunning, i18n::t("daemon-not-running"))
+                    .with_context("agent", name.to_string()),
+            )
+        }
+    };
+    let timeout = Duration::from_millis(config::current().stop_timeout_ms);
+    let shutdown = agent.child.terminate(timeout).unwrap_or_else(|e| {
+        tracing::warn!(target: "daemon", agent = name, "Failed to stop agent cleanly: {}", e);
+        Shutdown::Killed
+    });
+    state.bus.publish(AgentEvent::SessionEnded {
+        session_id: agent.session_id,
+    });
+    tracing::info!(target: "daemon", agent = name, ?shutdown, "Agent stopped");
+    if let Some(config) = find(name) {
+        publish(state, config);
+    }
+    Ok(match shutdown {
+        Shutdown::Graceful => i18n::t("daemon-stopped"),
+        Shutdown::Killed => i18n::t("daemon-killed"),
+    })
+}
+
+pub fn toggle(state: &AppState, name: &str) -> Result<String, AppError> {
+    let running = if name == DEFAULT_AGENT {
+        backends::is_running(state)
+    } else {
+        state.agents.running.lock().contains_key(name)
+    };
+    if running {
+        stop(state, name)
+    } else {
+        start(state, name)
+    }
+}
+
+// The default agent is left to `backends`.
+pub fn stop_all(state: &AppState) {
+    let names: Vec<String> = state.agents.running.lock().keys().cloned().collect();
+    for name in names {
+        let _ = stop(state, &name);
+    }
+}
+
+// Called when an agent's stdout closes. Returns false for sessions that are
+// not a named agent's. Agents are not restarted; the supervisor only watches
+// the default one.
+pub fn exited(state: &AppState, session_id: &str) -> bool {
+    let (name, mut agent) = {
+        let mut running = state.agents.running.lock();
+        let name = running
+            .iter()
+            .find(|(_, agent)| agent.session_id == session_id)
+            .map(|(name, _)| name.clone());
+        match name.and_then(|name| running.remove_entry(&name)) {
+            Some(entry) => entry,
+            None => return false,
+        }
+    };
+    let exit_code = agent.child.wait().ok().and_then(|status| status.code());
+    if exit_code != Some(0) {
+        state.bus.publish(AgentEvent::error(
+            "agents",
+            AppError::new(ErrorCode::Failed, format!("Agent {} exited", name))
+                .with_context("agent", name.clone())
+                .with_context("session_id", session_id.to_string())
+                .with_context("exit_code", exit_code.unwrap_or(-1)),
+        ));
+    }
+    state.bus.publish(AgentEvent::SessionEnded {
+        session_id: session_id.to_string(),
+    });
+    if let Some(config) = find(&name) {
+        publish(state, config);
+    }
+    true
+}
+
+pub fn submenu(state: &AppState) -> Option<SystemTraySubmenu> {
+    let agents = AGENTS.lock().agents.clone();
+    if agents.is_empty() {
+        return None;
+    }
+    let running = state.agents.running.lock();
+    let menu = agents.iter().fold(SystemTrayMenu::new(), |menu, agent| {
+        let item = CustomMenuItem::new(format!("{}{}", MENU_PREFIX, agent.name), &agent.name);
+        menu.add_item(if running.contains_key(&agent.name) {
+            item.selected()
+        } else {
+            item
+        })
+    });
+    Some(SystemTraySubmenu::new(i18n::t("tray-agents"), menu))
+}
+
+fn save(agents: Agents) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &agents)?;
+    *AGENTS.lock() = agents;
+    Ok(())
+}
+
+fn validate(config: &AgentConfig) -> CommandResult<()> {
+    let name = &config.name;
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Agent names are letters, digits, - and _",
+        ));
+    }
+    if name == DEFAULT_AGENT {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("{} is the built-in agent", DEFAULT_AGENT),
+        ));
+    }
+    if let Some(language) = &config.language {
+        if !profiles::is_language_code(language) {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Not a language code: {}", language),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_agents(state: tauri::State<'_, AppState>) -> Vec<AgentStatus> {
+    list(&state)
+}
+
+// Replaces an agent of the same name; a running one keeps its old settings
+// until it is restarted.
+#[tauri::command]
+pub fn set_agent(state: tauri::State<'_, AppState>, config: AgentConfig) -> CommandResult<()> {
+    validate(&config)?;
+    let mut agents = AGENTS.lock().clone();
+    match agents
+        .agents
+        .iter_mut()
+        .find(|agent| agent.name == config.name)
+    {
+        Some(agent) => *agent = config.clone(),
+        None => agents.agents.push(config.clone()),
+    }
+    save(agents)?;
+    publish(&state, config);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_agent(state: tauri::State<'_, AppState>, name: String) -> CommandResult<()> {
+    let mut agents = AGENTS.lock().clone();
+    let before = agents.agents.len();
+    agents.agents.retain(|agent| agent.name != name);
+    if agents.agents.len() == before {
+        return Err(not_found(&name));
+    }
+    if state.agents.running.lock().contains_key(&name) {
+        stop(&state, &name)?;
+    }
+    save(agents)
+}
+
+#[tauri::command]
+pub async fn start_agent(state: tauri::State<'_, AppState>, name: String) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || start(&state, &name))
+        .await
+        .map_err(|e| AppError::from(format!("Agent task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn stop_agent(state: tauri::State<'_, AppState>, name: String) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || stop(&state, &name))
+        .await
+        .map_err(|e| AppError::from(format!("Agent task failed: {}", e)))?
+}
@@ -0,0 +1,180 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::middleware;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::auth;
+use crate::bus::AgentEvent;
+use crate::captions;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::mcp;
+use crate::mdns::MdnsState;
+use crate::metrics;
+use crate::openai_api;
+use crate::remote_mic;
+use crate::store;
+use crate::streamdeck;
+use crate::usage;
+use crate::AppState;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8765";
+const CERT_FILE: &str = "api-cert.pem";
+const KEY_FILE: &str = "api-key.pem";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    pub tls: bool,
+}
+
+#[derive(Default)]
+pub struct ApiState {
+    server: Mutex<Option<(Handle, ApiServerStatus)>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiServerOptions {
+    pub address: Option<String>,
+    #[serde(default)]
+    pub tls: bool,
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .merge(captions::routes())
+        .merge(mcp::routes())
+        .merge(metrics::routes())
+        .merge(openai_api::routes())
+        .merge(remote_mic::routes())
+        .merge(streamdeck::routes())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::middleware,
+        ))
+        .with_state(state)
+}
+
+async fn tls_config() -> Result<RustlsConfig, String> {
+    let dir = store::data_dir();
+    let (cert_path, key_path) = (dir.join(CERT_FILE), dir.join(KEY_FILE));
+    if !cert_path.exists() || !key_path.exists() {
+        let mut names = vec!["localhost".to_string()];
+        if let Ok(hostname) = hostname::get() {
+            names.push(hostname.to_string_lossy().into_owned());
+        }
+        let cert = rcgen::generate_simple_self_signed(names)
+            .map_err(|e| format!("Failed to generate TLS certificate: {}", e))?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let pem = cert
+            .serialize_pem()
+            .map_err(|e| format!("Failed to encode TLS certificate: {}", e))?;
+        std::fs::write(&cert_path, pem)
+            .and_then(|_| std::fs::write(&key_path, cert.serialize_private_key_pem()))
+            .map_err(|e| format!("Failed to write TLS certificate: {}", e))?;
+    }
+    RustlsConfig::from_pem_file(cert_path, key_path)
+        .await
+        .map_err(|e| format!("Failed to load TLS certificate: {}", e))
+}
+
+pub async fn serve(
+    state: AppState,
+    addr: SocketAddr,
+    tls: bool,
+    handle: Handle,
+) -> Result<(), String> {
+    let app = router(state).into_make_service();
+    let result = if tls {
+        axum_server::bind_rustls(addr, tls_config().await?)
+            .handle(handle)
+            .serve(app)
+            .await
+    } else {
+        axum_server::bind(addr).handle(handle).serve(app).await
+    };
+    result.map_err(|e| format!("API server failed: {}", e))
+}
+
+#[tauri::command]
+pub fn start_api_server(
+    state: tauri::State<'_, AppState>,
+    api: tauri::State<'_, ApiState>,
+    mdns: tauri::State<'_, MdnsState>,
+    options: Option<ApiServerOptions>,
+) -> CommandResult<ApiServerStatus> {
+    let options = options.unwrap_or_default();
+    let address = options.address.unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let addr: SocketAddr = address.parse().map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid API server address {}: {}", address, e),
+        )
+    })?;
+
+    let mut server = api.server.lock();
+    if server.is_some() {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "API server is already running",
+        ));
+    }
+
+    usage::record("api_server.start");
+    let handle = Handle::new();
+    let state = state.inner().clone();
+    let serve_handle = handle.clone();
+    if let Err(message) = mdns.advertise(addr, options.tls) {
+        state.bus.publish(AgentEvent::error("mdns", message));
+    }
+    tauri::async_runtime::spawn(async move {
+        let bus = state.bus.clone();
+        if let Err(message) = serve(state, addr, options.tls, serve_handle).await {
+            bus.publish(AgentEvent::error("api", message));
+        }
+    });
+
+    let status = ApiServerStatus {
+        running: true,
+        address: Some(addr.to_string()),
+        tls: options.tls,
+    };
+    *server = Some((handle, status.clone()));
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn stop_api_server(
+    api: tauri::State<'_, ApiState>,
+    mdns: tauri::State<'_, MdnsState>,
+) -> CommandResult<String> {
+    match api.server.lock().take() {
+        Some((handle, _)) => {
+            mdns.stop();
+            handle.graceful_shutdown(Some(Duration::from_secs(2)));
+            Ok("API server stopped".to_string())
+        }
+        None => Err(AppError::new(
+            ErrorCode::NotRunning,
+            "API server is not running",
+        )),
+    }
+}
+
+#[tauri::command]
+pub fn get_api_server_status(api: tauri::State<'_, ApiState>) -> ApiServerStatus {
+    match api.server.lock().as_ref() {
+        Some((_, status)) => status.clone(),
+        None => ApiServerStatus {
+            running: false,
+            address: None,
+            tls: false,
+        },
+    }
+}
@@ -0,0 +1,935 @@
+#[cfg(target_os = "linux")]
+use std::io::Read;
+#[cfg(target_os = "linux")]
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample, SupportedStreamConfig};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::background_core;
+use crate::bus::AgentEvent;
+#[cfg(target_os = "linux")]
+use crate::children::ChildGuard;
+use crate::config;
+use crate::daemon::{self, InputSource};
+use crate::denoise;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::lookback;
+use crate::permissions;
+use crate::resampler::{AudioFormat, Converter};
+use crate::vad;
+use crate::AppState;
+
+// What the STT daemon expects on its ring: mono s16le.
+pub const SAMPLE_RATE: u32 = 16_000;
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+// Every 2 s: cpal has no device-change notifications, so the device is polled.
+const DEVICE_CHECK_TICKS: u32 = 4;
+// 20 levels a second.
+const LEVEL_WINDOW: usize = SAMPLE_RATE as usize / 20;
+// 20 ms of s16le from `parec`.
+#[cfg(target_os = "linux")]
+const MONITOR_BLOCK: usize = SAMPLE_RATE as usize / 50 * 2;
+// The speaker on system audio transcripts while the microphone is captured
+// too.
+pub const SYSTEM_SPEAKER: &str = "System";
+
+// Off until a meter is on screen.
+static LEVEL_METER: AtomicBool = AtomicBool::new(false);
+// Capture keeps running while paused, but nothing reaches the sink.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+// The same for the microphone alone, while the agent is talking.
+static DUCKED: AtomicBool = AtomicBool::new(false);
+
+// Saved as `capture_source` in config.toml; read by `start_capture`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    Microphone,
+    // What the computer plays, e.g. the other side of a call.
+    System,
+    // Each transcribed by a daemon of its own.
+    Both,
+}
+
+impl Default for CaptureSource {
+    fn default() -> Self {
+        CaptureSource::Microphone
+    }
+}
+
+// `id` is the name: cpal has no identifier that is stable across hosts.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDevice {
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+}
+
+// The device's own format; frames leave capture as 16 kHz mono whatever it is.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureInfo {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+// Linear amplitudes in [0, 1] over one window.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AudioLevel {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+// Counts 16 kHz samples rather than callbacks, so the rate does not depend
+// on the device's buffer size.
+#[derive(Default)]
+struct Meter {
+    sum_squares: f32,
+    peak: f32,
+    count: usize,
+}
+
+impl Meter {
+    fn push(&mut self, state: &AppState, samples: &[f32]) {
+        if !LEVEL_METER.load(Ordering::Relaxed) {
+            *self = Meter::default();
+            return;
+        }
+        for &sample in samples {
+            self.sum_squares += sample * sample;
+            self.peak = self.peak.max(sample.abs());
+            self.count += 1;
+            if self.count == LEVEL_WINDOW {
+                state.bus.publish(AgentEvent::AudioLevel(AudioLevel {
+                    rms: (self.sum_squares / self.count as f32).sqrt().min(1.0),
+                    peak: self.peak.min(1.0),
+                }));
+                *self = Meter::default();
+            }
+        }
+    }
+}
+
+// Receives each callback's audio as 16 kHz mono samples, on the audio thread.
+pub type Sink = Box<dyn FnMut(&[f32]) + Send>;
+// Outlives any one stream, so a device switch keeps feeding the same sink.
+type SharedSink = Arc<Mutex<Sink>>;
+
+// What a capture thread opens.
+enum Input {
+    // A named input device, else the configured or default one.
+    Microphone(Option<String>),
+    // Only the main stream shows levels and speech events.
+    System {
+        device: Option<String>,
+        primary: bool,
+    },
+}
+
+struct Capture {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+    // The daemon session being fed; None for an in-process pipeline.
+    session_id: Option<String>,
+    info: Arc<Mutex<CaptureInfo>>,
+    // Listening for the wake word; gives way to any other capture.
+    background: bool,
+    // System audio captured alongside, into a daemon of its own.
+    loopback: Option<Box<Capture>>,
+}
+
+fn halt(capture: Capture) {
+    let _ = capture.stop.send(());
+    let _ = capture.thread.join();
+    if let Some(loopback) = capture.loopback {
+        halt(*loopback);
+    }
+}
+
+static CAPTURE: Lazy<Mutex<Option<Capture>>> = Lazy::new(|| Mutex::new(None));
+
+fn to_pcm(samples: &[f32], pcm: &mut Vec<u8>) {
+    pcm.clear();
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        pcm.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn daemon_sink(state: AppState) -> Sink {
+    let mut pcm = Vec::new();
+    Box::new(move |samples: &[f32]| {
+        to_pcm(samples, &mut pcm);
+        // A missing ring means the session is ending; the watcher stops
+        // capture once it has.
+        let _ = daemon::push_audio(&state, &pcm);
+    })
+}
+
+fn find_device(name: Option<&str>) -> Result<cpal::Device, AppError> {
+    let host = cpal::default_host();
+    match name {
+        None => host
+            .default_input_device()
+            .ok_or_else(|| AppError::new(ErrorCode::NotFound, "No default input device")),
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| AppError::from(format!("Failed to list audio devices: {}", e)))?
+            .find(|device| device.name().map_or(false, |n| n == name))
+            .ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::NotFound,
+                    format!("No input device named {}", name),
+                )
+            }),
+    }
+}
+
+// The stages 16 kHz audio goes through before the sink, whatever it was
+// captured from.
+struct Pipeline {
+    state: AppState,
+    sink: SharedSink,
+    meter: Option<Meter>,
+    microphone: bool,
+    // System audio has no room noise to remove.
+    denoiser: Option<denoise::Denoiser>,
+    gate: vad::Gate,
+    denoised: Vec<f32>,
+    voiced: Vec<f32>,
+}
+
+impl Pipeline {
+    fn new(state: &AppState, sink: SharedSink, input: &Input) -> Self {
+        let primary = match input {
+            Input::Microphone(_) => true,
+            Input::System { primary, .. } => *primary,
+        };
+        Self {
+            state: state.clone(),
+            sink,
+            meter: if primary {
+                Some(Meter::default())
+            } else {
+                None
+            },
+            microphone: matches!(input, Input::Microphone(_)),
+            denoiser: match input {
+                Input::Microphone(_) => Some(denoise::Denoiser::default()),
+                Input::System { .. } => None,
+            },
+            gate: if primary {
+                vad::Gate::new(state)
+            } else {
+                vad::Gate::silent(state)
+            },
+            denoised: Vec::new(),
+            voiced: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        // The meter shows silence too, so a quiet mic is visibly working.
+        if let Some(meter) = &mut self.meter {
+            meter.push(&self.state, samples);
+        }
+        if PAUSED.load(Ordering::Relaxed) || (self.microphone && DUCKED.load(Ordering::Relaxed)) {
+            return;
+        }
+        let samples = match &mut self.denoiser {
+            Some(denoiser) => {
+                self.denoised.clear();
+                denoiser.process(samples, &mut self.denoised);
+                &self.denoised[..]
+            }
+            None => samples,
+        };
+        if self.microphone {
+            lookback::push(samples);
+        }
+        self.voiced.clear();
+        self.gate.process(samples, &mut self.voiced);
+        if !self.voiced.is_empty() {
+            (*self.sink.lock())(&self.voiced);
+        }
+    }
+}
+
+// Runs on the audio thread, so it reuses its buffers and never blocks.
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut pipeline: Pipeline,
+    mut converter: Converter,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = (config.channels as usize).max(1);
+    let mut mono = Vec::new();
+    let mut samples = Vec::new();
+    let errors = pipeline.state.clone();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            mono.clear();
+            for frame in data.chunks(channels) {
+                let sum: f32 = frame.iter().map(|&s| s.to_sample::<f32>()).sum();
+                mono.push(sum / frame.len() as f32);
+            }
+            samples.clear();
+            converter.push(&mono, &mut samples);
+            if !samples.is_empty() {
+                pipeline.push(&samples);
+            }
+        },
+        move |error: cpal::StreamError| {
+            errors.bus.publish(AgentEvent::error(
+                "audio",
+                format!("Audio capture failed: {}", error),
+            ));
+        },
+        None,
+    )
+}
+
+fn open(
+    state: &AppState,
+    name: Option<&str>,
+    sink: SharedSink,
+) -> Result<(cpal::Stream, CaptureInfo), AppError> {
+    let device = find_device(name)?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| AppError::from(format!("Failed to query the input device: {}", e)))?;
+    let pipeline = Pipeline::new(state, sink, &Input::Microphone(None));
+    start_stream(&device, supported, pipeline)
+}
+
+fn start_stream(
+    device: &cpal::Device,
+    supported: SupportedStreamConfig,
+    pipeline: Pipeline,
+) -> Result<(cpal::Stream, CaptureInfo), AppError> {
+    let config = supported.config();
+    let converter = Converter::new(config.sample_rate.0, AudioFormat::PIPELINE)?;
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(device, &config, pipeline, converter),
+        SampleFormat::I16 => build_stream::<i16>(device, &config, pipeline, converter),
+        SampleFormat::U16 => build_stream::<u16>(device, &config, pipeline, converter),
+        other => {
+            return Err(AppError::new(
+                ErrorCode::Unsupported,
+                format!("Unsupported sample format {:?}", other),
+            ))
+        }
+    }
+    .map_err(|e| AppError::from(format!("Failed to open the input stream: {}", e)))?;
+    stream
+        .play()
+        .map_err(|e| AppError::from(format!("Failed to start the input stream: {}", e)))?;
+    let info = CaptureInfo {
+        device: device.name().unwrap_or_default(),
+        sample_rate: config.sample_rate.0,
+        channels: config.channels,
+    };
+    Ok((stream, info))
+}
+
+#[cfg(windows)]
+type SystemSource = cpal::Stream;
+
+// WASAPI records what an output device plays when it is opened for input.
+#[cfg(windows)]
+fn open_system(
+    name: Option<&str>,
+    pipeline: Pipeline,
+) -> Result<(SystemSource, CaptureInfo), AppError> {
+    let host = cpal::default_host();
+    let device = match name {
+        None => host
+            .default_output_device()
+            .ok_or_else(|| AppError::new(ErrorCode::NotFound, "No default output device"))?,
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| AppError::from(format!("Failed to list audio devices: {}", e)))?
+            .find(|device| device.name().map_or(false, |n| n == name))
+            .ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::NotFound,
+                    format!("No output device named {}", name),
+                )
+            })?,
+    };
+    let supported = device
+        .default_output_config()
+        .map_err(|e| AppError::from(format!("Failed to query the output device: {}", e)))?;
+    start_stream(&device, supported, pipeline)
+}
+
+#[cfg(target_os = "macos")]
+type SystemSource = cpal::Stream;
+
+// CoreAudio has no loopback, so system audio has to be routed into an input
+// device first.
+#[cfg(target_os = "macos")]
+fn open_system(
+    name: Option<&str>,
+    pipeline: Pipeline,
+) -> Result<(SystemSource, CaptureInfo), AppError> {
+    let name = name.ok_or_else(|| {
+        AppError::new(
+            ErrorCode::Unsupported,
+            "macOS cannot capture system audio directly; route it into BlackHole or an aggregate device and set system_device to it",
+        )
+    })?;
+    let device = find_device(Some(name))?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| AppError::from(format!("Failed to query the input device: {}", e)))?;
+    start_stream(&device, supported, pipeline)
+}
+
+// A `parec` recording of a PulseAudio or PipeWire monitor source.
+#[cfg(target_os = "linux")]
+struct SystemSource {
+    child: ChildGuard,
+    stopping: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for SystemSource {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        let _ = self.child.kill();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_system(
+    name: Option<&str>,
+    mut pipeline: Pipeline,
+) -> Result<(SystemSource, CaptureInfo), AppError> {
+    // The monitor of whichever output is the default.
+    let device = name.unwrap_or("@DEFAULT_MONITOR@").to_string();
+    let mut child = std::process::Command::new("parec")
+        .args([
+            "--raw",
+            "--format=s16le",
+            "--channels=1",
+            "--latency-msec=20",
+        ])
+        .arg(format!("--rate={}", SAMPLE_RATE))
+        .arg(format!("--device={}", device))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Unsupported,
+                format!("System audio needs parec from pulseaudio-utils: {}", e),
+            )
+        })?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::from("parec has no output"))?;
+    let stopping = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let stopping = stopping.clone();
+        let device = device.clone();
+        thread::spawn(move || {
+            let mut block = [0u8; MONITOR_BLOCK];
+            let mut samples = Vec::with_capacity(MONITOR_BLOCK / 2);
+            while stdout.read_exact(&mut block).is_ok() {
+                samples.clear();
+                samples.extend(
+                    block
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32),
+                );
+                pipeline.push(&samples);
+            }
+            if !stopping.load(Ordering::Relaxed) {
+                pipeline.state.bus.publish(AgentEvent::error(
+                    "audio",
+                    format!("System audio capture from {} stopped", device),
+                ));
+            }
+        })
+    };
+    let source = SystemSource {
+        child: ChildGuard::new(child),
+        stopping,
+        reader: Some(reader),
+    };
+    let info = CaptureInfo {
+        device,
+        sample_rate: SAMPLE_RATE,
+        channels: 1,
+    };
+    Ok((source, info))
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+type SystemSource = ();
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn open_system(
+    _name: Option<&str>,
+    _pipeline: Pipeline,
+) -> Result<(SystemSource, CaptureInfo), AppError> {
+    Err(AppError::new(
+        ErrorCode::Unsupported,
+        "System audio capture is not supported on this platform",
+    ))
+}
+
+fn default_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_input_device()
+        .and_then(|device| device.name().ok())
+}
+
+// An explicit device, else the configured one, else whatever the system
+// default is right now.
+fn wanted_device(requested: &Option<String>) -> Option<String> {
+    requested.clone().or_else(|| config::current().input_device)
+}
+
+// cpal streams are not Send on every host, so each lives and dies on its own
+// thread. Capture for the daemon ends with its session, however that ends.
+// The stream is reopened when the wanted device changes, e.g. a headset
+// becoming the default when it is plugged in.
+fn run(
+    state: AppState,
+    requested: Option<String>,
+    session_id: Option<String>,
+    sink: Sink,
+    opened: Arc<Mutex<CaptureInfo>>,
+    stop: mpsc::Receiver<()>,
+    ready: mpsc::Sender<Result<CaptureInfo, AppError>>,
+) {
+    let sink: SharedSink = Arc::new(Mutex::new(sink));
+    let mut stream = match open(&state, wanted_device(&requested).as_deref(), sink.clone()) {
+        Ok((stream, info)) => {
+            *opened.lock() = info.clone();
+            let _ = ready.send(Ok(info));
+            stream
+        }
+        Err(error) => {
+            let _ = ready.send(Err(error));
+            return;
+        }
+    };
+    tracing::info!(target: "audio", "Capture started");
+    // A device that fails to open is not retried until the wanted one changes.
+    let mut failed: Option<String> = None;
+    let mut ticks = 0u32;
+    loop {
+        match stop.recv_timeout(WATCH_INTERVAL) {
+            Err(RecvTimeoutError::Timeout)
+                if session_id.is_none() || daemon::session_id(&state) == session_id => {}
+            _ => break,
+        }
+        ticks += 1;
+        if ticks % DEVICE_CHECK_TICKS != 0 {
+            continue;
+        }
+        let wanted = wanted_device(&requested);
+        let target = match wanted.clone().or_else(default_device_name) {
+            Some(target) => target,
+            None => continue,
+        };
+        if target == opened.lock().device || failed.as_deref() == Some(target.as_str()) {
+            continue;
+        }
+        // The old stream is dropped once the new one plays, so at most one
+        // callback from each overlaps.
+        match open(&state, wanted.as_deref(), sink.clone()) {
+            Ok((reopened, info)) => {
+                stream = reopened;
+                failed = None;
+                *opened.lock() = info.clone();
+                tracing::info!(target: "audio", device = %info.device, "Capture switched device");
+                state.bus.publish(AgentEvent::InputDeviceChanged(info));
+            }
+            Err(error) => {
+                failed = Some(target);
+                state.bus.publish(AgentEvent::error("audio", error));
+            }
+        }
+    }
+    drop(stream);
+    tracing::info!(target: "audio", "Capture stopped");
+}
+
+// System audio has no device to follow, so it stays on the one it opened.
+fn run_system(
+    state: AppState,
+    requested: Option<String>,
+    session_id: Option<String>,
+    pipeline: Pipeline,
+    opened: Arc<Mutex<CaptureInfo>>,
+    stop: mpsc::Receiver<()>,
+    ready: mpsc::Sender<Result<CaptureInfo, AppError>>,
+) {
+    let source = match open_system(requested.as_deref(), pipeline) {
+        Ok((source, info)) => {
+            *opened.lock() = info.clone();
+            let _ = ready.send(Ok(info));
+            source
+        }
+        Err(error) => {
+            let _ = ready.send(Err(error));
+            return;
+        }
+    };
+    tracing::info!(target: "audio", "System audio capture started");
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(WATCH_INTERVAL) {
+        if session_id.is_some() && daemon::session_id(&state) != session_id {
+            break;
+        }
+    }
+    drop(source);
+    tracing::info!(target: "audio", "System audio capture stopped");
+}
+
+fn is_capturing(state: &AppState, capture: &Capture) -> bool {
+    capture.session_id.is_none() || daemon::session_id(state) == capture.session_id
+}
+
+// Checks shared by both sinks: one capture at a time, and none while the
+// daemon holds the microphone. Background capture is stopped to make room.
+fn claim(state: &AppState, current: &mut Option<Capture>) -> Result<(), AppError> {
+    if current
+        .as_ref()
+        .map_or(false, |c| !c.background && is_capturing(state, c))
+    {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "Audio capture is already running",
+        ));
+    }
+    if let Some(stale) = current.take() {
+        halt(stale);
+    }
+    if daemon::is_running(state) {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "The agent is already listening on another input source",
+        ));
+    }
+    match permissions::microphone_error() {
+        Some(message) => Err(AppError::new(ErrorCode::PermissionDenied, message)),
+        None => Ok(()),
+    }
+}
+
+fn spawn(
+    state: &AppState,
+    input: Input,
+    session_id: Option<String>,
+    sink: Sink,
+) -> Result<(Capture, CaptureInfo), AppError> {
+    let (stop, stop_rx) = mpsc::channel();
+    let (ready_tx, ready) = mpsc::channel();
+    let opened = Arc::new(Mutex::new(CaptureInfo::default()));
+    let thread = {
+        let state = state.clone();
+        let session_id = session_id.clone();
+        let opened = opened.clone();
+        thread::spawn(move || match input {
+            Input::Microphone(device) => {
+                run(state, device, session_id, sink, opened, stop_rx, ready_tx)
+            }
+            Input::System { ref device, .. } => {
+                let pipeline = Pipeline::new(&state, Arc::new(Mutex::new(sink)), &input);
+                let device = device.clone();
+                run_system(
+                    state, device, session_id, pipeline, opened, stop_rx, ready_tx,
+                )
+            }
+        })
+    };
+    let info = ready
+        .recv()
+        .unwrap_or_else(|_| Err(AppError::from("Capture thread exited")));
+    match info {
+        Ok(info) => Ok((
+            Capture {
+                stop,
+                thread,
+                session_id,
+                info: opened,
+                background: false,
+                loopback: None,
+            },
+            info,
+        )),
+        Err(error) => {
+            let _ = thread.join();
+            Err(error)
+        }
+    }
+}
+
+// Capture gets a streamed daemon session of its own: sharing one with another
+// producer would interleave the two signals in the ring.
+pub fn start(state: &AppState, device: Option<String>) -> Result<CaptureInfo, AppError> {
+    let mut current = CAPTURE.lock();
+    claim(state, &mut current)?;
+    if background_core::is_attached() {
+        return Err(AppError::new(
+            ErrorCode::Unsupported,
+            "Native capture is unavailable while attached to the background core",
+        ));
+    }
+
+    let config = config::current();
+    daemon::start(state, InputSource::Stream)?;
+    let session_id = daemon::session_id(state);
+    let input = match config.capture_source {
+        CaptureSource::System => Input::System {
+            device: config.system_device.clone(),
+            primary: true,
+        },
+        CaptureSource::Microphone | CaptureSource::Both => Input::Microphone(device),
+    };
+    match spawn(state, input, session_id.clone(), daemon_sink(state.clone())) {
+        Ok((mut capture, info)) => {
+            // The microphone carries on without it.
+            if config.capture_source == CaptureSource::Both {
+                match spawn_loopback(state, session_id, config.system_device) {
+                    Ok(loopback) => capture.loopback = Some(Box::new(loopback)),
+                    Err(error) => state.bus.publish(AgentEvent::error("audio", error)),
+                }
+            }
+            *current = Some(capture);
+            Ok(info)
+        }
+        Err(error) => {
+            let _ = daemon::stop(state);
+            Err(error)
+        }
+    }
+}
+
+// A daemon of its own, so the two signals are transcribed apart. It ends with
+// the main session.
+fn spawn_loopback(
+    state: &AppState,
+    session_id: Option<String>,
+    device: Option<String>,
+) -> Result<Capture, AppError> {
+    let mut worker = daemon::spawn_worker(
+        state,
+        uuid::Uuid::new_v4().to_string(),
+        Some(SYSTEM_SPEAKER.to_string()),
+    )?;
+    let mut pcm = Vec::new();
+    let sink: Sink = Box::new(move |samples: &[f32]| {
+        to_pcm(samples, &mut pcm);
+        let _ = worker.push_audio(&pcm);
+    });
+    let input = Input::System {
+        device,
+        primary: false,
+    };
+    spawn(state, input, session_id, sink).map(|(capture, _)| capture)
+}
+
+// Feeds an in-process pipeline instead of the daemon; runs until stopped.
+pub fn start_with_sink(
+    state: &AppState,
+    device: Option<String>,
+    sink: Sink,
+) -> Result<CaptureInfo, AppError> {
+    let mut current = CAPTURE.lock();
+    claim(state, &mut current)?;
+    let (capture, info) = spawn(state, Input::Microphone(device), None, sink)?;
+    *current = Some(capture);
+    Ok(info)
+}
+
+// Runs only while nothing else wants the microphone: any other capture
+// takes over from it, and `stop` leaves it alone.
+pub fn start_background(state: &AppState, sink: Sink) -> Result<(), AppError> {
+    let mut current = CAPTURE.lock();
+    if current.as_ref().map_or(false, |c| is_capturing(state, c)) || daemon::is_running(state) {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "The microphone is in use",
+        ));
+    }
+    claim(state, &mut current)?;
+    let (mut capture, _) = spawn(state, Input::Microphone(None), None, sink)?;
+    capture.background = true;
+    *current = Some(capture);
+    Ok(())
+}
+
+pub fn stop_background() {
+    let mut current = CAPTURE.lock();
+    if current.as_ref().map_or(false, |c| c.background) {
+        if let Some(capture) = current.take() {
+            halt(capture);
+        }
+    }
+}
+
+pub fn is_idle(state: &AppState) -> bool {
+    !CAPTURE
+        .lock()
+        .as_ref()
+        .map_or(false, |c| is_capturing(state, c))
+}
+
+pub fn stop(state: &AppState) -> Result<(), AppError> {
+    let capture = {
+        let mut current = CAPTURE.lock();
+        match current.take() {
+            Some(capture) if !capture.background => capture,
+            other => {
+                *current = other;
+                return Err(AppError::new(
+                    ErrorCode::NotRunning,
+                    "Audio capture is not running",
+                ));
+            }
+        }
+    };
+    let owns_daemon = capture.session_id.is_some() && is_capturing(state, &capture);
+    halt(capture);
+    if owns_daemon {
+        daemon::stop(state)?;
+    }
+    Ok(())
+}
+
+pub fn current_device(state: &AppState) -> Option<String> {
+    CAPTURE
+        .lock()
+        .as_ref()
+        .filter(|c| is_capturing(state, c))
+        .map(|c| c.info.lock().device.clone())
+}
+
+// The format the device delivers, before it is converted to 16 kHz mono.
+pub fn capture_info(state: &AppState) -> Option<CaptureInfo> {
+    CAPTURE
+        .lock()
+        .as_ref()
+        .filter(|c| is_capturing(state, c))
+        .map(|c| c.info.lock().clone())
+}
+
+pub fn list_devices() -> Result<Vec<AudioDevice>, AppError> {
+    let host = cpal::default_host();
+    let default = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(|e| AppError::from(format!("Failed to list audio devices: {}", e)))?;
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let config = device.default_input_config().ok();
+            Some(AudioDevice {
+                is_default: default.as_deref() == Some(name.as_str()),
+                sample_rate: config.as_ref().map(|c| c.sample_rate().0),
+                channels: config.as_ref().map(|c| c.channels()),
+                id: name.clone(),
+                name,
+            })
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn start_capture(
+    state: tauri::State<'_, AppState>,
+    device: Option<String>,
+) -> CommandResult<CaptureInfo> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || start(&state, device))
+        .await
+        .map_err(|e| AppError::from(format!("Capture task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn stop_capture(state: tauri::State<'_, AppState>) -> CommandResult<()> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || stop(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Capture task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn enable_level_meter(enabled: bool) {
+    LEVEL_METER.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::Relaxed);
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+pub fn set_ducked(ducked: bool) {
+    DUCKED.store(ducked, Ordering::Relaxed);
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> CommandResult<Vec<AudioDevice>> {
+    list_devices()
+}
+
+#[tauri::command]
+pub fn list_input_devices() -> CommandResult<Vec<AudioDevice>> {
+    list_devices()
+}
+
+// None follows the system default. Running capture switches within a couple
+// of seconds; a daemon that opens the microphone itself from its next start.
+#[tauri::command]
+pub fn set_input_device(
+    state: tauri::State<'_, AppState>,
+    id: Option<String>,
+) -> CommandResult<()> {
+    if let Some(id) = &id {
+        if !list_devices()?.iter().any(|device| device.id == *id) {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No input device named {}", id),
+            ));
+        }
+    }
+    config::update(&state, |config| config.input_device = id)
+}
+
+// Applies from the next `start_capture`.
+#[tauri::command]
+pub fn set_capture_source(
+    state: tauri::State<'_, AppState>,
+    source: CaptureSource,
+) -> CommandResult<()> {
+    config::update(&state, |config| config.capture_source = source)
+}
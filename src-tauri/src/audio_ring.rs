@@ -0,0 +1,128 @@
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use memmap2::MmapMut;
+use serde::Serialize;
+
+// File layout, shared with the STT daemon. The read and write indices are
+// running byte counts on cache lines of their own, so the producer and the
+// consumer never write to the same line.
+const MAGIC: u32 = 0x5241_4d56;
+const VERSION: u32 = 1;
+const CAPACITY_AT: usize = 8;
+const CLOSED_AT: usize = 16;
+const WRITE_AT: usize = 64;
+const READ_AT: usize = 128;
+const DATA_AT: usize = 192;
+// About 30 s of 16 kHz mono s16le.
+pub const DEFAULT_CAPACITY: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RingStats {
+    pub used_bytes: u64,
+    pub capacity_bytes: u64,
+    // Audio that arrived while the consumer was a full buffer behind.
+    pub dropped_bytes: u64,
+}
+
+// The producer half of a lock-free single-producer, single-consumer byte
+// ring in a memory-mapped file, so capture hands PCM to the STT process
+// without a pipe copy or a per-chunk allocation. The daemon reads it in
+// place and advances the read index.
+pub struct AudioRing {
+    _map: MmapMut,
+    base: *mut u8,
+    capacity: u64,
+    dropped: AtomicU64,
+    path: PathBuf,
+}
+
+// Only the header atomics are touched by both sides at once; each data byte
+// belongs to one side at a time.
+unsafe impl Send for AudioRing {}
+unsafe impl Sync for AudioRing {}
+
+impl AudioRing {
+    pub fn create(path: &Path, capacity: usize) -> io::Result<Self> {
+        // Whole samples only.
+        let capacity = capacity.max(2) & !1;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((DATA_AT + capacity) as u64)?;
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        map[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        map[4..8].copy_from_slice(&VERSION.to_le_bytes());
+        map[CAPACITY_AT..CAPACITY_AT + 8].copy_from_slice(&(capacity as u64).to_le_bytes());
+        let base = map.as_mut_ptr();
+        Ok(Self {
+            _map: map,
+            base,
+            capacity: capacity as u64,
+            dropped: AtomicU64::new(0),
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn index(&self, at: usize) -> &AtomicU64 {
+        // The map is page aligned and every header offset is a multiple of 8.
+        unsafe { &*(self.base.add(at) as *const AtomicU64) }
+    }
+
+    fn closed(&self) -> &AtomicU32 {
+        unsafe { &*(self.base.add(CLOSED_AT) as *const AtomicU32) }
+    }
+
+    // Never blocks: what does not fit is dropped and counted, since stalling
+    // the producer would stall capture.
+    pub fn write(&self, data: &[u8]) -> usize {
+        let write = self.index(WRITE_AT).load(Ordering::Relaxed);
+        let read = self.index(READ_AT).load(Ordering::Acquire);
+        let free = self.capacity - (write - read);
+        let len = (data.len() as u64).min(free) as usize & !1;
+        let start = (write % self.capacity) as usize;
+        let first = len.min(self.capacity as usize - start);
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), self.base.add(DATA_AT + start), first);
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr().add(first),
+                self.base.add(DATA_AT),
+                len - first,
+            );
+        }
+        self.index(WRITE_AT)
+            .store(write + len as u64, Ordering::Release);
+        if len < data.len() {
+            self.dropped
+                .fetch_add((data.len() - len) as u64, Ordering::Relaxed);
+        }
+        len
+    }
+
+    // Tells the consumer no more audio is coming once it has drained the ring.
+    fn close(&self) {
+        self.closed().store(1, Ordering::Release);
+    }
+
+    pub fn stats(&self) -> RingStats {
+        let write = self.index(WRITE_AT).load(Ordering::Acquire);
+        let read = self.index(READ_AT).load(Ordering::Acquire);
+        RingStats {
+            used_bytes: write.saturating_sub(read),
+            capacity_bytes: self.capacity,
+            dropped_bytes: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for AudioRing {
+    fn drop(&mut self) {
+        self.close();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const STORE_FILE: &str = "auth.json";
+const AUDIT_LOG_LEN: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    ReadTranscripts,
+    ControlListening,
+    CallTools,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiToken {
+    id: String,
+    name: String,
+    scopes: Vec<Scope>,
+    created_at: u64,
+    hash: String,
+}
+
+// What the frontend sees; token hashes never leave this module.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: u64,
+}
+
+impl From<&ApiToken> for TokenInfo {
+    fn from(token: &ApiToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            created_at: token.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MintedToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: TokenInfo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub token_id: Option<String>,
+    pub token_name: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AuthConfig {
+    enabled: bool,
+    tokens: Vec<ApiToken>,
+}
+
+#[derive(Clone, Default)]
+pub struct AuthState {
+    config: Arc<Mutex<AuthConfig>>,
+    audit: Arc<Mutex<VecDeque<AuditEntry>>>,
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl AuthState {
+    pub fn load() -> Self {
+        Self {
+            config: Arc::new(Mutex::new(store::load_json(STORE_FILE))),
+            audit: Arc::default(),
+        }
+    }
+
+    fn save(&self, config: &AuthConfig) -> CommandResult<()> {
+        store::save_json(STORE_FILE, config)
+    }
+
+    fn record(&self, entry: AuditEntry) {
+        let mut audit = self.audit.lock();
+        if audit.len() == AUDIT_LOG_LEN {
+            audit.pop_front();
+        }
+        audit.push_back(entry);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.lock().enabled
+    }
+
+    fn authenticate(&self, token: &str) -> Option<ApiToken> {
+        let hash = hash(token);
+        self.config
+            .lock()
+            .tokens
+            .iter()
+            .find(|t| t.hash == hash)
+            .cloned()
+    }
+
+    pub fn mint(&self, name: String, scopes: Vec<Scope>) -> CommandResult<MintedToken> {
+        let token = format!(
+            "vma_{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let api_token = ApiToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            scopes,
+            created_at: now_millis(),
+            hash: hash(&token),
+        };
+        let mut config = self.config.lock();
+        let info = TokenInfo::from(&api_token);
+        config.tokens.push(api_token);
+        // Minting the first token is the opt-in to authentication.
+        config.enabled = true;
+        self.save(&config)?;
+        Ok(MintedToken { token, info })
+    }
+}
+
+pub(crate) fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    match path {
+        "/mic" => None,
+        "/mcp" | "/mcp/ws" => Some(Scope::CallTools),
+        "/metrics" | "/streamdeck/state" | "/streamdeck/ws" => Some(Scope::ReadTranscripts),
+        p if p.starts_with("/captions") && method == Method::GET => Some(Scope::ReadTranscripts),
+        _ => Some(Scope::ControlListening),
+    }
+}
+
+// Browsers cannot set headers on WebSocket or media requests, so `?token=`
+// is accepted alongside `Authorization: Bearer`.
+fn request_token<B>(request: &Request<B>) -> Option<String> {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+    bearer.or_else(|| {
+        request.uri().query().and_then(|query| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+                .map(str::to_string)
+        })
+    })
+}
+
+pub async fn middleware<B>(
+    State(state): State<AppState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let auth = &state.auth;
+    let scope = match required_scope(request.method(), request.uri().path()) {
+        Some(scope) if auth.enabled() => scope,
+        _ => return next.run(request).await,
+    };
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let token = request_token(&request).and_then(|t| auth.authenticate(&t));
+
+    let response = match &token {
+        Some(token) if token.scopes.contains(&scope) => next.run(request).await,
+        Some(_) => (StatusCode::FORBIDDEN, "Token lacks the required scope").into_response(),
+        None => (StatusCode::UNAUTHORIZED, "Missing or invalid API token").into_response(),
+    };
+    auth.record(AuditEntry {
+        token_id: token.as_ref().map(|t| t.id.clone()),
+        token_name: token.map(|t| t.name),
+        method,
+        path,
+        status: response.status().as_u16(),
+        timestamp: now_millis(),
+    });
+    response
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthStatus {
+    pub enabled: bool,
+    pub tokens: Vec<TokenInfo>,
+}
+
+#[tauri::command]
+pub fn get_api_auth_status(state: tauri::State<'_, AppState>) -> AuthStatus {
+    let config = state.auth.config.lock();
+    AuthStatus {
+        enabled: config.enabled,
+        tokens: config.tokens.iter().map(TokenInfo::from).collect(),
+    }
+}
+
+#[tauri::command]
+pub fn set_api_auth_enabled(state: tauri::State<'_, AppState>, enabled: bool) -> CommandResult<()> {
+    let mut config = state.auth.config.lock();
+    if enabled && config.tokens.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Mint a token before enabling authentication",
+        ));
+    }
+    config.enabled = enabled;
+    state.auth.save(&config)
+}
+
+#[tauri::command]
+pub fn mint_api_token(
+    state: tauri::State<'_, AppState>,
+    name: String,
+    scopes: Vec<Scope>,
+) -> CommandResult<MintedToken> {
+    if scopes.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "A token needs at least one scope",
+        ));
+    }
+    state.auth.mint(name, scopes)
+}
+
+#[tauri::command]
+pub fn revoke_api_token(state: tauri::State<'_, AppState>, id: String) -> CommandResult<()> {
+    let mut config = state.auth.config.lock();
+    let before = config.tokens.len();
+    config.tokens.retain(|t| t.id != id);
+    if config.tokens.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No API token with id {}", id),
+        ));
+    }
+    state.auth.save(&config)
+}
+
+#[tauri::command]
+pub fn get_api_audit_log(
+    state: tauri::State<'_, AppState>,
+    limit: Option<usize>,
+) -> Vec<AuditEntry> {
+    let audit = state.auth.audit.lock();
+    audit
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(AUDIT_LOG_LEN))
+        .cloned()
+        .collect()
+}
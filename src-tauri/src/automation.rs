@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use tauri::{AppHandle, ClipboardManager, Manager};
+use url::Url;
+
+use crate::backends;
+use crate::daemon::{self, InputSource};
+use crate::AppState;
+
+pub const SCHEME: &str = "voicemcp";
+
+pub fn register(app: &AppHandle) {
+    let app = app.clone();
+    let result = tauri_plugin_deep_link::register(SCHEME, move |request| {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || handle_url(&app, &request));
+    });
+    if let Err(e) = result {
+        tracing::error!("Failed to register {}:// handler: {}", SCHEME, e);
+    }
+}
+
+fn run_action(
+    state: &AppState,
+    action: &str,
+    params: &HashMap<String, String>,
+) -> Result<Option<String>, String> {
+    match action {
+        "toggle" => backends::toggle(state).map(|_| None).map_err(String::from),
+        "start" => backends::start(state, InputSource::Microphone)
+            .map(|_| None)
+            .map_err(String::from),
+        "stop" => backends::stop(state).map(|_| None).map_err(String::from),
+        "last-transcript" => state
+            .last_transcript
+            .lock()
+            .as_ref()
+            .map(|t| Some(t.text.clone()))
+            .ok_or_else(|| "No transcript yet".to_string()),
+        "transcribe" => match params.get("path") {
+            Some(path) => Ok(Some(daemon::transcribe_file(state, Path::new(path))?.text)),
+            None => Err("Missing path parameter".to_string()),
+        },
+        other => Err(format!("Unknown action: {}", other)),
+    }
+}
+
+fn open_callback(app: &AppHandle, callback: &str, key: &str, value: &str) {
+    match Url::parse(callback) {
+        Ok(mut url) => {
+            url.query_pairs_mut().append_pair(key, value);
+            if let Err(e) = tauri::api::shell::open(&app.shell_scope(), url.as_str(), None) {
+                tracing::warn!("Failed to open x-callback URL: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Invalid x-callback URL {}: {}", callback, e),
+    }
+}
+
+// Follows the x-callback-url convention so Shortcuts can read results back;
+// plain `open location` calls from AppleScript get text via the clipboard.
+fn handle_url(app: &AppHandle, request: &str) {
+    let url = match Url::parse(request) {
+        Ok(url) => url,
+        Err(e) => {
+            tracing::warn!("Invalid {}:// URL {}: {}", SCHEME, request, e);
+            return;
+        }
+    };
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let action = url.host_str().unwrap_or_default();
+    let state: tauri::State<AppState> = app.state();
+    let _span = tracing::info_span!("automation", action).entered();
+
+    match run_action(&state, action, &params) {
+        Ok(text) => match (params.get("x-success"), text) {
+            (Some(callback), text) => {
+                open_callback(app, callback, "text", text.as_deref().unwrap_or(""))
+            }
+            (None, Some(text)) => {
+                if let Err(e) = app.clipboard_manager().write_text(text) {
+                    tracing::warn!("Failed to copy transcript to clipboard: {}", e);
+                }
+            }
+            (None, None) => {}
+        },
+        Err(message) => match params.get("x-error") {
+            Some(callback) => open_callback(app, callback, "errorMessage", &message),
+            None => tracing::warn!("{}:// {} failed: {}", SCHEME, action, message),
+        },
+    }
+}
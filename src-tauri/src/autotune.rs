@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sysinfo::{System, SystemExt};
+
+use crate::config;
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::latency;
+use crate::models;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "autotune.json";
+const RUNS_PER_TRIAL: u32 = 2;
+// Rough float16 footprint of each MODEL_LADDER size on the GPU.
+const GPU_MEMORY_MB: [u64; 5] = [1024, 1024, 2048, 5120, 10240];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Device {
+    Cpu,
+    Cuda,
+}
+
+impl Device {
+    fn as_str(self) -> &'static str {
+        match self {
+            Device::Cpu => "cpu",
+            Device::Cuda => "cuda",
+        }
+    }
+}
+
+// Whisper runs on CTranslate2, which offloads the whole model or none of
+// it; `device` is the offload choice.
+// The user's override in config.toml; `auto` follows the hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComputeDevice {
+    Auto,
+    Cpu,
+    Gpu,
+}
+
+impl Default for ComputeDevice {
+    fn default() -> Self {
+        ComputeDevice::Auto
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tuning {
+    pub device: Device,
+    pub compute_type: String,
+    pub threads: usize,
+    pub beam_size: u32,
+    // Segments decoded together for file transcription; live chunks are
+    // always decoded one at a time.
+    pub batch_size: u32,
+}
+
+impl Tuning {
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            "--device".to_string(),
+            self.device.as_str().to_string(),
+            "--compute-type".to_string(),
+            self.compute_type.clone(),
+            "--threads".to_string(),
+            self.threads.to_string(),
+            "--beam-size".to_string(),
+            self.beam_size.to_string(),
+            "--batch-size".to_string(),
+            self.batch_size.to_string(),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Gpu {
+    pub name: String,
+    pub memory_mb: u64,
+    pub free_mb: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hardware {
+    pub physical_cores: usize,
+    pub logical_cores: usize,
+    pub gpu: Option<Gpu>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComputeCapabilities {
+    // The daemon can only offload to CUDA; the whisper.cpp engine can use
+    // any of the three, if it was built with that backend.
+    pub cuda: bool,
+    pub metal: bool,
+    pub vulkan: bool,
+    pub gpu: Option<Gpu>,
+    pub compute_device: ComputeDevice,
+    // Where the active model runs in the daemon with the current tuning.
+    pub daemon_device: Device,
+    pub native_gpu: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TuningStatus {
+    pub model: String,
+    pub hardware: Hardware,
+    pub tuning: Tuning,
+    // False while the tuning is still the heuristic guess.
+    pub measured: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Trial {
+    pub tuning: Tuning,
+    pub mean_ms: Option<f64>,
+    pub realtime_factor: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutotuneReport {
+    pub model: String,
+    pub hardware: Hardware,
+    pub trials: Vec<Trial>,
+    pub best: Tuning,
+}
+
+// Keyed by model: the best thread count for `tiny` is rarely the best for
+// `large`.
+static TUNED: Lazy<Mutex<HashMap<String, Tuning>>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+static HARDWARE: Lazy<Hardware> = Lazy::new(detect);
+static VULKAN: Lazy<bool> = Lazy::new(detect_vulkan);
+
+// Only NVIDIA cards count; CTranslate2 has no Metal or ROCm backend.
+fn detect_gpu() -> Option<Gpu> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=name,memory.total,memory.free",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [name, total, free] => Some(Gpu {
+                    name: name.to_string(),
+                    memory_mb: total.parse().ok()?,
+                    free_mb: free.parse().ok()?,
+                }),
+                _ => None,
+            }
+        })
+        .max_by_key(|gpu| gpu.free_mb)
+}
+
+// The loader library is there whenever a Vulkan driver is installed.
+fn detect_vulkan() -> bool {
+    let candidates: Vec<PathBuf> = if cfg!(windows) {
+        std::env::var_os("SystemRoot")
+            .map(|root| vec![PathBuf::from(root).join("System32").join("vulkan-1.dll")])
+            .unwrap_or_default()
+    } else if cfg!(target_os = "macos") {
+        // Only through MoltenVK, which whisper.cpp does not need next to Metal.
+        Vec::new()
+    } else {
+        [
+            "/usr/lib",
+            "/usr/lib64",
+            "/usr/lib/x86_64-linux-gnu",
+            "/usr/lib/aarch64-linux-gnu",
+        ]
+        .iter()
+        .map(|dir| Path::new(dir).join("libvulkan.so.1"))
+        .collect()
+    };
+    candidates.iter().any(|path| path.is_file())
+}
+
+fn detect() -> Hardware {
+    let logical_cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let physical_cores = System::new()
+        .physical_core_count()
+        .unwrap_or(logical_cores)
+        .max(1);
+    let hardware = Hardware {
+        physical_cores,
+        logical_cores,
+        gpu: detect_gpu(),
+    };
+    tracing::info!(target: "autotune", "Detected {:?}", hardware);
+    hardware
+}
+
+pub fn hardware() -> Hardware {
+    HARDWARE.clone()
+}
+
+// Every Mac the app supports has Metal.
+fn metal() -> bool {
+    cfg!(target_os = "macos")
+}
+
+pub fn capabilities(model: &str) -> ComputeCapabilities {
+    let gpu = hardware().gpu;
+    ComputeCapabilities {
+        cuda: gpu.is_some(),
+        metal: metal(),
+        vulkan: *VULKAN,
+        gpu,
+        compute_device: config::current().compute_device,
+        daemon_device: current(model).device,
+        native_gpu: native_gpu(),
+    }
+}
+
+// Whether the whisper.cpp engine should ask for its GPU backend.
+pub fn native_gpu() -> bool {
+    config::current().compute_device != ComputeDevice::Cpu
+        && (HARDWARE.gpu.is_some() || metal() || *VULKAN)
+}
+
+// Applies the compute device override on top of a tuning. Forcing the GPU
+// uses the compact compute type, since the model may have been judged too
+// big for it.
+fn prefer(tuning: Tuning) -> Tuning {
+    match (config::current().compute_device, tuning.device) {
+        (ComputeDevice::Cpu, Device::Cuda) => Tuning {
+            device: Device::Cpu,
+            compute_type: "int8".to_string(),
+            batch_size: 1,
+            ..tuning
+        },
+        (ComputeDevice::Gpu, Device::Cpu) if HARDWARE.gpu.is_some() => Tuning {
+            device: Device::Cuda,
+            compute_type: "int8_float16".to_string(),
+            ..tuning
+        },
+        _ => tuning,
+    }
+}
+
+fn gpu_need_mb(model: &str) -> u64 {
+    models::rank(model).map_or(GPU_MEMORY_MB[4], |rank| GPU_MEMORY_MB[rank])
+}
+
+// A starting point from the hardware alone, used until `run_autotune` has
+// measured something better.
+pub fn heuristic(hardware: &Hardware, model: &str) -> Tuning {
+    prefer(guess(hardware, model))
+}
+
+fn guess(hardware: &Hardware, model: &str) -> Tuning {
+    let need_mb = gpu_need_mb(model);
+    let cores = hardware.physical_cores;
+    match &hardware.gpu {
+        Some(gpu) if gpu.free_mb >= need_mb => Tuning {
+            device: Device::Cuda,
+            compute_type: "float16".to_string(),
+            threads: cores.min(4),
+            beam_size: 5,
+            batch_size: if gpu.free_mb >= need_mb * 2 { 8 } else { 4 },
+        },
+        // int8 weights take about half the memory.
+        Some(gpu) if gpu.free_mb >= need_mb / 2 => Tuning {
+            device: Device::Cuda,
+            compute_type: "int8_float16".to_string(),
+            threads: cores.min(4),
+            beam_size: 5,
+            batch_size: 2,
+        },
+        // Leaves a core for audio capture and the UI once there are enough
+        // to spare; greedy decoding keeps small machines real time.
+        _ => Tuning {
+            device: Device::Cpu,
+            compute_type: "int8".to_string(),
+            threads: if cores > 4 { cores - 1 } else { cores },
+            beam_size: if cores >= 8 { 5 } else { 1 },
+            batch_size: 1,
+        },
+    }
+}
+
+fn candidates(hardware: &Hardware, model: &str) -> Vec<Tuning> {
+    let base = heuristic(hardware, model);
+    let mut candidates = vec![base.clone()];
+    let mut push = |tuning: Tuning| {
+        if !candidates.contains(&tuning) {
+            candidates.push(tuning);
+        }
+    };
+    let cpu = Tuning {
+        device: Device::Cpu,
+        compute_type: "int8".to_string(),
+        ..base.clone()
+    };
+    for threads in [
+        hardware.physical_cores / 2,
+        hardware.physical_cores,
+        hardware.logical_cores,
+    ] {
+        push(Tuning {
+            threads: threads.max(1),
+            batch_size: 1,
+            ..cpu.clone()
+        });
+    }
+    push(Tuning {
+        beam_size: if base.beam_size == 1 { 5 } else { 1 },
+        ..base.clone()
+    });
+    if base.device == Device::Cuda {
+        for batch_size in [1, base.batch_size * 2] {
+            push(Tuning {
+                batch_size,
+                ..base.clone()
+            });
+        }
+    }
+    // An override keeps every trial on the chosen device.
+    if config::current().compute_device != ComputeDevice::Auto {
+        candidates.retain(|tuning| tuning.device == base.device);
+    }
+    candidates
+}
+
+pub fn current(model: &str) -> Tuning {
+    match TUNED.lock().get(model) {
+        Some(tuning) => prefer(tuning.clone()),
+        None => heuristic(&HARDWARE, model),
+    }
+}
+
+fn status(state: &AppState) -> TuningStatus {
+    let model = daemon::current_model(state);
+    TuningStatus {
+        measured: TUNED.lock().contains_key(&model),
+        tuning: current(&model),
+        hardware: hardware(),
+        model,
+    }
+}
+
+// Configurations that fail to run, such as CUDA without its runtime
+// libraries, are reported and skipped.
+pub fn run(state: &AppState, path: &Path, runs: u32) -> Result<AutotuneReport, AppError> {
+    let model = daemon::current_model(state);
+    let hardware = hardware();
+    let mut trials = Vec::new();
+    for tuning in candidates(&hardware, &model) {
+        tracing::info!(target: "autotune", "Measuring {} with {:?}", model, tuning);
+        let trial = match latency::benchmark_file(state, path, runs, Some(&tuning)) {
+            Ok(benchmark) => Trial {
+                mean_ms: benchmark.stt.map(|s| s.mean_ms),
+                realtime_factor: benchmark.realtime_factor,
+                error: None,
+                tuning,
+            },
+            Err(error) => Trial {
+                mean_ms: None,
+                realtime_factor: None,
+                error: Some(error),
+                tuning,
+            },
+        };
+        trials.push(trial);
+    }
+    let best = trials
+        .iter()
+        .filter_map(|t| Some((t, t.mean_ms?)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(t, _)| t.tuning.clone())
+        .ok_or_else(|| {
+            AppError::from(format!(
+                "No configuration could transcribe {}",
+                path.display()
+            ))
+        })?;
+
+    let mut tuned = TUNED.lock();
+    tuned.insert(model.clone(), best.clone());
+    store::save_json(STORE_FILE, &*tuned)?;
+    tracing::info!(target: "autotune", "Best for {}: {:?}", model, best);
+    Ok(AutotuneReport {
+        model,
+        hardware,
+        trials,
+        best,
+    })
+}
+
+#[tauri::command]
+pub fn get_tuning(state: tauri::State<'_, AppState>) -> TuningStatus {
+    status(&state)
+}
+
+// Applies from the next daemon start.
+#[tauri::command]
+pub async fn run_autotune(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    runs: Option<u32>,
+) -> CommandResult<AutotuneReport> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        run(
+            &state,
+            Path::new(&path),
+            runs.unwrap_or(RUNS_PER_TRIAL).max(1),
+        )
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Autotune task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn reset_tuning(state: tauri::State<'_, AppState>) -> CommandResult<TuningStatus> {
+    let model = daemon::current_model(&state);
+    let mut tuned = TUNED.lock();
+    tuned.remove(&model);
+    store::save_json(STORE_FILE, &*tuned)?;
+    drop(tuned);
+    Ok(status(&state))
+}
+
+#[tauri::command]
+pub fn get_compute_capabilities(state: tauri::State<'_, AppState>) -> ComputeCapabilities {
+    capabilities(&daemon::current_model(&state))
+}
+
+// Saved as `compute_device` in config.toml; applies from the next daemon
+// start or model load.
+#[tauri::command]
+pub fn set_compute_device(
+    state: tauri::State<'_, AppState>,
+    device: ComputeDevice,
+) -> CommandResult<ComputeCapabilities> {
+    if device == ComputeDevice::Gpu && HARDWARE.gpu.is_none() && !metal() && !*VULKAN {
+        return Err(AppError::new(
+            ErrorCode::Unsupported,
+            "No CUDA, Metal or Vulkan device was found",
+        ));
+    }
+    config::update(&state, |config| config.compute_device = device)?;
+    Ok(capabilities(&daemon::current_model(&state)))
+}
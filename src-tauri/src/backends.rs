@@ -0,0 +1,1341 @@
+use std::io::Cursor;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::audio::{self, SAMPLE_RATE};
+use crate::background_core;
+use crate::bus::AgentEvent;
+use crate::config;
+use crate::daemon::{self, InputSource};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::i18n;
+use crate::latency;
+use crate::network::{self, Backoff, OutageBuffer};
+use crate::profiles;
+use crate::recordings;
+use crate::resampler::{AudioFormat, Converter};
+use crate::secrets;
+use crate::stabilizer::Stabilizer;
+use crate::store;
+use crate::transcript::{now_millis, Transcript};
+use crate::vocab;
+use crate::AppState;
+
+const STORE_FILE: &str = "stt_backend.json";
+// The Whisper API is not streaming, so audio is uploaded in slices.
+const UPLOAD_SECONDS: usize = 10;
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+// Deepgram's streaming models are tuned for wideband audio.
+const DEEPGRAM_SAMPLE_RATE: u32 = 48_000;
+const DEEPGRAM_URL: &str = "https://api.deepgram.com";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type DeepgramSocket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+// Every backend a dictation session can run on. Cloud backends run behind
+// native capture; the local daemon captures its input itself.
+pub trait SpeechBackend: Send {
+    fn start_stream(&mut self, state: &AppState, session_id: &str) -> Result<(), AppError>;
+    // Called on the audio thread with samples in its kind's `format`; must
+    // not block.
+    fn push_audio(&mut self, samples: &[f32]) -> Result<(), AppError>;
+    // Transcribes whatever is still buffered and waits for the results.
+    fn finalize(&mut self) -> Result<(), AppError>;
+    // None detects the language. A backend that fixes it when it connects
+    // keeps the old one until its next session.
+    fn set_language(&mut self, _language: Option<&str>) -> Result<(), AppError> {
+        Ok(())
+    }
+    // A backend that captures for itself is not fed native capture, and
+    // reports its own session and listening state.
+    fn captures_input(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    Local,
+    Openai,
+    Deepgram,
+}
+
+impl Default for BackendKind {
+    fn default() -> Self {
+        BackendKind::Local
+    }
+}
+
+impl BackendKind {
+    // What the backend is sent; capture is converted to it from the
+    // pipeline's format before `push_audio`.
+    pub fn format(self) -> AudioFormat {
+        match self {
+            BackendKind::Deepgram => AudioFormat::mono(DEEPGRAM_SAMPLE_RATE),
+            BackendKind::Local | BackendKind::Openai => AudioFormat::PIPELINE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiConfig {
+    // Filled in from the keychain at start; only read from the file to move
+    // keys saved by earlier versions.
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub model: String,
+    // For OpenAI-compatible servers.
+    pub base_url: String,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            model: "whisper-1".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeepgramConfig {
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub model: String,
+}
+
+impl Default for DeepgramConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            model: "nova-2".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    pub backend: BackendKind,
+    pub openai: OpenAiConfig,
+    pub deepgram: DeepgramConfig,
+}
+
+// What the UI sees; keys never leave the keychain.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendStatus {
+    pub backend: BackendKind,
+    pub openai_configured: bool,
+    pub deepgram_configured: bool,
+    pub active: bool,
+    // The local backend is standing in for an unreachable cloud one.
+    pub fallback: bool,
+}
+
+struct Session {
+    backend: Arc<Mutex<Box<dyn SpeechBackend>>>,
+    session_id: String,
+    captures_input: bool,
+}
+
+static CONFIG: Lazy<Mutex<BackendConfig>> =
+    Lazy::new(|| Mutex::new(migrate_keys(store::load_json(STORE_FILE))));
+static SESSION: Lazy<Mutex<Option<Session>>> = Lazy::new(|| Mutex::new(None));
+// Held while a session starts, stops or hands over, so those run one at a
+// time without holding `SESSION` while a daemon loads or a backend connects.
+static SWITCHING: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+// The cloud backend a fallback session stands in for.
+static FALLBACK: Lazy<Mutex<Option<BackendKind>>> = Lazy::new(Mutex::default);
+
+// The file is only rewritten once every key is in the keychain, so a failed
+// move is retried on the next launch.
+fn migrate_keys(mut config: BackendConfig) -> BackendConfig {
+    let keys = [
+        ("openai", config.openai.api_key.take()),
+        ("deepgram", config.deepgram.api_key.take()),
+    ];
+    let mut pending = false;
+    let mut moved = false;
+    for (provider, key) in keys {
+        let key = match key {
+            Some(key) => key,
+            None => continue,
+        };
+        match secrets::set_api_key(provider, &key) {
+            Ok(()) => moved = true,
+            Err(e) => {
+                pending = true;
+                tracing::warn!(target: "stt_backend", provider, "Could not move the API key into the keychain: {}", e);
+            }
+        }
+    }
+    if moved && !pending {
+        if let Err(e) = store::save_json(STORE_FILE, &config) {
+            tracing::warn!(target: "stt_backend", "{}", e);
+        }
+    }
+    config
+}
+
+fn missing_key(name: &str) -> AppError {
+    AppError::new(
+        ErrorCode::InvalidInput,
+        format!("No {} API key is configured", name),
+    )
+}
+
+// The status is kept, so a rejected request is not retried as an outage.
+fn http_failed(what: &str, e: reqwest::Error) -> AppError {
+    let error = AppError::new(ErrorCode::Network, format!("{} failed: {}", what, e));
+    match e.status() {
+        Some(status) => error.with_context("status", status.as_u16()),
+        None => error,
+    }
+}
+
+fn transcript(
+    text: String,
+    is_final: bool,
+    confidence: Option<f32>,
+    session_id: &str,
+    span_ms: Option<(u64, u64)>,
+) -> Transcript {
+    Transcript {
+        id: uuid::Uuid::new_v4().to_string(),
+        text,
+        is_final,
+        timestamp: now_millis(),
+        confidence,
+        session_id: Some(session_id.to_string()),
+        start_ms: span_ms.map(|(start, _)| start),
+        end_ms: span_ms.map(|(_, end)| end),
+        speaker: None,
+        language: None,
+        translation: None,
+        latency: None,
+        words: Vec::new(),
+    }
+}
+
+pub fn wav(samples: &[f32]) -> Result<Vec<u8>, AppError> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let failed = |e: hound::Error| AppError::from(format!("Failed to encode audio: {}", e));
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut bytes, spec).map_err(failed)?;
+    for &sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(failed)?;
+    }
+    writer.finalize().map_err(failed)?;
+    Ok(bytes.into_inner())
+}
+
+struct OpenAiWhisper {
+    config: OpenAiConfig,
+    // Read for every upload.
+    language: Arc<Mutex<Option<String>>>,
+    buffer: Vec<f32>,
+    offset_ms: u64,
+    // Offset and capture time, in ms, with the chunk.
+    uploads: Option<mpsc::Sender<(u64, u64, Vec<f32>)>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl OpenAiWhisper {
+    fn new(config: OpenAiConfig, language: Option<String>) -> Self {
+        Self {
+            config,
+            language: Arc::new(Mutex::new(language)),
+            buffer: Vec::new(),
+            offset_ms: 0,
+            uploads: None,
+            worker: None,
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let samples = std::mem::take(&mut self.buffer);
+        let offset_ms = self.offset_ms;
+        self.offset_ms += samples.len() as u64 * 1000 / SAMPLE_RATE as u64;
+        if let Some(uploads) = &self.uploads {
+            let _ = uploads.send((offset_ms, now_millis(), samples));
+        }
+    }
+}
+
+// The server infers the format from the file name.
+async fn openai_transcribe(
+    client: &reqwest::Client,
+    config: &OpenAiConfig,
+    language: Option<&str>,
+    audio: Vec<u8>,
+    file_name: &str,
+) -> Result<String, AppError> {
+    let api_key = config
+        .api_key
+        .as_deref()
+        .ok_or_else(|| missing_key("OpenAI"))?;
+    let file = reqwest::multipart::Part::bytes(audio).file_name(file_name.to_string());
+    let mut form = reqwest::multipart::Form::new()
+        .text("model", config.model.clone())
+        .part("file", file);
+    // Left out, the server detects it.
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
+    if let Some(prompt) = vocab::prompt() {
+        form = form.text("prompt", prompt);
+    }
+    let network = |e| http_failed("OpenAI transcription", e);
+    let response: serde_json::Value = client
+        .post(format!(
+            "{}/audio/transcriptions",
+            config.base_url.trim_end_matches('/')
+        ))
+        .bearer_auth(api_key)
+        .multipart(form)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(network)?
+        .json()
+        .await
+        .map_err(network)?;
+    Ok(response["text"]
+        .as_str()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
+
+impl SpeechBackend for OpenAiWhisper {
+    fn start_stream(&mut self, state: &AppState, session_id: &str) -> Result<(), AppError> {
+        if self.config.api_key.is_none() {
+            return Err(missing_key("OpenAI"));
+        }
+        let (tx, rx) = mpsc::channel::<(u64, u64, Vec<f32>)>();
+        let (state, session_id) = (state.clone(), session_id.to_string());
+        let (config, language) = (self.config.clone(), self.language.clone());
+        let network_config = config::current().network;
+        self.uploads = Some(tx);
+        // Uploads go one at a time, so transcripts arrive in order. A slice
+        // that fails for want of a connection is retried, and the ones after
+        // it wait in `pending`.
+        self.worker = Some(thread::spawn(move || {
+            let client = reqwest::Client::new();
+            let mut pending = OutageBuffer::new(&network_config, SAMPLE_RATE);
+            let mut backoff = Backoff::new(&network_config);
+            let mut open = true;
+            loop {
+                if pending.is_empty() {
+                    match rx.recv() {
+                        Ok(upload) => pending.push(upload.2.len(), upload),
+                        Err(_) => break,
+                    }
+                }
+                let started_at = now_millis();
+                let code = language.lock().clone();
+                let result = match pending.front() {
+                    Some((_, _, samples)) => wav(samples).and_then(|audio| {
+                        tauri::async_runtime::block_on(openai_transcribe(
+                            &client,
+                            &config,
+                            code.as_deref(),
+                            audio,
+                            "audio.wav",
+                        ))
+                    }),
+                    None => continue,
+                };
+                match result {
+                    Err(error) if network::is_outage(&error) => {
+                        // Stopped while offline; what is still held is dropped.
+                        if !open {
+                            break;
+                        }
+                        if backoff.attempt() == 0 {
+                            lost(&state, BackendKind::Openai, &error);
+                        }
+                        // Waits on the queue rather than sleeping, so the cap
+                        // applies to new slices and a stop is seen at once.
+                        let deadline = Instant::now() + backoff.next();
+                        while open {
+                            let left = deadline.saturating_duration_since(Instant::now());
+                            match rx.recv_timeout(left) {
+                                Ok(upload) => pending.push(upload.2.len(), upload),
+                                Err(RecvTimeoutError::Timeout) => break,
+                                Err(RecvTimeoutError::Disconnected) => open = false,
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        pending.pop();
+                        state.bus.publish(AgentEvent::error("openai", error));
+                    }
+                    Ok(text) => {
+                        let (offset_ms, captured_at, samples) = match pending.pop() {
+                            Some(upload) => upload,
+                            None => continue,
+                        };
+                        if backoff.attempt() > 0 {
+                            backoff.reset();
+                            let dropped = pending.take_dropped() as u64;
+                            restored(
+                                &state,
+                                BackendKind::Openai,
+                                dropped * 1000 / SAMPLE_RATE as u64,
+                            );
+                        }
+                        if text.is_empty() {
+                            continue;
+                        }
+                        let end_ms = offset_ms + samples.len() as u64 * 1000 / SAMPLE_RATE as u64;
+                        let mut transcript =
+                            transcript(text, true, None, &session_id, Some((offset_ms, end_ms)));
+                        transcript.latency = Some(latency::measured(captured_at, started_at));
+                        transcript.language = code;
+                        recordings::save(&transcript, &samples);
+                        daemon::publish_transcript(&state, transcript);
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    fn push_audio(&mut self, samples: &[f32]) -> Result<(), AppError> {
+        self.buffer.extend_from_slice(samples);
+        if self.buffer.len() >= UPLOAD_SECONDS * SAMPLE_RATE as usize {
+            self.flush();
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), AppError> {
+        self.flush();
+        self.uploads.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        Ok(())
+    }
+
+    fn set_language(&mut self, language: Option<&str>) -> Result<(), AppError> {
+        *self.language.lock() = language.map(str::to_string);
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct DeepgramAlternative {
+    transcript: String,
+    confidence: Option<f32>,
+    // Streams in `multi` list what each result was in.
+    #[serde(default)]
+    languages: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct DeepgramChannel {
+    alternatives: Vec<DeepgramAlternative>,
+    #[serde(default)]
+    detected_language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeepgramResult {
+    #[serde(rename = "type")]
+    kind: String,
+    channel: Option<DeepgramChannel>,
+    #[serde(default)]
+    is_final: bool,
+    #[serde(default)]
+    start: f64,
+    #[serde(default)]
+    duration: f64,
+}
+
+#[derive(Deserialize)]
+struct DeepgramResults {
+    channels: Vec<DeepgramChannel>,
+}
+
+#[derive(Deserialize)]
+struct DeepgramResponse {
+    results: DeepgramResults,
+}
+
+struct Deepgram {
+    config: DeepgramConfig,
+    language: Option<String>,
+    pcm: Vec<u8>,
+    audio: Option<tokio::sync::mpsc::UnboundedSender<Vec<u8>>>,
+    task: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl Deepgram {
+    fn new(config: DeepgramConfig, language: Option<String>) -> Self {
+        Self {
+            config,
+            language,
+            pcm: Vec::new(),
+            audio: None,
+            task: None,
+        }
+    }
+}
+
+// `offset_ms` is the audio sent on earlier connections; Deepgram times
+// results from the start of each one.
+fn deepgram_transcript(
+    text: &str,
+    session_id: &str,
+    language: Option<&str>,
+    offset_ms: u64,
+) -> Option<Transcript> {
+    let result: DeepgramResult = serde_json::from_str(text).ok()?;
+    if result.kind != "Results" {
+        return None;
+    }
+    let best = result.channel?.alternatives.into_iter().next()?;
+    let words = best.transcript.trim().to_string();
+    if words.is_empty() {
+        return None;
+    }
+    let start_ms = offset_ms + (result.start * 1000.0) as u64;
+    let end_ms = offset_ms + ((result.start + result.duration) * 1000.0) as u64;
+    let mut transcript = transcript(
+        words,
+        result.is_final,
+        best.confidence,
+        session_id,
+        Some((start_ms, end_ms)),
+    );
+    transcript.language = best
+        .languages
+        .into_iter()
+        .next()
+        .or_else(|| language.map(str::to_string));
+    Some(transcript)
+}
+
+async fn deepgram_connect(
+    config: &DeepgramConfig,
+    language: Option<&str>,
+) -> Result<DeepgramSocket, AppError> {
+    let api_key = config
+        .api_key
+        .as_deref()
+        .ok_or_else(|| missing_key("Deepgram"))?;
+    let mut url = reqwest::Url::parse(&format!(
+        "wss://api.deepgram.com/v1/listen?model={}&language={}&encoding=linear16&sample_rate={}&channels=1&interim_results=true&punctuate=true",
+        config.model,
+        // Multilingual models transcribe code-switched speech as it comes.
+        language.unwrap_or("multi"),
+        BackendKind::Deepgram.format().sample_rate
+    ))
+    .map_err(|e| AppError::from(format!("Invalid Deepgram request: {}", e)))?;
+    // Terms added mid-session apply from the next connection.
+    url.query_pairs_mut()
+        .extend_pairs(deepgram_keywords(&config.model));
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| AppError::from(format!("Invalid Deepgram request: {}", e)))?;
+    let authorization = format!("Token {}", api_key)
+        .parse()
+        .map_err(|_| AppError::new(ErrorCode::InvalidInput, "Invalid Deepgram API key"))?;
+    request.headers_mut().insert("Authorization", authorization);
+    match tokio_tungstenite::connect_async(request).await {
+        Ok((socket, _)) => Ok(socket),
+        Err(e) => {
+            let error = AppError::new(
+                ErrorCode::Network,
+                format!("Failed to connect to Deepgram: {}", e),
+            );
+            Err(match &e {
+                tungstenite::Error::Http(response) => {
+                    error.with_context("status", response.status().as_u16())
+                }
+                _ => error,
+            })
+        }
+    }
+}
+
+// One session's stream, across as many connections as it takes.
+struct DeepgramStream {
+    state: AppState,
+    session_id: String,
+    config: DeepgramConfig,
+    language: Option<String>,
+    stabilizer: Stabilizer,
+    audio: tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>,
+    buffer: OutageBuffer<Vec<u8>>,
+    backoff: Backoff,
+    // Samples taken from capture; the buffered ones are not sent yet.
+    received: u64,
+}
+
+impl DeepgramStream {
+    fn offset_ms(&self) -> u64 {
+        (self.received - self.buffer.samples() as u64) * 1000
+            / BackendKind::Deepgram.format().sample_rate as u64
+    }
+
+    fn publish(&mut self, text: &str, offset_ms: u64) {
+        match deepgram_transcript(text, &self.session_id, self.language.as_deref(), offset_ms) {
+            Some(transcript) if transcript.is_final => {
+                self.stabilizer.reset();
+                daemon::publish_transcript(&self.state, transcript);
+            }
+            Some(mut transcript) => {
+                if let Some(stable) = self.stabilizer.partial(&transcript.text) {
+                    transcript.text = stable;
+                    daemon::publish_transcript(&self.state, transcript);
+                }
+            }
+            None => {}
+        }
+    }
+
+    // Sends what was held first. Returns why the connection dropped, or None
+    // once the audio ran out and Deepgram flushed its results.
+    async fn stream(&mut self, socket: DeepgramSocket) -> Option<AppError> {
+        let offset_ms = self.offset_ms();
+        let failed = |e: tungstenite::Error| {
+            AppError::new(ErrorCode::Network, format!("Deepgram stream failed: {}", e))
+        };
+        let (mut outgoing, mut incoming) = socket.split();
+        while let Some(pcm) = self.buffer.pop() {
+            if let Err(e) = outgoing.send(Message::Binary(pcm)).await {
+                return Some(failed(e));
+            }
+        }
+        let mut sending = true;
+        loop {
+            tokio::select! {
+                pcm = self.audio.recv(), if sending => match pcm {
+                    Some(pcm) => {
+                        self.received += pcm.len() as u64 / 2;
+                        if let Err(e) = outgoing.send(Message::Binary(pcm)).await {
+                            return Some(failed(e));
+                        }
+                    }
+                    // Deepgram flushes its results and closes the socket.
+                    None => {
+                        let close = serde_json::json!({ "type": "CloseStream" }).to_string();
+                        let _ = outgoing.send(Message::Text(close)).await;
+                        sending = false;
+                    }
+                },
+                message = incoming.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.publish(&text, offset_ms),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) if sending => return Some(failed(e)),
+                    None if sending => {
+                        return Some(AppError::new(ErrorCode::Network, "Deepgram closed the stream"))
+                    }
+                    Some(Err(_)) | None => return None,
+                },
+            }
+        }
+    }
+
+    // Holds capture in the buffer between attempts. None when the session
+    // stopped first, or Deepgram refused the connection outright.
+    async fn reconnect(&mut self) -> Option<DeepgramSocket> {
+        loop {
+            let wait = tokio::time::sleep(self.backoff.next());
+            tokio::pin!(wait);
+            loop {
+                tokio::select! {
+                    _ = &mut wait => break,
+                    pcm = self.audio.recv() => match pcm {
+                        Some(pcm) => {
+                            self.received += pcm.len() as u64 / 2;
+                            self.buffer.push(pcm.len() / 2, pcm);
+                        }
+                        None => return None,
+                    },
+                }
+            }
+            match deepgram_connect(&self.config, self.language.as_deref()).await {
+                Ok(socket) => return Some(socket),
+                Err(error) if network::is_outage(&error) => tracing::warn!(
+                    target: "stt_backend",
+                    "Deepgram reconnect {} failed: {}",
+                    self.backoff.attempt(),
+                    error
+                ),
+                Err(error) => {
+                    self.state.bus.publish(AgentEvent::error("deepgram", error));
+                    return None;
+                }
+            }
+        }
+    }
+
+    async fn run(mut self, mut socket: DeepgramSocket) {
+        loop {
+            let error = match self.stream(socket).await {
+                Some(error) => error,
+                None => return,
+            };
+            // The next connection starts its hypotheses over.
+            self.stabilizer.reset();
+            lost(&self.state, BackendKind::Deepgram, &error);
+            socket = match self.reconnect().await {
+                Some(socket) => socket,
+                None => return,
+            };
+            self.backoff.reset();
+            let dropped = self.buffer.take_dropped() as u64;
+            restored(
+                &self.state,
+                BackendKind::Deepgram,
+                dropped * 1000 / BackendKind::Deepgram.format().sample_rate as u64,
+            );
+        }
+    }
+}
+
+impl SpeechBackend for Deepgram {
+    fn start_stream(&mut self, state: &AppState, session_id: &str) -> Result<(), AppError> {
+        let socket = tauri::async_runtime::block_on(deepgram_connect(
+            &self.config,
+            self.language.as_deref(),
+        ))?;
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+        let network_config = config::current().network;
+        let stream = DeepgramStream {
+            state: state.clone(),
+            session_id: session_id.to_string(),
+            config: self.config.clone(),
+            language: self.language.clone(),
+            stabilizer: Stabilizer::new(&config::current().partials),
+            audio: rx,
+            buffer: OutageBuffer::new(&network_config, BackendKind::Deepgram.format().sample_rate),
+            backoff: Backoff::new(&network_config),
+            received: 0,
+        };
+        self.audio = Some(tx);
+        self.task = Some(tauri::async_runtime::spawn(stream.run(socket)));
+        Ok(())
+    }
+
+    fn push_audio(&mut self, samples: &[f32]) -> Result<(), AppError> {
+        self.pcm.clear();
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.pcm.extend_from_slice(&value.to_le_bytes());
+        }
+        match &self.audio {
+            Some(audio) => audio
+                .send(self.pcm.clone())
+                .map_err(|_| AppError::new(ErrorCode::Network, "The Deepgram stream has closed")),
+            None => Ok(()),
+        }
+    }
+
+    fn finalize(&mut self) -> Result<(), AppError> {
+        self.audio.take();
+        if let Some(task) = self.task.take() {
+            let _ = tauri::async_runtime::block_on(tokio::time::timeout(CLOSE_TIMEOUT, task));
+        }
+        Ok(())
+    }
+}
+
+// Nova-3 takes key terms as they are; older models take boosted keywords.
+fn deepgram_keywords(model: &str) -> Vec<(&'static str, String)> {
+    vocab::terms()
+        .into_iter()
+        .map(|term| {
+            if model.starts_with("nova-3") {
+                ("keyterm", term)
+            } else {
+                ("keywords", format!("{}:2", term))
+            }
+        })
+        .collect()
+}
+
+async fn deepgram_transcribe(
+    config: &DeepgramConfig,
+    language: Option<&str>,
+    audio: Vec<u8>,
+) -> Result<(String, Option<f32>, Option<String>), AppError> {
+    let api_key = config
+        .api_key
+        .as_deref()
+        .ok_or_else(|| missing_key("Deepgram"))?;
+    let network = |e| http_failed("Deepgram transcription", e);
+    let response: DeepgramResponse = reqwest::Client::new()
+        .post("https://api.deepgram.com/v1/listen")
+        .query(&[("model", config.model.as_str()), ("punctuate", "true")])
+        .query(&[match language {
+            Some(language) => ("language", language),
+            None => ("detect_language", "true"),
+        }])
+        .query(&deepgram_keywords(&config.model))
+        .header("Authorization", format!("Token {}", api_key))
+        .body(audio)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(network)?
+        .json()
+        .await
+        .map_err(network)?;
+    let channel = response.results.channels.into_iter().next();
+    let detected = channel
+        .as_ref()
+        .and_then(|channel| channel.detected_language.clone())
+        .or_else(|| language.map(str::to_string));
+    let best = channel.and_then(|channel| channel.alternatives.into_iter().next());
+    Ok(best.map_or((String::new(), None, None), |best| {
+        (
+            best.transcript.trim().to_string(),
+            best.confidence,
+            detected,
+        )
+    }))
+}
+
+// The Python daemon behind the lifecycle queue. It names its own session, and
+// stopping it transcribes whatever it still has queued.
+struct LocalDaemon {
+    state: AppState,
+    source: InputSource,
+}
+
+impl SpeechBackend for LocalDaemon {
+    fn start_stream(&mut self, state: &AppState, _session_id: &str) -> Result<(), AppError> {
+        daemon::start(state, self.source).map(|_| ())
+    }
+
+    fn push_audio(&mut self, _samples: &[f32]) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), AppError> {
+        daemon::stop(&self.state).map(|_| ())
+    }
+
+    fn set_language(&mut self, language: Option<&str>) -> Result<(), AppError> {
+        daemon::configure(&self.state, serde_json::json!({ "language": language }))
+    }
+
+    fn captures_input(&self) -> bool {
+        true
+    }
+}
+
+fn create(
+    kind: BackendKind,
+    state: &AppState,
+    source: InputSource,
+) -> Result<Box<dyn SpeechBackend>, AppError> {
+    let mut config = CONFIG.lock().clone();
+    let language = profiles::stt_language(state);
+    Ok(match kind {
+        BackendKind::Local => Box::new(LocalDaemon {
+            state: state.clone(),
+            source,
+        }),
+        BackendKind::Openai => {
+            config.openai.api_key = secrets::api_key("openai")?;
+            Box::new(OpenAiWhisper::new(config.openai, language))
+        }
+        BackendKind::Deepgram => {
+            config.deepgram.api_key = secrets::api_key("deepgram")?;
+            Box::new(Deepgram::new(config.deepgram, language))
+        }
+    })
+}
+
+pub fn selected() -> BackendKind {
+    CONFIG.lock().backend
+}
+
+// The server the OpenAI backend sends audio to.
+pub fn openai_base_url() -> String {
+    CONFIG.lock().openai.base_url.clone()
+}
+
+pub fn cloud_active() -> bool {
+    SESSION
+        .lock()
+        .as_ref()
+        .map_or(false, |session| !session.captures_input)
+}
+
+// The daemon can also stop outside a session, by crashing or from a caller
+// that drives it directly, and its session ends with it.
+fn session(state: &AppState) -> MutexGuard<'static, Option<Session>> {
+    let mut session = SESSION.lock();
+    if session
+        .as_ref()
+        .map_or(false, |session| session.captures_input)
+        && !daemon::is_running(state)
+    {
+        session.take();
+    }
+    session
+}
+
+fn start_session(
+    state: &AppState,
+    kind: BackendKind,
+    mut backend: Box<dyn SpeechBackend>,
+) -> Result<String, AppError> {
+    if self::session(state).is_some() || daemon::is_running(state) {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            i18n::t("daemon-already-running"),
+        ));
+    }
+    let session_id = uuid::Uuid::new_v4().to_string();
+    if backend.captures_input() {
+        backend.start_stream(state, &session_id)?;
+        *SESSION.lock() = Some(Session {
+            backend: Arc::new(Mutex::new(backend)),
+            session_id: daemon::session_id(state).unwrap_or(session_id),
+            captures_input: true,
+        });
+        return Ok(i18n::t("daemon-started"));
+    }
+    let mut converter = Converter::new(SAMPLE_RATE, kind.format())?;
+    backend.start_stream(state, &session_id)?;
+    let backend = Arc::new(Mutex::new(backend));
+    let sink = {
+        let backend = backend.clone();
+        let state = state.clone();
+        // One error per session; the rest would repeat it every callback.
+        let mut reported = false;
+        let mut converted = Vec::new();
+        Box::new(move |samples: &[f32]| {
+            converted.clear();
+            converter.push(samples, &mut converted);
+            if converted.is_empty() {
+                return;
+            }
+            if let Err(error) = backend.lock().push_audio(&converted) {
+                if !reported {
+                    reported = true;
+                    state.bus.publish(AgentEvent::error("stt_backend", error));
+                }
+            }
+        })
+    };
+    if let Err(error) = audio::start_with_sink(state, None, sink) {
+        let _ = backend.lock().finalize();
+        return Err(error);
+    }
+    *SESSION.lock() = Some(Session {
+        backend,
+        session_id,
+        captures_input: false,
+    });
+    audio::set_paused(false);
+    state.bus.publish(AgentEvent::ListeningChanged(true));
+    Ok(i18n::t("daemon-started"))
+}
+
+fn end_session(state: &AppState, session: Session) -> Result<(), AppError> {
+    if session.captures_input {
+        return session.backend.lock().finalize();
+    }
+    let _ = audio::stop(state);
+    let result = session.backend.lock().finalize();
+    state.bus.publish(AgentEvent::SessionEnded {
+        session_id: session.session_id,
+    });
+    result
+}
+
+fn stop_session(state: &AppState, session: Session) -> Result<String, AppError> {
+    let captures_input = session.captures_input;
+    let result = end_session(state, session);
+    if !captures_input {
+        state.bus.publish(AgentEvent::ListeningChanged(false));
+    }
+    result.map(|_| i18n::t("daemon-stopped"))
+}
+
+fn degraded(state: &AppState, kind: BackendKind, error: &AppError, fallback: bool) {
+    tracing::warn!(target: "stt_backend", "Lost the {:?} backend: {}", kind, error);
+    state.bus.publish(AgentEvent::BackendDegraded {
+        backend: kind,
+        reason: error.message.clone(),
+        fallback: if fallback {
+            Some(BackendKind::Local)
+        } else {
+            None
+        },
+    });
+}
+
+// A running cloud session lost its connection. Without `offline_fallback` it
+// keeps reconnecting on its own.
+fn lost(state: &AppState, kind: BackendKind, error: &AppError) {
+    let fallback = config::current().network.offline_fallback;
+    degraded(state, kind, error, fallback);
+    if fallback {
+        let state = state.clone();
+        // Off the backend's own thread, which ending the session waits for.
+        thread::spawn(move || fall_back(&state, kind));
+    }
+}
+
+fn restored(state: &AppState, kind: BackendKind, dropped_ms: u64) {
+    // A session that fell back in the meantime is not the one that came back.
+    if !cloud_active() {
+        return;
+    }
+    tracing::info!(target: "stt_backend", "The {:?} backend is back", kind);
+    state.bus.publish(AgentEvent::BackendRestored {
+        backend: kind,
+        dropped_ms,
+    });
+}
+
+// What the cloud backend still held is dropped with it; dictation carries on
+// without reporting listening as stopped.
+fn fall_back(state: &AppState, kind: BackendKind) {
+    let _switching = SWITCHING.lock();
+    // A local session is already the one standing in.
+    let session = {
+        let mut session = SESSION.lock();
+        match session.take() {
+            Some(cloud) if !cloud.captures_input => cloud,
+            local => {
+                *session = local;
+                return;
+            }
+        }
+    };
+    let _ = end_session(state, session);
+    if let Err(error) = start_fallback(state, kind) {
+        state.bus.publish(AgentEvent::error("stt_backend", error));
+        state.bus.publish(AgentEvent::ListeningChanged(false));
+    }
+}
+
+fn start_fallback(state: &AppState, kind: BackendKind) -> Result<String, AppError> {
+    let local = create(BackendKind::Local, state, InputSource::Microphone)?;
+    *FALLBACK.lock() = Some(kind);
+    match start_session(state, BackendKind::Local, local) {
+        Ok(message) => {
+            tauri::async_runtime::spawn(probe(state.clone(), kind));
+            Ok(message)
+        }
+        Err(error) => {
+            FALLBACK.lock().take();
+            Err(error)
+        }
+    }
+}
+
+// Until the cloud backend answers again; any HTTP response will do.
+async fn probe(state: AppState, kind: BackendKind) {
+    let url = match kind {
+        BackendKind::Openai => openai_base_url(),
+        BackendKind::Deepgram => DEEPGRAM_URL.to_string(),
+        BackendKind::Local => return,
+    };
+    let client = reqwest::Client::new();
+    let mut backoff = Backoff::new(&config::current().network);
+    loop {
+        tokio::time::sleep(backoff.next()).await;
+        if *FALLBACK.lock() != Some(kind) {
+            return;
+        }
+        if client
+            .head(&url)
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await
+            .is_err()
+        {
+            continue;
+        }
+        let restoring = state.clone();
+        match tauri::async_runtime::spawn_blocking(move || restore(&restoring, kind)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => state.bus.publish(AgentEvent::error("stt_backend", error)),
+            Err(e) => state.bus.publish(AgentEvent::error(
+                "stt_backend",
+                format!("Restore task failed: {}", e),
+            )),
+        }
+        return;
+    }
+}
+
+// Hands dictation back to the cloud backend, unless it was stopped in the
+// meantime. If the backend is gone again, the local one carries on.
+fn restore(state: &AppState, kind: BackendKind) -> Result<(), AppError> {
+    let _switching = SWITCHING.lock();
+    if FALLBACK.lock().take() != Some(kind) {
+        return Ok(());
+    }
+    let backend = create(kind, state, InputSource::Microphone)?;
+    if session(state).take().is_none() {
+        return Ok(());
+    }
+    // Not through the stop path, since dictation carries on.
+    daemon::hand_over(state)?;
+    let result = match start_session(state, kind, backend) {
+        Ok(_) => {
+            restored(state, kind, 0);
+            Ok(())
+        }
+        Err(error) if network::is_outage(&error) => {
+            degraded(state, kind, &error, true);
+            start_fallback(state, kind).map(|_| ())
+        }
+        Err(error) => Err(error),
+    };
+    // Nothing took over from the daemon.
+    if result.is_err() {
+        state.bus.publish(AgentEvent::ListeningChanged(false));
+    }
+    result
+}
+
+// What every entry point that starts dictation runs: the selected backend, so
+// callers do not have to know which one it is.
+pub fn start(state: &AppState, source: InputSource) -> Result<String, AppError> {
+    let _switching = SWITCHING.lock();
+    start_selected(state, source)
+}
+
+fn start_selected(state: &AppState, source: InputSource) -> Result<String, AppError> {
+    let kind = selected();
+    let backend = create(kind, state, source)?;
+    if kind == BackendKind::Local {
+        return start_session(state, kind, backend);
+    }
+    if source != InputSource::Microphone {
+        return Err(AppError::new(
+            ErrorCode::Unsupported,
+            "Cloud backends only transcribe the microphone",
+        ));
+    }
+    match start_session(state, kind, backend) {
+        // Starting offline goes straight to the local backend.
+        Err(error) if network::is_outage(&error) && config::current().network.offline_fallback => {
+            degraded(state, kind, &error, true);
+            start_fallback(state, kind)
+        }
+        result => result,
+    }
+}
+
+// Transcribes a whole recording without publishing it, for the
+// OpenAI-compatible endpoint. The local daemon returns its segments; the cloud
+// backends return one transcript for the file.
+pub fn transcribe(
+    state: &AppState,
+    kind: BackendKind,
+    audio: Vec<u8>,
+    file_name: &str,
+) -> Result<Vec<Transcript>, AppError> {
+    let mut config = CONFIG.lock().clone();
+    let language = profiles::stt_language(state);
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (text, confidence, language) = match kind {
+        BackendKind::Local => {
+            let extension = std::path::Path::new(file_name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("wav");
+            let path = std::env::temp_dir().join(format!(
+                "voice-mcp-upload-{}.{}",
+                uuid::Uuid::new_v4(),
+                extension
+            ));
+            std::fs::write(&path, &audio).map_err(|e| {
+                AppError::new(
+                    ErrorCode::Storage,
+                    format!("Failed to save the upload: {}", e),
+                )
+            })?;
+            let result = daemon::transcribe_segments(state, &path);
+            let _ = std::fs::remove_file(&path);
+            return result;
+        }
+        BackendKind::Openai => {
+            config.openai.api_key = secrets::api_key("openai")?;
+            let client = reqwest::Client::new();
+            let text = tauri::async_runtime::block_on(openai_transcribe(
+                &client,
+                &config.openai,
+                language.as_deref(),
+                audio,
+                file_name,
+            ))?;
+            (text, None, language)
+        }
+        BackendKind::Deepgram => {
+            config.deepgram.api_key = secrets::api_key("deepgram")?;
+            tauri::async_runtime::block_on(deepgram_transcribe(
+                &config.deepgram,
+                language.as_deref(),
+                audio,
+            ))?
+        }
+    };
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut transcript = transcript(text, true, confidence, &session_id, None);
+    transcript.language = language;
+    Ok(vec![transcript])
+}
+
+pub fn stop(state: &AppState) -> Result<String, AppError> {
+    let _switching = SWITCHING.lock();
+    stop_current(state)
+}
+
+fn stop_current(state: &AppState) -> Result<String, AppError> {
+    FALLBACK.lock().take();
+    let session = session(state).take();
+    match session {
+        Some(session) => stop_session(state, session),
+        // A daemon the supervisor brought back after a crash has no session.
+        None => daemon::stop(state),
+    }
+}
+
+pub fn is_running(state: &AppState) -> bool {
+    session(state).is_some() || daemon::is_running(state)
+}
+
+// Cloud sessions only take the microphone.
+pub fn input_source(state: &AppState) -> Option<InputSource> {
+    if cloud_active() {
+        Some(InputSource::Microphone)
+    } else {
+        daemon::input_source(state)
+    }
+}
+
+// Whichever backend is listening; transcripts carry it as `session_id`.
+pub fn session_id(state: &AppState) -> Option<String> {
+    match session(state).as_ref() {
+        Some(session) => Some(session.session_id.clone()),
+        None => daemon::session_id(state),
+    }
+}
+
+// Mutes input while the daemon keeps its model loaded. Captured audio is
+// dropped before it reaches any backend, and a daemon reading the microphone
+// itself is told to drop its own. Every new session starts unpaused.
+pub fn set_paused(state: &AppState, paused: bool) -> Result<String, AppError> {
+    if background_core::is_attached() {
+        return background_core::set_paused(paused).map_err(AppError::from);
+    }
+    if !is_running(state) {
+        return Err(AppError::new(
+            ErrorCode::NotRunning,
+            i18n::t("daemon-not-running"),
+        ));
+    }
+    if daemon::is_running(state) {
+        daemon::configure(state, serde_json::json!({ "paused": paused }))?;
+    }
+    if audio::is_paused() != paused {
+        audio::set_paused(paused);
+        state.bus.publish(AgentEvent::SttPaused(paused));
+    }
+    Ok(i18n::t(if paused { "stt-paused" } else { "stt-resumed" }))
+}
+
+pub fn toggle_paused(state: &AppState) -> Result<String, AppError> {
+    set_paused(state, !audio::is_paused())
+}
+
+// Stops whatever is listening, else starts the selected backend. Both happen
+// under one `SWITCHING` guard, so a stop cannot land in between.
+pub fn toggle(state: &AppState) -> Result<String, AppError> {
+    let _switching = SWITCHING.lock();
+    if is_running(state) {
+        stop_current(state)
+    } else {
+        start_selected(state, InputSource::Microphone)
+    }
+}
+
+#[tauri::command]
+pub fn get_stt_backend() -> BackendStatus {
+    let configured = |provider| secrets::api_key(provider).map_or(false, |key| key.is_some());
+    BackendStatus {
+        backend: selected(),
+        openai_configured: configured("openai"),
+        deepgram_configured: configured("deepgram"),
+        active: cloud_active(),
+        fallback: FALLBACK.lock().is_some(),
+    }
+}
+
+// Takes effect from the next start.
+#[tauri::command]
+pub fn set_stt_backend(name: BackendKind) -> CommandResult<()> {
+    let mut config = CONFIG.lock();
+    let mut updated = config.clone();
+    updated.backend = name;
+    store::save_json(STORE_FILE, &updated)?;
+    *config = updated;
+    Ok(())
+}
+
+// For a new model or profile. A running daemon only picks those up on a
+// restart; a cloud session takes the profile's language, as below.
+pub fn restart(state: &AppState) -> Result<(), AppError> {
+    let _switching = SWITCHING.lock();
+    if cloud_active() {
+        set_language(state, profiles::stt_language(state))
+    } else {
+        daemon::restart(state).map(|_| ())
+    }
+}
+
+// The daemon and OpenAI switch from their next chunk, and in-process Whisper
+// reads the profile for every chunk. Deepgram takes it from its next session.
+pub fn set_language(state: &AppState, language: Option<String>) -> Result<(), AppError> {
+    let session = session(state);
+    match session.as_ref() {
+        Some(session) => session.backend.lock().set_language(language.as_deref()),
+        None if daemon::is_running(state) => {
+            daemon::configure(state, serde_json::json!({ "language": language }))
+        }
+        None => Ok(()),
+    }
+}
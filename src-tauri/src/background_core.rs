@@ -0,0 +1,275 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+use crate::audio;
+use crate::bus::AgentEvent;
+use crate::control_socket;
+use crate::daemon::{InputSource, ListenerStatus};
+use crate::error::{AppError, ErrorCode};
+use crate::transcript::Transcript;
+use crate::AppState;
+
+// Set when this process is only the tray/UI for a background core (a
+// `--headless` instance) that owns capture, STT and the local API.
+static ATTACHED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_attached() -> bool {
+    ATTACHED.load(Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+fn open() -> std::io::Result<impl Read + Write> {
+    std::os::unix::net::UnixStream::connect(control_socket::socket_path())
+}
+
+#[cfg(windows)]
+fn open() -> std::io::Result<impl Read + Write> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(control_socket::pipe_name())
+}
+
+// Blocking on purpose so it can back daemon::start/stop from any thread.
+pub fn send(command: &str) -> Result<Value, String> {
+    let mut stream = open().map_err(|e| format!("Background core is unreachable: {}", e))?;
+    let mut request = json!({ "command": command }).to_string();
+    request.push('\n');
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to reach background core: {}", e))?;
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read from background core: {}", e))?;
+    let reply: Value =
+        serde_json::from_str(&line).map_err(|e| format!("Invalid core reply: {}", e))?;
+    if reply["ok"] == json!(true) {
+        Ok(reply["result"].clone())
+    } else {
+        Err(reply["error"]
+            .as_str()
+            .unwrap_or("Background core failed")
+            .to_string())
+    }
+}
+
+pub fn start(source: InputSource) -> Result<String, String> {
+    if source != InputSource::Microphone {
+        return Err("Only the microphone is available through the background core".to_string());
+    }
+    send("start").map(|result| result.as_str().unwrap_or_default().to_string())
+}
+
+pub fn stop() -> Result<String, String> {
+    send("stop").map(|result| result.as_str().unwrap_or_default().to_string())
+}
+
+pub fn set_paused(paused: bool) -> Result<String, String> {
+    send(if paused { "pause" } else { "resume" })
+        .map(|result| result.as_str().unwrap_or_default().to_string())
+}
+
+fn mirror_status(state: &AppState, status: &Value) {
+    let listening = status["listening"].as_bool().unwrap_or(false);
+    audio::set_paused(status["paused"].as_bool().unwrap_or(false));
+    state.status.send_replace(ListenerStatus {
+        listening,
+        source: serde_json::from_value(status["source"].clone()).unwrap_or(None),
+        session_id: status["session_id"].as_str().map(str::to_string),
+    });
+}
+
+// Re-publishes the core's events on the local bus so the tray and UI behave
+// as if the daemon were local.
+fn forward(state: &AppState, line: &str) -> Option<&'static str> {
+    let message: Value = serde_json::from_str(line).ok()?;
+    if message.get("event").is_none() {
+        if message["result"].get("listening").is_some() {
+            mirror_status(state, &message["result"]);
+        }
+        return None;
+    }
+    let payload = message["payload"].clone();
+    let event = match message["event"].as_str()? {
+        "transcript" => {
+            let transcript: Transcript = serde_json::from_value(payload).ok()?;
+            *state.last_transcript.lock() = Some(transcript.clone());
+            AgentEvent::Transcript(transcript)
+        }
+        "listening_changed" => {
+            state
+                .bus
+                .publish(AgentEvent::ListeningChanged(payload.as_bool()?));
+            return Some("status");
+        }
+        "stt_paused" => {
+            let paused = payload.as_bool()?;
+            audio::set_paused(paused);
+            AgentEvent::SttPaused(paused)
+        }
+        "session_ended" => AgentEvent::SessionEnded {
+            session_id: payload["session_id"].as_str()?.to_string(),
+        },
+        "error" => AgentEvent::Error {
+            source: payload["source"].as_str()?.to_string(),
+            error: serde_json::from_value(payload).ok()?,
+        },
+        _ => return None,
+    };
+    state.bus.publish(event);
+    None
+}
+
+pub fn attach(state: &AppState) {
+    ATTACHED.store(true, Ordering::SeqCst);
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        let stream = match control_socket::connect().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                ATTACHED.store(false, Ordering::SeqCst);
+                tracing::error!(target: "core", "Failed to attach to background core: {}", e);
+                return;
+            }
+        };
+        tracing::info!(target: "core", "Attached to background core");
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = tokio::io::BufReader::new(reader).lines();
+        let mut request = b"subscribe\nstatus\n".to_vec();
+        while writer.write_all(&request).await.is_ok() {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    request = match forward(&state, &line) {
+                        Some(command) => format!("{}\n", command).into_bytes(),
+                        None => Vec::new(),
+                    }
+                }
+                _ => break,
+            }
+        }
+        ATTACHED.store(false, Ordering::SeqCst);
+        state.status.send_replace(ListenerStatus::default());
+        state.bus.publish(AgentEvent::ListeningChanged(false));
+        state.bus.publish(AgentEvent::error(
+            "core",
+            AppError::new(
+                ErrorCode::NotRunning,
+                "Lost connection to the background core",
+            ),
+        ));
+    });
+}
+
+// Task names are machine-wide, so each user gets their own.
+#[cfg(windows)]
+fn task_name() -> String {
+    format!("VoiceMcpAgentCore-{}", crate::session::user_name())
+}
+
+#[cfg(windows)]
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(windows)]
+fn schtasks(args: &[&str]) -> Result<String, String> {
+    let output = std::process::Command::new("schtasks")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run schtasks: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() {
+        Ok(stdout)
+    } else {
+        Err(format!(
+            "schtasks failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// A per-user logon task rather than a real service: services run in session 0,
+// which has no access to the user's microphone or desktop.
+#[cfg(windows)]
+pub fn install_login_agent(api: Option<std::net::SocketAddr>) -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate app: {}", e))?;
+    let workdir = std::env::current_dir().map_err(|e| format!("Failed to read cwd: {}", e))?;
+    let user = match (std::env::var("USERDOMAIN"), std::env::var("USERNAME")) {
+        (Ok(domain), Ok(name)) => format!("{}\\{}", domain, name),
+        (_, Ok(name)) => name,
+        _ => return Err("Failed to determine the current user".to_string()),
+    };
+    let mut arguments = "--headless --no-autostart".to_string();
+    if let Some(api) = api {
+        arguments.push_str(&format!(" --api {}", api));
+    }
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <Triggers>
+    <LogonTrigger><Enabled>true</Enabled><UserId>{user}</UserId></LogonTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>{user}</UserId>
+      <LogonType>InteractiveToken</LogonType>
+      <RunLevel>LeastPrivilege</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure><Interval>PT1M</Interval><Count>10</Count></RestartOnFailure>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{exe}</Command>
+      <Arguments>{arguments}</Arguments>
+      <WorkingDirectory>{workdir}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        user = xml_escape(&user),
+        exe = xml_escape(&exe.display().to_string()),
+        arguments = xml_escape(&arguments),
+        workdir = xml_escape(&workdir.display().to_string()),
+    );
+    let path = std::env::temp_dir().join("voice-mcp-agent-task.xml");
+    std::fs::write(&path, xml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    let path = path.display().to_string();
+    let task = task_name();
+    let result = schtasks(&["/Create", "/TN", &task, "/XML", &path, "/F"]);
+    let _ = std::fs::remove_file(&path);
+    result?;
+    schtasks(&["/Run", "/TN", &task])?;
+    Ok(format!("Installed and started logon task {}", task))
+}
+
+#[cfg(windows)]
+pub fn uninstall_login_agent() -> Result<String, String> {
+    let task = task_name();
+    let _ = schtasks(&["/End", "/TN", &task]);
+    schtasks(&["/Delete", "/TN", &task, "/F"])?;
+    Ok(format!("Removed logon task {}", task))
+}
+
+#[cfg(not(windows))]
+pub fn install_login_agent(_api: Option<std::net::SocketAddr>) -> Result<String, String> {
+    Err("The background core login agent is only available on Windows".to_string())
+}
+
+#[cfg(not(windows))]
+pub fn uninstall_login_agent() -> Result<String, String> {
+    Err("The background core login agent is only available on Windows".to_string())
+}
@@ -0,0 +1,236 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::resampler;
+use crate::transcript::Transcript;
+use crate::usage;
+use crate::AppState;
+
+pub const EXTENSIONS: [&str; 5] = ["wav", "mp3", "flac", "ogg", "oga"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileState {
+    Queued,
+    Decoding,
+    Transcribing,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileProgress {
+    pub batch_id: String,
+    pub path: String,
+    // Position in the batch, from 0.
+    pub index: usize,
+    pub total: usize,
+    pub state: FileState,
+    pub transcript: Option<Transcript>,
+    pub error: Option<AppError>,
+}
+
+struct Job {
+    batch_id: String,
+    path: PathBuf,
+    index: usize,
+    total: usize,
+}
+
+// One worker for every batch, so dropping more files queues them behind the
+// ones already running instead of starting more daemons.
+static QUEUE: OnceCell<Mutex<Sender<Job>>> = OnceCell::new();
+
+pub fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map_or(false, |e| {
+            EXTENSIONS.contains(&e.to_ascii_lowercase().as_str())
+        })
+}
+
+fn unreadable(path: &Path, e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::InvalidInput,
+        format!("Cannot decode {}: {}", path.display(), e),
+    )
+}
+
+// Mixed down to mono and resampled to 16 kHz, the rate every backend takes.
+fn decode(path: &Path) -> Result<Vec<f32>, AppError> {
+    let file = File::open(path).map_err(|e| {
+        AppError::new(
+            ErrorCode::NotFound,
+            format!("Cannot open {}: {}", path.display(), e),
+        )
+    })?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| unreadable(path, e))?
+        .format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| unreadable(path, "no audio track"))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| unreadable(path, "unknown sample rate"))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| unreadable(path, e))?;
+
+    let mut mono = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // symphonia reports the end of the stream as an I/O error.
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(DecodeError::ResetRequired) => break,
+            Err(e) => return Err(unreadable(path, e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet is skipped, as players do.
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => return Err(unreadable(path, e)),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        samples.copy_interleaved_ref(decoded);
+        for frame in samples.samples().chunks(channels) {
+            mono.push(frame.iter().sum::<f32>() / channels as f32);
+        }
+    }
+    Ok(resampler::resample(&mono, sample_rate))
+}
+
+// Publishes the file as one final transcript, as `daemon::transcribe_file`
+// does, whichever backend is selected.
+fn transcribe(state: &AppState, path: &Path, samples: &[f32]) -> Result<Transcript, AppError> {
+    let name = path
+        .file_stem()
+        .map_or("audio".into(), |stem| stem.to_string_lossy());
+    let segments = backends::transcribe(
+        state,
+        backends::selected(),
+        backends::wav(samples)?,
+        &format!("{}.wav", name),
+    )?;
+    Ok(daemon::join_segments(state, &segments))
+}
+
+fn progress(
+    state: &AppState,
+    job: &Job,
+    file_state: FileState,
+    result: Option<Result<Transcript, AppError>>,
+) {
+    let (transcript, error) = match result {
+        Some(Ok(transcript)) => (Some(transcript), None),
+        Some(Err(error)) => (None, Some(error)),
+        None => (None, None),
+    };
+    state.bus.publish(AgentEvent::FileProgress(FileProgress {
+        batch_id: job.batch_id.clone(),
+        path: job.path.display().to_string(),
+        index: job.index,
+        total: job.total,
+        state: file_state,
+        transcript,
+        error,
+    }));
+}
+
+fn run(state: &AppState, job: &Job) {
+    progress(state, job, FileState::Decoding, None);
+    let samples = match decode(&job.path) {
+        Ok(samples) => samples,
+        Err(error) => return progress(state, job, FileState::Failed, Some(Err(error))),
+    };
+    progress(state, job, FileState::Transcribing, None);
+    let result = transcribe(state, &job.path, &samples);
+    let file_state = if result.is_ok() {
+        FileState::Done
+    } else {
+        FileState::Failed
+    };
+    progress(state, job, file_state, Some(result));
+}
+
+// Returns the batch id that the progress events carry.
+pub fn queue(state: &AppState, paths: Vec<PathBuf>) -> String {
+    let batch_id = uuid::Uuid::new_v4().to_string();
+    let queue = QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let state = state.clone();
+        thread::spawn(move || {
+            for job in rx {
+                run(&state, &job);
+            }
+        });
+        Mutex::new(tx)
+    });
+    let queue = queue.lock();
+    let total = paths.len();
+    for (index, path) in paths.into_iter().enumerate() {
+        let job = Job {
+            batch_id: batch_id.clone(),
+            path,
+            index,
+            total,
+        };
+        progress(state, &job, FileState::Queued, None);
+        let _ = queue.send(job);
+    }
+    batch_id
+}
+
+#[tauri::command]
+pub fn transcribe_files(
+    state: tauri::State<'_, AppState>,
+    paths: Vec<String>,
+) -> CommandResult<String> {
+    if paths.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "No files to transcribe",
+        ));
+    }
+    usage::record("transcribe_files");
+    Ok(queue(
+        &state,
+        paths.into_iter().map(PathBuf::from).collect(),
+    ))
+}
@@ -0,0 +1,178 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::a11y::Announcement;
+use crate::agents::AgentStatus;
+use crate::audio::{AudioLevel, CaptureInfo};
+use crate::audio_ring::RingStats;
+use crate::backends::BackendKind;
+use crate::batch::FileProgress;
+use crate::config::AppConfig;
+use crate::daemon::{DaemonLog, InputSource};
+use crate::dictation::DictationSession;
+use crate::env::InstallProgress;
+use crate::error::AppError;
+use crate::latency::UtteranceMetrics;
+use crate::lifecycle::OperationProgress;
+use crate::meeting::MeetingSummary;
+use crate::models::DownloadProgress;
+use crate::power::PowerEvent;
+use crate::profiles::SttLanguage;
+use crate::resources::ResourceUsage;
+use crate::startup::StageProgress;
+use crate::text_inject::InjectionOutcome;
+use crate::transcript::Transcript;
+use crate::voice_commands::VoiceMode;
+use crate::window_tracker::FocusedApp;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "payload", rename_all = "snake_case")]
+pub enum AgentEvent {
+    Transcript(Transcript),
+    ListeningChanged(bool),
+    SttPaused(bool),
+    SttLanguageChanged(SttLanguage),
+    ProfileChanged(String),
+    RepeatRequested(Transcript),
+    IntentDetected {
+        intent: String,
+        text: String,
+    },
+    SpeakRequested(String),
+    SpeakingChanged(bool),
+    McpToolCalled {
+        route_id: String,
+        server: String,
+        tool: String,
+        result: String,
+    },
+    VoiceModeChanged(VoiceMode),
+    // A voice command's `event` action.
+    VoiceCommandEvent {
+        name: String,
+        text: String,
+    },
+    ModelChanged {
+        model: String,
+        loaded: bool,
+        reason: String,
+    },
+    ModelDownload(DownloadProgress),
+    SttMetrics(ResourceUsage),
+    LatencyReport(UtteranceMetrics),
+    SessionTagged {
+        session_id: String,
+        title: String,
+    },
+    MeetingPrompt {
+        event_id: String,
+        title: String,
+    },
+    SessionEnded {
+        session_id: String,
+    },
+    // A LiveKit room's transcript, with the identity of whoever spoke.
+    RoomTranscript {
+        room: String,
+        participant: String,
+        transcript: Transcript,
+    },
+    // Everyone else in the room, after someone joins or leaves.
+    RoomParticipants {
+        room: String,
+        participants: Vec<String>,
+    },
+    SessionSummary(DictationSession),
+    MeetingSummary(MeetingSummary),
+    StartupProgress(StageProgress),
+    OperationProgress(OperationProgress),
+    FileProgress(FileProgress),
+    InstallProgress(InstallProgress),
+    // A second launch's command line.
+    ActivationRequested {
+        args: Vec<String>,
+    },
+    PowerChanged(PowerEvent),
+    LanguageChanged(String),
+    Announcement(Announcement),
+    AudioStats {
+        session_id: String,
+        buffer: RingStats,
+    },
+    AudioLevel(AudioLevel),
+    InputDeviceChanged(CaptureInfo),
+    FocusedAppChanged(FocusedApp),
+    InjectionCompleted(InjectionOutcome),
+    SpeechStarted,
+    SpeechEnded,
+    WakeWordDetected {
+        name: String,
+        score: f32,
+    },
+    DaemonLog(DaemonLog),
+    AgentChanged(AgentStatus),
+    DaemonCrashed {
+        session_id: String,
+        exit_code: Option<i32>,
+        source: InputSource,
+    },
+    DaemonRestarting {
+        attempt: u32,
+        delay_ms: u64,
+    },
+    // A cloud backend lost its connection; `fallback` is the backend standing
+    // in for it, if any.
+    BackendDegraded {
+        backend: BackendKind,
+        reason: String,
+        fallback: Option<BackendKind>,
+    },
+    // `dropped_ms` of audio did not fit the outage buffer.
+    BackendRestored {
+        backend: BackendKind,
+        dropped_ms: u64,
+    },
+    ConfigChanged(AppConfig),
+    PttPressed,
+    PttReleased,
+    Error {
+        source: String,
+        #[serde(flatten)]
+        error: AppError,
+    },
+}
+
+impl AgentEvent {
+    pub fn error(source: &str, error: impl Into<AppError>) -> Self {
+        AgentEvent::Error {
+            source: source.to_string(),
+            error: error.into(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<AgentEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self { tx }
+    }
+
+    pub fn publish(&self, event: AgentEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "calendar.json";
+const TICK: Duration = Duration::from_secs(30);
+const LOOKAHEAD_HOURS: i64 = 24;
+// Events already under way by more than this are left alone, so starting the
+// app mid-meeting does not suddenly prompt.
+const START_GRACE_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalDavConfig {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureAction {
+    Prompt,
+    AutoStart,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRule {
+    pub title_contains: String,
+    pub action: CaptureAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    pub enabled: bool,
+    pub ics_url: Option<String>,
+    pub caldav: Option<CalDavConfig>,
+    pub poll_minutes: u64,
+    pub stop_at_end: bool,
+    pub rules: Vec<CaptureRule>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ics_url: None,
+            caldav: None,
+            poll_minutes: 15,
+            stop_at_end: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl CalendarConfig {
+    fn action_for(&self, title: &str) -> Option<CaptureAction> {
+        let title = title.to_lowercase();
+        self.rules
+            .iter()
+            .find(|r| title.contains(&r.title_contains.to_lowercase()))
+            .map(|r| r.action)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Occurrence {
+    pub id: String,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarStatus {
+    pub enabled: bool,
+    pub upcoming: Vec<Occurrence>,
+    pub last_error: Option<String>,
+}
+
+struct Capture {
+    occurrence: Occurrence,
+    session_id: String,
+}
+
+#[derive(Default)]
+struct Shared {
+    upcoming: Mutex<Vec<Occurrence>>,
+    capture: Mutex<Option<Capture>>,
+    last_error: Mutex<Option<String>>,
+}
+
+pub struct CalendarState {
+    config: Mutex<CalendarConfig>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    shared: Arc<Shared>,
+}
+
+impl CalendarState {
+    pub fn load() -> Self {
+        Self {
+            config: Mutex::new(store::load_json(STORE_FILE)),
+            shutdown: Mutex::new(None),
+            shared: Arc::default(),
+        }
+    }
+
+    pub fn apply(&self, state: &AppState) {
+        if let Some(tx) = self.shutdown.lock().take() {
+            let _ = tx.send(());
+        }
+        self.shared.upcoming.lock().clear();
+
+        let config = self.config.lock().clone();
+        if !config.enabled || (config.ics_url.is_none() && config.caldav.is_none()) {
+            return;
+        }
+        let (tx, rx) = oneshot::channel();
+        *self.shutdown.lock() = Some(tx);
+        tauri::async_runtime::spawn(run(config, state.clone(), self.shared.clone(), rx));
+    }
+}
+
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+// Returns `None` for all-day dates, which are never meetings to capture.
+fn parse_time(params: &str, value: &str) -> Option<DateTime<Utc>> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+        return None;
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let tzid = params
+        .split(';')
+        .find_map(|p| p.strip_prefix("TZID="))
+        .map(|tz| tz.trim_matches('"'));
+    // Windows-style zone names are not IANA ids; local time is the best guess.
+    match tzid.and_then(|tz| tz.parse::<chrono_tz::Tz>().ok()) {
+        Some(tz) => tz
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc)),
+        None => Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc)),
+    }
+}
+
+fn expand(
+    dtstart: &(String, String),
+    rrule: &str,
+    window: (DateTime<Utc>, DateTime<Utc>),
+) -> Vec<DateTime<Utc>> {
+    let set: rrule::RRuleSet =
+        match format!("DTSTART{}:{}\nRRULE:{}", dtstart.0, dtstart.1, rrule).parse() {
+            Ok(set) => set,
+            Err(_) => return Vec::new(),
+        };
+    set.after(window.0.with_timezone(&rrule::Tz::UTC))
+        .before(window.1.with_timezone(&rrule::Tz::UTC))
+        .all(50)
+        .dates
+        .into_iter()
+        .map(|t| t.with_timezone(&Utc))
+        .collect()
+}
+
+pub fn parse_ics(ics: &str, window: (DateTime<Utc>, DateTime<Utc>)) -> Vec<Occurrence> {
+    let mut occurrences = Vec::new();
+    let mut event: Option<Vec<(String, String, String)>> = None;
+
+    for line in unfold(ics) {
+        match line.as_str() {
+            "BEGIN:VEVENT" => event = Some(Vec::new()),
+            "END:VEVENT" => {
+                if let Some(props) = event.take() {
+                    occurrences.extend(occurrences_for(&props, window));
+                }
+            }
+            _ => {
+                if let (Some(props), Some((key, value))) = (event.as_mut(), line.split_once(':')) {
+                    let (name, params) = match key.split_once(';') {
+                        Some((name, params)) => (name, format!(";{}", params)),
+                        None => (key, String::new()),
+                    };
+                    props.push((name.to_string(), params, value.to_string()));
+                }
+            }
+        }
+    }
+    occurrences.sort_by_key(|o| o.start);
+    occurrences
+}
+
+fn occurrences_for(
+    props: &[(String, String, String)],
+    window: (DateTime<Utc>, DateTime<Utc>),
+) -> Vec<Occurrence> {
+    let prop = |name: &str| props.iter().find(|(n, _, _)| n == name);
+    let (uid, title) = match (prop("UID"), prop("SUMMARY")) {
+        (Some(uid), Some(summary)) => (uid.2.clone(), unescape(&summary.2)),
+        _ => return Vec::new(),
+    };
+    if prop("STATUS").map(|s| s.2 == "CANCELLED").unwrap_or(false) {
+        return Vec::new();
+    }
+    let dtstart = match prop("DTSTART") {
+        Some((_, params, value)) => (params.clone(), value.clone()),
+        None => return Vec::new(),
+    };
+    let start = match parse_time(&dtstart.0, &dtstart.1) {
+        Some(start) => start,
+        None => return Vec::new(),
+    };
+    let length = prop("DTEND")
+        .and_then(|(_, params, value)| parse_time(params, value))
+        .map(|end| end - start)
+        .filter(|length| *length > chrono::Duration::zero())
+        .unwrap_or_else(|| chrono::Duration::minutes(30));
+
+    let starts = match prop("RRULE") {
+        Some((_, _, rule)) => expand(&dtstart, rule, window),
+        None if start >= window.0 && start < window.1 => vec![start],
+        None => Vec::new(),
+    };
+    starts
+        .into_iter()
+        .map(|start| Occurrence {
+            id: format!("{}@{}", uid, start.timestamp()),
+            title: title.clone(),
+            start,
+            end: start + length,
+        })
+        .collect()
+}
+
+fn caldav_body(window: (DateTime<Utc>, DateTime<Utc>)) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT">
+        <c:time-range start="{}" end="{}"/>
+      </c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+        window.0.format("%Y%m%dT%H%M%SZ"),
+        window.1.format("%Y%m%dT%H%M%SZ")
+    )
+}
+
+// Pulls the VCALENDAR payloads out of a CalDAV multistatus response without
+// a full XML parser; calendar-data is plain escaped text.
+fn calendar_data(multistatus: &str) -> Vec<String> {
+    multistatus
+        .split("BEGIN:VCALENDAR")
+        .skip(1)
+        .filter_map(|chunk| chunk.split("END:VCALENDAR").next())
+        .map(|body| {
+            format!("BEGIN:VCALENDAR{}END:VCALENDAR", body)
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&#13;", "")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+async fn fetch(
+    client: &reqwest::Client,
+    config: &CalendarConfig,
+    window: (DateTime<Utc>, DateTime<Utc>),
+) -> Result<Vec<Occurrence>, String> {
+    let mut occurrences = Vec::new();
+    if let Some(url) = &config.ics_url {
+        let ics = client
+            .get(url.replacen("webcal://", "https://", 1))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch calendar: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read calendar: {}", e))?;
+        occurrences.extend(parse_ics(&ics, window));
+    }
+    if let Some(caldav) = &config.caldav {
+        let method = reqwest::Method::from_bytes(b"REPORT").expect("REPORT is a valid method");
+        let multistatus = client
+            .request(method, &caldav.url)
+            .basic_auth(&caldav.username, Some(&caldav.password))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(caldav_body(window))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to query CalDAV: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read CalDAV response: {}", e))?;
+        for ics in calendar_data(&multistatus) {
+            occurrences.extend(parse_ics(&ics, window));
+        }
+    }
+    occurrences.sort_by_key(|o| o.start);
+    occurrences.dedup_by(|a, b| a.id == b.id);
+    Ok(occurrences)
+}
+
+fn begin_capture(state: &AppState, shared: &Shared, occurrence: Occurrence) -> Result<(), String> {
+    if backends::is_running(state) {
+        return Err("Already listening".to_string());
+    }
+    backends::start(state, InputSource::Microphone)?;
+    let session_id = backends::session_id(state).unwrap_or_default();
+    state.bus.publish(AgentEvent::SessionTagged {
+        session_id: session_id.clone(),
+        title: occurrence.title.clone(),
+    });
+    *shared.capture.lock() = Some(Capture {
+        occurrence,
+        session_id,
+    });
+    Ok(())
+}
+
+async fn run(
+    config: CalendarConfig,
+    state: AppState,
+    shared: Arc<Shared>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let client = reqwest::Client::new();
+    let refresh_every = Duration::from_secs(config.poll_minutes.max(1) * 60);
+    let mut last_refresh: Option<std::time::Instant> = None;
+    let mut handled: HashSet<String> = HashSet::new();
+    let mut ticker = tokio::time::interval(TICK);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = ticker.tick() => {}
+        }
+        let now = Utc::now();
+
+        if last_refresh.map_or(true, |t| t.elapsed() >= refresh_every) {
+            last_refresh = Some(std::time::Instant::now());
+            let window = (
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(LOOKAHEAD_HOURS),
+            );
+            match fetch(&client, &config, window).await {
+                Ok(occurrences) => {
+                    *shared.upcoming.lock() = occurrences;
+                    shared.last_error.lock().take();
+                }
+                Err(message) => {
+                    *shared.last_error.lock() = Some(message.clone());
+                    state.bus.publish(AgentEvent::error("calendar", message));
+                }
+            }
+        }
+
+        if config.stop_at_end {
+            let mut capture = shared.capture.lock();
+            let ended = matches!(&*capture, Some(c) if now >= c.occurrence.end);
+            if ended {
+                let capture = capture.take().unwrap();
+                if backends::session_id(&state).as_deref() == Some(capture.session_id.as_str()) {
+                    let _ = backends::stop(&state);
+                }
+            }
+        }
+
+        let starting: Vec<Occurrence> = shared
+            .upcoming
+            .lock()
+            .iter()
+            .filter(|o| {
+                o.start <= now && now < o.start + chrono::Duration::minutes(START_GRACE_MINUTES)
+            })
+            .filter(|o| !handled.contains(&o.id))
+            .cloned()
+            .collect();
+        for occurrence in starting {
+            handled.insert(occurrence.id.clone());
+            match config.action_for(&occurrence.title) {
+                Some(CaptureAction::AutoStart) => {
+                    if let Err(message) = begin_capture(&state, &shared, occurrence) {
+                        state.bus.publish(AgentEvent::error("calendar", message));
+                    }
+                }
+                Some(CaptureAction::Prompt) => state.bus.publish(AgentEvent::MeetingPrompt {
+                    event_id: occurrence.id,
+                    title: occurrence.title,
+                }),
+                None => {}
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_calendar_config(calendar: tauri::State<'_, CalendarState>) -> CalendarConfig {
+    calendar.config.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_calendar_config(
+    state: tauri::State<'_, AppState>,
+    calendar: tauri::State<'_, CalendarState>,
+    config: CalendarConfig,
+) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *calendar.config.lock() = config;
+    calendar.apply(&state);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_calendar_status(calendar: tauri::State<'_, CalendarState>) -> CalendarStatus {
+    CalendarStatus {
+        enabled: calendar.config.lock().enabled,
+        upcoming: calendar.shared.upcoming.lock().clone(),
+        last_error: calendar.shared.last_error.lock().clone(),
+    }
+}
+
+#[tauri::command]
+pub fn accept_meeting_prompt(
+    state: tauri::State<'_, AppState>,
+    calendar: tauri::State<'_, CalendarState>,
+    event_id: String,
+) -> CommandResult<String> {
+    let occurrence = calendar
+        .shared
+        .upcoming
+        .lock()
+        .iter()
+        .find(|o| o.id == event_id)
+        .cloned()
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorCode::NotFound,
+                format!("No calendar event {}", event_id),
+            )
+        })?;
+    let title = occurrence.title.clone();
+    begin_capture(&state, &calendar.shared, occurrence)?;
+    Ok(format!("Capturing {}", title))
+}
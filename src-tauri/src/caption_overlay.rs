@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use tauri::http::{Request, Response, ResponseBuilder};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Window, WindowBuilder, WindowUrl,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::config;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::AppState;
+
+const LABEL: &str = "captions";
+const PAGE: &str = include_str!("../assets/caption_overlay.html");
+pub const SCHEME: &str = "captions";
+// Of the monitor's width.
+const WIDTH_PERCENT: u32 = 80;
+
+// Saved as `[caption_overlay]` in config.toml.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionOverlayConfig {
+    // A monitor name as `list_monitors` reports it; the primary monitor when
+    // unset or not connected.
+    pub monitor: Option<String>,
+    // Of the caption's background; the text stays opaque.
+    pub opacity: f64,
+    pub font_family: String,
+    pub font_size: u32,
+    // Logical pixels between the captions and the bottom of the monitor.
+    pub bottom_margin: u32,
+    // How long a final caption stays up once speech stops.
+    pub linger_ms: u64,
+}
+
+impl Default for CaptionOverlayConfig {
+    fn default() -> Self {
+        Self {
+            monitor: None,
+            opacity: 0.6,
+            font_family: "system-ui, sans-serif".to_string(),
+            font_size: 28,
+            bottom_margin: 80,
+            linger_ms: 4000,
+        }
+    }
+}
+
+fn failed(e: tauri::Error) -> AppError {
+    AppError::from(format!("Caption overlay failed: {}", e))
+}
+
+// The page is served by the agent, so it needs no frontend build.
+pub fn protocol(
+    _app: &AppHandle,
+    _request: &Request,
+) -> Result<Response, Box<dyn std::error::Error>> {
+    ResponseBuilder::new()
+        .mimetype("text/html")
+        .body(PAGE.as_bytes().to_vec())
+}
+
+fn url() -> WindowUrl {
+    // WebView2 only loads custom schemes through this form.
+    let url = if cfg!(windows) {
+        format!("https://{}.localhost/", SCHEME)
+    } else {
+        format!("{}://localhost/", SCHEME)
+    };
+    WindowUrl::External(url.parse().expect("caption overlay URL is valid"))
+}
+
+fn style(config: &CaptionOverlayConfig) -> String {
+    serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn create(app_handle: &AppHandle) -> Result<Window, AppError> {
+    let config = config::current().caption_overlay;
+    let builder = WindowBuilder::new(app_handle, LABEL, url())
+        .title("Captions")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .focused(false)
+        .visible(false)
+        .initialization_script(&format!("window.__CAPTION_STYLE__ = {};", style(&config)));
+    // A see-through window needs the private API on macOS, so the captions
+    // sit on an opaque strip there.
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+    builder.build().map_err(failed)
+}
+
+pub fn monitor(window: &Window, name: Option<&str>) -> Option<Monitor> {
+    let chosen = name.and_then(|name| {
+        window
+            .available_monitors()
+            .ok()?
+            .into_iter()
+            .find(|monitor| monitor.name().map_or(false, |n| n == name))
+    });
+    chosen.or_else(|| window.primary_monitor().ok().flatten())
+}
+
+// Centred near the bottom of the monitor, tall enough for two lines.
+fn place(window: &Window, config: &CaptionOverlayConfig) -> Result<(), AppError> {
+    let monitor = monitor(window, config.monitor.as_deref())
+        .ok_or_else(|| AppError::new(ErrorCode::Unsupported, "No monitor to show captions on"))?;
+    let scale = monitor.scale_factor();
+    let area = monitor.size();
+    let origin = monitor.position();
+    let width = area.width * WIDTH_PERCENT / 100;
+    let height = (config.font_size as f64 * 3.2 * scale) as u32;
+    let margin = (config.bottom_margin as f64 * scale) as i32;
+    window
+        .set_size(PhysicalSize::new(width, height))
+        .map_err(failed)?;
+    window
+        .set_position(PhysicalPosition::new(
+            origin.x + ((area.width - width) / 2) as i32,
+            origin.y + area.height as i32 - height as i32 - margin,
+        ))
+        .map_err(failed)
+}
+
+fn visible(app_handle: &AppHandle) -> Option<Window> {
+    app_handle
+        .get_window(LABEL)
+        .filter(|window| window.is_visible().unwrap_or(false))
+}
+
+fn push(window: &Window, script: String) {
+    if let Err(e) = window.eval(&script) {
+        tracing::warn!(target: "caption_overlay", "Failed to update captions: {}", e);
+    }
+}
+
+pub fn show(app_handle: &AppHandle) -> Result<(), AppError> {
+    let window = match app_handle.get_window(LABEL) {
+        Some(window) => window,
+        None => create(app_handle)?,
+    };
+    place(&window, &config::current().caption_overlay)?;
+    window.show().map_err(failed)?;
+    // Clicks fall through to whatever is underneath.
+    window.set_ignore_cursor_events(true).map_err(failed)
+}
+
+pub fn toggle(app_handle: &AppHandle) -> Result<(), AppError> {
+    if visible(app_handle).is_some() {
+        hide(app_handle)
+    } else {
+        show(app_handle)
+    }
+}
+
+pub fn hide(app_handle: &AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app_handle.get_window(LABEL) {
+        push(
+            &window,
+            "window.captions && window.captions.clear()".to_string(),
+        );
+        window.hide().map_err(failed)?;
+    }
+    Ok(())
+}
+
+// Partial and final transcripts go to the overlay while it is shown, and a
+// config change restyles and moves it.
+pub fn spawn(app_handle: AppHandle, bus: &EventBus) {
+    let mut events = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::Transcript(transcript)) => {
+                    if let Some(window) = visible(&app_handle) {
+                        let text = serde_json::to_string(&transcript.text).unwrap_or_default();
+                        push(
+                            &window,
+                            format!(
+                                "window.captions && window.captions.show({}, {})",
+                                text, transcript.is_final
+                            ),
+                        );
+                    }
+                }
+                Ok(AgentEvent::ConfigChanged(config)) => {
+                    if let Some(window) = visible(&app_handle) {
+                        if let Err(e) = place(&window, &config.caption_overlay) {
+                            tracing::warn!(target: "caption_overlay", "{}", e);
+                        }
+                        push(
+                            &window,
+                            format!(
+                                "window.captions && window.captions.style({})",
+                                style(&config.caption_overlay)
+                            ),
+                        );
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// Creating a window from a synchronous command deadlocks on Windows.
+#[tauri::command]
+pub async fn show_caption_overlay(app_handle: AppHandle) -> CommandResult<()> {
+    show(&app_handle)
+}
+
+#[tauri::command]
+pub fn hide_caption_overlay(app_handle: AppHandle) -> CommandResult<()> {
+    hide(&app_handle)
+}
+
+// The names `[caption_overlay] monitor` accepts. The overlay window is needed to
+// ask, so it is created hidden if it does not exist yet.
+#[tauri::command]
+pub async fn list_monitors(app_handle: AppHandle) -> CommandResult<Vec<String>> {
+    let window = match app_handle.get_window(LABEL) {
+        Some(window) => window,
+        None => create(&app_handle)?,
+    };
+    Ok(window
+        .available_monitors()
+        .map_err(failed)?
+        .iter()
+        .filter_map(|monitor| monitor.name().cloned())
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_caption_overlay_config() -> CaptionOverlayConfig {
+    config::current().caption_overlay
+}
+
+#[tauri::command]
+pub fn set_caption_overlay_config(
+    state: tauri::State<'_, AppState>,
+    overlay: CaptionOverlayConfig,
+) -> CommandResult<()> {
+    if !(0.0..=1.0).contains(&overlay.opacity) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Caption opacity must be between 0 and 1",
+        ));
+    }
+    if overlay.font_size == 0 {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Caption font size must be positive",
+        ));
+    }
+    config::update(&state, |config| config.caption_overlay = overlay)
+}
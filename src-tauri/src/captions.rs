@@ -0,0 +1,118 @@
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+
+use axum::body::StreamBody;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use crate::bus::AgentEvent;
+use crate::transcript::Transcript;
+use crate::AppState;
+
+const MIN_CUE_DURATION: Duration = Duration::from_millis(1500);
+const MAX_CUE_DURATION: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cue {
+    pub id: String,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/captions.vtt", get(webvtt))
+        .route("/captions/ws", get(upgrade))
+}
+
+// Cue times are relative to when the client connected. A cue becomes visible
+// when its transcript arrives and stays up for as long as the speech lasted.
+fn cue_for(transcript: &Transcript, connected: Instant) -> Cue {
+    let spoken = match (transcript.start_ms, transcript.end_ms) {
+        (Some(start), Some(end)) if end > start => Duration::from_millis(end - start),
+        _ => MIN_CUE_DURATION,
+    };
+    let start = connected.elapsed();
+    let end = start + spoken.clamp(MIN_CUE_DURATION, MAX_CUE_DURATION);
+    Cue {
+        id: transcript.id.clone(),
+        start: start.as_secs_f64(),
+        end: end.as_secs_f64(),
+        text: transcript.text.clone(),
+    }
+}
+
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn format_cue(cue: &Cue) -> String {
+    format!(
+        "{}\n{} --> {}\n{}\n\n",
+        cue.id,
+        vtt_timestamp(cue.start),
+        vtt_timestamp(cue.end),
+        cue.text
+    )
+}
+
+async fn webvtt(State(state): State<AppState>) -> impl IntoResponse {
+    let connected = Instant::now();
+    let header = tokio_stream::once(Ok::<_, Infallible>("WEBVTT\n\n".to_string()));
+    let cues = BroadcastStream::new(state.bus.subscribe()).filter_map(move |event| match event {
+        Ok(AgentEvent::Transcript(t)) if t.is_final => {
+            Some(Ok(format_cue(&cue_for(&t, connected))))
+        }
+        _ => None,
+    });
+    (
+        [
+            (header::CONTENT_TYPE, "text/vtt; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        StreamBody::new(header.chain(cues)),
+    )
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(move |socket| feed(socket, state))
+}
+
+async fn feed(mut socket: WebSocket, state: AppState) {
+    let connected = Instant::now();
+    let mut events = state.bus.subscribe();
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => {
+                    let payload = serde_json::to_string(&cue_for(&t, connected)).unwrap_or_default();
+                    if socket.send(Message::Text(payload)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::{now_millis, Transcript};
+
+const STORE_FILE: &str = "chat_integrations.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatPlatform {
+    Slack,
+    Discord,
+}
+
+impl ChatPlatform {
+    fn max_len(&self) -> usize {
+        match self {
+            ChatPlatform::Slack => 3900,
+            ChatPlatform::Discord => 2000,
+        }
+    }
+
+    fn bold(&self, text: &str) -> String {
+        match self {
+            ChatPlatform::Slack => format!("*{}*", text),
+            ChatPlatform::Discord => format!("**{}**", text),
+        }
+    }
+
+    fn payload(&self, text: String) -> serde_json::Value {
+        match self {
+            ChatPlatform::Slack => json!({ "text": text }),
+            ChatPlatform::Discord => json!({ "content": text }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatIntegration {
+    pub id: String,
+    pub name: String,
+    pub platform: ChatPlatform,
+    pub webhook_url: String,
+    pub post_on_session_end: bool,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SessionDigest {
+    session_id: String,
+    title: Option<String>,
+    started_at: u64,
+    transcripts: Vec<Transcript>,
+    flagged: Vec<String>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    current: Option<SessionDigest>,
+    last: Option<SessionDigest>,
+}
+
+#[derive(Clone, Default)]
+pub struct ChatState {
+    integrations: Arc<Mutex<Vec<ChatIntegration>>>,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl ChatState {
+    pub fn load() -> Self {
+        Self {
+            integrations: Arc::new(Mutex::new(store::load_json(STORE_FILE))),
+            sessions: Arc::default(),
+        }
+    }
+
+    fn integration(&self, id: &str) -> Result<ChatIntegration, String> {
+        self.integrations
+            .lock()
+            .iter()
+            .find(|i| i.id == id)
+            .cloned()
+            .ok_or_else(|| format!("No chat integration with id {}", id))
+    }
+
+    fn record(&self, transcript: Transcript) {
+        let session_id = match &transcript.session_id {
+            Some(session_id) => session_id.clone(),
+            None => return,
+        };
+        let mut sessions = self.sessions.lock();
+        let current = sessions.current.get_or_insert_with(SessionDigest::default);
+        if current.session_id != session_id {
+            *current = SessionDigest {
+                session_id,
+                started_at: transcript.timestamp,
+                ..Default::default()
+            };
+        }
+        current.transcripts.push(transcript);
+    }
+
+    fn tag(&self, session_id: String, title: String) {
+        let mut sessions = self.sessions.lock();
+        match sessions.current.as_mut() {
+            Some(current) if current.session_id == session_id => current.title = Some(title),
+            _ => {
+                sessions.current = Some(SessionDigest {
+                    session_id,
+                    title: Some(title),
+                    started_at: now_millis(),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+
+    fn end_session(&self, session_id: &str) -> Option<SessionDigest> {
+        let mut sessions = self.sessions.lock();
+        match sessions.current.take() {
+            Some(digest) if digest.session_id == session_id => {
+                sessions.last = Some(digest.clone());
+                Some(digest)
+            }
+            other => {
+                sessions.current = other;
+                None
+            }
+        }
+    }
+}
+
+fn truncate(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_len.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn summary(platform: ChatPlatform, digest: &SessionDigest) -> String {
+    let ended_at = digest
+        .transcripts
+        .last()
+        .map(|t| t.timestamp)
+        .unwrap_or_else(now_millis);
+    let minutes = ended_at.saturating_sub(digest.started_at) / 60_000;
+    let words: usize = digest
+        .transcripts
+        .iter()
+        .map(|t| t.text.split_whitespace().count())
+        .sum();
+
+    let heading = match &digest.title {
+        Some(title) => format!("{} ended:", title),
+        None => "Voice session ended:".to_string(),
+    };
+    let mut text = format!(
+        "{} {} min, {} utterances, {} words",
+        platform.bold(&heading),
+        minutes.max(1),
+        digest.transcripts.len(),
+        words
+    );
+    let flagged: Vec<&Transcript> = digest
+        .transcripts
+        .iter()
+        .filter(|t| digest.flagged.contains(&t.id))
+        .collect();
+    if !flagged.is_empty() {
+        text.push_str(&format!("\n\n{}", platform.bold("Flagged")));
+        for transcript in flagged {
+            text.push_str(&format!("\n> {}", transcript.text.trim()));
+        }
+    }
+    let transcript = digest
+        .transcripts
+        .iter()
+        .map(|t| t.text.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !transcript.is_empty() {
+        text.push_str(&format!(
+            "\n\n{}\n{}",
+            platform.bold("Transcript"),
+            transcript
+        ));
+    }
+    text
+}
+
+async fn post(
+    client: &reqwest::Client,
+    integration: &ChatIntegration,
+    text: String,
+) -> Result<(), String> {
+    let text = truncate(&text, integration.platform.max_len());
+    let response = client
+        .post(&integration.webhook_url)
+        .timeout(Duration::from_secs(10))
+        .json(&integration.platform.payload(text))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to post to {}: {}", integration.name, e))?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to post to {}: HTTP {}",
+            integration.name,
+            response.status()
+        ))
+    }
+}
+
+pub fn spawn_dispatcher(state: ChatState, bus: &EventBus) {
+    let mut rx = bus.subscribe();
+    let bus = bus.clone();
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        loop {
+            match rx.recv().await {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => state.record(t),
+                Ok(AgentEvent::SessionTagged { session_id, title }) => state.tag(session_id, title),
+                Ok(AgentEvent::SessionEnded { session_id }) => {
+                    let digest = match state.end_session(&session_id) {
+                        Some(digest) if !digest.transcripts.is_empty() => digest,
+                        _ => continue,
+                    };
+                    let integrations: Vec<ChatIntegration> = state
+                        .integrations
+                        .lock()
+                        .iter()
+                        .filter(|i| i.enabled && i.post_on_session_end)
+                        .cloned()
+                        .collect();
+                    for integration in integrations {
+                        let text = summary(integration.platform, &digest);
+                        if let Err(message) = post(&client, &integration, text).await {
+                            bus.publish(AgentEvent::error("chat", message));
+                        }
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn list_chat_integrations(chat: tauri::State<'_, ChatState>) -> Vec<ChatIntegration> {
+    chat.integrations.lock().clone()
+}
+
+#[tauri::command]
+pub fn add_chat_integration(
+    chat: tauri::State<'_, ChatState>,
+    name: String,
+    platform: ChatPlatform,
+    webhook_url: String,
+    post_on_session_end: bool,
+) -> CommandResult<ChatIntegration> {
+    if !webhook_url.starts_with("https://") {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid webhook URL: {}", webhook_url),
+        ));
+    }
+    let integration = ChatIntegration {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        platform,
+        webhook_url,
+        post_on_session_end,
+        enabled: true,
+    };
+    let mut integrations = chat.integrations.lock();
+    integrations.push(integration.clone());
+    store::save_json(STORE_FILE, &*integrations)?;
+    Ok(integration)
+}
+
+#[tauri::command]
+pub fn remove_chat_integration(chat: tauri::State<'_, ChatState>, id: String) -> CommandResult<()> {
+    let mut integrations = chat.integrations.lock();
+    let before = integrations.len();
+    integrations.retain(|i| i.id != id);
+    if integrations.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No chat integration with id {}", id),
+        ));
+    }
+    store::save_json(STORE_FILE, &*integrations)
+}
+
+#[tauri::command]
+pub fn set_chat_integration_enabled(
+    chat: tauri::State<'_, ChatState>,
+    id: String,
+    enabled: bool,
+) -> CommandResult<()> {
+    let mut integrations = chat.integrations.lock();
+    match integrations.iter_mut().find(|i| i.id == id) {
+        Some(integration) => integration.enabled = enabled,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No chat integration with id {}", id),
+            ))
+        }
+    }
+    store::save_json(STORE_FILE, &*integrations)
+}
+
+// Flags a transcript of the current session (the latest one by default) so
+// it is quoted in the session summary.
+#[tauri::command]
+pub fn flag_transcript(
+    chat: tauri::State<'_, ChatState>,
+    transcript_id: Option<String>,
+) -> CommandResult<()> {
+    let mut sessions = chat.sessions.lock();
+    let current = sessions
+        .current
+        .as_mut()
+        .ok_or_else(|| "No active session".to_string())?;
+    let id = match transcript_id {
+        Some(id) if current.transcripts.iter().any(|t| t.id == id) => id,
+        Some(id) => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No transcript with id {} in this session", id),
+            ))
+        }
+        None => current
+            .transcripts
+            .last()
+            .map(|t| t.id.clone())
+            .ok_or_else(|| "No transcript yet".to_string())?,
+    };
+    if !current.flagged.contains(&id) {
+        current.flagged.push(id);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn post_session_summary(
+    chat: tauri::State<'_, ChatState>,
+    integration_id: String,
+) -> CommandResult<()> {
+    let integration = chat.integration(&integration_id)?;
+    let digest = {
+        let sessions = chat.sessions.lock();
+        sessions
+            .current
+            .clone()
+            .or_else(|| sessions.last.clone())
+            .ok_or_else(|| "No session to summarize".to_string())?
+    };
+    let text = summary(integration.platform, &digest);
+    post(&reqwest::Client::new(), &integration, text)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn post_transcript_snippet(
+    chat: tauri::State<'_, ChatState>,
+    integration_id: String,
+    text: String,
+) -> CommandResult<()> {
+    let integration = chat.integration(&integration_id)?;
+    if text.trim().is_empty() {
+        return Err(AppError::new(ErrorCode::InvalidInput, "Nothing to post"));
+    }
+    let text = format!("> {}", text.trim());
+    post(&reqwest::Client::new(), &integration, text)
+        .await
+        .map_err(AppError::from)
+}
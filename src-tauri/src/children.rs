@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::process::Child;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use sysinfo::{Pid, PidExt, ProcessExt, Signal, System, SystemExt};
+
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown {
+    Graceful,
+    Killed,
+}
+
+// Pids of every child the agent still owns, for the paths where their
+// guards never get dropped: a panic on the main thread and the final exit.
+static LIVE: Lazy<Mutex<HashSet<u32>>> = Lazy::new(Mutex::default);
+
+pub struct Tracked(u32);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        LIVE.lock().remove(&self.0);
+    }
+}
+
+pub fn track(pid: u32) -> Tracked {
+    LIVE.lock().insert(pid);
+    Tracked(pid)
+}
+
+// Owns a child process: dropping it kills and reaps the process, so a
+// daemon can only outlive its handle if the whole agent dies at once.
+pub struct ChildGuard {
+    child: Child,
+    _tracked: Tracked,
+}
+
+impl ChildGuard {
+    pub fn new(child: Child) -> Self {
+        let tracked = track(child.id());
+        Self {
+            child,
+            _tracked: tracked,
+        }
+    }
+}
+
+impl Deref for ChildGuard {
+    type Target = Child;
+
+    fn deref(&self) -> &Child {
+        &self.child
+    }
+}
+
+impl DerefMut for ChildGuard {
+    fn deref_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+}
+
+impl ChildGuard {
+    // SIGTERM first, so the child can finish what it is doing; it is killed
+    // if it is still running after `timeout`, or straight away where there
+    // are no signals.
+    pub fn terminate(&mut self, timeout: Duration) -> std::io::Result<Shutdown> {
+        let pid = Pid::from_u32(self.child.id());
+        let mut system = System::new();
+        let signalled = system.refresh_process(pid)
+            && system
+                .process(pid)
+                .and_then(|process| process.kill_with(Signal::Term))
+                .unwrap_or(false);
+        if signalled {
+            let deadline = Instant::now() + timeout;
+            while Instant::now() < deadline {
+                if self.child.try_wait()?.is_some() {
+                    return Ok(Shutdown::Graceful);
+                }
+                thread::sleep(EXIT_POLL_INTERVAL);
+            }
+        }
+        self.child.kill()?;
+        self.child.wait()?;
+        Ok(Shutdown::Killed)
+    }
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        // Already exited is fine; wait() then just reaps it.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn kill_all() {
+    let pids: Vec<u32> = LIVE.lock().drain().collect();
+    if pids.is_empty() {
+        return;
+    }
+    let mut system = System::new();
+    for pid in pids {
+        let pid = Pid::from_u32(pid);
+        if system.refresh_process(pid) {
+            if let Some(process) = system.process(pid) {
+                process.kill();
+            }
+        }
+    }
+}
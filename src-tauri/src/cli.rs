@@ -0,0 +1,270 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::mdns::MdnsState;
+use crate::soak::{self, SoakOptions};
+use crate::transcript::Transcript;
+use crate::{api, background_core, grpc, instance, mcp, native_messaging, AppState};
+
+#[derive(Parser)]
+#[command(name = "voice-mcp-agent", version, about)]
+pub struct Cli {
+    /// Run capture and STT without any window or tray
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Serve the local HTTP API on this address in headless mode
+    #[arg(long, value_name = "ADDR")]
+    pub api: Option<SocketAddr>,
+
+    /// Serve the gRPC control service on this address in headless mode
+    #[arg(long, value_name = "ADDR")]
+    pub grpc: Option<SocketAddr>,
+
+    /// Wait for a `start` command instead of listening immediately
+    #[arg(long)]
+    pub no_autostart: bool,
+
+    /// Also print interim (non-final) transcripts in headless mode
+    #[arg(long)]
+    pub partials: bool,
+
+    /// Replay this script through the mock STT daemon instead of a real model
+    #[arg(long, value_name = "SCRIPT", global = true)]
+    pub mock_stt: Option<PathBuf>,
+
+    /// Play this WAV or FLAC file in place of the microphone
+    #[arg(long, value_name = "FILE", global = true)]
+    pub simulate_input: Option<PathBuf>,
+
+    /// Playback speed for --simulate-input (2.0 plays twice as fast)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, global = true)]
+    pub simulate_speed: f32,
+
+    /// Audio files to transcribe, handed to the running instance if there is one
+    #[arg(value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Transcribe an audio file and print the text
+    Transcribe { file: PathBuf },
+    /// Start a headless core at every login (Windows), for the tray to attach to
+    InstallLoginAgent {
+        /// Also serve the local HTTP API on this address
+        #[arg(long, value_name = "ADDR")]
+        api: Option<SocketAddr>,
+    },
+    /// Remove the login agent installed by install-login-agent
+    UninstallLoginAgent,
+    /// Listen on simulated audio for hours and report leaks, drift and stalls
+    Soak {
+        /// How long to run, in hours
+        #[arg(long, value_name = "HOURS", default_value_t = 8.0)]
+        hours: f64,
+        /// Seconds between resource samples
+        #[arg(long, value_name = "SECS", default_value_t = 60)]
+        interval: u64,
+        /// Longest listening session, in seconds, before starting a new one
+        #[arg(long, value_name = "SECS", default_value_t = 300)]
+        cycle: u64,
+        /// Where to write the JSON report (default: <data dir>/soak/)
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+    /// Serve the agent's tools to an MCP client over stdin and stdout
+    Mcp,
+    /// Register the browser extension native messaging host
+    InstallNativeHost {
+        /// Chrome/Chromium/Edge extension id allowed to connect
+        #[arg(long = "chrome-extension", value_name = "ID")]
+        chrome_extensions: Vec<String>,
+        /// Firefox extension id allowed to connect
+        #[arg(long = "firefox-extension", value_name = "ID")]
+        firefox_extensions: Vec<String>,
+    },
+}
+
+pub fn run_command(state: &AppState, command: CliCommand) -> i32 {
+    match command {
+        CliCommand::Transcribe { file } => match daemon::transcribe_file(state, &file) {
+            Ok(transcript) => {
+                println!("{}", transcript.text);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+        CliCommand::InstallLoginAgent { api } => {
+            print_result(background_core::install_login_agent(api))
+        }
+        CliCommand::UninstallLoginAgent => print_result(background_core::uninstall_login_agent()),
+        CliCommand::Soak {
+            hours,
+            interval,
+            cycle,
+            report,
+        } => soak::run(
+            state,
+            SoakOptions {
+                duration: Duration::from_secs_f64(hours.max(0.0) * 3600.0),
+                interval: Duration::from_secs(interval.max(1)),
+                cycle: Duration::from_secs(cycle.max(1)),
+                report,
+            },
+        ),
+        CliCommand::Mcp => mcp::run_stdio(state.clone()),
+        CliCommand::InstallNativeHost {
+            chrome_extensions,
+            firefox_extensions,
+        } => match native_messaging::install(&chrome_extensions, &firefox_extensions) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("Wrote {}", path.display());
+                }
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+    }
+}
+
+fn print_result(result: Result<String, String>) -> i32 {
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn handle_control(state: &AppState, line: &str) -> Result<String, String> {
+    match line.trim() {
+        "start" => backends::start(state, InputSource::Microphone).map_err(String::from),
+        "stop" => backends::stop(state).map_err(String::from),
+        "toggle" => backends::toggle(state).map_err(String::from),
+        "status" => Ok(if backends::is_running(state) {
+            "listening".to_string()
+        } else {
+            "idle".to_string()
+        }),
+        "last" => state
+            .last_transcript
+            .lock()
+            .as_ref()
+            .map(|t| t.text.clone())
+            .ok_or_else(|| "No transcript yet".to_string()),
+        "" => Ok(String::new()),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+fn json_line(transcript: &Transcript) -> serde_json::Value {
+    json!({
+        "text": transcript.text,
+        "is_final": transcript.is_final,
+        "timestamp": transcript.timestamp,
+        "start_ms": transcript.start_ms,
+        "end_ms": transcript.end_ms,
+        "confidence": transcript.confidence,
+        "session_id": transcript.session_id,
+        "speaker": transcript.speaker,
+    })
+}
+
+// Transcripts go to stdout as JSON lines; control replies and errors go to
+// stderr so the transcript stream can be piped straight into jq.
+pub fn run_headless(state: AppState, cli: &Cli) -> i32 {
+    instance::mark_headless();
+    tauri::async_runtime::block_on(async move {
+        let mdns = MdnsState::load();
+        if let Some(addr) = cli.api {
+            if let Err(e) = mdns.advertise(addr, false) {
+                eprintln!("{}", e);
+            }
+            let state = state.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = api::serve(state, addr, false, axum_server::Handle::new()).await {
+                    eprintln!("{}", e);
+                }
+            });
+        }
+        let (_grpc_shutdown, grpc_rx) = oneshot::channel();
+        if let Some(addr) = cli.grpc {
+            let state = state.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = grpc::serve(state, addr, grpc_rx).await {
+                    eprintln!("{}", e);
+                }
+            });
+        }
+
+        let mut events = state.bus.subscribe();
+        let partials = cli.partials;
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(AgentEvent::Transcript(t)) if t.is_final || partials => {
+                        println!("{}", json_line(&t))
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        if !cli.no_autostart {
+            if let Err(e) = backends::start(&state, InputSource::Microphone) {
+                eprintln!("{}", e);
+                return 1;
+            }
+        }
+
+        let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdin_open = true;
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                line = stdin.next_line(), if stdin_open => match line {
+                    Ok(Some(line)) if matches!(line.trim(), "quit" | "exit") => break,
+                    Ok(Some(line)) => match handle_control(&state, &line) {
+                        Ok(reply) if !reply.is_empty() => eprintln!("{}", reply),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("error: {}", e),
+                    },
+                    // Keep running detached from a terminal until interrupted.
+                    Ok(None) | Err(_) => stdin_open = false,
+                },
+            }
+        }
+
+        if backends::is_running(&state) {
+            let _ = backends::stop(&state);
+        }
+        mdns.stop();
+        0
+    })
+}
@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::CaptureSource;
+use crate::autotune::ComputeDevice;
+use crate::bus::AgentEvent;
+use crate::caption_overlay::CaptionOverlayConfig;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::network::NetworkConfig;
+use crate::notifications::NotificationPolicy;
+use crate::shortcuts::ShortcutsConfig;
+use crate::stabilizer::PartialsConfig;
+use crate::store;
+use crate::tray_icon::TrayConfig;
+use crate::AppState;
+
+const CONFIG_FILE: &str = "config.toml";
+
+// The hand-editable settings. Everything here is read when it is used, so a
+// change reaches the daemon from its next start.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub python: String,
+    // Defaults to system_stt_daemon.py in the working directory.
+    pub daemon_script: Option<PathBuf>,
+    // Overrides the active profile's model.
+    pub model: Option<String>,
+    // An input device name as `list_audio_devices` reports it; the system
+    // default when unset.
+    pub input_device: Option<String>,
+    pub capture_source: CaptureSource,
+    // What system audio is captured from: an output device on Windows, a
+    // monitor source on Linux, an input device such as BlackHole on macOS.
+    pub system_device: Option<String>,
+    // How long a stopping daemon gets to finish its queued audio before it
+    // is killed.
+    pub stop_timeout_ms: u64,
+    pub compute_device: ComputeDevice,
+    // Labels transcripts with who is speaking; needs pyannote.audio.
+    pub diarization: bool,
+    pub notifications: NotificationPolicy,
+    pub caption_overlay: CaptionOverlayConfig,
+    pub tray: TrayConfig,
+    pub partials: PartialsConfig,
+    pub shortcuts: ShortcutsConfig,
+    pub network: NetworkConfig,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            // Windows installs `python`; `python3` there is a Store shim.
+            python: if cfg!(windows) { "python" } else { "python3" }.to_string(),
+            daemon_script: None,
+            model: None,
+            input_device: None,
+            capture_source: CaptureSource::Microphone,
+            system_device: None,
+            stop_timeout_ms: 5000,
+            compute_device: ComputeDevice::Auto,
+            diarization: false,
+            notifications: NotificationPolicy::default(),
+            caption_overlay: CaptionOverlayConfig::default(),
+            tray: TrayConfig::default(),
+            partials: PartialsConfig::default(),
+            shortcuts: ShortcutsConfig::default(),
+            network: NetworkConfig::default(),
+        }
+    }
+}
+
+static CONFIG: Lazy<Mutex<AppConfig>> = Lazy::new(|| {
+    Mutex::new(read(&path()).unwrap_or_else(|e| {
+        tracing::warn!(target: "config", "{}", e);
+        AppConfig::default()
+    }))
+});
+
+fn path() -> PathBuf {
+    store::data_dir().join(CONFIG_FILE)
+}
+
+// A missing file is the default config; a malformed one is an error, so a
+// typo does not silently reset everything.
+fn read(path: &Path) -> Result<AppConfig, AppError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AppConfig::default()),
+        Err(e) => {
+            return Err(AppError::new(
+                ErrorCode::Storage,
+                format!("Failed to read {}: {}", path.display(), e),
+            ))
+        }
+    };
+    toml::from_str(&contents).map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid {}: {}", path.display(), e),
+        )
+    })
+}
+
+fn write(config: &AppConfig) -> Result<(), AppError> {
+    let storage = |message: String| AppError::new(ErrorCode::Storage, message);
+    let dir = store::data_dir();
+    fs::create_dir_all(&dir)
+        .map_err(|e| storage(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let contents = toml::to_string_pretty(config)
+        .map_err(|e| storage(format!("Failed to serialize {}: {}", CONFIG_FILE, e)))?;
+    fs::write(dir.join(CONFIG_FILE), contents)
+        .map_err(|e| storage(format!("Failed to write {}: {}", CONFIG_FILE, e)))
+}
+
+pub fn current() -> AppConfig {
+    CONFIG.lock().clone()
+}
+
+// Publishes only real changes, so our own writes coming back through the
+// watcher are ignored.
+fn apply(state: &AppState, config: AppConfig) {
+    let mut current = CONFIG.lock();
+    if *current == config {
+        return;
+    }
+    *current = config.clone();
+    drop(current);
+    tracing::info!(target: "config", "Configuration changed");
+    state.bus.publish(AgentEvent::ConfigChanged(config));
+}
+
+fn reload(state: &AppState) {
+    match read(&path()) {
+        Ok(config) => apply(state, config),
+        // Keeps the last good config while the file is being edited.
+        Err(error) => state.bus.publish(AgentEvent::error("config", error)),
+    }
+}
+
+// Watches the directory rather than the file, since editors that save by
+// renaming replace the file being watched.
+pub fn spawn_watcher(state: AppState) {
+    let dir = store::data_dir();
+    if let Err(e) = fs::create_dir_all(&dir) {
+        tracing::warn!(target: "config", "Failed to create {}: {}", dir.display(), e);
+        return;
+    }
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            tracing::warn!(target: "config", "Failed to start the config watcher: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        tracing::warn!(target: "config", "Failed to watch {}: {}", dir.display(), e);
+        return;
+    }
+    thread::spawn(move || {
+        let _watcher = watcher;
+        for event in rx {
+            let event: notify::Event = match event {
+                Ok(event) => event,
+                Err(_) => continue,
+            };
+            let touched = matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) && event
+                .paths
+                .iter()
+                .any(|p| p.file_name().map_or(false, |name| name == CONFIG_FILE));
+            if touched {
+                reload(&state);
+            }
+        }
+    });
+}
+
+// For commands that own one setting; the rest of the file is kept.
+pub fn update(state: &AppState, change: impl FnOnce(&mut AppConfig)) -> Result<(), AppError> {
+    let mut config = current();
+    change(&mut config);
+    write(&config)?;
+    apply(state, config);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_config() -> AppConfig {
+    current()
+}
+
+#[tauri::command]
+pub fn set_config(state: tauri::State<'_, AppState>, config: AppConfig) -> CommandResult<()> {
+    if config.python.trim().is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "The Python interpreter must not be empty",
+        ));
+    }
+    write(&config)?;
+    apply(&state, config);
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+use crate::audio;
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::error::{AppError, ErrorCode};
+use crate::instance;
+use crate::session;
+use crate::usage;
+use crate::AppState;
+
+#[derive(Deserialize)]
+struct Request {
+    command: String,
+    #[serde(default)]
+    files: Vec<PathBuf>,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+fn parse_command(line: &str) -> Result<Request, String> {
+    let line = line.trim();
+    if line.starts_with('{') {
+        serde_json::from_str::<Request>(line).map_err(|e| format!("Invalid request: {}", e))
+    } else {
+        Ok(Request {
+            command: line.to_string(),
+            files: Vec::new(),
+            args: Vec::new(),
+        })
+    }
+}
+
+// Accepts either a bare word per line (`status`) or `{"command": "status"}`,
+// and always answers with a single JSON line.
+fn handle_command(state: &AppState, request: Request) -> Value {
+    let result = match request.command.as_str() {
+        "start" => backends::start(state, InputSource::Microphone).map(Value::from),
+        "stop" => backends::stop(state).map(Value::from),
+        "toggle" => backends::toggle(state).map(Value::from),
+        "pause" => backends::set_paused(state, true).map(Value::from),
+        "resume" => backends::set_paused(state, false).map(Value::from),
+        "status" => Ok(json!({
+            "listening": backends::is_running(state),
+            "source": backends::input_source(state),
+            "session_id": backends::session_id(state),
+            "paused": audio::is_paused(),
+        })),
+        "last-transcript" => Ok(json!(state.last_transcript.lock().clone())),
+        // Sent by a second launch of the app instead of starting another copy.
+        "activate" => {
+            instance::queue_files(state, request.files);
+            state
+                .bus
+                .publish(AgentEvent::ActivationRequested { args: request.args });
+            Ok(json!({ "ui": !instance::is_headless() }))
+        }
+        other => Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Unknown command: {}", other),
+        )),
+    };
+    match result {
+        Ok(result) => json!({ "ok": true, "result": result }),
+        Err(error) => json!({ "ok": false, "error": error.message, "code": error.code }),
+    }
+}
+
+// Starting and stopping wait on the lifecycle queue, so commands run off the
+// connection's task.
+async fn run_command(state: &AppState, request: Request) -> Value {
+    let state = state.clone();
+    tauri::async_runtime::spawn_blocking(move || handle_command(&state, request))
+        .await
+        .unwrap_or_else(|e| json!({ "ok": false, "error": format!("Command task failed: {}", e) }))
+}
+
+async fn next_event(events: &mut Option<Receiver<AgentEvent>>) -> Option<AgentEvent> {
+    let events = match events {
+        Some(events) => events,
+        None => return std::future::pending().await,
+    };
+    loop {
+        match events.recv().await {
+            Ok(event @ AgentEvent::Transcript(_))
+            | Ok(event @ AgentEvent::ListeningChanged(_))
+            | Ok(event @ AgentEvent::SttPaused(_))
+            | Ok(event @ AgentEvent::SessionEnded { .. })
+            | Ok(event @ AgentEvent::Error { .. }) => return Some(event),
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+// After `subscribe`, bus events are interleaved with replies as
+// `{"event": .., "payload": ..}` lines.
+pub async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(stream: S, state: AppState) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let mut events = None;
+    loop {
+        let reply = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => match parse_command(&line) {
+                    Ok(request) if request.command == "subscribe" => {
+                        events = Some(state.bus.subscribe());
+                        json!({ "ok": true, "result": "subscribed" })
+                    }
+                    Ok(request) => {
+                        usage::record("control_socket.command");
+                        run_command(&state, request).await
+                    }
+                    Err(error) => json!({ "ok": false, "error": error }),
+                },
+                _ => break,
+            },
+            event = next_event(&mut events) => match event {
+                Some(event) => json!(event),
+                None => break,
+            },
+        };
+        let mut reply = reply.to_string();
+        reply.push('\n');
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn socket_path() -> std::path::PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(crate::store::data_dir)
+        .join(format!("voice-mcp-{}.sock", session::namespace()))
+}
+
+#[cfg(unix)]
+pub async fn connect() -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(socket_path()).await
+}
+
+#[cfg(unix)]
+pub fn spawn(state: AppState) {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::UnixListener;
+
+    tauri::async_runtime::spawn(async move {
+        let path = socket_path();
+        if tokio::net::UnixStream::connect(&path).await.is_ok() {
+            tracing::info!(
+                "Control socket {} is owned by another instance",
+                path.display()
+            );
+            return;
+        }
+        let _ = std::fs::remove_file(&path);
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Failed to bind control socket {}: {}", path.display(), e);
+                return;
+            }
+        };
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+        while let Ok((stream, _)) = listener.accept().await {
+            tauri::async_runtime::spawn(serve_connection(stream, state.clone()));
+        }
+    });
+}
+
+#[cfg(windows)]
+pub fn pipe_name() -> String {
+    format!(r"\\.\pipe\voice-mcp-{}", session::namespace())
+}
+
+#[cfg(windows)]
+pub async fn connect() -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    tokio::net::windows::named_pipe::ClientOptions::new().open(pipe_name())
+}
+
+#[cfg(windows)]
+pub fn spawn(state: AppState) {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    tauri::async_runtime::spawn(async move {
+        let name = pipe_name();
+        let mut server = match ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(&name)
+        {
+            Ok(server) => server,
+            Err(e) => {
+                tracing::error!("Failed to create control pipe {}: {}", name, e);
+                return;
+            }
+        };
+        loop {
+            if server.connect().await.is_err() {
+                break;
+            }
+            let connected = server;
+            server = match ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(&name)
+            {
+                Ok(server) => server,
+                Err(e) => {
+                    tracing::error!("Failed to create control pipe {}: {}", name, e);
+                    break;
+                }
+            };
+            tauri::async_runtime::spawn(serve_connection(connected, state.clone()));
+        }
+    });
+}
@@ -0,0 +1,324 @@
+use std::backtrace::Backtrace;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::children;
+use crate::daemon::{self, DaemonLog, LogStream};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::logging::{self, LogEntry};
+use crate::store;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const STORE_FILE: &str = "crash.json";
+const LOG_LINES: usize = 100;
+const BUNDLE_LOG_LINES: usize = 2000;
+// Older bundles are deleted as new ones are made.
+const MAX_BUNDLES: usize = 5;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrashConfig {
+    pub upload_enabled: bool,
+    pub upload_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub timestamp: u64,
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub daemon: serde_json::Value,
+    pub logs: Vec<LogEntry>,
+    // The daemon's stderr lines, which are otherwise lost with the process.
+    #[serde(default)]
+    pub daemon_stderr: Vec<DaemonLog>,
+    #[serde(default)]
+    pub acknowledged: bool,
+}
+
+pub fn reports_dir() -> PathBuf {
+    store::data_dir().join("crash-reports")
+}
+
+fn report_path(id: &str) -> Result<PathBuf, AppError> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid crash report id {}", id),
+        ));
+    }
+    Ok(reports_dir().join(format!("{}.json", id)))
+}
+
+fn read_report(path: &Path) -> Option<CrashReport> {
+    let data = std::fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn write_report(report: &CrashReport) -> Result<(), String> {
+    let path = report_path(&report.id)?;
+    let data = serde_json::to_vec_pretty(report)
+        .map_err(|e| format!("Failed to encode crash report: {}", e))?;
+    std::fs::create_dir_all(reports_dir())
+        .and_then(|_| std::fs::write(&path, data))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn daemon_stderr(state: &AppState, limit: usize) -> Vec<DaemonLog> {
+    let mut lines: Vec<DaemonLog> = daemon::recent_logs(state, usize::MAX)
+        .into_iter()
+        .filter(|log| log.stream == LogStream::Stderr)
+        .collect();
+    lines.drain(..lines.len().saturating_sub(limit));
+    lines
+}
+
+// Only Rust panics are captured; native faults in the webview or Python
+// daemon do not run this hook.
+pub fn install(state: AppState) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let report = CrashReport {
+            id: format!("crash-{}", now_millis()),
+            timestamp: now_millis(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("unnamed")
+                .to_string(),
+            message,
+            location: info.location().map(|l| l.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+            daemon: serde_json::to_value(daemon::status(&state)).unwrap_or_default(),
+            logs: logging::recent(LOG_LINES),
+            daemon_stderr: daemon_stderr(&state, LOG_LINES),
+            acknowledged: false,
+        };
+        match write_report(&report) {
+            Ok(()) => tracing::error!("Crash report written to {}", reports_dir().display()),
+            Err(e) => tracing::error!("{}", e),
+        }
+        // Nothing unwinds far enough to drop the child guards once the main
+        // thread is gone.
+        if std::thread::current().name() == Some("main") {
+            children::kill_all();
+        }
+        default_hook(info);
+    }));
+}
+
+pub fn list() -> Vec<CrashReport> {
+    let mut reports: Vec<CrashReport> = std::fs::read_dir(reports_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| read_report(&entry.path()))
+                .collect()
+        })
+        .unwrap_or_default();
+    reports.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    reports
+}
+
+pub fn pending() -> Vec<CrashReport> {
+    list().into_iter().filter(|r| !r.acknowledged).collect()
+}
+
+fn load(id: &str) -> CommandResult<CrashReport> {
+    read_report(&report_path(id)?)
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("No crash report {}", id)))
+}
+
+pub fn bundles_dir() -> PathBuf {
+    store::data_dir().join("support-bundles")
+}
+
+#[derive(Serialize)]
+struct BundleInfo {
+    created: u64,
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    status: daemon::DaemonStatus,
+}
+
+fn app_log(entries: &[LogEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} {} {}: {}\n",
+                entry.timestamp, entry.level, entry.target, entry.message
+            )
+        })
+        .collect()
+}
+
+fn daemon_log(lines: &[DaemonLog]) -> String {
+    lines
+        .iter()
+        .map(|log| format!("{} {} {}\n", log.timestamp, log.session_id, log.line))
+        .collect()
+}
+
+fn prune_bundles() {
+    let mut bundles: Vec<PathBuf> = std::fs::read_dir(bundles_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "zip"))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Named by creation time, so the oldest sort first.
+    bundles.sort();
+    for path in bundles.iter().rev().skip(MAX_BUNDLES) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// The status, recent app logs and daemon stderr, and every crash report, in
+// one zip that can be attached to an issue. Nothing is sent anywhere.
+pub fn create_bundle(state: &AppState) -> Result<PathBuf, AppError> {
+    let created = now_millis();
+    let path = bundles_dir().join(format!("support-bundle-{}.zip", created));
+    let failed = |e: &dyn std::fmt::Display| {
+        AppError::new(
+            ErrorCode::Storage,
+            format!("Failed to write {}: {}", path.display(), e),
+        )
+    };
+    let info = serde_json::to_vec_pretty(&BundleInfo {
+        created,
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        status: daemon::daemon_status(state),
+    })
+    .map_err(|e| failed(&e))?;
+    let mut files = vec![
+        ("info.json".to_string(), info),
+        (
+            "app.log".to_string(),
+            app_log(&logging::recent(BUNDLE_LOG_LINES)).into_bytes(),
+        ),
+        (
+            "daemon-stderr.log".to_string(),
+            daemon_log(&daemon_stderr(state, BUNDLE_LOG_LINES)).into_bytes(),
+        ),
+    ];
+    for report in list() {
+        let data = serde_json::to_vec_pretty(&report).map_err(|e| failed(&e))?;
+        files.push((format!("crash-reports/{}.json", report.id), data));
+    }
+
+    std::fs::create_dir_all(bundles_dir()).map_err(|e| failed(&e))?;
+    let mut zip = ZipWriter::new(File::create(&path).map_err(|e| failed(&e))?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, data) in files {
+        zip.start_file(name, options).map_err(|e| failed(&e))?;
+        zip.write_all(&data).map_err(|e| failed(&e))?;
+    }
+    zip.finish().map_err(|e| failed(&e))?;
+    prune_bundles();
+    tracing::info!(target: "crash", "Support bundle written to {}", path.display());
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn create_support_bundle(state: tauri::State<'_, AppState>) -> CommandResult<String> {
+    let state = state.inner().clone();
+    let path = tauri::async_runtime::spawn_blocking(move || create_bundle(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Support bundle task failed: {}", e)))??;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+pub fn list_crash_reports() -> Vec<CrashReport> {
+    list()
+}
+
+#[tauri::command]
+pub fn acknowledge_crash_report(id: String) -> CommandResult<()> {
+    let mut report = load(&id)?;
+    report.acknowledged = true;
+    write_report(&report).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn export_crash_report(id: String, path: String) -> CommandResult<()> {
+    std::fs::copy(report_path(&id)?, &path)
+        .map(|_| ())
+        .map_err(|e| AppError::from(format!("Failed to export crash report to {}: {}", path, e)))
+}
+
+#[tauri::command]
+pub fn delete_crash_report(id: String) -> CommandResult<()> {
+    std::fs::remove_file(report_path(&id)?)
+        .map_err(|e| AppError::from(format!("Failed to delete crash report {}: {}", id, e)))
+}
+
+#[tauri::command]
+pub fn get_crash_config() -> CrashConfig {
+    store::load_json(STORE_FILE)
+}
+
+#[tauri::command]
+pub fn set_crash_config(config: CrashConfig) -> CommandResult<()> {
+    if let Some(url) = &config.upload_url {
+        if !url.starts_with("https://") {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Crash upload URL must use https: {}", url),
+            ));
+        }
+    }
+    store::save_json(STORE_FILE, &config)
+}
+
+// Uploads are never automatic; the user sends each report explicitly, and
+// only once uploading has been enabled.
+#[tauri::command]
+pub async fn upload_crash_report(id: String) -> CommandResult<()> {
+    let config: CrashConfig = store::load_json(STORE_FILE);
+    let url = match (config.upload_enabled, config.upload_url) {
+        (true, Some(url)) => url,
+        _ => {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Crash report upload is not enabled",
+            ))
+        }
+    };
+    let report = load(&id)?;
+    reqwest::Client::new()
+        .post(&url)
+        .json(&report)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to upload crash report: {}", e))?;
+    Ok(())
+}
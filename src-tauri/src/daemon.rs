@@ -0,0 +1,1099 @@
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStderr, ChildStdout, Command, Output, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::agents::{self, AgentConfig};
+use crate::audio;
+use crate::audio_ring::{self, AudioRing};
+use crate::autotune::{self, Tuning};
+use crate::background_core;
+use crate::bus::AgentEvent;
+use crate::children::{self, ChildGuard, Shutdown};
+use crate::config;
+use crate::daemon_client::{self, DaemonClient, Line, CALL_TIMEOUT};
+use crate::error::{AppError, ErrorCode};
+use crate::i18n;
+use crate::latency::{self, Timings};
+use crate::lifecycle::Operation;
+use crate::meeting;
+use crate::metrics;
+use crate::models::SttBackend;
+use crate::permissions;
+use crate::portal;
+use crate::postprocess;
+use crate::profiles;
+use crate::recordings;
+use crate::redaction;
+use crate::sandbox::{self, ProcessKind};
+use crate::schedule::{self, ScheduleStatus};
+use crate::session;
+use crate::sidecar;
+use crate::simulated_input;
+use crate::supervisor;
+use crate::text_inject;
+use crate::transcript::{now_millis, Transcript, Translation, Word};
+use crate::vad;
+use crate::vocab;
+use crate::voice_commands;
+use crate::AppState;
+
+const RING_STATS_INTERVAL: Duration = Duration::from_secs(1);
+const RECENT_LOGS: usize = 500;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+// Three missed pings; transcribing a long chunk can hold one up briefly.
+const UNRESPONSIVE_AFTER: Duration = Duration::from_secs(6);
+// `start` answers once the model is loaded, which includes downloading it.
+const START_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputSource {
+    Microphone,
+    Stream,
+    Remote,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ListenerStatus {
+    pub listening: bool,
+    pub source: Option<InputSource>,
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonState {
+    Stopped,
+    // Running, but not yet answering pings; usually still loading the model.
+    Starting,
+    Running,
+    Unresponsive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonStatus {
+    pub state: DaemonState,
+    pub session_id: Option<String>,
+    pub uptime_ms: Option<u64>,
+    pub model: Option<String>,
+    // Unix millis of the last answered ping.
+    pub last_heartbeat: Option<u64>,
+    // Crash restarts and explicit restarts since the app started.
+    pub restart_count: u32,
+    pub schedule: ScheduleStatus,
+}
+
+// What the heartbeat has learned about the current daemon.
+#[derive(Debug, Default)]
+pub struct Health {
+    started_at: Option<Instant>,
+    last_pong: Option<Instant>,
+    unresponsive: bool,
+    // Set by a crash, so the start that follows counts as a restart.
+    crashed: bool,
+    restarts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+// A daemon output line that is not part of the JSON protocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonLog {
+    pub timestamp: u64,
+    pub session_id: String,
+    pub stream: LogStream,
+    pub level: String,
+    pub line: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DaemonMessage {
+    Transcript {
+        text: String,
+        confidence: Option<f32>,
+        start: Option<f64>,
+        end: Option<f64>,
+        processing_ms: Option<f64>,
+        timings: Option<Timings>,
+        // The chunk's audio, staged while recordings are on.
+        audio: Option<PathBuf>,
+        language: Option<String>,
+        // A label such as "Speaker 1", while diarizing.
+        speaker: Option<String>,
+        #[serde(default)]
+        words: Vec<DaemonWord>,
+    },
+    Injected {
+        ms: f64,
+    },
+    AudioStatus {
+        status: String,
+    },
+    ModelStatus {
+        status: String,
+        model: String,
+    },
+}
+
+// Seconds from the beginning of the stream or file, as `start` and `end` are.
+#[derive(Deserialize)]
+struct DaemonWord {
+    word: String,
+    start: f64,
+    end: f64,
+    probability: Option<f32>,
+}
+
+fn daemon_command(state: &AppState) -> Command {
+    tuned_command(state, None, None)
+}
+
+// `tuning` stands in for the model's saved tuning during autotune trials, and
+// `agent` overrides the settings for a named agent's daemon.
+fn tuned_command(
+    state: &AppState,
+    tuning: Option<&Tuning>,
+    agent: Option<&AgentConfig>,
+) -> Command {
+    let language = agent
+        .and_then(|agent| agent.language.clone())
+        .or_else(|| profiles::stt_language(state))
+        .unwrap_or_else(|| "auto".to_string());
+    let model = state.models.effective_model(
+        &agent
+            .and_then(|agent| agent.model.clone())
+            .unwrap_or_else(|| profiles::active_model(state)),
+    );
+    let config = state.models.config();
+    let app_config = config::current();
+    let policy = sandbox::policy(ProcessKind::Daemon);
+    let mut command = match (&config.backend, sidecar::daemon()) {
+        (SttBackend::Whisper, Some(sidecar)) => {
+            sandbox::command(ProcessKind::Daemon, &policy, sidecar)
+        }
+        (SttBackend::Whisper, None) => {
+            let mut command = sandbox::command(ProcessKind::Daemon, &policy, &app_config.python);
+            command.arg(
+                app_config
+                    .daemon_script
+                    .unwrap_or_else(|| sidecar::script("system_stt_daemon.py")),
+            );
+            command
+        }
+        (SttBackend::Mock { script }, _) => {
+            let mut command = sandbox::command(ProcessKind::Daemon, &policy, &app_config.python);
+            command
+                .arg(sidecar::script("mock_stt_daemon.py"))
+                .arg("--script")
+                .arg(script);
+            command
+        }
+    };
+    command
+        .arg("--json")
+        .args(["--model", &model, "--language", &language])
+        .args(
+            tuning
+                .cloned()
+                .unwrap_or_else(|| autotune::current(&model))
+                .args(),
+        );
+    if let Some(device) = agent
+        .and_then(|agent| agent.input_device.as_ref())
+        .or(app_config.input_device.as_ref())
+    {
+        command.args(["--input-device", device]);
+    }
+    if let Some(prompt) = vocab::prompt() {
+        command.args(["--initial-prompt", &prompt]);
+    }
+    command.args(vad::capture_params().args());
+    if app_config.diarization || meeting::is_active() {
+        command.arg("--diarize");
+    }
+    if let Some(minutes) = config.idle_unload_minutes {
+        command.args(["--idle-unload", &(u64::from(minutes) * 60).to_string()]);
+    }
+    if portal::injection_active()
+        || text_inject::agent_types()
+        || agent.map_or(false, |agent| !agent.inject)
+    {
+        command.arg("--no-type");
+    }
+    command
+}
+
+// Streamed audio reaches the daemon through a shared ring in the temp dir,
+// which sandboxed daemons can also write.
+fn create_ring() -> std::io::Result<(PathBuf, AudioRing)> {
+    let path = std::env::temp_dir().join(format!(
+        "voice-mcp-{}-{}.ring",
+        session::namespace(),
+        uuid::Uuid::new_v4()
+    ));
+    let ring = AudioRing::create(&path, audio_ring::DEFAULT_CAPACITY)?;
+    Ok((path, ring))
+}
+
+fn spawn(command: &mut Command) -> std::io::Result<Child> {
+    let child = command.spawn()?;
+    sandbox::confine(&sandbox::policy(ProcessKind::Daemon), child.id());
+    Ok(child)
+}
+
+fn output(command: &mut Command) -> std::io::Result<Output> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let child = spawn(command)?;
+    let _tracked = children::track(child.id());
+    child.wait_with_output()
+}
+
+fn parse_line(state: &AppState, line: &str, session_id: Option<&str>) -> Option<Transcript> {
+    let message = match daemon_client::decode(line)? {
+        Line::Message(message) => message,
+        // Only the listening daemon is sent requests.
+        Line::Response(id, result) => {
+            if let Some(client) = state.stt_client.lock().as_ref() {
+                client.resolve(id, result);
+            }
+            return None;
+        }
+    };
+    match serde_json::from_value(message) {
+        Ok(DaemonMessage::Transcript {
+            text,
+            confidence,
+            start,
+            end,
+            processing_ms,
+            timings,
+            audio,
+            language,
+            speaker,
+            words,
+        }) => {
+            if let Some(ms) = processing_ms {
+                metrics::STT_LATENCY.observe(ms / 1000.0);
+            }
+            let origin = start.unwrap_or(0.0);
+            let since = |at: f64| ((at - origin).max(0.0) * 1000.0) as u64;
+            let words = words
+                .into_iter()
+                .map(|word| Word {
+                    word: word.word,
+                    start_ms: since(word.start),
+                    end_ms: since(word.end),
+                    confidence: word.probability,
+                })
+                .collect();
+            let transcript = Transcript {
+                id: uuid::Uuid::new_v4().to_string(),
+                text,
+                is_final: true,
+                timestamp: now_millis(),
+                confidence,
+                session_id: session_id.map(str::to_string),
+                start_ms: start.map(|s| (s * 1000.0) as u64),
+                end_ms: end.map(|s| (s * 1000.0) as u64),
+                speaker,
+                language,
+                translation: None,
+                latency: timings.map(Timings::into_latency),
+                words,
+            };
+            if let Some(audio) = audio {
+                recordings::adopt(&transcript.id, &audio);
+            }
+            Some(transcript)
+        }
+        Ok(DaemonMessage::Injected { ms }) => {
+            latency::record_injection(ms);
+            None
+        }
+        Ok(DaemonMessage::AudioStatus { status }) => {
+            if status.contains("underflow") || status.contains("overflow") {
+                metrics::AUDIO_UNDERRUNS.inc();
+            }
+            None
+        }
+        Ok(DaemonMessage::ModelStatus { status, model }) => {
+            let loaded = status == "loaded";
+            state.models.set_loaded(loaded);
+            state.bus.publish(AgentEvent::ModelChanged {
+                model,
+                loaded,
+                reason: if loaded { "activity" } else { "idle" }.to_string(),
+            });
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+// Room participants are transcribed by workers; a diarized speaker comes
+// from the listening session itself.
+pub fn is_remote_speaker(state: &AppState, transcript: &Transcript) -> bool {
+    transcript.speaker.is_some()
+        && transcript.session_id.is_some()
+        && transcript.session_id != session_id(state)
+}
+
+pub fn publish_transcript(state: &AppState, transcript: Transcript) {
+    if !agents::dictates(state, &transcript) || !voice_commands::route(state, &transcript) {
+        deliver(state, transcript);
+    }
+}
+
+// Files are never commands, so they skip routing.
+fn deliver(state: &AppState, transcript: Transcript) {
+    let started = Instant::now();
+    let mut transcript = match postprocess::run(state, transcript) {
+        Some(transcript) => redaction::run(transcript),
+        None => return,
+    };
+    if let Some(latency) = transcript.latency.as_mut() {
+        latency::finish(latency, started.elapsed());
+    }
+    if transcript.is_final {
+        metrics::UTTERANCES.inc();
+    }
+    *state.last_transcript.lock() = Some(transcript.clone());
+    state
+        .bus
+        .publish(AgentEvent::Transcript(transcript.clone()));
+    latency::report(state, &transcript);
+}
+
+// A missing interpreter is the usual cause; it needs setup, not a retry.
+fn spawn_error(e: std::io::Error) -> AppError {
+    let code = if e.kind() == std::io::ErrorKind::NotFound {
+        ErrorCode::DaemonUnavailable
+    } else {
+        ErrorCode::Failed
+    };
+    let program = match sidecar::daemon() {
+        Some(sidecar) => sidecar.display().to_string(),
+        None => config::current().python,
+    };
+    AppError::new(code, format!("Failed to start STT daemon: {}", e))
+        .with_context("program", program)
+}
+
+// Lifecycle changes go through the state's queue so concurrent callers cannot
+// interleave; the attached background core runs its own.
+pub fn start(state: &AppState, source: InputSource) -> Result<String, AppError> {
+    if background_core::is_attached() {
+        return background_core::start(source).map_err(AppError::from);
+    }
+    state.lifecycle.submit(state, Operation::Start { source })
+}
+
+pub fn stop(state: &AppState) -> Result<String, AppError> {
+    supervisor::cancel_restart();
+    if background_core::is_attached() {
+        return background_core::stop().map_err(AppError::from);
+    }
+    state.lifecycle.submit(state, Operation::Stop)
+}
+
+pub fn hand_over(state: &AppState) -> Result<String, AppError> {
+    supervisor::cancel_restart();
+    if background_core::is_attached() {
+        return background_core::stop().map_err(AppError::from);
+    }
+    state.lifecycle.submit(state, Operation::HandOver)
+}
+
+pub fn restart(state: &AppState) -> Result<String, AppError> {
+    if background_core::is_attached() {
+        return match input_source(state) {
+            Some(source) => background_core::stop()
+                .and_then(|_| background_core::start(source))
+                .map_err(AppError::from),
+            None => Ok(i18n::t("daemon-not-running")),
+        };
+    }
+    state.lifecycle.submit(state, Operation::Restart)
+}
+
+// Only the lifecycle worker calls this.
+pub(crate) fn run(state: &AppState, operation: Operation) -> Result<String, AppError> {
+    match operation {
+        Operation::Start { source } => start_now(state, source),
+        Operation::Stop => stop_now(state, true),
+        Operation::HandOver => stop_now(state, false),
+        Operation::Restart => match input_source(state) {
+            Some(source) => {
+                stop_now(state, true)?;
+                let result = start_now(state, source);
+                metrics::DAEMON_RESTARTS.inc();
+                state.health.lock().restarts += 1;
+                result
+            }
+            None => Ok(i18n::t("daemon-not-running")),
+        },
+    }
+}
+
+fn start_now(state: &AppState, source: InputSource) -> Result<String, AppError> {
+    let mut process_guard = state.stt_process.lock();
+
+    if process_guard.is_some() {
+        let mut error = AppError::new(ErrorCode::AlreadyRunning, i18n::t("daemon-already-running"));
+        if let Some(session_id) = session_id(state) {
+            error = error.with_context("session_id", session_id);
+        }
+        return Err(error);
+    }
+
+    let mut command = daemon_command(state);
+    command
+        .arg("--rpc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = recordings::staging_dir() {
+        command.arg("--save-audio").arg(dir);
+    }
+    let mut ring = None;
+    if source != InputSource::Microphone {
+        let (path, created) = create_ring()
+            .map_err(|e| AppError::from(format!("Failed to create the audio buffer: {}", e)))?;
+        command.args(["--input", "shm", "--ring"]).arg(path);
+        ring = Some(created);
+    } else if let Some(input) = simulated_input::current(state) {
+        command.args(input.daemon_args());
+    } else if state.models.config().backend == SttBackend::Whisper {
+        if let Some(message) = permissions::microphone_error() {
+            return Err(AppError::new(ErrorCode::PermissionDenied, message));
+        }
+    }
+
+    match spawn(&mut command) {
+        Ok(mut child) => {
+            let session_id = uuid::Uuid::new_v4().to_string();
+            if let Some(stdout) = child.stdout.take() {
+                spawn_stdout_reader(stdout, state.clone(), session_id.clone(), None);
+            }
+            if let Some(stderr) = child.stderr.take() {
+                spawn_stderr_reader(stderr, state.clone(), session_id.clone());
+            }
+            {
+                let mut health = state.health.lock();
+                if health.crashed {
+                    health.restarts += 1;
+                }
+                *health = Health {
+                    started_at: Some(Instant::now()),
+                    restarts: health.restarts,
+                    ..Health::default()
+                };
+            }
+            if let Some(stdin) = child.stdin.take() {
+                let client = Arc::new(DaemonClient::new(stdin));
+                *state.stt_client.lock() = Some(client.clone());
+                spawn_heartbeat(state.clone(), session_id.clone(), client);
+            }
+            audio::set_paused(false);
+            let streamed = ring.is_some();
+            *state.stt_ring.lock() = ring;
+            *process_guard = Some(ChildGuard::new(child));
+            state.models.set_loaded(true);
+            state.status.send_replace(ListenerStatus {
+                listening: true,
+                source: Some(source),
+                session_id: Some(session_id.clone()),
+            });
+            if streamed {
+                spawn_ring_stats(state.clone(), session_id);
+            }
+            metrics::DAEMON_STARTS.inc();
+            tracing::info!(target: "daemon", ?source, "STT daemon started");
+            state.bus.publish(AgentEvent::ListeningChanged(true));
+            Ok(i18n::t("daemon-started"))
+        }
+        Err(e) => {
+            let error = spawn_error(e);
+            state
+                .bus
+                .publish(AgentEvent::error("daemon", error.clone()));
+            Err(error)
+        }
+    }
+}
+
+fn stop_now(state: &AppState, report: bool) -> Result<String, AppError> {
+    let mut process_guard = state.stt_process.lock();
+
+    if let Some(mut child) = process_guard.take() {
+        // Closing the ring ends a streamed session's input; the daemon then
+        // transcribes what it has queued, and those transcripts still arrive
+        // before the session ends.
+        state.stt_ring.lock().take();
+        // A daemon too busy to answer is signalled below anyway.
+        let client = state.stt_client.lock().clone();
+        if let Some(client) = client {
+            if let Err(e) = client.call("stop", serde_json::json!({}), CALL_TIMEOUT) {
+                tracing::warn!(target: "daemon", "STT daemon did not take the stop request: {}", e.message);
+            }
+            client.close();
+        }
+        state.stt_client.lock().take();
+        let timeout = Duration::from_millis(config::current().stop_timeout_ms);
+        // A failed wait still ends with the guard killing the daemon.
+        let shutdown = child.terminate(timeout).unwrap_or_else(|e| {
+            tracing::warn!(target: "daemon", "Failed to stop STT daemon cleanly: {}", e);
+            Shutdown::Killed
+        });
+        {
+            let mut health = state.health.lock();
+            health.started_at = None;
+            health.crashed = false;
+        }
+        let ended = state
+            .status
+            .send_replace(ListenerStatus::default())
+            .session_id;
+        if report {
+            if let Some(session_id) = ended {
+                state.bus.publish(AgentEvent::SessionEnded { session_id });
+            }
+            state.bus.publish(AgentEvent::ListeningChanged(false));
+        }
+        tracing::info!(target: "daemon", ?shutdown, "STT daemon stopped");
+        Ok(match shutdown {
+            Shutdown::Graceful => i18n::t("daemon-stopped"),
+            Shutdown::Killed => i18n::t("daemon-killed"),
+        })
+    } else {
+        Err(AppError::new(
+            ErrorCode::NotRunning,
+            i18n::t("daemon-not-running"),
+        ))
+    }
+}
+
+pub fn status(state: &AppState) -> ListenerStatus {
+    state.status.borrow().clone()
+}
+
+pub fn daemon_status(state: &AppState) -> DaemonStatus {
+    let status = self::status(state);
+    let health = state.health.lock();
+    let daemon_state = match (status.listening, health.last_pong) {
+        (false, _) => DaemonState::Stopped,
+        _ if health.unresponsive => DaemonState::Unresponsive,
+        (true, None) => DaemonState::Starting,
+        (true, Some(_)) => DaemonState::Running,
+    };
+    let listening = status.listening;
+    DaemonStatus {
+        state: daemon_state,
+        session_id: status.session_id,
+        uptime_ms: health
+            .started_at
+            .filter(|_| listening)
+            .map(|at| at.elapsed().as_millis() as u64),
+        model: if listening {
+            Some(current_model(state))
+        } else {
+            None
+        },
+        last_heartbeat: health
+            .last_pong
+            .filter(|_| listening)
+            .map(|at| now_millis().saturating_sub(at.elapsed().as_millis() as u64)),
+        restart_count: health.restarts,
+        schedule: schedule::status(),
+    }
+}
+
+// Configures and starts the listening daemon, then pings it. A daemon that
+// stops answering is reported once, not restarted: it may only be stuck on
+// a long chunk.
+fn spawn_heartbeat(state: AppState, session_id: String, client: Arc<DaemonClient>) {
+    thread::spawn(move || {
+        let started = client
+            .negotiate(serde_json::json!({}))
+            .and_then(|_| client.call("start", serde_json::json!({}), START_TIMEOUT));
+        if let Err(e) = started {
+            if e.code == ErrorCode::NotRunning {
+                return;
+            }
+            tracing::warn!(target: "daemon", "STT daemon failed to start: {}", e.message);
+            state.bus.publish(AgentEvent::error(
+                "daemon",
+                e.with_context("session_id", session_id.clone()),
+            ));
+            // A daemon that never started would only sit there.
+            if self::session_id(&state).as_deref() == Some(session_id.as_str()) {
+                let _ = stop(&state);
+            }
+            return;
+        }
+        loop {
+            thread::sleep(HEARTBEAT_INTERVAL);
+            if state.status.borrow().session_id.as_deref() != Some(session_id.as_str()) {
+                break;
+            }
+            match client.call("ping", serde_json::json!({}), HEARTBEAT_INTERVAL) {
+                Ok(_) => {
+                    let mut health = state.health.lock();
+                    health.last_pong = Some(Instant::now());
+                    if health.unresponsive {
+                        health.unresponsive = false;
+                        tracing::info!(target: "daemon", "STT daemon is responding again");
+                    }
+                    continue;
+                }
+                Err(e) if e.code == ErrorCode::NotRunning => break,
+                Err(_) => {}
+            }
+            let mut health = state.health.lock();
+            let late = health
+                .last_pong
+                .map_or(false, |at| at.elapsed() >= UNRESPONSIVE_AFTER);
+            if late && !health.unresponsive {
+                health.unresponsive = true;
+                drop(health);
+                tracing::warn!(target: "daemon", "STT daemon stopped answering heartbeats");
+                state.bus.publish(AgentEvent::error(
+                    "daemon",
+                    AppError::new(ErrorCode::Failed, i18n::t("daemon-unresponsive"))
+                        .with_context("session_id", session_id.clone()),
+                ));
+            }
+        }
+    });
+}
+
+// Changes settings of the listening daemon, e.g. `{ "paused": true }`.
+pub fn configure(state: &AppState, params: serde_json::Value) -> Result<(), AppError> {
+    let client = state
+        .stt_client
+        .lock()
+        .clone()
+        .ok_or_else(|| AppError::new(ErrorCode::NotRunning, i18n::t("daemon-not-running")))?;
+    client.call("configure", params, CALL_TIMEOUT).map(|_| ())
+}
+
+pub fn watch_status(state: &AppState) -> watch::Receiver<ListenerStatus> {
+    state.status.subscribe()
+}
+
+pub fn is_running(state: &AppState) -> bool {
+    state.status.borrow().listening
+}
+
+pub fn input_source(state: &AppState) -> Option<InputSource> {
+    state.status.borrow().source
+}
+
+pub fn session_id(state: &AppState) -> Option<String> {
+    state.status.borrow().session_id.clone()
+}
+
+pub fn push_audio(state: &AppState, pcm: &[u8]) -> Result<(), String> {
+    match state.stt_ring.lock().as_ref() {
+        Some(ring) => {
+            ring.write(pcm);
+            Ok(())
+        }
+        None => Err("STT daemon is not accepting streamed audio".to_string()),
+    }
+}
+
+// Occupancy creeping towards capacity means STT is falling behind the stream.
+fn spawn_ring_stats(state: AppState, session_id: String) {
+    thread::spawn(move || loop {
+        thread::sleep(RING_STATS_INTERVAL);
+        if state.status.borrow().session_id.as_deref() != Some(session_id.as_str()) {
+            break;
+        }
+        let buffer = match state.stt_ring.lock().as_ref() {
+            Some(ring) => ring.stats(),
+            None => break,
+        };
+        state.bus.publish(AgentEvent::AudioStats {
+            session_id: session_id.clone(),
+            buffer,
+        });
+    });
+}
+
+fn run_oneshot(state: &AppState, flag: &str) -> Result<String, String> {
+    let output = output(daemon_command(state).arg(flag))
+        .map_err(|e| format!("Failed to start STT daemon: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().last().unwrap_or("unknown error").to_string());
+    }
+    // Import-time banners share stdout, so the result is the last line.
+    Ok(stdout.lines().last().unwrap_or_default().to_string())
+}
+
+pub fn list_input_devices(state: &AppState) -> Result<Vec<String>, String> {
+    let line = run_oneshot(state, "--list-devices")
+        .map_err(|e| format!("Failed to probe audio devices: {}", e))?;
+    serde_json::from_str(&line).map_err(|e| format!("Invalid device list: {}", e))
+}
+
+pub fn current_model(state: &AppState) -> String {
+    state.models.effective_model(&profiles::active_model(state))
+}
+
+// Downloads the active model's weights without loading them, so the first
+// listen does not wait on the network.
+pub fn prefetch_model(state: &AppState) -> Result<String, String> {
+    run_oneshot(state, "--prefetch").map_err(|e| format!("Failed to fetch model: {}", e))?;
+    Ok(current_model(state))
+}
+
+// Where the active model's weights are cached, without downloading them.
+pub fn check_model(state: &AppState) -> Result<String, String> {
+    run_oneshot(state, "--check-model")
+}
+
+pub fn benchmark_file(
+    state: &AppState,
+    path: &Path,
+    runs: u32,
+    tuning: Option<&Tuning>,
+) -> Result<String, String> {
+    if !path.is_file() {
+        return Err(format!("No such file: {}", path.display()));
+    }
+    let output = output(
+        tuned_command(state, tuning, None)
+            .arg("--benchmark")
+            .arg(path)
+            .args(["--runs", &runs.to_string()]),
+    )
+    .map_err(|e| format!("Failed to start STT daemon: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to benchmark {}: {}",
+            path.display(),
+            stderr.lines().last().unwrap_or("unknown error")
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn transcribe_segments(state: &AppState, path: &Path) -> Result<Vec<Transcript>, AppError> {
+    if !path.is_file() {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No such file: {}", path.display()),
+        ));
+    }
+    let output =
+        output(daemon_command(state).arg("--transcribe-file").arg(path)).map_err(spawn_error)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::from(format!(
+            "Failed to transcribe {}: {}",
+            path.display(),
+            stderr.lines().last().unwrap_or("unknown error")
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| parse_line(state, line, None))
+        .collect())
+}
+
+pub fn transcribe_file(state: &AppState, path: &Path) -> Result<Transcript, AppError> {
+    let segments = transcribe_segments(state, path)?;
+    Ok(join_segments(state, &segments))
+}
+
+// Only when one person spoke throughout; the segments keep their own.
+fn join_speakers(segments: &[Transcript]) -> Option<String> {
+    let first = segments.first()?.speaker.clone()?;
+    if segments
+        .iter()
+        .all(|s| s.speaker.as_deref() == Some(first.as_str()))
+    {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+// Only when every segment was translated, as Whisper's translate task does.
+fn join_translations(segments: &[Transcript]) -> Option<Translation> {
+    let translations: Vec<&Translation> = segments
+        .iter()
+        .map(|s| s.translation.as_ref())
+        .collect::<Option<_>>()?;
+    let first = translations.first()?;
+    Some(Translation {
+        language: first.language.clone(),
+        text: translations
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+    })
+}
+
+// Each segment's words, moved onto the first segment's start.
+fn join_words(segments: &[Transcript]) -> Vec<Word> {
+    let origin = segments.first().and_then(|s| s.start_ms).unwrap_or(0);
+    segments
+        .iter()
+        .flat_map(|s| {
+            let shift = s.start_ms.unwrap_or(origin).saturating_sub(origin);
+            s.words.iter().map(move |word| Word {
+                start_ms: word.start_ms + shift,
+                end_ms: word.end_ms + shift,
+                ..word.clone()
+            })
+        })
+        .collect()
+}
+
+// Publishes the whole file as one final transcript, as a dictated utterance
+// would be.
+pub fn join_segments(state: &AppState, segments: &[Transcript]) -> Transcript {
+    let confidences: Vec<f32> = segments.iter().filter_map(|s| s.confidence).collect();
+    let transcript = Transcript {
+        id: uuid::Uuid::new_v4().to_string(),
+        text: segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        is_final: true,
+        timestamp: now_millis(),
+        confidence: if confidences.is_empty() {
+            None
+        } else {
+            Some(confidences.iter().sum::<f32>() / confidences.len() as f32)
+        },
+        session_id: None,
+        start_ms: segments.first().and_then(|s| s.start_ms),
+        end_ms: segments.last().and_then(|s| s.end_ms),
+        speaker: join_speakers(segments),
+        language: segments.first().and_then(|s| s.language.clone()),
+        translation: join_translations(segments),
+        latency: None,
+        words: join_words(segments),
+    };
+    deliver(state, transcript.clone());
+    transcript
+}
+
+pub struct StreamWorker {
+    child: ChildGuard,
+    ring: AudioRing,
+}
+
+impl StreamWorker {
+    // A ring write cannot fail the way a pipe to a dead process does, so the
+    // worker is checked directly.
+    pub fn push_audio(&mut self, pcm: &[u8]) -> Result<(), String> {
+        if let Ok(Some(status)) = self.child.try_wait() {
+            return Err(format!("STT worker exited with {}", status));
+        }
+        self.ring.write(pcm);
+        Ok(())
+    }
+}
+
+// A stdin-fed daemon outside the main listening slot, for sources with several
+// concurrent speakers that each need their own transcript stream.
+pub fn spawn_worker(
+    state: &AppState,
+    session_id: String,
+    speaker: Option<String>,
+) -> Result<StreamWorker, String> {
+    let (path, ring) =
+        create_ring().map_err(|e| format!("Failed to create the audio buffer: {}", e))?;
+    let mut child = spawn(
+        daemon_command(state)
+            .args(["--input", "shm", "--ring"])
+            .arg(path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )
+    .map_err(|e| format!("Failed to start STT worker: {}", e))?;
+    if let Some(stderr) = child.stderr.take() {
+        spawn_stderr_reader(stderr, state.clone(), session_id.clone());
+    }
+    if let Some(stdout) = child.stdout.take() {
+        spawn_stdout_reader(stdout, state.clone(), session_id, speaker);
+    }
+    Ok(StreamWorker {
+        child: ChildGuard::new(child),
+        ring,
+    })
+}
+
+// A named agent's daemon, listening on its own from the start. It is not
+// sent requests, so it has no heartbeat.
+pub fn spawn_agent(
+    state: &AppState,
+    agent: &AgentConfig,
+    session_id: &str,
+) -> Result<ChildGuard, AppError> {
+    let mut child = spawn(
+        tuned_command(state, None, Some(agent))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )
+    .map_err(spawn_error)?;
+    if let Some(stderr) = child.stderr.take() {
+        spawn_stderr_reader(stderr, state.clone(), session_id.to_string());
+    }
+    if let Some(stdout) = child.stdout.take() {
+        spawn_stdout_reader(stdout, state.clone(), session_id.to_string(), None);
+    }
+    Ok(ChildGuard::new(child))
+}
+
+fn spawn_stdout_reader(
+    stdout: ChildStdout,
+    state: AppState,
+    session_id: String,
+    speaker: Option<String>,
+) {
+    thread::spawn(move || {
+        let _span = tracing::info_span!("daemon", session = %session_id).entered();
+        for line in BufReader::new(stdout).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            // Banners and prints share stdout with the protocol.
+            if !line.trim_start().starts_with('{') {
+                record_log(&state, &session_id, LogStream::Stdout, line);
+                continue;
+            }
+            if let Some(mut transcript) = parse_line(&state, &line, Some(&session_id)) {
+                // The worker's own speaker wins over diarization.
+                if speaker.is_some() {
+                    transcript.speaker = speaker.clone();
+                }
+                publish_transcript(&state, transcript);
+            }
+        }
+        handle_exit(&state, &session_id);
+    });
+}
+
+// Python's default logging format is `LEVEL:logger:message`; anything
+// else on stderr is most likely a traceback.
+fn log_level(line: &str, stream: LogStream) -> &'static str {
+    match line.split(':').next() {
+        Some("DEBUG") => "debug",
+        Some("INFO") => "info",
+        Some("WARNING") => "warn",
+        Some("ERROR") | Some("CRITICAL") => "error",
+        _ if stream == LogStream::Stderr => "warn",
+        _ => "info",
+    }
+}
+
+fn record_log(state: &AppState, session_id: &str, stream: LogStream, line: String) {
+    let level = log_level(&line, stream);
+    match level {
+        "debug" => tracing::debug!(target: "stt", "{}", line),
+        "warn" => tracing::warn!(target: "stt", "{}", line),
+        "error" => tracing::error!(target: "stt", "{}", line),
+        _ => tracing::info!(target: "stt", "{}", line),
+    }
+    let entry = DaemonLog {
+        timestamp: now_millis(),
+        session_id: session_id.to_string(),
+        stream,
+        level: level.to_string(),
+        line,
+    };
+    {
+        let mut logs = state.daemon_logs.lock();
+        if logs.len() == RECENT_LOGS {
+            logs.pop_front();
+        }
+        logs.push_back(entry.clone());
+    }
+    state.bus.publish(AgentEvent::DaemonLog(entry));
+}
+
+// Drained for as long as the daemon runs, so a chatty daemon never blocks
+// on a full pipe.
+fn spawn_stderr_reader(stderr: ChildStderr, state: AppState, session_id: String) {
+    thread::spawn(move || {
+        let _span = tracing::info_span!("daemon", session = %session_id).entered();
+        for line in BufReader::new(stderr).lines() {
+            match line {
+                Ok(line) => record_log(&state, &session_id, LogStream::Stderr, line),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+pub fn recent_logs(state: &AppState, limit: usize) -> Vec<DaemonLog> {
+    let logs = state.daemon_logs.lock();
+    let skip = logs.len().saturating_sub(limit);
+    logs.iter().skip(skip).cloned().collect()
+}
+
+// stdout closing while the session is still current means the daemon died
+// without stop() being called. A clean exit is a simulated input running out.
+fn handle_exit(state: &AppState, session_id: &str) {
+    if agents::exited(state, session_id) {
+        return;
+    }
+    let mut process_guard = state.stt_process.lock();
+    if state.status.borrow().session_id.as_deref() != Some(session_id) {
+        return;
+    }
+    let source = state
+        .status
+        .send_replace(ListenerStatus::default())
+        .source
+        .unwrap_or(InputSource::Microphone);
+    state.stt_ring.lock().take();
+    if let Some(client) = state.stt_client.lock().take() {
+        client.close();
+    }
+    let exit_code = process_guard
+        .take()
+        .and_then(|mut child| child.wait().ok())
+        .and_then(|status| status.code());
+    {
+        let mut health = state.health.lock();
+        health.started_at = None;
+        health.crashed = exit_code != Some(0);
+    }
+    if exit_code != Some(0) {
+        state.bus.publish(AgentEvent::DaemonCrashed {
+            session_id: session_id.to_string(),
+            exit_code,
+            source,
+        });
+    }
+    state.bus.publish(AgentEvent::SessionEnded {
+        session_id: session_id.to_string(),
+    });
+    state.bus.publish(AgentEvent::ListeningChanged(false));
+}
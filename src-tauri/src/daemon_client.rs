@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::ChildStdin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{AppError, ErrorCode};
+use crate::i18n;
+
+// Bumped whenever a daemon speaking the previous version would misread us.
+pub const PROTOCOL_VERSION: u32 = 1;
+// The oldest daemon protocol the agent still speaks.
+const MIN_PROTOCOL_VERSION: u32 = 1;
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Unique across daemons, so a late answer from a stopped one can never be
+// taken for the current one's.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Incoming {
+    Response {
+        id: u64,
+        #[serde(default)]
+        result: Value,
+        error: Option<RpcError>,
+    },
+    Notification {
+        method: String,
+        #[serde(default)]
+        params: Value,
+    },
+}
+
+#[derive(Deserialize)]
+struct Handshake {
+    protocol: u32,
+    daemon: Option<String>,
+}
+
+// One line of daemon output.
+pub enum Line {
+    Response(u64, Result<Value, AppError>),
+    // A notification, or a plain line from a one-shot run, as
+    // `{ "type": ..., ... }`.
+    Message(Value),
+}
+
+pub fn decode(line: &str) -> Option<Line> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    if value.get("jsonrpc").is_none() {
+        return Some(Line::Message(value));
+    }
+    match serde_json::from_value(value).ok()? {
+        Incoming::Response { id, result, error } => Some(Line::Response(
+            id,
+            match error {
+                Some(error) => Err(AppError::new(ErrorCode::Failed, error.message)
+                    .with_context("rpc_code", error.code)),
+                None => Ok(result),
+            },
+        )),
+        Incoming::Notification { method, params } => {
+            let mut message = match params {
+                Value::Object(params) => params,
+                _ => Default::default(),
+            };
+            message.insert("type".to_string(), Value::String(method));
+            Some(Line::Message(Value::Object(message)))
+        }
+    }
+}
+
+// JSON-RPC 2.0 over the listening daemon's stdin, one request per line. The
+// answers arrive on stdout, where the session's reader hands them to
+// `resolve`.
+pub struct DaemonClient {
+    stdin: Mutex<Option<ChildStdin>>,
+    pending: Mutex<HashMap<u64, mpsc::Sender<Result<Value, AppError>>>>,
+}
+
+impl DaemonClient {
+    pub fn new(stdin: ChildStdin) -> Self {
+        Self {
+            stdin: Mutex::new(Some(stdin)),
+            pending: Mutex::default(),
+        }
+    }
+
+    fn send(&self, message: &Value) -> Result<(), AppError> {
+        let mut stdin = self.stdin.lock();
+        let stdin = stdin
+            .as_mut()
+            .ok_or_else(|| AppError::new(ErrorCode::NotRunning, i18n::t("daemon-not-running")))?;
+        writeln!(stdin, "{}", message)
+            .and_then(|_| stdin.flush())
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::DaemonUnavailable,
+                    format!("Failed to reach the STT daemon: {}", e),
+                )
+            })
+    }
+
+    pub fn call(&self, method: &str, params: Value, timeout: Duration) -> Result<Value, AppError> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let (answer, answered) = mpsc::channel();
+        self.pending.lock().insert(id, answer);
+        let result = self
+            .send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .and_then(|_| match answered.recv_timeout(timeout) {
+                Ok(result) => result,
+                Err(RecvTimeoutError::Timeout) => Err(AppError::new(
+                    ErrorCode::DaemonUnavailable,
+                    format!(
+                        "The STT daemon did not answer {} within {} ms",
+                        method,
+                        timeout.as_millis()
+                    ),
+                )),
+                Err(RecvTimeoutError::Disconnected) => Err(AppError::new(
+                    ErrorCode::NotRunning,
+                    i18n::t("daemon-not-running"),
+                )),
+            });
+        self.pending.lock().remove(&id);
+        result.map_err(|error| error.with_context("method", method.to_string()))
+    }
+
+    pub fn resolve(&self, id: u64, result: Result<Value, AppError>) {
+        if let Some(answer) = self.pending.lock().remove(&id) {
+            let _ = answer.send(result);
+        }
+    }
+
+    // The first `configure` carries the agent's protocol version, and the
+    // daemon answers with the one it will speak.
+    pub fn negotiate(&self, mut params: Value) -> Result<u32, AppError> {
+        params["protocol"] = PROTOCOL_VERSION.into();
+        let handshake: Handshake =
+            serde_json::from_value(self.call("configure", params, CALL_TIMEOUT)?).map_err(|e| {
+                AppError::new(
+                    ErrorCode::Unsupported,
+                    format!("The STT daemon does not negotiate a protocol: {}", e),
+                )
+            })?;
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&handshake.protocol) {
+            return Err(AppError::new(
+                ErrorCode::Unsupported,
+                format!(
+                    "The STT daemon speaks protocol {}, but the agent needs {} to {}",
+                    handshake.protocol, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+                ),
+            )
+            .with_context("daemon", handshake.daemon.unwrap_or_default()));
+        }
+        tracing::info!(
+            target: "daemon",
+            protocol = handshake.protocol,
+            daemon = handshake.daemon.as_deref().unwrap_or("unknown"),
+            "STT daemon connected"
+        );
+        Ok(handshake.protocol)
+    }
+
+    // Closing stdin tells the daemon the agent has gone; waiting calls fail
+    // straight away.
+    pub fn close(&self) {
+        self.stdin.lock().take();
+        self.pending.lock().clear();
+    }
+}
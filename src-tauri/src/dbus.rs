@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{dbus_interface, fdo, ConnectionBuilder, SignalContext};
+
+use crate::audio;
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::error::AppError;
+use crate::AppState;
+
+const NAME: &str = "org.voicemcp.Agent";
+const PATH: &str = "/org/voicemcp/Agent";
+
+struct Agent {
+    state: AppState,
+}
+
+// The snake_case name serde gives a unit variant.
+fn name(value: impl Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+// D-Bus has no field for the code, so it ends the message, e.g.
+// "STT daemon is not running (not_running)".
+fn failed(error: AppError) -> fdo::Error {
+    fdo::Error::Failed(format!("{} ({})", error.message, name(error.code)))
+}
+
+impl Agent {
+    // Starting and stopping wait on the lifecycle queue, so they are kept off
+    // the bus connection's executor.
+    async fn run(&self, action: fn(&AppState) -> Result<String, AppError>) -> fdo::Result<String> {
+        let state = self.state.clone();
+        tauri::async_runtime::spawn_blocking(move || action(&state))
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("D-Bus task failed: {}", e)))?
+            .map_err(failed)
+    }
+}
+
+#[dbus_interface(name = "org.voicemcp.Agent")]
+impl Agent {
+    async fn start(&self) -> fdo::Result<String> {
+        self.run(|state| backends::start(state, InputSource::Microphone))
+            .await
+    }
+
+    async fn stop(&self) -> fdo::Result<String> {
+        self.run(backends::stop).await
+    }
+
+    async fn toggle(&self) -> fdo::Result<String> {
+        self.run(backends::toggle).await
+    }
+
+    // `listening` and `paused` always; `source` and `session_id` while
+    // listening.
+    fn get_status(&self) -> HashMap<String, OwnedValue> {
+        let mut status = HashMap::new();
+        status.insert(
+            "listening".to_string(),
+            Value::from(backends::is_running(&self.state)).into(),
+        );
+        status.insert("paused".to_string(), Value::from(audio::is_paused()).into());
+        if let Some(source) = backends::input_source(&self.state) {
+            status.insert("source".to_string(), Value::from(name(source)).into());
+        }
+        if let Some(session_id) = backends::session_id(&self.state) {
+            status.insert("session_id".to_string(), Value::from(session_id).into());
+        }
+        status
+    }
+
+    #[dbus_interface(signal)]
+    async fn transcript_ready(
+        context: &SignalContext<'_>,
+        id: &str,
+        text: &str,
+    ) -> zbus::Result<()>;
+}
+
+async fn serve(state: AppState) -> zbus::Result<()> {
+    let mut events = state.bus.subscribe();
+    let connection = ConnectionBuilder::session()?
+        .name(NAME)?
+        .serve_at(PATH, Agent { state })?
+        .build()
+        .await?;
+    tracing::info!(target: "dbus", "Serving {} on the session bus", NAME);
+    let context = SignalContext::new(&connection, PATH)?;
+    loop {
+        match events.recv().await {
+            Ok(AgentEvent::Transcript(transcript)) if transcript.is_final => {
+                if let Err(e) =
+                    Agent::transcript_ready(&context, &transcript.id, &transcript.text).await
+                {
+                    tracing::warn!(target: "dbus", "TranscriptReady not sent: {}", e);
+                }
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+    Ok(())
+}
+
+// Without a session bus, or with the name already owned by another
+// instance, the rest of the app carries on without it.
+pub fn spawn(state: AppState) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(state).await {
+            tracing::warn!(target: "dbus", "D-Bus interface unavailable: {}", e);
+        }
+    });
+}
@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nnnoiseless::DenoiseState;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandResult;
+use crate::store;
+
+const STORE_FILE: &str = "noise_suppression.json";
+// RNNoise runs on 10 ms frames at 48 kHz, three times the capture rate.
+const RATIO: usize = 3;
+const FRAME: usize = DenoiseState::FRAME_SIZE / RATIO;
+// RNNoise works on samples at 16-bit scale.
+const SCALE: f32 = i16::MAX as f32;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseSuppressionConfig {
+    pub enabled: bool,
+}
+
+static CONFIG: Lazy<Mutex<NoiseSuppressionConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// Mirrors the config for the audio thread, which should not take locks.
+static ENABLED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(CONFIG.lock().enabled));
+
+// Sits in front of the VAD gate, so steady noise such as fans and key clicks
+// stops looking like speech. Samples are delayed by at most one frame.
+pub struct Denoiser {
+    state: Option<Box<DenoiseState<'static>>>,
+    frame: Vec<f32>,
+    upsampled: Vec<f32>,
+    denoised: Vec<f32>,
+    previous: f32,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            state: None,
+            frame: Vec::with_capacity(FRAME),
+            upsampled: Vec::with_capacity(DenoiseState::FRAME_SIZE),
+            denoised: vec![0.0; DenoiseState::FRAME_SIZE],
+            previous: 0.0,
+        }
+    }
+}
+
+impl Denoiser {
+    // Appends the cleaned-up samples to `out`, or the samples as they are
+    // while suppression is off.
+    pub fn process(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            self.state = None;
+            out.append(&mut self.frame);
+            out.extend_from_slice(samples);
+            return;
+        }
+        let state = self.state.get_or_insert_with(DenoiseState::new);
+        for &sample in samples {
+            self.frame.push(sample);
+            if self.frame.len() < FRAME {
+                continue;
+            }
+            // Up by interpolation and back down by averaging; speech has
+            // nothing above 8 kHz for either to lose.
+            self.upsampled.clear();
+            for &sample in &self.frame {
+                for step in 1..=RATIO {
+                    let t = step as f32 / RATIO as f32;
+                    self.upsampled
+                        .push((self.previous + (sample - self.previous) * t) * SCALE);
+                }
+                self.previous = sample;
+            }
+            state.process_frame(&mut self.denoised, &self.upsampled);
+            out.extend(
+                self.denoised
+                    .chunks(RATIO)
+                    .map(|chunk| chunk.iter().sum::<f32>() / (RATIO as f32 * SCALE)),
+            );
+            self.frame.clear();
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_noise_suppression() -> bool {
+    CONFIG.lock().enabled
+}
+
+// Applies to running capture straight away.
+#[tauri::command]
+pub fn set_noise_suppression(enabled: bool) -> CommandResult<()> {
+    let config = NoiseSuppressionConfig { enabled };
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
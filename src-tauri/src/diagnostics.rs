@@ -0,0 +1,340 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::audio;
+use crate::backends::{self, BackendKind};
+use crate::config;
+use crate::daemon;
+use crate::env;
+use crate::error::{AppError, CommandResult};
+use crate::i18n;
+use crate::models::SttBackend;
+use crate::permissions::{self, Permission, PermissionState};
+use crate::secrets;
+use crate::sidecar;
+use crate::AppState;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const DEEPGRAM_URL: &str = "https://api.deepgram.com";
+// Where faster-whisper fetches a model it has not cached.
+const MODEL_HUB_URL: &str = "https://huggingface.co";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckId {
+    Python,
+    DaemonScript,
+    Microphone,
+    AudioDevices,
+    Model,
+    Network,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    // Dictation works, but not as well or not yet, e.g. before the model is
+    // downloaded.
+    Warning,
+    Failed,
+    // Not needed with the current settings.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub id: CheckId,
+    pub status: CheckStatus,
+    // What was found, in English, so it can be pasted into a bug report.
+    pub detail: String,
+    // What to do about it, in the UI's language.
+    pub fix: Option<String>,
+    // The command that fixes it, for a button next to the suggestion.
+    pub action: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    // Nothing failed.
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Check {
+    fn ok(id: CheckId, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+            action: None,
+        }
+    }
+
+    fn skipped(id: CheckId, detail: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Skipped,
+            ..Self::ok(id, detail)
+        }
+    }
+
+    fn problem(
+        id: CheckId,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        fix: &str,
+        action: Option<&'static str>,
+    ) -> Self {
+        Self {
+            id,
+            status,
+            detail: detail.into(),
+            fix: Some(i18n::t(fix)),
+            action,
+        }
+    }
+}
+
+// With a cloud backend the daemon is not needed to dictate, so its problems
+// only warn.
+fn daemon_severity() -> CheckStatus {
+    if backends::selected() == BackendKind::Local {
+        CheckStatus::Failed
+    } else {
+        CheckStatus::Warning
+    }
+}
+
+fn python() -> Check {
+    if let Some(sidecar) = sidecar::daemon() {
+        return Check::skipped(
+            CheckId::Python,
+            format!(
+                "The bundled daemon at {} has its own Python",
+                sidecar.display()
+            ),
+        );
+    }
+    let python = config::current().python;
+    match env::configured() {
+        None => Check::problem(
+            CheckId::Python,
+            daemon_severity(),
+            format!("{} does not run", python),
+            "diagnostics-fix-python",
+            Some("select_python_env"),
+        ),
+        Some(env) if !env.missing_packages.is_empty() => Check::problem(
+            CheckId::Python,
+            daemon_severity(),
+            format!(
+                "Python {} at {} is missing {}",
+                env.version,
+                env.path,
+                env.missing_packages.join(", ")
+            ),
+            "diagnostics-fix-packages",
+            Some("install_daemon_deps"),
+        ),
+        Some(env) => Check::ok(
+            CheckId::Python,
+            format!("Python {} at {}", env.version, env.path),
+        ),
+    }
+}
+
+fn daemon_script(state: &AppState) -> Check {
+    if let Some(sidecar) = sidecar::daemon() {
+        return Check::ok(
+            CheckId::DaemonScript,
+            format!("Bundled daemon at {}", sidecar.display()),
+        );
+    }
+    let script = match state.models.config().backend {
+        SttBackend::Mock { .. } => sidecar::script("mock_stt_daemon.py"),
+        SttBackend::Whisper => config::current()
+            .daemon_script
+            .unwrap_or_else(|| sidecar::script("system_stt_daemon.py")),
+    };
+    if script.is_file() {
+        Check::ok(CheckId::DaemonScript, script.display().to_string())
+    } else {
+        Check::problem(
+            CheckId::DaemonScript,
+            daemon_severity(),
+            format!("No daemon script at {}", script.display()),
+            "diagnostics-fix-daemon-script",
+            None,
+        )
+    }
+}
+
+fn microphone() -> Check {
+    if let Some(error) = permissions::microphone_error() {
+        return Check::problem(
+            CheckId::Microphone,
+            CheckStatus::Failed,
+            error,
+            "diagnostics-fix-microphone",
+            Some("open_permission_settings"),
+        );
+    }
+    match permissions::state(Permission::Microphone) {
+        PermissionState::NotDetermined => Check::problem(
+            CheckId::Microphone,
+            CheckStatus::Warning,
+            "Microphone access has not been asked for yet",
+            "diagnostics-fix-microphone-ask",
+            Some("check_mic_permission"),
+        ),
+        PermissionState::Unsupported => Check::ok(
+            CheckId::Microphone,
+            "This platform does not gate the microphone",
+        ),
+        _ => Check::ok(CheckId::Microphone, "Microphone access is granted"),
+    }
+}
+
+fn audio_devices() -> Check {
+    match audio::list_devices() {
+        Err(error) => Check::problem(
+            CheckId::AudioDevices,
+            CheckStatus::Failed,
+            error.message,
+            "diagnostics-fix-audio-devices",
+            None,
+        ),
+        Ok(devices) if devices.is_empty() => Check::problem(
+            CheckId::AudioDevices,
+            CheckStatus::Failed,
+            "No input devices were found",
+            "diagnostics-fix-audio-devices",
+            None,
+        ),
+        Ok(devices) => {
+            let default = devices
+                .iter()
+                .find(|device| device.is_default)
+                .map_or("none", |device| device.name.as_str());
+            Check::ok(
+                CheckId::AudioDevices,
+                format!(
+                    "{} input devices; the default is {}",
+                    devices.len(),
+                    default
+                ),
+            )
+        }
+    }
+}
+
+// A missing model only warns: the daemon downloads it on the first listen.
+fn model(state: &AppState, daemon_works: bool) -> Check {
+    if backends::selected() != BackendKind::Local {
+        return Check::skipped(CheckId::Model, "A cloud backend is selected");
+    }
+    if !daemon_works {
+        return Check::skipped(CheckId::Model, "The daemon cannot start");
+    }
+    let model = daemon::current_model(state);
+    match daemon::check_model(state) {
+        Ok(path) => Check::ok(CheckId::Model, format!("{} is cached at {}", model, path)),
+        Err(error) => Check::problem(
+            CheckId::Model,
+            CheckStatus::Warning,
+            error,
+            "diagnostics-fix-model",
+            None,
+        ),
+    }
+}
+
+// Any HTTP response counts; only the connection is being checked. A cloud
+// backend also needs its key.
+async fn network(model_missing: bool) -> Check {
+    let (provider, url) = match backends::selected() {
+        BackendKind::Openai => (Some("openai"), backends::openai_base_url()),
+        BackendKind::Deepgram => (Some("deepgram"), DEEPGRAM_URL.to_string()),
+        BackendKind::Local if model_missing => (None, MODEL_HUB_URL.to_string()),
+        BackendKind::Local => {
+            return Check::skipped(CheckId::Network, "The local backend works offline")
+        }
+    };
+    if let Some(provider) = provider {
+        let detail = match secrets::api_key(provider) {
+            Ok(Some(_)) => None,
+            Ok(None) => Some(format!("No {} API key is stored", provider)),
+            Err(error) => Some(error.message),
+        };
+        if let Some(detail) = detail {
+            return Check::problem(
+                CheckId::Network,
+                CheckStatus::Failed,
+                detail,
+                "diagnostics-fix-api-key",
+                Some("store_api_key"),
+            );
+        }
+    }
+    match reqwest::Client::new()
+        .head(&url)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(_) => Check::ok(CheckId::Network, format!("Reached {}", url)),
+        Err(e) => Check::problem(
+            CheckId::Network,
+            if provider.is_some() {
+                CheckStatus::Failed
+            } else {
+                CheckStatus::Warning
+            },
+            format!("Could not reach {}: {}", url, e),
+            "diagnostics-fix-network",
+            None,
+        ),
+    }
+}
+
+fn local_checks(state: &AppState) -> Vec<Check> {
+    let mut checks = vec![
+        python(),
+        daemon_script(state),
+        microphone(),
+        audio_devices(),
+    ];
+    let daemon_works = checks
+        .iter()
+        .filter(|check| check.id == CheckId::Python || check.id == CheckId::DaemonScript)
+        .all(|check| check.status == CheckStatus::Ok || check.status == CheckStatus::Skipped);
+    checks.push(model(state, daemon_works));
+    checks
+}
+
+pub async fn run(state: AppState) -> Result<DiagnosticsReport, AppError> {
+    let mut checks = tauri::async_runtime::spawn_blocking(move || local_checks(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Diagnostics task failed: {}", e)))?;
+    let model_missing = checks
+        .iter()
+        .any(|check| check.id == CheckId::Model && check.status == CheckStatus::Warning);
+    checks.push(network(model_missing).await);
+    Ok(DiagnosticsReport {
+        ready: checks
+            .iter()
+            .all(|check| check.status != CheckStatus::Failed),
+        checks,
+    })
+}
+
+// Every check runs even after one fails, so the first run can list all that
+// is left to set up.
+#[tauri::command]
+pub async fn run_diagnostics(
+    state: tauri::State<'_, AppState>,
+) -> CommandResult<DiagnosticsReport> {
+    run(state.inner().clone()).await
+}
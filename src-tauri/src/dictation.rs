@@ -0,0 +1,182 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::history::{self, HistoryEntry};
+use crate::meeting::MeetingSummary;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const DEFAULT_LIMIT: u32 = 50;
+
+// A named stretch of dictation. Unlike a daemon session it survives stops and
+// restarts, so one meeting or document can span several.
+#[derive(Debug, Clone, Serialize)]
+pub struct DictationSession {
+    pub id: String,
+    pub name: String,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    // So far, while the session is open.
+    pub duration_ms: u64,
+    pub word_count: u64,
+    pub transcript_count: u64,
+}
+
+impl DictationSession {
+    pub fn update_duration(&mut self) {
+        let end = self.ended_at.unwrap_or_else(now_millis);
+        self.duration_ms = end.saturating_sub(self.started_at);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDetail {
+    #[serde(flatten)]
+    pub session: DictationSession,
+    pub transcripts: Vec<HistoryEntry>,
+    // From meeting mode; empty for other sessions.
+    pub summaries: Vec<MeetingSummary>,
+}
+
+static ACTIVE: Lazy<Mutex<Option<DictationSession>>> = Lazy::new(|| Mutex::new(None));
+
+fn blocking_error(e: impl std::fmt::Display) -> AppError {
+    AppError::from(format!("Session task failed: {}", e))
+}
+
+// Runs next to the history recorder, so the attached tray counts nothing
+// either.
+pub fn spawn(bus: &EventBus) {
+    if let Err(e) = history::close_open_sessions() {
+        tracing::warn!(target: "dictation", "{}", e);
+    }
+    let mut rx = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(AgentEvent::Transcript(transcript)) if transcript.is_final => {
+                    let session = {
+                        let mut active = ACTIVE.lock();
+                        match active.as_mut() {
+                            Some(session) => {
+                                session.word_count +=
+                                    transcript.text.split_whitespace().count() as u64;
+                                session.transcript_count += 1;
+                                session.clone()
+                            }
+                            None => continue,
+                        }
+                    };
+                    let result = tauri::async_runtime::spawn_blocking(move || {
+                        history::link_transcript(&session, &transcript.id)
+                    })
+                    .await;
+                    if let Ok(Err(e)) = result {
+                        tracing::warn!(target: "dictation", "{}", e);
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+pub fn start(name: &str) -> Result<DictationSession, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "A session needs a name",
+        ));
+    }
+    let mut active = ACTIVE.lock();
+    if let Some(session) = active.as_ref() {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            format!("Session {} is already running", session.name),
+        )
+        .with_context("session_id", session.id.clone()));
+    }
+    let session = DictationSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        started_at: now_millis(),
+        ended_at: None,
+        duration_ms: 0,
+        word_count: 0,
+        transcript_count: 0,
+    };
+    history::save_session(&session)?;
+    *active = Some(session.clone());
+    Ok(session)
+}
+
+pub fn end(bus: &EventBus) -> Result<DictationSession, AppError> {
+    let mut active = ACTIVE.lock();
+    let mut session = active
+        .clone()
+        .ok_or_else(|| AppError::new(ErrorCode::NotRunning, "No session is running"))?;
+    session.ended_at = Some(now_millis());
+    session.update_duration();
+    history::save_session(&session)?;
+    *active = None;
+    bus.publish(AgentEvent::SessionSummary(session.clone()));
+    Ok(session)
+}
+
+pub fn current() -> Option<DictationSession> {
+    let mut session = ACTIVE.lock().clone()?;
+    session.update_duration();
+    Some(session)
+}
+
+#[tauri::command]
+pub async fn start_session(name: String) -> CommandResult<DictationSession> {
+    tauri::async_runtime::spawn_blocking(move || start(&name))
+        .await
+        .map_err(blocking_error)?
+}
+
+#[tauri::command]
+pub async fn end_session(state: tauri::State<'_, AppState>) -> CommandResult<DictationSession> {
+    let bus = state.bus.clone();
+    tauri::async_runtime::spawn_blocking(move || end(&bus))
+        .await
+        .map_err(blocking_error)?
+}
+
+#[tauri::command]
+pub fn get_current_session() -> Option<DictationSession> {
+    current()
+}
+
+#[tauri::command]
+pub async fn list_sessions(
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> CommandResult<Vec<DictationSession>> {
+    tauri::async_runtime::spawn_blocking(move || {
+        history::sessions(limit.unwrap_or(DEFAULT_LIMIT), offset.unwrap_or(0))
+    })
+    .await
+    .map_err(blocking_error)?
+}
+
+#[tauri::command]
+pub async fn get_session(id: String) -> CommandResult<SessionDetail> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (session, transcripts) = history::get_session(&id)?;
+        Ok(SessionDetail {
+            summaries: history::summaries(&id)?,
+            session,
+            transcripts,
+        })
+    })
+    .await
+    .map_err(blocking_error)?
+}
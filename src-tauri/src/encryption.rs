@@ -0,0 +1,219 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::history;
+use crate::recordings;
+use crate::secrets;
+use crate::store;
+
+const STORE_FILE: &str = "encryption.json";
+// Marks a sealed column value; anything without it is read as it is.
+const TEXT_PREFIX: &str = "enc1:";
+// Starts every sealed file, ahead of the nonce.
+const FILE_MAGIC: &[u8; 4] = b"VMA1";
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    pub enabled: bool,
+}
+
+// What a migration rewrote; data already in the wanted form is left alone.
+#[derive(Debug, Clone, Serialize)]
+pub struct Migration {
+    pub enabled: bool,
+    pub transcripts: usize,
+    pub recordings: usize,
+}
+
+static CONFIG: Lazy<Mutex<EncryptionConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// Read from the keychain on first use.
+static KEY: Lazy<Mutex<Option<LessSafeKey>>> = Lazy::new(|| Mutex::new(None));
+// Only one migration at a time.
+static MIGRATING: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+
+fn failed(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorCode::Storage, message)
+}
+
+fn parse(stored: &str) -> Result<LessSafeKey, AppError> {
+    let bytes = hex::decode(stored.trim())
+        .ok()
+        .filter(|bytes| bytes.len() == KEY_LEN)
+        .ok_or_else(|| failed("The encryption key in the system keychain is not valid"))?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| failed("The encryption key in the system keychain is not valid"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+// A key is only created when encryption is turned on; reading without one
+// means the keychain lost it.
+fn load(create: bool) -> Result<LessSafeKey, AppError> {
+    if let Some(stored) = secrets::storage_key()? {
+        return parse(&stored);
+    }
+    if !create {
+        return Err(failed(
+            "The encryption key is missing from the system keychain",
+        ));
+    }
+    let mut bytes = [0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| failed("Failed to generate an encryption key"))?;
+    let stored = hex::encode(bytes);
+    secrets::set_storage_key(&stored)?;
+    parse(&stored)
+}
+
+fn with_key<T>(
+    create: bool,
+    f: impl FnOnce(&LessSafeKey) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let mut key = KEY.lock();
+    if let Some(key) = key.as_ref() {
+        return f(key);
+    }
+    let loaded = load(create)?;
+    f(key.insert(loaded))
+}
+
+pub fn enabled() -> bool {
+    CONFIG.lock().enabled
+}
+
+// The nonce, then the ciphertext and its tag.
+fn seal(plain: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| failed("Failed to generate a nonce"))?;
+    let mut sealed = plain.to_vec();
+    with_key(false, |key| {
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut sealed,
+        )
+        .map_err(|_| failed("Failed to encrypt"))
+    })?;
+    Ok(nonce.iter().copied().chain(sealed).collect())
+}
+
+fn open(sealed: &[u8]) -> Result<Vec<u8>, AppError> {
+    let undecryptable = || failed("Failed to decrypt; the encryption key may have changed");
+    if sealed.len() < NONCE_LEN {
+        return Err(undecryptable());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    nonce_bytes.copy_from_slice(nonce);
+    let mut buffer = ciphertext.to_vec();
+    with_key(false, |key| {
+        key.open_in_place(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::empty(),
+            &mut buffer,
+        )
+        .map(|plain| plain.to_vec())
+        .map_err(|_| undecryptable())
+    })
+}
+
+pub fn is_sealed_text(stored: &str) -> bool {
+    stored.starts_with(TEXT_PREFIX)
+}
+
+pub fn seal_text(text: &str) -> Result<String, AppError> {
+    Ok(format!(
+        "{}{}",
+        TEXT_PREFIX,
+        hex::encode(seal(text.as_bytes())?)
+    ))
+}
+
+pub fn open_text(stored: &str) -> Result<String, AppError> {
+    let sealed = match stored.strip_prefix(TEXT_PREFIX) {
+        Some(sealed) => sealed,
+        None => return Ok(stored.to_string()),
+    };
+    let bytes = hex::decode(sealed).map_err(|e| failed(format!("Not encrypted text: {}", e)))?;
+    String::from_utf8(open(&bytes)?)
+        .map_err(|e| failed(format!("Decrypted text is not UTF-8: {}", e)))
+}
+
+// Sealed when encryption is on; what is stored goes through as it is.
+pub fn store_text(text: &str) -> Result<String, AppError> {
+    if enabled() {
+        seal_text(text)
+    } else {
+        Ok(text.to_string())
+    }
+}
+
+pub fn is_sealed_file(bytes: &[u8]) -> bool {
+    bytes.starts_with(FILE_MAGIC)
+}
+
+pub fn seal_file(plain: &[u8]) -> Result<Vec<u8>, AppError> {
+    Ok(FILE_MAGIC.iter().copied().chain(seal(plain)?).collect())
+}
+
+pub fn open_file(stored: &[u8]) -> Result<Vec<u8>, AppError> {
+    match stored.strip_prefix(FILE_MAGIC.as_slice()) {
+        Some(sealed) => open(sealed),
+        None => Ok(stored.to_vec()),
+    }
+}
+
+// The setting flips first, so whatever is recorded during the migration is
+// already in the new form; running it again picks up what a failure left.
+fn migrate(enabled: bool) -> Result<Migration, AppError> {
+    let _migrating = MIGRATING.lock();
+    if enabled {
+        with_key(true, |_| Ok(()))?;
+    }
+    let config = EncryptionConfig { enabled };
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    let transcripts = history::migrate(enabled)?;
+    let recordings = recordings::migrate(enabled)?;
+    tracing::info!(
+        target: "encryption",
+        enabled,
+        transcripts,
+        recordings,
+        "Storage migrated"
+    );
+    Ok(Migration {
+        enabled,
+        transcripts,
+        recordings,
+    })
+}
+
+#[tauri::command]
+pub fn get_encryption_config() -> EncryptionConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub async fn enable_encryption() -> CommandResult<Migration> {
+    tauri::async_runtime::spawn_blocking(|| migrate(true))
+        .await
+        .map_err(|e| AppError::from(format!("Encryption task failed: {}", e)))?
+}
+
+// The key stays in the keychain, for anything a failed migration left sealed.
+#[tauri::command]
+pub async fn disable_encryption() -> CommandResult<Migration> {
+    tauri::async_runtime::spawn_blocking(|| migrate(false))
+        .await
+        .map_err(|e| AppError::from(format!("Encryption task failed: {}", e)))?
+}
@@ -0,0 +1,310 @@
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::Serialize;
+
+use crate::bus::AgentEvent;
+use crate::children;
+use crate::config;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+// What system_stt_daemon.py cannot start without; pyautogui is optional.
+const REQUIRED_PACKAGES: [&str; 3] = ["faster-whisper", "sounddevice", "numpy"];
+// Prints the interpreter as Python resolves it, so `python3` and the path it
+// points at are listed once.
+const PROBE: &str =
+    "import sys; print(sys.executable); print('%d.%d.%d' % sys.version_info[:3]); print(sys.prefix); print(sys.base_prefix)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvKind {
+    System,
+    Venv,
+    Conda,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonEnv {
+    pub path: String,
+    pub version: String,
+    pub kind: EnvKind,
+    pub missing_packages: Vec<String>,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallProgress {
+    pub python: String,
+    pub line: String,
+}
+
+struct Probe {
+    executable: String,
+    version: String,
+    kind: EnvKind,
+}
+
+fn interpreter_in(prefix: &Path) -> PathBuf {
+    if cfg!(windows) {
+        let scripts = prefix.join("Scripts").join("python.exe");
+        // Conda keeps python.exe at the top of the environment.
+        if scripts.exists() {
+            scripts
+        } else {
+            prefix.join("python.exe")
+        }
+    } else {
+        prefix.join("bin").join("python")
+    }
+}
+
+// The configured interpreter first, then active and nearby environments,
+// then whatever is on PATH.
+fn candidates() -> Vec<String> {
+    let mut prefixes = Vec::new();
+    for var in ["VIRTUAL_ENV", "CONDA_PREFIX"] {
+        if let Some(prefix) = std::env::var_os(var) {
+            prefixes.push(PathBuf::from(prefix));
+        }
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        prefixes.push(cwd.join(".venv"));
+        prefixes.push(cwd.join("venv"));
+    }
+    prefixes.push(store::data_dir().join("venv"));
+    if let Some(home) = dirs::home_dir() {
+        for conda in ["miniconda3", "anaconda3", "miniforge3"] {
+            let base = home.join(conda);
+            prefixes.push(base.clone());
+            if let Ok(envs) = std::fs::read_dir(base.join("envs")) {
+                prefixes.extend(envs.filter_map(|e| e.ok()).map(|e| e.path()));
+            }
+        }
+    }
+
+    let mut candidates = vec![config::current().python];
+    candidates.extend(
+        prefixes
+            .iter()
+            .map(|prefix| interpreter_in(prefix))
+            .filter(|path| path.is_file())
+            .map(|path| path.display().to_string()),
+    );
+    let on_path: &[&str] = if cfg!(windows) {
+        &["python", "py", "python3"]
+    } else {
+        &["python3", "python"]
+    };
+    candidates.extend(on_path.iter().map(|name| name.to_string()));
+    candidates
+}
+
+fn probe(python: &str) -> Option<Probe> {
+    let output = Command::new(python).args(["-c", PROBE]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines().map(str::trim);
+    let executable = lines.next()?.to_string();
+    let version = lines.next()?.to_string();
+    let prefix = lines.next()?;
+    let base_prefix = lines.next()?;
+    let kind = if Path::new(prefix).join("conda-meta").exists() {
+        EnvKind::Conda
+    } else if prefix != base_prefix {
+        EnvKind::Venv
+    } else {
+        EnvKind::System
+    };
+    Some(Probe {
+        executable,
+        version,
+        kind,
+    })
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+// `pip show` prints a `Name:` block for each installed package and warns
+// about the rest; without pip, everything counts as missing.
+fn missing_packages(python: &str) -> Vec<String> {
+    let installed: Vec<String> = Command::new(python)
+        .args(["-m", "pip", "show"])
+        .args(REQUIRED_PACKAGES)
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.strip_prefix("Name:"))
+                .map(normalize)
+                .collect()
+        })
+        .unwrap_or_default();
+    REQUIRED_PACKAGES
+        .iter()
+        .filter(|package| !installed.contains(&normalize(package)))
+        .map(|package| package.to_string())
+        .collect()
+}
+
+fn describe(path: String, probe: Probe, selected: bool) -> PythonEnv {
+    PythonEnv {
+        missing_packages: missing_packages(&path),
+        path,
+        version: probe.version,
+        kind: probe.kind,
+        selected,
+    }
+}
+
+pub fn detect() -> Vec<PythonEnv> {
+    let configured = config::current().python;
+    let configured_executable = probe(&configured).map(|p| p.executable);
+    let mut seen: Vec<String> = Vec::new();
+    let mut envs = Vec::new();
+    for candidate in candidates() {
+        let probe = match probe(&candidate) {
+            Some(probe) => probe,
+            None => continue,
+        };
+        if seen.contains(&probe.executable) {
+            continue;
+        }
+        seen.push(probe.executable.clone());
+        let selected = candidate == configured
+            || configured_executable.as_deref() == Some(probe.executable.as_str());
+        envs.push(describe(candidate, probe, selected));
+    }
+    envs
+}
+
+// The interpreter the daemon starts with, if it runs at all.
+pub fn configured() -> Option<PythonEnv> {
+    let python = config::current().python;
+    let probe = probe(&python)?;
+    Some(describe(python, probe, true))
+}
+
+fn not_python(path: &str) -> AppError {
+    AppError::new(
+        ErrorCode::InvalidInput,
+        format!("{} is not a working Python interpreter", path),
+    )
+}
+
+fn install(state: &AppState, python: &str) -> Result<(), AppError> {
+    let mut child = Command::new(python)
+        .args(["-m", "pip", "install", "--disable-pip-version-check"])
+        .args(REQUIRED_PACKAGES)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DaemonUnavailable,
+                format!("Failed to run {}: {}", python, e),
+            )
+        })?;
+    let _tracked = children::track(child.id());
+    let publish = {
+        let state = state.clone();
+        let python = python.to_string();
+        move |line: String| {
+            state
+                .bus
+                .publish(AgentEvent::InstallProgress(InstallProgress {
+                    python: python.clone(),
+                    line,
+                }))
+        }
+    };
+    // stderr is drained alongside stdout so neither pipe can fill and stall pip.
+    let stderr = child.stderr.take().map(|stderr| {
+        let publish = publish.clone();
+        thread::spawn(move || {
+            let mut last = String::new();
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if !line.trim().is_empty() {
+                    last = line.clone();
+                }
+                publish(line);
+            }
+            last
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            publish(line);
+        }
+    }
+    let last_error = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    let status = child
+        .wait()
+        .map_err(|e| AppError::from(format!("pip did not finish: {}", e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::from(format!(
+            "pip install failed: {}",
+            if last_error.is_empty() {
+                "unknown error"
+            } else {
+                last_error.as_str()
+            }
+        ))
+        .with_context("python", python))
+    }
+}
+
+#[tauri::command]
+pub async fn detect_python_envs() -> CommandResult<Vec<PythonEnv>> {
+    tauri::async_runtime::spawn_blocking(detect)
+        .await
+        .map_err(|e| AppError::from(format!("Detection task failed: {}", e)))
+}
+
+// Saved as `python` in config.toml; the daemon picks it up from its next start.
+#[tauri::command]
+pub async fn select_python_env(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> CommandResult<PythonEnv> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let probe = probe(&path).ok_or_else(|| not_python(&path))?;
+        config::update(&state, |config| config.python = path.clone())?;
+        Ok(describe(path, probe, true))
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Selection task failed: {}", e)))?
+}
+
+// Installs into `path`, or the configured interpreter, and lists what is
+// still missing afterwards.
+#[tauri::command]
+pub async fn install_daemon_deps(
+    state: tauri::State<'_, AppState>,
+    path: Option<String>,
+) -> CommandResult<PythonEnv> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let configured = config::current().python;
+        let python = path.unwrap_or_else(|| configured.clone());
+        let probe = probe(&python).ok_or_else(|| not_python(&python))?;
+        install(&state, &python)?;
+        let selected = python == configured;
+        Ok(describe(python, probe, selected))
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Install task failed: {}", e)))?
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::i18n;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    AlreadyRunning,
+    NotRunning,
+    NotFound,
+    InvalidInput,
+    PermissionDenied,
+    Unsupported,
+    DaemonUnavailable,
+    Storage,
+    Network,
+    Failed,
+}
+
+impl ErrorCode {
+    // Unrecoverable errors need something outside the app fixed first.
+    fn recoverable(self) -> bool {
+        !matches!(self, ErrorCode::Unsupported | ErrorCode::DaemonUnavailable)
+    }
+
+    fn hint(self) -> Option<String> {
+        let id = match self {
+            ErrorCode::AlreadyRunning => "error-hint-already-running",
+            ErrorCode::NotRunning => "error-hint-not-running",
+            ErrorCode::PermissionDenied => "error-hint-permission-denied",
+            ErrorCode::DaemonUnavailable => "error-hint-daemon-unavailable",
+            ErrorCode::Storage => "error-hint-storage",
+            ErrorCode::Network => "error-hint-network",
+            _ => return None,
+        };
+        Some(i18n::t(id))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message}")]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub recoverable: bool,
+    pub hint: Option<String>,
+    // Machine-readable details for the frontend, such as the program that
+    // could not be launched.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub context: Map<String, Value>,
+}
+
+pub type CommandResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            recoverable: code.recoverable(),
+            hint: code.hint(),
+            context: Map::new(),
+        }
+    }
+
+    pub fn with_context(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.context.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+// Most internals still report plain messages; those surface as `Failed`.
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorCode::Failed, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::new(ErrorCode::Failed, message)
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.message
+    }
+}
@@ -0,0 +1,281 @@
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Window};
+
+use crate::a11y::Announcement;
+use crate::agents::AgentStatus;
+use crate::audio::{AudioLevel, CaptureInfo};
+use crate::audio_ring::RingStats;
+use crate::backends::BackendKind;
+use crate::batch::FileProgress;
+use crate::config::AppConfig;
+use crate::crash::CrashReport;
+use crate::daemon::DaemonLog;
+use crate::dictation::DictationSession;
+use crate::env::InstallProgress;
+use crate::latency::UtteranceMetrics;
+use crate::lifecycle::OperationProgress;
+use crate::logging::LogEntry;
+use crate::meeting::MeetingSummary;
+use crate::models::DownloadProgress;
+use crate::permissions::PermissionStatus;
+use crate::power::PowerEvent;
+use crate::profiles::SttLanguage;
+use crate::resources::ResourceUsage;
+use crate::startup::StageProgress;
+use crate::text_inject::InjectionOutcome;
+use crate::transcript::Transcript;
+use crate::updater::{UpdateInfo, UpdateProgress};
+use crate::voice_commands::VoiceMode;
+use crate::window_tracker::FocusedApp;
+
+// Bumped when the envelope itself changes shape.
+pub const ENVELOPE_VERSION: u32 = 1;
+// Every event the frontend can receive, with the version of its payload. A
+// payload's version is bumped whenever a field is renamed or removed, or
+// changes type; new fields do not need a bump.
+const EVENTS: &[(&str, u32)] = &[
+    ("stt_status", 1),
+    ("stt_paused", 1),
+    ("stt_language_changed", 1),
+    ("stt_transcript", 1),
+    ("stt_repeat", 1),
+    ("stt_log", 1),
+    ("stt_crashed", 1),
+    ("stt_restarting", 1),
+    ("backend_degraded", 1),
+    ("backend_restored", 1),
+    ("stt_metrics", 1),
+    ("agent_status", 1),
+    ("config_changed", 1),
+    ("speech_started", 1),
+    ("speech_ended", 1),
+    ("wake_word_detected", 1),
+    ("ptt_pressed", 1),
+    ("ptt_released", 1),
+    ("speak_requested", 1),
+    ("speaking_changed", 1),
+    ("mcp_tool_called", 1),
+    ("instance_activated", 1),
+    ("system_suspended", 1),
+    ("system_resumed", 1),
+    ("power_changed", 1),
+    ("language_changed", 1),
+    ("a11y_announcement", 1),
+    ("audio_stats", 1),
+    ("audio_level", 1),
+    ("input_device_changed", 1),
+    ("focused_app_changed", 1),
+    ("injection_completed", 1),
+    ("startup_progress", 1),
+    ("operation_progress", 1),
+    ("file_progress", 1),
+    ("files_dropped", 1),
+    ("session_summary", 1),
+    ("meeting_summary", 1),
+    ("model_download_progress", 1),
+    ("model_changed", 1),
+    ("latency_report", 1),
+    ("deps_install_progress", 1),
+    ("voice_command", 1),
+    ("voice_mode_changed", 1),
+    ("meeting_prompt", 1),
+    ("livekit_transcript", 1),
+    ("livekit_participants", 1),
+    ("crash_reports_pending", 1),
+    ("app_log", 1),
+    ("permissions_changed", 1),
+    ("update_available", 1),
+    ("update_progress", 1),
+];
+
+#[derive(Serialize)]
+#[serde(tag = "event", content = "payload", rename_all = "snake_case")]
+pub enum UiEvent {
+    SttStatus {
+        listening: bool,
+    },
+    SttPaused {
+        paused: bool,
+    },
+    SttLanguageChanged(SttLanguage),
+    SttTranscript(Transcript),
+    SttRepeat(Transcript),
+    SttLog(DaemonLog),
+    SttCrashed {
+        session_id: String,
+        exit_code: Option<i32>,
+    },
+    SttRestarting {
+        attempt: u32,
+        delay_ms: u64,
+    },
+    BackendDegraded {
+        backend: BackendKind,
+        reason: String,
+        fallback: Option<BackendKind>,
+    },
+    BackendRestored {
+        backend: BackendKind,
+        dropped_ms: u64,
+    },
+    SttMetrics(ResourceUsage),
+    AgentStatus(AgentStatus),
+    ConfigChanged(AppConfig),
+    SpeechStarted {},
+    SpeechEnded {},
+    WakeWordDetected {
+        name: String,
+        score: f32,
+    },
+    PttPressed {},
+    PttReleased {},
+    SpeakRequested {
+        text: String,
+    },
+    SpeakingChanged {
+        speaking: bool,
+    },
+    McpToolCalled {
+        route_id: String,
+        server: String,
+        tool: String,
+        result: String,
+    },
+    InstanceActivated {
+        args: Vec<String>,
+    },
+    SystemSuspended {
+        reason: String,
+    },
+    SystemResumed {
+        reason: String,
+    },
+    PowerChanged(PowerEvent),
+    LanguageChanged {
+        locale: String,
+    },
+    A11yAnnouncement(Announcement),
+    AudioStats {
+        session_id: String,
+        buffer: RingStats,
+    },
+    AudioLevel(AudioLevel),
+    InputDeviceChanged(CaptureInfo),
+    FocusedAppChanged(FocusedApp),
+    InjectionCompleted(InjectionOutcome),
+    StartupProgress(StageProgress),
+    OperationProgress(OperationProgress),
+    FileProgress(FileProgress),
+    FilesDropped {
+        batch_id: String,
+    },
+    SessionSummary(DictationSession),
+    MeetingSummary(MeetingSummary),
+    ModelDownloadProgress(DownloadProgress),
+    ModelChanged {
+        model: String,
+        loaded: bool,
+        reason: String,
+    },
+    LatencyReport(UtteranceMetrics),
+    DepsInstallProgress(InstallProgress),
+    VoiceCommand {
+        intent: String,
+        text: String,
+    },
+    VoiceModeChanged {
+        mode: VoiceMode,
+    },
+    MeetingPrompt {
+        event_id: String,
+        title: String,
+    },
+    LivekitTranscript {
+        room: String,
+        participant: String,
+        transcript: Transcript,
+    },
+    LivekitParticipants {
+        room: String,
+        participants: Vec<String>,
+    },
+    CrashReportsPending {
+        reports: Vec<CrashReport>,
+    },
+    AppLog(LogEntry),
+    PermissionsChanged {
+        permissions: Vec<PermissionStatus>,
+    },
+    UpdateAvailable(UpdateInfo),
+    UpdateProgress(UpdateProgress),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventCapability {
+    pub name: &'static str,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    pub envelope: u32,
+    pub events: Vec<EventCapability>,
+}
+
+fn version(name: &str) -> u32 {
+    EVENTS
+        .iter()
+        .find(|(event, _)| *event == name)
+        .map_or(1, |&(_, version)| version)
+}
+
+fn envelope(name: &str, payload: Value) -> Value {
+    serde_json::json!({
+        "event": name,
+        "version": version(name),
+        "timestamp": crate::transcript::now_millis(),
+        "payload": payload,
+    })
+}
+
+// The event's name and its envelope.
+fn wrap(event: &UiEvent) -> Option<(String, Value)> {
+    let mut tagged = serde_json::to_value(event).ok()?;
+    let name = tagged.get("event")?.as_str()?.to_string();
+    let payload = tagged
+        .as_object_mut()
+        .and_then(|tagged| tagged.remove("payload"))
+        .unwrap_or(Value::Null);
+    let envelope = envelope(&name, payload);
+    Some((name, envelope))
+}
+
+pub fn emit(app: &AppHandle, event: UiEvent) {
+    if let Some((name, envelope)) = wrap(&event) {
+        let _ = app.emit_all(&name, envelope);
+    }
+}
+
+pub fn emit_to(window: &Window, event: UiEvent) {
+    if let Some((name, envelope)) = wrap(&event) {
+        let _ = window.emit(&name, envelope);
+    }
+}
+
+// A voice command's `event` action goes out under the name the user gave
+// it, so it is not listed in the capabilities.
+pub fn emit_custom(app: &AppHandle, name: &str, text: String) {
+    let _ = app.emit_all(name, envelope(name, serde_json::json!({ "text": text })));
+}
+
+#[tauri::command]
+pub fn subscribe_capabilities() -> Capabilities {
+    Capabilities {
+        envelope: ENVELOPE_VERSION,
+        events: EVENTS
+            .iter()
+            .map(|&(name, version)| EventCapability { name, version })
+            .collect(),
+    }
+}
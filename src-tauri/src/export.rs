@@ -0,0 +1,177 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::history::{self, HistoryEntry};
+use crate::transcript::Transcript;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Txt,
+    Md,
+    Json,
+    Srt,
+    Vtt,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Txt => "txt",
+            ExportFormat::Md => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+        }
+    }
+}
+
+fn timestamp(millis: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+fn clock(millis: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60
+    )
+}
+
+// Segments without timings get a nominal slot after the previous one so the
+// file is still valid.
+fn timed(segments: &[Transcript]) -> Vec<(u64, u64, &str)> {
+    let mut previous_end = 0;
+    segments
+        .iter()
+        .map(|s| {
+            let start = s.start_ms.unwrap_or(previous_end);
+            let end = s.end_ms.filter(|&end| end > start).unwrap_or(start + 2000);
+            // Trimmed to the speech itself when the words were timed.
+            let (start, end) = match (s.words.first(), s.words.last()) {
+                (Some(first), Some(last)) if last.end_ms > first.start_ms => {
+                    (start + first.start_ms, start + last.end_ms)
+                }
+                _ => (start, end),
+            };
+            previous_end = end;
+            (start, end, s.text.trim())
+        })
+        .collect()
+}
+
+pub fn render(segments: &[Transcript], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Txt => {
+            let mut text = segments
+                .iter()
+                .map(|s| s.text.trim())
+                .collect::<Vec<_>>()
+                .join("\n");
+            text.push('\n');
+            text
+        }
+        ExportFormat::Md => segments
+            .iter()
+            .map(|s| {
+                let mut line = String::new();
+                if let Some(start) = s.start_ms {
+                    line.push_str(&format!("**[{}]** ", clock(start)));
+                }
+                if let Some(speaker) = &s.speaker {
+                    line.push_str(&format!("**{}:** ", speaker));
+                }
+                line.push_str(s.text.trim());
+                line.push_str("\n\n");
+                line
+            })
+            .collect(),
+        ExportFormat::Json => serde_json::to_string_pretty(segments).unwrap_or_default(),
+        ExportFormat::Srt => timed(segments)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (start, end, text))| {
+                format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    i + 1,
+                    timestamp(start, ','),
+                    timestamp(end, ','),
+                    text
+                )
+            })
+            .collect(),
+        ExportFormat::Vtt => {
+            let mut vtt = "WEBVTT\n\n".to_string();
+            for (start, end, text) in timed(segments) {
+                vtt.push_str(&format!(
+                    "{} --> {}\n{}\n\n",
+                    timestamp(start, '.'),
+                    timestamp(end, '.'),
+                    text
+                ));
+            }
+            vtt
+        }
+    }
+}
+
+// History keeps when each transcript arrived and how long it took to say, so
+// the entries are laid out on one timeline from the start of the first.
+fn timeline(mut entries: Vec<HistoryEntry>) -> Vec<Transcript> {
+    entries.sort_by_key(|e| e.timestamp);
+    let spoken = |e: &HistoryEntry| e.timestamp.saturating_sub(e.duration_ms.unwrap_or(0));
+    let origin = entries.iter().map(spoken).min().unwrap_or(0);
+    entries
+        .into_iter()
+        .map(|e| Transcript {
+            words: history::words(&e.id).unwrap_or_default(),
+            start_ms: Some(spoken(&e) - origin),
+            end_ms: e.duration_ms.map(|_| e.timestamp - origin),
+            id: e.id,
+            text: e.text,
+            is_final: true,
+            timestamp: e.timestamp,
+            confidence: e.confidence,
+            session_id: e.session_id,
+            speaker: e.speaker,
+            language: None,
+            translation: None,
+            latency: None,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn export_transcripts(
+    ids: Vec<String>,
+    format: ExportFormat,
+    path: String,
+) -> CommandResult<()> {
+    if ids.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "No transcripts to export",
+        ));
+    }
+    let entries = ids
+        .iter()
+        .map(|id| history::get(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let path = Path::new(&path);
+    std::fs::write(path, render(&timeline(entries), format)).map_err(|e| {
+        AppError::new(
+            ErrorCode::Storage,
+            format!("Failed to write {}: {}", path.display(), e),
+        )
+    })
+}
@@ -0,0 +1,316 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::window_tracker;
+
+const STORE_FILE: &str = "formatting.json";
+// Stripped from a spoken token before it is matched, since the STT often
+// punctuates "comma" itself.
+const TRAILING: &[char] = &['.', ',', '!', '?', ';', ':'];
+const PUNCTUATION: [(&str, &str); 9] = [
+    ("period", "."),
+    ("full stop", "."),
+    ("comma", ","),
+    ("question mark", "?"),
+    ("exclamation mark", "!"),
+    ("exclamation point", "!"),
+    ("colon", ":"),
+    ("semicolon", ";"),
+    ("dash", " -"),
+];
+const BREAKS: [(&str, &str); 2] = [("new paragraph", "\n\n"), ("new line", "\n")];
+const UNITS: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormattingStage {
+    // "comma", "period", "question mark" and the like become symbols.
+    Punctuation,
+    // "new line" and "new paragraph" become line breaks.
+    Paragraphs,
+    // "twenty three" becomes 23; a lone word under ten is left spelled out.
+    Numbers,
+    // Sentence starts and a lone "i".
+    Capitalization,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormattingRule {
+    pub stage: FormattingStage,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct FormattingConfig {
+    // Run in this order; a stage that is not listed does not run.
+    rules: Vec<FormattingRule>,
+}
+
+impl Default for FormattingConfig {
+    fn default() -> Self {
+        let rules = [
+            FormattingStage::Punctuation,
+            FormattingStage::Paragraphs,
+            FormattingStage::Numbers,
+            FormattingStage::Capitalization,
+        ]
+        .iter()
+        .map(|&stage| FormattingRule {
+            stage,
+            enabled: false,
+        })
+        .collect();
+        Self { rules }
+    }
+}
+
+static CONFIG: Lazy<Mutex<FormattingConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+// The focused app's rules, if it has its own.
+fn rules() -> Vec<FormattingRule> {
+    window_tracker::formatting_rules().unwrap_or_else(|| CONFIG.lock().rules.clone())
+}
+
+pub fn enabled() -> bool {
+    rules().iter().any(|rule| rule.enabled)
+}
+
+fn is_break(token: &str) -> bool {
+    token.starts_with('\n')
+}
+
+fn bare(token: &str) -> String {
+    token.trim_end_matches(TRAILING).to_lowercase()
+}
+
+// Hands each spoken phrase of one or two words to `place`, with its
+// replacement, in place of the words.
+fn replace_phrases(
+    tokens: Vec<String>,
+    phrases: &[(&str, &str)],
+    mut place: impl FnMut(&mut Vec<String>, &str),
+) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let one = bare(&tokens[i]);
+        let two = tokens
+            .get(i + 1)
+            .map(|next| format!("{} {}", one, bare(next)));
+        let matched = phrases.iter().find_map(|&(phrase, replacement)| {
+            if two.as_deref() == Some(phrase) {
+                Some((2, replacement))
+            } else if one == phrase {
+                Some((1, replacement))
+            } else {
+                None
+            }
+        });
+        match matched {
+            Some((len, replacement)) => {
+                place(&mut out, replacement);
+                i += len;
+            }
+            None => {
+                out.push(tokens[i].clone());
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn punctuation(tokens: Vec<String>) -> Vec<String> {
+    replace_phrases(tokens, &PUNCTUATION, |out, symbol| match out.last_mut() {
+        Some(last) if !is_break(last) => {
+            let trimmed = last.trim_end_matches(TRAILING).len();
+            last.truncate(trimmed);
+            last.push_str(symbol);
+        }
+        _ => out.push(symbol.trim().to_string()),
+    })
+}
+
+fn paragraphs(tokens: Vec<String>) -> Vec<String> {
+    replace_phrases(tokens, &BREAKS, |out, brk| out.push(brk.to_string()))
+}
+
+fn number_word(word: &str) -> Option<u64> {
+    if let Some(i) = UNITS.iter().position(|&w| w == word) {
+        return Some(i as u64);
+    }
+    TENS.iter()
+        .position(|&w| w == word)
+        .map(|i| (i as u64 + 2) * 10)
+}
+
+fn scale_word(word: &str) -> Option<u64> {
+    match word {
+        "hundred" => Some(100),
+        "thousand" => Some(1_000),
+        "million" => Some(1_000_000),
+        _ => None,
+    }
+}
+
+// Folds a run of number words into one value, e.g. "three hundred twenty one".
+fn numbers(tokens: Vec<String>) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let (mut total, mut current, mut len) = (0u64, 0u64, 0usize);
+        let mut suffix = String::new();
+        // Units follow a ten or a scale; "one two" is two numbers.
+        let mut after_ten = false;
+        let mut after_scale = false;
+        while let Some(token) = tokens.get(i + len) {
+            let word = bare(token);
+            if let Some(value) =
+                number_word(&word).filter(|&v| len == 0 || after_scale || (after_ten && v < 10))
+            {
+                current += value;
+                after_ten = value >= 20;
+                after_scale = false;
+            } else if let Some(scale) = scale_word(&word).filter(|_| len > 0) {
+                if scale == 100 {
+                    current = current.max(1) * 100;
+                } else {
+                    total += current.max(1) * scale;
+                    current = 0;
+                }
+                after_ten = false;
+                after_scale = true;
+            } else {
+                break;
+            }
+            len += 1;
+            suffix = token[token.trim_end_matches(TRAILING).len()..].to_string();
+            // Punctuation ends the number.
+            if !suffix.is_empty() {
+                break;
+            }
+        }
+        let value = total + current;
+        if len == 0 || (len == 1 && value < 10) {
+            out.push(tokens[i].clone());
+            i += len.max(1);
+        } else {
+            out.push(format!("{}{}", value, suffix));
+            i += len;
+        }
+    }
+    out
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn capitalization(tokens: Vec<String>) -> Vec<String> {
+    let mut sentence_start = true;
+    tokens
+        .into_iter()
+        .map(|token| {
+            if is_break(&token) {
+                sentence_start = true;
+                return token;
+            }
+            let lower = bare(&token);
+            let token = if sentence_start || lower == "i" || lower.starts_with("i'") {
+                capitalize(&token)
+            } else {
+                token
+            };
+            sentence_start = token.ends_with(&['.', '!', '?'][..]);
+            token
+        })
+        .collect()
+}
+
+fn render(tokens: &[String]) -> String {
+    let mut text = String::new();
+    for token in tokens {
+        if is_break(token) {
+            text.truncate(text.trim_end_matches(' ').len());
+        } else if !text.is_empty() && !text.ends_with('\n') {
+            text.push(' ');
+        }
+        text.push_str(token);
+    }
+    text
+}
+
+pub fn format(text: &str) -> String {
+    let rules = rules();
+    let mut tokens: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+    for rule in rules.iter().filter(|rule| rule.enabled) {
+        tokens = match rule.stage {
+            FormattingStage::Punctuation => punctuation(tokens),
+            FormattingStage::Paragraphs => paragraphs(tokens),
+            FormattingStage::Numbers => numbers(tokens),
+            FormattingStage::Capitalization => capitalization(tokens),
+        };
+    }
+    render(&tokens)
+}
+
+pub fn validate(rules: &[FormattingRule]) -> Result<(), AppError> {
+    for (i, rule) in rules.iter().enumerate() {
+        if rules[..i].iter().any(|other| other.stage == rule.stage) {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Each formatting stage can only be listed once",
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_formatting_rules() -> Vec<FormattingRule> {
+    CONFIG.lock().rules.clone()
+}
+
+// Typing moves from the daemon to text injection from the daemon's next
+// start, so the formatted text is what gets typed.
+#[tauri::command]
+pub fn set_formatting_rules(rules: Vec<FormattingRule>) -> CommandResult<()> {
+    validate(&rules)?;
+    let config = FormattingConfig { rules };
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
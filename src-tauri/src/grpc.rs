@@ -0,0 +1,233 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::transcript::Transcript;
+use crate::usage;
+use crate::AppState;
+
+pub mod pb {
+    tonic::include_proto!("voice_agent.v1");
+}
+
+use pb::voice_agent_server::{VoiceAgent, VoiceAgentServer};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:50051";
+
+type TranscriptStream = Pin<Box<dyn Stream<Item = Result<pb::Transcript, Status>> + Send>>;
+
+#[derive(Default)]
+pub struct GrpcState {
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+struct Service {
+    state: AppState,
+}
+
+impl Service {
+    fn status(&self, message: String) -> pb::StatusReply {
+        let source = daemon::input_source(&self.state);
+        pb::StatusReply {
+            listening: backends::is_running(&self.state),
+            source: match source {
+                Some(InputSource::Stream) => pb::InputSource::Stream as i32,
+                Some(InputSource::Remote) => pb::InputSource::Remote as i32,
+                _ => pb::InputSource::Microphone as i32,
+            },
+            session_id: backends::session_id(&self.state).unwrap_or_default(),
+            message,
+        }
+    }
+
+    // Starting and stopping wait on the lifecycle queue, so they are kept off
+    // the server's executor.
+    async fn blocking(
+        &self,
+        action: impl FnOnce(&AppState) -> Result<String, AppError> + Send + 'static,
+    ) -> Result<String, Status> {
+        let state = self.state.clone();
+        tauri::async_runtime::spawn_blocking(move || action(&state))
+            .await
+            .map_err(|e| Status::internal(format!("gRPC task failed: {}", e)))?
+            .map_err(Status::failed_precondition)
+    }
+}
+
+fn to_proto(transcript: Transcript) -> pb::Transcript {
+    pb::Transcript {
+        id: transcript.id,
+        text: transcript.text,
+        is_final: transcript.is_final,
+        timestamp: transcript.timestamp,
+        confidence: transcript.confidence,
+        session_id: transcript.session_id.unwrap_or_default(),
+        start_ms: transcript.start_ms,
+        end_ms: transcript.end_ms,
+        speaker: transcript.speaker.unwrap_or_default(),
+        language: transcript.language.unwrap_or_default(),
+        translation: transcript.translation.map(|t| pb::Translation {
+            language: t.language,
+            text: t.text,
+        }),
+    }
+}
+
+fn transcript_stream(state: &AppState, include_partials: bool) -> TranscriptStream {
+    let stream = BroadcastStream::new(state.bus.subscribe()).filter_map(move |event| match event {
+        Ok(AgentEvent::Transcript(t)) if include_partials || t.is_final => Some(Ok(to_proto(t))),
+        _ => None,
+    });
+    Box::pin(stream)
+}
+
+#[tonic::async_trait]
+impl VoiceAgent for Service {
+    async fn start_listening(
+        &self,
+        request: Request<pb::StartListeningRequest>,
+    ) -> Result<Response<pb::StatusReply>, Status> {
+        let source = match request.into_inner().source() {
+            pb::InputSource::Stream => InputSource::Stream,
+            pb::InputSource::Remote => InputSource::Remote,
+            pb::InputSource::Microphone => InputSource::Microphone,
+        };
+        self.blocking(move |state| backends::start(state, source))
+            .await
+            .map(|message| Response::new(self.status(message)))
+    }
+
+    async fn stop_listening(
+        &self,
+        _request: Request<pb::StopListeningRequest>,
+    ) -> Result<Response<pb::StatusReply>, Status> {
+        self.blocking(backends::stop)
+            .await
+            .map(|message| Response::new(self.status(message)))
+    }
+
+    async fn get_status(
+        &self,
+        _request: Request<pb::GetStatusRequest>,
+    ) -> Result<Response<pb::StatusReply>, Status> {
+        Ok(Response::new(self.status(String::new())))
+    }
+
+    type StreamTranscriptsStream = TranscriptStream;
+
+    async fn stream_transcripts(
+        &self,
+        request: Request<pb::StreamTranscriptsRequest>,
+    ) -> Result<Response<Self::StreamTranscriptsStream>, Status> {
+        let include_partials = request.into_inner().include_partials;
+        Ok(Response::new(transcript_stream(
+            &self.state,
+            include_partials,
+        )))
+    }
+
+    type TranscribeStream = TranscriptStream;
+
+    async fn transcribe(
+        &self,
+        request: Request<Streaming<pb::AudioChunk>>,
+    ) -> Result<Response<Self::TranscribeStream>, Status> {
+        match daemon::input_source(&self.state) {
+            Some(InputSource::Stream) => {}
+            Some(_) => {
+                return Err(Status::failed_precondition(
+                    "STT daemon is busy with another input source",
+                ))
+            }
+            None => {
+                self.blocking(|state| backends::start(state, InputSource::Stream))
+                    .await?;
+            }
+        }
+
+        let transcripts = transcript_stream(&self.state, false);
+        let mut audio = request.into_inner();
+        let state = self.state.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(Ok(chunk)) = audio.next().await {
+                if daemon::push_audio(&state, &chunk.pcm).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(transcripts))
+    }
+}
+
+pub async fn serve(
+    state: AppState,
+    addr: SocketAddr,
+    shutdown: oneshot::Receiver<()>,
+) -> Result<(), String> {
+    Server::builder()
+        .add_service(VoiceAgentServer::new(Service { state }))
+        .serve_with_shutdown(addr, async {
+            let _ = shutdown.await;
+        })
+        .await
+        .map_err(|e| format!("gRPC server failed: {}", e))
+}
+
+#[tauri::command]
+pub fn start_grpc_server(
+    state: tauri::State<'_, AppState>,
+    grpc: tauri::State<'_, GrpcState>,
+    address: Option<String>,
+) -> CommandResult<String> {
+    let address = address.unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let addr: SocketAddr = address.parse().map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid gRPC address {}: {}", address, e),
+        )
+    })?;
+
+    let mut shutdown = grpc.shutdown.lock();
+    if shutdown.is_some() {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "gRPC server is already running",
+        ));
+    }
+
+    usage::record("grpc_server.start");
+    let (tx, rx) = oneshot::channel();
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let bus = state.bus.clone();
+        if let Err(message) = serve(state, addr, rx).await {
+            bus.publish(AgentEvent::error("grpc", message));
+        }
+    });
+    *shutdown = Some(tx);
+    Ok(format!("gRPC server listening on {}", addr))
+}
+
+#[tauri::command]
+pub fn stop_grpc_server(grpc: tauri::State<'_, GrpcState>) -> CommandResult<String> {
+    match grpc.shutdown.lock().take() {
+        Some(tx) => {
+            let _ = tx.send(());
+            Ok("gRPC server stopped".to_string())
+        }
+        None => Err(AppError::new(
+            ErrorCode::NotRunning,
+            "gRPC server is not running",
+        )),
+    }
+}
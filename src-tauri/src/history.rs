@@ -0,0 +1,742 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rusqlite::types::{Type, Value as SqlValue};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio;
+use crate::bus::{AgentEvent, EventBus};
+use crate::config;
+use crate::daemon::{self, InputSource};
+use crate::dictation::DictationSession;
+use crate::encryption;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::latency::UtteranceMetrics;
+use crate::meeting::MeetingSummary;
+use crate::recordings;
+use crate::redaction::{self, Destination};
+use crate::store;
+use crate::transcript::{Transcript, Word};
+use crate::AppState;
+
+const DB_FILE: &str = "history.sqlite3";
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+// The FTS table mirrors `transcripts` through triggers, so only the base
+// table is ever written.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transcripts (
+    id TEXT PRIMARY KEY,
+    text TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    duration_ms INTEGER,
+    confidence REAL,
+    device TEXT,
+    session_id TEXT,
+    speaker TEXT
+);
+CREATE INDEX IF NOT EXISTS transcripts_timestamp ON transcripts (timestamp);
+CREATE VIRTUAL TABLE IF NOT EXISTS transcripts_fts
+    USING fts5 (text, content = 'transcripts', content_rowid = 'rowid');
+-- Encrypted text is kept out of the index. The triggers are recreated on
+-- every open so that databases from before encryption get the condition.
+DROP TRIGGER IF EXISTS transcripts_insert;
+CREATE TRIGGER transcripts_insert AFTER INSERT ON transcripts
+    WHEN new.text NOT LIKE 'enc1:%' BEGIN
+    INSERT INTO transcripts_fts (rowid, text) VALUES (new.rowid, new.text);
+END;
+DROP TRIGGER IF EXISTS transcripts_delete;
+CREATE TRIGGER transcripts_delete AFTER DELETE ON transcripts
+    WHEN old.text NOT LIKE 'enc1:%' BEGIN
+    INSERT INTO transcripts_fts (transcripts_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+END;
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER,
+    word_count INTEGER NOT NULL DEFAULT 0,
+    transcript_count INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS sessions_started_at ON sessions (started_at);
+-- Dictation sessions span daemon sessions, so they are linked separately
+-- from `transcripts.session_id`.
+CREATE TABLE IF NOT EXISTS session_transcripts (
+    session_id TEXT NOT NULL,
+    transcript_id TEXT NOT NULL,
+    PRIMARY KEY (session_id, transcript_id)
+);
+-- One row per final transcript that carried stage timings.
+CREATE TABLE IF NOT EXISTS utterance_metrics (
+    transcript_id TEXT PRIMARY KEY,
+    session_id TEXT,
+    backend TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    latency_ms REAL NOT NULL,
+    stt_ms REAL NOT NULL,
+    audio_ms INTEGER,
+    realtime_factor REAL,
+    words INTEGER NOT NULL,
+    words_per_minute REAL
+);
+CREATE INDEX IF NOT EXISTS utterance_metrics_session ON utterance_metrics (session_id);
+CREATE TRIGGER IF NOT EXISTS utterance_metrics_delete AFTER DELETE ON transcripts BEGIN
+    DELETE FROM utterance_metrics WHERE transcript_id = old.id;
+END;
+-- Timed words, for the transcripts whose backend reported them.
+CREATE TABLE IF NOT EXISTS transcript_words (
+    transcript_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    word TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL,
+    confidence REAL,
+    PRIMARY KEY (transcript_id, position)
+);
+CREATE TRIGGER IF NOT EXISTS transcript_words_delete AFTER DELETE ON transcripts BEGIN
+    DELETE FROM transcript_words WHERE transcript_id = old.id;
+END;
+-- What meeting mode's summarization tool returned, one row per chunk.
+CREATE TABLE IF NOT EXISTS session_summaries (
+    session_id TEXT NOT NULL,
+    chunk_start INTEGER NOT NULL,
+    chunk_end INTEGER NOT NULL,
+    summary TEXT NOT NULL,
+    PRIMARY KEY (session_id, chunk_start)
+);
+";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub text: String,
+    pub timestamp: u64,
+    pub duration_ms: Option<u64>,
+    pub confidence: Option<f32>,
+    pub device: Option<String>,
+    pub session_id: Option<String>,
+    pub speaker: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    // Full-text search; every word must match, the last one as a prefix.
+    pub query: Option<String>,
+    pub session_id: Option<String>,
+    pub device: Option<String>,
+    // Unix millis, inclusive.
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+// Opened on first use and kept open; None until then or after a failure.
+static DB: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+fn storage(e: rusqlite::Error) -> AppError {
+    AppError::new(
+        ErrorCode::Storage,
+        format!("Transcript history failed: {}", e),
+    )
+}
+
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, AppError> {
+    let mut db = DB.lock();
+    if db.is_none() {
+        let dir = store::data_dir();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            AppError::new(
+                ErrorCode::Storage,
+                format!("Failed to create {}: {}", dir.display(), e),
+            )
+        })?;
+        let connection = Connection::open(dir.join(DB_FILE)).map_err(storage)?;
+        connection.execute_batch(SCHEMA).map_err(storage)?;
+        *db = Some(connection);
+    }
+    match db.as_ref() {
+        Some(connection) => f(connection).map_err(storage),
+        None => Err(AppError::from("Transcript history is unavailable")),
+    }
+}
+
+// Quotes each word so FTS operators typed into the search box are literal.
+fn match_expression(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// The index's rules for text it cannot see because it is encrypted: every
+// word must match a whole word, the last one as a prefix.
+fn matches(text: &str, query: &str) -> bool {
+    let text = tokens(text);
+    let query = tokens(query);
+    query.iter().enumerate().all(|(i, word)| {
+        text.iter().any(|token| {
+            if i + 1 == query.len() {
+                token.starts_with(word.as_str())
+            } else {
+                token == word
+            }
+        })
+    })
+}
+
+// Decrypts a column as it is read, so every query sees plain text.
+fn plain(row: &Row, index: usize) -> rusqlite::Result<String> {
+    encryption::open_text(&row.get::<_, String>(index)?)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+// The capture device when the agent opened the microphone itself, otherwise
+// the one the daemon was told to use; None for the system default.
+fn device(state: &AppState, transcript: &Transcript) -> Option<String> {
+    if daemon::is_remote_speaker(state, transcript) {
+        return None;
+    }
+    audio::current_device(state).or_else(|| match daemon::input_source(state) {
+        Some(InputSource::Microphone) => config::current().input_device,
+        _ => None,
+    })
+}
+
+pub fn record(transcript: &Transcript, device: Option<String>) -> Result<(), AppError> {
+    let duration_ms = match (transcript.start_ms, transcript.end_ms) {
+        (Some(start), Some(end)) if end >= start => Some((end - start) as i64),
+        _ => None,
+    };
+    let text = encryption::store_text(&transcript.text)?;
+    let words = transcript
+        .words
+        .iter()
+        .map(|word| encryption::store_text(&word.word))
+        .collect::<Result<Vec<_>, _>>()?;
+    with_db(|db| {
+        db.execute(
+            "INSERT OR IGNORE INTO transcripts
+                (id, text, timestamp, duration_ms, confidence, device, session_id, speaker)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                transcript.id,
+                text,
+                transcript.timestamp as i64,
+                duration_ms,
+                transcript.confidence.map(f64::from),
+                device,
+                transcript.session_id,
+                transcript.speaker,
+            ],
+        )?;
+        let mut statement = db.prepare(
+            "INSERT OR IGNORE INTO transcript_words
+                (transcript_id, position, word, start_ms, end_ms, confidence)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (position, (word, stored)) in transcript.words.iter().zip(&words).enumerate() {
+            statement.execute(params![
+                transcript.id,
+                position as i64,
+                stored,
+                word.start_ms as i64,
+                word.end_ms as i64,
+                word.confidence.map(f64::from),
+            ])?;
+        }
+        Ok(())
+    })
+}
+
+// In order; empty for transcripts stored without word timings.
+pub fn words(id: &str) -> Result<Vec<Word>, AppError> {
+    with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT word, start_ms, end_ms, confidence FROM transcript_words
+             WHERE transcript_id = ?1 ORDER BY position",
+        )?;
+        let rows = statement.query_map(params![id], |row| {
+            Ok(Word {
+                word: plain(row, 0)?,
+                start_ms: row.get::<_, i64>(1)? as u64,
+                end_ms: row.get::<_, i64>(2)? as u64,
+                confidence: row.get::<_, Option<f64>>(3)?.map(|c| c as f32),
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+pub fn record_metrics(metrics: &UtteranceMetrics) -> Result<(), AppError> {
+    with_db(|db| {
+        db.execute(
+            "INSERT OR REPLACE INTO utterance_metrics
+                (transcript_id, session_id, backend, timestamp, latency_ms, stt_ms, audio_ms,
+                 realtime_factor, words, words_per_minute)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                metrics.transcript_id,
+                metrics.session_id,
+                metrics.backend,
+                metrics.timestamp as i64,
+                metrics.latency_ms,
+                metrics.stt_ms,
+                metrics.audio_ms.map(|ms| ms as i64),
+                metrics.realtime_factor,
+                metrics.words as i64,
+                metrics.words_per_minute,
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+// Oldest first.
+pub fn session_metrics(session_id: &str) -> Result<Vec<UtteranceMetrics>, AppError> {
+    with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT transcript_id, session_id, backend, timestamp, latency_ms, stt_ms, audio_ms,
+                    realtime_factor, words, words_per_minute
+             FROM utterance_metrics WHERE session_id = ?1 ORDER BY timestamp",
+        )?;
+        let rows = statement.query_map(params![session_id], |row| {
+            Ok(UtteranceMetrics {
+                transcript_id: row.get(0)?,
+                session_id: row.get(1)?,
+                backend: row.get(2)?,
+                timestamp: row.get::<_, i64>(3)? as u64,
+                latency_ms: row.get(4)?,
+                stt_ms: row.get(5)?,
+                audio_ms: row.get::<_, Option<i64>>(6)?.map(|ms| ms as u64),
+                realtime_factor: row.get(7)?,
+                words: row.get::<_, i64>(8)? as u64,
+                words_per_minute: row.get(9)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+// Final transcripts only; partials are superseded within the second.
+pub fn spawn_recorder(bus: &EventBus, state: AppState) {
+    let mut rx = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(AgentEvent::Transcript(mut transcript)) if transcript.is_final => {
+                    transcript.text = redaction::text_for(Destination::Store, &transcript);
+                    transcript.words = redaction::words_for(Destination::Store, &transcript);
+                    let device = device(&state, &transcript);
+                    let result =
+                        tauri::async_runtime::spawn_blocking(move || record(&transcript, device))
+                            .await;
+                    if let Ok(Err(e)) = result {
+                        tracing::warn!(target: "history", "{}", e);
+                    }
+                }
+                Ok(AgentEvent::LatencyReport(metrics)) => {
+                    let result =
+                        tauri::async_runtime::spawn_blocking(move || record_metrics(&metrics))
+                            .await;
+                    if let Ok(Err(e)) = result {
+                        tracing::warn!(target: "history", "{}", e);
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// Columns in the order both queries select them.
+fn entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        text: plain(row, 1)?,
+        timestamp: row.get::<_, i64>(2)? as u64,
+        duration_ms: row.get::<_, Option<i64>>(3)?.map(|d| d as u64),
+        confidence: row.get::<_, Option<f64>>(4)?.map(|c| c as f32),
+        device: row.get(5)?,
+        session_id: row.get(6)?,
+        speaker: row.get(7)?,
+    })
+}
+
+pub fn query(
+    filter: &HistoryFilter,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let mut sql = "SELECT t.id, t.text, t.timestamp, t.duration_ms, t.confidence, t.device,
+                          t.session_id, t.speaker
+                   FROM transcripts t"
+        .to_string();
+    let mut conditions = Vec::new();
+    let mut values: Vec<SqlValue> = Vec::new();
+    let expression = filter.query.as_deref().and_then(match_expression);
+    // Encrypted text is searched once it is decrypted, so the page is cut
+    // here rather than in SQL.
+    let search = filter
+        .query
+        .as_deref()
+        .filter(|_| expression.is_some() && encryption::enabled());
+    if let (Some(expression), None) = (expression, search) {
+        sql.push_str(" JOIN transcripts_fts f ON f.rowid = t.rowid");
+        conditions.push("f.text MATCH ?");
+        values.push(SqlValue::Text(expression));
+    }
+    if let Some(session_id) = &filter.session_id {
+        conditions.push("t.session_id = ?");
+        values.push(SqlValue::Text(session_id.clone()));
+    }
+    if let Some(device) = &filter.device {
+        conditions.push("t.device = ?");
+        values.push(SqlValue::Text(device.clone()));
+    }
+    if let Some(since) = filter.since {
+        conditions.push("t.timestamp >= ?");
+        values.push(SqlValue::Integer(since as i64));
+    }
+    if let Some(until) = filter.until {
+        conditions.push("t.timestamp <= ?");
+        values.push(SqlValue::Integer(until as i64));
+    }
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql.push_str(" ORDER BY t.timestamp DESC");
+    if let Some(query) = search {
+        let entries: Vec<HistoryEntry> = with_db(|db| {
+            let mut statement = db.prepare(&sql)?;
+            let rows = statement.query_map(params_from_iter(values), entry)?;
+            rows.collect()
+        })?;
+        return Ok(entries
+            .into_iter()
+            .filter(|entry| matches(&entry.text, query))
+            .skip(offset as usize)
+            .take(limit.min(MAX_LIMIT) as usize)
+            .collect());
+    }
+    sql.push_str(" LIMIT ? OFFSET ?");
+    values.push(SqlValue::Integer(limit.min(MAX_LIMIT) as i64));
+    values.push(SqlValue::Integer(offset as i64));
+
+    with_db(|db| {
+        let mut statement = db.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), entry)?;
+        rows.collect()
+    })
+}
+
+pub fn get(id: &str) -> Result<HistoryEntry, AppError> {
+    let found = with_db(|db| {
+        db.query_row(
+            "SELECT id, text, timestamp, duration_ms, confidence, device, session_id, speaker
+             FROM transcripts WHERE id = ?1",
+            params![id],
+            entry,
+        )
+        .optional()
+    })?;
+    found.ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("No transcript with id {}", id)))
+}
+
+pub fn delete(id: &str) -> Result<(), AppError> {
+    let deleted = with_db(|db| db.execute("DELETE FROM transcripts WHERE id = ?1", params![id]))?;
+    if deleted == 0 {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No transcript with id {}", id),
+        ));
+    }
+    recordings::delete(id);
+    Ok(())
+}
+
+pub fn clear() -> Result<(), AppError> {
+    with_db(|db| {
+        db.execute_batch("DELETE FROM transcripts; DELETE FROM session_transcripts; DELETE FROM sessions; DELETE FROM utterance_metrics; DELETE FROM transcript_words; DELETE FROM session_summaries; VACUUM;")
+    })?;
+    recordings::clear();
+    Ok(())
+}
+
+// Rewrites text and words that are not yet in the wanted form. The index is
+// emptied when they are encrypted and rebuilt when they are not, and the
+// database is vacuumed so no plain copies stay in free pages.
+pub fn migrate(encrypt: bool) -> Result<usize, AppError> {
+    let convert = |stored: &str| -> Result<Option<String>, AppError> {
+        if encryption::is_sealed_text(stored) == encrypt {
+            Ok(None)
+        } else if encrypt {
+            encryption::seal_text(stored).map(Some)
+        } else {
+            encryption::open_text(stored).map(Some)
+        }
+    };
+    let (texts, words, summaries) = with_db(|db| {
+        let texts = db
+            .prepare("SELECT id, text FROM transcripts")?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let words = db
+            .prepare("SELECT transcript_id, position, word FROM transcript_words")?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let summaries = db
+            .prepare("SELECT session_id, chunk_start, summary FROM session_summaries")?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok((texts, words, summaries))
+    })?;
+    let mut changed_texts = Vec::new();
+    for (id, text) in texts {
+        if let Some(text) = convert(&text)? {
+            changed_texts.push((id, text));
+        }
+    }
+    let mut changed_words = Vec::new();
+    for (id, position, word) in words {
+        if let Some(word) = convert(&word)? {
+            changed_words.push((id, position, word));
+        }
+    }
+    let mut changed_summaries = Vec::new();
+    for (id, chunk_start, summary) in summaries {
+        if let Some(summary) = convert(&summary)? {
+            changed_summaries.push((id, chunk_start, summary));
+        }
+    }
+    let count = changed_texts.len();
+    with_db(|db| {
+        let transaction = db.unchecked_transaction()?;
+        for (id, text) in &changed_texts {
+            transaction.execute(
+                "UPDATE transcripts SET text = ?2 WHERE id = ?1",
+                params![id, text],
+            )?;
+        }
+        for (id, position, word) in &changed_words {
+            transaction.execute(
+                "UPDATE transcript_words SET word = ?3 WHERE transcript_id = ?1 AND position = ?2",
+                params![id, position, word],
+            )?;
+        }
+        for (id, chunk_start, summary) in &changed_summaries {
+            transaction.execute(
+                "UPDATE session_summaries SET summary = ?3 WHERE session_id = ?1 AND chunk_start = ?2",
+                params![id, chunk_start, summary],
+            )?;
+        }
+        transaction.execute(
+            "INSERT INTO transcripts_fts (transcripts_fts) VALUES (?1)",
+            params![if encrypt { "delete-all" } else { "rebuild" }],
+        )?;
+        transaction.commit()?;
+        db.execute_batch("VACUUM")
+    })?;
+    Ok(count)
+}
+
+fn session(row: &Row) -> rusqlite::Result<DictationSession> {
+    let mut session = DictationSession {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        started_at: row.get::<_, i64>(2)? as u64,
+        ended_at: row.get::<_, Option<i64>>(3)?.map(|t| t as u64),
+        duration_ms: 0,
+        word_count: row.get::<_, i64>(4)? as u64,
+        transcript_count: row.get::<_, i64>(5)? as u64,
+    };
+    session.update_duration();
+    Ok(session)
+}
+
+pub fn save_session(session: &DictationSession) -> Result<(), AppError> {
+    with_db(|db| {
+        db.execute(
+            "INSERT OR REPLACE INTO sessions
+                (id, name, started_at, ended_at, word_count, transcript_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                session.id,
+                session.name,
+                session.started_at as i64,
+                session.ended_at.map(|t| t as i64),
+                session.word_count as i64,
+                session.transcript_count as i64,
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+pub fn link_transcript(session: &DictationSession, transcript_id: &str) -> Result<(), AppError> {
+    save_session(session)?;
+    with_db(|db| {
+        db.execute(
+            "INSERT OR IGNORE INTO session_transcripts (session_id, transcript_id) VALUES (?1, ?2)",
+            params![session.id, transcript_id],
+        )
+        .map(|_| ())
+    })
+}
+
+// A session still open after a crash or quit ends with its last transcript.
+pub fn close_open_sessions() -> Result<(), AppError> {
+    with_db(|db| {
+        db.execute(
+            "UPDATE sessions SET ended_at = COALESCE(
+                (SELECT MAX(t.timestamp) FROM transcripts t
+                 JOIN session_transcripts l ON l.transcript_id = t.id
+                 WHERE l.session_id = sessions.id),
+                started_at)
+             WHERE ended_at IS NULL",
+            [],
+        )
+        .map(|_| ())
+    })
+}
+
+pub fn sessions(limit: u32, offset: u32) -> Result<Vec<DictationSession>, AppError> {
+    with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT id, name, started_at, ended_at, word_count, transcript_count
+             FROM sessions ORDER BY started_at DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let rows =
+            statement.query_map(params![limit.min(MAX_LIMIT) as i64, offset as i64], session)?;
+        rows.collect()
+    })
+}
+
+pub fn get_session(id: &str) -> Result<(DictationSession, Vec<HistoryEntry>), AppError> {
+    let found = with_db(|db| {
+        db.query_row(
+            "SELECT id, name, started_at, ended_at, word_count, transcript_count
+             FROM sessions WHERE id = ?1",
+            params![id],
+            session,
+        )
+        .optional()
+    })?;
+    let found = found
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("No session with id {}", id)))?;
+    let transcripts = with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT t.id, t.text, t.timestamp, t.duration_ms, t.confidence, t.device,
+                    t.session_id, t.speaker
+             FROM transcripts t JOIN session_transcripts l ON l.transcript_id = t.id
+             WHERE l.session_id = ?1 ORDER BY t.timestamp",
+        )?;
+        let rows = statement.query_map(params![id], entry)?;
+        rows.collect()
+    })?;
+    Ok((found, transcripts))
+}
+
+pub fn save_summary(summary: &MeetingSummary) -> Result<(), AppError> {
+    let text = encryption::store_text(&summary.summary)?;
+    with_db(|db| {
+        db.execute(
+            "INSERT OR REPLACE INTO session_summaries (session_id, chunk_start, chunk_end, summary)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                summary.session_id,
+                summary.chunk_start as i64,
+                summary.chunk_end as i64,
+                text,
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+pub fn summaries(session_id: &str) -> Result<Vec<MeetingSummary>, AppError> {
+    with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT session_id, chunk_start, chunk_end, summary FROM session_summaries
+             WHERE session_id = ?1 ORDER BY chunk_start",
+        )?;
+        let rows = statement.query_map(params![session_id], |row| {
+            Ok(MeetingSummary {
+                session_id: row.get(0)?,
+                chunk_start: row.get::<_, i64>(1)? as u64,
+                chunk_end: row.get::<_, i64>(2)? as u64,
+                summary: plain(row, 3)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+#[tauri::command]
+pub async fn query_transcripts(
+    filter: Option<HistoryFilter>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> CommandResult<Vec<HistoryEntry>> {
+    tauri::async_runtime::spawn_blocking(move || {
+        query(
+            &filter.unwrap_or_default(),
+            limit.unwrap_or(DEFAULT_LIMIT),
+            offset.unwrap_or(0),
+        )
+    })
+    .await
+    .map_err(|e| AppError::from(format!("History task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn get_transcript_segments(id: String) -> CommandResult<Vec<Word>> {
+    tauri::async_runtime::spawn_blocking(move || {
+        get(&id)?;
+        words(&id)
+    })
+    .await
+    .map_err(|e| AppError::from(format!("History task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn delete_transcript(id: String) -> CommandResult<()> {
+    tauri::async_runtime::spawn_blocking(move || delete(&id))
+        .await
+        .map_err(|e| AppError::from(format!("History task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn clear_history() -> CommandResult<()> {
+    tauri::async_runtime::spawn_blocking(clear)
+        .await
+        .map_err(|e| AppError::from(format!("History task failed: {}", e)))?
+}
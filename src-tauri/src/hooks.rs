@@ -0,0 +1,327 @@
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Semaphore;
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::children;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::sandbox::{self, ProcessKind, SandboxPolicy};
+use crate::store;
+use crate::transcript::now_millis;
+
+const STORE_FILE: &str = "hooks.json";
+const MAX_CONCURRENT: usize = 4;
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const RUN_LOG_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    Transcript,
+    SessionEnded,
+    DaemonCrashed,
+}
+
+impl HookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::Transcript => "transcript",
+            HookEvent::SessionEnded => "session_ended",
+            HookEvent::DaemonCrashed => "daemon_crashed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub id: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub events: Vec<HookEvent>,
+    pub timeout_secs: Option<u64>,
+    pub enabled: bool,
+    // Replaces the shared hook policy for this hook only.
+    #[serde(default)]
+    pub sandbox: Option<SandboxPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookRun {
+    pub hook_id: String,
+    pub event: HookEvent,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+pub struct HookState {
+    hooks: Arc<Mutex<Vec<Hook>>>,
+    log: Arc<Mutex<VecDeque<HookRun>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl HookState {
+    pub fn load() -> Self {
+        Self {
+            hooks: Arc::new(Mutex::new(store::load_json(STORE_FILE))),
+            log: Arc::default(),
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT)),
+        }
+    }
+
+    fn record(&self, run: HookRun) {
+        let mut log = self.log.lock();
+        if log.len() == RUN_LOG_LEN {
+            log.pop_front();
+        }
+        log.push_back(run);
+    }
+}
+
+fn event_kind(event: &AgentEvent) -> Option<HookEvent> {
+    match event {
+        AgentEvent::Transcript(t) if t.is_final => Some(HookEvent::Transcript),
+        AgentEvent::SessionEnded { .. } => Some(HookEvent::SessionEnded),
+        AgentEvent::DaemonCrashed { .. } => Some(HookEvent::DaemonCrashed),
+        _ => None,
+    }
+}
+
+fn event_env(event: &AgentEvent) -> Vec<(&'static str, String)> {
+    match event {
+        AgentEvent::Transcript(t) => {
+            let mut env = vec![("VOICE_AGENT_TEXT", t.text.clone())];
+            if let Some(session_id) = &t.session_id {
+                env.push(("VOICE_AGENT_SESSION_ID", session_id.clone()));
+            }
+            env
+        }
+        AgentEvent::SessionEnded { session_id } => {
+            vec![("VOICE_AGENT_SESSION_ID", session_id.clone())]
+        }
+        AgentEvent::DaemonCrashed {
+            session_id,
+            exit_code,
+            ..
+        } => {
+            let mut env = vec![("VOICE_AGENT_SESSION_ID", session_id.clone())];
+            if let Some(code) = exit_code {
+                env.push(("VOICE_AGENT_EXIT_CODE", code.to_string()));
+            }
+            env
+        }
+        _ => Vec::new(),
+    }
+}
+
+pub fn spawn_dispatcher(state: HookState, bus: &EventBus) {
+    let mut rx = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let kind = match event_kind(&event) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            let mut payload = serde_json::to_value(&event).unwrap_or_default();
+            payload["timestamp"] = now_millis().into();
+            let payload = payload.to_string();
+            let env = event_env(&event);
+
+            let hooks: Vec<Hook> = state
+                .hooks
+                .lock()
+                .iter()
+                .filter(|h| h.enabled && h.events.contains(&kind))
+                .cloned()
+                .collect();
+            for hook in hooks {
+                tauri::async_runtime::spawn(run(
+                    state.clone(),
+                    hook,
+                    kind,
+                    payload.clone(),
+                    env.clone(),
+                ));
+            }
+        }
+    });
+}
+
+async fn run(
+    state: HookState,
+    hook: Hook,
+    kind: HookEvent,
+    payload: String,
+    env: Vec<(&'static str, String)>,
+) {
+    let _permit = match state.permits.clone().acquire_owned().await {
+        Ok(permit) => permit,
+        Err(_) => return,
+    };
+    let started = Instant::now();
+    let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+
+    let policy = hook
+        .sandbox
+        .clone()
+        .unwrap_or_else(|| sandbox::policy(ProcessKind::Hook));
+    let mut command = Command::from(sandbox::command(ProcessKind::Hook, &policy, &hook.command));
+    command
+        .args(&hook.args)
+        .env("VOICE_AGENT_EVENT", kind.as_str())
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let (exit_code, timed_out, error) = match command.spawn() {
+        Ok(mut child) => {
+            let _tracked = child.id().map(children::track);
+            if let Some(pid) = child.id() {
+                sandbox::confine(&policy, pid);
+            }
+            if let Some(mut stdin) = child.stdin.take() {
+                // Hooks that ignore stdin close it early; that is not an error.
+                let _ = stdin.write_all(payload.as_bytes()).await;
+            }
+            match tokio::time::timeout(timeout, child.wait()).await {
+                Ok(Ok(status)) => (status.code(), false, None),
+                Ok(Err(e)) => (None, false, Some(e.to_string())),
+                Err(_) => {
+                    let _ = child.kill().await;
+                    (None, true, Some(format!("Timed out after {:?}", timeout)))
+                }
+            }
+        }
+        Err(e) => (
+            None,
+            false,
+            Some(format!("Failed to run {}: {}", hook.command, e)),
+        ),
+    };
+
+    state.record(HookRun {
+        hook_id: hook.id,
+        event: kind,
+        exit_code,
+        timed_out,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+        timestamp: now_millis(),
+    });
+}
+
+#[tauri::command]
+pub fn list_hooks(state: tauri::State<'_, HookState>) -> Vec<Hook> {
+    state.hooks.lock().clone()
+}
+
+#[tauri::command]
+pub fn add_hook(
+    state: tauri::State<'_, HookState>,
+    command: String,
+    args: Option<Vec<String>>,
+    events: Vec<HookEvent>,
+    timeout_secs: Option<u64>,
+) -> CommandResult<Hook> {
+    if command.trim().is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Hook command cannot be empty",
+        ));
+    }
+    let hook = Hook {
+        id: uuid::Uuid::new_v4().to_string(),
+        command,
+        args: args.unwrap_or_default(),
+        events,
+        timeout_secs,
+        enabled: true,
+        sandbox: None,
+    };
+    let mut hooks = state.hooks.lock();
+    hooks.push(hook.clone());
+    store::save_json(STORE_FILE, &*hooks)?;
+    Ok(hook)
+}
+
+#[tauri::command]
+pub fn remove_hook(state: tauri::State<'_, HookState>, id: String) -> CommandResult<()> {
+    let mut hooks = state.hooks.lock();
+    let before = hooks.len();
+    hooks.retain(|h| h.id != id);
+    if hooks.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No hook with id {}", id),
+        ));
+    }
+    store::save_json(STORE_FILE, &*hooks)
+}
+
+#[tauri::command]
+pub fn set_hook_enabled(
+    state: tauri::State<'_, HookState>,
+    id: String,
+    enabled: bool,
+) -> CommandResult<()> {
+    let mut hooks = state.hooks.lock();
+    match hooks.iter_mut().find(|h| h.id == id) {
+        Some(hook) => hook.enabled = enabled,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No hook with id {}", id),
+            ))
+        }
+    }
+    store::save_json(STORE_FILE, &*hooks)
+}
+
+#[tauri::command]
+pub fn set_hook_sandbox(
+    state: tauri::State<'_, HookState>,
+    id: String,
+    sandbox: Option<SandboxPolicy>,
+) -> CommandResult<()> {
+    let mut hooks = state.hooks.lock();
+    match hooks.iter_mut().find(|h| h.id == id) {
+        Some(hook) => hook.sandbox = sandbox,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No hook with id {}", id),
+            ))
+        }
+    }
+    store::save_json(STORE_FILE, &*hooks)
+}
+
+#[tauri::command]
+pub fn get_hook_runs(state: tauri::State<'_, HookState>, limit: Option<usize>) -> Vec<HookRun> {
+    let log = state.log.lock();
+    log.iter()
+        .rev()
+        .take(limit.unwrap_or(RUN_LOG_LEN))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,213 @@
+use std::path::PathBuf;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+use crate::bus::AgentEvent;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "i18n.json";
+const FALLBACK: &str = "en-US";
+// Translations shipped with the app. Community ones live in
+// `<data dir>/locales/<locale>.ftl` and add languages or override messages.
+const BUNDLED: [(&str, &str); 2] = [
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct LanguageConfig {
+    // None follows the system locale.
+    language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageSettings {
+    pub language: Option<String>,
+    pub system: Option<String>,
+    pub active: String,
+    pub available: Vec<String>,
+}
+
+struct Catalog {
+    locale: String,
+    bundle: FluentBundle<FluentResource>,
+    // Messages a translation has not caught up with yet.
+    fallback: FluentBundle<FluentResource>,
+}
+
+static CATALOG: Lazy<RwLock<Catalog>> = Lazy::new(|| {
+    let config: LanguageConfig = store::load_json(STORE_FILE);
+    RwLock::new(Catalog::load(&resolve(config.language.as_deref())))
+});
+
+impl Catalog {
+    fn load(locale: &str) -> Self {
+        tracing::info!(target: "i18n", "Using locale {}", locale);
+        Self {
+            locale: locale.to_string(),
+            bundle: bundle(locale),
+            fallback: bundle(FALLBACK),
+        }
+    }
+}
+
+fn community_dir() -> PathBuf {
+    store::data_dir().join("locales")
+}
+
+fn add_resource(bundle: &mut FluentBundle<FluentResource>, locale: &str, source: String) {
+    let resource = match FluentResource::try_new(source) {
+        Ok(resource) => resource,
+        // The messages that did parse are still usable.
+        Err((resource, errors)) => {
+            tracing::warn!(target: "i18n", "{} has {} syntax errors", locale, errors.len());
+            resource
+        }
+    };
+    bundle.add_resource_overriding(resource);
+}
+
+fn bundle(locale: &str) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = locale.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    // Isolation marks render as stray glyphs in native tray menus.
+    bundle.set_use_isolating(false);
+    if let Some((_, source)) = BUNDLED.iter().find(|(l, _)| *l == locale) {
+        add_resource(&mut bundle, locale, source.to_string());
+    }
+    let path = community_dir().join(format!("{}.ftl", locale));
+    if let Ok(source) = std::fs::read_to_string(&path) {
+        add_resource(&mut bundle, locale, source);
+    }
+    bundle
+}
+
+pub fn available() -> Vec<String> {
+    let mut locales: Vec<String> = BUNDLED.iter().map(|(l, _)| l.to_string()).collect();
+    if let Ok(entries) = std::fs::read_dir(community_dir()) {
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().map_or(true, |e| e != "ftl") {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                if !locales.iter().any(|l| l == stem) {
+                    locales.push(stem.to_string());
+                }
+            }
+        }
+    }
+    locales.sort();
+    locales
+}
+
+// POSIX locales look like `de_DE.UTF-8@euro`.
+pub fn system_locale() -> Option<String> {
+    let locale = sys_locale::get_locale()?;
+    let locale = locale.split(|c| c == '.' || c == '@').next()?;
+    Some(locale.replace('_', "-"))
+}
+
+// An exact match wins, then the same language in another region.
+fn negotiate(requested: &str, available: &[String]) -> Option<String> {
+    let requested: LanguageIdentifier = requested.parse().ok()?;
+    let candidates: Vec<(&String, LanguageIdentifier)> = available
+        .iter()
+        .filter_map(|l| Some((l, l.parse().ok()?)))
+        .collect();
+    candidates
+        .iter()
+        .find(|(_, id)| *id == requested)
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|(_, id)| id.language == requested.language)
+        })
+        .map(|(locale, _)| locale.to_string())
+}
+
+fn resolve(language: Option<&str>) -> String {
+    language
+        .map(str::to_string)
+        .or_else(system_locale)
+        .and_then(|requested| negotiate(&requested, &available()))
+        .unwrap_or_else(|| FALLBACK.to_string())
+}
+
+pub fn active_locale() -> String {
+    CATALOG.read().locale.clone()
+}
+
+fn format(id: &str, args: Option<&FluentArgs>) -> String {
+    let catalog = CATALOG.read();
+    for bundle in [&catalog.bundle, &catalog.fallback] {
+        if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+            let mut errors = Vec::new();
+            return bundle
+                .format_pattern(pattern, args, &mut errors)
+                .into_owned();
+        }
+    }
+    tracing::warn!(target: "i18n", "Missing message {}", id);
+    id.to_string()
+}
+
+pub fn t(id: &str) -> String {
+    format(id, None)
+}
+
+pub fn t_args(id: &str, args: &[(&str, &str)]) -> String {
+    let mut fluent = FluentArgs::new();
+    for (name, value) in args {
+        fluent.set(*name, *value);
+    }
+    format(id, Some(&fluent))
+}
+
+fn settings() -> LanguageSettings {
+    let config: LanguageConfig = store::load_json(STORE_FILE);
+    LanguageSettings {
+        language: config.language,
+        system: system_locale(),
+        active: active_locale(),
+        available: available(),
+    }
+}
+
+#[tauri::command]
+pub fn get_language_settings() -> LanguageSettings {
+    settings()
+}
+
+// Also picks up edits to community translation files.
+#[tauri::command]
+pub fn set_language(
+    state: tauri::State<'_, AppState>,
+    language: Option<String>,
+) -> CommandResult<LanguageSettings> {
+    if let Some(language) = &language {
+        if language.parse::<LanguageIdentifier>().is_err() {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Invalid language tag {}", language),
+            ));
+        }
+    }
+    store::save_json(
+        STORE_FILE,
+        &LanguageConfig {
+            language: language.clone(),
+        },
+    )?;
+    let locale = resolve(language.as_deref());
+    *CATALOG.write() = Catalog::load(&locale);
+    state.bus.publish(AgentEvent::LanguageChanged(locale));
+    Ok(settings())
+}
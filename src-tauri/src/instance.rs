@@ -0,0 +1,147 @@
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fs2::FileExt;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::bus::AgentEvent;
+use crate::control_socket;
+use crate::daemon;
+use crate::session;
+use crate::store;
+use crate::AppState;
+
+// How long a second launch waits for an instance that holds the lock but has
+// not opened its control socket yet.
+const STARTUP_WAIT: Duration = Duration::from_secs(10);
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+static QUEUE: OnceCell<Mutex<Sender<PathBuf>>> = OnceCell::new();
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+// Held until exit; the OS drops the lock with the process, even on a crash.
+static LOCK: OnceCell<File> = OnceCell::new();
+
+pub enum Handoff {
+    None,
+    // The running instance has a window and took over the launch.
+    Activated,
+    // The running instance is a headless core with no UI of its own.
+    Core,
+    // Another instance holds the lock but never answered.
+    Unreachable,
+}
+
+pub fn mark_headless() {
+    HEADLESS.store(true, Ordering::SeqCst);
+}
+
+pub fn is_headless() -> bool {
+    HEADLESS.load(Ordering::SeqCst)
+}
+
+// Files handed over on launch are transcribed one at a time, in order, on a
+// single worker so repeated launches cannot pile up daemons.
+pub fn queue_files(state: &AppState, files: Vec<PathBuf>) {
+    if files.is_empty() {
+        return;
+    }
+    let queue = QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+        let state = state.clone();
+        thread::spawn(move || {
+            for path in rx {
+                tracing::info!(target: "instance", "Transcribing {}", path.display());
+                if let Err(message) = daemon::transcribe_file(&state, &path) {
+                    state.bus.publish(AgentEvent::error("activation", message));
+                }
+            }
+        });
+        Mutex::new(tx)
+    });
+    let queue = queue.lock();
+    for file in files {
+        let _ = queue.send(file);
+    }
+}
+
+fn claim_lock() -> bool {
+    let dir = store::data_dir();
+    let _ = std::fs::create_dir_all(&dir);
+    let path = dir.join(format!("instance-{}.lock", session::namespace()));
+    let file = match OpenOptions::new().create(true).write(true).open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            // Without a lock file the control socket check is all there is.
+            tracing::warn!(target: "instance", "Failed to open {}: {}", path.display(), e);
+            return true;
+        }
+    };
+    if file.try_lock_exclusive().is_err() {
+        return false;
+    }
+    let _ = LOCK.set(file);
+    true
+}
+
+// Either hands the launch over to the running instance or makes this the one
+// instance. Two launches at once both miss the control socket, so the lock
+// decides which of them starts and the other keeps forwarding until the
+// winner is listening.
+pub fn hand_off(files: &[PathBuf]) -> Handoff {
+    let started = Instant::now();
+    loop {
+        match forward_to_running(files) {
+            Handoff::None => {}
+            handoff => return handoff,
+        }
+        if claim_lock() {
+            return Handoff::None;
+        }
+        if started.elapsed() >= STARTUP_WAIT {
+            return Handoff::Unreachable;
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+// Asks an already running instance to come to the front and take `files`.
+// The whole command line goes along for the frontend.
+fn forward_to_running(files: &[PathBuf]) -> Handoff {
+    // The running instance has its own working directory.
+    let files: Vec<PathBuf> = files
+        .iter()
+        .map(|f| std::fs::canonicalize(f).unwrap_or_else(|_| f.clone()))
+        .collect();
+    tauri::async_runtime::block_on(async move {
+        let stream = match control_socket::connect().await {
+            Ok(stream) => stream,
+            Err(_) => return Handoff::None,
+        };
+        let (reader, mut writer) = tokio::io::split(stream);
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let mut request =
+            json!({ "command": "activate", "files": files, "args": args }).to_string();
+        request.push('\n');
+        if writer.write_all(request.as_bytes()).await.is_err() {
+            return Handoff::None;
+        }
+        let reply = match BufReader::new(reader).lines().next_line().await {
+            Ok(Some(line)) => serde_json::from_str::<Value>(&line).unwrap_or_default(),
+            _ => return Handoff::None,
+        };
+        if reply["ok"] != json!(true) {
+            Handoff::None
+        } else if reply["result"]["ui"] == json!(false) {
+            Handoff::Core
+        } else {
+            Handoff::Activated
+        }
+    })
+}
@@ -0,0 +1,587 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Barrier, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use axum::http::Method;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::Receiver;
+
+use crate::auth::{self, Scope};
+use crate::bus::{AgentEvent, EventBus};
+use crate::daemon::{self, DaemonState, InputSource};
+use crate::error::{AppError, ErrorCode};
+use crate::history::{self, HistoryFilter};
+use crate::lifecycle::{Operation, OperationState};
+use crate::mcp;
+use crate::models::SttBackend;
+use crate::redaction::{self, RedactionKind, RedactionRule};
+use crate::simulated_input::SimulatedInput;
+use crate::transcript::{now_millis, Transcript, Word};
+use crate::AppState;
+
+const EVENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn isolate_data_dir() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("voice-agent-tests-{}", std::process::id()));
+        std::env::set_var("VOICE_AGENT_DATA_DIR", dir);
+    });
+}
+
+fn script(steps: Value) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mock-stt-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(&path, json!({ "steps": steps }).to_string()).unwrap();
+    path
+}
+
+fn mock_state(steps: Value) -> (AppState, Receiver<AgentEvent>) {
+    isolate_data_dir();
+    let state = AppState::new(EventBus::new());
+    state.models.set_backend(SttBackend::Mock {
+        script: script(steps),
+    });
+    let events = state.bus.subscribe();
+    (state, events)
+}
+
+// Waits for the next event the daemon lifecycle cares about, skipping
+// incidental ones such as model status.
+fn next_event(events: &mut Receiver<AgentEvent>) -> AgentEvent {
+    tauri::async_runtime::block_on(async {
+        loop {
+            let event = tokio::time::timeout(EVENT_TIMEOUT, events.recv())
+                .await
+                .expect("timed out waiting for an event")
+                .expect("event bus closed");
+            match event {
+                AgentEvent::Transcript(_)
+                | AgentEvent::ListeningChanged(_)
+                | AgentEvent::SessionEnded { .. }
+                | AgentEvent::DaemonCrashed { .. }
+                | AgentEvent::Error { .. } => return event,
+                _ => continue,
+            }
+        }
+    })
+}
+
+fn transcript_text(event: AgentEvent) -> String {
+    match event {
+        AgentEvent::Transcript(t) => t.text,
+        other => panic!("expected a transcript, got {:?}", other),
+    }
+}
+
+#[test]
+fn start_publishes_listening_then_transcripts_in_order() {
+    let (state, mut events) = mock_state(json!([
+        { "delay_ms": 50, "text": "first" },
+        { "delay_ms": 50, "text": "second" },
+        { "delay_ms": 50, "text": "third" },
+    ]));
+    daemon::start(&state, InputSource::Microphone).unwrap();
+
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(true)
+    ));
+    let session_id = daemon::session_id(&state).unwrap();
+    for expected in ["first", "second", "third"] {
+        match next_event(&mut events) {
+            AgentEvent::Transcript(t) => {
+                assert_eq!(t.text, expected);
+                assert_eq!(t.session_id.as_deref(), Some(session_id.as_str()));
+            }
+            other => panic!("expected a transcript, got {:?}", other),
+        }
+    }
+    assert_eq!(
+        state
+            .last_transcript
+            .lock()
+            .as_ref()
+            .map(|t| t.text.clone()),
+        Some("third".to_string())
+    );
+    daemon::stop(&state).unwrap();
+}
+
+#[test]
+fn stop_ends_the_session_before_reporting_idle() {
+    let (state, mut events) = mock_state(json!([]));
+    daemon::start(&state, InputSource::Microphone).unwrap();
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(true)
+    ));
+    let session_id = daemon::session_id(&state).unwrap();
+
+    daemon::stop(&state).unwrap();
+    match next_event(&mut events) {
+        AgentEvent::SessionEnded { session_id: ended } => assert_eq!(ended, session_id),
+        other => panic!("expected the session to end, got {:?}", other),
+    }
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(false)
+    ));
+    assert!(!daemon::is_running(&state));
+    assert_eq!(daemon::session_id(&state), None);
+    assert_eq!(daemon::input_source(&state), None);
+}
+
+#[test]
+fn second_start_and_idle_stop_are_rejected() {
+    let (state, _events) = mock_state(json!([]));
+    assert_eq!(
+        daemon::stop(&state).unwrap_err().code,
+        ErrorCode::NotRunning
+    );
+    daemon::start(&state, InputSource::Microphone).unwrap();
+    assert_eq!(
+        daemon::start(&state, InputSource::Stream).unwrap_err().code,
+        ErrorCode::AlreadyRunning
+    );
+    assert_eq!(daemon::input_source(&state), Some(InputSource::Microphone));
+    daemon::stop(&state).unwrap();
+    assert_eq!(
+        daemon::stop(&state).unwrap_err().code,
+        ErrorCode::NotRunning
+    );
+}
+
+#[test]
+fn streamed_audio_is_drained_through_the_ring() {
+    let (state, mut events) = mock_state(json!([]));
+    daemon::start(&state, InputSource::Stream).unwrap();
+    let session_id = daemon::session_id(&state).unwrap();
+    daemon::push_audio(&state, &[0; 32_000]).unwrap();
+
+    let buffer = tauri::async_runtime::block_on(async {
+        loop {
+            let event = tokio::time::timeout(EVENT_TIMEOUT, events.recv())
+                .await
+                .expect("timed out waiting for audio stats")
+                .expect("event bus closed");
+            match event {
+                AgentEvent::AudioStats {
+                    session_id: id,
+                    buffer,
+                } if id == session_id && buffer.used_bytes == 0 => return buffer,
+                _ => continue,
+            }
+        }
+    });
+    assert_eq!(buffer.dropped_bytes, 0);
+    assert!(buffer.capacity_bytes >= 32_000);
+    daemon::stop(&state).unwrap();
+}
+
+#[test]
+fn concurrent_lifecycle_requests_spawn_one_daemon() {
+    let (state, mut events) = mock_state(json!([]));
+    let race = |op: fn(&AppState) -> Result<String, AppError>| {
+        let barrier = Arc::new(Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (state, barrier) = (state.clone(), barrier.clone());
+                thread::spawn(move || {
+                    barrier.wait();
+                    op(&state)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    // Requests that arrive while the first runs either share its result or
+    // find the daemon already in the state they asked for.
+    let starts = race(|state| daemon::start(state, InputSource::Microphone));
+    assert!(starts
+        .iter()
+        .all(|r| r.is_ok() || r.as_ref().unwrap_err().code == ErrorCode::AlreadyRunning));
+    assert!(daemon::is_running(&state));
+    let stops = race(daemon::stop);
+    assert!(stops
+        .iter()
+        .all(|r| r.is_ok() || r.as_ref().unwrap_err().code == ErrorCode::NotRunning));
+    assert!(!daemon::is_running(&state));
+    assert!(state.stt_process.lock().is_none());
+
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(true)
+    ));
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::SessionEnded { .. }
+    ));
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(false)
+    ));
+    assert!(state.lifecycle.pending().is_empty());
+}
+
+#[test]
+fn lifecycle_requests_only_join_the_last_waiting_one() {
+    let (state, mut events) = mock_state(json!([]));
+    let submit = |op: fn(&AppState) -> Result<String, AppError>| {
+        let state = state.clone();
+        thread::spawn(move || op(&state))
+    };
+    let wait_for_pending = |len: usize| {
+        let deadline = Instant::now() + EVENT_TIMEOUT;
+        while state.lifecycle.pending().len() != len {
+            assert!(Instant::now() < deadline, "timed out waiting for the queue");
+            thread::sleep(Duration::from_millis(5));
+        }
+    };
+
+    // Holding the process lock keeps the worker on its first request while
+    // the rest queue up behind it.
+    let process = state.stt_process.lock();
+    let blocker = submit(daemon::stop);
+    tauri::async_runtime::block_on(async {
+        loop {
+            let event = tokio::time::timeout(EVENT_TIMEOUT, events.recv())
+                .await
+                .expect("timed out waiting for an event")
+                .expect("event bus closed");
+            if let AgentEvent::OperationProgress(progress) = event {
+                if progress.state == OperationState::Running {
+                    break;
+                }
+            }
+        }
+    });
+    let first = submit(|state| daemon::start(state, InputSource::Microphone));
+    wait_for_pending(1);
+    let stop = submit(daemon::stop);
+    wait_for_pending(2);
+    let last = submit(|state| daemon::start(state, InputSource::Microphone));
+    wait_for_pending(3);
+    let start = Operation::Start {
+        source: InputSource::Microphone,
+    };
+    assert_eq!(
+        state.lifecycle.pending(),
+        vec![start, Operation::Stop, start]
+    );
+    drop(process);
+
+    assert_eq!(
+        blocker.join().unwrap().unwrap_err().code,
+        ErrorCode::NotRunning
+    );
+    assert!(first.join().unwrap().is_ok());
+    assert!(stop.join().unwrap().is_ok());
+    assert!(last.join().unwrap().is_ok());
+    assert!(daemon::is_running(&state));
+    daemon::stop(&state).unwrap();
+}
+
+#[test]
+fn daemon_exit_is_reported_as_a_crash_and_clears_state() {
+    let (state, mut events) = mock_state(json!([
+        { "delay_ms": 50, "text": "before the crash" },
+        { "delay_ms": 50, "exit": 3 },
+    ]));
+    daemon::start(&state, InputSource::Microphone).unwrap();
+    let session_id = daemon::session_id(&state).unwrap();
+
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(true)
+    ));
+    assert_eq!(transcript_text(next_event(&mut events)), "before the crash");
+    match next_event(&mut events) {
+        AgentEvent::DaemonCrashed {
+            session_id: crashed,
+            exit_code,
+            source,
+        } => {
+            assert_eq!(crashed, session_id);
+            assert_eq!(exit_code, Some(3));
+            assert_eq!(source, InputSource::Microphone);
+        }
+        other => panic!("expected a crash, got {:?}", other),
+    }
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::SessionEnded { .. }
+    ));
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(false)
+    ));
+    assert!(!daemon::is_running(&state));
+    assert!(state.stt_process.lock().is_none());
+
+    // A crashed daemon must not block the next session.
+    daemon::start(&state, InputSource::Microphone).unwrap();
+    assert_ne!(daemon::session_id(&state), Some(session_id));
+    daemon::stop(&state).ok();
+}
+
+#[test]
+fn heartbeat_reports_a_responsive_daemon_and_counts_restarts() {
+    let (state, _events) = mock_state(json!([]));
+    assert_eq!(daemon::daemon_status(&state).state, DaemonState::Stopped);
+    daemon::start(&state, InputSource::Microphone).unwrap();
+
+    let started = Instant::now();
+    while daemon::daemon_status(&state).state != DaemonState::Running {
+        assert!(started.elapsed() < EVENT_TIMEOUT, "no heartbeat answered");
+        thread::sleep(Duration::from_millis(20));
+    }
+    let status = daemon::daemon_status(&state);
+    assert!(status.last_heartbeat.is_some());
+    assert!(status.uptime_ms.is_some());
+    assert_eq!(status.session_id, daemon::session_id(&state));
+    assert_eq!(status.restart_count, 0);
+
+    daemon::restart(&state).unwrap();
+    assert_eq!(daemon::daemon_status(&state).restart_count, 1);
+    daemon::stop(&state).unwrap();
+    let status = daemon::daemon_status(&state);
+    assert_eq!(status.state, DaemonState::Stopped);
+    assert_eq!(status.uptime_ms, None);
+    assert_eq!(status.last_heartbeat, None);
+}
+
+#[test]
+fn listening_daemon_answers_configure_until_stopped() {
+    let (state, _events) = mock_state(json!([]));
+    daemon::start(&state, InputSource::Microphone).unwrap();
+    daemon::configure(&state, json!({ "paused": true, "language": null })).unwrap();
+    daemon::stop(&state).unwrap();
+    let error = daemon::configure(&state, json!({ "paused": false })).unwrap_err();
+    assert_eq!(error.code, ErrorCode::NotRunning);
+}
+
+#[test]
+fn finished_simulated_input_ends_the_session_without_a_crash() {
+    let (state, mut events) = mock_state(json!([{ "delay_ms": 50, "text": "from the file" }]));
+    let wav = std::env::temp_dir().join(format!("simulated-{}.wav", uuid::Uuid::new_v4()));
+    std::fs::write(&wav, b"RIFF").unwrap();
+    // Set in memory only; the data dir is shared by every test.
+    *state.simulated_input.lock() = Some(SimulatedInput::new(wav, 4.0).unwrap());
+    daemon::start(&state, InputSource::Microphone).unwrap();
+
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(true)
+    ));
+    assert_eq!(transcript_text(next_event(&mut events)), "from the file");
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::SessionEnded { .. }
+    ));
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(false)
+    ));
+    assert!(!daemon::is_running(&state));
+}
+
+#[test]
+fn rapid_restarts_never_report_a_crash() {
+    let (state, mut events) = mock_state(json!([{ "delay_ms": 10, "text": "hello" }]));
+    for _ in 0..10 {
+        daemon::start(&state, InputSource::Microphone).unwrap();
+        daemon::stop(&state).unwrap();
+    }
+    // The last reader thread to notice its closed stdout must have seen a
+    // stale session and stayed quiet.
+    thread::sleep(Duration::from_millis(500));
+    while let Ok(event) = events.try_recv() {
+        assert!(
+            !matches!(event, AgentEvent::DaemonCrashed { .. }),
+            "stop() was reported as a crash"
+        );
+    }
+    assert!(!daemon::is_running(&state));
+}
+
+#[test]
+fn transcribe_file_joins_scripted_segments() {
+    let (state, mut events) = mock_state(json!([
+        { "text": "one", "confidence": 0.5 },
+        { "text": "two", "confidence": 1.0 },
+    ]));
+    let file = script(json!([]));
+    let transcript = daemon::transcribe_file(&state, &file).unwrap();
+    assert_eq!(transcript.text, "one two");
+    assert_eq!(transcript.confidence, Some(0.75));
+    assert_eq!(transcript.start_ms, Some(0));
+    assert_eq!(transcript.end_ms, Some(2000));
+    assert_eq!(transcript_text(next_event(&mut events)), "one two");
+    assert!(!daemon::is_running(&state));
+}
+
+#[test]
+fn diarized_segments_keep_their_speakers() {
+    let (state, _events) = mock_state(json!([
+        { "text": "hi", "speaker": "Speaker 1" },
+        { "text": "hello", "speaker": "Speaker 2" },
+    ]));
+    let file = script(json!([]));
+    let segments = daemon::transcribe_segments(&state, &file).unwrap();
+    let speakers: Vec<_> = segments.iter().map(|s| s.speaker.as_deref()).collect();
+    assert_eq!(speakers, [Some("Speaker 1"), Some("Speaker 2")]);
+    assert_eq!(daemon::join_segments(&state, &segments).speaker, None);
+    assert_eq!(
+        daemon::join_segments(&state, &segments[..1])
+            .speaker
+            .as_deref(),
+        Some("Speaker 1")
+    );
+}
+
+#[test]
+fn transcribe_file_reports_daemon_failures() {
+    let (state, _events) = mock_state(json!([{ "exit": 2, "error": "decoder blew up" }]));
+    let file = script(json!([]));
+    let error = daemon::transcribe_file(&state, &file).unwrap_err();
+    assert_eq!(error.code, ErrorCode::Failed);
+    assert!(error.message.contains("decoder blew up"), "{}", error);
+}
+
+#[test]
+fn transcribing_a_missing_file_is_not_found() {
+    let (state, _events) = mock_state(json!([]));
+    let error =
+        daemon::transcribe_file(&state, &PathBuf::from("/nonexistent/audio.wav")).unwrap_err();
+    assert_eq!(error.code, ErrorCode::NotFound);
+    assert!(error.recoverable);
+}
+
+#[test]
+fn mcp_tools_drive_listening_and_read_back_transcripts() {
+    let (state, mut events) = mock_state(json!([{ "delay_ms": 50, "text": "hello from mcp" }]));
+    let call = |id: u64, method: &str, params: Value| {
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        tauri::async_runtime::block_on(mcp::handle(&state, &request.to_string())).unwrap()
+    };
+
+    let tools = call(1, "tools/list", json!({}));
+    assert_eq!(tools["result"]["tools"].as_array().unwrap().len(), 4);
+    let started = call(2, "tools/call", json!({ "name": "start_listening" }));
+    assert_eq!(started["result"]["isError"], false);
+    assert!(matches!(
+        next_event(&mut events),
+        AgentEvent::ListeningChanged(true)
+    ));
+    assert_eq!(transcript_text(next_event(&mut events)), "hello from mcp");
+
+    let last = call(3, "tools/call", json!({ "name": "get_last_transcript" }));
+    assert_eq!(last["result"]["content"][0]["text"], "hello from mcp");
+    let unknown = call(4, "tools/call", json!({ "name": "self_destruct" }));
+    assert_eq!(unknown["error"]["code"], -32602);
+    daemon::stop(&state).unwrap();
+}
+
+#[test]
+fn mcp_routes_need_the_call_tools_scope() {
+    assert_eq!(
+        auth::required_scope(&Method::GET, "/mcp/ws"),
+        Some(Scope::CallTools)
+    );
+    assert_eq!(
+        auth::required_scope(&Method::POST, "/mcp"),
+        Some(Scope::CallTools)
+    );
+    assert_eq!(
+        auth::required_scope(&Method::POST, "/streamdeck/toggle"),
+        Some(Scope::ControlListening)
+    );
+    assert_eq!(
+        auth::required_scope(&Method::GET, "/captions/ws"),
+        Some(Scope::ReadTranscripts)
+    );
+    assert_eq!(auth::required_scope(&Method::GET, "/mic"), None);
+}
+
+#[test]
+fn card_numbers_are_masked_next_to_other_digits() {
+    let rule = redaction::compile(&RedactionRule {
+        kind: RedactionKind::CreditCard,
+        enabled: true,
+        words: Vec::new(),
+        pattern: None,
+        replacement: None,
+    })
+    .unwrap();
+    assert_eq!(
+        redaction::apply(&rule, "card 4111 1111 1111 1111 exp 12"),
+        "card [card number] exp 12"
+    );
+    assert_eq!(
+        redaction::apply(&rule, "card 4111 1111 1111 1111 2025"),
+        "card [card number] 2025"
+    );
+    assert_eq!(
+        redaction::apply(&rule, "order 7 4111111111111111"),
+        "order 7 [card number]"
+    );
+    assert_eq!(
+        redaction::apply(&rule, "call 1234 5678 9012 3456"),
+        "call 1234 5678 9012 3456"
+    );
+}
+
+#[test]
+fn history_search_matches_word_prefixes_and_deletes_by_id() {
+    isolate_data_dir();
+    // Unique words keep other tests' history out of the results.
+    let marker = format!("zq{}", uuid::Uuid::new_v4().simple());
+    let transcript = Transcript {
+        id: uuid::Uuid::new_v4().to_string(),
+        text: format!("{} the quick brown fox", marker),
+        is_final: true,
+        timestamp: now_millis(),
+        confidence: Some(0.9),
+        session_id: None,
+        start_ms: Some(1000),
+        end_ms: Some(2500),
+        speaker: None,
+        language: None,
+        translation: None,
+        latency: None,
+        words: vec![Word {
+            word: "quick".to_string(),
+            start_ms: 200,
+            end_ms: 450,
+            confidence: Some(0.8),
+        }],
+    };
+    history::record(&transcript, Some("USB Mic".to_string())).unwrap();
+    let search = |query: String| {
+        let filter = HistoryFilter {
+            query: Some(query),
+            ..Default::default()
+        };
+        history::query(&filter, 10, 0).unwrap()
+    };
+
+    let found = search(format!("{} bro", marker));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].duration_ms, Some(1500));
+    assert_eq!(found[0].device.as_deref(), Some("USB Mic"));
+    assert!(search(format!("{} \"fox OR", marker)).is_empty());
+    assert_eq!(history::words(&transcript.id).unwrap(), transcript.words);
+
+    history::delete(&transcript.id).unwrap();
+    assert!(search(marker).is_empty());
+    assert!(history::words(&transcript.id).unwrap().is_empty());
+    assert_eq!(
+        history::delete(&transcript.id).unwrap_err().code,
+        ErrorCode::NotFound
+    );
+}
@@ -0,0 +1,375 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::autotune::Tuning;
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::error::{AppError, CommandResult};
+use crate::history;
+use crate::metrics;
+use crate::postprocess;
+use crate::transcript::{now_millis, Latency, Transcript};
+use crate::AppState;
+
+const RECENT_LEN: usize = 200;
+const DEFAULT_RUNS: u32 = 5;
+
+static RECENT: Lazy<Mutex<VecDeque<Latency>>> = Lazy::new(Mutex::default);
+
+// Wall-clock stamps (epoch ms) the daemon attaches to each transcript.
+#[derive(Debug, Deserialize)]
+pub struct Timings {
+    speech_at: Option<f64>,
+    captured_at: f64,
+    stt_started_at: f64,
+    stt_finished_at: f64,
+}
+
+impl Timings {
+    pub fn into_latency(self) -> Latency {
+        let received_at = now_millis() as f64;
+        Latency {
+            vad_ms: self.speech_at.map(|at| self.captured_at - at),
+            queue_ms: self.stt_started_at - self.captured_at,
+            stt_ms: self.stt_finished_at - self.stt_started_at,
+            handoff_ms: (received_at - self.stt_finished_at).max(0.0),
+            postprocess_ms: 0.0,
+            injection_ms: None,
+            total_ms: received_at - self.speech_at.unwrap_or(self.captured_at),
+        }
+    }
+}
+
+// For backends that transcribe in the agent: the chunk was complete at
+// `captured_at` and handed to the model at `stt_started_at`, both epoch ms.
+pub fn measured(captured_at: u64, stt_started_at: u64) -> Latency {
+    Timings {
+        speech_at: None,
+        captured_at: captured_at as f64,
+        stt_started_at: stt_started_at as f64,
+        stt_finished_at: now_millis() as f64,
+    }
+    .into_latency()
+}
+
+fn observe(stage: &str, ms: f64) {
+    metrics::PIPELINE_LATENCY
+        .with_label_values(&[stage])
+        .observe(ms / 1000.0);
+}
+
+// Called once post-processing is done, right before the transcript is
+// published.
+pub fn finish(latency: &mut Latency, postprocess: Duration) {
+    latency.postprocess_ms = postprocess.as_secs_f64() * 1000.0;
+    latency.total_ms += latency.postprocess_ms;
+    if let Some(ms) = latency.vad_ms {
+        observe("vad", ms);
+    }
+    observe("queue", latency.queue_ms);
+    observe("stt", latency.stt_ms);
+    observe("handoff", latency.handoff_ms);
+    observe("postprocess", latency.postprocess_ms);
+    observe("total", latency.total_ms);
+
+    let mut recent = RECENT.lock();
+    if recent.len() == RECENT_LEN {
+        recent.pop_front();
+    }
+    recent.push_back(latency.clone());
+}
+
+// The daemon types text after reporting it, so injection time only reaches
+// the recent log, not the published transcript.
+pub fn record_injection(ms: f64) {
+    observe("injection", ms);
+    if let Some(latest) = RECENT.lock().back_mut() {
+        latest.injection_ms.get_or_insert(ms);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageStats {
+    pub count: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+fn stats(mut values: Vec<f64>) -> Option<StageStats> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let percentile = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+    Some(StageStats {
+        count: values.len(),
+        mean_ms: values.iter().sum::<f64>() / values.len() as f64,
+        p50_ms: percentile(0.5),
+        p95_ms: percentile(0.95),
+        max_ms: values[values.len() - 1],
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveLatency {
+    pub vad: Option<StageStats>,
+    pub queue: Option<StageStats>,
+    pub stt: Option<StageStats>,
+    pub handoff: Option<StageStats>,
+    pub postprocess: Option<StageStats>,
+    pub injection: Option<StageStats>,
+    pub total: Option<StageStats>,
+}
+
+fn live() -> LiveLatency {
+    let recent = RECENT.lock();
+    let stage = |f: fn(&Latency) -> Option<f64>| stats(recent.iter().filter_map(f).collect());
+    LiveLatency {
+        vad: stage(|l| l.vad_ms),
+        queue: stage(|l| Some(l.queue_ms)),
+        stt: stage(|l| Some(l.stt_ms)),
+        handoff: stage(|l| Some(l.handoff_ms)),
+        postprocess: stage(|l| Some(l.postprocess_ms)),
+        injection: stage(|l| l.injection_ms),
+        total: stage(|l| Some(l.total_ms)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UtteranceMetrics {
+    pub transcript_id: String,
+    pub session_id: Option<String>,
+    // `local`, `native`, `openai` or `deepgram`.
+    pub backend: String,
+    pub timestamp: u64,
+    // From the end of the audio to the transcript being published.
+    pub latency_ms: f64,
+    pub stt_ms: f64,
+    pub audio_ms: Option<u64>,
+    pub realtime_factor: Option<f64>,
+    pub words: u64,
+    pub words_per_minute: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMetrics {
+    pub session_id: String,
+    pub words: u64,
+    pub audio_ms: u64,
+    pub latency: Option<StageStats>,
+    // Totals over the session, so long utterances weigh more.
+    pub realtime_factor: Option<f64>,
+    pub words_per_minute: Option<f64>,
+    pub utterances: Vec<UtteranceMetrics>,
+}
+
+fn per_minute(words: u64, audio_ms: u64) -> Option<f64> {
+    if audio_ms == 0 {
+        None
+    } else {
+        Some(words as f64 * 60_000.0 / audio_ms as f64)
+    }
+}
+
+fn backend_name(state: &AppState) -> String {
+    if backends::cloud_active() {
+        serde_json::json!(backends::selected())
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    } else if daemon::is_running(state) {
+        "local".to_string()
+    } else {
+        "native".to_string()
+    }
+}
+
+// Published for each final transcript whose backend reported stage timings;
+// the history recorder stores it next to the transcript.
+pub fn report(state: &AppState, transcript: &Transcript) {
+    let latency = match (&transcript.latency, transcript.is_final) {
+        (Some(latency), true) => latency,
+        _ => return,
+    };
+    let audio_ms = match (transcript.start_ms, transcript.end_ms) {
+        (Some(start), Some(end)) if end > start => Some(end - start),
+        _ => None,
+    };
+    let words = transcript.text.split_whitespace().count() as u64;
+    state
+        .bus
+        .publish(AgentEvent::LatencyReport(UtteranceMetrics {
+            transcript_id: transcript.id.clone(),
+            session_id: transcript.session_id.clone(),
+            backend: backend_name(state),
+            timestamp: transcript.timestamp,
+            latency_ms: latency.total_ms - latency.vad_ms.unwrap_or(0.0),
+            stt_ms: latency.stt_ms,
+            audio_ms,
+            realtime_factor: audio_ms.map(|ms| latency.stt_ms / ms as f64),
+            words,
+            words_per_minute: audio_ms.and_then(|ms| per_minute(words, ms)),
+        }));
+}
+
+pub fn session_metrics(session_id: &str) -> Result<SessionMetrics, AppError> {
+    let utterances = history::session_metrics(session_id)?;
+    let words = utterances.iter().map(|u| u.words).sum();
+    let timed: Vec<&UtteranceMetrics> =
+        utterances.iter().filter(|u| u.audio_ms.is_some()).collect();
+    let audio_ms = timed.iter().filter_map(|u| u.audio_ms).sum();
+    let stt_ms: f64 = timed.iter().map(|u| u.stt_ms).sum();
+    let timed_words = timed.iter().map(|u| u.words).sum();
+    Ok(SessionMetrics {
+        session_id: session_id.to_string(),
+        words,
+        audio_ms,
+        latency: stats(utterances.iter().map(|u| u.latency_ms).collect()),
+        realtime_factor: if audio_ms == 0 {
+            None
+        } else {
+            Some(stt_ms / audio_ms as f64)
+        },
+        words_per_minute: per_minute(timed_words, audio_ms),
+        utterances,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BenchmarkLine {
+    BenchmarkLoad {
+        ms: f64,
+    },
+    BenchmarkRun {
+        stt_ms: f64,
+        text: String,
+        duration: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileBenchmark {
+    pub path: String,
+    pub model: String,
+    pub audio_ms: f64,
+    pub model_load_ms: Option<f64>,
+    pub stt: Option<StageStats>,
+    pub postprocess: Option<StageStats>,
+    // Processing time over audio length; below 1.0 keeps up with live speech.
+    pub realtime_factor: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub live: LiveLatency,
+    pub file: Option<FileBenchmark>,
+}
+
+pub fn benchmark_file(
+    state: &AppState,
+    path: &Path,
+    runs: u32,
+    tuning: Option<&Tuning>,
+) -> Result<FileBenchmark, String> {
+    let output = daemon::benchmark_file(state, path, runs, tuning)?;
+    let (mut model_load_ms, mut audio_ms) = (None, 0.0);
+    let (mut stt, mut postprocess) = (Vec::new(), Vec::new());
+    for line in output.lines() {
+        match serde_json::from_str(line) {
+            Ok(BenchmarkLine::BenchmarkLoad { ms }) => model_load_ms = Some(ms),
+            Ok(BenchmarkLine::BenchmarkRun {
+                stt_ms,
+                text,
+                duration,
+            }) => {
+                stt.push(stt_ms);
+                audio_ms = duration * 1000.0;
+                let transcript = Transcript {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    text,
+                    is_final: true,
+                    timestamp: now_millis(),
+                    confidence: None,
+                    session_id: None,
+                    start_ms: None,
+                    end_ms: None,
+                    speaker: None,
+                    language: None,
+                    translation: None,
+                    latency: None,
+                    words: Vec::new(),
+                };
+                let started = Instant::now();
+                postprocess::run(state, transcript);
+                postprocess.push(started.elapsed().as_secs_f64() * 1000.0);
+            }
+            Err(_) => {}
+        }
+    }
+    if stt.is_empty() {
+        return Err(format!("Benchmark of {} produced no runs", path.display()));
+    }
+    let stt = stats(stt);
+    Ok(FileBenchmark {
+        path: path.display().to_string(),
+        model: daemon::current_model(state),
+        audio_ms,
+        model_load_ms,
+        realtime_factor: stt
+            .as_ref()
+            .filter(|_| audio_ms > 0.0)
+            .map(|s| s.mean_ms / audio_ms),
+        stt,
+        postprocess: stats(postprocess),
+    })
+}
+
+pub fn run_benchmark(
+    state: &AppState,
+    path: Option<&Path>,
+    runs: Option<u32>,
+) -> Result<LatencyReport, String> {
+    let file = match path {
+        Some(path) => Some(benchmark_file(
+            state,
+            path,
+            runs.unwrap_or(DEFAULT_RUNS).max(1),
+            None,
+        )?),
+        None => None,
+    };
+    Ok(LatencyReport { live: live(), file })
+}
+
+#[tauri::command]
+pub async fn run_latency_benchmark(
+    state: tauri::State<'_, AppState>,
+    path: Option<String>,
+    runs: Option<u32>,
+) -> CommandResult<LatencyReport> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        run_benchmark(&state, path.as_deref().map(Path::new), runs)
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Benchmark task failed: {}", e)))?
+    .map_err(AppError::from)
+}
+
+// Sessions are the daemon or backend session a transcript carries as
+// `session_id`; an unknown id has no utterances rather than failing.
+#[tauri::command]
+pub async fn get_latency_metrics(session_id: String) -> CommandResult<SessionMetrics> {
+    tauri::async_runtime::spawn_blocking(move || session_metrics(&session_id))
+        .await
+        .map_err(|e| AppError::from(format!("Metrics task failed: {}", e)))?
+}
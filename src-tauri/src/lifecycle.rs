@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::error::AppError;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+    Start { source: InputSource },
+    Stop,
+    // Picks up a new model or profile; does nothing while idle.
+    Restart,
+    // Stops the daemon for a cloud backend taking over the same dictation,
+    // so neither the session nor listening is reported as ended.
+    HandOver,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationProgress {
+    pub id: u64,
+    pub operation: Operation,
+    pub state: OperationState,
+    pub position: Option<usize>,
+    pub error: Option<AppError>,
+}
+
+type Reply = mpsc::Sender<Result<String, AppError>>;
+
+struct Pending {
+    id: u64,
+    operation: Operation,
+    state: AppState,
+    waiters: Vec<Reply>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<Pending>,
+    next_id: u64,
+    worker: bool,
+}
+
+// Lifecycle requests from the tray, hotkeys, the API and integrations run one
+// at a time, in arrival order. A request identical to the last one still
+// waiting joins it and shares its result instead of queueing again; joining
+// an earlier one would move it past the requests in between.
+#[derive(Clone, Default)]
+pub struct LifecycleQueue {
+    inner: Arc<(Mutex<Queue>, Condvar)>,
+}
+
+impl LifecycleQueue {
+    pub fn submit(&self, state: &AppState, operation: Operation) -> Result<String, AppError> {
+        let (tx, rx) = mpsc::channel();
+        {
+            let (queue, ready) = &*self.inner;
+            let mut queue = queue.lock();
+            if let Some(pending) = queue
+                .pending
+                .back_mut()
+                .filter(|p| p.operation == operation)
+            {
+                pending.waiters.push(tx);
+            } else {
+                queue.next_id += 1;
+                let id = queue.next_id;
+                queue.pending.push_back(Pending {
+                    id,
+                    operation,
+                    state: state.clone(),
+                    waiters: vec![tx],
+                });
+                state.bus.publish(progress(
+                    id,
+                    operation,
+                    OperationState::Queued,
+                    Some(queue.pending.len() - 1),
+                    None,
+                ));
+                if !queue.worker {
+                    queue.worker = true;
+                    let inner = self.inner.clone();
+                    thread::spawn(move || work(inner));
+                }
+                ready.notify_one();
+            }
+        }
+        rx.recv()
+            .unwrap_or_else(|_| Err(AppError::from("Lifecycle worker stopped")))
+    }
+
+    pub fn pending(&self) -> Vec<Operation> {
+        let queue = self.inner.0.lock();
+        queue.pending.iter().map(|p| p.operation).collect()
+    }
+}
+
+fn progress(
+    id: u64,
+    operation: Operation,
+    state: OperationState,
+    position: Option<usize>,
+    error: Option<AppError>,
+) -> AgentEvent {
+    AgentEvent::OperationProgress(OperationProgress {
+        id,
+        operation,
+        state,
+        position,
+        error,
+    })
+}
+
+fn work(inner: Arc<(Mutex<Queue>, Condvar)>) {
+    let (queue, ready) = &*inner;
+    loop {
+        let job = {
+            let mut queue = queue.lock();
+            while queue.pending.is_empty() {
+                ready.wait(&mut queue);
+            }
+            let job = queue.pending.pop_front().unwrap();
+            // Everyone still waiting moved up one place.
+            for (position, pending) in queue.pending.iter().enumerate() {
+                pending.state.bus.publish(progress(
+                    pending.id,
+                    pending.operation,
+                    OperationState::Queued,
+                    Some(position),
+                    None,
+                ));
+            }
+            job
+        };
+
+        job.state.bus.publish(progress(
+            job.id,
+            job.operation,
+            OperationState::Running,
+            None,
+            None,
+        ));
+        // A panic fails the job instead of the worker, which would leave
+        // everyone queued behind it waiting for good.
+        let result =
+            panic::catch_unwind(AssertUnwindSafe(|| daemon::run(&job.state, job.operation)))
+                .unwrap_or_else(|_| Err(AppError::from("The lifecycle operation panicked")));
+        let (outcome, error) = match &result {
+            Ok(_) => (OperationState::Done, None),
+            Err(e) => (OperationState::Failed, Some(e.clone())),
+        };
+        job.state
+            .bus
+            .publish(progress(job.id, job.operation, outcome, None, error));
+        for waiter in job.waiters {
+            let _ = waiter.send(result.clone());
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_pending_operations(state: tauri::State<'_, AppState>) -> Vec<Operation> {
+    state.lifecycle.pending()
+}
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use livekit::options::TrackPublishOptions;
+use livekit::prelude::*;
+use livekit::webrtc::audio_frame::AudioFrame;
+use livekit::webrtc::audio_source::native::NativeAudioSource;
+use livekit::webrtc::audio_source::{AudioSourceOptions, RtcAudioSource};
+use livekit::webrtc::audio_stream::native::NativeAudioStream;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::StreamExt;
+
+use crate::audio::{self, Sink};
+use crate::bus::AgentEvent;
+use crate::daemon::{self, StreamWorker};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "livekit.json";
+const SAMPLE_RATE: i32 = 16000;
+// How much microphone audio LiveKit may queue before it drops frames.
+const PUBLISH_QUEUE_MS: u32 = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiveKitConfig {
+    pub url: String,
+    pub token: String,
+    // Sends this machine's microphone to the room. It holds the microphone
+    // until the room is left.
+    pub publish_microphone: bool,
+    // Transcribes everyone in the room, the published microphone included.
+    pub transcribe: bool,
+}
+
+impl Default for LiveKitConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            token: String::new(),
+            publish_microphone: false,
+            transcribe: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveKitStatus {
+    pub connected: bool,
+    pub room: Option<String>,
+    pub session_id: Option<String>,
+    pub participants: Vec<String>,
+    pub publishing: bool,
+}
+
+struct RoomSession {
+    shutdown: oneshot::Sender<()>,
+    room: String,
+    session_id: String,
+    participants: Arc<Mutex<Vec<String>>>,
+    publishing: bool,
+}
+
+pub struct LiveKitState {
+    config: Mutex<LiveKitConfig>,
+    session: Arc<Mutex<Option<RoomSession>>>,
+}
+
+impl LiveKitState {
+    pub fn load() -> Self {
+        Self {
+            config: Mutex::new(store::load_json(STORE_FILE)),
+            session: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+fn to_pcm(samples: &[i16]) -> Vec<u8> {
+    samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+}
+
+fn transcribe_track(
+    state: AppState,
+    track: RemoteAudioTrack,
+    session_id: String,
+    identity: String,
+) -> JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut worker = match daemon::spawn_worker(&state, session_id, Some(identity.clone())) {
+            Ok(worker) => worker,
+            Err(message) => {
+                state.bus.publish(AgentEvent::error("livekit", message));
+                return;
+            }
+        };
+        let mut frames = NativeAudioStream::new(track.rtc_track(), SAMPLE_RATE, 1);
+        while let Some(frame) = frames.next().await {
+            if worker.push_audio(&to_pcm(&frame.data)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+// The capture thread hands frames to LiveKit through a channel, since
+// publishing is async. It ends when the capture is stopped.
+fn microphone_sink(source: NativeAudioSource, mut worker: Option<StreamWorker>) -> Sink {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Vec<i16>>();
+    tauri::async_runtime::spawn(async move {
+        while let Some(data) = rx.recv().await {
+            let frame = AudioFrame {
+                samples_per_channel: data.len() as u32,
+                data: data.into(),
+                sample_rate: SAMPLE_RATE as u32,
+                num_channels: 1,
+            };
+            if source.capture_frame(&frame).await.is_err() {
+                break;
+            }
+        }
+    });
+    Box::new(move |samples: &[f32]| {
+        let data: Vec<i16> = samples
+            .iter()
+            .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .collect();
+        if let Some(worker) = worker.as_mut() {
+            let _ = worker.push_audio(&to_pcm(&data));
+        }
+        let _ = tx.send(data);
+    })
+}
+
+async fn publish_microphone(
+    state: &AppState,
+    room: &Room,
+    session_id: &str,
+    transcribe: bool,
+) -> Result<(), AppError> {
+    let source = NativeAudioSource::new(
+        AudioSourceOptions::default(),
+        SAMPLE_RATE as u32,
+        1,
+        PUBLISH_QUEUE_MS,
+    );
+    let track =
+        LocalAudioTrack::create_audio_track("microphone", RtcAudioSource::Native(source.clone()));
+    room.local_participant()
+        .publish_track(
+            LocalTrack::Audio(track),
+            TrackPublishOptions {
+                source: TrackSource::Microphone,
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Network,
+                format!("Failed to publish the microphone: {}", e),
+            )
+        })?;
+    let state = state.clone();
+    let session_id = session_id.to_string();
+    let identity = room.local_participant().identity().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let worker = if transcribe {
+            Some(daemon::spawn_worker(&state, session_id, Some(identity))?)
+        } else {
+            None
+        };
+        audio::start_with_sink(&state, None, microphone_sink(source, worker)).map(|_| ())
+    })
+    .await
+    .map_err(|e| AppError::from(format!("LiveKit task failed: {}", e)))?
+}
+
+fn publish_participants(state: &AppState, room: &str, participants: &Mutex<Vec<String>>) {
+    state.bus.publish(AgentEvent::RoomParticipants {
+        room: room.to_string(),
+        participants: participants.lock().clone(),
+    });
+}
+
+async fn run_room(
+    state: AppState,
+    room: Room,
+    mut events: mpsc::UnboundedReceiver<RoomEvent>,
+    session_id: String,
+    participants: Arc<Mutex<Vec<String>>>,
+    config: LiveKitConfig,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let room_name = room.name();
+    let mut transcripts = state.bus.subscribe();
+    let mut tracks: HashMap<String, JoinHandle<()>> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            event = transcripts.recv() => match event {
+                Ok(AgentEvent::Transcript(transcript))
+                    if transcript.session_id.as_deref() == Some(session_id.as_str()) =>
+                {
+                    state.bus.publish(AgentEvent::RoomTranscript {
+                        room: room_name.clone(),
+                        participant: transcript.speaker.clone().unwrap_or_default(),
+                        transcript,
+                    });
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            event = events.recv() => match event {
+                Some(RoomEvent::TrackSubscribed { track: RemoteTrack::Audio(track), publication, participant }) if config.transcribe => {
+                    let identity = participant.identity().to_string();
+                    let handle = transcribe_track(state.clone(), track, session_id.clone(), identity);
+                    if let Some(previous) = tracks.insert(publication.sid().to_string(), handle) {
+                        previous.abort();
+                    }
+                }
+                Some(RoomEvent::TrackUnsubscribed { publication, .. }) => {
+                    if let Some(handle) = tracks.remove(&publication.sid().to_string()) {
+                        handle.abort();
+                    }
+                }
+                Some(RoomEvent::ParticipantConnected(participant)) => {
+                    let identity = participant.identity().to_string();
+                    {
+                        let mut participants = participants.lock();
+                        if participants.contains(&identity) {
+                            continue;
+                        }
+                        participants.push(identity);
+                    }
+                    publish_participants(&state, &room_name, &participants);
+                }
+                Some(RoomEvent::ParticipantDisconnected(participant)) => {
+                    let identity = participant.identity().to_string();
+                    participants.lock().retain(|p| *p != identity);
+                    publish_participants(&state, &room_name, &participants);
+                }
+                Some(RoomEvent::Disconnected { reason }) => {
+                    state.bus.publish(AgentEvent::error(
+                        "livekit",
+                        AppError::new(
+                            ErrorCode::Network,
+                            format!("Disconnected from room: {:?}", reason),
+                        ),
+                    ));
+                    break;
+                }
+                Some(_) => {}
+                None => break,
+            },
+        }
+    }
+    for (_, handle) in tracks {
+        handle.abort();
+    }
+    if config.publish_microphone {
+        let stopping = state.clone();
+        let _ = tauri::async_runtime::spawn_blocking(move || audio::stop(&stopping)).await;
+    }
+    let _ = room.close().await;
+    state.bus.publish(AgentEvent::SessionEnded { session_id });
+}
+
+#[tauri::command]
+pub fn get_livekit_config(livekit: tauri::State<'_, LiveKitState>) -> LiveKitConfig {
+    livekit.config.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_livekit_config(
+    livekit: tauri::State<'_, LiveKitState>,
+    config: LiveKitConfig,
+) -> CommandResult<()> {
+    if !config.publish_microphone && !config.transcribe {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Publish the microphone, transcribe the room, or both",
+        ));
+    }
+    store::save_json(STORE_FILE, &config)?;
+    *livekit.config.lock() = config;
+    Ok(())
+}
+
+// Transcripts from the room carry its session id, and each participant's
+// identity as their speaker.
+#[tauri::command]
+pub async fn join_livekit_room(
+    state: tauri::State<'_, AppState>,
+    livekit: tauri::State<'_, LiveKitState>,
+) -> CommandResult<LiveKitStatus> {
+    if livekit.session.lock().is_some() {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "Already connected to a LiveKit room",
+        ));
+    }
+    let config = livekit.config.lock().clone();
+    if config.url.is_empty() || config.token.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "LiveKit URL and token must be set first",
+        ));
+    }
+
+    let options = RoomOptions {
+        auto_subscribe: config.transcribe,
+        ..Default::default()
+    };
+    let (room, events) = Room::connect(&config.url, &config.token, options)
+        .await
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Network,
+                format!("Failed to join LiveKit room: {}", e),
+            )
+        })?;
+    let room_name = room.name();
+    let session_id = uuid::Uuid::new_v4().to_string();
+    if config.publish_microphone {
+        if let Err(error) =
+            publish_microphone(state.inner(), &room, &session_id, config.transcribe).await
+        {
+            let _ = room.close().await;
+            return Err(error);
+        }
+    }
+    let joined: Vec<String> = room
+        .remote_participants()
+        .keys()
+        .map(|identity| identity.to_string())
+        .collect();
+    let participants = Arc::new(Mutex::new(joined.clone()));
+    let (tx, rx) = oneshot::channel();
+
+    *livekit.session.lock() = Some(RoomSession {
+        shutdown: tx,
+        room: room_name.clone(),
+        session_id: session_id.clone(),
+        participants: participants.clone(),
+        publishing: config.publish_microphone,
+    });
+
+    let session = livekit.session.clone();
+    let task = run_room(
+        state.inner().clone(),
+        room,
+        events,
+        session_id.clone(),
+        participants,
+        config.clone(),
+        rx,
+    );
+    let finished_id = session_id.clone();
+    tauri::async_runtime::spawn(async move {
+        task.await;
+        let mut session = session.lock();
+        if matches!(session.as_ref(), Some(s) if s.session_id == finished_id) {
+            session.take();
+        }
+    });
+
+    Ok(LiveKitStatus {
+        connected: true,
+        room: Some(room_name),
+        session_id: Some(session_id),
+        participants: joined,
+        publishing: config.publish_microphone,
+    })
+}
+
+#[tauri::command]
+pub fn leave_livekit_room(livekit: tauri::State<'_, LiveKitState>) -> CommandResult<String> {
+    match livekit.session.lock().take() {
+        Some(session) => {
+            let _ = session.shutdown.send(());
+            Ok(format!("Left LiveKit room {}", session.room))
+        }
+        None => Err(AppError::new(
+            ErrorCode::NotRunning,
+            "Not connected to a LiveKit room",
+        )),
+    }
+}
+
+#[tauri::command]
+pub fn get_livekit_status(livekit: tauri::State<'_, LiveKitState>) -> LiveKitStatus {
+    match livekit.session.lock().as_ref() {
+        Some(session) => LiveKitStatus {
+            connected: true,
+            room: Some(session.room.clone()),
+            session_id: Some(session.session_id.clone()),
+            participants: session.participants.lock().clone(),
+            publishing: session.publishing,
+        },
+        None => LiveKitStatus {
+            connected: false,
+            room: None,
+            session_id: None,
+            participants: Vec::new(),
+            publishing: false,
+        },
+    }
+}
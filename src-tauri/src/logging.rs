@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::now_millis;
+
+const STORE_FILE: &str = "logging.json";
+const BUFFER_LEN: usize = 2000;
+// A week of daily files.
+const MAX_LOG_FILES: usize = 7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct LogConfig {
+    level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
+
+static BUFFER: Lazy<Mutex<VecDeque<LogEntry>>> = Lazy::new(Mutex::default);
+static LIVE: Lazy<broadcast::Sender<LogEntry>> = Lazy::new(|| broadcast::channel(256).0);
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+// Keeps recent events in memory for the log viewer and crash reports.
+struct BufferLayer;
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let entry = LogEntry {
+            timestamp: now_millis(),
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message + &visitor.fields,
+        };
+        {
+            let mut buffer = BUFFER.lock();
+            if buffer.len() == BUFFER_LEN {
+                buffer.pop_front();
+            }
+            buffer.push_back(entry.clone());
+        }
+        let _ = LIVE.send(entry);
+    }
+}
+
+// macOS keeps logs in ~/Library/Logs, where Console.app finds them.
+pub fn log_dir() -> PathBuf {
+    if cfg!(target_os = "macos") && std::env::var_os("VOICE_AGENT_DATA_DIR").is_none() {
+        if let Some(home) = dirs::home_dir() {
+            return home.join("Library/Logs/com.voice.mcp.agent");
+        }
+    }
+    store::data_dir().join("logs")
+}
+
+fn file_appender() -> RollingFileAppender {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("voice-mcp-agent")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir())
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to open the log directory: {}", e);
+            tracing_appender::rolling::never(std::env::temp_dir(), "voice-mcp-agent.log")
+        })
+}
+
+// Console output goes to stderr so headless mode, the native messaging host
+// and the MCP server keep stdout for their protocols. `RUST_LOG` overrides the
+// saved level.
+pub fn init() -> WorkerGuard {
+    let config: LogConfig = store::load_json(STORE_FILE);
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+    let (filter, handle) = reload::Layer::new(filter);
+    let (file, guard) = tracing_appender::non_blocking(file_appender());
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(fmt::layer().with_ansi(false).with_writer(file))
+        .with(BufferLayer)
+        .init();
+    let _ = FILTER.set(handle);
+    guard
+}
+
+pub fn recent(limit: usize) -> Vec<LogEntry> {
+    let buffer = BUFFER.lock();
+    let skip = buffer.len().saturating_sub(limit);
+    buffer.iter().skip(skip).cloned().collect()
+}
+
+pub fn subscribe() -> broadcast::Receiver<LogEntry> {
+    LIVE.subscribe()
+}
+
+// Errors reach the bus from every subsystem; mirror them into the log so
+// they are persisted with everything else.
+pub fn spawn_bus_logger(bus: &EventBus) {
+    let mut rx = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(AgentEvent::Error { source, error }) => {
+                    tracing::warn!(target: "bus", source = %source, code = ?error.code, "{}", error)
+                }
+                Ok(AgentEvent::DaemonCrashed {
+                    session_id,
+                    exit_code,
+                    ..
+                }) => {
+                    tracing::error!(target: "bus", session_id = %session_id, ?exit_code, "STT daemon exited unexpectedly")
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn set_log_level(level: String) -> CommandResult<()> {
+    let filter = EnvFilter::try_new(&level).map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid log level {}: {}", level, e),
+        )
+    })?;
+    FILTER
+        .get()
+        .ok_or_else(|| AppError::from("Logging is not initialized"))?
+        .reload(filter)
+        .map_err(|e| AppError::from(format!("Failed to set log level: {}", e)))?;
+    store::save_json(STORE_FILE, &LogConfig { level })
+}
+
+#[tauri::command]
+pub fn get_app_logs(
+    level: Option<String>,
+    target: Option<String>,
+    contains: Option<String>,
+    limit: Option<usize>,
+) -> CommandResult<Vec<LogEntry>> {
+    let min_level = match level {
+        Some(level) => Some(level.parse::<Level>().map_err(|_| {
+            AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Invalid log level {}", level),
+            )
+        })?),
+        None => None,
+    };
+    let contains = contains.map(|c| c.to_lowercase());
+    let buffer = BUFFER.lock();
+    let mut entries: Vec<LogEntry> = buffer
+        .iter()
+        .rev()
+        .filter(|e| match min_level {
+            // Level ordering in tracing is by verbosity: ERROR < WARN < INFO.
+            Some(min) => e.level.parse::<Level>().map_or(true, |l| l <= min),
+            None => true,
+        })
+        .filter(|e| target.as_deref().map_or(true, |t| e.target.starts_with(t)))
+        .filter(|e| {
+            contains
+                .as_deref()
+                .map_or(true, |c| e.message.to_lowercase().contains(c))
+        })
+        .take(limit.unwrap_or(500))
+        .cloned()
+        .collect();
+    entries.reverse();
+    Ok(entries)
+}
+
+// The recent entries at `level` or more severe, oldest first.
+#[tauri::command]
+pub fn get_log_entries(
+    level: Option<String>,
+    limit: Option<usize>,
+) -> CommandResult<Vec<LogEntry>> {
+    get_app_logs(level, None, None, limit)
+}
+
+pub fn open_folder() -> Result<(), AppError> {
+    let dir = log_dir();
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::process::Command::new(program).arg(&dir).spawn())
+        .map(|_| ())
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Failed,
+                format!("Failed to open {}: {}", dir.display(), e),
+            )
+        })
+}
+
+#[tauri::command]
+pub fn open_log_folder() -> CommandResult<()> {
+    open_folder()
+}
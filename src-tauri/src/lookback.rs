@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{self, SAMPLE_RATE};
+use crate::backends;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::ptt;
+use crate::store;
+use crate::transcript::Transcript;
+use crate::wake_word;
+use crate::AppState;
+
+const STORE_FILE: &str = "lookback.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_SECONDS: u32 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LookbackConfig {
+    // Off by default: on, the microphone is open whenever nothing else has it.
+    pub enabled: bool,
+    // How much audio is kept, and what the hotkey transcribes.
+    pub seconds: u32,
+}
+
+impl Default for LookbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seconds: 30,
+        }
+    }
+}
+
+static CONFIG: Lazy<Mutex<LookbackConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// Checked on the audio thread before any lock is taken.
+static ENABLED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(CONFIG.lock().enabled));
+// 16 kHz mono, oldest first.
+static BUFFER: Lazy<Mutex<VecDeque<f32>>> = Lazy::new(Mutex::default);
+
+fn capacity() -> usize {
+    CONFIG.lock().seconds as usize * SAMPLE_RATE as usize
+}
+
+// Fed by every microphone capture, before silence is gated, so the buffer
+// keeps real time.
+pub fn push(samples: &[f32]) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let capacity = capacity();
+    let mut buffer = BUFFER.lock();
+    buffer.extend(samples);
+    let excess = buffer.len().saturating_sub(capacity);
+    buffer.drain(..excess);
+}
+
+// Holds the microphone for the buffer until something else wants it.
+fn hold(state: &AppState) -> Result<(), AppError> {
+    let (tx, rx) = mpsc::channel::<()>();
+    // Only there to be dropped when the capture ends.
+    audio::start_background(
+        state,
+        Box::new(move |_: &[f32]| {
+            let _ = &tx;
+        }),
+    )?;
+    tracing::info!(target: "lookback", "Buffering the microphone");
+    loop {
+        if let Err(mpsc::RecvTimeoutError::Disconnected) = rx.recv_timeout(POLL_INTERVAL) {
+            return Ok(());
+        }
+        if !ENABLED.load(Ordering::Relaxed) || backends::is_running(state) || wake_word::wanted() {
+            break;
+        }
+    }
+    audio::stop_background();
+    Ok(())
+}
+
+// Opens the microphone while it is idle. The wake word's listener feeds the
+// buffer too, so it is given the microphone when it wants it.
+pub fn spawn(state: AppState) {
+    thread::spawn(move || {
+        let mut last_error: Option<String> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if !ENABLED.load(Ordering::Relaxed)
+                || wake_word::wanted()
+                || backends::is_running(&state)
+                || !audio::is_idle(&state)
+            {
+                continue;
+            }
+            match hold(&state) {
+                Ok(()) => last_error = None,
+                Err(error) if last_error.as_deref() != Some(error.message.as_str()) => {
+                    last_error = Some(error.message.clone());
+                    tracing::warn!(target: "lookback", "{}", error);
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+// The last `seconds` of the buffer, or all of it, through the same path as
+// push-to-talk.
+pub fn transcribe_window(state: &AppState, seconds: Option<u32>) -> Result<Transcript, AppError> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Err(AppError::new(
+            ErrorCode::NotRunning,
+            "The lookback buffer is off",
+        ));
+    }
+    let wanted = seconds.unwrap_or(MAX_SECONDS) as usize * SAMPLE_RATE as usize;
+    let samples: Vec<f32> = {
+        let buffer = BUFFER.lock();
+        let skip = buffer.len().saturating_sub(wanted);
+        buffer.iter().skip(skip).copied().collect()
+    };
+    if samples.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            "Nothing has been heard yet",
+        ));
+    }
+    ptt::transcribe(state, &samples)
+}
+
+#[tauri::command]
+pub fn get_lookback_config() -> LookbackConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_lookback_config(config: LookbackConfig) -> CommandResult<()> {
+    if config.seconds == 0 || config.seconds > MAX_SECONDS {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("The buffer holds 1 to {} seconds", MAX_SECONDS),
+        ));
+    }
+    store::save_json(STORE_FILE, &config)?;
+    if !config.enabled {
+        BUFFER.lock().clear();
+    }
+    ENABLED.store(config.enabled, Ordering::Relaxed);
+    *CONFIG.lock() = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn transcribe_buffer_window(
+    state: tauri::State<'_, AppState>,
+    seconds: u32,
+) -> CommandResult<Transcript> {
+    if seconds == 0 {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Choose at least one second",
+        ));
+    }
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || transcribe_window(&state, Some(seconds)))
+        .await
+        .map_err(|e| AppError::from(format!("Lookback task failed: {}", e)))?
+}
@@ -3,140 +3,1107 @@
     windows_subsystem = "windows"
 )]
 
-use tauri::{CustomMenuItem, SystemTray, SystemTrayEvent, SystemTrayMenu, Manager};
+mod a11y;
+mod agents;
+mod api;
+mod audio;
+mod audio_ring;
+mod auth;
+#[cfg(target_os = "macos")]
+mod automation;
+mod autotune;
+mod backends;
+mod background_core;
+mod batch;
+mod bus;
+mod calendar;
+mod caption_overlay;
+mod captions;
+mod chat;
+mod children;
+mod cli;
+mod config;
+mod control_socket;
+mod crash;
+mod daemon;
+mod daemon_client;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod denoise;
+mod diagnostics;
+mod dictation;
+mod encryption;
+mod env;
+mod error;
+mod events;
+mod export;
+mod formatting;
+mod grpc;
+mod history;
+mod hooks;
+mod i18n;
+mod instance;
+#[cfg(test)]
+mod integration_tests;
+mod latency;
+mod lifecycle;
+mod livekit;
+mod logging;
+mod lookback;
+mod mcp;
+mod mcp_client;
+mod mdns;
+mod meeting;
+mod meeting_captions;
+mod metrics;
+mod models;
+mod mqtt;
+mod native_messaging;
+mod native_stt;
+mod network;
+mod notifications;
+mod openai_api;
+mod permissions;
+mod pill;
+mod plugins;
+mod portal;
+mod postprocess;
+mod power;
+mod profiles;
+mod ptt;
+mod recent;
+mod recordings;
+mod redaction;
+mod remote_mic;
+mod resampler;
+mod resources;
+mod sandbox;
+mod schedule;
+mod secrets;
+mod session;
+mod settings;
+mod shortcuts;
+mod sidecar;
+mod simulated_input;
+mod soak;
+mod stabilizer;
+mod startup;
+mod store;
+mod streamdeck;
+mod supervisor;
+mod text_inject;
+mod transcript;
+mod translation;
+mod tray_icon;
+mod tts;
+mod updater;
+mod usage;
+mod vad;
+mod vocab;
+mod voice_commands;
+mod wake_word;
+mod watch_folders;
+mod webhooks;
+mod window_tracker;
+mod ws_server;
+
+use tauri::{CustomMenuItem, FileDropEvent, SystemTray, SystemTrayEvent, SystemTrayMenu, Manager};
 use tauri_plugin_shell::process::CommandEvent;
-use std::process::{Command, Stdio};
-use std::sync::{Arc, Mutex};
+use clap::Parser;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
 use tokio::process::Command as TokioCommand;
+use tokio::sync::broadcast::error::RecvError;
+
+use agents::AgentRegistry;
+use api::ApiState;
+use audio_ring::AudioRing;
+use auth::AuthState;
+use bus::{AgentEvent, EventBus};
+use calendar::CalendarState;
+use chat::ChatState;
+use children::ChildGuard;
+use daemon::{DaemonLog, DaemonStatus, Health, InputSource, ListenerStatus};
+use daemon_client::DaemonClient;
+use error::{AppError, CommandResult};
+use events::UiEvent;
+use grpc::GrpcState;
+use hooks::HookState;
+use instance::Handoff;
+use lifecycle::LifecycleQueue;
+use livekit::LiveKitState;
+use mdns::MdnsState;
+use meeting_captions::MeetingCaptionState;
+use models::{ModelManager, SttBackend};
+use mqtt::MqttState;
+use plugins::PluginHost;
+use portal::PortalState;
+use power::PowerState;
+use profiles::Profiles;
+use simulated_input::SimulatedInput;
+use startup::StartupState;
+use transcript::Transcript;
+use tray_icon::{TrayAction, TrayClick};
+use updater::UpdaterState;
+use watch_folders::WatchState;
+use webhooks::WebhookState;
+use ws_server::WsState;
 
+#[derive(Clone)]
 struct AppState {
-    stt_process: Mutex<Option<std::process::Child>>,
+    stt_process: Arc<Mutex<Option<ChildGuard>>>,
+    stt_ring: Arc<Mutex<Option<AudioRing>>>,
+    // The listening daemon's JSON-RPC channel: heartbeats and settings.
+    stt_client: Arc<Mutex<Option<Arc<DaemonClient>>>>,
+    daemon_logs: Arc<Mutex<VecDeque<DaemonLog>>>,
+    status: Arc<tokio::sync::watch::Sender<ListenerStatus>>,
+    health: Arc<Mutex<Health>>,
+    last_transcript: Arc<Mutex<Option<Transcript>>>,
+    profiles: Arc<Mutex<Profiles>>,
+    plugins: PluginHost,
+    auth: AuthState,
+    models: ModelManager,
+    simulated_input: Arc<Mutex<Option<SimulatedInput>>>,
+    lifecycle: LifecycleQueue,
+    agents: AgentRegistry,
+    bus: EventBus,
 }
 
-#[tauri::command]
-async fn start_stt_daemon(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let mut process_guard = state.stt_process.lock().unwrap();
-    
-    if process_guard.is_some() {
-        return Err("STT daemon is already running".to_string());
-    }
-    
-    match Command::new("python3")
-        .arg("system_stt_daemon.py")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-    {
-        Ok(child) => {
-            *process_guard = Some(child);
-            Ok("STT daemon started successfully".to_string())
+impl AppState {
+    fn new(bus: EventBus) -> Self {
+        Self {
+            stt_process: Arc::new(Mutex::new(None)),
+            stt_ring: Arc::new(Mutex::new(None)),
+            stt_client: Arc::new(Mutex::new(None)),
+            daemon_logs: Arc::default(),
+            status: Arc::new(tokio::sync::watch::channel(ListenerStatus::default()).0),
+            health: Arc::default(),
+            last_transcript: Arc::new(Mutex::new(None)),
+            profiles: Arc::new(Mutex::new(Profiles::load())),
+            plugins: PluginHost::load(),
+            auth: AuthState::load(),
+            models: ModelManager::load(),
+            simulated_input: Arc::new(Mutex::new(simulated_input::load())),
+            lifecycle: LifecycleQueue::default(),
+            agents: AgentRegistry::default(),
+            bus,
         }
-        Err(e) => Err(format!("Failed to start STT daemon: {}", e)),
     }
 }
 
+fn apply_integrations(
+    state: &AppState,
+    mqtt: &MqttState,
+    calendar: &CalendarState,
+    watch: &WatchState,
+) -> Result<(), String> {
+    mqtt.apply(state);
+    calendar.apply(state);
+    watch.apply(state)
+}
+
+#[tauri::command]
+async fn start_stt_daemon(
+    state: tauri::State<'_, AppState>,
+    source: Option<InputSource>,
+) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        backends::start(&state, source.unwrap_or(InputSource::Microphone))
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Start task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn stop_stt_daemon(state: tauri::State<'_, AppState>) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || backends::stop(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Stop task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn pause_stt(state: tauri::State<'_, AppState>) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || backends::set_paused(&state, true))
+        .await
+        .map_err(|e| AppError::from(format!("Pause task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn resume_stt(state: tauri::State<'_, AppState>) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || backends::set_paused(&state, false))
+        .await
+        .map_err(|e| AppError::from(format!("Resume task failed: {}", e)))?
+}
+
+// The tray's left click and the dictation hotkey go through the same toggle.
 #[tauri::command]
-async fn stop_stt_daemon(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let mut process_guard = state.stt_process.lock().unwrap();
-    
-    if let Some(mut child) = process_guard.take() {
-        match child.kill() {
-            Ok(_) => Ok("STT daemon stopped successfully".to_string()),
-            Err(e) => Err(format!("Failed to stop STT daemon: {}", e)),
+async fn toggle_stt(state: tauri::State<'_, AppState>) -> CommandResult<String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || backends::toggle(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Toggle task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn transcribe_file(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> CommandResult<Transcript> {
+    usage::record("transcribe_file");
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        daemon::transcribe_file(&state, std::path::Path::new(&path))
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Transcription task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn get_stt_status(state: tauri::State<'_, AppState>) -> CommandResult<bool> {
+    Ok(backends::is_running(&state))
+}
+
+#[tauri::command]
+fn get_daemon_status(state: tauri::State<'_, AppState>) -> DaemonStatus {
+    daemon::daemon_status(&state)
+}
+
+#[tauri::command]
+fn get_recent_logs(state: tauri::State<'_, AppState>, limit: Option<usize>) -> Vec<DaemonLog> {
+    daemon::recent_logs(&state, limit.unwrap_or(200))
+}
+
+fn enable_tray_items(app_handle: &tauri::AppHandle, listening: bool) {
+    let tray = app_handle.tray_handle();
+    tray.get_item("start_stt").set_enabled(!listening).unwrap();
+    tray.get_item("stop_stt").set_enabled(listening).unwrap();
+    let paused = audio::is_paused();
+    tray.get_item("pause_stt")
+        .set_enabled(listening && !paused)
+        .unwrap();
+    tray.get_item("resume_stt")
+        .set_enabled(listening && paused)
+        .unwrap();
+}
+
+fn set_tray_listening(app_handle: &tauri::AppHandle, listening: bool) {
+    enable_tray_items(app_handle, listening);
+    events::emit(app_handle, UiEvent::SttStatus { listening });
+}
+
+const TRAY_ITEMS: [(&str, &str); 8] = [
+    ("start_stt", "tray-start"),
+    ("stop_stt", "tray-stop"),
+    ("pause_stt", "tray-pause"),
+    ("resume_stt", "tray-resume"),
+    ("show", "tray-show"),
+    ("update", "tray-check-updates"),
+    ("open_logs", "tray-open-logs"),
+    ("quit", "tray-quit"),
+];
+
+// The Recent and Agents submenus go after the start, stop, pause and resume
+// items.
+fn tray_menu(state: &AppState) -> SystemTrayMenu {
+    TRAY_ITEMS
+        .iter()
+        .fold(SystemTrayMenu::new(), |menu, (id, label)| {
+            let menu = menu.add_item(CustomMenuItem::new(id.to_string(), i18n::t(label)));
+            if *id != "resume_stt" {
+                return menu;
+            }
+            let menu = menu.add_submenu(recent::submenu());
+            match agents::submenu(state) {
+                Some(submenu) => menu.add_submenu(submenu),
+                None => menu,
+            }
+        })
+}
+
+// Menus cannot be changed in place, so new labels or transcripts mean a new
+// menu, and the item states have to be put back.
+fn rebuild_tray(app_handle: &tauri::AppHandle) {
+    let state: tauri::State<AppState> = app_handle.state();
+    let _ = app_handle.tray_handle().set_menu(tray_menu(&state));
+    enable_tray_items(app_handle, backends::is_running(&state));
+    updater::relabel_tray(app_handle);
+}
+
+// Tray clicks arrive on the main thread, and starting or stopping waits on the
+// lifecycle queue and the daemon, so the menu would freeze until it is done.
+fn in_background(
+    app_handle: tauri::AppHandle,
+    what: &'static str,
+    action: impl FnOnce(&AppState) -> Result<String, AppError> + Send + 'static,
+) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state: tauri::State<AppState> = app_handle.state();
+        if let Err(e) = action(&state) {
+            tracing::error!("Error {}: {}", what, e);
         }
-    } else {
-        Err("STT daemon is not running".to_string())
+    });
+}
+
+// Ends the session cleanly, then takes down whatever else is still running.
+fn shutdown(app_handle: &tauri::AppHandle) {
+    let state: tauri::State<AppState> = app_handle.state();
+    // An attached tray leaves the core's daemon alone.
+    if !background_core::is_attached() && backends::is_running(&state) {
+        let _ = backends::stop(&state);
     }
+    agents::stop_all(&state);
+    children::kill_all();
 }
 
-#[tauri::command]
-async fn get_stt_status(state: tauri::State<'_, AppState>) -> Result<bool, String> {
-    let process_guard = state.stt_process.lock().unwrap();
-    Ok(process_guard.is_some())
+// `std::process::exit` skips destructors, so child guards never run.
+fn exit(code: i32) -> ! {
+    children::kill_all();
+    std::process::exit(code)
+}
+
+fn show_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
 }
 
 fn main() {
-    let tray_menu = SystemTrayMenu::new()
-        .add_item(CustomMenuItem::new("start_stt".to_string(), "Start STT"))
-        .add_item(CustomMenuItem::new("stop_stt".to_string(), "Stop STT"))
-        .add_item(CustomMenuItem::new("show".to_string(), "Show"))
-        .add_item(CustomMenuItem::new("quit".to_string(), "Quit"));
+    let _log_guard = logging::init();
+    if native_messaging::is_host_invocation() {
+        exit(native_messaging::run(AppState::new(EventBus::new())));
+    }
+    let args = cli::Cli::parse();
+    let mut attach_to_core = false;
+    if !args.headless && args.command.is_none() {
+        match instance::hand_off(&args.files) {
+            Handoff::Activated => {
+                tracing::info!("Handed off to the running instance");
+                return;
+            }
+            // A headless core owns capture and STT; this process is its tray.
+            Handoff::Core => attach_to_core = true,
+            Handoff::Unreachable => {
+                eprintln!("Another instance is running but not responding");
+                exit(1);
+            }
+            Handoff::None => {}
+        }
+    }
+
+    let bus = EventBus::new();
+    let app_state = AppState::new(bus.clone());
+    if let Some(script) = args.mock_stt.clone() {
+        app_state.models.set_backend(SttBackend::Mock { script });
+    }
+    if let Some(path) = args.simulate_input.clone() {
+        match SimulatedInput::new(path, args.simulate_speed) {
+            Ok(input) => *app_state.simulated_input.lock() = Some(input),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(2);
+            }
+        }
+    }
+    crash::install(app_state.clone());
+    logging::spawn_bus_logger(&bus);
+    let webhook_state = WebhookState::load();
+    let hook_state = HookState::load();
+    let chat_state = ChatState::load();
+    // An attached tray only mirrors the core's events; the core delivers them.
+    if attach_to_core {
+        background_core::attach(&app_state);
+    } else {
+        usage::spawn_recorder(&bus, app_state.clone());
+        history::spawn_recorder(&bus, app_state.clone());
+        dictation::spawn(&bus);
+        webhooks::spawn_dispatcher(webhook_state.clone(), &bus);
+        hooks::spawn_dispatcher(hook_state.clone(), &bus);
+        chat::spawn_dispatcher(chat_state.clone(), &bus);
+    }
+    let mqtt_state = MqttState::load();
+
+    if let Some(command) = args.command {
+        mqtt_state.apply(&app_state);
+        exit(cli::run_command(&app_state, command));
+    }
+    control_socket::spawn(app_state.clone());
+    config::spawn_watcher(app_state.clone());
+    models::spawn_budget_monitor(app_state.clone());
+    resources::spawn_monitor(app_state.clone());
+    recordings::spawn_cleanup();
+    let calendar_state = CalendarState::load();
+    let watch_state = WatchState::load();
+    let startup_state = StartupState::default();
+    let portal_state = PortalState::default();
+    let power_state = PowerState::load();
+    if !attach_to_core {
+        instance::queue_files(&app_state, args.files.clone());
+        power::spawn_monitor(app_state.clone(), power_state.clone(), portal_state.clone());
+        session::spawn_monitor(app_state.clone(), power_state.clone(), portal_state.clone());
+        supervisor::spawn(app_state.clone());
+        text_inject::spawn(app_state.clone());
+        tts::spawn(app_state.clone());
+        mcp_client::spawn(app_state.clone());
+        #[cfg(target_os = "linux")]
+        dbus::spawn(app_state.clone());
+        wake_word::spawn(app_state.clone());
+        schedule::spawn(app_state.clone());
+        meeting::spawn(app_state.clone());
+        lookback::spawn(app_state.clone());
+        window_tracker::spawn(app_state.clone());
+    }
+    if args.headless {
+        startup::run(&app_state, &startup_state, || {
+            apply_integrations(&app_state, &mqtt_state, &calendar_state, &watch_state)
+        });
+        exit(cli::run_headless(app_state, &args));
+    }
+
+    portal::spawn(app_state.clone(), portal_state.clone());
+    a11y::spawn(app_state.clone());
+
+    #[cfg(target_os = "macos")]
+    tauri_plugin_deep_link::prepare("com.voice.mcp.agent");
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .system_tray(SystemTray::new().with_menu(tray_menu))
+        .setup(move |app| {
+            let app_handle = app.handle();
+            sidecar::init(&app_handle);
+            #[cfg(target_os = "macos")]
+            automation::register(&app_handle);
+            ptt::init(&app_handle, app.state::<AppState>().inner().clone());
+            tray_icon::spawn(app_handle.clone(), &bus);
+            notifications::spawn(app_handle.clone(), &bus);
+            caption_overlay::spawn(app_handle.clone(), &bus);
+            pill::spawn(app_handle.clone(), &bus);
+            shortcuts::spawn(&bus);
+            let mut events = bus.subscribe();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(AgentEvent::ListeningChanged(listening)) => {
+                            set_tray_listening(&app_handle, listening)
+                        }
+                        Ok(AgentEvent::SttPaused(paused)) => {
+                            enable_tray_items(&app_handle, true);
+                            events::emit(&app_handle, UiEvent::SttPaused { paused });
+                        }
+                        Ok(AgentEvent::SttLanguageChanged(language)) => {
+                            events::emit(&app_handle, UiEvent::SttLanguageChanged(language));
+                        }
+                        Ok(AgentEvent::Transcript(transcript)) => {
+                            if recent::push(&transcript) {
+                                rebuild_tray(&app_handle);
+                            }
+                            events::emit(&app_handle, UiEvent::SttTranscript(transcript));
+                        }
+                        Ok(AgentEvent::DaemonLog(entry)) => {
+                            events::emit(&app_handle, UiEvent::SttLog(entry));
+                        }
+                        Ok(AgentEvent::AgentChanged(status)) => {
+                            rebuild_tray(&app_handle);
+                            events::emit(&app_handle, UiEvent::AgentStatus(status));
+                        }
+                        Ok(AgentEvent::DaemonCrashed {
+                            session_id,
+                            exit_code,
+                            ..
+                        }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::SttCrashed {
+                                    session_id,
+                                    exit_code,
+                                },
+                            );
+                        }
+                        Ok(AgentEvent::DaemonRestarting { attempt, delay_ms }) => {
+                            events::emit(&app_handle, UiEvent::SttRestarting { attempt, delay_ms });
+                        }
+                        Ok(AgentEvent::BackendDegraded {
+                            backend,
+                            reason,
+                            fallback,
+                        }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::BackendDegraded {
+                                    backend,
+                                    reason,
+                                    fallback,
+                                },
+                            );
+                        }
+                        Ok(AgentEvent::BackendRestored {
+                            backend,
+                            dropped_ms,
+                        }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::BackendRestored {
+                                    backend,
+                                    dropped_ms,
+                                },
+                            );
+                        }
+                        Ok(AgentEvent::ConfigChanged(config)) => {
+                            events::emit(&app_handle, UiEvent::ConfigChanged(config));
+                        }
+                        Ok(AgentEvent::SpeechStarted) => {
+                            events::emit(&app_handle, UiEvent::SpeechStarted {});
+                        }
+                        Ok(AgentEvent::SpeechEnded) => {
+                            events::emit(&app_handle, UiEvent::SpeechEnded {});
+                        }
+                        Ok(AgentEvent::WakeWordDetected { name, score }) => {
+                            events::emit(&app_handle, UiEvent::WakeWordDetected { name, score });
+                        }
+                        Ok(AgentEvent::PttPressed) => {
+                            events::emit(&app_handle, UiEvent::PttPressed {});
+                        }
+                        Ok(AgentEvent::PttReleased) => {
+                            events::emit(&app_handle, UiEvent::PttReleased {});
+                        }
+                        Ok(AgentEvent::RepeatRequested(transcript)) => {
+                            events::emit(&app_handle, UiEvent::SttRepeat(transcript));
+                        }
+                        Ok(AgentEvent::SpeakRequested(text)) => {
+                            events::emit(&app_handle, UiEvent::SpeakRequested { text });
+                        }
+                        Ok(AgentEvent::SpeakingChanged(speaking)) => {
+                            events::emit(&app_handle, UiEvent::SpeakingChanged { speaking });
+                        }
+                        Ok(AgentEvent::McpToolCalled {
+                            route_id,
+                            server,
+                            tool,
+                            result,
+                        }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::McpToolCalled {
+                                    route_id,
+                                    server,
+                                    tool,
+                                    result,
+                                },
+                            );
+                        }
+                        Ok(AgentEvent::ActivationRequested { args }) => {
+                            show_main_window(&app_handle);
+                            events::emit(&app_handle, UiEvent::InstanceActivated { args });
+                        }
+                        Ok(AgentEvent::PowerChanged(event)) => {
+                            if let Some(system_event) = event.system_event() {
+                                events::emit(&app_handle, system_event);
+                            }
+                            events::emit(&app_handle, UiEvent::PowerChanged(event));
+                        }
+                        Ok(AgentEvent::LanguageChanged(locale)) => {
+                            rebuild_tray(&app_handle);
+                            events::emit(&app_handle, UiEvent::LanguageChanged { locale });
+                        }
+                        Ok(AgentEvent::Announcement(announcement)) => {
+                            events::emit(&app_handle, UiEvent::A11yAnnouncement(announcement));
+                        }
+                        Ok(AgentEvent::AudioStats { session_id, buffer }) => {
+                            events::emit(&app_handle, UiEvent::AudioStats { session_id, buffer });
+                        }
+                        Ok(AgentEvent::AudioLevel(level)) => {
+                            events::emit(&app_handle, UiEvent::AudioLevel(level));
+                        }
+                        Ok(AgentEvent::InputDeviceChanged(info)) => {
+                            events::emit(&app_handle, UiEvent::InputDeviceChanged(info));
+                        }
+                        Ok(AgentEvent::FocusedAppChanged(app)) => {
+                            events::emit(&app_handle, UiEvent::FocusedAppChanged(app));
+                        }
+                        Ok(AgentEvent::InjectionCompleted(outcome)) => {
+                            events::emit(&app_handle, UiEvent::InjectionCompleted(outcome));
+                        }
+                        Ok(AgentEvent::StartupProgress(progress)) => {
+                            events::emit(&app_handle, UiEvent::StartupProgress(progress));
+                        }
+                        Ok(AgentEvent::OperationProgress(progress)) => {
+                            events::emit(&app_handle, UiEvent::OperationProgress(progress));
+                        }
+                        Ok(AgentEvent::FileProgress(progress)) => {
+                            events::emit(&app_handle, UiEvent::FileProgress(progress));
+                        }
+                        Ok(AgentEvent::SessionSummary(session)) => {
+                            events::emit(&app_handle, UiEvent::SessionSummary(session));
+                        }
+                        Ok(AgentEvent::MeetingSummary(summary)) => {
+                            events::emit(&app_handle, UiEvent::MeetingSummary(summary));
+                        }
+                        Ok(AgentEvent::ModelDownload(progress)) => {
+                            events::emit(&app_handle, UiEvent::ModelDownloadProgress(progress));
+                        }
+                        Ok(AgentEvent::LatencyReport(metrics)) => {
+                            events::emit(&app_handle, UiEvent::LatencyReport(metrics));
+                        }
+                        Ok(AgentEvent::SttMetrics(usage)) => {
+                            events::emit(&app_handle, UiEvent::SttMetrics(usage));
+                        }
+                        Ok(AgentEvent::InstallProgress(progress)) => {
+                            events::emit(&app_handle, UiEvent::DepsInstallProgress(progress));
+                        }
+                        Ok(AgentEvent::IntentDetected { intent, text }) => {
+                            events::emit(&app_handle, UiEvent::VoiceCommand { intent, text });
+                        }
+                        Ok(AgentEvent::VoiceCommandEvent { name, text }) => {
+                            events::emit_custom(&app_handle, &name, text);
+                        }
+                        Ok(AgentEvent::VoiceModeChanged(mode)) => {
+                            events::emit(&app_handle, UiEvent::VoiceModeChanged { mode });
+                        }
+                        Ok(AgentEvent::ModelChanged {
+                            model,
+                            loaded,
+                            reason,
+                        }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::ModelChanged {
+                                    model,
+                                    loaded,
+                                    reason,
+                                },
+                            );
+                        }
+                        Ok(AgentEvent::MeetingPrompt { event_id, title }) => {
+                            notifications::show(
+                                &app_handle,
+                                &i18n::t_args("notification-meeting-title", &[("title", &title)]),
+                                &i18n::t("notification-meeting-body"),
+                            );
+                            events::emit(&app_handle, UiEvent::MeetingPrompt { event_id, title });
+                        }
+                        Ok(AgentEvent::RoomTranscript {
+                            room,
+                            participant,
+                            transcript,
+                        }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::LivekitTranscript {
+                                    room,
+                                    participant,
+                                    transcript,
+                                },
+                            );
+                        }
+                        Ok(AgentEvent::RoomParticipants { room, participants }) => {
+                            events::emit(
+                                &app_handle,
+                                UiEvent::LivekitParticipants { room, participants },
+                            );
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+            let app_handle = app.handle();
+            std::thread::spawn(move || {
+                let state: tauri::State<AppState> = app_handle.state();
+                let startup: tauri::State<StartupState> = app_handle.state();
+                startup::run(&state, &startup, || {
+                    if background_core::is_attached() {
+                        return Ok(());
+                    }
+                    apply_integrations(
+                        &state,
+                        &app_handle.state::<MqttState>(),
+                        &app_handle.state::<CalendarState>(),
+                        &app_handle.state::<WatchState>(),
+                    )
+                });
+            });
+            updater::spawn_checker(app.handle());
+            permissions::recheck(&app.handle());
+            let pending = crash::pending();
+            if !pending.is_empty() {
+                events::emit(
+                    &app.handle(),
+                    UiEvent::CrashReportsPending { reports: pending },
+                );
+            }
+            let app_handle = app.handle();
+            let mut logs = logging::subscribe();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match logs.recv().await {
+                        Ok(entry) => {
+                            events::emit(&app_handle, UiEvent::AppLog(entry));
+                        }
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+            Ok(())
+        })
+        .register_uri_scheme_protocol(caption_overlay::SCHEME, caption_overlay::protocol)
+        .register_uri_scheme_protocol(pill::SCHEME, pill::protocol)
+        // On macOS the menu moves to a right click while the left click does something.
+        .system_tray(
+            SystemTray::new()
+                .with_menu(tray_menu(&app_state))
+                .with_menu_on_left_click(config::current().tray.left_click == TrayAction::None),
+        )
+        .on_window_event(|event| match event.event() {
+            tauri::WindowEvent::Focused(true) => permissions::recheck(&event.window().app_handle()),
+            // Anything that is not audio is ignored rather than failed.
+            tauri::WindowEvent::FileDrop(FileDropEvent::Dropped(paths)) => {
+                let audio: Vec<_> = paths
+                    .iter()
+                    .filter(|p| batch::is_audio(p))
+                    .cloned()
+                    .collect();
+                if !audio.is_empty() {
+                    let state: tauri::State<AppState> = event.window().state();
+                    let batch_id = batch::queue(&state, audio);
+                    events::emit_to(event.window(), UiEvent::FilesDropped { batch_id });
+                }
+            }
+            _ => {}
+        })
         .on_system_tray_event(|app, event| match event {
             SystemTrayEvent::MenuItemClick { id, .. } => {
                 let app_handle = app.app_handle();
                 match id.as_str() {
-                    "start_stt" => {
-                        let state: tauri::State<AppState> = app_handle.state();
-                        tauri::async_runtime::spawn(async move {
-                            match start_stt_daemon(state).await {
-                                Ok(msg) => {
-                                    app_handle.tray_handle()
-                                        .get_item("start_stt")
-                                        .set_enabled(false)
-                                        .unwrap();
-                                    app_handle.tray_handle()
-                                        .get_item("stop_stt")
-                                        .set_enabled(true)
-                                        .unwrap();
-                                    app_handle.emit_all("stt_status", true).unwrap();
-                                }
-                                Err(e) => {
-                                    println!("Error starting STT: {}", e);
-                                }
-                            }
-                        });
+                    "start_stt" => in_background(app_handle, "starting STT", |state| {
+                        backends::start(state, InputSource::Microphone)
+                    }),
+                    "stop_stt" => in_background(app_handle, "stopping STT", backends::stop),
+                    "pause_stt" | "resume_stt" => {
+                        let paused = id == "pause_stt";
+                        in_background(app_handle, "pausing STT", move |state| {
+                            backends::set_paused(state, paused)
+                        })
                     }
-                    "stop_stt" => {
-                        let state: tauri::State<AppState> = app_handle.state();
-                        taira::async_runtime::spawn(async move {
-                            match stop_stt_daemon(state).await {
-                                Ok(msg) => {
-                                    app_handle.tray_handle()
-                                        .get_item("start_stt")
-                                        .set_enabled(true)
-                                        .unwrap();
-                                    app_handle.tray_handle()
-                                        .get_item("stop_stt")
-                                        .set_enabled(false)
-                                        .unwrap();
-                                    app_handle.emit_all("stt_status", false).unwrap();
-                                }
-                                Err(e) => {
-                                    println!("Error stopping STT: {}", e);
-                                }
-                            }
-                        });
-                    }
-                    "show" => {
-                        let window = app_handle.get_window("main").unwrap();
-                        window.show().unwrap();
-                        window.set_focus().unwrap();
+                    "update" => updater::on_tray_click(&app_handle),
+                    "show" => show_main_window(&app_handle),
+                    "open_logs" => {
+                        if let Err(e) = logging::open_folder() {
+                            tracing::error!("{}", e);
+                        }
                     }
                     "quit" => {
-                        let state: tauri::State<AppState> = app_handle.state();
-                        let mut process_guard = state.stt_process.lock().unwrap();
-                        if let Some(mut child) = process_guard.take() {
-                            let _ = child.kill();
+                        shutdown(&app_handle);
+                        app_handle.exit(0);
+                    }
+                    id if id.starts_with(agents::MENU_PREFIX) => {
+                        let name = id[agents::MENU_PREFIX.len()..].to_string();
+                        in_background(app_handle, "toggling agent", move |state| {
+                            agents::toggle(state, &name)
+                        })
+                    }
+                    id if id.starts_with(recent::MENU_PREFIX) => {
+                        if let Err(e) = recent::copy(&id[recent::MENU_PREFIX.len()..]) {
+                            tracing::error!("Error copying transcript: {}", e);
                         }
-                        std::process::exit(0);
                     }
                     _ => {}
                 }
             }
+            SystemTrayEvent::LeftClick { .. } => {
+                tray_icon::clicked(&app.app_handle(), TrayClick::Left)
+            }
+            SystemTrayEvent::DoubleClick { .. } => {
+                tray_icon::clicked(&app.app_handle(), TrayClick::Double)
+            }
             _ => {}
         })
-        .manage(AppState {
-            stt_process: Mutex::new(None),
-        })
+        .manage(app_state)
+        .manage(webhook_state)
+        .manage(hook_state)
+        .manage(chat_state)
+        .manage(GrpcState::default())
+        .manage(ApiState::default())
+        .manage(WsState::default())
+        .manage(MdnsState::load())
+        .manage(mqtt_state)
+        .manage(watch_state)
+        .manage(calendar_state)
+        .manage(UpdaterState::load())
+        .manage(LiveKitState::load())
+        .manage(MeetingCaptionState::default())
+        .manage(startup_state)
+        .manage(portal_state)
+        .manage(power_state)
         .invoke_handler(tauri::generate_handler![
             start_stt_daemon,
             stop_stt_daemon,
-            get_stt_status
+            pause_stt,
+            resume_stt,
+            toggle_stt,
+            get_stt_status,
+            get_daemon_status,
+            get_recent_logs,
+            supervisor::get_restart_policy,
+            supervisor::set_restart_policy,
+            audio::start_capture,
+            audio::stop_capture,
+            audio::list_audio_devices,
+            audio::enable_level_meter,
+            audio::list_input_devices,
+            audio::set_input_device,
+            audio::set_capture_source,
+            resampler::get_pipeline_format,
+            vad::get_vad_config,
+            vad::set_vad_sensitivity,
+            vad::get_capture_params,
+            vad::set_capture_params,
+            denoise::get_noise_suppression,
+            denoise::set_noise_suppression,
+            wake_word::get_wake_word_config,
+            wake_word::set_wake_word,
+            wake_word::enable_wake_word,
+            backends::get_stt_backend,
+            backends::set_stt_backend,
+            secrets::store_api_key,
+            secrets::get_api_key_status,
+            secrets::delete_api_key,
+            native_stt::start_native_stt,
+            native_stt::stop_native_stt,
+            native_stt::transcribe_buffer,
+            config::get_config,
+            config::set_config,
+            settings::export_settings,
+            settings::import_settings,
+            notifications::get_notification_policy,
+            notifications::set_notification_policy,
+            caption_overlay::show_caption_overlay,
+            caption_overlay::hide_caption_overlay,
+            caption_overlay::list_monitors,
+            caption_overlay::get_caption_overlay_config,
+            caption_overlay::set_caption_overlay_config,
+            pill::show_pill,
+            pill::hide_pill,
+            pill::set_pill_position,
+            env::detect_python_envs,
+            env::select_python_env,
+            env::install_daemon_deps,
+            diagnostics::run_diagnostics,
+            ptt::get_ptt_hotkey,
+            ptt::register_ptt_hotkey,
+            ptt::unregister_ptt_hotkey,
+            ptt::register_undo_hotkey,
+            ptt::unregister_undo_hotkey,
+            ptt::register_lookback_hotkey,
+            ptt::unregister_lookback_hotkey,
+            shortcuts::list_shortcuts,
+            shortcuts::bind_shortcut,
+            shortcuts::unbind_shortcut,
+            lookback::get_lookback_config,
+            lookback::set_lookback_config,
+            lookback::transcribe_buffer_window,
+            schedule::get_schedule,
+            schedule::set_schedule,
+            history::query_transcripts,
+            history::get_transcript_segments,
+            history::delete_transcript,
+            history::clear_history,
+            export::export_transcripts,
+            dictation::start_session,
+            dictation::end_session,
+            dictation::get_current_session,
+            dictation::list_sessions,
+            dictation::get_session,
+            meeting::start_meeting,
+            meeting::stop_meeting,
+            meeting::get_meeting_status,
+            meeting::get_meeting_summaries,
+            meeting::get_meeting_config,
+            meeting::set_meeting_config,
+            formatting::get_formatting_rules,
+            formatting::set_formatting_rules,
+            voice_commands::list_command_rules,
+            voice_commands::add_command_rule,
+            voice_commands::remove_command_rule,
+            voice_commands::set_command_rule_enabled,
+            voice_commands::get_voice_mode,
+            voice_commands::set_voice_mode,
+            recent::copy_transcript,
+            transcribe_file,
+            batch::transcribe_files,
+            text_inject::inject_text,
+            text_inject::undo_last_injection,
+            text_inject::get_inject_config,
+            text_inject::set_inject_config,
+            tts::speak,
+            tts::stop_speaking,
+            tts::list_voices,
+            tts::get_tts_config,
+            tts::set_tts_config,
+            mcp_client::add_mcp_server,
+            mcp_client::remove_mcp_server,
+            mcp_client::list_mcp_servers,
+            mcp_client::list_mcp_tools,
+            mcp_client::call_mcp_tool,
+            mcp_client::add_mcp_route,
+            mcp_client::remove_mcp_route,
+            mcp_client::list_mcp_routes,
+            mcp_client::set_mcp_route_enabled,
+            events::subscribe_capabilities,
+            redaction::get_redaction_config,
+            redaction::set_redaction_rules,
+            agents::list_agents,
+            agents::set_agent,
+            agents::remove_agent,
+            agents::start_agent,
+            agents::stop_agent,
+            window_tracker::get_focused_app,
+            window_tracker::list_app_profiles,
+            window_tracker::set_app_profile,
+            window_tracker::remove_app_profile,
+            api::start_api_server,
+            api::stop_api_server,
+            api::get_api_server_status,
+            openai_api::set_openai_api_enabled,
+            openai_api::get_openai_api_enabled,
+            ws_server::start_ws_server,
+            ws_server::stop_ws_server,
+            ws_server::get_ws_server_status,
+            ws_server::rotate_webhook_token,
+            mdns::get_mdns_config,
+            mdns::set_mdns_config,
+            mdns::get_mdns_status,
+            auth::get_api_auth_status,
+            auth::set_api_auth_enabled,
+            auth::mint_api_token,
+            auth::revoke_api_token,
+            auth::get_api_audit_log,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::delete_profile,
+            profiles::set_active_profile,
+            profiles::get_stt_language,
+            profiles::set_stt_language,
+            profiles::detect_language,
+            translation::get_translation_config,
+            translation::set_translation_target,
+            translation::set_translation_provider,
+            vocab::list_vocab_terms,
+            vocab::add_vocab_term,
+            vocab::remove_vocab_term,
+            vocab::import_vocab,
+            mqtt::get_mqtt_config,
+            mqtt::set_mqtt_config,
+            mqtt::get_mqtt_status,
+            livekit::get_livekit_config,
+            livekit::set_livekit_config,
+            livekit::join_livekit_room,
+            livekit::leave_livekit_room,
+            livekit::get_livekit_status,
+            meeting_captions::start_caption_posting,
+            meeting_captions::stop_caption_posting,
+            meeting_captions::get_caption_posting_status,
+            plugins::list_plugins,
+            plugins::install_plugin,
+            plugins::remove_plugin,
+            plugins::set_plugin_enabled,
+            plugins::reload_plugins,
+            grpc::start_grpc_server,
+            grpc::stop_grpc_server,
+            webhooks::list_webhooks,
+            webhooks::add_webhook,
+            webhooks::remove_webhook,
+            webhooks::set_webhook_enabled,
+            webhooks::get_webhook_deliveries,
+            crash::list_crash_reports,
+            crash::acknowledge_crash_report,
+            crash::export_crash_report,
+            crash::delete_crash_report,
+            crash::get_crash_config,
+            crash::set_crash_config,
+            crash::upload_crash_report,
+            crash::create_support_bundle,
+            updater::get_update_config,
+            updater::set_update_config,
+            updater::check_for_updates,
+            updater::install_update,
+            models::get_model_config,
+            models::set_model_config,
+            models::get_model_status,
+            models::list_models,
+            models::download_model,
+            models::delete_model,
+            models::set_active_model,
+            resources::get_resource_usage,
+            recordings::get_recording_config,
+            recordings::set_recording_config,
+            recordings::get_audio_for_transcript,
+            encryption::get_encryption_config,
+            encryption::enable_encryption,
+            encryption::disable_encryption,
+            startup::get_startup_status,
+            latency::run_latency_benchmark,
+            latency::get_latency_metrics,
+            autotune::get_tuning,
+            autotune::run_autotune,
+            autotune::reset_tuning,
+            autotune::get_compute_capabilities,
+            autotune::set_compute_device,
+            lifecycle::get_pending_operations,
+            permissions::get_permissions,
+            permissions::check_mic_permission,
+            permissions::open_permission_settings,
+            portal::get_portal_status,
+            portal::set_portal_injection,
+            power::get_power_status,
+            power::set_pause_on_battery_saver,
+            a11y::get_a11y_config,
+            a11y::set_a11y_config,
+            i18n::get_language_settings,
+            i18n::set_language,
+            sandbox::get_sandbox_status,
+            sandbox::set_sandbox_config,
+            simulated_input::get_simulated_input,
+            simulated_input::set_simulated_input,
+            usage::get_metrics,
+            usage::set_metrics_enabled,
+            usage::clear_metrics,
+            logging::set_log_level,
+            logging::get_app_logs,
+            logging::get_log_entries,
+            logging::open_log_folder,
+            hooks::list_hooks,
+            hooks::add_hook,
+            hooks::remove_hook,
+            hooks::set_hook_enabled,
+            hooks::set_hook_sandbox,
+            hooks::get_hook_runs,
+            calendar::get_calendar_config,
+            calendar::set_calendar_config,
+            calendar::get_calendar_status,
+            calendar::accept_meeting_prompt,
+            chat::list_chat_integrations,
+            chat::add_chat_integration,
+            chat::remove_chat_integration,
+            chat::set_chat_integration_enabled,
+            chat::flag_transcript,
+            chat::post_session_summary,
+            chat::post_transcript_snippet,
+            watch_folders::get_watch_status,
+            watch_folders::add_watch_folder,
+            watch_folders::remove_watch_folder,
+            watch_folders::set_watch_folder_enabled
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| match event {
+            tauri::RunEvent::Exit => shutdown(app_handle),
+            tauri::RunEvent::Updater(event) => updater::on_event(app_handle, event),
+            _ => {}
+        });
 }
\ No newline at end of file
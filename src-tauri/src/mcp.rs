@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::backends;
+use crate::daemon::{self, InputSource};
+use crate::error::{AppError, ErrorCode};
+use crate::AppState;
+
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn tools() -> Value {
+    let no_arguments = json!({ "type": "object", "properties": {} });
+    json!([
+        {
+            "name": "transcribe_audio",
+            "description": "Transcribe an audio file on this machine and return the text",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Absolute path to the audio file" }
+                },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "get_last_transcript",
+            "description": "Return the most recent final transcript",
+            "inputSchema": no_arguments
+        },
+        {
+            "name": "start_listening",
+            "description": "Start transcribing the microphone",
+            "inputSchema": no_arguments
+        },
+        {
+            "name": "stop_listening",
+            "description": "Stop transcribing the microphone",
+            "inputSchema": no_arguments
+        }
+    ])
+}
+
+async fn blocking<T: Send + 'static>(
+    state: &AppState,
+    f: impl FnOnce(&AppState) -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    let state = state.clone();
+    tauri::async_runtime::spawn_blocking(move || f(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Tool task failed: {}", e)))?
+}
+
+// None for a tool that does not exist.
+async fn call_tool(
+    state: &AppState,
+    name: &str,
+    arguments: &Value,
+) -> Option<Result<String, AppError>> {
+    let result = match name {
+        "transcribe_audio" => match arguments.get("path").and_then(Value::as_str) {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                blocking(state, move |state| daemon::transcribe_file(state, &path))
+                    .await
+                    .map(|t| t.text)
+            }
+            None => Err(AppError::new(ErrorCode::InvalidInput, "`path` is required")),
+        },
+        "get_last_transcript" => state
+            .last_transcript
+            .lock()
+            .as_ref()
+            .map(|t| t.text.clone())
+            .ok_or_else(|| AppError::new(ErrorCode::NotFound, "No transcript yet")),
+        "start_listening" => {
+            blocking(state, |state| {
+                backends::start(state, InputSource::Microphone)
+            })
+            .await
+        }
+        "stop_listening" => blocking(state, backends::stop).await,
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+// One JSON-RPC message in, at most one out: notifications get no reply.
+// Tool failures are results with `isError`, as MCP asks, so the model sees
+// them; protocol mistakes are JSON-RPC errors.
+pub async fn handle(state: &AppState, message: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(message) {
+        Ok(request) => request,
+        Err(e) => return Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+    };
+    let id = request.get("id").cloned()?;
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "voice-mcp-agent", "version": env!("CARGO_PKG_VERSION") }
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let name = params
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let arguments = params
+                .get("arguments")
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            match call_tool(state, name, &arguments).await {
+                Some(Ok(text)) => json!({
+                    "content": [{ "type": "text", "text": text }],
+                    "isError": false
+                }),
+                Some(Err(e)) => json!({
+                    "content": [{ "type": "text", "text": e.message }],
+                    "isError": true
+                }),
+                None => {
+                    return Some(error(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("Unknown tool: {}", name),
+                    ))
+                }
+            }
+        }
+        _ => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method: {}", method),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+// Newline-delimited messages on stdin and stdout, as MCP clients spawn
+// servers; stdout carries nothing else.
+pub fn run_stdio(state: AppState) -> i32 {
+    tauri::async_runtime::block_on(async move {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(reply) = handle(&state, &line).await {
+                let mut bytes = reply.to_string().into_bytes();
+                bytes.push(b'\n');
+                if stdout.write_all(&bytes).await.is_err() || stdout.flush().await.is_err() {
+                    break;
+                }
+            }
+        }
+        if backends::is_running(&state) {
+            let _ = blocking(&state, backends::stop).await;
+        }
+        0
+    })
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/mcp/ws", get(upgrade))
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.protocols(["mcp"])
+        .on_upgrade(move |socket| serve_socket(socket, state))
+}
+
+async fn serve_socket(mut socket: WebSocket, state: AppState) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        if let Some(reply) = handle(&state, &text).await {
+            if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use std::collections::{BTreeMap, HashMap};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::AgentEvent;
+use crate::children::{self, Tracked};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::mcp::PROTOCOL_VERSION;
+use crate::sandbox::{self, ProcessKind};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "mcp_servers.json";
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+// JSON-RPC error code, for requests the server sends us.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "transport", rename_all = "snake_case")]
+pub enum McpTransport {
+    // Started by the agent, newline-delimited JSON-RPC on stdin and stdout.
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+    },
+    // Streamable HTTP: each message is a POST to `url`.
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub transport: McpTransport,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerStatus {
+    #[serde(flatten)]
+    pub config: McpServerConfig,
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpRoute {
+    pub id: String,
+    // Matched against the start of the utterance, ignoring case and
+    // punctuation; the words after it are `{rest}`.
+    pub pattern: String,
+    pub server: String,
+    pub tool: String,
+    // String values have `{text}` and `{rest}` filled in.
+    pub arguments: Value,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct McpClientConfig {
+    servers: Vec<McpServerConfig>,
+    routes: Vec<McpRoute>,
+}
+
+enum Transport {
+    Stdio {
+        _child: Child,
+        _tracked: Tracked,
+        stdin: ChildStdin,
+        stdout: Lines<BufReader<ChildStdout>>,
+    },
+    Http {
+        client: reqwest::Client,
+        url: String,
+        headers: BTreeMap<String, String>,
+        // Handed out by the server at `initialize`.
+        session: Option<String>,
+    },
+}
+
+struct Connection {
+    transport: Transport,
+    next_id: u64,
+}
+
+static CONFIG: Lazy<Mutex<McpClientConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// Servers are connected on first use and stay connected.
+static CONNECTIONS: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<Connection>>>>> =
+    Lazy::new(Mutex::default);
+
+fn server_error(server: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::Network,
+        format!("MCP server {} failed: {}", server, e),
+    )
+    .with_context("server", server.to_string())
+}
+
+// A streamed reply is a series of SSE `data:` lines; the answer is among them.
+fn answer(body: &str, id: u64) -> Option<Value> {
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        return Some(value);
+    }
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+        .find(|message| message.get("id").and_then(Value::as_u64) == Some(id))
+}
+
+impl Connection {
+    async fn send(&mut self, server: &str, message: &Value) -> Result<Option<Value>, AppError> {
+        match &mut self.transport {
+            Transport::Stdio { stdin, .. } => {
+                let mut bytes = message.to_string().into_bytes();
+                bytes.push(b'\n');
+                stdin
+                    .write_all(&bytes)
+                    .await
+                    .map_err(|e| server_error(server, e))?;
+                stdin.flush().await.map_err(|e| server_error(server, e))?;
+                Ok(None)
+            }
+            Transport::Http {
+                client,
+                url,
+                headers,
+                session,
+            } => {
+                let mut request = client
+                    .post(url.as_str())
+                    .header("Accept", "application/json, text/event-stream")
+                    .json(message);
+                for (name, value) in headers.iter() {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                if let Some(session) = session.as_deref() {
+                    request = request.header("Mcp-Session-Id", session);
+                }
+                let response = request
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| server_error(server, e))?;
+                if let Some(id) = response
+                    .headers()
+                    .get("Mcp-Session-Id")
+                    .and_then(|id| id.to_str().ok())
+                {
+                    *session = Some(id.to_string());
+                }
+                let body = response.text().await.map_err(|e| server_error(server, e))?;
+                Ok(message
+                    .get("id")
+                    .and_then(Value::as_u64)
+                    .and_then(|id| answer(&body, id)))
+            }
+        }
+    }
+
+    // Skips notifications, and turns down requests from the server, until
+    // the answer with `id` arrives.
+    async fn receive(&mut self, server: &str, id: u64) -> Result<Value, AppError> {
+        loop {
+            let line = match &mut self.transport {
+                Transport::Stdio { stdout, .. } => stdout
+                    .next_line()
+                    .await
+                    .map_err(|e| server_error(server, e))?
+                    .ok_or_else(|| server_error(server, "it exited"))?,
+                Transport::Http { .. } => return Err(server_error(server, "no answer")),
+            };
+            let message: Value = match serde_json::from_str(&line) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+            if message.get("method").is_some() {
+                if let Some(request) = message.get("id").cloned() {
+                    let refusal = json!({
+                        "jsonrpc": "2.0",
+                        "id": request,
+                        "error": { "code": METHOD_NOT_FOUND, "message": "Not supported" }
+                    });
+                    self.send(server, &refusal).await?;
+                }
+                continue;
+            }
+            if message.get("id").and_then(Value::as_u64) == Some(id) {
+                return Ok(message);
+            }
+        }
+    }
+
+    async fn request(
+        &mut self,
+        server: &str,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, AppError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let exchange = async {
+            match self.send(server, &message).await? {
+                Some(reply) => Ok(reply),
+                None => self.receive(server, id).await,
+            }
+        };
+        let reply = tokio::time::timeout(CALL_TIMEOUT, exchange)
+            .await
+            .map_err(|_| {
+                server_error(
+                    server,
+                    format!(
+                        "no answer to {} within {} s",
+                        method,
+                        CALL_TIMEOUT.as_secs()
+                    ),
+                )
+            })??;
+        if let Some(error) = reply.get("error") {
+            let text = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(AppError::new(
+                ErrorCode::Failed,
+                format!("MCP server {} refused {}: {}", server, method, text),
+            )
+            .with_context("server", server.to_string()));
+        }
+        Ok(reply.get("result").cloned().unwrap_or(Value::Null))
+    }
+}
+
+async fn connect(config: &McpServerConfig) -> Result<Connection, AppError> {
+    let transport = match &config.transport {
+        McpTransport::Stdio { command, args, env } => {
+            // Third-party programs get the same sandbox as hooks.
+            let policy = sandbox::policy(ProcessKind::Hook);
+            let mut std_command = sandbox::command(ProcessKind::Hook, &policy, command);
+            std_command
+                .args(args)
+                .envs(env)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null());
+            let mut child = tokio::process::Command::from(std_command)
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| {
+                    AppError::new(
+                        ErrorCode::NotFound,
+                        format!("Failed to start MCP server {}: {}", config.name, e),
+                    )
+                })?;
+            let pid = child.id().unwrap_or_default();
+            sandbox::confine(&policy, pid);
+            let (stdin, stdout) = match (child.stdin.take(), child.stdout.take()) {
+                (Some(stdin), Some(stdout)) => (stdin, stdout),
+                _ => return Err(server_error(&config.name, "no stdio")),
+            };
+            Transport::Stdio {
+                _child: child,
+                _tracked: children::track(pid),
+                stdin,
+                stdout: BufReader::new(stdout).lines(),
+            }
+        }
+        McpTransport::Http { url, headers } => Transport::Http {
+            client: reqwest::Client::new(),
+            url: url.clone(),
+            headers: headers.clone(),
+            session: None,
+        },
+    };
+    let mut connection = Connection {
+        transport,
+        next_id: 1,
+    };
+    connection
+        .request(
+            &config.name,
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "voice-mcp-agent", "version": env!("CARGO_PKG_VERSION") }
+            }),
+        )
+        .await?;
+    connection
+        .send(
+            &config.name,
+            &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        )
+        .await?;
+    tracing::info!(target: "mcp_client", server = %config.name, "MCP server connected");
+    Ok(connection)
+}
+
+fn server(name: &str) -> Result<McpServerConfig, AppError> {
+    CONFIG
+        .lock()
+        .servers
+        .iter()
+        .find(|server| server.name == name)
+        .cloned()
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("No MCP server named {}", name)))
+}
+
+// A connection that fails is dropped, so the next request starts afresh.
+async fn request(name: &str, method: &str, params: Value) -> Result<Value, AppError> {
+    let config = server(name)?;
+    let existing = CONNECTIONS.lock().get(name).cloned();
+    let connection = match existing {
+        Some(connection) => connection,
+        None => {
+            let connection = Arc::new(tokio::sync::Mutex::new(connect(&config).await?));
+            CONNECTIONS
+                .lock()
+                .insert(name.to_string(), connection.clone());
+            connection
+        }
+    };
+    let result = connection.lock().await.request(name, method, params).await;
+    if let Err(error) = &result {
+        if error.code != ErrorCode::Failed {
+            CONNECTIONS.lock().remove(name);
+        }
+    }
+    result
+}
+
+fn disconnect(name: &str) {
+    CONNECTIONS.lock().remove(name);
+}
+
+// Returns the tool's text content.
+pub async fn call_tool(server: &str, tool: &str, arguments: Value) -> Result<String, AppError> {
+    let result = request(
+        server,
+        "tools/call",
+        json!({ "name": tool, "arguments": arguments }),
+    )
+    .await?;
+    let text = result
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|content| {
+            content
+                .iter()
+                .filter_map(|part| part.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return Err(AppError::new(
+            ErrorCode::Failed,
+            format!("{} on {} failed: {}", tool, server, text),
+        )
+        .with_context("server", server.to_string())
+        .with_context("tool", tool.to_string()));
+    }
+    Ok(text)
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// The words of `text` after `pattern`, if it starts with it.
+fn rest(pattern: &str, text: &str) -> Option<String> {
+    let pattern = words(pattern);
+    if pattern.is_empty() {
+        return None;
+    }
+    let mut tokens = text.split_whitespace();
+    let mut spoken = Vec::new();
+    while spoken.len() < pattern.len() {
+        let token = tokens.next()?;
+        spoken.extend(words(token));
+    }
+    if spoken != pattern {
+        return None;
+    }
+    let rest: Vec<&str> = tokens.collect();
+    let rest = rest.join(" ");
+    Some(
+        rest.trim_start_matches(|c: char| !c.is_alphanumeric())
+            .to_string(),
+    )
+}
+
+pub fn fill(template: &Value, text: &str, rest: &str) -> Value {
+    match template {
+        Value::String(s) => Value::String(s.replace("{text}", text).replace("{rest}", rest)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| fill(v, text, rest)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), fill(v, text, rest)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+// Each final transcript goes to the first enabled route it matches. It is
+// still typed and published as usual.
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => {
+                    let routes = CONFIG.lock().routes.clone();
+                    let matched = routes
+                        .into_iter()
+                        .filter(|r| r.enabled)
+                        .find_map(|route| rest(&route.pattern, &t.text).map(|rest| (route, rest)));
+                    let (route, rest) = match matched {
+                        Some(matched) => matched,
+                        None => continue,
+                    };
+                    let arguments = fill(&route.arguments, &t.text, &rest);
+                    let state = state.clone();
+                    tauri::async_runtime::spawn(async move {
+                        match call_tool(&route.server, &route.tool, arguments).await {
+                            Ok(result) => state.bus.publish(AgentEvent::McpToolCalled {
+                                route_id: route.id,
+                                server: route.server,
+                                tool: route.tool,
+                                result,
+                            }),
+                            Err(error) => state.bus.publish(AgentEvent::error(
+                                "mcp_client",
+                                error.with_context("route_id", route.id),
+                            )),
+                        }
+                    });
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn save(config: McpClientConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::new(ErrorCode::InvalidInput, message)
+}
+
+// Replaces a server with the same name, reconnecting on next use.
+#[tauri::command]
+pub fn add_mcp_server(config: McpServerConfig) -> CommandResult<()> {
+    if config.name.trim().is_empty() {
+        return Err(invalid("MCP server name cannot be empty"));
+    }
+    match &config.transport {
+        McpTransport::Stdio { command, .. } if command.trim().is_empty() => {
+            return Err(invalid("MCP server command cannot be empty"))
+        }
+        McpTransport::Http { url, .. } if reqwest::Url::parse(url).is_err() => {
+            return Err(invalid("MCP server URL is not valid"))
+        }
+        _ => {}
+    }
+    let mut client = CONFIG.lock().clone();
+    client.servers.retain(|server| server.name != config.name);
+    let name = config.name.clone();
+    client.servers.push(config);
+    save(client)?;
+    disconnect(&name);
+    Ok(())
+}
+
+// Its routes go with it.
+#[tauri::command]
+pub fn remove_mcp_server(name: String) -> CommandResult<()> {
+    let mut client = CONFIG.lock().clone();
+    let before = client.servers.len();
+    client.servers.retain(|server| server.name != name);
+    if client.servers.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No MCP server named {}", name),
+        ));
+    }
+    client.routes.retain(|route| route.server != name);
+    save(client)?;
+    disconnect(&name);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_mcp_servers() -> Vec<McpServerStatus> {
+    let connections = CONNECTIONS.lock();
+    CONFIG
+        .lock()
+        .servers
+        .iter()
+        .map(|config| McpServerStatus {
+            connected: connections.contains_key(&config.name),
+            config: config.clone(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn list_mcp_tools(server: String) -> CommandResult<Value> {
+    let result = request(&server, "tools/list", json!({})).await?;
+    Ok(result.get("tools").cloned().unwrap_or_else(|| json!([])))
+}
+
+#[tauri::command]
+pub async fn call_mcp_tool(
+    server: String,
+    tool: String,
+    arguments: Option<Value>,
+) -> CommandResult<String> {
+    call_tool(&server, &tool, arguments.unwrap_or_else(|| json!({}))).await
+}
+
+#[tauri::command]
+pub fn list_mcp_routes() -> Vec<McpRoute> {
+    CONFIG.lock().routes.clone()
+}
+
+// Without `arguments` the tool gets `{ "text": "{rest}" }`.
+#[tauri::command]
+pub fn add_mcp_route(
+    pattern: String,
+    server: String,
+    tool: String,
+    arguments: Option<Value>,
+) -> CommandResult<McpRoute> {
+    if words(&pattern).is_empty() {
+        return Err(invalid("Route pattern cannot be empty"));
+    }
+    if tool.trim().is_empty() {
+        return Err(invalid("Tool name cannot be empty"));
+    }
+    self::server(&server)?;
+    let arguments = arguments.unwrap_or_else(|| json!({ "text": "{rest}" }));
+    if !arguments.is_object() {
+        return Err(invalid("Tool arguments must be an object"));
+    }
+    let route = McpRoute {
+        id: uuid::Uuid::new_v4().to_string(),
+        pattern,
+        server,
+        tool,
+        arguments,
+        enabled: true,
+    };
+    let mut client = CONFIG.lock().clone();
+    client.routes.push(route.clone());
+    save(client)?;
+    Ok(route)
+}
+
+#[tauri::command]
+pub fn remove_mcp_route(id: String) -> CommandResult<()> {
+    let mut client = CONFIG.lock().clone();
+    let before = client.routes.len();
+    client.routes.retain(|route| route.id != id);
+    if client.routes.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No MCP route with id {}", id),
+        ));
+    }
+    save(client)
+}
+
+#[tauri::command]
+pub fn set_mcp_route_enabled(id: String, enabled: bool) -> CommandResult<()> {
+    let mut client = CONFIG.lock().clone();
+    match client.routes.iter_mut().find(|route| route.id == id) {
+        Some(route) => route.enabled = enabled,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No MCP route with id {}", id),
+            ))
+        }
+    }
+    save(client)
+}
@@ -0,0 +1,174 @@
+use std::net::SocketAddr;
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+
+const STORE_FILE: &str = "mdns.json";
+const SERVICE_TYPE: &str = "_voicemcp._tcp.local.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MdnsConfig {
+    pub enabled: bool,
+    pub service_name: String,
+}
+
+impl Default for MdnsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            service_name: "Voice MCP Agent".to_string(),
+        }
+    }
+}
+
+impl MdnsConfig {
+    // The service name doubles as the advertised `<name>.local` host so a
+    // phone browser can open the mic page without knowing the IP.
+    fn host_label(&self) -> String {
+        let label: String = self
+            .service_name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let label = label.trim_matches('-').to_string();
+        if label.is_empty() {
+            "voice-mcp-agent".to_string()
+        } else {
+            label
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MdnsStatus {
+    pub advertising: bool,
+    pub url: Option<String>,
+}
+
+struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+#[derive(Default)]
+pub struct MdnsState {
+    config: Mutex<MdnsConfig>,
+    endpoint: Mutex<Option<(SocketAddr, bool)>>,
+    advertisement: Mutex<Option<Advertisement>>,
+}
+
+impl MdnsState {
+    pub fn load() -> Self {
+        Self {
+            config: Mutex::new(store::load_json(STORE_FILE)),
+            ..Default::default()
+        }
+    }
+
+    pub fn advertise(&self, addr: SocketAddr, tls: bool) -> Result<(), String> {
+        self.withdraw();
+        *self.endpoint.lock() = Some((addr, tls));
+
+        let config = self.config.lock().clone();
+        if !config.enabled || addr.ip().is_loopback() {
+            return Ok(());
+        }
+
+        let daemon =
+            ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS responder: {}", e))?;
+        let host = format!("{}.local.", config.host_label());
+        let properties = [
+            ("version", env!("CARGO_PKG_VERSION")),
+            ("tls", if tls { "1" } else { "0" }),
+            ("mic", "/mic"),
+            ("mic_ws", "/mic/ws"),
+            ("captions_ws", "/captions/ws"),
+        ];
+        let ip = if addr.ip().is_unspecified() {
+            String::new()
+        } else {
+            addr.ip().to_string()
+        };
+        let mut info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &config.service_name,
+            &host,
+            ip.as_str(),
+            addr.port(),
+            &properties[..],
+        )
+        .map_err(|e| format!("Invalid mDNS service {}: {}", config.service_name, e))?;
+        if addr.ip().is_unspecified() {
+            info = info.enable_addr_auto();
+        }
+
+        let fullname = info.get_fullname().to_string();
+        daemon
+            .register(info)
+            .map_err(|e| format!("Failed to advertise {}: {}", config.service_name, e))?;
+        *self.advertisement.lock() = Some(Advertisement { daemon, fullname });
+        Ok(())
+    }
+
+    pub fn withdraw(&self) {
+        if let Some(advertisement) = self.advertisement.lock().take() {
+            let _ = advertisement.daemon.unregister(&advertisement.fullname);
+            let _ = advertisement.daemon.shutdown();
+        }
+    }
+
+    pub fn stop(&self) {
+        self.withdraw();
+        self.endpoint.lock().take();
+    }
+
+    fn status(&self) -> MdnsStatus {
+        let advertising = self.advertisement.lock().is_some();
+        let url = match *self.endpoint.lock() {
+            Some((addr, tls)) if advertising => Some(format!(
+                "{}://{}.local:{}/mic",
+                if tls { "https" } else { "http" },
+                self.config.lock().host_label(),
+                addr.port()
+            )),
+            _ => None,
+        };
+        MdnsStatus { advertising, url }
+    }
+}
+
+#[tauri::command]
+pub fn get_mdns_config(mdns: tauri::State<'_, MdnsState>) -> MdnsConfig {
+    mdns.config.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_mdns_config(
+    mdns: tauri::State<'_, MdnsState>,
+    config: MdnsConfig,
+) -> CommandResult<MdnsStatus> {
+    if config.service_name.trim().is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "mDNS service name cannot be empty",
+        ));
+    }
+    store::save_json(STORE_FILE, &config)?;
+    *mdns.config.lock() = config;
+    let endpoint = *mdns.endpoint.lock();
+    if let Some((addr, tls)) = endpoint {
+        mdns.advertise(addr, tls)?;
+    }
+    Ok(mdns.status())
+}
+
+#[tauri::command]
+pub fn get_mdns_status(mdns: tauri::State<'_, MdnsState>) -> MdnsStatus {
+    mdns.status()
+}
@@ -0,0 +1,330 @@
+use std::time::Duration;
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::dictation;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::history;
+use crate::mcp_client;
+use crate::store;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const STORE_FILE: &str = "meeting.json";
+const TICK: Duration = Duration::from_secs(5);
+const MAX_CHUNK_MINUTES: u32 = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingConfig {
+    // The MCP server and tool each chunk of transcript is sent to.
+    pub server: Option<String>,
+    pub tool: Option<String>,
+    // `{text}` is the chunk; without arguments the tool gets { "text": "{text}" }.
+    pub arguments: Option<Value>,
+    pub chunk_minutes: u32,
+}
+
+impl Default for MeetingConfig {
+    fn default() -> Self {
+        Self {
+            server: None,
+            tool: None,
+            arguments: None,
+            chunk_minutes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingSummary {
+    // The dictation session the meeting is recorded in.
+    pub session_id: String,
+    pub chunk_start: u64,
+    pub chunk_end: u64,
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingStatus {
+    pub session_id: String,
+    pub started_at: u64,
+    pub chunk_started_at: u64,
+    // Final transcripts waiting for the next summary.
+    pub pending_transcripts: usize,
+}
+
+struct Meeting {
+    session_id: String,
+    started_at: u64,
+    chunk_started_at: u64,
+    lines: Vec<String>,
+    // What stop undoes: a session or dictation that was already running is
+    // left running.
+    owns_session: bool,
+    started_dictation: bool,
+}
+
+impl Meeting {
+    fn status(&self) -> MeetingStatus {
+        MeetingStatus {
+            session_id: self.session_id.clone(),
+            started_at: self.started_at,
+            chunk_started_at: self.chunk_started_at,
+            pending_transcripts: self.lines.len(),
+        }
+    }
+}
+
+struct Chunk {
+    session_id: String,
+    start: u64,
+    end: u64,
+    text: String,
+}
+
+static CONFIG: Lazy<Mutex<MeetingConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+static ACTIVE: Lazy<Mutex<Option<Meeting>>> = Lazy::new(Mutex::default);
+
+// The local daemon is started with diarization while a meeting runs; cloud
+// backends have none.
+pub fn is_active() -> bool {
+    ACTIVE.lock().is_some()
+}
+
+fn take_chunk(meeting: &mut Meeting) -> Option<Chunk> {
+    let end = now_millis();
+    let start = std::mem::replace(&mut meeting.chunk_started_at, end);
+    if meeting.lines.is_empty() {
+        return None;
+    }
+    Some(Chunk {
+        session_id: meeting.session_id.clone(),
+        start,
+        end,
+        text: std::mem::take(&mut meeting.lines).join("\n"),
+    })
+}
+
+fn due_chunk() -> Option<Chunk> {
+    let minutes = CONFIG.lock().chunk_minutes;
+    let mut active = ACTIVE.lock();
+    let meeting = active.as_mut()?;
+    if now_millis().saturating_sub(meeting.chunk_started_at) < minutes as u64 * 60_000 {
+        return None;
+    }
+    take_chunk(meeting)
+}
+
+async fn summarize(state: AppState, chunk: Chunk) {
+    let config = CONFIG.lock().clone();
+    let (server, tool) = match (config.server, config.tool) {
+        (Some(server), Some(tool)) => (server, tool),
+        _ => return,
+    };
+    let template = config
+        .arguments
+        .unwrap_or_else(|| json!({ "text": "{text}" }));
+    let arguments = mcp_client::fill(&template, &chunk.text, "");
+    let summary = match mcp_client::call_tool(&server, &tool, arguments).await {
+        Ok(summary) => MeetingSummary {
+            session_id: chunk.session_id,
+            chunk_start: chunk.start,
+            chunk_end: chunk.end,
+            summary,
+        },
+        Err(error) => {
+            state.bus.publish(AgentEvent::error(
+                "meeting",
+                error.with_context("session_id", chunk.session_id),
+            ));
+            return;
+        }
+    };
+    let saved = summary.clone();
+    match tauri::async_runtime::spawn_blocking(move || history::save_summary(&saved)).await {
+        Ok(Err(error)) => state.bus.publish(AgentEvent::error("meeting", error)),
+        _ => state.bus.publish(AgentEvent::MeetingSummary(summary)),
+    }
+}
+
+// Final transcripts are collected with their speaker, and every
+// `chunk_minutes` what was said is summarized. A chunk without speech is
+// skipped.
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(TICK);
+        loop {
+            let event = tokio::select! {
+                _ = ticker.tick() => {
+                    if let Some(chunk) = due_chunk() {
+                        tauri::async_runtime::spawn(summarize(state.clone(), chunk));
+                    }
+                    continue;
+                }
+                event = events.recv() => event,
+            };
+            match event {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => {
+                    if let Some(meeting) = ACTIVE.lock().as_mut() {
+                        meeting.lines.push(match &t.speaker {
+                            Some(speaker) => format!("{}: {}", speaker, t.text),
+                            None => t.text,
+                        });
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn not_configured() -> AppError {
+    AppError::new(
+        ErrorCode::InvalidInput,
+        "Meeting mode needs an MCP server and tool to summarize with",
+    )
+}
+
+// Joins the dictation session that is open, or opens one, and starts
+// dictation unless it is already running.
+pub fn start(state: &AppState, name: Option<String>) -> Result<MeetingStatus, AppError> {
+    {
+        let config = CONFIG.lock();
+        if config.server.is_none() || config.tool.is_none() {
+            return Err(not_configured());
+        }
+    }
+    let mut active = ACTIVE.lock();
+    if let Some(meeting) = active.as_ref() {
+        return Err(
+            AppError::new(ErrorCode::AlreadyRunning, "A meeting is already running")
+                .with_context("session_id", meeting.session_id.clone()),
+        );
+    }
+    let (session, owns_session) = match dictation::current() {
+        Some(session) => (session, false),
+        None => {
+            let name = name
+                .unwrap_or_else(|| format!("Meeting {}", Local::now().format("%Y-%m-%d %H:%M")));
+            (dictation::start(&name)?, true)
+        }
+    };
+    let now = now_millis();
+    let started_dictation = !backends::is_running(state);
+    let meeting = Meeting {
+        session_id: session.id,
+        started_at: now,
+        chunk_started_at: now,
+        lines: Vec::new(),
+        owns_session,
+        started_dictation,
+    };
+    let status = meeting.status();
+    *active = Some(meeting);
+    drop(active);
+    if started_dictation {
+        if let Err(error) = backends::start(state, InputSource::Microphone) {
+            *ACTIVE.lock() = None;
+            if owns_session {
+                let _ = dictation::end(&state.bus);
+            }
+            return Err(error);
+        }
+    }
+    Ok(status)
+}
+
+// What was said since the last summary is summarized before the meeting
+// ends.
+pub fn stop(state: &AppState) -> Result<MeetingStatus, AppError> {
+    let mut meeting = ACTIVE
+        .lock()
+        .take()
+        .ok_or_else(|| AppError::new(ErrorCode::NotRunning, "No meeting is running"))?;
+    let status = meeting.status();
+    if let Some(chunk) = take_chunk(&mut meeting) {
+        tauri::async_runtime::spawn(summarize(state.clone(), chunk));
+    }
+    if meeting.started_dictation {
+        backends::stop(state)?;
+    }
+    if meeting.owns_session {
+        dictation::end(&state.bus)?;
+    }
+    Ok(status)
+}
+
+pub fn status() -> Option<MeetingStatus> {
+    ACTIVE.lock().as_ref().map(Meeting::status)
+}
+
+#[tauri::command]
+pub async fn start_meeting(
+    state: tauri::State<'_, AppState>,
+    name: Option<String>,
+) -> CommandResult<MeetingStatus> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || start(&state, name))
+        .await
+        .map_err(|e| AppError::from(format!("Meeting task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn stop_meeting(state: tauri::State<'_, AppState>) -> CommandResult<MeetingStatus> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || stop(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Meeting task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn get_meeting_status() -> Option<MeetingStatus> {
+    status()
+}
+
+#[tauri::command]
+pub async fn get_meeting_summaries(session_id: String) -> CommandResult<Vec<MeetingSummary>> {
+    tauri::async_runtime::spawn_blocking(move || history::summaries(&session_id))
+        .await
+        .map_err(|e| AppError::from(format!("Meeting task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn get_meeting_config() -> MeetingConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_meeting_config(config: MeetingConfig) -> CommandResult<()> {
+    if config.chunk_minutes == 0 || config.chunk_minutes > MAX_CHUNK_MINUTES {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Chunks are 1 to {} minutes long", MAX_CHUNK_MINUTES),
+        ));
+    }
+    if config
+        .server
+        .as_deref()
+        .map_or(false, |s| s.trim().is_empty())
+        || config
+            .tool
+            .as_deref()
+            .map_or(false, |t| t.trim().is_empty())
+    {
+        return Err(not_configured());
+    }
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+
+use crate::bus::AgentEvent;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPlatform {
+    Zoom,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptionPostingStatus {
+    pub active: bool,
+    pub platform: Option<CaptionPlatform>,
+    pub language: Option<String>,
+    pub posted: u64,
+    pub last_error: Option<String>,
+}
+
+struct ActiveCaptions {
+    platform: CaptionPlatform,
+    language: String,
+    shutdown: oneshot::Sender<()>,
+    posted: Arc<AtomicU64>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+#[derive(Default)]
+pub struct MeetingCaptionState {
+    active: Mutex<Option<ActiveCaptions>>,
+}
+
+// Zoom's third-party caption API wants a strictly increasing `seq` per meeting,
+// so pick up where any earlier poster (or a previous run) left off.
+async fn zoom_last_seq(client: &reqwest::Client, url: &str) -> u64 {
+    let seq_url = url.replacen("/closedcaption?", "/closedcaption/seq?", 1);
+    match client.get(&seq_url).send().await {
+        Ok(response) => response
+            .text()
+            .await
+            .ok()
+            .and_then(|body| body.trim().parse().ok())
+            .unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+async fn post_zoom(
+    client: &reqwest::Client,
+    url: &str,
+    seq: u64,
+    language: &str,
+    text: &str,
+) -> Result<(), String> {
+    let response = client
+        .post(url)
+        .query(&[("seq", seq.to_string()), ("lang", language.to_string())])
+        .header("Content-Type", "text/plain")
+        .timeout(Duration::from_secs(10))
+        .body(text.to_string())
+        .send()
+        .await
+        .map_err(|e| format!("Caption post failed: {}", e))?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Caption post failed: HTTP {}", response.status()))
+    }
+}
+
+async fn run(
+    state: AppState,
+    url: String,
+    language: String,
+    posted: Arc<AtomicU64>,
+    last_error: Arc<Mutex<Option<String>>>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let client = reqwest::Client::new();
+    let mut seq = zoom_last_seq(&client, &url).await;
+    let mut events = state.bus.subscribe();
+    loop {
+        let transcript = tokio::select! {
+            _ = &mut shutdown => break,
+            event = events.recv() => match event {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => t,
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+        };
+        seq += 1;
+        match post_zoom(&client, &url, seq, &language, &transcript.text).await {
+            Ok(()) => {
+                posted.fetch_add(1, Ordering::SeqCst);
+                last_error.lock().take();
+            }
+            Err(message) => {
+                *last_error.lock() = Some(message.clone());
+                state
+                    .bus
+                    .publish(AgentEvent::error("meeting_captions", message));
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn start_caption_posting(
+    state: tauri::State<'_, AppState>,
+    captions: tauri::State<'_, MeetingCaptionState>,
+    platform: CaptionPlatform,
+    url: String,
+    language: Option<String>,
+) -> CommandResult<CaptionPostingStatus> {
+    let url = url.trim().to_string();
+    match platform {
+        CaptionPlatform::Zoom if !url.starts_with("https://") || !url.contains("closedcaption") => {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Paste the API token URL from Zoom's closed caption settings",
+            ))
+        }
+        CaptionPlatform::Zoom => {}
+    }
+
+    let mut active = captions.active.lock();
+    if let Some(previous) = active.take() {
+        let _ = previous.shutdown.send(());
+    }
+
+    let language = language.unwrap_or_else(|| "en-US".to_string());
+    let (tx, rx) = oneshot::channel();
+    let posted = Arc::new(AtomicU64::new(0));
+    let last_error = Arc::new(Mutex::new(None));
+    tauri::async_runtime::spawn(run(
+        state.inner().clone(),
+        url,
+        language.clone(),
+        posted.clone(),
+        last_error.clone(),
+        rx,
+    ));
+    *active = Some(ActiveCaptions {
+        platform,
+        language: language.clone(),
+        shutdown: tx,
+        posted,
+        last_error,
+    });
+    Ok(CaptionPostingStatus {
+        active: true,
+        platform: Some(platform),
+        language: Some(language),
+        posted: 0,
+        last_error: None,
+    })
+}
+
+#[tauri::command]
+pub fn stop_caption_posting(
+    captions: tauri::State<'_, MeetingCaptionState>,
+) -> CommandResult<String> {
+    match captions.active.lock().take() {
+        Some(active) => {
+            let _ = active.shutdown.send(());
+            Ok("Stopped posting captions".to_string())
+        }
+        None => Err(AppError::new(
+            ErrorCode::NotRunning,
+            "Caption posting is not active",
+        )),
+    }
+}
+
+#[tauri::command]
+pub fn get_caption_posting_status(
+    captions: tauri::State<'_, MeetingCaptionState>,
+) -> CaptionPostingStatus {
+    match captions.active.lock().as_ref() {
+        Some(active) => CaptionPostingStatus {
+            active: true,
+            platform: Some(active.platform),
+            language: Some(active.language.clone()),
+            posted: active.posted.load(Ordering::SeqCst),
+            last_error: active.last_error.lock().clone(),
+        },
+        None => CaptionPostingStatus {
+            active: false,
+            platform: None,
+            language: None,
+            posted: 0,
+            last_error: None,
+        },
+    }
+}
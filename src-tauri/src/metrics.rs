@@ -0,0 +1,122 @@
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use once_cell::sync::Lazy;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::AppState;
+
+pub static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+fn register<T: Collector + Clone + 'static>(metric: T) -> T {
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric names are unique");
+    metric
+}
+
+fn counter(name: &str, help: &str) -> IntCounter {
+    register(IntCounter::new(name, help).expect("valid counter"))
+}
+
+fn histogram(name: &str, help: &str, buckets: Vec<f64>) -> Histogram {
+    register(
+        Histogram::with_opts(HistogramOpts::new(name, help).buckets(buckets))
+            .expect("valid histogram"),
+    )
+}
+
+pub static UTTERANCES: Lazy<IntCounter> = Lazy::new(|| {
+    counter(
+        "voice_agent_utterances_transcribed_total",
+        "Final transcripts produced by any STT backend",
+    )
+});
+
+pub static STT_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    histogram(
+        "voice_agent_stt_latency_seconds",
+        "Time from an audio chunk being queued to its transcript",
+        vec![0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0],
+    )
+});
+
+pub static PIPELINE_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "voice_agent_pipeline_latency_seconds",
+                "Time spent in each stage between speech and a published transcript",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0]),
+            &["stage"],
+        )
+        .expect("valid histogram"),
+    )
+});
+
+pub static AUDIO_UNDERRUNS: Lazy<IntCounter> = Lazy::new(|| {
+    counter(
+        "voice_agent_audio_underruns_total",
+        "Audio input overflows and underflows reported by the capture stream",
+    )
+});
+
+pub static DAEMON_STARTS: Lazy<IntCounter> =
+    Lazy::new(|| counter("voice_agent_daemon_starts_total", "STT daemon launches"));
+
+pub static DAEMON_RESTARTS: Lazy<IntCounter> = Lazy::new(|| {
+    counter(
+        "voice_agent_daemon_restarts_total",
+        "STT daemon restarts after a crash or configuration change",
+    )
+});
+
+pub static TOOL_CALLS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "voice_agent_tool_calls_total",
+                "Tool calls handled, by tool",
+            ),
+            &["tool"],
+        )
+        .expect("valid counter"),
+    )
+});
+
+pub static LLM_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    histogram(
+        "voice_agent_llm_latency_seconds",
+        "Round-trip time of LLM requests",
+        vec![0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0],
+    )
+});
+
+pub fn routes() -> Router<AppState> {
+    // Register everything up front so idle metrics still show up as zero.
+    Lazy::force(&UTTERANCES);
+    Lazy::force(&STT_LATENCY);
+    Lazy::force(&PIPELINE_LATENCY);
+    Lazy::force(&AUDIO_UNDERRUNS);
+    Lazy::force(&DAEMON_STARTS);
+    Lazy::force(&DAEMON_RESTARTS);
+    Lazy::force(&TOOL_CALLS);
+    Lazy::force(&LLM_LATENCY);
+    Router::new().route("/metrics", get(render))
+}
+
+async fn render() -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    let _ = encoder.encode(&REGISTRY.gather(), &mut buffer);
+    (
+        [(header::CONTENT_TYPE, encoder.format_type().to_string())],
+        buffer,
+    )
+}
@@ -0,0 +1,548 @@
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::config;
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::profiles;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "models.json";
+const BUDGET_POLL: Duration = Duration::from_secs(15);
+// Smallest first; sizes like `base.en` or `large-v3` rank by their family.
+const MODEL_LADDER: [&str; 5] = ["tiny", "base", "small", "medium", "large"];
+const DOWNLOAD_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+// ggml downloads and their approximate size in MB, for showing before one
+// is fetched.
+const CATALOG: [(&str, u64); 12] = [
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    ("medium", 1500),
+    ("medium.en", 1500),
+    ("large-v1", 2900),
+    ("large-v2", 2900),
+    ("large-v3", 2900),
+    ("large-v3-turbo", 1500),
+];
+
+// `Mock` replays a script through mock_stt_daemon.py instead of running a
+// model, for tests and demos.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SttBackend {
+    Whisper,
+    Mock { script: PathBuf },
+}
+
+impl Default for SttBackend {
+    fn default() -> Self {
+        SttBackend::Whisper
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelConfig {
+    pub backend: SttBackend,
+    pub idle_unload_minutes: Option<u32>,
+    pub memory_budget_mb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelStatus {
+    pub model: String,
+    pub downgraded_from: Option<String>,
+    pub loaded: bool,
+    pub memory_mb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub name: String,
+    pub size_mb: u64,
+    pub downloaded: bool,
+    // Bytes of an interrupted download that the next one resumes from.
+    pub partial_bytes: Option<u64>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Downloading,
+    Verifying,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub model: String,
+    pub state: DownloadState,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub error: Option<AppError>,
+}
+
+static DOWNLOADS: Lazy<Mutex<HashSet<String>>> = Lazy::new(Mutex::default);
+
+struct Downloading(String);
+
+impl Drop for Downloading {
+    fn drop(&mut self) {
+        DOWNLOADS.lock().remove(&self.0);
+    }
+}
+
+#[derive(Clone)]
+pub struct ModelManager {
+    config: Arc<Mutex<ModelConfig>>,
+    downgrade: Arc<Mutex<Option<String>>>,
+    loaded: Arc<Mutex<bool>>,
+}
+
+pub fn rank(model: &str) -> Option<usize> {
+    let family = model.split(['.', '-']).next().unwrap_or(model);
+    MODEL_LADDER.iter().position(|m| *m == family)
+}
+
+fn daemon_memory_mb(state: &AppState) -> Option<u64> {
+    let pid = Pid::from_u32(state.stt_process.lock().as_ref()?.id());
+    let mut system = System::new();
+    system.refresh_process(pid);
+    system.process(pid).map(|p| p.memory() / (1024 * 1024))
+}
+
+impl ModelManager {
+    pub fn load() -> Self {
+        Self {
+            config: Arc::new(Mutex::new(store::load_json(STORE_FILE))),
+            downgrade: Arc::default(),
+            loaded: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> ModelConfig {
+        self.config.lock().clone()
+    }
+
+    // The model the daemon should use: the profile's, unless the memory
+    // budget forced a smaller one.
+    pub fn effective_model(&self, profile_model: &str) -> String {
+        self.downgrade
+            .lock()
+            .clone()
+            .unwrap_or_else(|| profile_model.to_string())
+    }
+
+    // Not persisted; used by `--mock-stt` and tests.
+    pub fn set_backend(&self, backend: SttBackend) {
+        self.config.lock().backend = backend;
+    }
+
+    pub fn clear_downgrade(&self) {
+        self.downgrade.lock().take();
+    }
+
+    pub fn set_loaded(&self, loaded: bool) {
+        *self.loaded.lock() = loaded;
+    }
+}
+
+pub fn status(state: &AppState) -> ModelStatus {
+    let profile_model = profiles::active_model(state);
+    let model = state.models.effective_model(&profile_model);
+    ModelStatus {
+        downgraded_from: if model != profile_model {
+            Some(profile_model)
+        } else {
+            None
+        },
+        model,
+        loaded: daemon::is_running(state) && *state.models.loaded.lock(),
+        memory_mb: daemon_memory_mb(state),
+    }
+}
+
+fn downgrade(state: &AppState, used_mb: u64, budget_mb: u64) {
+    let current = state.models.effective_model(&profiles::active_model(state));
+    let smaller = match rank(&current) {
+        Some(rank) if rank > 0 => MODEL_LADDER[rank - 1].to_string(),
+        _ => {
+            state.bus.publish(AgentEvent::error(
+                "models",
+                format!(
+                    "STT daemon uses {} MB, over the {} MB budget, and {} has no smaller model",
+                    used_mb, budget_mb, current
+                ),
+            ));
+            return;
+        }
+    };
+    tracing::warn!(
+        target: "models",
+        "{} uses {} MB, over the {} MB budget; switching to {}",
+        current,
+        used_mb,
+        budget_mb,
+        smaller
+    );
+    *state.models.downgrade.lock() = Some(smaller.clone());
+
+    if let Err(error) = backends::restart(state) {
+        state.bus.publish(AgentEvent::error("models", error));
+        return;
+    }
+    state.bus.publish(AgentEvent::ModelChanged {
+        model: smaller,
+        loaded: true,
+        reason: "memory_budget".to_string(),
+    });
+}
+
+pub fn spawn_budget_monitor(state: AppState) {
+    thread::spawn(move || loop {
+        thread::sleep(BUDGET_POLL);
+        let budget_mb = match state.models.config().memory_budget_mb {
+            Some(budget_mb) => budget_mb,
+            None => continue,
+        };
+        if let Some(used_mb) = daemon_memory_mb(&state) {
+            if used_mb > budget_mb {
+                downgrade(&state, used_mb, budget_mb);
+            }
+        }
+    });
+}
+
+// Where downloads go, and where the whisper.cpp engine loads them from.
+pub fn model_path(name: &str) -> PathBuf {
+    store::data_dir()
+        .join("models")
+        .join(format!("ggml-{}.bin", name))
+}
+
+fn partial_path(name: &str) -> PathBuf {
+    model_path(name).with_extension("bin.part")
+}
+
+fn known(name: &str) -> bool {
+    CATALOG.iter().any(|(known, _)| *known == name) || model_path(name).is_file()
+}
+
+fn unknown(name: &str) -> AppError {
+    AppError::new(ErrorCode::NotFound, format!("No model named {}", name))
+}
+
+// Catalog models first, then any other ggml file placed in the folder.
+pub fn list(state: &AppState) -> Vec<ModelInfo> {
+    let active = daemon::current_model(state);
+    let mut sizes: Vec<(String, u64)> = CATALOG
+        .iter()
+        .map(|(name, size_mb)| (name.to_string(), *size_mb))
+        .collect();
+    if let Ok(entries) = fs::read_dir(store::data_dir().join("models")) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = match file_name
+                .strip_prefix("ggml-")
+                .and_then(|rest| rest.strip_suffix(".bin"))
+            {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            if !sizes.iter().any(|(known, _)| *known == name) {
+                let size_mb = entry.metadata().map_or(0, |m| m.len() / (1024 * 1024));
+                sizes.push((name, size_mb));
+            }
+        }
+    }
+    sizes
+        .into_iter()
+        .map(|(name, size_mb)| ModelInfo {
+            downloaded: model_path(&name).is_file(),
+            partial_bytes: fs::metadata(partial_path(&name)).ok().map(|m| m.len()),
+            active: name == active,
+            size_mb,
+            name,
+        })
+        .collect()
+}
+
+fn network(e: reqwest::Error) -> AppError {
+    AppError::new(ErrorCode::Network, format!("Model download failed: {}", e))
+}
+
+fn storage(path: &Path, e: std::io::Error) -> AppError {
+    AppError::new(
+        ErrorCode::Storage,
+        format!("Failed to write {}: {}", path.display(), e),
+    )
+}
+
+// Hugging Face answers the download URL with a redirect whose linked etag is
+// the file's SHA-256, without following it to the CDN.
+async fn expected_checksum(url: &str) -> Result<(String, Option<u64>), AppError> {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(network)?;
+    let response = client.head(url).send().await.map_err(network)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("{} is not available for download", url),
+        ));
+    }
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim_matches('"').to_ascii_lowercase())
+    };
+    let checksum = header("x-linked-etag")
+        .filter(|etag| etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorCode::Network,
+                format!("No checksum was published for {}", url),
+            )
+        })?;
+    Ok((
+        checksum,
+        header("x-linked-size").and_then(|size| size.parse().ok()),
+    ))
+}
+
+// Hashes what an interrupted download already wrote, so it can carry on.
+fn resume_point(part: &Path, hasher: &mut Sha256) -> u64 {
+    let mut file = match File::open(part) {
+        Ok(file) => file,
+        Err(_) => return 0,
+    };
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut len = 0;
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return len,
+            Ok(n) => {
+                hasher.update(&buffer[..n]);
+                len += n as u64;
+            }
+            Err(_) => {
+                *hasher = Sha256::new();
+                return 0;
+            }
+        }
+    }
+}
+
+async fn download(state: &AppState, name: &str) -> Result<(), AppError> {
+    let url = format!("{}/ggml-{}.bin", DOWNLOAD_URL, name);
+    let (checksum, total_bytes) = expected_checksum(&url).await?;
+    let part = partial_path(name);
+    if let Some(dir) = part.parent() {
+        fs::create_dir_all(dir).map_err(|e| storage(dir, e))?;
+    }
+    let publish = |stage: DownloadState, downloaded_bytes: u64| {
+        state
+            .bus
+            .publish(AgentEvent::ModelDownload(DownloadProgress {
+                model: name.to_string(),
+                state: stage,
+                downloaded_bytes,
+                total_bytes,
+                error: None,
+            }))
+    };
+
+    let mut hasher = Sha256::new();
+    let mut downloaded = resume_point(&part, &mut hasher);
+    if total_bytes.map_or(false, |total| downloaded > total) {
+        downloaded = 0;
+        hasher = Sha256::new();
+    }
+    if total_bytes.map_or(true, |total| downloaded < total) {
+        let mut request = reqwest::Client::new().get(&url);
+        if downloaded > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", downloaded));
+        }
+        let mut response = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(network)?;
+        // A server that ignores the range sends the whole file again.
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            downloaded = 0;
+            hasher = Sha256::new();
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(downloaded > 0)
+            .truncate(downloaded == 0)
+            .open(&part)
+            .map_err(|e| storage(&part, e))?;
+        let mut reported = Instant::now();
+        publish(DownloadState::Downloading, downloaded);
+        while let Some(chunk) = response.chunk().await.map_err(network)? {
+            file.write_all(&chunk).map_err(|e| storage(&part, e))?;
+            hasher.update(&chunk);
+            downloaded += chunk.len() as u64;
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                reported = Instant::now();
+                publish(DownloadState::Downloading, downloaded);
+            }
+        }
+    }
+
+    publish(DownloadState::Verifying, downloaded);
+    if hex::encode(hasher.finalize()) != checksum {
+        // Starting over is the only way past a corrupt partial file.
+        let _ = fs::remove_file(&part);
+        return Err(AppError::new(
+            ErrorCode::Failed,
+            format!("{} failed checksum verification", name),
+        )
+        .with_context("expected", checksum));
+    }
+    let path = model_path(name);
+    fs::rename(&part, &path).map_err(|e| storage(&path, e))?;
+    publish(DownloadState::Done, downloaded);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_model_config(state: tauri::State<'_, AppState>) -> ModelConfig {
+    state.models.config()
+}
+
+#[tauri::command]
+pub fn set_model_config(
+    state: tauri::State<'_, AppState>,
+    config: ModelConfig,
+) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *state.models.config.lock() = config;
+    // A new budget gets a fresh chance at the profile's own model, and the
+    // idle timeout is a daemon argument; both apply from the next start.
+    state.models.clear_downgrade();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_model_status(state: tauri::State<'_, AppState>) -> ModelStatus {
+    status(&state)
+}
+
+#[tauri::command]
+pub fn list_models(state: tauri::State<'_, AppState>) -> Vec<ModelInfo> {
+    list(&state)
+}
+
+// Resumes an interrupted download; progress arrives as
+// `model_download_progress` events.
+#[tauri::command]
+pub async fn download_model(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> CommandResult<ModelInfo> {
+    if !CATALOG.iter().any(|(known, _)| *known == name) {
+        return Err(unknown(&name));
+    }
+    if !DOWNLOADS.lock().insert(name.clone()) {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            format!("{} is already downloading", name),
+        ));
+    }
+    let _downloading = Downloading(name.clone());
+    let state = state.inner().clone();
+    if let Err(error) = download(&state, &name).await {
+        state
+            .bus
+            .publish(AgentEvent::ModelDownload(DownloadProgress {
+                model: name.clone(),
+                state: DownloadState::Failed,
+                downloaded_bytes: fs::metadata(partial_path(&name)).map_or(0, |m| m.len()),
+                total_bytes: None,
+                error: Some(error.clone()),
+            }));
+        return Err(error);
+    }
+    list(&state)
+        .into_iter()
+        .find(|model| model.name == name)
+        .ok_or_else(|| unknown(&name))
+}
+
+#[tauri::command]
+pub fn delete_model(name: String) -> CommandResult<()> {
+    if DOWNLOADS.lock().contains(&name) {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            format!("{} is still downloading", name),
+        ));
+    }
+    let mut removed = false;
+    for path in [model_path(&name), partial_path(&name)] {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| {
+                AppError::new(
+                    ErrorCode::Storage,
+                    format!("Failed to delete {}: {}", path.display(), e),
+                )
+            })?;
+            removed = true;
+        }
+    }
+    if removed {
+        Ok(())
+    } else {
+        Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("{} is not downloaded", name),
+        ))
+    }
+}
+
+// Saved as `model` in config.toml, overriding every profile, and a running
+// daemon restarts onto it.
+#[tauri::command]
+pub async fn set_active_model(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> CommandResult<()> {
+    if !known(&name) {
+        return Err(unknown(&name));
+    }
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        config::update(&state, |config| config.model = Some(name))?;
+        state.models.clear_downgrade();
+        backends::restart(&state)
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Model switch failed: {}", e)))?
+}
@@ -0,0 +1,298 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::error::CommandResult;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "mqtt.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub base_topic: String,
+    pub discovery_prefix: String,
+    pub node_id: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            base_topic: "voice_mcp_agent".to_string(),
+            discovery_prefix: "homeassistant".to_string(),
+            node_id: "voice_mcp_agent".to_string(),
+        }
+    }
+}
+
+impl MqttConfig {
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.base_topic, name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MqttStatus {
+    pub enabled: bool,
+    pub connected: bool,
+    pub broker: String,
+}
+
+pub struct MqttState {
+    config: Mutex<MqttConfig>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    connected: Arc<AtomicBool>,
+}
+
+impl MqttState {
+    pub fn load() -> Self {
+        Self {
+            config: Mutex::new(store::load_json(STORE_FILE)),
+            shutdown: Mutex::new(None),
+            connected: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn apply(&self, state: &AppState) {
+        if let Some(tx) = self.shutdown.lock().take() {
+            let _ = tx.send(());
+        }
+        self.connected.store(false, Ordering::SeqCst);
+
+        let config = self.config.lock().clone();
+        if !config.enabled {
+            return;
+        }
+        let (tx, rx) = oneshot::channel();
+        *self.shutdown.lock() = Some(tx);
+        tauri::async_runtime::spawn(run(config, state.clone(), self.connected.clone(), rx));
+    }
+}
+
+async fn publish_discovery(client: &AsyncClient, config: &MqttConfig) {
+    let device = json!({
+        "identifiers": [config.node_id],
+        "name": "Voice MCP Agent",
+        "manufacturer": "voice-mcp-agent",
+        "model": "Voice MCP Agent",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let availability = config.topic("status");
+    let entities = [
+        (
+            "switch",
+            "listening",
+            json!({
+                "name": "Listening",
+                "icon": "mdi:microphone",
+                "state_topic": config.topic("listening"),
+                "command_topic": config.topic("set/listening"),
+            }),
+        ),
+        (
+            "sensor",
+            "transcript",
+            json!({
+                "name": "Last transcript",
+                "icon": "mdi:text-box-outline",
+                "state_topic": config.topic("transcript"),
+                "value_template": "{{ value_json.text[:255] }}",
+                "json_attributes_topic": config.topic("transcript"),
+            }),
+        ),
+        (
+            "sensor",
+            "intent",
+            json!({
+                "name": "Last intent",
+                "icon": "mdi:lightning-bolt",
+                "state_topic": config.topic("intent"),
+                "value_template": "{{ value_json.intent }}",
+                "json_attributes_topic": config.topic("intent"),
+            }),
+        ),
+        (
+            "text",
+            "speak",
+            json!({
+                "name": "Speak",
+                "icon": "mdi:account-voice",
+                "command_topic": config.topic("speak"),
+            }),
+        ),
+    ];
+
+    for (component, object_id, mut payload) in entities {
+        payload["unique_id"] = format!("{}_{}", config.node_id, object_id).into();
+        payload["availability_topic"] = availability.clone().into();
+        payload["device"] = device.clone();
+        let topic = format!(
+            "{}/{}/{}/{}/config",
+            config.discovery_prefix, component, config.node_id, object_id
+        );
+        let _ = client
+            .publish(topic, QoS::AtLeastOnce, true, payload.to_string())
+            .await;
+    }
+}
+
+async fn publish_listening(client: &AsyncClient, config: &MqttConfig, listening: bool) {
+    let payload = if listening { "ON" } else { "OFF" };
+    let _ = client
+        .publish(config.topic("listening"), QoS::AtLeastOnce, true, payload)
+        .await;
+}
+
+fn handle_command(state: &AppState, config: &MqttConfig, topic: &str, payload: &str) {
+    let result = if topic == config.topic("set/listening") {
+        match payload.trim().to_uppercase().as_str() {
+            "ON" | "START" => backends::start(state, InputSource::Microphone)
+                .map(|_| ())
+                .map_err(String::from),
+            "OFF" | "STOP" => backends::stop(state).map(|_| ()).map_err(String::from),
+            "TOGGLE" => backends::toggle(state).map(|_| ()).map_err(String::from),
+            other => Err(format!("Unknown listening command: {}", other)),
+        }
+    } else if topic == config.topic("speak") {
+        state
+            .bus
+            .publish(AgentEvent::SpeakRequested(payload.to_string()));
+        Ok(())
+    } else {
+        Ok(())
+    };
+    if let Err(message) = result {
+        tracing::warn!("MQTT command failed: {}", message);
+    }
+}
+
+async fn run(
+    config: MqttConfig,
+    state: AppState,
+    connected: Arc<AtomicBool>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let mut options = MqttOptions::new(config.node_id.clone(), config.host.clone(), config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        config.topic("status"),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 64);
+    let mut events = state.bus.subscribe();
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => {
+                let _ = client
+                    .publish(config.topic("status"), QoS::AtLeastOnce, true, "offline")
+                    .await;
+                let _ = client.disconnect().await;
+                break;
+            }
+            notification = eventloop.poll() => match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    connected.store(true, Ordering::SeqCst);
+                    publish_discovery(&client, &config).await;
+                    let _ = client
+                        .publish(config.topic("status"), QoS::AtLeastOnce, true, "online")
+                        .await;
+                    publish_listening(&client, &config, backends::is_running(&state)).await;
+                    let _ = client
+                        .subscribe(config.topic("set/listening"), QoS::AtLeastOnce)
+                        .await;
+                    let _ = client.subscribe(config.topic("speak"), QoS::AtLeastOnce).await;
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    let payload = String::from_utf8_lossy(&publish.payload).into_owned();
+                    let (state, config) = (state.clone(), config.clone());
+                    // Off the event loop, which has to keep polling while a
+                    // daemon loads its model.
+                    tauri::async_runtime::spawn_blocking(move || {
+                        handle_command(&state, &config, &publish.topic, &payload)
+                    });
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    // The event loop reconnects on the next poll.
+                    connected.store(false, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            },
+            event = events.recv() => match event {
+                Ok(AgentEvent::ListeningChanged(listening)) => {
+                    publish_listening(&client, &config, listening).await;
+                }
+                Ok(AgentEvent::Transcript(t)) if t.is_final => {
+                    let payload = serde_json::to_string(&t).unwrap_or_default();
+                    let _ = client
+                        .publish(config.topic("transcript"), QoS::AtLeastOnce, false, payload)
+                        .await;
+                }
+                Ok(AgentEvent::IntentDetected { intent, text }) => {
+                    let payload = json!({ "intent": intent, "text": text }).to_string();
+                    let _ = client
+                        .publish(config.topic("intent"), QoS::AtLeastOnce, false, payload)
+                        .await;
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+    connected.store(false, Ordering::SeqCst);
+}
+
+#[tauri::command]
+pub fn get_mqtt_config(mqtt: tauri::State<'_, MqttState>) -> MqttConfig {
+    mqtt.config.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_mqtt_config(
+    state: tauri::State<'_, AppState>,
+    mqtt: tauri::State<'_, MqttState>,
+    config: MqttConfig,
+) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *mqtt.config.lock() = config;
+    mqtt.apply(&state);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_mqtt_status(mqtt: tauri::State<'_, MqttState>) -> MqttStatus {
+    let config = mqtt.config.lock();
+    MqttStatus {
+        enabled: config.enabled,
+        connected: mqtt.connected.load(Ordering::SeqCst),
+        broker: format!("{}:{}", config.host, config.port),
+    }
+}
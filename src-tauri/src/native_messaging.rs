@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::control_socket;
+#[cfg(windows)]
+use crate::store;
+use crate::AppState;
+
+pub const HOST_NAME: &str = "com.voice.mcp.agent";
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+// Chrome passes the caller's origin as the first argument; Firefox passes the
+// path to the host manifest followed by the extension id.
+pub fn is_host_invocation() -> bool {
+    match std::env::args().nth(1) {
+        Some(arg) => {
+            arg.starts_with("chrome-extension://")
+                || (arg.ends_with(&format!("{}.json", HOST_NAME)) && std::env::args().count() == 3)
+        }
+        None => false,
+    }
+}
+
+async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Option<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).await.ok()?;
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return None;
+    }
+    let mut message = vec![0u8; len];
+    reader.read_exact(&mut message).await.ok()?;
+    Some(message)
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &[u8]) -> bool {
+    writer
+        .write_all(&(message.len() as u32).to_ne_bytes())
+        .await
+        .is_ok()
+        && writer.write_all(message).await.is_ok()
+        && writer.flush().await.is_ok()
+}
+
+// Translates between the browser's length-prefixed JSON messages and the
+// control socket's JSON lines. Messages from the extension are control
+// requests like `{"command": "start"}`.
+async fn bridge<S: AsyncRead + AsyncWrite + Unpin>(stream: S) -> i32 {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let mut stdin = tokio::io::stdin();
+    let mut stdout = tokio::io::stdout();
+
+    if writer.write_all(b"subscribe\n").await.is_err() {
+        return 1;
+    }
+    loop {
+        tokio::select! {
+            message = read_message(&mut stdin) => {
+                let message = match message {
+                    Some(message) => message,
+                    None => return 0,
+                };
+                let request = match serde_json::from_slice::<serde_json::Value>(&message) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        let error = json!({ "ok": false, "error": format!("Invalid message: {}", e) });
+                        if !write_message(&mut stdout, error.to_string().as_bytes()).await {
+                            return 1;
+                        }
+                        continue;
+                    }
+                };
+                let mut line = request.to_string();
+                line.push('\n');
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    return 1;
+                }
+            }
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if !write_message(&mut stdout, line.as_bytes()).await {
+                        return 0;
+                    }
+                }
+                _ => return 1,
+            },
+        }
+    }
+}
+
+pub fn run(state: AppState) -> i32 {
+    tauri::async_runtime::block_on(async move {
+        match control_socket::connect().await {
+            Ok(stream) => bridge(stream).await,
+            // No desktop instance is running, so serve the extension from an
+            // in-process core instead.
+            Err(_) => {
+                let (client, server) = tokio::io::duplex(MAX_MESSAGE_LEN);
+                tauri::async_runtime::spawn(control_socket::serve_connection(server, state));
+                bridge(client).await
+            }
+        }
+    })
+}
+
+fn manifest(allowed: serde_json::Value) -> Result<serde_json::Value, String> {
+    let path =
+        std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Voice MCP Agent transcripts and dictation control",
+        "path": path,
+        "type": "stdio",
+    });
+    if let serde_json::Value::Object(allowed) = allowed {
+        manifest.as_object_mut().unwrap().extend(allowed);
+    }
+    Ok(manifest)
+}
+
+fn write_manifest(dir: PathBuf, manifest: &serde_json::Value) -> Result<PathBuf, String> {
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&path, manifest.to_string()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(target_os = "linux")]
+fn manifest_dirs() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let config = dirs::config_dir().unwrap_or_default();
+    let home = dirs::home_dir().unwrap_or_default();
+    (
+        vec![
+            config.join("google-chrome/NativeMessagingHosts"),
+            config.join("chromium/NativeMessagingHosts"),
+            config.join("BraveSoftware/Brave-Browser/NativeMessagingHosts"),
+        ],
+        vec![home.join(".mozilla/native-messaging-hosts")],
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn manifest_dirs() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let support = dirs::home_dir()
+        .unwrap_or_default()
+        .join("Library/Application Support");
+    (
+        vec![
+            support.join("Google/Chrome/NativeMessagingHosts"),
+            support.join("Chromium/NativeMessagingHosts"),
+            support.join("BraveSoftware/Brave-Browser/NativeMessagingHosts"),
+        ],
+        vec![support.join("Mozilla/NativeMessagingHosts")],
+    )
+}
+
+// Windows browsers find manifests through the registry rather than fixed
+// folders, so point a per-user key at a manifest in our data dir.
+#[cfg(windows)]
+fn manifest_dirs() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let dir = store::data_dir().join("native-messaging");
+    (vec![dir.join("chrome")], vec![dir.join("firefox")])
+}
+
+#[cfg(windows)]
+fn register(key: &str, manifest: &std::path::Path) -> Result<(), String> {
+    let status = std::process::Command::new("reg")
+        .args(["add", key, "/ve", "/t", "REG_SZ", "/f", "/d"])
+        .arg(manifest)
+        .status()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to register {}", key))
+    }
+}
+
+pub fn install(
+    chrome_extensions: &[String],
+    firefox_extensions: &[String],
+) -> Result<Vec<PathBuf>, String> {
+    if chrome_extensions.is_empty() && firefox_extensions.is_empty() {
+        return Err("Pass at least one extension id to allow".to_string());
+    }
+    let (chrome_dirs, firefox_dirs) = manifest_dirs();
+    let mut written = Vec::new();
+
+    if !chrome_extensions.is_empty() {
+        let origins: Vec<String> = chrome_extensions
+            .iter()
+            .map(|id| format!("chrome-extension://{}/", id))
+            .collect();
+        let manifest = manifest(json!({ "allowed_origins": origins }))?;
+        for dir in chrome_dirs {
+            let path = write_manifest(dir, &manifest)?;
+            #[cfg(windows)]
+            {
+                register(
+                    &format!(
+                        r"HKCU\Software\Google\Chrome\NativeMessagingHosts\{}",
+                        HOST_NAME
+                    ),
+                    &path,
+                )?;
+                register(
+                    &format!(
+                        r"HKCU\Software\Microsoft\Edge\NativeMessagingHosts\{}",
+                        HOST_NAME
+                    ),
+                    &path,
+                )?;
+            }
+            written.push(path);
+        }
+    }
+    if !firefox_extensions.is_empty() {
+        let manifest = manifest(json!({ "allowed_extensions": firefox_extensions }))?;
+        for dir in firefox_dirs {
+            let path = write_manifest(dir, &manifest)?;
+            #[cfg(windows)]
+            register(
+                &format!(r"HKCU\Software\Mozilla\NativeMessagingHosts\{}", HOST_NAME),
+                &path,
+            )?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
@@ -0,0 +1,345 @@
+use std::sync::mpsc;
+use std::thread;
+
+use crate::audio::{self, CaptureInfo, SAMPLE_RATE};
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::error::{AppError, CommandResult};
+use crate::latency;
+use crate::recordings;
+use crate::resampler;
+use crate::transcript::{now_millis, Transcript};
+use crate::AppState;
+
+// The same chunking and speech gate the Python daemon uses.
+const CHUNK_SECONDS: usize = 3;
+const SPEECH_RMS_THRESHOLD: f32 = 0.01;
+// About 10 s of capture callbacks; beyond that audio is dropped rather than
+// stalling the audio thread.
+const QUEUE_DEPTH: usize = 1000;
+
+#[cfg(feature = "whisper")]
+mod engine {
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use once_cell::sync::Lazy;
+    use parking_lot::Mutex;
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    use crate::audio::SAMPLE_RATE;
+    use crate::autotune::{self, Tuning};
+    use crate::daemon;
+    use crate::error::{AppError, ErrorCode};
+    use crate::metrics;
+    use crate::models;
+    use crate::profiles;
+    use crate::transcript::{now_millis, Transcript, Translation};
+    use crate::translation;
+    use crate::vocab;
+    use crate::AppState;
+
+    // One model stays loaded between calls; switching models or the compute
+    // device reloads it.
+    static CONTEXT: Lazy<Mutex<Option<((PathBuf, bool), WhisperContext)>>> =
+        Lazy::new(|| Mutex::new(None));
+
+    pub fn load(state: &AppState) -> Result<(), AppError> {
+        let path = models::model_path(&daemon::current_model(state));
+        let use_gpu = autotune::native_gpu();
+        let mut context = CONTEXT.lock();
+        if context.as_ref().map_or(false, |((loaded, gpu), _)| {
+            *loaded == path && *gpu == use_gpu
+        }) {
+            return Ok(());
+        }
+        if !path.is_file() {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No whisper.cpp model at {}", path.display()),
+            ));
+        }
+        // Without a GPU backend compiled in, whisper.cpp stays on the CPU.
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(use_gpu);
+        let loaded = WhisperContext::new_with_params(&path.to_string_lossy(), params)
+            .map_err(|e| AppError::from(format!("Failed to load {}: {}", path.display(), e)))?;
+        tracing::info!(target: "native_stt", "Loaded {} (gpu: {})", path.display(), use_gpu);
+        *context = Some(((path, use_gpu), loaded));
+        Ok(())
+    }
+
+    fn failed(e: whisper_rs::WhisperError) -> AppError {
+        AppError::from(format!("Transcription failed: {}", e))
+    }
+
+    fn params<'a>(
+        tuning: &Tuning,
+        language: &'a str,
+        prompt: Option<&'a str>,
+        translate: bool,
+    ) -> FullParams<'a, 'a> {
+        let strategy = if tuning.beam_size > 1 {
+            SamplingStrategy::BeamSearch {
+                beam_size: tuning.beam_size as i32,
+                patience: -1.0,
+            }
+        } else {
+            SamplingStrategy::Greedy { best_of: 1 }
+        };
+        let mut params = FullParams::new(strategy);
+        params.set_n_threads(tuning.threads as i32);
+        params.set_language(Some(language));
+        params.set_translate(translate);
+        if let Some(prompt) = prompt {
+            params.set_initial_prompt(prompt);
+        }
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        params
+    }
+
+    // Whisper's translate task only writes English, and it replaces the
+    // transcription, so each segment gets a second pass over its own audio.
+    fn translate(
+        context: &WhisperContext,
+        tuning: &Tuning,
+        language: &str,
+        prompt: Option<&str>,
+        samples: &[f32],
+    ) -> Result<String, AppError> {
+        let mut whisper = context.create_state().map_err(failed)?;
+        whisper
+            .full(params(tuning, language, prompt, true), samples)
+            .map_err(failed)?;
+        let mut text = Vec::new();
+        for segment in 0..whisper.full_n_segments().map_err(failed)? {
+            text.push(whisper.full_get_segment_text(segment).map_err(failed)?);
+        }
+        Ok(text.join(" ").trim().to_string())
+    }
+
+    pub fn transcribe(
+        state: &AppState,
+        samples: &[f32],
+        offset_ms: u64,
+        session_id: Option<&str>,
+    ) -> Result<Vec<Transcript>, AppError> {
+        load(state)?;
+        let tuning = autotune::current(&daemon::current_model(state));
+        let language = profiles::stt_language(state);
+        let spoken = language.as_deref().unwrap_or("auto");
+        let prompt = vocab::prompt();
+
+        let context = CONTEXT.lock();
+        let context = match context.as_ref() {
+            Some((_, context)) => context,
+            None => return Err(AppError::from("Whisper model is not loaded")),
+        };
+        let mut whisper = context.create_state().map_err(failed)?;
+        let started = Instant::now();
+        whisper
+            .full(params(&tuning, spoken, prompt.as_deref(), false), samples)
+            .map_err(failed)?;
+        metrics::STT_LATENCY.observe(started.elapsed().as_secs_f64());
+        let language = language.or_else(|| {
+            let id = whisper.full_lang_id_from_state().ok()?;
+            whisper_rs::get_lang_str(id).map(str::to_string)
+        });
+        let translating = translation::whisper_translates() && language.as_deref() != Some("en");
+
+        let mut transcripts = Vec::new();
+        for segment in 0..whisper.full_n_segments().map_err(failed)? {
+            let text = whisper.full_get_segment_text(segment).map_err(failed)?;
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            // Segment times are in centiseconds.
+            let start = whisper.full_get_segment_t0(segment).map_err(failed)?.max(0) as usize;
+            let end = whisper.full_get_segment_t1(segment).map_err(failed)?.max(0) as usize;
+            let translation = if translating {
+                let per_cs = SAMPLE_RATE as usize / 100;
+                let from = (start * per_cs).min(samples.len());
+                let to = (end * per_cs).clamp(from, samples.len());
+                let text = translate(
+                    context,
+                    &tuning,
+                    spoken,
+                    prompt.as_deref(),
+                    &samples[from..to],
+                )?;
+                Some(Translation {
+                    language: "en".to_string(),
+                    text,
+                })
+            } else {
+                None
+            };
+            transcripts.push(Transcript {
+                id: uuid::Uuid::new_v4().to_string(),
+                text: text.to_string(),
+                is_final: true,
+                timestamp: now_millis(),
+                confidence: None,
+                session_id: session_id.map(str::to_string),
+                start_ms: Some(offset_ms + start as u64 * 10),
+                end_ms: Some(offset_ms + end as u64 * 10),
+                speaker: None,
+                language: language.clone(),
+                translation,
+                latency: None,
+                words: Vec::new(),
+            });
+        }
+        Ok(transcripts)
+    }
+}
+
+#[cfg(not(feature = "whisper"))]
+mod engine {
+    use crate::error::{AppError, ErrorCode};
+    use crate::transcript::Transcript;
+    use crate::AppState;
+
+    fn unavailable() -> AppError {
+        AppError::new(
+            ErrorCode::Unsupported,
+            "This build has no in-process Whisper; rebuild with --features whisper",
+        )
+    }
+
+    pub fn load(_state: &AppState) -> Result<(), AppError> {
+        Err(unavailable())
+    }
+
+    pub fn transcribe(
+        _state: &AppState,
+        _samples: &[f32],
+        _offset_ms: u64,
+        _session_id: Option<&str>,
+    ) -> Result<Vec<Transcript>, AppError> {
+        Err(unavailable())
+    }
+}
+
+fn is_speech(samples: &[f32]) -> bool {
+    if samples.is_empty() {
+        return false;
+    }
+    let power: f32 = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+    power.sqrt() >= SPEECH_RMS_THRESHOLD
+}
+
+// The transcript's part of the chunk, from its segment times.
+fn utterance<'a>(buffer: &'a [f32], offset_ms: u64, transcript: &Transcript) -> &'a [f32] {
+    let sample = |ms: Option<u64>, default: usize| {
+        ms.map_or(default, |ms| {
+            (ms.saturating_sub(offset_ms) as usize * SAMPLE_RATE as usize / 1000).min(buffer.len())
+        })
+    };
+    let start = sample(transcript.start_ms, 0);
+    let end = sample(transcript.end_ms, buffer.len()).max(start);
+    &buffer[start..end]
+}
+
+// Ends once capture stops and drops the sender; the tail is still
+// transcribed.
+fn transcribe_stream(state: AppState, audio: mpsc::Receiver<Vec<f32>>) {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let chunk = SAMPLE_RATE as usize * CHUNK_SECONDS;
+    let mut buffer = Vec::with_capacity(chunk);
+    let mut offset_ms = 0;
+    loop {
+        let done = match audio.recv() {
+            Ok(samples) => {
+                buffer.extend_from_slice(&samples);
+                false
+            }
+            Err(_) => true,
+        };
+        if buffer.len() < chunk && !done {
+            continue;
+        }
+        if is_speech(&buffer) {
+            let captured_at = now_millis();
+            match engine::transcribe(&state, &buffer, offset_ms, Some(&session_id)) {
+                Ok(transcripts) => {
+                    for mut transcript in transcripts {
+                        // The chunk goes straight to the model, so it is not queued.
+                        transcript.latency = Some(latency::measured(captured_at, captured_at));
+                        recordings::save(&transcript, utterance(&buffer, offset_ms, &transcript));
+                        daemon::publish_transcript(&state, transcript);
+                    }
+                }
+                Err(error) => state.bus.publish(AgentEvent::error("native_stt", error)),
+            }
+        }
+        offset_ms += buffer.len() as u64 * 1000 / SAMPLE_RATE as u64;
+        buffer.clear();
+        if done {
+            break;
+        }
+    }
+}
+
+// Loads the model before opening the microphone, so a missing model fails
+// the command rather than the first chunk.
+pub fn start(state: &AppState, device: Option<String>) -> Result<CaptureInfo, AppError> {
+    engine::load(state)?;
+    let (tx, rx) = mpsc::sync_channel(QUEUE_DEPTH);
+    let sink = Box::new(move |samples: &[f32]| {
+        let _ = tx.try_send(samples.to_vec());
+    });
+    let info = audio::start_with_sink(state, device, sink)?;
+    let state = state.clone();
+    thread::spawn(move || transcribe_stream(state, rx));
+    Ok(info)
+}
+
+// Publishes the buffer as one final transcript, as `transcribe_file` does.
+pub fn transcribe_samples(
+    state: &AppState,
+    samples: &[f32],
+    sample_rate: u32,
+) -> Result<Transcript, AppError> {
+    let samples = resampler::resample(samples, sample_rate);
+    let segments = engine::transcribe(state, &samples, 0, None)?;
+    Ok(daemon::join_segments(state, &segments))
+}
+
+#[tauri::command]
+pub async fn start_native_stt(
+    state: tauri::State<'_, AppState>,
+    device: Option<String>,
+) -> CommandResult<CaptureInfo> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || start(&state, device))
+        .await
+        .map_err(|e| AppError::from(format!("Native STT task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn stop_native_stt(state: tauri::State<'_, AppState>) -> CommandResult<()> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || audio::stop(&state))
+        .await
+        .map_err(|e| AppError::from(format!("Native STT task failed: {}", e)))?
+}
+
+// `samples` are mono floats in [-1, 1] at `sample_rate`, 16 kHz by default.
+#[tauri::command]
+pub async fn transcribe_buffer(
+    state: tauri::State<'_, AppState>,
+    samples: Vec<f32>,
+    sample_rate: Option<u32>,
+) -> CommandResult<Transcript> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        transcribe_samples(&state, &samples, sample_rate.unwrap_or(SAMPLE_RATE).max(1))
+    })
+    .await
+    .map_err(|e| AppError::from(format!("Transcription task failed: {}", e)))?
+}
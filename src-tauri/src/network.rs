@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, ErrorCode};
+
+// Saved as `[network]` in config.toml. Only the cloud backends use it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    // Dictate on the local backend while the cloud one cannot be reached, and
+    // go back to it once it can.
+    pub offline_fallback: bool,
+    // Audio held for a backend that is reconnecting; the oldest goes first
+    // once it is full.
+    pub buffer_seconds: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            offline_fallback: false,
+            buffer_seconds: 30,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+// Doubles with every attempt. Each delay is drawn from the upper half of the
+// current one, so clients that lost the same server do not retry in step.
+pub struct Backoff {
+    initial_ms: u64,
+    max_ms: u64,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(config: &NetworkConfig) -> Self {
+        Self {
+            initial_ms: config.initial_delay_ms,
+            max_ms: config.max_delay_ms,
+            attempt: 0,
+        }
+    }
+
+    pub fn next(&mut self) -> Duration {
+        self.attempt += 1;
+        let factor = 1u64 << (self.attempt - 1).min(20);
+        let ceiling = self
+            .initial_ms
+            .saturating_mul(factor)
+            .min(self.max_ms)
+            .max(1);
+        let mut bytes = [0u8; 8];
+        let random = match SystemRandom::new().fill(&mut bytes) {
+            Ok(()) => u64::from_le_bytes(bytes),
+            Err(_) => 0,
+        };
+        Duration::from_millis(ceiling - ceiling / 2 + random % (ceiling / 2 + 1))
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+// Audio waiting for a connection, capped at `buffer_seconds`. Each item is
+// pushed with how many samples it holds.
+pub struct OutageBuffer<T> {
+    items: VecDeque<(usize, T)>,
+    samples: usize,
+    cap: usize,
+    dropped: usize,
+}
+
+impl<T> OutageBuffer<T> {
+    pub fn new(config: &NetworkConfig, sample_rate: u32) -> Self {
+        Self {
+            items: VecDeque::new(),
+            samples: 0,
+            cap: config.buffer_seconds as usize * sample_rate as usize,
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: usize, item: T) {
+        self.items.push_back((samples, item));
+        self.samples += samples;
+        while self.samples > self.cap {
+            match self.items.pop_front() {
+                Some((len, _)) => {
+                    self.samples -= len;
+                    self.dropped += len;
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.items.front().map(|(_, item)| item)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let (len, item) = self.items.pop_front()?;
+        self.samples -= len;
+        Some(item)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    // Samples dropped to stay under the cap since the last call.
+    pub fn take_dropped(&mut self) -> usize {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+// A server that could not be reached, or answered that it is overloaded or
+// broken. A rejected key or request fails the same way on every retry.
+pub fn is_outage(error: &AppError) -> bool {
+    error.code == ErrorCode::Network
+        && error
+            .context
+            .get("status")
+            .and_then(|status| status.as_u64())
+            .map_or(true, |status| {
+                status == 408 || status == 429 || status >= 500
+            })
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::config;
+use crate::error::CommandResult;
+use crate::i18n;
+use crate::AppState;
+
+const BODY_CHARS: usize = 200;
+
+// Saved as `[notifications]` in config.toml.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationPolicy {
+    pub daemon_crash: bool,
+    pub wake_word: bool,
+    // Final transcripts, only while the main window is hidden.
+    pub transcripts: bool,
+    pub respect_do_not_disturb: bool,
+}
+
+impl Default for NotificationPolicy {
+    fn default() -> Self {
+        Self {
+            daemon_crash: true,
+            wake_word: true,
+            transcripts: false,
+            respect_do_not_disturb: true,
+        }
+    }
+}
+
+// GNOME's do-not-disturb switch turns banners off.
+#[cfg(target_os = "linux")]
+pub fn do_not_disturb() -> Option<bool> {
+    let output = std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "false")
+}
+
+// Focus records an assertion while it is on. The file can need Full Disk
+// Access, so older systems' setting is the fallback.
+#[cfg(target_os = "macos")]
+pub fn do_not_disturb() -> Option<bool> {
+    let focus = dirs::home_dir()
+        .map(|home| home.join("Library/DoNotDisturb/DB/Assertions.json"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok());
+    if let Some(focus) = focus {
+        let records = focus["data"][0]["storeAssertionRecords"].as_array();
+        return Some(records.map_or(false, |records| !records.is_empty()));
+    }
+    let output = std::process::Command::new("defaults")
+        .args([
+            "-currentHost",
+            "read",
+            "com.apple.notificationcenterui",
+            "doNotDisturb",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+// Quiet hours, presentation mode and full-screen apps all count; Focus
+// Assist itself has no public API.
+#[cfg(windows)]
+pub fn do_not_disturb() -> Option<bool> {
+    const QUNS_ACCEPTS_NOTIFICATIONS: i32 = 5;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(state: *mut i32) -> i32;
+    }
+
+    let mut state = 0;
+    if unsafe { SHQueryUserNotificationState(&mut state) } != 0 {
+        return None;
+    }
+    Some(state != QUNS_ACCEPTS_NOTIFICATIONS)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn do_not_disturb() -> Option<bool> {
+    None
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(BODY_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+// Held back while do-not-disturb is on, unless the policy says otherwise.
+pub fn show(app_handle: &AppHandle, title: &str, body: &str) {
+    if config::current().notifications.respect_do_not_disturb && do_not_disturb() == Some(true) {
+        return;
+    }
+    if let Err(e) =
+        tauri::api::notification::Notification::new(&app_handle.config().tauri.bundle.identifier)
+            .title(title)
+            .body(body)
+            .show()
+    {
+        tracing::warn!(target: "notifications", "Failed to show a notification: {}", e);
+    }
+}
+
+fn window_hidden(app_handle: &AppHandle) -> bool {
+    app_handle
+        .get_window("main")
+        .map_or(true, |window| !window.is_visible().unwrap_or(false))
+}
+
+// The title and body for an event the policy wants shown.
+fn notification(app_handle: &AppHandle, event: AgentEvent) -> Option<(String, String)> {
+    let policy = config::current().notifications;
+    match event {
+        AgentEvent::DaemonCrashed { exit_code, .. } if policy.daemon_crash => {
+            let body = match exit_code {
+                Some(code) => i18n::t_args(
+                    "notification-daemon-crashed-body",
+                    &[("code", &code.to_string())],
+                ),
+                None => i18n::t("notification-daemon-crashed-signal"),
+            };
+            Some((i18n::t("notification-daemon-crashed-title"), body))
+        }
+        AgentEvent::WakeWordDetected { name, .. } if policy.wake_word => Some((
+            i18n::t_args("notification-wake-word-title", &[("name", &name)]),
+            i18n::t("notification-wake-word-body"),
+        )),
+        AgentEvent::Transcript(transcript)
+            if policy.transcripts && transcript.is_final && window_hidden(app_handle) =>
+        {
+            Some((
+                i18n::t("notification-transcript-title"),
+                snippet(&transcript.text),
+            ))
+        }
+        _ => None,
+    }
+}
+
+// Checking do-not-disturb runs a process, so it happens off the loop.
+pub fn spawn(app_handle: AppHandle, bus: &EventBus) {
+    let mut events = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if let Some((title, body)) = notification(&app_handle, event) {
+                        let app_handle = app_handle.clone();
+                        tauri::async_runtime::spawn_blocking(move || {
+                            show(&app_handle, &title, &body)
+                        });
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_notification_policy() -> NotificationPolicy {
+    config::current().notifications
+}
+
+#[tauri::command]
+pub fn set_notification_policy(
+    state: tauri::State<'_, AppState>,
+    policy: NotificationPolicy,
+) -> CommandResult<()> {
+    config::update(&state, |config| config.notifications = policy)
+}
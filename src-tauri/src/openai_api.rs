@@ -0,0 +1,207 @@
+use axum::extract::{DefaultBodyLimit, Multipart, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::backends::{self, BackendKind};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::export::{self, ExportFormat};
+use crate::profiles;
+use crate::store;
+use crate::transcript::Transcript;
+use crate::usage;
+use crate::AppState;
+
+const STORE_FILE: &str = "openai_api.json";
+// OpenAI's own upload limit.
+const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiApiConfig {
+    pub enabled: bool,
+}
+
+static CONFIG: Lazy<Mutex<OpenAiApiConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/audio/transcriptions", post(transcriptions))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES))
+}
+
+// OpenAI's error body, so clients show the message instead of a parse error.
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    let kind = if status.is_client_error() {
+        "invalid_request_error"
+    } else {
+        "server_error"
+    };
+    let body = json!({ "error": { "message": message.into(), "type": kind, "param": null, "code": null } });
+    (status, Json(body)).into_response()
+}
+
+fn app_error_response(error: AppError) -> Response {
+    let status = match error.code {
+        ErrorCode::InvalidInput | ErrorCode::Unsupported => StatusCode::BAD_REQUEST,
+        ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        ErrorCode::Network => StatusCode::BAD_GATEWAY,
+        ErrorCode::DaemonUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error_response(status, error.message)
+}
+
+#[derive(Default)]
+struct Upload {
+    file: Option<(String, Vec<u8>)>,
+    model: Option<String>,
+    response_format: Option<String>,
+}
+
+async fn read_upload(mut multipart: Multipart) -> Result<Upload, String> {
+    let mut upload = Upload::default();
+    while let Some(field) = multipart.next_field().await.map_err(|e| e.to_string())? {
+        match field.name().unwrap_or_default() {
+            "file" => {
+                let name = field.file_name().unwrap_or("audio.wav").to_string();
+                let bytes = field.bytes().await.map_err(|e| e.to_string())?;
+                upload.file = Some((name, bytes.to_vec()));
+            }
+            "model" => upload.model = Some(field.text().await.map_err(|e| e.to_string())?),
+            "response_format" => {
+                upload.response_format = Some(field.text().await.map_err(|e| e.to_string())?)
+            }
+            // `language`, `prompt` and `temperature` are accepted and ignored.
+            _ => {}
+        }
+    }
+    Ok(upload)
+}
+
+// `local`, `openai` and `deepgram` pick that backend; any other model name,
+// such as `whisper-1`, means the selected one.
+fn backend_for(model: Option<&str>) -> BackendKind {
+    model
+        .and_then(|model| serde_json::from_value(json!(model.trim())).ok())
+        .unwrap_or_else(backends::selected)
+}
+
+fn span(segment: &Transcript) -> (u64, u64) {
+    let start = segment.start_ms.unwrap_or_default();
+    (start, segment.end_ms.unwrap_or(start).max(start))
+}
+
+fn respond(state: &AppState, format: &str, segments: &[Transcript]) -> Response {
+    let text = segments
+        .iter()
+        .map(|s| s.text.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let plain = |body: String, content_type: &'static str| {
+        ([(header::CONTENT_TYPE, content_type)], body).into_response()
+    };
+    match format {
+        "json" => Json(json!({ "text": text })).into_response(),
+        "text" => plain(text, "text/plain; charset=utf-8"),
+        "srt" => plain(
+            export::render(segments, ExportFormat::Srt),
+            "text/plain; charset=utf-8",
+        ),
+        "vtt" => plain(
+            export::render(segments, ExportFormat::Vtt),
+            "text/vtt; charset=utf-8",
+        ),
+        _ => {
+            let duration = segments.last().map_or(0, |s| span(s).1);
+            let segments: Vec<_> = segments
+                .iter()
+                .enumerate()
+                .map(|(i, segment)| {
+                    let (start, end) = span(segment);
+                    json!({
+                        "id": i,
+                        "start": start as f64 / 1000.0,
+                        "end": end as f64 / 1000.0,
+                        "text": segment.text.trim(),
+                    })
+                })
+                .collect();
+            Json(json!({
+                "task": "transcribe",
+                "language": profiles::active_profile(state).language,
+                "duration": duration as f64 / 1000.0,
+                "text": text,
+                "segments": segments,
+            }))
+            .into_response()
+        }
+    }
+}
+
+async fn transcriptions(State(state): State<AppState>, multipart: Multipart) -> Response {
+    if !CONFIG.lock().enabled {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            "The OpenAI-compatible endpoint is disabled",
+        );
+    }
+    let upload = match read_upload(multipart).await {
+        Ok(upload) => upload,
+        Err(e) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid multipart body: {}", e),
+            )
+        }
+    };
+    let format = upload.response_format.unwrap_or_else(|| "json".to_string());
+    if !["json", "text", "srt", "vtt", "verbose_json"].contains(&format.as_str()) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported response_format {}", format),
+        );
+    }
+    let (file_name, audio) = match upload.file {
+        Some(file) => file,
+        None => return error_response(StatusCode::BAD_REQUEST, "The file field is required"),
+    };
+
+    usage::record("openai_api.transcribe");
+    let kind = backend_for(upload.model.as_deref());
+    let worker_state = state.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        backends::transcribe(&worker_state, kind, audio, &file_name)
+    })
+    .await;
+    match result {
+        Ok(Ok(segments)) => respond(&state, &format, &segments),
+        Ok(Err(error)) => app_error_response(error),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Transcription task failed: {}", e),
+        ),
+    }
+}
+
+// The endpoint is served by the API server, so that has to be running too.
+#[tauri::command]
+pub fn set_openai_api_enabled(enabled: bool) -> CommandResult<()> {
+    let mut config = CONFIG.lock();
+    let mut updated = config.clone();
+    updated.enabled = enabled;
+    store::save_json(STORE_FILE, &updated)?;
+    *config = updated;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_openai_api_enabled() -> bool {
+    CONFIG.lock().enabled
+}
@@ -0,0 +1,363 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::events::{self, UiEvent};
+use crate::i18n;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Microphone,
+    Accessibility,
+    InputMonitoring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    NotDetermined,
+    Restricted,
+    // The platform has no such permission gate.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MicPermission {
+    pub state: PermissionState,
+    // The OS prompt is up; the answer arrives as `permissions_changed`.
+    pub prompted: bool,
+    pub settings_url: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PermissionStatus {
+    pub permission: Permission,
+    pub state: PermissionState,
+    // What stops working without it.
+    pub needed_for: String,
+}
+
+const ALL: [Permission; 3] = [
+    Permission::Microphone,
+    Permission::Accessibility,
+    Permission::InputMonitoring,
+];
+
+static LAST: Lazy<Mutex<Vec<PermissionStatus>>> = Lazy::new(Mutex::default);
+
+impl Permission {
+    fn needed_for(self) -> String {
+        i18n::t(match self {
+            Permission::Microphone => "permission-needed-microphone",
+            Permission::Accessibility => "permission-needed-accessibility",
+            Permission::InputMonitoring => "permission-needed-input-monitoring",
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use block::ConcreteBlock;
+    use objc::runtime::{Class, Object, BOOL};
+    use objc::{msg_send, sel, sel_impl};
+    use tauri::AppHandle;
+
+    use super::{Permission, PermissionState};
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeAudio: *mut Object;
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+
+    const HID_REQUEST_LISTEN_EVENT: u32 = 1;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOHIDCheckAccess(request: u32) -> u32;
+        fn IOHIDRequestAccess(request: u32) -> bool;
+    }
+
+    fn microphone() -> PermissionState {
+        let class = match Class::get("AVCaptureDevice") {
+            Some(class) => class,
+            None => return PermissionState::Unsupported,
+        };
+        // AVAuthorizationStatus
+        let status: isize =
+            unsafe { msg_send![class, authorizationStatusForMediaType: AVMediaTypeAudio] };
+        match status {
+            0 => PermissionState::NotDetermined,
+            1 => PermissionState::Restricted,
+            2 => PermissionState::Denied,
+            _ => PermissionState::Granted,
+        }
+    }
+
+    pub fn state(permission: Permission) -> PermissionState {
+        match permission {
+            Permission::Microphone => microphone(),
+            Permission::Accessibility => {
+                if unsafe { AXIsProcessTrusted() } {
+                    PermissionState::Granted
+                } else {
+                    PermissionState::Denied
+                }
+            }
+            // IOHIDAccessType
+            Permission::InputMonitoring => {
+                match unsafe { IOHIDCheckAccess(HID_REQUEST_LISTEN_EVENT) } {
+                    0 => PermissionState::Granted,
+                    1 => PermissionState::Denied,
+                    _ => PermissionState::NotDetermined,
+                }
+            }
+        }
+    }
+
+    // Input monitoring is the only one with a plain prompt call; the others
+    // are granted in System Settings (the mic prompt also appears on first
+    // capture).
+    pub fn request(permission: Permission) {
+        if permission == Permission::InputMonitoring {
+            unsafe { IOHIDRequestAccess(HID_REQUEST_LISTEN_EVENT) };
+        }
+    }
+
+    // macOS only asks once; after that the answer is changed in System
+    // Settings.
+    pub fn request_microphone(app: AppHandle) -> bool {
+        let class = match Class::get("AVCaptureDevice") {
+            Some(class) => class,
+            None => return false,
+        };
+        let handler = ConcreteBlock::new(move |_granted: BOOL| super::recheck(&app)).copy();
+        unsafe {
+            let _: () = msg_send![
+                class,
+                requestAccessForMediaType: AVMediaTypeAudio
+                completionHandler: &*handler
+            ];
+        }
+        true
+    }
+
+    pub fn settings_url(permission: Permission) -> &'static str {
+        match permission {
+            Permission::Microphone => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone"
+            }
+            Permission::Accessibility => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"
+            }
+            Permission::InputMonitoring => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent"
+            }
+        }
+    }
+}
+
+// Windows never prompts desktop apps; the microphone is allowed unless it
+// has been turned off in Settings, for everyone or for desktop apps.
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+
+    use super::{Permission, PermissionState};
+
+    const HKEY_CURRENT_USER: isize = 0x8000_0001u32 as i32 as isize;
+    const HKEY_LOCAL_MACHINE: isize = 0x8000_0002u32 as i32 as isize;
+    const RRF_RT_REG_SZ: u32 = 0x0002;
+    const CONSENT_STORE: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn RegGetValueW(
+            key: isize,
+            subkey: *const u16,
+            value: *const u16,
+            flags: u32,
+            kind: *mut u32,
+            data: *mut c_void,
+            len: *mut u32,
+        ) -> i32;
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    fn denied(key: isize, subkey: &str) -> bool {
+        let subkey = wide(subkey);
+        let name = wide("Value");
+        let mut data = [0u16; 16];
+        let mut len = (data.len() * 2) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                key,
+                subkey.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                data.as_mut_ptr() as *mut c_void,
+                &mut len,
+            )
+        };
+        status == 0
+            && String::from_utf16_lossy(&data[..len as usize / 2]).trim_end_matches('\0') == "Deny"
+    }
+
+    pub fn state(permission: Permission) -> PermissionState {
+        if permission != Permission::Microphone {
+            return PermissionState::Unsupported;
+        }
+        let desktop_apps = format!(r"{}\NonPackaged", CONSENT_STORE);
+        if denied(HKEY_LOCAL_MACHINE, CONSENT_STORE) {
+            PermissionState::Restricted
+        } else if denied(HKEY_CURRENT_USER, CONSENT_STORE)
+            || denied(HKEY_CURRENT_USER, &desktop_apps)
+        {
+            PermissionState::Denied
+        } else {
+            PermissionState::Granted
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn state(permission: Permission) -> PermissionState {
+    macos::state(permission)
+}
+
+#[cfg(windows)]
+pub fn state(permission: Permission) -> PermissionState {
+    windows::state(permission)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn state(_permission: Permission) -> PermissionState {
+    PermissionState::Unsupported
+}
+
+#[cfg(target_os = "macos")]
+fn settings_url(permission: Permission) -> Option<&'static str> {
+    Some(macos::settings_url(permission))
+}
+
+#[cfg(windows)]
+fn settings_url(permission: Permission) -> Option<&'static str> {
+    match permission {
+        Permission::Microphone => Some("ms-settings:privacy-microphone"),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn settings_url(_permission: Permission) -> Option<&'static str> {
+    None
+}
+
+pub fn check() -> Vec<PermissionStatus> {
+    ALL.iter()
+        .map(|&permission| PermissionStatus {
+            permission,
+            state: state(permission),
+            needed_for: permission.needed_for(),
+        })
+        .collect()
+}
+
+// Permissions change in System Settings while the app is in the background,
+// so they are re-read whenever a window regains focus.
+pub fn recheck(app: &AppHandle) {
+    let current = check();
+    let changed = {
+        let mut last = LAST.lock();
+        let changed = !last.is_empty() && *last != current;
+        *last = current.clone();
+        changed
+    };
+    if changed {
+        events::emit(
+            app,
+            UiEvent::PermissionsChanged {
+                permissions: current,
+            },
+        );
+    }
+}
+
+pub fn microphone_error() -> Option<String> {
+    match state(Permission::Microphone) {
+        PermissionState::Denied | PermissionState::Restricted => Some(i18n::t(if cfg!(windows) {
+            "permission-microphone-denied-windows"
+        } else {
+            "permission-microphone-denied"
+        })),
+        _ => None,
+    }
+}
+
+#[tauri::command]
+pub fn get_permissions() -> Vec<PermissionStatus> {
+    let current = check();
+    *LAST.lock() = current.clone();
+    current
+}
+
+// Asks for the microphone if the user has not been asked yet, so the UI can
+// explain what is missing instead of capture quietly recording silence.
+#[tauri::command]
+pub fn check_mic_permission(app: AppHandle) -> MicPermission {
+    let current = check();
+    *LAST.lock() = current;
+    let state = state(Permission::Microphone);
+    #[cfg(target_os = "macos")]
+    let prompted = state == PermissionState::NotDetermined && macos::request_microphone(app);
+    #[cfg(not(target_os = "macos"))]
+    let prompted = {
+        let _ = app;
+        false
+    };
+    MicPermission {
+        state,
+        prompted,
+        settings_url: settings_url(Permission::Microphone),
+    }
+}
+
+#[tauri::command]
+pub fn open_permission_settings(permission: Permission) -> CommandResult<()> {
+    #[cfg(target_os = "macos")]
+    macos::request(permission);
+    let url = settings_url(permission).ok_or_else(|| {
+        AppError::new(
+            ErrorCode::Unsupported,
+            "This platform has no settings page for that permission",
+        )
+    })?;
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "explorer"
+    };
+    std::process::Command::new(program)
+        .arg(url)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Failed,
+                format!("Failed to open the privacy settings: {}", e),
+            )
+        })
+}
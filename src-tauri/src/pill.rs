@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::http::{Request, Response, ResponseBuilder};
+use tauri::{
+    AppHandle, LogicalSize, Manager, Monitor, PhysicalPosition, Window, WindowBuilder, WindowEvent,
+    WindowUrl,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio;
+use crate::backends;
+use crate::bus::{AgentEvent, EventBus};
+use crate::caption_overlay;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const LABEL: &str = "pill";
+const PAGE: &str = include_str!("../assets/pill.html");
+pub const SCHEME: &str = "pill";
+const STORE_FILE: &str = "pill.json";
+// Logical pixels.
+const WIDTH: f64 = 150.0;
+const HEIGHT: f64 = 36.0;
+const TOP_MARGIN: f64 = 24.0;
+// A drag is saved once the window has been still this long.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+// Logical pixels from the monitor's top-left corner to the pill's.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PillPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PillStore {
+    // The monitor the pill was last on, by name.
+    monitor: Option<String>,
+    positions: HashMap<String, PillPosition>,
+}
+
+static STORE: Lazy<Mutex<PillStore>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// Bumped by every move, so a waiting save knows the drag went on.
+static MOVES: AtomicU64 = AtomicU64::new(0);
+
+fn failed(e: tauri::Error) -> AppError {
+    AppError::from(format!("Pill window failed: {}", e))
+}
+
+pub fn protocol(
+    _app: &AppHandle,
+    _request: &Request,
+) -> Result<Response, Box<dyn std::error::Error>> {
+    ResponseBuilder::new()
+        .mimetype("text/html")
+        .body(PAGE.as_bytes().to_vec())
+}
+
+fn url() -> WindowUrl {
+    let url = if cfg!(windows) {
+        format!("https://{}.localhost/", SCHEME)
+    } else {
+        format!("{}://localhost/", SCHEME)
+    };
+    WindowUrl::External(url.parse().expect("pill URL is valid"))
+}
+
+fn monitor_name(monitor: &Monitor) -> String {
+    monitor.name().cloned().unwrap_or_default()
+}
+
+fn current_state(app_handle: &AppHandle) -> &'static str {
+    let state = app_handle.state::<AppState>();
+    if !backends::is_running(&state) {
+        "idle"
+    } else if audio::is_paused() {
+        "paused"
+    } else {
+        "listening"
+    }
+}
+
+fn create(app_handle: &AppHandle) -> Result<Window, AppError> {
+    let builder = WindowBuilder::new(app_handle, LABEL, url())
+        .title("Recorder")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .focused(false)
+        .visible(false)
+        .inner_size(WIDTH, HEIGHT)
+        .initialization_script(&format!(
+            "window.__PILL_STATE__ = {:?};",
+            current_state(app_handle)
+        ));
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+    let window = builder.build().map_err(failed)?;
+    let moved = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Moved(_) = event {
+            let generation = MOVES.fetch_add(1, Ordering::SeqCst) + 1;
+            let window = moved.clone();
+            thread::spawn(move || {
+                thread::sleep(SAVE_DELAY);
+                if MOVES.load(Ordering::SeqCst) == generation {
+                    remember(&window);
+                }
+            });
+        }
+    });
+    Ok(window)
+}
+
+// Where a drag left the pill, for the monitor it ended up on.
+fn remember(window: &Window) {
+    let (monitor, position) = match (window.current_monitor(), window.outer_position()) {
+        (Ok(Some(monitor)), Ok(position)) => (monitor, position),
+        _ => return,
+    };
+    let scale = monitor.scale_factor();
+    let origin = monitor.position();
+    let name = monitor_name(&monitor);
+    let position = PillPosition {
+        x: (position.x - origin.x) as f64 / scale,
+        y: (position.y - origin.y) as f64 / scale,
+    };
+    let mut store = STORE.lock();
+    if store.monitor.as_ref() == Some(&name) && store.positions.get(&name) == Some(&position) {
+        return;
+    }
+    store.monitor = Some(name.clone());
+    store.positions.insert(name, position);
+    if let Err(e) = store::save_json(STORE_FILE, &*store) {
+        tracing::warn!(target: "pill", "Pill position not saved: {}", e);
+    }
+}
+
+// On the monitor the pill was last on, where it was left there; centred near
+// the top the first time. A position off a monitor that shrank is pulled back
+// onto it.
+fn place(window: &Window) -> Result<(), AppError> {
+    let store = STORE.lock().clone();
+    let monitor = caption_overlay::monitor(window, store.monitor.as_deref())
+        .ok_or_else(|| AppError::new(ErrorCode::Unsupported, "No monitor to show the pill on"))?;
+    let scale = monitor.scale_factor();
+    let area = monitor.size().to_logical::<f64>(scale);
+    let origin = monitor.position();
+    let position = store
+        .positions
+        .get(&monitor_name(&monitor))
+        .copied()
+        .unwrap_or(PillPosition {
+            x: (area.width - WIDTH) / 2.0,
+            y: TOP_MARGIN,
+        });
+    let x = position.x.clamp(0.0, (area.width - WIDTH).max(0.0));
+    let y = position.y.clamp(0.0, (area.height - HEIGHT).max(0.0));
+    window
+        .set_size(LogicalSize::new(WIDTH, HEIGHT))
+        .map_err(failed)?;
+    window
+        .set_position(PhysicalPosition::new(
+            origin.x + (x * scale) as i32,
+            origin.y + (y * scale) as i32,
+        ))
+        .map_err(failed)
+}
+
+fn visible(app_handle: &AppHandle) -> Option<Window> {
+    app_handle
+        .get_window(LABEL)
+        .filter(|window| window.is_visible().unwrap_or(false))
+}
+
+fn push(window: &Window, script: String) {
+    if let Err(e) = window.eval(&script) {
+        tracing::warn!(target: "pill", "Failed to update the pill: {}", e);
+    }
+}
+
+fn state_script(state: &str) -> String {
+    format!("window.pill && window.pill.state({:?})", state)
+}
+
+pub fn show(app_handle: &AppHandle) -> Result<(), AppError> {
+    let window = match app_handle.get_window(LABEL) {
+        Some(window) => window,
+        None => create(app_handle)?,
+    };
+    place(&window)?;
+    window.show().map_err(failed)?;
+    push(&window, state_script(current_state(app_handle)));
+    Ok(())
+}
+
+pub fn hide(app_handle: &AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app_handle.get_window(LABEL) {
+        window.hide().map_err(failed)?;
+    }
+    Ok(())
+}
+
+// The pill follows listening, pausing and speech, and its meter follows the
+// input level, while it is shown.
+pub fn spawn(app_handle: AppHandle, bus: &EventBus) {
+    let mut events = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let script = match event {
+                AgentEvent::AudioLevel(level) => format!(
+                    "window.pill && window.pill.level({})",
+                    level.rms.clamp(0.0, 1.0)
+                ),
+                AgentEvent::ListeningChanged(true) | AgentEvent::SttPaused(false) => {
+                    state_script("listening")
+                }
+                AgentEvent::ListeningChanged(false) => state_script("idle"),
+                AgentEvent::SttPaused(true) => state_script("paused"),
+                AgentEvent::SpeechStarted => state_script("speaking"),
+                AgentEvent::SpeechEnded => state_script("listening"),
+                AgentEvent::DaemonCrashed { .. } | AgentEvent::Error { .. } => {
+                    state_script("error")
+                }
+                _ => continue,
+            };
+            if let Some(window) = visible(&app_handle) {
+                push(&window, script);
+            }
+        }
+    });
+}
+
+// Creating a window from a synchronous command deadlocks on Windows.
+#[tauri::command]
+pub async fn show_pill(app_handle: AppHandle) -> CommandResult<()> {
+    show(&app_handle)
+}
+
+#[tauri::command]
+pub fn hide_pill(app_handle: AppHandle) -> CommandResult<()> {
+    hide(&app_handle)
+}
+
+// Saves where the pill sits on `monitor`, a name `list_monitors` returns, or
+// on the monitor it was last on. A shown pill moves there.
+#[tauri::command]
+pub async fn set_pill_position(
+    app_handle: AppHandle,
+    x: f64,
+    y: f64,
+    monitor: Option<String>,
+) -> CommandResult<()> {
+    let window = match app_handle.get_window(LABEL) {
+        Some(window) => window,
+        None => create(&app_handle)?,
+    };
+    let last = STORE.lock().monitor.clone();
+    let chosen = caption_overlay::monitor(&window, monitor.as_deref().or(last.as_deref()))
+        .ok_or_else(|| AppError::new(ErrorCode::Unsupported, "No monitor to show the pill on"))?;
+    let name = monitor_name(&chosen);
+    if let Some(wanted) = monitor.filter(|wanted| *wanted != name) {
+        return Err(
+            AppError::new(ErrorCode::NotFound, "That monitor is not connected")
+                .with_context("monitor", wanted),
+        );
+    }
+    {
+        let mut store = STORE.lock();
+        let mut updated = store.clone();
+        updated.monitor = Some(name.clone());
+        updated.positions.insert(name, PillPosition { x, y });
+        store::save_json(STORE_FILE, &updated)?;
+        *store = updated;
+    }
+    if window.is_visible().unwrap_or(false) {
+        place(&window)?;
+    }
+    Ok(())
+}
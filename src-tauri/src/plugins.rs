@@ -0,0 +1,299 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use wasmtime::{
+    Caller, Config, Engine, Extern, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::{now_millis, Transcript};
+use crate::usage;
+use crate::AppState;
+
+const STORE_FILE: &str = "plugins.json";
+const FUEL_PER_CALL: u64 = 50_000_000;
+const MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+const MAX_LOG_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Log,
+    Clock,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub file: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Deserialize)]
+struct PluginOutput {
+    text: String,
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    module: Option<Module>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+    pub loaded: bool,
+    pub error: Option<String>,
+}
+
+struct HostState {
+    plugin: String,
+    limits: StoreLimits,
+}
+
+#[derive(Clone)]
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Arc<Mutex<Vec<LoadedPlugin>>>,
+}
+
+pub fn plugins_dir() -> PathBuf {
+    store::data_dir().join("plugins")
+}
+
+impl PluginHost {
+    pub fn load() -> Self {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).expect("wasmtime engine config is valid");
+        let host = Self {
+            engine,
+            plugins: Arc::default(),
+        };
+        host.reload(store::load_json(STORE_FILE));
+        host
+    }
+
+    fn reload(&self, manifests: Vec<PluginManifest>) {
+        let loaded = manifests
+            .into_iter()
+            .map(|manifest| {
+                match Module::from_file(&self.engine, plugins_dir().join(&manifest.file)) {
+                    Ok(module) => LoadedPlugin {
+                        manifest,
+                        module: Some(module),
+                        error: None,
+                    },
+                    Err(e) => LoadedPlugin {
+                        manifest,
+                        module: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect();
+        *self.plugins.lock() = loaded;
+    }
+
+    fn manifests(&self) -> Vec<PluginManifest> {
+        self.plugins
+            .lock()
+            .iter()
+            .map(|p| p.manifest.clone())
+            .collect()
+    }
+
+    fn save_and_reload(&self, manifests: Vec<PluginManifest>) -> Result<(), String> {
+        store::save_json(STORE_FILE, &manifests)?;
+        self.reload(manifests);
+        Ok(())
+    }
+
+    // Runs every enabled plugin in order. A plugin that fails is skipped so a
+    // broken formatter never swallows dictation; `Ok(None)` means a plugin
+    // deliberately dropped the transcript.
+    pub fn process(&self, mut transcript: Transcript) -> (Option<Transcript>, Vec<String>) {
+        let plugins: Vec<(PluginManifest, Module)> = self
+            .plugins
+            .lock()
+            .iter()
+            .filter(|p| p.manifest.enabled)
+            .filter_map(|p| p.module.clone().map(|m| (p.manifest.clone(), m)))
+            .collect();
+
+        let mut errors = Vec::new();
+        for (manifest, module) in plugins {
+            match self.call(&manifest, &module, &transcript) {
+                Ok(Some(text)) => transcript.text = text,
+                Ok(None) => return (None, errors),
+                Err(e) => {
+                    usage::record_error("plugin", Some(&manifest.name));
+                    errors.push(format!("Plugin {} failed: {}", manifest.name, e))
+                }
+            }
+        }
+        (Some(transcript), errors)
+    }
+
+    fn call(
+        &self,
+        manifest: &PluginManifest,
+        module: &Module,
+        transcript: &Transcript,
+    ) -> anyhow::Result<Option<String>> {
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                plugin: manifest.name.clone(),
+                limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+            },
+        );
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+
+        // Only granted capabilities are linked, so a module importing anything
+        // else fails to instantiate.
+        let mut linker: Linker<HostState> = Linker::new(&self.engine);
+        if manifest.capabilities.contains(&Capability::Log) {
+            linker.func_wrap(
+                "env",
+                "log",
+                |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+                    if let Some(Extern::Memory(memory)) = caller.get_export("memory") {
+                        let mut buffer = vec![0u8; (len.max(0) as usize).min(MAX_LOG_LEN)];
+                        if memory.read(&caller, ptr as usize, &mut buffer).is_ok() {
+                            tracing::info!(
+                                target: "plugin",
+                                plugin = %caller.data().plugin,
+                                "{}",
+                                String::from_utf8_lossy(&buffer)
+                            );
+                        }
+                    }
+                },
+            )?;
+        }
+        if manifest.capabilities.contains(&Capability::Clock) {
+            linker.func_wrap("env", "now_ms", || now_millis() as i64)?;
+        }
+
+        let instance = linker.instantiate(&mut store, module)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("plugin does not export memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let process =
+            instance.get_typed_func::<(i32, i32), i64>(&mut store, "process_transcript")?;
+
+        let input = serde_json::to_vec(transcript)?;
+        let ptr = alloc.call(&mut store, input.len() as i32)?;
+        memory.write(&mut store, ptr as usize, &input)?;
+        let packed = process.call(&mut store, (ptr, input.len() as i32))? as u64;
+
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if out_len == 0 {
+            return Ok(None);
+        }
+        let mut output = vec![0u8; out_len];
+        memory.read(&store, out_ptr, &mut output)?;
+        let output: PluginOutput = serde_json::from_slice(&output)?;
+        Ok(Some(output.text))
+    }
+}
+
+#[tauri::command]
+pub fn list_plugins(state: tauri::State<'_, AppState>) -> Vec<PluginInfo> {
+    state
+        .plugins
+        .plugins
+        .lock()
+        .iter()
+        .map(|p| PluginInfo {
+            manifest: p.manifest.clone(),
+            loaded: p.module.is_some(),
+            error: p.error.clone(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn install_plugin(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    capabilities: Vec<Capability>,
+) -> CommandResult<()> {
+    let source = Path::new(&path);
+    let name = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("Invalid plugin path: {}", path))?;
+    let file = format!("{}.wasm", name);
+
+    Module::from_file(&state.plugins.engine, source)
+        .map_err(|e| format!("Invalid WASM module {}: {}", path, e))?;
+    std::fs::create_dir_all(plugins_dir())
+        .and_then(|_| std::fs::copy(source, plugins_dir().join(&file)))
+        .map_err(|e| format!("Failed to install plugin {}: {}", name, e))?;
+
+    let mut manifests = state.plugins.manifests();
+    manifests.retain(|m| m.name != name);
+    manifests.push(PluginManifest {
+        name,
+        file,
+        enabled: true,
+        capabilities,
+    });
+    state
+        .plugins
+        .save_and_reload(manifests)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn remove_plugin(state: tauri::State<'_, AppState>, name: String) -> CommandResult<()> {
+    let mut manifests = state.plugins.manifests();
+    let manifest = manifests
+        .iter()
+        .position(|m| m.name == name)
+        .map(|i| manifests.remove(i))
+        .ok_or_else(|| format!("No plugin named {}", name))?;
+    let _ = std::fs::remove_file(plugins_dir().join(manifest.file));
+    state
+        .plugins
+        .save_and_reload(manifests)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn set_plugin_enabled(
+    state: tauri::State<'_, AppState>,
+    name: String,
+    enabled: bool,
+) -> CommandResult<()> {
+    let mut manifests = state.plugins.manifests();
+    match manifests.iter_mut().find(|m| m.name == name) {
+        Some(manifest) => manifest.enabled = enabled,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No plugin named {}", name),
+            ))
+        }
+    }
+    state
+        .plugins
+        .save_and_reload(manifests)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn reload_plugins(state: tauri::State<'_, AppState>) {
+    state.plugins.reload(store::load_json(STORE_FILE));
+}
@@ -0,0 +1,359 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::AppState;
+
+// Set while transcripts are typed through the RemoteDesktop portal, so the
+// daemon stops typing them itself (its X11 path cannot reach Wayland apps).
+static INJECTING: AtomicBool = AtomicBool::new(false);
+
+pub const TOGGLE_SHORTCUT: &str = "toggle_dictation";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortalStatus {
+    pub wayland: bool,
+    pub desktop: Option<String>,
+    pub global_shortcuts_version: Option<u32>,
+    pub remote_desktop_version: Option<u32>,
+    pub shortcut_bound: bool,
+    pub injection_active: bool,
+    pub notes: Vec<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct PortalState {
+    shortcut_bound: Arc<AtomicBool>,
+    rebind: Arc<tokio::sync::Notify>,
+    release: Arc<tokio::sync::Notify>,
+    #[cfg(target_os = "linux")]
+    injector: Arc<tokio::sync::Mutex<Option<linux::Injector>>>,
+}
+
+pub fn is_wayland() -> bool {
+    cfg!(target_os = "linux")
+        && (std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var("XDG_SESSION_TYPE")
+                .map(|t| t == "wayland")
+                .unwrap_or(false))
+}
+
+pub fn injection_active() -> bool {
+    INJECTING.load(Ordering::SeqCst)
+}
+
+fn notes(status: &PortalStatus) -> Vec<String> {
+    let mut notes = Vec::new();
+    if !status.wayland {
+        notes.push("Not a Wayland session; X11 hotkeys and typing are used.".to_string());
+        return notes;
+    }
+    match status.global_shortcuts_version {
+        Some(_) if status.shortcut_bound => notes.push(
+            "Global shortcut is registered; change its keys in the desktop's shortcut settings."
+                .to_string(),
+        ),
+        Some(_) => notes.push(
+            "The GlobalShortcuts portal is available but the shortcut was not bound (it may have been declined)."
+                .to_string(),
+        ),
+        None => notes.push(
+            "This compositor's portal has no GlobalShortcuts interface. Bind a desktop shortcut to `voice-mcp-agent` control socket commands instead."
+                .to_string(),
+        ),
+    }
+    match status.remote_desktop_version {
+        Some(_) if status.injection_active => {
+            notes.push("Transcripts are typed through the RemoteDesktop portal.".to_string())
+        }
+        Some(_) => notes.push(
+            "Typing into Wayland apps needs the RemoteDesktop portal; enable it to approve keyboard access."
+                .to_string(),
+        ),
+        None => notes.push(
+            "This compositor's portal has no RemoteDesktop interface, so transcripts cannot be typed into native Wayland apps."
+                .to_string(),
+        ),
+    }
+    notes
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+    use ashpd::desktop::remote_desktop::{DeviceType, KeyState, RemoteDesktop};
+    use ashpd::desktop::{PersistMode, Session};
+    use ashpd::WindowIdentifier;
+    use tokio_stream::StreamExt;
+    use zbus::names::InterfaceName;
+
+    use crate::backends;
+    use crate::schedule;
+    use crate::AppState;
+
+    const XK_RETURN: i32 = 0xff0d;
+
+    pub struct Injector {
+        proxy: RemoteDesktop<'static>,
+        session: Session<'static>,
+    }
+
+    pub async fn version(interface: &'static str) -> Option<u32> {
+        let connection = zbus::Connection::session().await.ok()?;
+        let properties = zbus::fdo::PropertiesProxy::builder(&connection)
+            .destination("org.freedesktop.portal.Desktop")
+            .ok()?
+            .path("/org/freedesktop/portal/desktop")
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        let value = properties
+            .get(
+                InterfaceName::from_static_str_unchecked(interface),
+                "version",
+            )
+            .await
+            .ok()?;
+        u32::try_from(value).ok()
+    }
+
+    pub async fn bind_shortcut() -> ashpd::Result<(GlobalShortcuts<'static>, Session<'static>)> {
+        let proxy = GlobalShortcuts::new().await?;
+        let session = proxy.create_session().await?;
+        let shortcut = NewShortcut::new(super::TOGGLE_SHORTCUT, "Start or stop dictation")
+            .preferred_trigger("CTRL+ALT+D");
+        proxy
+            .bind_shortcuts(&session, &[shortcut], &WindowIdentifier::default())
+            .await?
+            .response()?;
+        Ok((proxy, session))
+    }
+
+    pub async fn listen(proxy: GlobalShortcuts<'static>, state: AppState) {
+        let mut activated = match proxy.receive_activated().await {
+            Ok(activated) => activated,
+            Err(e) => {
+                tracing::error!(target: "portal", "Failed to listen for shortcuts: {}", e);
+                return;
+            }
+        };
+        while let Some(event) = activated.next().await {
+            if event.shortcut_id() != super::TOGGLE_SHORTCUT || schedule::quiet() {
+                continue;
+            }
+            let state = state.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = backends::toggle(&state) {
+                    tracing::error!(target: "portal", "Shortcut toggle failed: {}", e);
+                }
+            });
+        }
+    }
+
+    // Shows the compositor's consent dialog for keyboard control.
+    pub async fn start_injector() -> ashpd::Result<Injector> {
+        let proxy = RemoteDesktop::new().await?;
+        let session = proxy.create_session().await?;
+        proxy
+            .select_devices(
+                &session,
+                DeviceType::Keyboard.into(),
+                None,
+                PersistMode::DoNot,
+            )
+            .await?;
+        proxy
+            .start(&session, &WindowIdentifier::default())
+            .await?
+            .response()?;
+        Ok(Injector { proxy, session })
+    }
+
+    fn keysym(c: char) -> i32 {
+        match c {
+            '\n' => XK_RETURN,
+            // Latin-1 keysyms equal their code points; everything else uses
+            // the Unicode keysym range.
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as i32,
+            _ => 0x0100_0000 | c as i32,
+        }
+    }
+
+    impl Injector {
+        pub async fn type_text(&self, text: &str) -> ashpd::Result<()> {
+            for c in text.chars().chain(std::iter::once(' ')) {
+                let keysym = keysym(c);
+                for key_state in [KeyState::Pressed, KeyState::Released] {
+                    self.proxy
+                        .notify_keyboard_keysym(&self.session, keysym, key_state)
+                        .await?;
+                }
+            }
+            Ok(())
+        }
+
+        pub async fn close(self) {
+            let _ = self.session.close().await;
+        }
+    }
+}
+
+impl PortalState {
+    // Portal sessions do not survive suspend on every compositor.
+    pub fn reregister_shortcuts(&self) {
+        self.rebind.notify_one();
+    }
+
+    // Hands the shortcut back to the compositor until the next
+    // `reregister_shortcuts`.
+    pub fn release_shortcuts(&self) {
+        self.release.notify_waiters();
+    }
+
+    pub async fn status(&self) -> PortalStatus {
+        let wayland = is_wayland();
+        #[cfg(target_os = "linux")]
+        let (global_shortcuts_version, remote_desktop_version) = if wayland {
+            (
+                linux::version("org.freedesktop.portal.GlobalShortcuts").await,
+                linux::version("org.freedesktop.portal.RemoteDesktop").await,
+            )
+        } else {
+            (None, None)
+        };
+        #[cfg(not(target_os = "linux"))]
+        let (global_shortcuts_version, remote_desktop_version) = (None, None);
+        let mut status = PortalStatus {
+            wayland,
+            desktop: std::env::var("XDG_CURRENT_DESKTOP").ok(),
+            global_shortcuts_version,
+            remote_desktop_version,
+            shortcut_bound: self.shortcut_bound.load(Ordering::SeqCst),
+            injection_active: injection_active(),
+            notes: Vec::new(),
+        };
+        status.notes = notes(&status);
+        status
+    }
+}
+
+pub fn spawn(state: AppState, portal: PortalState) {
+    if !is_wayland() {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use crate::a11y;
+        use crate::agents;
+        use crate::bus::AgentEvent;
+        use crate::redaction::{self, Destination};
+        use tokio::sync::broadcast::error::RecvError;
+
+        let shortcut_state = state.clone();
+        let bound = portal.shortcut_bound.clone();
+        let rebind = portal.rebind.clone();
+        let release = portal.release.clone();
+        tauri::async_runtime::spawn(async move {
+            if linux::version("org.freedesktop.portal.GlobalShortcuts")
+                .await
+                .is_none()
+            {
+                return;
+            }
+            loop {
+                let rebind_requested = match linux::bind_shortcut().await {
+                    Ok((proxy, session)) => {
+                        bound.store(true, Ordering::SeqCst);
+                        let requested = tokio::select! {
+                            _ = linux::listen(proxy, shortcut_state.clone()) => false,
+                            _ = rebind.notified() => true,
+                            _ = release.notified() => false,
+                        };
+                        let _ = session.close().await;
+                        bound.store(false, Ordering::SeqCst);
+                        requested
+                    }
+                    Err(e) => {
+                        tracing::warn!(target: "portal", "Global shortcut unavailable: {}", e);
+                        false
+                    }
+                };
+                if !rebind_requested {
+                    rebind.notified().await;
+                }
+                tracing::info!(target: "portal", "Re-registering global shortcut");
+            }
+        });
+
+        let mut events = state.bus.subscribe();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(AgentEvent::Transcript(t)) if t.is_final && agents::dictates(&state, &t) => {
+                        if let Some(injector) = portal.injector.lock().await.as_ref() {
+                            let text = redaction::text_for(Destination::Inject, &t);
+                            match injector.type_text(&text).await {
+                                Ok(()) => a11y::transcript_inserted(&state, &text),
+                                Err(e) => state.bus.publish(AgentEvent::error(
+                                    "portal",
+                                    format!("Failed to type transcript: {}", e),
+                                )),
+                            }
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn get_portal_status(
+    portal: tauri::State<'_, PortalState>,
+) -> CommandResult<PortalStatus> {
+    Ok(portal.status().await)
+}
+
+// Takes effect for the daemon from its next start.
+#[tauri::command]
+pub async fn set_portal_injection(
+    portal: tauri::State<'_, PortalState>,
+    enabled: bool,
+) -> CommandResult<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut injector = portal.injector.lock().await;
+        if let Some(existing) = injector.take() {
+            existing.close().await;
+        }
+        INJECTING.store(false, Ordering::SeqCst);
+        if enabled {
+            if !is_wayland() {
+                return Err(AppError::new(
+                    ErrorCode::Unsupported,
+                    "Portal typing is only needed on Wayland",
+                ));
+            }
+            *injector = Some(
+                linux::start_injector()
+                    .await
+                    .map_err(|e| format!("Failed to start RemoteDesktop session: {}", e))?,
+            );
+            INJECTING.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (portal, enabled);
+        Err(AppError::new(
+            ErrorCode::Unsupported,
+            "Portal typing is only needed on Wayland",
+        ))
+    }
+}
@@ -0,0 +1,34 @@
+use crate::bus::AgentEvent;
+use crate::formatting;
+use crate::transcript::Transcript;
+use crate::translation;
+use crate::vocab;
+use crate::AppState;
+
+pub fn run(state: &AppState, transcript: Transcript) -> Option<Transcript> {
+    if !transcript.is_final {
+        return Some(transcript);
+    }
+    let transcript = Transcript {
+        text: vocab::correct(&transcript.text),
+        ..transcript
+    };
+    let transcript = if formatting::enabled() {
+        Transcript {
+            text: formatting::format(&transcript.text),
+            ..transcript
+        }
+    } else {
+        transcript
+    };
+    let (transcript, errors) = state.plugins.process(transcript);
+    for message in errors {
+        state.bus.publish(AgentEvent::error("plugins", message));
+    }
+    let mut transcript = transcript.filter(|t| !t.text.trim().is_empty())?;
+    // An untranslated transcript is still published.
+    if let Err(error) = translation::run(&mut transcript) {
+        state.bus.publish(AgentEvent::error("translation", error));
+    }
+    Some(transcript)
+}
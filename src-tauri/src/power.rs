@@ -0,0 +1,446 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::error::CommandResult;
+use crate::events::UiEvent;
+use crate::portal::PortalState;
+use crate::store;
+use crate::usage;
+use crate::AppState;
+
+const STORE_FILE: &str = "power.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// A poll that wakes up this much later than scheduled slept with the machine.
+const SLEEP_GAP: Duration = Duration::from_secs(15);
+
+static LOGIND_WATCHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    pub pause_on_battery_saver: bool,
+    // Written before suspend so capture comes back even if the agent was
+    // restarted while the machine slept.
+    pub resume_listening: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseReason {
+    Suspend,
+    BatterySaver,
+    SessionInactive,
+    ScreenLocked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum PowerEvent {
+    Suspending,
+    Resumed,
+    BatterySaver { enabled: bool },
+    SessionInactive,
+    SessionActive,
+    ScreenLocked,
+    ScreenUnlocked,
+}
+
+impl PowerEvent {
+    // The UI's `system_suspended` / `system_resumed` pair, with why.
+    pub fn system_event(&self) -> Option<UiEvent> {
+        let reason = match self {
+            PowerEvent::Suspending | PowerEvent::Resumed => "sleep",
+            PowerEvent::ScreenLocked | PowerEvent::ScreenUnlocked => "lock",
+            _ => return None,
+        }
+        .to_string();
+        Some(match self {
+            PowerEvent::Suspending | PowerEvent::ScreenLocked => {
+                UiEvent::SystemSuspended { reason }
+            }
+            _ => UiEvent::SystemResumed { reason },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerStatus {
+    pub pause_on_battery_saver: bool,
+    pub battery_saver: Option<bool>,
+    pub paused: Option<PauseReason>,
+}
+
+#[derive(Clone)]
+pub struct PowerState {
+    config: Arc<Mutex<PowerConfig>>,
+    paused: Arc<Mutex<Option<PauseReason>>>,
+}
+
+impl PowerState {
+    pub fn load() -> Self {
+        let config: PowerConfig = store::load_json(STORE_FILE);
+        let paused = if config.resume_listening {
+            Some(PauseReason::Suspend)
+        } else {
+            None
+        };
+        Self {
+            config: Arc::new(Mutex::new(config)),
+            paused: Arc::new(Mutex::new(paused)),
+        }
+    }
+
+    fn save(&self) {
+        let config = self.config.lock().clone();
+        if let Err(e) = store::save_json(STORE_FILE, &config) {
+            tracing::warn!(target: "power", "{}", e);
+        }
+    }
+
+    fn set_resume_listening(&self, resume: bool) {
+        self.config.lock().resume_listening = resume;
+        self.save();
+    }
+
+    fn listening_to_microphone(state: &AppState) -> bool {
+        backends::cloud_active() || daemon::input_source(state) == Some(InputSource::Microphone)
+    }
+
+    // Only the microphone is tied to this machine's audio devices; streamed
+    // and remote sources reconnect on their own.
+    fn pause(&self, state: &AppState, reason: PauseReason) -> bool {
+        if !Self::listening_to_microphone(state) {
+            return false;
+        }
+        if let Err(e) = backends::stop(state) {
+            tracing::warn!(target: "power", "{}", e);
+            return false;
+        }
+        *self.paused.lock() = Some(reason);
+        true
+    }
+
+    fn unpause(&self, state: &AppState) {
+        *self.paused.lock() = None;
+        if backends::is_running(state) {
+            return;
+        }
+        if let Err(message) = backends::start(state, InputSource::Microphone) {
+            state.bus.publish(AgentEvent::error("power", message));
+        }
+    }
+
+    pub fn suspend(&self, state: &AppState) {
+        tracing::info!(target: "power", "System is suspending");
+        if self.pause(state, PauseReason::Suspend) {
+            self.set_resume_listening(true);
+        }
+        usage::flush();
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::Suspending));
+    }
+
+    pub fn resume(&self, state: &AppState, portal: &PortalState) {
+        tracing::info!(target: "power", "System resumed");
+        portal.reregister_shortcuts();
+        let paused = *self.paused.lock();
+        if paused == Some(PauseReason::Suspend) {
+            self.set_resume_listening(false);
+            if self.battery_saver_applies() {
+                *self.paused.lock() = Some(PauseReason::BatterySaver);
+            } else {
+                self.unpause(state);
+            }
+        } else if backends::cloud_active() {
+            // Suspend went unnoticed, so capture still holds an audio stream
+            // that died with the device, and the provider has likely dropped
+            // the connection too.
+            if let Err(error) = backends::stop(state) {
+                tracing::warn!(target: "power", "{}", error.message);
+            }
+            self.unpause(state);
+        } else if daemon::input_source(state) == Some(InputSource::Microphone) {
+            if let Err(error) = backends::restart(state) {
+                state.bus.publish(AgentEvent::error("power", error));
+            }
+        }
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::Resumed));
+    }
+
+    pub fn session_inactive(&self, state: &AppState) {
+        tracing::info!(target: "power", "Session moved to the background");
+        self.pause(state, PauseReason::SessionInactive);
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::SessionInactive));
+    }
+
+    pub fn session_active(&self, state: &AppState, portal: &PortalState) {
+        tracing::info!(target: "power", "Session is active again");
+        portal.reregister_shortcuts();
+        if *self.paused.lock() == Some(PauseReason::SessionInactive) {
+            if self.battery_saver_applies() {
+                *self.paused.lock() = Some(PauseReason::BatterySaver);
+            } else {
+                self.unpause(state);
+            }
+        }
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::SessionActive));
+    }
+
+    // Nothing dictated at a lock screen belongs in the app behind it.
+    pub fn screen_locked(&self, state: &AppState) {
+        tracing::info!(target: "power", "Screen locked");
+        if self.paused.lock().is_none() {
+            self.pause(state, PauseReason::ScreenLocked);
+        }
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::ScreenLocked));
+    }
+
+    pub fn screen_unlocked(&self, state: &AppState) {
+        tracing::info!(target: "power", "Screen unlocked");
+        if *self.paused.lock() == Some(PauseReason::ScreenLocked) {
+            if self.battery_saver_applies() {
+                *self.paused.lock() = Some(PauseReason::BatterySaver);
+            } else {
+                self.unpause(state);
+            }
+        }
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::ScreenUnlocked));
+    }
+
+    fn battery_saver_applies(&self) -> bool {
+        self.config.lock().pause_on_battery_saver && battery_saver() == Some(true)
+    }
+
+    fn battery_saver_changed(&self, state: &AppState, enabled: bool) {
+        state
+            .bus
+            .publish(AgentEvent::PowerChanged(PowerEvent::BatterySaver {
+                enabled,
+            }));
+        if !self.config.lock().pause_on_battery_saver {
+            return;
+        }
+        let paused = *self.paused.lock();
+        if enabled && paused.is_none() {
+            self.pause(state, PauseReason::BatterySaver);
+        } else if !enabled && paused == Some(PauseReason::BatterySaver) {
+            self.unpause(state);
+        }
+    }
+
+    pub fn status(&self) -> PowerStatus {
+        PowerStatus {
+            pause_on_battery_saver: self.config.lock().pause_on_battery_saver,
+            battery_saver: battery_saver(),
+            paused: *self.paused.lock(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn battery_saver() -> Option<bool> {
+    let output = std::process::Command::new("powerprofilesctl")
+        .arg("get")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "power-saver")
+}
+
+#[cfg(target_os = "macos")]
+pub fn battery_saver() -> Option<bool> {
+    let output = std::process::Command::new("pmset")
+        .arg("-g")
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let line = text
+        .lines()
+        .find(|line| line.trim_start().starts_with("lowpowermode"))?;
+    Some(line.split_whitespace().nth(1) == Some("1"))
+}
+
+#[cfg(windows)]
+pub fn battery_saver() -> Option<bool> {
+    #[repr(C)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    let mut status = SystemPowerStatus {
+        ac_line_status: 0,
+        battery_flag: 0,
+        battery_life_percent: 0,
+        system_status_flag: 0,
+        battery_life_time: 0,
+        battery_full_life_time: 0,
+    };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    Some(status.system_status_flag == 1)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn battery_saver() -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+mod logind {
+    use tokio_stream::StreamExt;
+    use zbus::zvariant::OwnedFd;
+
+    use super::PowerState;
+    use crate::portal::PortalState;
+    use crate::AppState;
+
+    async fn manager(connection: &zbus::Connection) -> zbus::Result<zbus::Proxy<'static>> {
+        zbus::Proxy::new(
+            connection,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )
+        .await
+    }
+
+    // A delay lock holds suspend back until capture has been stopped.
+    async fn inhibit(manager: &zbus::Proxy<'_>) -> Option<OwnedFd> {
+        manager
+            .call(
+                "Inhibit",
+                &(
+                    "sleep",
+                    "Voice MCP Agent",
+                    "Stop audio capture before suspend",
+                    "delay",
+                ),
+            )
+            .await
+            .ok()
+    }
+
+    pub async fn watch(
+        state: AppState,
+        power: PowerState,
+        portal: PortalState,
+    ) -> zbus::Result<()> {
+        let connection = zbus::Connection::system().await?;
+        let manager = manager(&connection).await?;
+        let mut signals = manager.receive_signal("PrepareForSleep").await?;
+        let mut lock = inhibit(&manager).await;
+        super::LOGIND_WATCHING.store(true, super::Ordering::SeqCst);
+        while let Some(message) = signals.next().await {
+            let suspending: bool = match message.body() {
+                Ok(suspending) => suspending,
+                Err(_) => continue,
+            };
+            let (state, power, portal) = (state.clone(), power.clone(), portal.clone());
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if suspending {
+                    power.suspend(&state);
+                } else {
+                    power.resume(&state, &portal);
+                }
+            })
+            .await;
+            if suspending {
+                lock.take();
+            } else {
+                lock = inhibit(&manager).await;
+            }
+        }
+        super::LOGIND_WATCHING.store(false, super::Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+// logind reports suspend ahead of time on Linux; elsewhere (and when logind
+// is unavailable) a resume is noticed from the clock gap afterwards.
+pub fn spawn_monitor(state: AppState, power: PowerState, portal: PortalState) {
+    #[cfg(target_os = "linux")]
+    {
+        let (state, power, portal) = (state.clone(), power.clone(), portal.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = logind::watch(state, power, portal).await {
+                tracing::warn!(target: "power", "logind sleep signals unavailable: {}", e);
+            }
+        });
+    }
+
+    std::thread::spawn(move || {
+        if *power.paused.lock() == Some(PauseReason::Suspend) {
+            power.resume(&state, &portal);
+        }
+        let mut battery_saver_on = battery_saver();
+        let mut last_tick = SystemTime::now();
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let now = SystemTime::now();
+            let elapsed = now.duration_since(last_tick).unwrap_or_default();
+            last_tick = now;
+            if elapsed > POLL_INTERVAL + SLEEP_GAP && !LOGIND_WATCHING.load(Ordering::SeqCst) {
+                power.resume(&state, &portal);
+            }
+
+            let current = battery_saver();
+            if current.is_some() && current != battery_saver_on {
+                power.battery_saver_changed(&state, current == Some(true));
+            }
+            battery_saver_on = current;
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_power_status(power: tauri::State<'_, PowerState>) -> PowerStatus {
+    power.status()
+}
+
+#[tauri::command]
+pub fn set_pause_on_battery_saver(
+    state: tauri::State<'_, AppState>,
+    power: tauri::State<'_, PowerState>,
+    enabled: bool,
+) -> CommandResult<()> {
+    power.config.lock().pause_on_battery_saver = enabled;
+    power.save();
+    let paused = *power.paused.lock();
+    if enabled && paused.is_none() && battery_saver() == Some(true) {
+        power.pause(&state, PauseReason::BatterySaver);
+    } else if !enabled && paused == Some(PauseReason::BatterySaver) {
+        power.unpause(&state);
+    }
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::config;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::window_tracker;
+use crate::AppState;
+
+const STORE_FILE: &str = "profiles.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub model: String,
+    pub language: String,
+    // Each utterance's language is detected and `language` is ignored.
+    #[serde(default)]
+    pub detect_language: bool,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            model: "base".to_string(),
+            language: "en".to_string(),
+            detect_language: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profiles {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        let profile = Profile::default();
+        Self {
+            active: profile.name.clone(),
+            profiles: vec![profile],
+        }
+    }
+}
+
+impl Profiles {
+    pub fn load() -> Self {
+        store::load_json(STORE_FILE)
+    }
+
+    pub fn active_profile(&self) -> Profile {
+        self.profiles
+            .iter()
+            .find(|p| p.name == self.active)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+pub fn active_profile(state: &AppState) -> Profile {
+    state.profiles.lock().active_profile()
+}
+
+// A model set in config.toml wins over the profile's.
+pub fn active_model(state: &AppState) -> String {
+    config::current()
+        .model
+        .unwrap_or_else(|| active_profile(state).model)
+}
+
+// What the backends transcribe in, or None to detect it. The focused app's
+// language wins.
+pub fn stt_language(state: &AppState) -> Option<String> {
+    if let Some(language) = window_tracker::language() {
+        return Some(language);
+    }
+    let profile = active_profile(state);
+    if profile.detect_language {
+        None
+    } else {
+        Some(profile.language)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SttLanguage {
+    pub language: String,
+    pub auto_detect: bool,
+}
+
+impl From<Profile> for SttLanguage {
+    fn from(profile: Profile) -> Self {
+        Self {
+            language: profile.language,
+            auto_detect: profile.detect_language,
+        }
+    }
+}
+
+// Saved to the active profile and handed to whatever is transcribing, so
+// switching languages needs no restart.
+fn update_language(
+    state: &AppState,
+    change: impl FnOnce(&mut Profile),
+) -> Result<SttLanguage, AppError> {
+    let settings = {
+        let mut profiles = state.profiles.lock();
+        let active = profiles.active.clone();
+        let profile = profiles
+            .profiles
+            .iter_mut()
+            .find(|p| p.name == active)
+            .ok_or_else(|| {
+                AppError::new(ErrorCode::NotFound, format!("No profile named {}", active))
+            })?;
+        change(profile);
+        let settings = SttLanguage::from(profile.clone());
+        store::save_json(STORE_FILE, &*profiles)?;
+        settings
+    };
+    backends::set_language(state, stt_language(state))?;
+    state
+        .bus
+        .publish(AgentEvent::SttLanguageChanged(settings.clone()));
+    Ok(settings)
+}
+
+pub fn switch(state: &AppState, name: &str) -> Result<Profile, String> {
+    let profile = {
+        let mut profiles = state.profiles.lock();
+        let profile = profiles
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| format!("No profile named {}", name))?;
+        profiles.active = profile.name.clone();
+        store::save_json(STORE_FILE, &*profiles)?;
+        profile
+    };
+    state.models.clear_downgrade();
+    state
+        .bus
+        .publish(AgentEvent::ProfileChanged(profile.name.clone()));
+
+    backends::restart(state)?;
+    Ok(profile)
+}
+
+// For an imported settings file. Returns the profile to switch to, which may
+// not be the file's active one if that is missing from its list.
+pub fn replace(state: &AppState, mut imported: Profiles) -> Result<String, AppError> {
+    let first = match imported.profiles.first() {
+        Some(profile) => profile.name.clone(),
+        None => {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "The settings file has no profiles",
+            ))
+        }
+    };
+    if !imported.profiles.iter().any(|p| p.name == imported.active) {
+        imported.active = first;
+    }
+    let active = imported.active.clone();
+    store::save_json(STORE_FILE, &imported)?;
+    *state.profiles.lock() = imported;
+    Ok(active)
+}
+
+pub fn switch_next(state: &AppState) -> Result<Profile, String> {
+    let next = {
+        let profiles = state.profiles.lock();
+        let index = profiles
+            .profiles
+            .iter()
+            .position(|p| p.name == profiles.active)
+            .map(|i| (i + 1) % profiles.profiles.len())
+            .unwrap_or(0);
+        match profiles.profiles.get(index) {
+            Some(profile) => profile.name.clone(),
+            None => return Err("No profiles configured".to_string()),
+        }
+    };
+    switch(state, &next)
+}
+
+#[tauri::command]
+pub fn get_stt_language(state: tauri::State<'_, AppState>) -> SttLanguage {
+    SttLanguage::from(active_profile(&state))
+}
+
+pub fn is_language_code(code: &str) -> bool {
+    (2..=8).contains(&code.len())
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && code != "auto"
+}
+
+// A code such as `en` or `pt-BR`. `set_language` is the interface language.
+#[tauri::command]
+pub fn set_stt_language(
+    state: tauri::State<'_, AppState>,
+    code: String,
+) -> CommandResult<SttLanguage> {
+    let code = code.trim().to_string();
+    if !is_language_code(&code) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Not a language code: {}", code),
+        ));
+    }
+    update_language(&state, |profile| profile.language = code)
+}
+
+// Detection falls back to the profile's language once it is switched off.
+#[tauri::command]
+pub fn detect_language(
+    state: tauri::State<'_, AppState>,
+    auto: bool,
+) -> CommandResult<SttLanguage> {
+    update_language(&state, |profile| profile.detect_language = auto)
+}
+
+#[tauri::command]
+pub fn list_profiles(state: tauri::State<'_, AppState>) -> Profiles {
+    state.profiles.lock().clone()
+}
+
+#[tauri::command]
+pub fn save_profile(state: tauri::State<'_, AppState>, profile: Profile) -> CommandResult<()> {
+    if profile.name.trim().is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Profile name cannot be empty",
+        ));
+    }
+    let mut profiles = state.profiles.lock();
+    match profiles
+        .profiles
+        .iter_mut()
+        .find(|p| p.name == profile.name)
+    {
+        Some(existing) => *existing = profile,
+        None => profiles.profiles.push(profile),
+    }
+    store::save_json(STORE_FILE, &*profiles)
+}
+
+#[tauri::command]
+pub fn delete_profile(state: tauri::State<'_, AppState>, name: String) -> CommandResult<()> {
+    let mut profiles = state.profiles.lock();
+    if profiles.active == name {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Cannot delete the active profile",
+        ));
+    }
+    let before = profiles.profiles.len();
+    profiles.profiles.retain(|p| p.name != name);
+    if profiles.profiles.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No profile named {}", name),
+        ));
+    }
+    store::save_json(STORE_FILE, &*profiles)
+}
+
+#[tauri::command]
+pub async fn set_active_profile(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> CommandResult<Profile> {
+    switch(&state, &name).map_err(AppError::from)
+}
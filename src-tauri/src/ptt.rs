@@ -0,0 +1,369 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::audio::{self, SAMPLE_RATE};
+use crate::bus::AgentEvent;
+use crate::config;
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::lookback;
+use crate::native_stt;
+use crate::schedule;
+use crate::shortcuts::{self, ShortcutAction};
+use crate::store;
+use crate::transcript::Transcript;
+use crate::AppState;
+
+const STORE_FILE: &str = "ptt.json";
+// A key held down by accident should not buffer audio forever.
+const MAX_SECONDS: usize = 120;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PttConfig {
+    // An accelerator such as "Ctrl+Alt+Space".
+    pub combo: Option<String>,
+    // Older versions kept the undo combo here. It is read once and moved to
+    // the shortcut registry's `undo_injection`.
+    #[serde(skip_serializing)]
+    pub undo_combo: Option<String>,
+    // Transcribes the lookback buffer.
+    pub lookback_combo: Option<String>,
+}
+
+static CONFIG: Lazy<Mutex<PttConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+static APP: OnceCell<AppHandle> = OnceCell::new();
+static BOUND: Lazy<Mutex<Option<HotKey>>> = Lazy::new(|| Mutex::new(None));
+static LOOKBACK_BOUND: Lazy<Mutex<Option<HotKey>>> = Lazy::new(|| Mutex::new(None));
+// Key repeat sends a stream of presses while the combo is held.
+static HELD: AtomicBool = AtomicBool::new(false);
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+static RECORDING: Lazy<Arc<Mutex<Vec<f32>>>> = Lazy::new(Arc::default);
+
+thread_local! {
+    // macOS and Windows deliver hotkeys to the thread running the event
+    // loop, so the manager lives on the main thread.
+    static MANAGER: RefCell<Option<GlobalHotKeyManager>> = RefCell::new(None);
+}
+
+pub fn parse(combo: &str) -> Result<HotKey, AppError> {
+    HotKey::from_str(combo).map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid hotkey {}: {}", combo, e),
+        )
+    })
+}
+
+// Main thread only. `slot` is BOUND, LOOKBACK_BOUND or one of the shortcut
+// registry's.
+pub fn bind(slot: &'static Mutex<Option<HotKey>>, hotkey: Option<HotKey>) -> Result<(), AppError> {
+    MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        if manager.is_none() {
+            *manager = Some(GlobalHotKeyManager::new().map_err(|e| {
+                AppError::new(
+                    ErrorCode::Unsupported,
+                    format!("Global hotkeys are unavailable: {}", e),
+                )
+            })?);
+        }
+        let manager = match manager.as_ref() {
+            Some(manager) => manager,
+            None => return Ok(()),
+        };
+        let mut bound = slot.lock();
+        if let Some(current) = bound.take() {
+            let _ = manager.unregister(current);
+        }
+        if let Some(hotkey) = hotkey {
+            manager
+                .register(hotkey)
+                .map_err(|e| AppError::from(format!("Failed to register the hotkey: {}", e)))?;
+            *bound = Some(hotkey);
+        }
+        Ok(())
+    })
+}
+
+pub async fn bind_on_main_thread(
+    slot: &'static Mutex<Option<HotKey>>,
+    hotkey: Option<HotKey>,
+) -> Result<(), AppError> {
+    let app = APP
+        .get()
+        .ok_or_else(|| AppError::new(ErrorCode::Unsupported, "Global hotkeys need the window"))?;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(bind(slot, hotkey));
+    })
+    .map_err(|e| AppError::from(format!("Failed to reach the main thread: {}", e)))?;
+    rx.await
+        .map_err(|_| AppError::from("Hotkey registration was dropped"))?
+}
+
+pub fn bind_later(slot: &'static Mutex<Option<HotKey>>, hotkey: Option<HotKey>) {
+    if let Some(app) = APP.get() {
+        let _ = app.run_on_main_thread(move || {
+            if let Err(e) = bind(slot, hotkey) {
+                tracing::warn!(target: "ptt", "{}", e);
+            }
+        });
+    }
+}
+
+fn write_wav(path: &Path, samples: &[f32]) -> Result<(), AppError> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let failed = |e: hound::Error| AppError::from(format!("Failed to write the recording: {}", e));
+    let mut writer = hound::WavWriter::create(path, spec).map_err(failed)?;
+    for &sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(failed)?;
+    }
+    writer.finalize().map_err(failed)
+}
+
+// In process when built with `whisper`; otherwise through the daemon's file
+// mode, which loads the model for every utterance.
+pub fn transcribe(state: &AppState, samples: &[f32]) -> Result<Transcript, AppError> {
+    match native_stt::transcribe_samples(state, samples, SAMPLE_RATE) {
+        Err(error) if error.code == ErrorCode::Unsupported => {}
+        result => return result,
+    }
+    let path = std::env::temp_dir().join(format!("voice-mcp-ptt-{}.wav", uuid::Uuid::new_v4()));
+    write_wav(&path, samples)?;
+    let result = daemon::transcribe_file(state, &path);
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+fn press(state: &AppState) {
+    state.bus.publish(AgentEvent::PttPressed);
+    RECORDING.lock().clear();
+    let recording = RECORDING.clone();
+    let sink = Box::new(move |samples: &[f32]| {
+        let mut recording = recording.lock();
+        if recording.len() < MAX_SECONDS * SAMPLE_RATE as usize {
+            recording.extend_from_slice(samples);
+        }
+    });
+    match audio::start_with_sink(state, None, sink) {
+        Ok(_) => CAPTURING.store(true, Ordering::SeqCst),
+        Err(error) => state.bus.publish(AgentEvent::error("ptt", error)),
+    }
+}
+
+fn release(state: &AppState) {
+    state.bus.publish(AgentEvent::PttReleased);
+    if !CAPTURING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    if let Err(error) = audio::stop(state) {
+        tracing::warn!(target: "ptt", "{}", error);
+    }
+    let samples = std::mem::take(&mut *RECORDING.lock());
+    if samples.is_empty() {
+        return;
+    }
+    let state = state.clone();
+    thread::spawn(move || {
+        if let Err(error) = transcribe(&state, &samples) {
+            state.bus.publish(AgentEvent::error("ptt", error));
+        }
+    });
+}
+
+fn look_back(state: &AppState) {
+    let state = state.clone();
+    thread::spawn(move || {
+        if let Err(error) = lookback::transcribe_window(&state, None) {
+            state.bus.publish(AgentEvent::error("lookback", error));
+        }
+    });
+}
+
+fn listen(state: AppState) {
+    let events = GlobalHotKeyEvent::receiver();
+    while let Ok(event) = events.recv() {
+        // A release still ends a press made before quiet hours began.
+        if matches!(event.state, HotKeyState::Pressed) && schedule::quiet() {
+            continue;
+        }
+        if let Some(action) = shortcuts::action_for(event.id) {
+            if matches!(event.state, HotKeyState::Pressed) {
+                shortcuts::run(&state, action);
+            }
+            continue;
+        }
+        if LOOKBACK_BOUND.lock().map(|hotkey| hotkey.id()) == Some(event.id) {
+            if matches!(event.state, HotKeyState::Pressed) {
+                look_back(&state);
+            }
+            continue;
+        }
+        if BOUND.lock().map(|hotkey| hotkey.id()) != Some(event.id) {
+            continue;
+        }
+        match event.state {
+            HotKeyState::Pressed => {
+                if !HELD.swap(true, Ordering::SeqCst) {
+                    press(&state);
+                }
+            }
+            HotKeyState::Released => {
+                if HELD.swap(false, Ordering::SeqCst) {
+                    release(&state);
+                }
+            }
+        }
+    }
+}
+
+// Keeps a shortcut the registry already has.
+fn move_undo_combo(state: &AppState, config: &mut PttConfig) -> Result<(), AppError> {
+    match config.undo_combo.take() {
+        Some(combo) => config::update(state, |current| {
+            current.shortcuts.undo_injection.get_or_insert(combo);
+        }),
+        None => Ok(()),
+    }
+}
+
+// Called from setup, on the main thread.
+pub fn init(app: &AppHandle, state: AppState) {
+    let _ = APP.set(app.clone());
+    let mut config = CONFIG.lock().clone();
+    if config.undo_combo.is_some() {
+        if let Err(e) = move_undo_combo(&state, &mut config).and_then(|_| save(config.clone())) {
+            tracing::warn!(target: "ptt", "Undo hotkey not moved to the shortcuts: {}", e);
+        }
+    }
+    thread::spawn(move || listen(state));
+    if let Some(combo) = config.combo {
+        if let Err(e) = parse(&combo).and_then(|hotkey| bind(&BOUND, Some(hotkey))) {
+            tracing::warn!(target: "ptt", "Push-to-talk hotkey not restored: {}", e);
+        }
+    }
+    if let Some(combo) = config.lookback_combo {
+        if let Err(e) = parse(&combo).and_then(|hotkey| bind(&LOOKBACK_BOUND, Some(hotkey))) {
+            tracing::warn!(target: "ptt", "Lookback hotkey not restored: {}", e);
+        }
+    }
+    shortcuts::init(app);
+}
+
+// Lets another session's user have the combos; `restore_hotkey` takes them
+// back.
+pub fn release_hotkey() {
+    bind_later(&BOUND, None);
+    bind_later(&LOOKBACK_BOUND, None);
+    shortcuts::release();
+}
+
+pub fn restore_hotkey() {
+    let config = CONFIG.lock().clone();
+    if let Some(hotkey) = config.combo.and_then(|combo| parse(&combo).ok()) {
+        bind_later(&BOUND, Some(hotkey));
+    }
+    if let Some(hotkey) = config.lookback_combo.and_then(|combo| parse(&combo).ok()) {
+        bind_later(&LOOKBACK_BOUND, Some(hotkey));
+    }
+    shortcuts::restore(&config::current().shortcuts);
+}
+
+// The saved push-to-talk combos, named for conflict messages.
+pub fn combos() -> Vec<(&'static str, String)> {
+    let config = CONFIG.lock().clone();
+    [
+        ("push_to_talk", config.combo),
+        ("lookback", config.lookback_combo),
+    ]
+    .into_iter()
+    .filter_map(|(name, combo)| combo.map(|combo| (name, combo)))
+    .collect()
+}
+
+fn save(config: PttConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
+
+// For an imported settings file. A combo that does not parse is left unbound.
+pub fn replace(state: &AppState, mut config: PttConfig) -> Result<(), AppError> {
+    move_undo_combo(state, &mut config)?;
+    let hotkey = |combo: &Option<String>| combo.as_deref().and_then(|combo| parse(combo).ok());
+    bind_later(&BOUND, hotkey(&config.combo));
+    bind_later(&LOOKBACK_BOUND, hotkey(&config.lookback_combo));
+    save(config)
+}
+
+#[tauri::command]
+pub fn get_ptt_hotkey() -> PttConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub async fn register_ptt_hotkey(combo: String) -> CommandResult<()> {
+    let hotkey = parse(&combo)?;
+    bind_on_main_thread(&BOUND, Some(hotkey)).await?;
+    let mut config = CONFIG.lock().clone();
+    config.combo = Some(combo);
+    save(config)
+}
+
+#[tauri::command]
+pub async fn unregister_ptt_hotkey() -> CommandResult<()> {
+    bind_on_main_thread(&BOUND, None).await?;
+    let mut config = CONFIG.lock().clone();
+    config.combo = None;
+    save(config)
+}
+
+// The registry's `undo_injection`, under its older name.
+#[tauri::command]
+pub async fn register_undo_hotkey(
+    state: tauri::State<'_, AppState>,
+    combo: String,
+) -> CommandResult<()> {
+    shortcuts::bind_shortcut(state, ShortcutAction::UndoInjection, combo).await
+}
+
+#[tauri::command]
+pub async fn unregister_undo_hotkey(state: tauri::State<'_, AppState>) -> CommandResult<()> {
+    shortcuts::unbind_shortcut(state, ShortcutAction::UndoInjection).await
+}
+
+#[tauri::command]
+pub async fn register_lookback_hotkey(combo: String) -> CommandResult<()> {
+    let hotkey = parse(&combo)?;
+    bind_on_main_thread(&LOOKBACK_BOUND, Some(hotkey)).await?;
+    let mut config = CONFIG.lock().clone();
+    config.lookback_combo = Some(combo);
+    save(config)
+}
+
+#[tauri::command]
+pub async fn unregister_lookback_hotkey() -> CommandResult<()> {
+    bind_on_main_thread(&LOOKBACK_BOUND, None).await?;
+    let mut config = CONFIG.lock().clone();
+    config.lookback_combo = None;
+    save(config)
+}
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tauri::{CustomMenuItem, SystemTrayMenu, SystemTraySubmenu};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::history::{self, HistoryFilter};
+use crate::i18n;
+use crate::transcript::Transcript;
+
+// Tray item ids are this prefix and the transcript id.
+pub const MENU_PREFIX: &str = "recent:";
+const COUNT: usize = 5;
+const LABEL_CHARS: usize = 40;
+
+// Newest first: (id, text). Seeded from history so the menu is not empty
+// after a restart.
+static RECENT: Lazy<Mutex<VecDeque<(String, String)>>> = Lazy::new(|| {
+    let entries = history::query(&HistoryFilter::default(), COUNT as u32, 0).unwrap_or_default();
+    Mutex::new(entries.into_iter().map(|e| (e.id, e.text)).collect())
+});
+
+// Returns whether the list changed, i.e. whether the menu needs rebuilding.
+pub fn push(transcript: &Transcript) -> bool {
+    if !transcript.is_final || transcript.text.trim().is_empty() {
+        return false;
+    }
+    let mut recent = RECENT.lock();
+    recent.push_front((transcript.id.clone(), transcript.text.clone()));
+    recent.truncate(COUNT);
+    true
+}
+
+fn label(text: &str) -> String {
+    let text = text.trim();
+    let mut label: String = text.chars().take(LABEL_CHARS).collect();
+    if text.chars().count() > LABEL_CHARS {
+        label.push('…');
+    }
+    label
+}
+
+pub fn submenu() -> SystemTraySubmenu {
+    let recent = RECENT.lock();
+    let menu = if recent.is_empty() {
+        SystemTrayMenu::new()
+            .add_item(CustomMenuItem::new("recent_empty", i18n::t("tray-recent-empty")).disabled())
+    } else {
+        recent
+            .iter()
+            .fold(SystemTrayMenu::new(), |menu, (id, text)| {
+                menu.add_item(CustomMenuItem::new(
+                    format!("{}{}", MENU_PREFIX, id),
+                    label(text),
+                ))
+            })
+    };
+    SystemTraySubmenu::new(i18n::t("tray-recent"), menu)
+}
+
+fn text(id: &str) -> Result<String, AppError> {
+    let cached = RECENT
+        .lock()
+        .iter()
+        .find(|(recent_id, _)| recent_id == id)
+        .map(|(_, text)| text.clone());
+    match cached {
+        Some(text) => Ok(text),
+        None => history::get(id).map(|entry| entry.text),
+    }
+}
+
+pub fn copy(id: &str) -> Result<(), AppError> {
+    let text = text(id)?;
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| AppError::from(format!("Failed to copy the transcript: {}", e)))
+}
+
+pub fn copy_latest() -> Result<(), AppError> {
+    let id = RECENT.lock().front().map(|(id, _)| id.clone());
+    match id {
+        Some(id) => copy(&id),
+        None => Err(AppError::new(
+            ErrorCode::NotFound,
+            "Nothing has been transcribed yet",
+        )),
+    }
+}
+
+#[tauri::command]
+pub async fn copy_transcript(id: String) -> CommandResult<()> {
+    tauri::async_runtime::spawn_blocking(move || copy(&id))
+        .await
+        .map_err(|e| AppError::from(format!("Copy task failed: {}", e)))?
+}
@@ -0,0 +1,377 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use flacenc::component::BitRepr;
+use flacenc::error::Verify;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::SAMPLE_RATE;
+use crate::backends;
+use crate::encryption;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::Transcript;
+
+const STORE_FILE: &str = "recordings.json";
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// Chunks the daemon wrote for transcripts that never arrived.
+const STAGING_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+// Added after the format's extension while encryption is on.
+const SEALED_EXTENSION: &str = "enc";
+// Decrypted copies handed out for playback.
+const PLAYBACK_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    Wav,
+    // Lossless, at about half the size.
+    Flac,
+}
+
+impl RecordingFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "wav",
+            RecordingFormat::Flac => "flac",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    pub enabled: bool,
+    pub format: RecordingFormat,
+    // Oldest recordings go first once either limit is passed.
+    pub max_size_mb: Option<u64>,
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: RecordingFormat::Wav,
+            max_size_mb: Some(1024),
+            max_age_days: Some(30),
+        }
+    }
+}
+
+static CONFIG: Lazy<Mutex<RecordingConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+fn dir() -> PathBuf {
+    store::data_dir().join("recordings")
+}
+
+fn staging() -> PathBuf {
+    dir().join(".staging")
+}
+
+fn playback() -> PathBuf {
+    dir().join(".playback")
+}
+
+fn sealed_path(path: &Path) -> PathBuf {
+    let mut sealed = path.as_os_str().to_owned();
+    sealed.push(".");
+    sealed.push(SEALED_EXTENSION);
+    PathBuf::from(sealed)
+}
+
+fn is_sealed(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == SEALED_EXTENSION)
+}
+
+fn storage(path: &Path, e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::Storage,
+        format!("Failed to save {}: {}", path.display(), e),
+    )
+}
+
+// Where the daemon writes each chunk it transcribes, while recording is on.
+pub fn staging_dir() -> Option<PathBuf> {
+    if !CONFIG.lock().enabled {
+        return None;
+    }
+    let staging = staging();
+    match fs::create_dir_all(&staging) {
+        Ok(()) => Some(staging),
+        Err(e) => {
+            tracing::warn!(target: "recordings", "{}", storage(&staging, e));
+            None
+        }
+    }
+}
+
+fn encode_flac(path: &Path, samples: &[i32]) -> Result<Vec<u8>, AppError> {
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| storage(path, format!("{:?}", e)))?;
+    let source = flacenc::source::MemSource::from_samples(samples, 1, 16, SAMPLE_RATE as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| storage(path, format!("{:?}", e)))?;
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| storage(path, format!("{:?}", e)))?;
+    Ok(sink.as_slice().to_vec())
+}
+
+// Under `path`, or sealed next to it while encryption is on.
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), AppError> {
+    if encryption::enabled() {
+        let sealed = sealed_path(path);
+        fs::write(&sealed, encryption::seal_file(bytes)?).map_err(|e| storage(&sealed, e))
+    } else {
+        fs::write(path, bytes).map_err(|e| storage(path, e))
+    }
+}
+
+fn write(id: &str, samples: &[f32], format: RecordingFormat) -> Result<(), AppError> {
+    let path = dir().join(format!("{}.{}", id, format.extension()));
+    fs::create_dir_all(dir()).map_err(|e| storage(&path, e))?;
+    let bytes = match format {
+        RecordingFormat::Wav => backends::wav(samples)?,
+        RecordingFormat::Flac => {
+            let pcm: Vec<i32> = samples
+                .iter()
+                .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i32)
+                .collect();
+            encode_flac(&path, &pcm)?
+        }
+    };
+    write_file(&path, &bytes)
+}
+
+// The utterance's audio, for backends that transcribe in the agent.
+pub fn save(transcript: &Transcript, samples: &[f32]) {
+    let config = CONFIG.lock().clone();
+    if !config.enabled || !transcript.is_final || samples.is_empty() {
+        return;
+    }
+    if let Err(e) = write(&transcript.id, samples, config.format) {
+        tracing::warn!(target: "recordings", "{}", e);
+    }
+}
+
+fn read_wav(path: &Path) -> Result<Vec<f32>, AppError> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| storage(path, e))?;
+    reader
+        .samples::<i16>()
+        .map(|sample| sample.map(|s| s as f32 / i16::MAX as f32))
+        .collect::<Result<_, _>>()
+        .map_err(|e| storage(path, e))
+}
+
+// Takes over a chunk the daemon staged. Only files in the staging folder are
+// touched, whatever path the daemon reports.
+pub fn adopt(id: &str, staged: &Path) {
+    if staged.parent() != Some(staging().as_path()) {
+        return;
+    }
+    let config = CONFIG.lock().clone();
+    let result = if !config.enabled {
+        Ok(())
+    } else if config.format == RecordingFormat::Wav && !encryption::enabled() {
+        let path = dir().join(format!("{}.wav", id));
+        fs::rename(staged, &path).map_err(|e| storage(&path, e))
+    } else if config.format == RecordingFormat::Wav {
+        let path = dir().join(format!("{}.wav", id));
+        fs::read(staged)
+            .map_err(|e| storage(staged, e))
+            .and_then(|bytes| write_file(&path, &bytes))
+    } else {
+        read_wav(staged).and_then(|samples| write(id, &samples, config.format))
+    };
+    if let Err(e) = result {
+        tracing::warn!(target: "recordings", "{}", e);
+    }
+    let _ = fs::remove_file(staged);
+}
+
+// Ids are uuids; anything else cannot name a recording.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+pub fn path_for(id: &str) -> Option<PathBuf> {
+    if !valid_id(id) {
+        return None;
+    }
+    [RecordingFormat::Wav, RecordingFormat::Flac]
+        .iter()
+        .map(|format| dir().join(format!("{}.{}", id, format.extension())))
+        .flat_map(|path| [sealed_path(&path), path])
+        .find(|path| path.is_file())
+}
+
+// A sealed recording is decrypted into a copy that the next cleanup removes.
+fn playable(path: PathBuf) -> Result<PathBuf, AppError> {
+    if !is_sealed(&path) {
+        return Ok(path);
+    }
+    let name = match path.file_stem() {
+        Some(name) => name.to_owned(),
+        None => return Ok(path),
+    };
+    let copy = playback().join(name);
+    let sealed = fs::read(&path).map_err(|e| storage(&path, e))?;
+    fs::create_dir_all(playback()).map_err(|e| storage(&copy, e))?;
+    fs::write(&copy, encryption::open_file(&sealed)?).map_err(|e| storage(&copy, e))?;
+    Ok(copy)
+}
+
+pub fn delete(id: &str) {
+    if let Some(path) = path_for(id) {
+        if let Some(name) = path.file_stem().filter(|_| is_sealed(&path)) {
+            let _ = fs::remove_file(playback().join(name));
+        }
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn clear() {
+    let _ = fs::remove_dir_all(dir());
+}
+
+// Oldest first.
+fn recordings() -> Vec<(PathBuf, SystemTime, u64)> {
+    let mut files: Vec<_> = fs::read_dir(dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let metadata = entry.metadata().ok()?;
+                    if !metadata.is_file() {
+                        return None;
+                    }
+                    Some((entry.path(), metadata.modified().ok()?, metadata.len()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|(_, modified, _)| *modified);
+    files
+}
+
+fn older_than(modified: SystemTime, age: Duration) -> bool {
+    SystemTime::now()
+        .duration_since(modified)
+        .map_or(false, |elapsed| elapsed > age)
+}
+
+fn remove_stale(dir: &Path, max_age: Duration) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let stale = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map_or(false, |modified| older_than(modified, max_age));
+            if stale {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+pub fn prune() {
+    let config = CONFIG.lock().clone();
+    remove_stale(&staging(), STAGING_MAX_AGE);
+    remove_stale(&playback(), PLAYBACK_MAX_AGE);
+    let mut files = recordings();
+    if let Some(days) = config.max_age_days {
+        let max_age = DAY * days;
+        files.retain(|(path, modified, _)| {
+            let expired = older_than(*modified, max_age);
+            if expired {
+                let _ = fs::remove_file(path);
+            }
+            !expired
+        });
+    }
+    if let Some(max_mb) = config.max_size_mb {
+        let max_bytes = max_mb * 1024 * 1024;
+        let mut total: u64 = files.iter().map(|(_, _, len)| len).sum();
+        for (path, _, len) in &files {
+            if total <= max_bytes {
+                break;
+            }
+            if fs::remove_file(path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+// Seals or opens every recording not yet in the wanted form. A rewritten
+// file's age limit starts again from the migration.
+pub fn migrate(encrypt: bool) -> Result<usize, AppError> {
+    let _ = fs::remove_dir_all(playback());
+    let mut count = 0;
+    for (path, _, _) in recordings() {
+        if is_sealed(&path) == encrypt {
+            continue;
+        }
+        let bytes = fs::read(&path).map_err(|e| storage(&path, e))?;
+        let (target, converted) = if encrypt {
+            if encryption::is_sealed_file(&bytes) {
+                continue;
+            }
+            (sealed_path(&path), encryption::seal_file(&bytes)?)
+        } else {
+            (path.with_extension(""), encryption::open_file(&bytes)?)
+        };
+        fs::write(&target, converted).map_err(|e| storage(&target, e))?;
+        fs::remove_file(&path).map_err(|e| storage(&path, e))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+pub fn spawn_cleanup() {
+    thread::spawn(|| loop {
+        prune();
+        thread::sleep(CLEANUP_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_recording_config() -> RecordingConfig {
+    CONFIG.lock().clone()
+}
+
+// A running daemon starts staging audio from its next start.
+#[tauri::command]
+pub fn set_recording_config(config: RecordingConfig) -> CommandResult<()> {
+    if config.max_size_mb == Some(0) || config.max_age_days == Some(0) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Recording limits must be positive; leave one out for no limit",
+        ));
+    }
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    thread::spawn(prune);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_audio_for_transcript(id: String) -> CommandResult<String> {
+    let path = path_for(&id).ok_or_else(|| {
+        AppError::new(
+            ErrorCode::NotFound,
+            format!("No recording for transcript {}", id),
+        )
+    })?;
+    Ok(playable(path)?.display().to_string())
+}
@@ -0,0 +1,394 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::{Transcript, Word};
+
+const STORE_FILE: &str = "redaction.json";
+// Raw texts kept for the destinations that do not redact; far more than are
+// ever in flight on the bus.
+const RAW_CACHE: usize = 64;
+const EMAIL: &str = r"(?i)\b[A-Z0-9._%+-]+@[A-Z0-9-]+(?:\.[A-Z0-9-]+)*\.[A-Z]{2,}\b";
+const CARD_NUMBER: &str = r"\b\d(?:[ -]?\d){12,18}\b";
+const PHONE: &str = r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]?\d{3}[ .-]?\d{4}\b";
+const PROFANITY: &[&str] = &[
+    "arse",
+    "arsehole",
+    "ass",
+    "asshole",
+    "bastard",
+    "bitch",
+    "bollocks",
+    "bullshit",
+    "crap",
+    "cunt",
+    "damn",
+    "dick",
+    "dickhead",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fucking",
+    "goddamn",
+    "motherfucker",
+    "piss",
+    "pissed",
+    "prick",
+    "shit",
+    "shitty",
+    "twat",
+    "wanker",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionKind {
+    // The built-in word list and `words`; a match keeps its first letter.
+    Profanity,
+    Email,
+    // 13 to 19 digits that pass the Luhn check, with or without separators.
+    CreditCard,
+    // Ten digits, optionally after a `+` country code.
+    Phone,
+    // `pattern`, a regular expression.
+    Custom,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRule {
+    pub kind: RedactionKind,
+    pub enabled: bool,
+    #[serde(default)]
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    // What a match becomes; each kind has its own default.
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+// Which destinations get the redacted text; the others get what was said.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionDestinations {
+    // Typed into the focused app.
+    pub inject: bool,
+    // Written to history.
+    pub store: bool,
+    // Events to the UI, the APIs, webhooks, MQTT and hooks.
+    pub broadcast: bool,
+}
+
+impl Default for RedactionDestinations {
+    fn default() -> Self {
+        Self {
+            inject: true,
+            store: true,
+            broadcast: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    Inject,
+    Store,
+    Broadcast,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    // Run in this order.
+    pub rules: Vec<RedactionRule>,
+    pub destinations: RedactionDestinations,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        let rules = [
+            RedactionKind::CreditCard,
+            RedactionKind::Email,
+            RedactionKind::Phone,
+            RedactionKind::Profanity,
+        ]
+        .iter()
+        .map(|&kind| RedactionRule {
+            kind,
+            enabled: false,
+            words: Vec::new(),
+            pattern: None,
+            replacement: None,
+        })
+        .collect();
+        Self {
+            rules,
+            destinations: RedactionDestinations::default(),
+        }
+    }
+}
+
+pub(crate) struct Compiled {
+    kind: RedactionKind,
+    regex: Regex,
+    replacement: Option<String>,
+}
+
+struct Redactor {
+    config: RedactionConfig,
+    compiled: Vec<Compiled>,
+}
+
+// A saved rule that no longer compiles is skipped rather than failing the
+// whole filter.
+static REDACTOR: Lazy<Mutex<Redactor>> = Lazy::new(|| {
+    let config: RedactionConfig = store::load_json(STORE_FILE);
+    let compiled = config
+        .rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| match compile(rule) {
+            Ok(compiled) => Some(compiled),
+            Err(e) => {
+                tracing::warn!(target: "redaction", "Skipping redaction rule: {}", e.message);
+                None
+            }
+        })
+        .collect();
+    Mutex::new(Redactor { config, compiled })
+});
+// Newest last.
+static RAW: Lazy<Mutex<VecDeque<Raw>>> = Lazy::new(Mutex::default);
+
+struct Raw {
+    id: String,
+    text: String,
+    words: Vec<Word>,
+}
+
+fn invalid(e: regex::Error) -> AppError {
+    AppError::new(
+        ErrorCode::InvalidInput,
+        format!("Invalid redaction pattern: {}", e),
+    )
+}
+
+fn words_pattern(words: &[String]) -> String {
+    let words: Vec<String> = PROFANITY
+        .iter()
+        .map(|word| word.to_string())
+        .chain(words.iter().map(|word| word.trim().to_lowercase()))
+        .filter(|word| !word.is_empty())
+        .map(|word| regex::escape(&word))
+        .collect();
+    format!(r"(?i)\b(?:{})\b", words.join("|"))
+}
+
+pub(crate) fn compile(rule: &RedactionRule) -> Result<Compiled, AppError> {
+    let pattern = match rule.kind {
+        RedactionKind::Profanity => words_pattern(&rule.words),
+        RedactionKind::Email => EMAIL.to_string(),
+        RedactionKind::CreditCard => CARD_NUMBER.to_string(),
+        RedactionKind::Phone => PHONE.to_string(),
+        RedactionKind::Custom => match rule.pattern.as_deref().map(str::trim) {
+            Some(pattern) if !pattern.is_empty() => pattern.to_string(),
+            _ => {
+                return Err(AppError::new(
+                    ErrorCode::InvalidInput,
+                    "A custom redaction rule needs a pattern",
+                ))
+            }
+        },
+    };
+    Ok(Compiled {
+        kind: rule.kind,
+        regex: Regex::new(&pattern).map_err(invalid)?,
+        replacement: rule.replacement.clone(),
+    })
+}
+
+// Rules out most runs of digits that only look like a card number.
+fn luhn(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+// The pattern can take in a number said next to a card, so each run of whole
+// digit groups with 13 to 19 digits is tried, longest first.
+fn card_in(found: &str) -> Option<(usize, usize)> {
+    let mut groups = Vec::new();
+    let mut offset = 0;
+    for group in found.split(|c| c == ' ' || c == '-') {
+        groups.push((offset, offset + group.len()));
+        offset += group.len() + 1;
+    }
+    let digits =
+        |(from, to): (usize, usize)| found[from..to].chars().filter(char::is_ascii_digit).count();
+    let mut runs: Vec<(usize, usize)> = (0..groups.len())
+        .flat_map(|first| {
+            let from = groups[first].0;
+            groups[first..].iter().map(move |&(_, to)| (from, to))
+        })
+        .filter(|&run| (13..=19).contains(&digits(run)))
+        .collect();
+    runs.sort_by_key(|&run| Reverse(digits(run)));
+    runs.into_iter().find(|&(from, to)| luhn(&found[from..to]))
+}
+
+fn mask(rule: &Compiled, found: &str) -> String {
+    if let Some(replacement) = &rule.replacement {
+        return replacement.clone();
+    }
+    match rule.kind {
+        RedactionKind::Profanity => {
+            let mut chars = found.chars();
+            let first: String = chars.next().into_iter().collect();
+            format!("{}{}", first, "*".repeat(chars.count()))
+        }
+        RedactionKind::Email => "[email]".to_string(),
+        RedactionKind::CreditCard => "[card number]".to_string(),
+        RedactionKind::Phone => "[phone number]".to_string(),
+        RedactionKind::Custom => "[redacted]".to_string(),
+    }
+}
+
+pub(crate) fn apply(rule: &Compiled, text: &str) -> String {
+    rule.regex
+        .replace_all(text, |captures: &Captures| {
+            let found = &captures[0];
+            if rule.kind != RedactionKind::CreditCard {
+                return mask(rule, found);
+            }
+            match card_in(found) {
+                Some((from, to)) => format!(
+                    "{}{}{}",
+                    &found[..from],
+                    mask(rule, &found[from..to]),
+                    &found[to..]
+                ),
+                None => found.to_string(),
+            }
+        })
+        .into_owned()
+}
+
+pub fn redact(text: &str) -> String {
+    let redactor = REDACTOR.lock();
+    redactor
+        .compiled
+        .iter()
+        .fold(text.to_string(), |text, rule| apply(rule, &text))
+}
+
+pub fn redacts(destination: Destination) -> bool {
+    let redactor = REDACTOR.lock();
+    let destinations = redactor.config.destinations;
+    !redactor.compiled.is_empty()
+        && match destination {
+            Destination::Inject => destinations.inject,
+            Destination::Store => destinations.store,
+            Destination::Broadcast => destinations.broadcast,
+        }
+}
+
+// Gives the transcript its broadcast text, and keeps what was said for the
+// destinations that get it, which look it up with `text_for`.
+pub fn run(mut transcript: Transcript) -> Transcript {
+    if REDACTOR.lock().compiled.is_empty() {
+        return transcript;
+    }
+    if transcript.is_final {
+        let mut raw = RAW.lock();
+        if raw.len() == RAW_CACHE {
+            raw.pop_front();
+        }
+        raw.push_back(Raw {
+            id: transcript.id.clone(),
+            text: transcript.text.clone(),
+            words: transcript.words.clone(),
+        });
+    }
+    if redacts(Destination::Broadcast) {
+        let text = redact(&transcript.text);
+        // The words would give away what was masked.
+        if text != transcript.text {
+            transcript.words.clear();
+        }
+        transcript.text = text;
+        if let Some(translation) = transcript.translation.as_mut() {
+            translation.text = redact(&translation.text);
+        }
+    }
+    transcript
+}
+
+fn raw(transcript: &Transcript) -> Option<(String, Vec<Word>)> {
+    RAW.lock()
+        .iter()
+        .rev()
+        .find(|raw| raw.id == transcript.id)
+        .map(|raw| (raw.text.clone(), raw.words.clone()))
+}
+
+pub fn text_for(destination: Destination, transcript: &Transcript) -> String {
+    match raw(transcript) {
+        Some((raw, _)) if redacts(destination) => redact(&raw),
+        Some((raw, _)) => raw,
+        None => transcript.text.clone(),
+    }
+}
+
+// None of a transcript's words when redaction changed its text.
+pub fn words_for(destination: Destination, transcript: &Transcript) -> Vec<Word> {
+    match raw(transcript) {
+        Some((raw, _)) if redacts(destination) && redact(&raw) != raw => Vec::new(),
+        Some((_, words)) => words,
+        None => transcript.words.clone(),
+    }
+}
+
+fn save(config: RedactionConfig) -> CommandResult<()> {
+    let compiled = config
+        .rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .map(compile)
+        .collect::<Result<Vec<_>, _>>()?;
+    store::save_json(STORE_FILE, &config)?;
+    *REDACTOR.lock() = Redactor { config, compiled };
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_redaction_config() -> RedactionConfig {
+    REDACTOR.lock().config.clone()
+}
+
+#[tauri::command]
+pub fn set_redaction_rules(
+    rules: Vec<RedactionRule>,
+    destinations: Option<RedactionDestinations>,
+) -> CommandResult<()> {
+    let mut config = REDACTOR.lock().config.clone();
+    config.rules = rules;
+    if let Some(destinations) = destinations {
+        config.destinations = destinations;
+    }
+    save(config)
+}
@@ -0,0 +1,83 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::{Html, Response};
+use axum::routing::get;
+use axum::Router;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::AppState;
+
+const PAGE: &str = include_str!("../assets/remote_mic.html");
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/mic", get(page))
+        .route("/mic/ws", get(upgrade))
+}
+
+async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(move |socket| ingest(socket, state))
+}
+
+async fn send_json(socket: &mut WebSocket, value: serde_json::Value) -> bool {
+    socket.send(Message::Text(value.to_string())).await.is_ok()
+}
+
+async fn ingest(mut socket: WebSocket, state: AppState) {
+    let ready = match daemon::input_source(&state) {
+        Some(InputSource::Remote) => Ok(()),
+        Some(_) => Err("The agent is listening on another input source".to_string()),
+        // Waits for the daemon to load its model, so off the socket's task.
+        None => {
+            let starting = state.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                backends::start(&starting, InputSource::Remote)
+            })
+            .await
+            .map_err(|e| format!("Start task failed: {}", e))
+            .and_then(|result| result.map(|_| ()).map_err(String::from))
+        }
+    };
+    if let Err(message) = ready {
+        send_json(&mut socket, json!({ "type": "error", "message": message })).await;
+        return;
+    }
+    send_json(
+        &mut socket,
+        json!({ "type": "ready", "sample_rate": 16000 }),
+    )
+    .await;
+
+    let mut events = state.bus.subscribe();
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Binary(pcm))) => {
+                    if let Err(message) = daemon::push_audio(&state, &pcm) {
+                        send_json(&mut socket, json!({ "type": "error", "message": message })).await;
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => {
+                    if !send_json(&mut socket, json!({ "type": "transcript", "text": t.text })).await {
+                        break;
+                    }
+                }
+                Ok(AgentEvent::ListeningChanged(false)) | Err(RecvError::Closed) => break,
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+            },
+        }
+    }
+}
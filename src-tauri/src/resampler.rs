@@ -0,0 +1,170 @@
+use rubato::{FftFixedIn, Resampler};
+use serde::Serialize;
+
+use crate::audio::{self, CaptureInfo, SAMPLE_RATE};
+use crate::backends::{self, BackendKind};
+use crate::error::{AppError, ErrorCode};
+use crate::AppState;
+
+// Input frames per resampler call; 20 ms at 48 kHz.
+const CHUNK_FRAMES: usize = 960;
+const SUB_CHUNKS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl AudioFormat {
+    // What capture produces, and what denoising, the speech gate, the
+    // lookback buffer and the local daemon work in.
+    pub const PIPELINE: AudioFormat = AudioFormat::mono(SAMPLE_RATE);
+
+    pub const fn mono(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            channels: 1,
+        }
+    }
+}
+
+// Mono audio in at one rate, interleaved out in another format. Buffers are
+// kept between calls, so it can run on the audio thread.
+pub struct Converter {
+    resampler: Option<FftFixedIn<f32>>,
+    ratio: f64,
+    channels: usize,
+    pending: Vec<f32>,
+    output: Vec<Vec<f32>>,
+}
+
+fn interleave(samples: &[f32], channels: usize, out: &mut Vec<f32>) {
+    if channels == 1 {
+        out.extend_from_slice(samples);
+        return;
+    }
+    for &sample in samples {
+        out.extend(std::iter::repeat(sample).take(channels));
+    }
+}
+
+impl Converter {
+    pub fn new(input_rate: u32, output: AudioFormat) -> Result<Self, AppError> {
+        let resampler = if input_rate == output.sample_rate {
+            None
+        } else {
+            Some(
+                FftFixedIn::new(
+                    input_rate as usize,
+                    output.sample_rate as usize,
+                    CHUNK_FRAMES,
+                    SUB_CHUNKS,
+                    1,
+                )
+                .map_err(|e| {
+                    AppError::new(
+                        ErrorCode::Unsupported,
+                        format!(
+                            "Cannot resample {} Hz to {} Hz: {}",
+                            input_rate, output.sample_rate, e
+                        ),
+                    )
+                })?,
+            )
+        };
+        let output_buffer = resampler
+            .as_ref()
+            .map_or_else(Vec::new, |resampler| resampler.output_buffer_allocate(true));
+        Ok(Self {
+            resampler,
+            ratio: output.sample_rate as f64 / input_rate as f64,
+            channels: output.channels.max(1) as usize,
+            pending: Vec::with_capacity(CHUNK_FRAMES * 2),
+            output: output_buffer,
+        })
+    }
+
+    // Appends what is converted so far; up to a chunk is held back for the
+    // next call.
+    pub fn push(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        let resampler = match self.resampler.as_mut() {
+            Some(resampler) => resampler,
+            None => return interleave(samples, self.channels, out),
+        };
+        self.pending.extend_from_slice(samples);
+        let mut used = 0;
+        loop {
+            let frames = resampler.input_frames_next();
+            if self.pending.len() - used < frames {
+                break;
+            }
+            let chunk = [&self.pending[used..used + frames]];
+            if let Ok((_, written)) = resampler.process_into_buffer(&chunk, &mut self.output, None)
+            {
+                interleave(&self.output[0][..written], self.channels, out);
+            }
+            used += frames;
+        }
+        self.pending.drain(..used);
+    }
+
+    // Flushes the held-back audio with silence, then trims the filter's delay
+    // from `out`, which holds everything `push` gave, so a whole recording
+    // keeps its length and timing.
+    fn finish(mut self, input_len: usize, out: &mut Vec<f32>) {
+        let resampler = match self.resampler.as_mut() {
+            Some(resampler) => resampler,
+            None => return,
+        };
+        let delay = resampler.output_delay() * self.channels;
+        let wanted = (input_len as f64 * self.ratio).round() as usize * self.channels;
+        let mut pending = Some([std::mem::take(&mut self.pending)]);
+        while out.len() < delay + wanted {
+            let chunk = pending.take();
+            let chunk = chunk.as_ref().map(|chunk| &chunk[..]);
+            match resampler.process_partial_into_buffer(chunk, &mut self.output, None) {
+                Ok((_, 0)) | Err(_) => break,
+                Ok((_, written)) => interleave(&self.output[0][..written], self.channels, out),
+            }
+        }
+        out.drain(..delay.min(out.len()));
+        out.truncate(wanted);
+    }
+}
+
+// A whole recording to the pipeline's 16 kHz mono.
+pub fn resample(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let mut converter = match Converter::new(sample_rate, AudioFormat::PIPELINE) {
+        Ok(converter) => converter,
+        Err(error) => {
+            tracing::warn!(target: "resampler", "{}", error);
+            return samples.to_vec();
+        }
+    };
+    let mut out = Vec::with_capacity(samples.len() * SAMPLE_RATE as usize / sample_rate as usize);
+    converter.push(samples, &mut out);
+    converter.finish(samples.len(), &mut out);
+    out
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineFormat {
+    // The device's own format; None while nothing is captured.
+    pub capture: Option<CaptureInfo>,
+    pub pipeline: AudioFormat,
+    pub backend: BackendKind,
+    // What the backend is sent, resampled from the pipeline when it differs.
+    pub backend_format: AudioFormat,
+}
+
+#[tauri::command]
+pub fn get_pipeline_format(state: tauri::State<'_, AppState>) -> PipelineFormat {
+    let backend = backends::selected();
+    PipelineFormat {
+        capture: audio::capture_info(state.inner()),
+        pipeline: AudioFormat::PIPELINE,
+        backend,
+        backend_format: backend.format(),
+    }
+}
@@ -0,0 +1,115 @@
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+use crate::audio;
+use crate::autotune;
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::AppState;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineProcess {
+    // The Python daemon.
+    Daemon,
+    // The agent itself, for in-process Whisper and other capture that feeds
+    // a pipeline without the daemon.
+    Agent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceUsage {
+    pub process: PipelineProcess,
+    pub pid: u32,
+    pub model: String,
+    // 100 per fully busy core, as `top` shows it.
+    pub cpu_percent: f32,
+    pub rss_mb: u64,
+    // Only NVIDIA cards report memory per process.
+    pub gpu_memory_mb: Option<u64>,
+}
+
+static LATEST: Lazy<Mutex<Option<ResourceUsage>>> = Lazy::new(|| Mutex::new(None));
+
+fn pipeline(state: &AppState) -> Option<(PipelineProcess, u32)> {
+    if let Some(child) = state.stt_process.lock().as_ref() {
+        return Some((PipelineProcess::Daemon, child.id()));
+    }
+    if !audio::is_idle(state) {
+        return Some((PipelineProcess::Agent, std::process::id()));
+    }
+    None
+}
+
+fn gpu_memory_mb(pid: u32) -> Option<u64> {
+    autotune::hardware().gpu?;
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-compute-apps=pid,used_memory",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // A process using several GPUs is listed once per GPU.
+    let used: u64 = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (app, used) = line.split_once(',')?;
+            if app.trim().parse::<u32>().ok()? != pid {
+                return None;
+            }
+            used.trim().parse::<u64>().ok()
+        })
+        .sum();
+    Some(used)
+}
+
+fn sample(state: &AppState, system: &mut System) -> Option<ResourceUsage> {
+    let (process, pid) = pipeline(state)?;
+    let sys_pid = Pid::from_u32(pid);
+    if !system.refresh_process(sys_pid) {
+        return None;
+    }
+    let info = system.process(sys_pid)?;
+    Some(ResourceUsage {
+        process,
+        pid,
+        model: daemon::current_model(state),
+        cpu_percent: info.cpu_usage(),
+        rss_mb: info.memory() / (1024 * 1024),
+        gpu_memory_mb: gpu_memory_mb(pid),
+    })
+}
+
+// CPU usage is measured between two refreshes of the same process, so the
+// first sample after a daemon starts reads 0.
+pub fn spawn_monitor(state: AppState) {
+    thread::spawn(move || {
+        let mut system = System::new();
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            let usage = sample(&state, &mut system);
+            *LATEST.lock() = usage.clone();
+            if let Some(usage) = usage {
+                state.bus.publish(AgentEvent::SttMetrics(usage));
+            }
+        }
+    });
+}
+
+// The last sample, or nothing while no pipeline is running.
+#[tauri::command]
+pub fn get_resource_usage() -> Option<ResourceUsage> {
+    LATEST.lock().clone()
+}
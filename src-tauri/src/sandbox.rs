@@ -0,0 +1,428 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::Command;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandResult;
+use crate::store;
+
+const STORE_FILE: &str = "sandbox.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessKind {
+    Daemon,
+    Hook,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxPolicy {
+    pub enabled: bool,
+    pub network: bool,
+    // Writable on top of the temp dir (and, for the daemon, the data and
+    // model cache dirs); everything else is read-only.
+    pub writable_dirs: Vec<PathBuf>,
+    pub memory_mb: Option<u64>,
+    // Of one core, so 200 allows two full cores.
+    pub cpu_percent: Option<u32>,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            network: true,
+            writable_dirs: Vec::new(),
+            memory_mb: None,
+            cpu_percent: None,
+        }
+    }
+}
+
+// Hooks can override `hooks` individually.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    pub daemon: SandboxPolicy,
+    pub hooks: SandboxPolicy,
+}
+
+// What this platform can actually enforce.
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxSupport {
+    pub filesystem: bool,
+    pub network: bool,
+    pub memory: bool,
+    pub cpu: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxStatus {
+    pub config: SandboxConfig,
+    pub support: SandboxSupport,
+}
+
+static CONFIG: Lazy<Mutex<SandboxConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+pub fn policy(kind: ProcessKind) -> SandboxPolicy {
+    let config = CONFIG.lock();
+    match kind {
+        ProcessKind::Daemon => config.daemon.clone(),
+        ProcessKind::Hook => config.hooks.clone(),
+    }
+}
+
+fn writable_dirs(kind: ProcessKind, policy: &SandboxPolicy) -> Vec<PathBuf> {
+    let mut dirs = vec![std::env::temp_dir()];
+    if kind == ProcessKind::Daemon {
+        dirs.push(store::data_dir());
+        dirs.extend(dirs::cache_dir());
+    }
+    dirs.extend(policy.writable_dirs.iter().cloned());
+    // Bind mounts and sandbox profiles need real paths.
+    dirs.into_iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::ffi::{OsStr, OsString};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use super::{SandboxPolicy, SandboxSupport};
+
+    fn on_path(program: &str) -> bool {
+        std::env::var_os("PATH").map_or(false, |path| {
+            std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+        })
+    }
+
+    pub fn support() -> SandboxSupport {
+        let bwrap = on_path("bwrap");
+        let systemd = on_path("systemd-run");
+        SandboxSupport {
+            filesystem: bwrap,
+            network: bwrap,
+            memory: systemd,
+            cpu: systemd,
+        }
+    }
+
+    // systemd-run puts the process in its own cgroup scope for the limits,
+    // then bubblewrap gives it a read-only view of the filesystem. /dev stays
+    // fully bound so audio devices keep working.
+    pub fn command(writable: &[PathBuf], policy: &SandboxPolicy, program: &OsStr) -> Command {
+        let support = support();
+        let mut argv: Vec<OsString> = Vec::new();
+        if policy.memory_mb.is_some() || policy.cpu_percent.is_some() {
+            if support.memory {
+                argv.extend(
+                    ["systemd-run", "--user", "--scope", "--quiet", "--collect"]
+                        .iter()
+                        .map(OsString::from),
+                );
+                if let Some(mb) = policy.memory_mb {
+                    argv.push("-p".into());
+                    argv.push(format!("MemoryMax={}M", mb).into());
+                }
+                if let Some(percent) = policy.cpu_percent {
+                    argv.push("-p".into());
+                    argv.push(format!("CPUQuota={}%", percent).into());
+                }
+            } else {
+                tracing::warn!(target: "sandbox", "systemd-run not found; resource limits are not applied");
+            }
+        }
+        if support.filesystem {
+            argv.extend(
+                [
+                    "bwrap",
+                    "--ro-bind",
+                    "/",
+                    "/",
+                    "--dev-bind",
+                    "/dev",
+                    "/dev",
+                    "--proc",
+                    "/proc",
+                    "--die-with-parent",
+                ]
+                .iter()
+                .map(OsString::from),
+            );
+            for dir in writable {
+                argv.push("--bind".into());
+                argv.push(dir.into());
+                argv.push(dir.into());
+            }
+            if !policy.network {
+                argv.push("--unshare-net".into());
+            }
+            argv.push("--".into());
+        } else {
+            tracing::warn!(target: "sandbox", "bwrap not found; filesystem and network are not restricted");
+        }
+        argv.push(program.into());
+
+        let mut command = Command::new(&argv[0]);
+        command.args(&argv[1..]);
+        command
+    }
+
+    pub fn confine(_policy: &SandboxPolicy, _pid: u32) {}
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::OsStr;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use super::{SandboxPolicy, SandboxSupport};
+
+    pub fn support() -> SandboxSupport {
+        SandboxSupport {
+            filesystem: true,
+            network: true,
+            memory: false,
+            cpu: false,
+        }
+    }
+
+    fn quote(path: &Path) -> String {
+        path.to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    }
+
+    // Seatbelt profile: deny writes outside the allowed dirs and, optionally,
+    // IP traffic. Local sockets stay open for CoreAudio and the agent.
+    fn profile(writable: &[PathBuf], policy: &SandboxPolicy) -> String {
+        let mut profile = String::from("(version 1)\n(allow default)\n(deny file-write*)\n");
+        profile.push_str("(allow file-write* (literal \"/dev/null\")");
+        for dir in writable {
+            profile.push_str(&format!(" (subpath \"{}\")", quote(dir)));
+        }
+        profile.push_str(")\n");
+        if !policy.network {
+            profile.push_str(
+                "(deny network-outbound (remote ip))\n(deny network-inbound (local ip))\n",
+            );
+        }
+        profile
+    }
+
+    pub fn command(writable: &[PathBuf], policy: &SandboxPolicy, program: &OsStr) -> Command {
+        if policy.memory_mb.is_some() || policy.cpu_percent.is_some() {
+            tracing::warn!(target: "sandbox", "Resource limits are not supported on macOS");
+        }
+        let mut command = Command::new("sandbox-exec");
+        command
+            .arg("-p")
+            .arg(profile(writable, policy))
+            .arg(program);
+        command
+    }
+
+    pub fn confine(_policy: &SandboxPolicy, _pid: u32) {}
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::{c_void, OsStr};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use super::{SandboxPolicy, SandboxSupport};
+
+    const PROCESS_TERMINATE: u32 = 0x0001;
+    const PROCESS_SET_QUOTA: u32 = 0x0100;
+    const JOB_OBJECT_LIMIT_PROCESS_MEMORY: u32 = 0x0100;
+    const EXTENDED_LIMIT_INFORMATION: i32 = 9;
+    const CPU_RATE_CONTROL_INFORMATION: i32 = 15;
+    const CPU_RATE_CONTROL_ENABLE: u32 = 0x1;
+    const CPU_RATE_CONTROL_HARD_CAP: u32 = 0x4;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct BasicLimitInformation {
+        per_process_user_time_limit: i64,
+        per_job_user_time_limit: i64,
+        limit_flags: u32,
+        minimum_working_set_size: usize,
+        maximum_working_set_size: usize,
+        active_process_limit: u32,
+        affinity: usize,
+        priority_class: u32,
+        scheduling_class: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct IoCounters {
+        read_operation_count: u64,
+        write_operation_count: u64,
+        other_operation_count: u64,
+        read_transfer_count: u64,
+        write_transfer_count: u64,
+        other_transfer_count: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct ExtendedLimitInformation {
+        basic: BasicLimitInformation,
+        io: IoCounters,
+        process_memory_limit: usize,
+        job_memory_limit: usize,
+        peak_process_memory_used: usize,
+        peak_job_memory_used: usize,
+    }
+
+    #[repr(C)]
+    struct CpuRateControlInformation {
+        control_flags: u32,
+        cpu_rate: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateJobObjectW(attributes: *mut c_void, name: *const u16) -> *mut c_void;
+        fn SetInformationJobObject(
+            job: *mut c_void,
+            class: i32,
+            info: *mut c_void,
+            length: u32,
+        ) -> i32;
+        fn AssignProcessToJobObject(job: *mut c_void, process: *mut c_void) -> i32;
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> *mut c_void;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    pub fn support() -> SandboxSupport {
+        SandboxSupport {
+            filesystem: false,
+            network: false,
+            memory: true,
+            cpu: true,
+        }
+    }
+
+    pub fn command(_writable: &[PathBuf], policy: &SandboxPolicy, program: &OsStr) -> Command {
+        if !policy.network || !policy.writable_dirs.is_empty() {
+            tracing::warn!(target: "sandbox", "Filesystem and network restrictions are not supported on Windows");
+        }
+        Command::new(program)
+    }
+
+    // Job objects can only take a process once it exists, so limits apply a
+    // moment after launch. The job lives on until the process exits.
+    pub fn confine(policy: &SandboxPolicy, pid: u32) {
+        if policy.memory_mb.is_none() && policy.cpu_percent.is_none() {
+            return;
+        }
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null_mut(), std::ptr::null());
+            if job.is_null() {
+                tracing::warn!(target: "sandbox", "Failed to create a job object");
+                return;
+            }
+            if let Some(mb) = policy.memory_mb {
+                let mut limits = ExtendedLimitInformation::default();
+                limits.basic.limit_flags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                limits.process_memory_limit = (mb * 1024 * 1024) as usize;
+                SetInformationJobObject(
+                    job,
+                    EXTENDED_LIMIT_INFORMATION,
+                    &mut limits as *mut _ as *mut c_void,
+                    std::mem::size_of::<ExtendedLimitInformation>() as u32,
+                );
+            }
+            if let Some(percent) = policy.cpu_percent {
+                // The job rate is in hundredths of a percent of the whole
+                // machine.
+                let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+                let mut rate = CpuRateControlInformation {
+                    control_flags: CPU_RATE_CONTROL_ENABLE | CPU_RATE_CONTROL_HARD_CAP,
+                    cpu_rate: (percent * 100 / cores).clamp(1, 10_000),
+                };
+                SetInformationJobObject(
+                    job,
+                    CPU_RATE_CONTROL_INFORMATION,
+                    &mut rate as *mut _ as *mut c_void,
+                    std::mem::size_of::<CpuRateControlInformation>() as u32,
+                );
+            }
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+            if process.is_null() || AssignProcessToJobObject(job, process) == 0 {
+                tracing::warn!(target: "sandbox", pid, "Failed to apply resource limits");
+            }
+            if !process.is_null() {
+                CloseHandle(process);
+            }
+            CloseHandle(job);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use std::ffi::OsStr;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use super::{SandboxPolicy, SandboxSupport};
+
+    pub fn support() -> SandboxSupport {
+        SandboxSupport {
+            filesystem: false,
+            network: false,
+            memory: false,
+            cpu: false,
+        }
+    }
+
+    pub fn command(_writable: &[PathBuf], _policy: &SandboxPolicy, program: &OsStr) -> Command {
+        tracing::warn!(target: "sandbox", "Sandboxing is not supported on this platform");
+        Command::new(program)
+    }
+
+    pub fn confine(_policy: &SandboxPolicy, _pid: u32) {}
+}
+
+// Callers add the program's own arguments to the returned command.
+pub fn command(kind: ProcessKind, policy: &SandboxPolicy, program: impl AsRef<OsStr>) -> Command {
+    if !policy.enabled {
+        return Command::new(program);
+    }
+    platform::command(&writable_dirs(kind, policy), policy, program.as_ref())
+}
+
+// Applies limits that need the running process; call right after spawning.
+pub fn confine(policy: &SandboxPolicy, pid: u32) {
+    if policy.enabled {
+        platform::confine(policy, pid);
+    }
+}
+
+#[tauri::command]
+pub fn get_sandbox_status() -> SandboxStatus {
+    SandboxStatus {
+        config: CONFIG.lock().clone(),
+        support: platform::support(),
+    }
+}
+
+// Applies to processes launched from now on.
+#[tauri::command]
+pub fn set_sandbox_config(config: SandboxConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveTime};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const STORE_FILE: &str = "schedule.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const MAX_IDLE_MINUTES: u32 = 24 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    // Local "HH:MM"; an end before the start runs past midnight.
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    // The wake word and hotkeys are ignored in between.
+    pub quiet_hours: Option<QuietHours>,
+    // Listening stops after this long without speech.
+    pub idle_stop_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleStatus {
+    pub quiet: bool,
+    // Unix millis when listening stops unless speech is heard first.
+    pub idle_stop_at: Option<u64>,
+}
+
+static CONFIG: Lazy<Mutex<ScheduleConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// The last speech, or when listening started; None while stopped.
+static LAST_ACTIVITY: Lazy<Mutex<Option<Instant>>> = Lazy::new(Mutex::default);
+
+fn parse(time: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("{} is not a time such as 22:30", time),
+        )
+    })
+}
+
+fn within(hours: &QuietHours, now: NaiveTime) -> bool {
+    let (start, end) = match (parse(&hours.start), parse(&hours.end)) {
+        (Ok(start), Ok(end)) => (start, end),
+        _ => return false,
+    };
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+pub fn quiet() -> bool {
+    CONFIG
+        .lock()
+        .quiet_hours
+        .as_ref()
+        .map_or(false, |hours| within(hours, Local::now().time()))
+}
+
+fn idle_deadline() -> Option<Instant> {
+    let minutes = CONFIG.lock().idle_stop_minutes?;
+    LAST_ACTIVITY
+        .lock()
+        .map(|at| at + Duration::from_secs(minutes as u64 * 60))
+}
+
+pub fn status() -> ScheduleStatus {
+    ScheduleStatus {
+        quiet: quiet(),
+        idle_stop_at: idle_deadline().map(|at| {
+            now_millis() + at.saturating_duration_since(Instant::now()).as_millis() as u64
+        }),
+    }
+}
+
+fn heard() {
+    if let Some(at) = LAST_ACTIVITY.lock().as_mut() {
+        *at = Instant::now();
+    }
+}
+
+// Partials count as speech, so a long dictation is never cut off for want
+// of a final transcript.
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::Transcript(_)) | Ok(AgentEvent::SpeechStarted) => heard(),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        if !backends::is_running(&state) {
+            *LAST_ACTIVITY.lock() = None;
+            continue;
+        }
+        LAST_ACTIVITY.lock().get_or_insert_with(Instant::now);
+        if !idle_deadline().map_or(false, |at| at <= Instant::now()) {
+            continue;
+        }
+        tracing::info!(target: "schedule", "No speech heard; stopping");
+        *LAST_ACTIVITY.lock() = None;
+        if let Err(error) = backends::stop(&state) {
+            state.bus.publish(AgentEvent::error("schedule", error));
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_schedule() -> ScheduleConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_schedule(config: ScheduleConfig) -> CommandResult<()> {
+    if let Some(hours) = &config.quiet_hours {
+        if parse(&hours.start)? == parse(&hours.end)? {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Quiet hours need different start and end times",
+            ));
+        }
+    }
+    if let Some(minutes) = config.idle_stop_minutes {
+        if minutes == 0 || minutes > MAX_IDLE_MINUTES {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Auto-stop waits 1 to {} minutes", MAX_IDLE_MINUTES),
+            ));
+        }
+    }
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
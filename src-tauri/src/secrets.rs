@@ -0,0 +1,96 @@
+use keyring::Entry;
+use serde::Serialize;
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+
+// One credential per provider under this service, in the macOS Keychain,
+// Windows Credential Manager or the Secret Service.
+const SERVICE: &str = "com.voice.mcp.agent";
+const PROVIDERS: [&str; 4] = ["openai", "deepgram", "deepl", "libretranslate"];
+// The key that encrypts history and recordings. It is not a provider, so the
+// API key commands can neither read nor delete it.
+const STORAGE_KEY: &str = "storage-encryption";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyStatus {
+    pub provider: String,
+    pub configured: bool,
+}
+
+fn failed(e: keyring::Error) -> AppError {
+    AppError::new(
+        ErrorCode::Storage,
+        format!("The system keychain is unavailable: {}", e),
+    )
+}
+
+fn entry(provider: &str) -> Result<Entry, AppError> {
+    if !PROVIDERS.contains(&provider) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Unknown API key provider {}", provider),
+        )
+        .with_context("providers", PROVIDERS.join(", ")));
+    }
+    Entry::new(SERVICE, provider).map_err(failed)
+}
+
+pub fn api_key(provider: &str) -> Result<Option<String>, AppError> {
+    match entry(provider)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(failed(e)),
+    }
+}
+
+pub fn set_api_key(provider: &str, key: &str) -> Result<(), AppError> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "The API key is empty",
+        ));
+    }
+    entry(provider)?.set_password(key).map_err(failed)
+}
+
+pub fn storage_key() -> Result<Option<String>, AppError> {
+    match Entry::new(SERVICE, STORAGE_KEY)
+        .map_err(failed)?
+        .get_password()
+    {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(failed(e)),
+    }
+}
+
+pub fn set_storage_key(key: &str) -> Result<(), AppError> {
+    Entry::new(SERVICE, STORAGE_KEY)
+        .map_err(failed)?
+        .set_password(key)
+        .map_err(failed)
+}
+
+#[tauri::command]
+pub fn store_api_key(provider: String, key: String) -> CommandResult<()> {
+    set_api_key(&provider, &key)
+}
+
+// Never returns the key itself.
+#[tauri::command]
+pub fn get_api_key_status(provider: String) -> CommandResult<ApiKeyStatus> {
+    let configured = api_key(&provider)?.is_some();
+    Ok(ApiKeyStatus {
+        provider,
+        configured,
+    })
+}
+
+#[tauri::command]
+pub fn delete_api_key(provider: String) -> CommandResult<()> {
+    match entry(&provider)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(failed(e)),
+    }
+}
@@ -0,0 +1,260 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::portal::PortalState;
+use crate::power::PowerState;
+use crate::ptt;
+use crate::AppState;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+// Safe in pipe and file names.
+pub fn user_name() -> String {
+    let name = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        .collect();
+    if name.is_empty() {
+        "user".to_string()
+    } else {
+        name
+    }
+}
+
+// Names anything two users, or two sessions of one user, could otherwise
+// collide on. Unix runtime and config dirs are already per user, and a
+// user's sessions share them so the login agent and the tray can meet;
+// Windows pipes are machine-wide, so they also carry the session.
+pub fn namespace() -> String {
+    #[cfg(windows)]
+    {
+        format!("{}-{}", user_name(), windows::session_id())
+    }
+    #[cfg(not(windows))]
+    {
+        user_name()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn session_flag(property: &str) -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = std::process::Command::new("loginctl")
+        .args(["show-session", &session, "--property", property, "--value"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "yes")
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_active() -> Option<bool> {
+    session_flag("Active")
+}
+
+// Set by screen lockers that tell logind, which the common ones do.
+#[cfg(target_os = "linux")]
+pub fn is_locked() -> Option<bool> {
+    session_flag("LockedHint")
+}
+
+// None without a session; Some(None) when the key is not set.
+#[cfg(target_os = "macos")]
+fn session_flag(key: &[u8]) -> Option<Option<bool>> {
+    use std::ffi::c_void;
+    use std::os::raw::c_char;
+
+    const UTF8_ENCODING: u32 = 0x0800_0100;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> *const c_void;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            allocator: *const c_void,
+            string: *const c_char,
+            encoding: u32,
+        ) -> *const c_void;
+        fn CFDictionaryGetValue(dictionary: *const c_void, key: *const c_void) -> *const c_void;
+        fn CFBooleanGetValue(boolean: *const c_void) -> bool;
+        fn CFRelease(object: *const c_void);
+    }
+
+    unsafe {
+        let session = CGSessionCopyCurrentDictionary();
+        if session.is_null() {
+            return None;
+        }
+        let key = CFStringCreateWithCString(
+            std::ptr::null(),
+            key.as_ptr() as *const c_char,
+            UTF8_ENCODING,
+        );
+        let value = CFDictionaryGetValue(session, key);
+        let flag = if value.is_null() {
+            None
+        } else {
+            Some(CFBooleanGetValue(value))
+        };
+        CFRelease(key);
+        CFRelease(session);
+        Some(flag)
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_active() -> Option<bool> {
+    session_flag(b"kCGSSessionOnConsoleKey\0").flatten()
+}
+
+// The key is only there while the screen is locked.
+#[cfg(target_os = "macos")]
+pub fn is_locked() -> Option<bool> {
+    session_flag(b"CGSSessionScreenIsLocked\0").map(|locked| locked.unwrap_or(false))
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+
+    const WTS_CURRENT_SESSION: u32 = u32::MAX;
+    const WTS_CONNECT_STATE: i32 = 8;
+    const WTS_ACTIVE: i32 = 0;
+    const DESKTOP_SWITCHDESKTOP: u32 = 0x0100;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcessId() -> u32;
+        fn ProcessIdToSessionId(pid: u32, session: *mut u32) -> i32;
+    }
+
+    #[link(name = "wtsapi32")]
+    extern "system" {
+        fn WTSQuerySessionInformationW(
+            server: *mut c_void,
+            session: u32,
+            class: i32,
+            buffer: *mut *mut c_void,
+            bytes: *mut u32,
+        ) -> i32;
+        fn WTSFreeMemory(memory: *mut c_void);
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn OpenInputDesktop(flags: u32, inherit: i32, access: u32) -> *mut c_void;
+        fn SwitchDesktop(desktop: *mut c_void) -> i32;
+        fn CloseDesktop(desktop: *mut c_void) -> i32;
+    }
+
+    pub fn session_id() -> u32 {
+        let mut session = 0;
+        unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session) };
+        session
+    }
+
+    // Switched-away sessions drop to connected or disconnected; a locked
+    // console stays active.
+    pub fn is_active() -> Option<bool> {
+        let mut buffer = std::ptr::null_mut();
+        let mut bytes = 0;
+        unsafe {
+            if WTSQuerySessionInformationW(
+                std::ptr::null_mut(),
+                WTS_CURRENT_SESSION,
+                WTS_CONNECT_STATE,
+                &mut buffer,
+                &mut bytes,
+            ) == 0
+                || buffer.is_null()
+            {
+                return None;
+            }
+            let state = *(buffer as *const i32);
+            WTSFreeMemory(buffer);
+            Some(state == WTS_ACTIVE)
+        }
+    }
+
+    // The lock screen runs on the secure desktop, which an app cannot
+    // switch to.
+    pub fn is_locked() -> Option<bool> {
+        unsafe {
+            let desktop = OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP);
+            if desktop.is_null() {
+                return Some(true);
+            }
+            let switched = SwitchDesktop(desktop) != 0;
+            CloseDesktop(desktop);
+            Some(!switched)
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn is_active() -> Option<bool> {
+    windows::is_active()
+}
+
+#[cfg(windows)]
+pub fn is_locked() -> Option<bool> {
+    windows::is_locked()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn is_active() -> Option<bool> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn is_locked() -> Option<bool> {
+    None
+}
+
+// With fast user switching the other user needs the microphone and the
+// hotkeys, so both are handed back while this session is in the background.
+// A locked screen only stops dictation; the hotkeys cannot reach it anyway.
+pub fn spawn_monitor(state: AppState, power: PowerState, portal: PortalState) {
+    thread::spawn(move || {
+        let mut active = true;
+        let mut locked = false;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if let Some(current) = is_active() {
+                if current != active {
+                    active = current;
+                    if active {
+                        ptt::restore_hotkey();
+                        power.session_active(&state, &portal);
+                    } else {
+                        portal.release_shortcuts();
+                        ptt::release_hotkey();
+                        power.session_inactive(&state);
+                    }
+                }
+            }
+            // A background session looks locked on Windows.
+            if !active {
+                continue;
+            }
+            if let Some(current) = is_locked() {
+                if current != locked {
+                    locked = current;
+                    if locked {
+                        power.screen_locked(&state);
+                    } else {
+                        power.screen_unlocked(&state);
+                    }
+                }
+            }
+        }
+    });
+}
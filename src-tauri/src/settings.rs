@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{self, AppConfig};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::profiles::{self, Profiles};
+use crate::ptt::{self, PttConfig};
+use crate::transcript::now_millis;
+use crate::vocab::{self, VocabTerm};
+use crate::voice_commands::{self, CommandRule};
+use crate::window_tracker::{self, AppProfile};
+use crate::AppState;
+
+const FORMAT: &str = "voice-mcp-agent-settings";
+// Each one takes a file from the version at its index + 1 to the next, so an
+// old export is brought up to date one step at a time.
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+pub const VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+// API keys stay in the OS keychain and are never written here. A section
+// missing from an imported file is left as it is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsFile {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub exported_at: u64,
+    pub config: Option<AppConfig>,
+    pub hotkeys: Option<PttConfig>,
+    pub vocabulary: Option<Vec<VocabTerm>>,
+    pub command_rules: Option<Vec<CommandRule>>,
+    pub profiles: Option<Profiles>,
+    pub app_profiles: Option<BTreeMap<String, AppProfile>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedSettings {
+    // The version the file was written in, before it was migrated.
+    pub version: u32,
+    pub sections: Vec<String>,
+}
+
+fn invalid(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorCode::InvalidInput, message)
+}
+
+fn migrate(value: &mut Value) -> Result<u32, AppError> {
+    if value.get("format").and_then(Value::as_str) != Some(FORMAT) {
+        return Err(invalid("This is not a settings file"));
+    }
+    let version = match value.get("version").and_then(Value::as_u64) {
+        Some(version) if version >= 1 => version as u32,
+        _ => return Err(invalid("The settings file has no version")),
+    };
+    if version > VERSION {
+        return Err(AppError::new(
+            ErrorCode::Unsupported,
+            "The settings file was made by a newer version of the app",
+        )
+        .with_context("version", version));
+    }
+    for step in &MIGRATIONS[version as usize - 1..] {
+        step(value);
+    }
+    value["version"] = Value::from(VERSION);
+    Ok(version)
+}
+
+// The interpreter, script and devices belong to the machine, not the user, so
+// the ones set here are kept.
+fn keep_local(imported: &mut AppConfig, current: &AppConfig) {
+    imported.python = current.python.clone();
+    imported.daemon_script = current.daemon_script.clone();
+    imported.input_device = current.input_device.clone();
+    imported.system_device = current.system_device.clone();
+}
+
+#[tauri::command]
+pub fn export_settings(state: tauri::State<'_, AppState>, path: PathBuf) -> CommandResult<()> {
+    let settings = SettingsFile {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: now_millis(),
+        config: Some(config::current()),
+        hotkeys: Some(ptt::get_ptt_hotkey()),
+        vocabulary: Some(vocab::list_vocab_terms()),
+        command_rules: Some(voice_commands::list_command_rules()),
+        profiles: Some(state.profiles.lock().clone()),
+        app_profiles: Some(window_tracker::list_app_profiles()),
+    };
+    let storage = |message: String| AppError::new(ErrorCode::Storage, message);
+    let contents = serde_json::to_string_pretty(&settings)
+        .map_err(|e| storage(format!("Failed to serialize the settings: {}", e)))?;
+    fs::write(&path, contents)
+        .map_err(|e| storage(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+// Sections are replaced one at a time. If one fails, the ones before it stay
+// replaced; they are named in `applied`.
+fn apply(
+    state: &AppState,
+    settings: SettingsFile,
+    applied: &mut Vec<String>,
+) -> Result<(), AppError> {
+    if let Some(mut imported) = settings.config {
+        config::update(state, |config| {
+            keep_local(&mut imported, config);
+            *config = imported;
+        })?;
+        applied.push("config".to_string());
+    }
+    if let Some(hotkeys) = settings.hotkeys {
+        ptt::replace(state, hotkeys)?;
+        applied.push("hotkeys".to_string());
+    }
+    if let Some(terms) = settings.vocabulary {
+        vocab::replace(state, terms)?;
+        applied.push("vocabulary".to_string());
+    }
+    if let Some(rules) = settings.command_rules {
+        voice_commands::replace_rules(rules)?;
+        applied.push("command_rules".to_string());
+    }
+    if let Some(apps) = settings.app_profiles {
+        window_tracker::replace(apps)?;
+        applied.push("app_profiles".to_string());
+    }
+    if let Some(imported) = settings.profiles {
+        let active = profiles::replace(state, imported)?;
+        applied.push("profiles".to_string());
+        // Last, since it restarts a running daemon.
+        profiles::switch(state, &active).map_err(AppError::from)?;
+    }
+    Ok(())
+}
+
+// The whole file is parsed and checked first, so a malformed one changes
+// nothing.
+#[tauri::command]
+pub fn import_settings(
+    state: tauri::State<'_, AppState>,
+    path: PathBuf,
+) -> CommandResult<ImportedSettings> {
+    let contents = fs::read_to_string(&path).map_err(|e| {
+        let code = if e.kind() == std::io::ErrorKind::NotFound {
+            ErrorCode::NotFound
+        } else {
+            ErrorCode::Storage
+        };
+        AppError::new(code, format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let mut value: Value = serde_json::from_str(&contents)
+        .map_err(|e| invalid(format!("Invalid {}: {}", path.display(), e)))?;
+    let version = migrate(&mut value)?;
+    let settings: SettingsFile = serde_json::from_value(value)
+        .map_err(|e| invalid(format!("Invalid {}: {}", path.display(), e)))?;
+    if settings
+        .profiles
+        .as_ref()
+        .map_or(false, |profiles| profiles.profiles.is_empty())
+    {
+        return Err(invalid("The settings file has no profiles"));
+    }
+
+    let mut sections = Vec::new();
+    if let Err(error) = apply(&state, settings, &mut sections) {
+        return Err(error.with_context("applied", sections));
+    }
+    tracing::info!(target: "settings", "Imported {} from {}", sections.join(", "), path.display());
+    Ok(ImportedSettings { version, sections })
+}
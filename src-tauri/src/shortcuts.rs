@@ -0,0 +1,255 @@
+use std::thread;
+
+use global_hotkey::hotkey::HotKey;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backends;
+use crate::bus::{AgentEvent, EventBus};
+use crate::caption_overlay;
+use crate::config;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::ptt;
+use crate::recent;
+use crate::text_inject;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ToggleDictation,
+    TogglePause,
+    UndoInjection,
+    CopyLastTranscript,
+    // Shows the caption overlay, or hides it while it is shown.
+    ShowOverlay,
+}
+
+const ACTIONS: [ShortcutAction; 5] = [
+    ShortcutAction::ToggleDictation,
+    ShortcutAction::TogglePause,
+    ShortcutAction::UndoInjection,
+    ShortcutAction::CopyLastTranscript,
+    ShortcutAction::ShowOverlay,
+];
+
+// Saved as `[shortcuts]` in config.toml, one accelerator such as
+// "Ctrl+Alt+D" per action.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutsConfig {
+    pub toggle_dictation: Option<String>,
+    pub toggle_pause: Option<String>,
+    pub undo_injection: Option<String>,
+    pub copy_last_transcript: Option<String>,
+    pub show_overlay: Option<String>,
+}
+
+impl ShortcutsConfig {
+    fn combo(&self, action: ShortcutAction) -> Option<&String> {
+        match action {
+            ShortcutAction::ToggleDictation => self.toggle_dictation.as_ref(),
+            ShortcutAction::TogglePause => self.toggle_pause.as_ref(),
+            ShortcutAction::UndoInjection => self.undo_injection.as_ref(),
+            ShortcutAction::CopyLastTranscript => self.copy_last_transcript.as_ref(),
+            ShortcutAction::ShowOverlay => self.show_overlay.as_ref(),
+        }
+    }
+
+    fn set(&mut self, action: ShortcutAction, combo: Option<String>) {
+        let slot = match action {
+            ShortcutAction::ToggleDictation => &mut self.toggle_dictation,
+            ShortcutAction::TogglePause => &mut self.toggle_pause,
+            ShortcutAction::UndoInjection => &mut self.undo_injection,
+            ShortcutAction::CopyLastTranscript => &mut self.copy_last_transcript,
+            ShortcutAction::ShowOverlay => &mut self.show_overlay,
+        };
+        *slot = combo;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Shortcut {
+    pub action: ShortcutAction,
+    pub combo: Option<String>,
+    // False when the combo is saved but could not be registered.
+    pub bound: bool,
+}
+
+static APP: OnceCell<AppHandle> = OnceCell::new();
+static TOGGLE_DICTATION: Lazy<Mutex<Option<HotKey>>> = Lazy::new(Mutex::default);
+static TOGGLE_PAUSE: Lazy<Mutex<Option<HotKey>>> = Lazy::new(Mutex::default);
+static UNDO_INJECTION: Lazy<Mutex<Option<HotKey>>> = Lazy::new(Mutex::default);
+static COPY_LAST_TRANSCRIPT: Lazy<Mutex<Option<HotKey>>> = Lazy::new(Mutex::default);
+static SHOW_OVERLAY: Lazy<Mutex<Option<HotKey>>> = Lazy::new(Mutex::default);
+
+fn slot(action: ShortcutAction) -> &'static Mutex<Option<HotKey>> {
+    match action {
+        ShortcutAction::ToggleDictation => &*TOGGLE_DICTATION,
+        ShortcutAction::TogglePause => &*TOGGLE_PAUSE,
+        ShortcutAction::UndoInjection => &*UNDO_INJECTION,
+        ShortcutAction::CopyLastTranscript => &*COPY_LAST_TRANSCRIPT,
+        ShortcutAction::ShowOverlay => &*SHOW_OVERLAY,
+    }
+}
+
+fn name(action: ShortcutAction) -> String {
+    serde_json::to_value(action)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+// For the hotkey listener, which owns the event queue.
+pub fn action_for(id: u32) -> Option<ShortcutAction> {
+    ACTIONS
+        .iter()
+        .copied()
+        .find(|&action| slot(action).lock().map(|hotkey| hotkey.id()) == Some(id))
+}
+
+pub fn run(state: &AppState, action: ShortcutAction) {
+    let state = state.clone();
+    thread::spawn(move || {
+        let result = match action {
+            ShortcutAction::ToggleDictation => backends::toggle(&state).map(|_| ()),
+            ShortcutAction::TogglePause => backends::toggle_paused(&state).map(|_| ()),
+            ShortcutAction::UndoInjection => text_inject::undo_last().map(|_| ()),
+            ShortcutAction::CopyLastTranscript => recent::copy_latest(),
+            ShortcutAction::ShowOverlay => match APP.get() {
+                Some(app_handle) => caption_overlay::toggle(app_handle),
+                None => Ok(()),
+            },
+        };
+        if let Err(error) = result {
+            state.bus.publish(AgentEvent::error(
+                "shortcuts",
+                error.with_context("action", name(action)),
+            ));
+        }
+    });
+}
+
+// Called from the push-to-talk setup, on the main thread.
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    let config = config::current().shortcuts;
+    for action in ACTIONS {
+        if let Some(combo) = config.combo(action) {
+            if let Err(e) =
+                ptt::parse(combo).and_then(|hotkey| ptt::bind(slot(action), Some(hotkey)))
+            {
+                tracing::warn!(target: "shortcuts", "{} not restored: {}", name(action), e);
+            }
+        }
+    }
+}
+
+pub fn release() {
+    for action in ACTIONS {
+        ptt::bind_later(slot(action), None);
+    }
+}
+
+// Also follows hand edits of config.toml; combos that did not change are left
+// registered.
+pub fn restore(config: &ShortcutsConfig) {
+    for action in ACTIONS {
+        let wanted = config
+            .combo(action)
+            .and_then(|combo| ptt::parse(combo).ok());
+        if slot(action).lock().map(|hotkey| hotkey.id()) != wanted.map(|hotkey| hotkey.id()) {
+            ptt::bind_later(slot(action), wanted);
+        }
+    }
+}
+
+pub fn spawn(bus: &EventBus) {
+    let mut events = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::ConfigChanged(config)) => restore(&config.shortcuts),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// Another action, or one of the push-to-talk combos, already has it.
+fn conflict(action: ShortcutAction, hotkey: HotKey) -> Option<String> {
+    let config = config::current().shortcuts;
+    let same = |combo: &String| ptt::parse(combo).map_or(false, |other| other.id() == hotkey.id());
+    ACTIONS
+        .iter()
+        .copied()
+        .filter(|&other| other != action)
+        .find(|&other| config.combo(other).map_or(false, &same))
+        .map(name)
+        .or_else(|| {
+            ptt::combos()
+                .into_iter()
+                .find(|(_, combo)| same(combo))
+                .map(|(owner, _)| owner.to_string())
+        })
+}
+
+#[tauri::command]
+pub fn list_shortcuts() -> Vec<Shortcut> {
+    let config = config::current().shortcuts;
+    ACTIONS
+        .iter()
+        .map(|&action| Shortcut {
+            action,
+            combo: config.combo(action).cloned(),
+            bound: slot(action).lock().is_some(),
+        })
+        .collect()
+}
+
+// The OS refuses a combo another app has registered, which fails here rather
+// than silently never firing.
+#[tauri::command]
+pub async fn bind_shortcut(
+    state: tauri::State<'_, AppState>,
+    action: ShortcutAction,
+    combo: String,
+) -> CommandResult<()> {
+    let hotkey = ptt::parse(&combo)?;
+    if let Some(owner) = conflict(action, hotkey) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("{} is already bound to {}", combo, owner),
+        )
+        .with_context("conflict", owner));
+    }
+    ptt::bind_on_main_thread(slot(action), Some(hotkey))
+        .await
+        .map_err(|error| {
+            if error.code != ErrorCode::Failed {
+                return error;
+            }
+            AppError::new(
+                ErrorCode::InvalidInput,
+                format!(
+                    "{} could not be registered; another app may be using it",
+                    combo
+                ),
+            )
+            .with_context("conflict", "system")
+        })?;
+    config::update(&state, |config| config.shortcuts.set(action, Some(combo)))
+}
+
+#[tauri::command]
+pub async fn unbind_shortcut(
+    state: tauri::State<'_, AppState>,
+    action: ShortcutAction,
+) -> CommandResult<()> {
+    ptt::bind_on_main_thread(slot(action), None).await?;
+    config::update(&state, |config| config.shortcuts.set(action, None))
+}
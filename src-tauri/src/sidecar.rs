@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+use tauri::AppHandle;
+
+use crate::config::{self, AppConfig};
+
+// Bundled from `binaries/stt-daemon-<target triple>` (see
+// scripts/build-sidecar.sh); Tauri installs it next to the executable without
+// the triple.
+const SIDECAR: &str = "stt-daemon";
+
+static RESOURCE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+// Headless runs never build the app, so they look next to the executable
+// and in the working directory only.
+pub fn init(app_handle: &AppHandle) {
+    if let Some(dir) = app_handle.path_resolver().resource_dir() {
+        let _ = RESOURCE_DIR.set(dir);
+    }
+}
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()?
+        .parent()
+        .map(Path::to_path_buf)
+}
+
+fn bundled() -> Option<PathBuf> {
+    let path = exe_dir()?.join(format!("{}{}", SIDECAR, std::env::consts::EXE_SUFFIX));
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+// Choosing an interpreter or a script in config.toml opts out of the
+// sidecar, which has its own Python inside.
+pub fn daemon() -> Option<PathBuf> {
+    let config = config::current();
+    if config.daemon_script.is_some() || config.python != AppConfig::default().python {
+        return None;
+    }
+    bundled()
+}
+
+// Installed apps find the scripts among their resources; a checkout finds
+// them in the working directory.
+pub fn script(name: &str) -> PathBuf {
+    RESOURCE_DIR
+        .get()
+        .cloned()
+        .into_iter()
+        .chain(exe_dir())
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
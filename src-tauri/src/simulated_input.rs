@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "simulated_input.json";
+const EXTENSIONS: [&str; 2] = ["wav", "flac"];
+
+// Stands in for the microphone: the daemon plays the file through its normal
+// capture path, `speed` times faster than real time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedInput {
+    pub path: PathBuf,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+impl SimulatedInput {
+    pub fn new(path: PathBuf, speed: f32) -> Result<Self, AppError> {
+        let input = Self { path, speed };
+        input.validate()?;
+        Ok(input)
+    }
+
+    fn validate(&self) -> Result<(), AppError> {
+        if !self.path.is_file() {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No such file: {}", self.path.display()),
+            ));
+        }
+        let supported = self
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+            .unwrap_or(false);
+        if !supported {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!(
+                    "Simulated input must be a WAV or FLAC file: {}",
+                    self.path.display()
+                ),
+            ));
+        }
+        if self.speed.is_nan() || self.speed <= 0.0 {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Playback speed must be positive",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn daemon_args(&self) -> Vec<String> {
+        vec![
+            "--input".to_string(),
+            "file".to_string(),
+            "--input-file".to_string(),
+            self.path.display().to_string(),
+            "--speed".to_string(),
+            self.speed.to_string(),
+        ]
+    }
+}
+
+pub fn load() -> Option<SimulatedInput> {
+    store::load_json(STORE_FILE)
+}
+
+pub fn current(state: &AppState) -> Option<SimulatedInput> {
+    state.simulated_input.lock().clone()
+}
+
+pub fn set(state: &AppState, input: Option<SimulatedInput>) -> CommandResult<()> {
+    if let Some(input) = &input {
+        input.validate()?;
+    }
+    store::save_json(STORE_FILE, &input)?;
+    *state.simulated_input.lock() = input;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_simulated_input(state: tauri::State<'_, AppState>) -> Option<SimulatedInput> {
+    current(&state)
+}
+
+#[tauri::command]
+pub fn set_simulated_input(
+    state: tauri::State<'_, AppState>,
+    path: Option<String>,
+    speed: Option<f32>,
+) -> CommandResult<()> {
+    let input = match path {
+        Some(path) => Some(SimulatedInput::new(
+            Path::new(&path).to_path_buf(),
+            speed.unwrap_or_else(default_speed),
+        )?),
+        None => None,
+    };
+    set(&state, input)
+}
@@ -0,0 +1,464 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::models::SttBackend;
+use crate::simulated_input;
+use crate::store;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const LOOP_TICK: Duration = Duration::from_millis(100);
+const STALL_MS: u64 = 1000;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+// Growth needs this many samples after warm-up before it is judged.
+const MIN_SAMPLES: usize = 6;
+// Share of sample-to-sample steps that must not go down for growth to count
+// as monotonic rather than noise.
+const MONOTONIC: f64 = 0.8;
+
+pub struct SoakOptions {
+    pub duration: Duration,
+    pub interval: Duration,
+    pub cycle: Duration,
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample {
+    pub elapsed_s: f64,
+    pub rss_mb: f64,
+    pub virtual_mb: f64,
+    pub handles: Option<u64>,
+    pub threads: Option<u64>,
+    pub daemon_rss_mb: Option<f64>,
+    pub max_loop_lag_ms: u64,
+    pub transcripts: u64,
+    pub mean_latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Stall {
+    pub elapsed_s: f64,
+    pub lag_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub metric: String,
+    pub first: f64,
+    pub last: f64,
+    pub slope_per_hour: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    pub started_at: u64,
+    pub duration_s: f64,
+    pub cycles: u64,
+    pub transcripts: u64,
+    pub crashes: u64,
+    pub errors: Vec<String>,
+    pub stalls: Vec<Stall>,
+    pub findings: Vec<Finding>,
+    pub samples: Vec<Sample>,
+}
+
+#[derive(Default)]
+struct Counters {
+    transcripts: AtomicU64,
+    crashes: AtomicU64,
+    cycles: AtomicU64,
+    max_lag_ms: AtomicU64,
+    // Total latency of transcripts since the last sample.
+    latencies: Mutex<Vec<f64>>,
+    errors: Mutex<Vec<String>>,
+}
+
+#[cfg(target_os = "linux")]
+fn handle_count() -> Option<u64> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count() as u64)
+}
+
+#[cfg(target_os = "macos")]
+fn handle_count() -> Option<u64> {
+    Some(std::fs::read_dir("/dev/fd").ok()?.count() as u64)
+}
+
+#[cfg(windows)]
+fn handle_count() -> Option<u64> {
+    use std::ffi::c_void;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+        fn GetProcessHandleCount(process: *mut c_void, count: *mut u32) -> i32;
+    }
+
+    let mut count = 0;
+    if unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count) } == 0 {
+        return None;
+    }
+    Some(u64::from(count))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn handle_count() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn thread_count() -> Option<u64> {
+    Some(std::fs::read_dir("/proc/self/task").ok()?.count() as u64)
+}
+
+#[cfg(target_os = "macos")]
+fn thread_count() -> Option<u64> {
+    use std::ffi::c_void;
+
+    // `struct proc_taskinfo`: six u64 sizes and times, then twelve i32
+    // counters of which `pti_threadnum` is the tenth.
+    const PROC_PIDTASKINFO: i32 = 4;
+    const TASKINFO_SIZE: usize = 96;
+    const THREADNUM_AT: usize = 84;
+
+    extern "C" {
+        fn proc_pidinfo(pid: i32, flavor: i32, arg: u64, buffer: *mut c_void, size: i32) -> i32;
+    }
+
+    let mut info = [0u8; TASKINFO_SIZE];
+    let written = unsafe {
+        proc_pidinfo(
+            std::process::id() as i32,
+            PROC_PIDTASKINFO,
+            0,
+            info.as_mut_ptr() as *mut c_void,
+            TASKINFO_SIZE as i32,
+        )
+    };
+    if written != TASKINFO_SIZE as i32 {
+        return None;
+    }
+    let mut threads = [0u8; 4];
+    threads.copy_from_slice(&info[THREADNUM_AT..THREADNUM_AT + 4]);
+    Some(i32::from_ne_bytes(threads) as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn thread_count() -> Option<u64> {
+    None
+}
+
+fn mb(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+fn sample(state: &AppState, system: &mut System, counters: &Counters, started: Instant) -> Sample {
+    let pid = Pid::from_u32(std::process::id());
+    system.refresh_process(pid);
+    let (rss_mb, virtual_mb) = system
+        .process(pid)
+        .map_or((0.0, 0.0), |p| (mb(p.memory()), mb(p.virtual_memory())));
+    let daemon_pid = state
+        .stt_process
+        .lock()
+        .as_ref()
+        .map(|child| Pid::from_u32(child.id()));
+    let daemon_rss_mb = daemon_pid.and_then(|pid| {
+        system.refresh_process(pid);
+        system.process(pid).map(|p| mb(p.memory()))
+    });
+    let latencies: Vec<f64> = counters.latencies.lock().drain(..).collect();
+    Sample {
+        elapsed_s: started.elapsed().as_secs_f64(),
+        rss_mb,
+        virtual_mb,
+        handles: handle_count(),
+        threads: thread_count(),
+        daemon_rss_mb,
+        max_loop_lag_ms: counters.max_lag_ms.swap(0, Ordering::Relaxed),
+        transcripts: counters.transcripts.load(Ordering::Relaxed),
+        mean_latency_ms: if latencies.is_empty() {
+            None
+        } else {
+            Some(latencies.iter().sum::<f64>() / latencies.len() as f64)
+        },
+    }
+}
+
+// Least-squares slope, in units per hour.
+fn slope(points: &[(f64, f64)]) -> f64 {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance += (x - mean_x) * (x - mean_x);
+    }
+    if variance == 0.0 {
+        0.0
+    } else {
+        covariance / variance * 3600.0
+    }
+}
+
+// Flags a series that keeps climbing past `min_growth` in absolute terms and
+// `min_ratio` relative to where it started.
+fn growth(metric: &str, points: &[(f64, f64)], min_growth: f64, min_ratio: f64) -> Option<Finding> {
+    if points.len() < MIN_SAMPLES {
+        return None;
+    }
+    let first = points[0].1;
+    let last = points[points.len() - 1].1;
+    let rising = points.windows(2).filter(|w| w[1].1 >= w[0].1).count();
+    let monotonic = rising as f64 / (points.len() - 1) as f64;
+    let slope_per_hour = slope(points);
+    let grown = last - first;
+    if slope_per_hour > 0.0
+        && monotonic >= MONOTONIC
+        && grown >= min_growth
+        && grown >= first.abs() * min_ratio
+    {
+        Some(Finding {
+            metric: metric.to_string(),
+            first,
+            last,
+            slope_per_hour,
+        })
+    } else {
+        None
+    }
+}
+
+fn series(samples: &[Sample], value: fn(&Sample) -> Option<f64>) -> Vec<(f64, f64)> {
+    samples
+        .iter()
+        .filter_map(|s| Some((s.elapsed_s, value(s)?)))
+        .collect()
+}
+
+// The first tenth of the run is warm-up: models load, caches fill and the
+// allocator settles.
+fn findings(samples: &[Sample]) -> Vec<Finding> {
+    let steady = &samples[samples.len() / 10..];
+    [
+        growth("rss_mb", &series(steady, |s| Some(s.rss_mb)), 10.0, 0.05),
+        growth(
+            "handles",
+            &series(steady, |s| s.handles.map(|h| h as f64)),
+            20.0,
+            0.1,
+        ),
+        growth(
+            "threads",
+            &series(steady, |s| s.threads.map(|t| t as f64)),
+            5.0,
+            0.1,
+        ),
+        growth(
+            "daemon_rss_mb",
+            &series(steady, |s| s.daemon_rss_mb),
+            50.0,
+            0.1,
+        ),
+        growth(
+            "mean_latency_ms",
+            &series(steady, |s| s.mean_latency_ms),
+            100.0,
+            0.5,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn spawn_listener(state: &AppState, counters: Arc<Counters>) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::Transcript(transcript)) if transcript.is_final => {
+                    counters.transcripts.fetch_add(1, Ordering::Relaxed);
+                    if let Some(latency) = transcript.latency {
+                        counters.latencies.lock().push(latency.total_ms);
+                    }
+                }
+                Ok(AgentEvent::DaemonCrashed { .. }) => {
+                    counters.crashes.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(AgentEvent::Error { source, error }) => {
+                    counters
+                        .errors
+                        .lock()
+                        .push(format!("{}: {}", source, error.message));
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// A tick that fires late means something blocked the shared async runtime.
+fn spawn_lag_probe(counters: Arc<Counters>, stalls: Arc<Mutex<Vec<Stall>>>, started: Instant) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let before = Instant::now();
+            tokio::time::sleep(LOOP_TICK).await;
+            let lag_ms = before.elapsed().saturating_sub(LOOP_TICK).as_millis() as u64;
+            counters.max_lag_ms.fetch_max(lag_ms, Ordering::Relaxed);
+            if lag_ms >= STALL_MS {
+                stalls.lock().push(Stall {
+                    elapsed_s: started.elapsed().as_secs_f64(),
+                    lag_ms,
+                });
+            }
+        }
+    });
+}
+
+// Starts listening over and over: each session runs until the simulated
+// input ends or `cycle` passes, like a day of dictation compressed.
+fn spawn_exerciser(
+    state: AppState,
+    counters: Arc<Counters>,
+    cycle: Duration,
+    done: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        while !done.load(Ordering::Relaxed) {
+            if let Err(error) = daemon::start(&state, InputSource::Microphone) {
+                counters.errors.lock().push(format!("start: {}", error));
+                thread::sleep(RETRY_DELAY);
+                continue;
+            }
+            let session = daemon::session_id(&state);
+            let started = Instant::now();
+            while started.elapsed() < cycle
+                && !done.load(Ordering::Relaxed)
+                && daemon::session_id(&state) == session
+            {
+                thread::sleep(Duration::from_millis(200));
+            }
+            if daemon::session_id(&state) == session {
+                let _ = daemon::stop(&state);
+            }
+            counters.cycles.fetch_add(1, Ordering::Relaxed);
+        }
+    })
+}
+
+fn report_path() -> PathBuf {
+    store::data_dir()
+        .join("soak")
+        .join(format!("soak-{}.json", now_millis()))
+}
+
+pub fn run(state: &AppState, options: SoakOptions) -> i32 {
+    let simulated = simulated_input::current(state).is_some()
+        || matches!(state.models.config().backend, SttBackend::Mock { .. });
+    if !simulated {
+        eprintln!(
+            "Soak testing needs simulated audio: pass --simulate-input FILE or --mock-stt SCRIPT"
+        );
+        return 2;
+    }
+
+    let started = Instant::now();
+    let started_at = now_millis();
+    let counters = Arc::new(Counters::default());
+    let stalls = Arc::new(Mutex::new(Vec::new()));
+    let done = Arc::new(AtomicBool::new(false));
+    spawn_listener(state, counters.clone());
+    spawn_lag_probe(counters.clone(), stalls.clone(), started);
+    let exerciser = spawn_exerciser(state.clone(), counters.clone(), options.cycle, done.clone());
+
+    let mut system = System::new();
+    let mut samples = Vec::new();
+    eprintln!(
+        "Soak test running for {:.1} h, sampling every {} s",
+        options.duration.as_secs_f64() / 3600.0,
+        options.interval.as_secs()
+    );
+    tauri::async_runtime::block_on(async {
+        while started.elapsed() < options.duration {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                _ = tokio::time::sleep(options.interval) => {}
+            }
+            let sample = sample(state, &mut system, &counters, started);
+            eprintln!(
+                "[{:>7.0}s] rss {:.1} MB, handles {:?}, threads {:?}, lag {} ms, {} transcripts",
+                sample.elapsed_s,
+                sample.rss_mb,
+                sample.handles,
+                sample.threads,
+                sample.max_loop_lag_ms,
+                sample.transcripts
+            );
+            samples.push(sample);
+        }
+    });
+    done.store(true, Ordering::Relaxed);
+    let _ = exerciser.join();
+    if daemon::is_running(state) {
+        let _ = daemon::stop(state);
+    }
+
+    let report = SoakReport {
+        started_at,
+        duration_s: started.elapsed().as_secs_f64(),
+        cycles: counters.cycles.load(Ordering::Relaxed),
+        transcripts: counters.transcripts.load(Ordering::Relaxed),
+        crashes: counters.crashes.load(Ordering::Relaxed),
+        errors: counters.errors.lock().clone(),
+        stalls: stalls.lock().clone(),
+        findings: findings(&samples),
+        samples,
+    };
+    let path = options.report.unwrap_or_else(report_path);
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| {
+            std::fs::write(
+                &path,
+                serde_json::to_string_pretty(&report).unwrap_or_default(),
+            )
+        });
+    match written {
+        Ok(()) => println!("{}", path.display()),
+        Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+    }
+
+    for finding in &report.findings {
+        eprintln!(
+            "Growth in {}: {:.1} -> {:.1} ({:+.1}/h)",
+            finding.metric, finding.first, finding.last, finding.slope_per_hour
+        );
+    }
+    if !report.stalls.is_empty() {
+        eprintln!(
+            "{} event loop stalls over {} ms",
+            report.stalls.len(),
+            STALL_MS
+        );
+    }
+    if report.findings.is_empty() && report.stalls.is_empty() && report.crashes == 0 {
+        eprintln!("No leaks, drift or stalls over {} cycles", report.cycles);
+        0
+    } else {
+        1
+    }
+}
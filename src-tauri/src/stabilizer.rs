@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+// Saved as `[partials]` in config.toml.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartialsConfig {
+    // How many hypotheses in a row a word has to survive before it is
+    // published; 0 or 1 publishes every hypothesis as it comes.
+    pub stability_updates: u32,
+}
+
+impl Default for PartialsConfig {
+    fn default() -> Self {
+        Self {
+            stability_updates: 3,
+        }
+    }
+}
+
+// Turns a backend's revised hypotheses for one utterance into partials that
+// only ever grow. A published word is never taken back; the final transcript
+// corrects it if the backend changes its mind.
+pub struct Stabilizer {
+    updates: usize,
+    recent: VecDeque<Vec<String>>,
+    published: Vec<String>,
+}
+
+impl Stabilizer {
+    pub fn new(config: &PartialsConfig) -> Self {
+        Self {
+            updates: config.stability_updates as usize,
+            recent: VecDeque::new(),
+            published: Vec::new(),
+        }
+    }
+
+    // The stable prefix when it grew, or None while nothing new has settled.
+    pub fn partial(&mut self, text: &str) -> Option<String> {
+        if self.updates <= 1 {
+            return Some(text.to_string());
+        }
+        self.recent
+            .push_back(text.split_whitespace().map(str::to_string).collect());
+        if self.recent.len() > self.updates {
+            self.recent.pop_front();
+        }
+        if self.recent.len() < self.updates {
+            return None;
+        }
+        let latest = &self.recent[self.recent.len() - 1];
+        let agreed = (0..latest.len())
+            .take_while(|&i| {
+                self.recent
+                    .iter()
+                    .all(|words| words.get(i) == Some(&latest[i]))
+                    && self
+                        .published
+                        .get(i)
+                        .map_or(true, |word| *word == latest[i])
+            })
+            .count();
+        if agreed <= self.published.len() {
+            return None;
+        }
+        self.published = latest[..agreed].to_vec();
+        Some(self.published.join(" "))
+    }
+
+    // Called with each final transcript; the next hypothesis starts a new
+    // utterance.
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.published.clear();
+    }
+}
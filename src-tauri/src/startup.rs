@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Integrations,
+    Devices,
+    Model,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageProgress {
+    pub stage: Stage,
+    pub state: StageState,
+    pub detail: Option<String>,
+    pub elapsed_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupStatus {
+    pub ready: bool,
+    pub stages: Vec<StageProgress>,
+    pub input_devices: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct StartupState {
+    status: Arc<Mutex<StartupStatus>>,
+}
+
+impl Default for StartupState {
+    fn default() -> Self {
+        let stages = [Stage::Integrations, Stage::Devices, Stage::Model]
+            .into_iter()
+            .map(|stage| StageProgress {
+                stage,
+                state: StageState::Pending,
+                detail: None,
+                elapsed_ms: None,
+            })
+            .collect();
+        Self {
+            status: Arc::new(Mutex::new(StartupStatus {
+                stages,
+                ..Default::default()
+            })),
+        }
+    }
+}
+
+impl StartupState {
+    fn update(&self, state: &AppState, progress: StageProgress) {
+        {
+            let mut status = self.status.lock();
+            if let Some(slot) = status.stages.iter_mut().find(|s| s.stage == progress.stage) {
+                *slot = progress.clone();
+            }
+        }
+        state.bus.publish(AgentEvent::StartupProgress(progress));
+    }
+
+    fn stage(
+        &self,
+        state: &AppState,
+        stage: Stage,
+        work: impl FnOnce() -> Result<Option<String>, String>,
+    ) {
+        self.update(
+            state,
+            StageProgress {
+                stage,
+                state: StageState::Running,
+                detail: None,
+                elapsed_ms: None,
+            },
+        );
+        let started = Instant::now();
+        let (stage_state, detail) = match work() {
+            Ok(detail) => (StageState::Done, detail),
+            Err(e) => {
+                tracing::warn!(target: "startup", ?stage, "{}", e);
+                (StageState::Failed, Some(e))
+            }
+        };
+        self.update(
+            state,
+            StageProgress {
+                stage,
+                state: stage_state,
+                detail,
+                elapsed_ms: Some(started.elapsed().as_millis() as u64),
+            },
+        );
+    }
+
+    pub fn status(&self) -> StartupStatus {
+        self.status.lock().clone()
+    }
+}
+
+// Runs after the tray is up. Nothing here is needed to show the UI, and the
+// model itself is only fetched to disk; it is loaded on the first listen.
+pub fn run(
+    state: &AppState,
+    startup: &StartupState,
+    integrations: impl FnOnce() -> Result<(), String>,
+) {
+    startup.stage(state, Stage::Integrations, || integrations().map(|_| None));
+    startup.stage(state, Stage::Devices, || {
+        let devices = daemon::list_input_devices(state)?;
+        let detail = format!("{} input devices", devices.len());
+        startup.status.lock().input_devices = devices;
+        Ok(Some(detail))
+    });
+    startup.stage(state, Stage::Model, || {
+        daemon::prefetch_model(state).map(Some)
+    });
+    startup.status.lock().ready = true;
+}
+
+#[tauri::command]
+pub fn get_startup_status(startup: tauri::State<'_, StartupState>) -> StartupStatus {
+    startup.status()
+}
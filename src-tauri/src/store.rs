@@ -0,0 +1,37 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{AppError, ErrorCode};
+use crate::session;
+
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("VOICE_AGENT_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    match dirs::config_dir() {
+        Some(dir) => dir.join("com.voice.mcp.agent"),
+        // The temp dir can be shared by every user on the machine.
+        None => std::env::temp_dir().join(format!("com.voice.mcp.agent-{}", session::user_name())),
+    }
+}
+
+pub fn load_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    fs::read_to_string(data_dir().join(name))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> Result<(), AppError> {
+    let storage = |message: String| AppError::new(ErrorCode::Storage, message);
+    let dir = data_dir();
+    fs::create_dir_all(&dir)
+        .map_err(|e| storage(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let contents = serde_json::to_string_pretty(value)
+        .map_err(|e| storage(format!("Failed to serialize {}: {}", name, e)))?;
+    fs::write(dir.join(name), contents)
+        .map_err(|e| storage(format!("Failed to write {}: {}", name, e)))
+}
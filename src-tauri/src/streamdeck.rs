@@ -0,0 +1,144 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::profiles;
+use crate::AppState;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeckState {
+    pub listening: bool,
+    pub source: Option<InputSource>,
+    pub profile: String,
+    pub last_transcript: Option<String>,
+}
+
+type DeckResult = Result<Json<DeckState>, (StatusCode, String)>;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/streamdeck/state", get(state))
+        .route("/streamdeck/toggle", post(toggle))
+        .route("/streamdeck/profile/next", post(next_profile))
+        .route("/streamdeck/profile/:name", post(switch_profile))
+        .route("/streamdeck/repeat", post(repeat))
+        .route("/streamdeck/ws", get(upgrade))
+}
+
+fn deck_state(state: &AppState) -> DeckState {
+    DeckState {
+        listening: backends::is_running(state),
+        source: backends::input_source(state),
+        profile: profiles::active_profile(state).name,
+        last_transcript: state
+            .last_transcript
+            .lock()
+            .as_ref()
+            .map(|t| t.text.clone()),
+    }
+}
+
+fn conflict(message: String) -> (StatusCode, String) {
+    (StatusCode::CONFLICT, message)
+}
+
+async fn state(State(state): State<AppState>) -> Json<DeckState> {
+    Json(deck_state(&state))
+}
+
+// Toggling and switching profiles wait on the lifecycle queue, so they are
+// kept off the server's executor.
+async fn blocking(
+    state: &AppState,
+    action: impl FnOnce(&AppState) -> Result<(), (StatusCode, String)> + Send + 'static,
+) -> DeckResult {
+    let running = state.clone();
+    tauri::async_runtime::spawn_blocking(move || action(&running))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Stream Deck task failed: {}", e),
+            )
+        })??;
+    Ok(Json(deck_state(state)))
+}
+
+async fn toggle(State(state): State<AppState>) -> DeckResult {
+    blocking(&state, |state| {
+        backends::toggle(state)
+            .map(|_| ())
+            .map_err(|e| conflict(e.message))
+    })
+    .await
+}
+
+async fn next_profile(State(state): State<AppState>) -> DeckResult {
+    blocking(&state, |state| {
+        profiles::switch_next(state).map(|_| ()).map_err(conflict)
+    })
+    .await
+}
+
+async fn switch_profile(State(state): State<AppState>, Path(name): Path<String>) -> DeckResult {
+    blocking(&state, move |state| {
+        profiles::switch(state, &name)
+            .map(|_| ())
+            .map_err(|e| (StatusCode::NOT_FOUND, e))
+    })
+    .await
+}
+
+async fn repeat(State(state): State<AppState>) -> DeckResult {
+    let last = state.last_transcript.lock().clone();
+    match last {
+        Some(transcript) => {
+            state.bus.publish(AgentEvent::RepeatRequested(transcript));
+            Ok(Json(deck_state(&state)))
+        }
+        None => Err((StatusCode::NOT_FOUND, "No transcript yet".to_string())),
+    }
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(move |socket| push_state(socket, state))
+}
+
+async fn send_state(socket: &mut WebSocket, state: &AppState) -> bool {
+    let payload = serde_json::to_string(&deck_state(state)).unwrap_or_default();
+    socket.send(Message::Text(payload)).await.is_ok()
+}
+
+async fn push_state(mut socket: WebSocket, state: AppState) {
+    let mut events = state.bus.subscribe();
+    if !send_state(&mut socket, &state).await {
+        return;
+    }
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(AgentEvent::ListeningChanged(_))
+                | Ok(AgentEvent::ProfileChanged(_))
+                | Ok(AgentEvent::Transcript(_)) => {
+                    if !send_state(&mut socket, &state).await {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::AgentEvent;
+use crate::daemon::{self, InputSource};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::i18n;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "restart.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub enabled: bool,
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    // A session that stayed up this long starts the backoff over.
+    pub stable_after_secs: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: 5,
+            initial_delay_ms: 1000,
+            max_delay_ms: 60_000,
+            stable_after_secs: 60,
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(20);
+        Duration::from_millis(
+            self.initial_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_delay_ms),
+        )
+    }
+}
+
+static POLICY: Lazy<Mutex<RestartPolicy>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+// Bumped by every explicit stop, so a restart waiting out its backoff does
+// not undo what the user just asked for.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn cancel_restart() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+// Retries until a start succeeds; a daemon that then dies again comes back
+// through the crash event with the attempt count intact.
+async fn restart(state: &AppState, source: InputSource, attempts: &mut u32) {
+    let generation = GENERATION.load(Ordering::Relaxed);
+    loop {
+        let policy = POLICY.lock().clone();
+        if !policy.enabled {
+            return;
+        }
+        *attempts += 1;
+        if *attempts > policy.max_attempts {
+            state.bus.publish(AgentEvent::error(
+                "supervisor",
+                AppError::new(
+                    ErrorCode::Failed,
+                    i18n::t_args(
+                        "daemon-restart-gave-up",
+                        &[("attempts", &policy.max_attempts.to_string())],
+                    ),
+                ),
+            ));
+            return;
+        }
+        let delay = policy.delay(*attempts);
+        tracing::warn!(
+            target: "supervisor",
+            "Restarting STT daemon in {} ms (attempt {})",
+            delay.as_millis(),
+            attempts
+        );
+        state.bus.publish(AgentEvent::DaemonRestarting {
+            attempt: *attempts,
+            delay_ms: delay.as_millis() as u64,
+        });
+        tokio::time::sleep(delay).await;
+        if GENERATION.load(Ordering::Relaxed) != generation || daemon::is_running(state) {
+            return;
+        }
+        let starting = state.clone();
+        let result =
+            tauri::async_runtime::spawn_blocking(move || daemon::start(&starting, source)).await;
+        match result {
+            Ok(Ok(_)) => return,
+            Ok(Err(error)) => state.bus.publish(AgentEvent::error("supervisor", error)),
+            Err(e) => state.bus.publish(AgentEvent::error(
+                "supervisor",
+                format!("Restart task failed: {}", e),
+            )),
+        }
+    }
+}
+
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut attempts = 0;
+        let mut started_at = Instant::now();
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::ListeningChanged(true)) => started_at = Instant::now(),
+                Ok(AgentEvent::DaemonCrashed { source, .. }) => {
+                    let stable = Duration::from_secs(POLICY.lock().stable_after_secs);
+                    if started_at.elapsed() >= stable {
+                        attempts = 0;
+                    }
+                    restart(&state, source, &mut attempts).await;
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_restart_policy() -> RestartPolicy {
+    POLICY.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_restart_policy(policy: RestartPolicy) -> CommandResult<()> {
+    if policy.initial_delay_ms == 0 || policy.max_delay_ms < policy.initial_delay_ms {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Restart delays must be positive, with the maximum no smaller than the initial delay",
+        ));
+    }
+    store::save_json(STORE_FILE, &policy)?;
+    *POLICY.lock() = policy;
+    Ok(())
+}
@@ -0,0 +1,416 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::a11y;
+use crate::agents;
+use crate::bus::AgentEvent;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::formatting;
+use crate::latency;
+use crate::portal;
+use crate::redaction::{self, Destination};
+use crate::store;
+use crate::voice_commands;
+use crate::window_tracker::{self, FocusedApp};
+use crate::AppState;
+
+const STORE_FILE: &str = "inject.json";
+// The target app reads the clipboard some time after the paste keystroke.
+const PASTE_SETTLE: Duration = Duration::from_millis(100);
+const RESTORE_DELAY_MS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectMode {
+    // Types each character; slow for long text, but leaves the clipboard alone.
+    Keystrokes,
+    // Puts the text on the clipboard and sends the paste shortcut.
+    Paste,
+}
+
+impl Default for InjectMode {
+    fn default() -> Self {
+        InjectMode::Keystrokes
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InjectConfig {
+    // Types every final transcript into the focused app, in place of the
+    // daemon's own typing.
+    pub auto_inject: bool,
+    pub mode: InjectMode,
+    // Puts back what was on the clipboard before a paste.
+    pub restore_clipboard: bool,
+    // Slow apps read the clipboard late, and would paste the old contents.
+    pub restore_delay_ms: u64,
+    // Types stable partials as they settle, so the text keeps up with speech.
+    pub type_partials: bool,
+}
+
+impl Default for InjectConfig {
+    fn default() -> Self {
+        Self {
+            auto_inject: false,
+            mode: InjectMode::default(),
+            restore_clipboard: true,
+            restore_delay_ms: RESTORE_DELAY_MS,
+            type_partials: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InjectionOutcome {
+    pub mode: InjectMode,
+    pub success: bool,
+    pub error: Option<String>,
+    pub clipboard_restored: bool,
+}
+
+pub struct Injected {
+    pub elapsed: Duration,
+    pub clipboard_restored: bool,
+}
+
+#[derive(Debug, Clone)]
+struct LastInjection {
+    chars: usize,
+    // Where it was typed, when the window tracker knows.
+    app: Option<FocusedApp>,
+}
+
+static LAST: Lazy<Mutex<Option<LastInjection>>> = Lazy::new(Mutex::default);
+// The utterance typed so far from its partials.
+static TYPED: Lazy<Mutex<String>> = Lazy::new(Mutex::default);
+
+// What was on the clipboard before a paste.
+struct Snapshot {
+    text: Option<String>,
+    html: Option<String>,
+    image: Option<arboard::ImageData<'static>>,
+}
+
+impl Snapshot {
+    fn take(clipboard: &mut arboard::Clipboard) -> Self {
+        Self {
+            text: clipboard.get_text().ok(),
+            html: clipboard.get().html().ok(),
+            image: clipboard.get_image().ok(),
+        }
+    }
+
+    // One format comes back: an image, or rich text with its plain text.
+    fn restore(self, clipboard: &mut arboard::Clipboard) -> Result<(), arboard::Error> {
+        match (self.image, self.html, self.text) {
+            (Some(image), _, _) => clipboard.set_image(image),
+            (None, Some(html), text) => clipboard.set_html(html, text),
+            (None, None, Some(text)) => clipboard.set_text(text),
+            (None, None, None) => clipboard.clear(),
+        }
+    }
+}
+
+// A paste whose clipboard still has to be put back.
+struct Pasted {
+    clipboard: arboard::Clipboard,
+    snapshot: Snapshot,
+}
+
+static CONFIG: Lazy<Mutex<InjectConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+pub fn auto_inject() -> bool {
+    CONFIG.lock().auto_inject
+}
+
+// Whether the agent types final transcripts instead of the daemon: with
+// auto-injection on, or when voice commands, formatting or per-app profiles
+// have to see the text before it is typed.
+pub fn agent_types() -> bool {
+    auto_inject()
+        || voice_commands::intercepts_typing()
+        || formatting::enabled()
+        || window_tracker::has_profiles()
+        || redaction::redacts(Destination::Inject)
+}
+
+fn failed(e: impl std::fmt::Display) -> AppError {
+    AppError::from(format!("Failed to type into the focused app: {}", e))
+}
+
+fn keyboard() -> Result<Enigo, AppError> {
+    Enigo::new(&Settings::default()).map_err(|e| {
+        AppError::new(
+            ErrorCode::Unsupported,
+            format!("Keyboard input is unavailable: {}", e),
+        )
+    })
+}
+
+fn paste_shortcut(enigo: &mut Enigo) -> Result<(), AppError> {
+    let modifier = if cfg!(target_os = "macos") {
+        Key::Meta
+    } else {
+        Key::Control
+    };
+    enigo.key(modifier, Direction::Press).map_err(failed)?;
+    let pressed = enigo.key(Key::Unicode('v'), Direction::Click);
+    enigo.key(modifier, Direction::Release).map_err(failed)?;
+    pressed.map_err(failed)
+}
+
+fn paste(enigo: &mut Enigo, text: &str, restore: bool) -> Result<Option<Pasted>, AppError> {
+    let mut clipboard = arboard::Clipboard::new().map_err(failed)?;
+    let snapshot = if restore {
+        Some(Snapshot::take(&mut clipboard))
+    } else {
+        None
+    };
+    clipboard.set_text(text).map_err(failed)?;
+    let sent = paste_shortcut(enigo);
+    // Dropping the clipboard too early loses the text on X11, where it is
+    // served by this process.
+    thread::sleep(PASTE_SETTLE);
+    if let Err(error) = sent {
+        if let Some(snapshot) = snapshot {
+            let _ = snapshot.restore(&mut clipboard);
+        }
+        return Err(error);
+    }
+    Ok(snapshot.map(|snapshot| Pasted {
+        clipboard,
+        snapshot,
+    }))
+}
+
+fn restore(mut pasted: Pasted, text: &str, delay: Duration) -> bool {
+    thread::sleep(delay.saturating_sub(PASTE_SETTLE));
+    // Anything copied in the meantime is the user's to keep.
+    if pasted.clipboard.get_text().ok().as_deref() != Some(text) {
+        return false;
+    }
+    match pasted.snapshot.restore(&mut pasted.clipboard) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(target: "text_inject", "Failed to restore the clipboard: {}", e);
+            false
+        }
+    }
+}
+
+// Blocks until the text is typed and the clipboard is back; the time it
+// took ends at the paste, since the restore delay is not spent typing.
+pub fn inject(text: &str, mode: InjectMode) -> Result<Injected, AppError> {
+    let started = Instant::now();
+    let mut enigo = keyboard()?;
+    let config = CONFIG.lock().clone();
+    let pasted = match mode {
+        InjectMode::Keystrokes => {
+            enigo.text(text).map_err(failed)?;
+            None
+        }
+        InjectMode::Paste => paste(&mut enigo, text, config.restore_clipboard)?,
+    };
+    let elapsed = started.elapsed();
+    *LAST.lock() = Some(LastInjection {
+        chars: text.chars().count(),
+        app: window_tracker::focused(),
+    });
+    let clipboard_restored = pasted.map_or(false, |pasted| {
+        restore(pasted, text, Duration::from_millis(config.restore_delay_ms))
+    });
+    Ok(Injected {
+        elapsed,
+        clipboard_restored,
+    })
+}
+
+fn backspace(enigo: &mut Enigo, chars: usize) -> Result<(), AppError> {
+    for _ in 0..chars {
+        enigo
+            .key(Key::Backspace, Direction::Click)
+            .map_err(failed)?;
+    }
+    Ok(())
+}
+
+// What is already typed from partials stays: only the text after it is
+// typed, after backspacing to where the new text differs.
+fn continuation(text: &str, is_final: bool) -> (usize, String) {
+    let mut typed = TYPED.lock();
+    let common = typed
+        .chars()
+        .zip(text.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let erase = typed.chars().count() - common;
+    if is_final {
+        typed.clear();
+    } else {
+        *typed = text.to_string();
+    }
+    (erase, text.chars().skip(common).collect())
+}
+
+// Undo removes the whole utterance, not just its last piece.
+fn type_continuation(
+    text: &str,
+    is_final: bool,
+    mode: InjectMode,
+) -> Result<Option<Injected>, AppError> {
+    let (erase, rest) = continuation(text, is_final);
+    if erase == 0 && rest.is_empty() {
+        return Ok(None);
+    }
+    if erase > 0 {
+        backspace(&mut keyboard()?, erase)?;
+    }
+    let injected = inject(&rest, mode)?;
+    if let Some(last) = LAST.lock().as_mut() {
+        last.chars = text.chars().count();
+    }
+    Ok(Some(injected))
+}
+
+// Backspaces over the last injected text. It is kept when focus has moved to
+// another app, so the user can go back and try again.
+pub fn undo_last() -> Result<usize, AppError> {
+    let last = LAST
+        .lock()
+        .clone()
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, "Nothing has been typed to undo"))?;
+    let current = window_tracker::focused();
+    if let (Some(target), Some(current)) = (&last.app, &current) {
+        if target.app != current.app {
+            return Err(AppError::new(
+                ErrorCode::Failed,
+                format!(
+                    "The text was typed into {}, which no longer has focus",
+                    target.app
+                ),
+            )
+            .with_context("app", target.app.clone()));
+        }
+    }
+    let mut enigo = keyboard()?;
+    LAST.lock().take();
+    backspace(&mut enigo, last.chars)?;
+    Ok(last.chars)
+}
+
+fn completed(state: &AppState, mode: InjectMode, result: &Result<Injected, AppError>) {
+    state
+        .bus
+        .publish(AgentEvent::InjectionCompleted(InjectionOutcome {
+            mode,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|error| error.message.clone()),
+            clipboard_restored: result
+                .as_ref()
+                .map_or(false, |injected| injected.clipboard_restored),
+        }));
+}
+
+// The daemon is started with --no-type while the agent types, so each
+// transcript is typed once. The RemoteDesktop portal types on
+// Wayland instead. Partials are only typed once stabilized, and only their
+// progress is reported.
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::Transcript(t)) if t.is_final || CONFIG.lock().type_partials => {
+                    if !agent_types()
+                        || portal::injection_active()
+                        || window_tracker::dictation_disabled()
+                        || !agents::dictates(&state, &t)
+                        || t.text.trim().is_empty()
+                    {
+                        continue;
+                    }
+                    let mode = window_tracker::focused_profile()
+                        .and_then(|profile| profile.inject_mode)
+                        .unwrap_or_else(|| CONFIG.lock().mode);
+                    let text = redaction::text_for(Destination::Inject, &t);
+                    let is_final = t.is_final;
+                    let typed = tauri::async_runtime::spawn_blocking({
+                        let text = text.clone();
+                        move || type_continuation(&text, is_final, mode)
+                    })
+                    .await
+                    .unwrap_or_else(|e| {
+                        Err(AppError::from(format!("Injection task failed: {}", e)))
+                    });
+                    let typed = match typed {
+                        Ok(None) => continue,
+                        Ok(Some(_)) if !is_final => continue,
+                        Ok(Some(injected)) => Ok(injected),
+                        Err(error) => Err(error),
+                    };
+                    completed(&state, mode, &typed);
+                    match typed {
+                        Ok(injected) => {
+                            latency::record_injection(injected.elapsed.as_secs_f64() * 1000.0);
+                            a11y::transcript_inserted(&state, &text);
+                        }
+                        Err(error) => state.bus.publish(AgentEvent::error("text_inject", error)),
+                    }
+                }
+                // An utterance cut off by a stop has no final to finish it.
+                Ok(AgentEvent::ListeningChanged(false)) => TYPED.lock().clear(),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// Text sent from outside, rather than dictated; the configured mode unless
+// one is given.
+pub fn type_text(state: &AppState, text: &str, mode: Option<InjectMode>) -> Result<(), AppError> {
+    let mode = mode.unwrap_or_else(|| CONFIG.lock().mode);
+    let typed = inject(text, mode);
+    completed(state, mode, &typed);
+    typed.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn inject_text(
+    state: tauri::State<'_, AppState>,
+    text: String,
+    mode: Option<InjectMode>,
+) -> CommandResult<()> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || type_text(&state, &text, mode))
+        .await
+        .map_err(|e| AppError::from(format!("Injection task failed: {}", e)))?
+}
+
+// Returns how many characters were removed.
+#[tauri::command]
+pub async fn undo_last_injection() -> CommandResult<usize> {
+    tauri::async_runtime::spawn_blocking(undo_last)
+        .await
+        .map_err(|e| AppError::from(format!("Undo task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn get_inject_config() -> InjectConfig {
+    CONFIG.lock().clone()
+}
+
+// Auto-injection reaches the daemon's typing from its next start.
+#[tauri::command]
+pub fn set_inject_config(config: InjectConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
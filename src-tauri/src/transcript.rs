@@ -0,0 +1,64 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub id: String,
+    pub text: String,
+    pub is_final: bool,
+    pub timestamp: u64,
+    pub confidence: Option<f32>,
+    pub session_id: Option<String>,
+    pub start_ms: Option<u64>,
+    pub end_ms: Option<u64>,
+    pub speaker: Option<String>,
+    // A language code, as set or as the backend detected it.
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub translation: Option<Translation>,
+    #[serde(default)]
+    pub latency: Option<Latency>,
+    // Word by word, when the backend times them.
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+// Times are milliseconds from the start of the transcript, and the words are
+// as heard, before vocabulary and formatting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub word: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub confidence: Option<f32>,
+}
+
+// `text` stays the original; this is the same utterance in `language`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Translation {
+    pub language: String,
+    pub text: String,
+}
+
+// Per-stage durations of one utterance, in milliseconds. `vad_ms` runs from
+// the first loud audio block to the chunk being captured, and `total_ms` from
+// there to the transcript being published.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Latency {
+    pub vad_ms: Option<f64>,
+    pub queue_ms: f64,
+    pub stt_ms: f64,
+    pub handoff_ms: f64,
+    pub postprocess_ms: f64,
+    pub injection_ms: Option<f64>,
+    pub total_ms: f64,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,218 @@
+use std::future::Future;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::secrets;
+use crate::store;
+use crate::transcript::{Transcript, Translation};
+
+const STORE_FILE: &str = "translation.json";
+// Translation holds up the transcript, so a slow provider is given up on.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranslationProvider {
+    // Whisper's translate task, into English only, for in-process Whisper.
+    Whisper,
+    // A LibreTranslate server; the `libretranslate` API key is sent if set.
+    LibreTranslate { url: String },
+    // Uses the `deepl` API key; free keys go to the free API.
+    Deepl,
+}
+
+impl Default for TranslationProvider {
+    fn default() -> Self {
+        TranslationProvider::LibreTranslate {
+            url: "http://localhost:5000".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationConfig {
+    // A language code; translation is off while unset.
+    pub target: Option<String>,
+    pub provider: TranslationProvider,
+}
+
+static CONFIG: Lazy<Mutex<TranslationConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+// In-process Whisper translates while it transcribes; nothing else does.
+pub fn whisper_translates() -> bool {
+    let config = CONFIG.lock();
+    config.provider == TranslationProvider::Whisper && config.target.as_deref() == Some("en")
+}
+
+fn network(provider: &str, e: reqwest::Error) -> AppError {
+    AppError::new(
+        ErrorCode::Network,
+        format!("{} translation failed: {}", provider, e),
+    )
+}
+
+// Transcripts are delivered from plain threads and from Deepgram's task.
+fn block_on<F: Future>(future: F) -> F::Output {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+        Err(_) => tauri::async_runtime::block_on(future),
+    }
+}
+
+async fn libretranslate(
+    url: &str,
+    text: &str,
+    source: Option<&str>,
+    target: &str,
+) -> Result<String, AppError> {
+    let mut body = serde_json::json!({
+        "q": text,
+        "source": source.unwrap_or("auto"),
+        "target": target,
+        "format": "text",
+    });
+    if let Some(key) = secrets::api_key("libretranslate")? {
+        body["api_key"] = key.into();
+    }
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/translate", url.trim_end_matches('/')))
+        .timeout(TIMEOUT)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| network("LibreTranslate", e))?
+        .json()
+        .await
+        .map_err(|e| network("LibreTranslate", e))?;
+    Ok(response["translatedText"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+async fn deepl(text: &str, source: Option<&str>, target: &str) -> Result<String, AppError> {
+    let key = secrets::api_key("deepl")?
+        .ok_or_else(|| AppError::new(ErrorCode::InvalidInput, "No DeepL API key is configured"))?;
+    let host = if key.ends_with(":fx") {
+        "api-free.deepl.com"
+    } else {
+        "api.deepl.com"
+    };
+    // DeepL wants a variant of English.
+    let target = match target {
+        "en" => "EN-US".to_string(),
+        other => other.to_uppercase(),
+    };
+    let mut form = vec![("text", text.to_string()), ("target_lang", target)];
+    if let Some(source) = source {
+        form.push(("source_lang", source.to_uppercase()));
+    }
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("https://{}/v2/translate", host))
+        .timeout(TIMEOUT)
+        .header("Authorization", format!("DeepL-Auth-Key {}", key))
+        .form(&form)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| network("DeepL", e))?
+        .json()
+        .await
+        .map_err(|e| network("DeepL", e))?;
+    Ok(response["translations"][0]["text"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+// Adds the translation to a final transcript, unless it is already in the
+// target language or Whisper translated it.
+pub fn run(transcript: &mut Transcript) -> Result<(), AppError> {
+    let config = CONFIG.lock().clone();
+    let target = match config.target {
+        Some(target) if transcript.is_final && transcript.translation.is_none() => target,
+        _ => return Ok(()),
+    };
+    let source = transcript.language.as_deref();
+    if source == Some(target.as_str()) {
+        return Ok(());
+    }
+    let text = match &config.provider {
+        TranslationProvider::Whisper => return Ok(()),
+        TranslationProvider::LibreTranslate { url } => {
+            block_on(libretranslate(url, &transcript.text, source, &target))?
+        }
+        TranslationProvider::Deepl => block_on(deepl(&transcript.text, source, &target))?,
+    };
+    if !text.trim().is_empty() {
+        transcript.translation = Some(Translation {
+            language: target,
+            text: text.trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate(config: &TranslationConfig) -> Result<(), AppError> {
+    if let Some(target) = &config.target {
+        let valid = (2..=8).contains(&target.len())
+            && target
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !valid {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Not a language code: {}", target),
+            ));
+        }
+    }
+    match &config.provider {
+        TranslationProvider::Whisper if config.target.as_deref().map_or(false, |t| t != "en") => {
+            Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Whisper only translates into English",
+            ))
+        }
+        TranslationProvider::LibreTranslate { url } if url.trim().is_empty() => Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "The LibreTranslate URL is empty",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn save(config: TranslationConfig) -> CommandResult<()> {
+    validate(&config)?;
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_translation_config() -> TranslationConfig {
+    CONFIG.lock().clone()
+}
+
+// No target, or an empty one, turns translation off.
+#[tauri::command]
+pub fn set_translation_target(lang: Option<String>) -> CommandResult<()> {
+    let mut config = CONFIG.lock().clone();
+    config.target = lang
+        .map(|lang| lang.trim().to_string())
+        .filter(|lang| !lang.is_empty());
+    save(config)
+}
+
+#[tauri::command]
+pub fn set_translation_provider(provider: TranslationProvider) -> CommandResult<()> {
+    let mut config = CONFIG.lock().clone();
+    config.provider = provider;
+    save(config)
+}
@@ -0,0 +1,301 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Icon, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::audio;
+use crate::backends::{self, BackendKind};
+use crate::bus::{AgentEvent, EventBus};
+use crate::config;
+use crate::error::AppError;
+use crate::i18n;
+use crate::AppState;
+
+const SIZE: u32 = 32;
+// Levels are quantized so the 20 Hz meter redraws only when the ring changes.
+const LEVEL_STEPS: f32 = 4.0;
+const SNIPPET_CHARS: usize = 60;
+// How long a left click waits to see whether it is half of a double click.
+const DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    None,
+    ToggleDictation,
+    TogglePause,
+}
+
+// Saved as `[tray]` in config.toml. Tauri reports no middle click, so the
+// second binding is a double click.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrayConfig {
+    pub left_click: TrayAction,
+    pub double_click: TrayAction,
+}
+
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self {
+            left_click: TrayAction::ToggleDictation,
+            double_click: TrayAction::TogglePause,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayClick {
+    Left,
+    Double,
+}
+
+// What a click is about to do, drawn before the backend gets there.
+static FEEDBACK: OnceCell<mpsc::UnboundedSender<TrayState>> = OnceCell::new();
+// Bumped by every click, so a waiting left click knows a double click took
+// over.
+static CLICKS: AtomicU64 = AtomicU64::new(0);
+static LAST_DOUBLE: Lazy<Mutex<Option<Instant>>> = Lazy::new(Mutex::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrayState {
+    Idle,
+    Listening,
+    // Speech is being heard or partial transcripts are arriving.
+    Transcribing,
+    Paused,
+    Error,
+}
+
+impl TrayState {
+    fn color(self) -> [u8; 3] {
+        match self {
+            TrayState::Idle => [128, 128, 128],
+            TrayState::Listening => [0, 122, 255],
+            TrayState::Transcribing => [46, 160, 67],
+            TrayState::Paused => [255, 159, 10],
+            TrayState::Error => [220, 53, 69],
+        }
+    }
+}
+
+// Drawn at runtime, so the bundle needs no icon per state: a disc in the
+// state's colour, with a ring around it that grows with the input level.
+fn render(state: TrayState, level: u8) -> Icon {
+    let [r, g, b] = state.color();
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let disc = 9.0;
+    let ring = disc + 2.0 + level as f32 * 5.0 / LEVEL_STEPS;
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let distance = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            let alpha = if distance <= disc {
+                255
+            } else if level > 0 && distance > disc + 2.0 && distance <= ring {
+                160
+            } else {
+                0
+            };
+            rgba.extend_from_slice(&[r, g, b, alpha]);
+        }
+    }
+    Icon::Rgba {
+        rgba,
+        width: SIZE,
+        height: SIZE,
+    }
+}
+
+fn backend_label(kind: BackendKind) -> String {
+    i18n::t(match kind {
+        BackendKind::Local => "tray-backend-local",
+        BackendKind::Openai => "tray-backend-openai",
+        BackendKind::Deepgram => "tray-backend-deepgram",
+    })
+}
+
+fn tooltip(last: Option<&str>) -> String {
+    let backend = backend_label(backends::selected());
+    let title = i18n::t_args("tray-tooltip", &[("backend", &backend)]);
+    match last {
+        Some(text) => {
+            let mut snippet: String = text.chars().take(SNIPPET_CHARS).collect();
+            if text.chars().count() > SNIPPET_CHARS {
+                snippet.push('…');
+            }
+            format!(
+                "{}\n{}",
+                title,
+                i18n::t_args("tray-tooltip-last", &[("text", &snippet)])
+            )
+        }
+        None => title,
+    }
+}
+
+struct Tray {
+    app_handle: AppHandle,
+    state: TrayState,
+    level: u8,
+    last: Option<String>,
+    drawn: Option<(TrayState, u8)>,
+}
+
+impl Tray {
+    fn redraw(&mut self) {
+        // The ring only means something while capturing.
+        let level = match self.state {
+            TrayState::Listening | TrayState::Transcribing => self.level,
+            TrayState::Idle | TrayState::Paused | TrayState::Error => 0,
+        };
+        if self.drawn == Some((self.state, level)) {
+            return;
+        }
+        self.drawn = Some((self.state, level));
+        let _ = self
+            .app_handle
+            .tray_handle()
+            .set_icon(render(self.state, level));
+    }
+
+    fn retitle(&self) {
+        let _ = self
+            .app_handle
+            .tray_handle()
+            .set_tooltip(&tooltip(self.last.as_deref()));
+    }
+
+    fn set_state(&mut self, state: TrayState) {
+        self.state = state;
+        self.redraw();
+        self.retitle();
+    }
+
+    fn handle(&mut self, event: AgentEvent) {
+        match event {
+            AgentEvent::ListeningChanged(true) => self.set_state(TrayState::Listening),
+            AgentEvent::SttPaused(true) => self.set_state(TrayState::Paused),
+            AgentEvent::SttPaused(false) if self.state == TrayState::Paused => {
+                self.set_state(TrayState::Listening)
+            }
+            // An error stays up past the stop it caused, until the next start.
+            AgentEvent::ListeningChanged(false) if self.state != TrayState::Error => {
+                self.set_state(TrayState::Idle)
+            }
+            AgentEvent::SpeechStarted if self.state == TrayState::Listening => {
+                self.set_state(TrayState::Transcribing)
+            }
+            AgentEvent::SpeechEnded if self.state == TrayState::Transcribing => {
+                self.set_state(TrayState::Listening)
+            }
+            AgentEvent::Transcript(t) if t.is_final => {
+                self.last = Some(t.text);
+                if self.state == TrayState::Transcribing {
+                    self.set_state(TrayState::Listening);
+                } else {
+                    self.retitle();
+                }
+            }
+            AgentEvent::Transcript(_) if self.state == TrayState::Listening => {
+                self.set_state(TrayState::Transcribing)
+            }
+            AgentEvent::AudioLevel(level) => {
+                self.level = (level.rms.clamp(0.0, 1.0).sqrt() * LEVEL_STEPS).round() as u8;
+                self.redraw();
+            }
+            AgentEvent::DaemonCrashed { .. } | AgentEvent::Error { .. } => {
+                self.set_state(TrayState::Error)
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn spawn(app_handle: AppHandle, bus: &EventBus) {
+    let mut events = bus.subscribe();
+    let mut tray = Tray {
+        app_handle,
+        state: TrayState::Idle,
+        level: 0,
+        last: None,
+        drawn: None,
+    };
+    let (tx, mut feedback) = mpsc::unbounded_channel();
+    let _ = FEEDBACK.set(tx);
+    tauri::async_runtime::spawn(async move {
+        tray.set_state(TrayState::Idle);
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => tray.handle(event),
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+                Some(state) = feedback.recv() => tray.set_state(state),
+            }
+        }
+    });
+}
+
+fn run(app_handle: &AppHandle, action: TrayAction) {
+    let state = app_handle.state::<AppState>().inner().clone();
+    let (expected, action): (TrayState, fn(&AppState) -> Result<String, AppError>) = match action {
+        TrayAction::None => return,
+        TrayAction::ToggleDictation if backends::is_running(&state) => {
+            (TrayState::Idle, backends::toggle)
+        }
+        TrayAction::ToggleDictation => (TrayState::Listening, backends::toggle),
+        // Nothing to pause while idle.
+        TrayAction::TogglePause if !backends::is_running(&state) => return,
+        TrayAction::TogglePause if audio::is_paused() => {
+            (TrayState::Listening, backends::toggle_paused)
+        }
+        TrayAction::TogglePause => (TrayState::Paused, backends::toggle_paused),
+    };
+    if let Some(feedback) = FEEDBACK.get() {
+        let _ = feedback.send(expected);
+    }
+    // A failure turns the icon red, so the optimistic state does not stick.
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(error) = action(&state) {
+            state.bus.publish(AgentEvent::error("tray", error));
+        }
+    });
+}
+
+// A left click waits out the double-click window only while a double click
+// does something. Windows also reports the release that ends a double click
+// as a left click, which is dropped.
+pub fn clicked(app_handle: &AppHandle, click: TrayClick) {
+    let config = config::current().tray;
+    let generation = CLICKS.fetch_add(1, Ordering::SeqCst) + 1;
+    match click {
+        TrayClick::Double => {
+            *LAST_DOUBLE.lock() = Some(Instant::now());
+            run(app_handle, config.double_click);
+        }
+        TrayClick::Left
+            if LAST_DOUBLE
+                .lock()
+                .map_or(false, |at| at.elapsed() < DOUBLE_CLICK_WINDOW) => {}
+        TrayClick::Left if config.double_click == TrayAction::None => {
+            run(app_handle, config.left_click)
+        }
+        TrayClick::Left => {
+            let app_handle = app_handle.clone();
+            thread::spawn(move || {
+                thread::sleep(DOUBLE_CLICK_WINDOW);
+                if CLICKS.load(Ordering::SeqCst) == generation {
+                    run(&app_handle, config.left_click);
+                }
+            });
+        }
+    }
+}
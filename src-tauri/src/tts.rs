@@ -0,0 +1,628 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio;
+use crate::bus::AgentEvent;
+use crate::children::ChildGuard;
+use crate::daemon::{self, InputSource};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::secrets;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "tts.json";
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// The room echoes the agent's voice for a moment after it stops.
+const DUCK_RELEASE: Duration = Duration::from_millis(300);
+// OpenAI's `pcm` format: 24 kHz mono s16le.
+const OPENAI_SAMPLE_RATE: u32 = 24_000;
+const OPENAI_VOICES: [&str; 6] = ["alloy", "echo", "fable", "onyx", "nova", "shimmer"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsEngineKind {
+    // `say` on macOS, SAPI on Windows, espeak-ng on Linux.
+    Native,
+    Piper,
+    Openai,
+}
+
+impl Default for TtsEngineKind {
+    fn default() -> Self {
+        TtsEngineKind::Native
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PiperConfig {
+    pub binary: String,
+    // Holds `<voice>.onnx` with its `<voice>.onnx.json`; `<data dir>/piper`
+    // when unset.
+    pub voices_dir: Option<PathBuf>,
+}
+
+impl Default for PiperConfig {
+    fn default() -> Self {
+        Self {
+            binary: "piper".to_string(),
+            voices_dir: None,
+        }
+    }
+}
+
+impl PiperConfig {
+    fn voices_dir(&self) -> PathBuf {
+        self.voices_dir
+            .clone()
+            .unwrap_or_else(|| store::data_dir().join("piper"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiTtsConfig {
+    pub model: String,
+    // For OpenAI-compatible servers.
+    pub base_url: String,
+}
+
+impl Default for OpenAiTtsConfig {
+    fn default() -> Self {
+        Self {
+            model: "tts-1".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TtsConfig {
+    pub engine: TtsEngineKind,
+    // The engine's default voice when unset.
+    pub voice: Option<String>,
+    // Mutes the microphone while speaking, so the agent does not transcribe
+    // itself.
+    pub duck_microphone: bool,
+    pub piper: PiperConfig,
+    pub openai: OpenAiTtsConfig,
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            engine: TtsEngineKind::default(),
+            voice: None,
+            duck_microphone: true,
+            piper: PiperConfig::default(),
+            openai: OpenAiTtsConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Voice {
+    // What `speak` takes as `voice`.
+    pub id: String,
+    pub name: String,
+    pub language: Option<String>,
+}
+
+trait TtsEngine {
+    fn voices(&self) -> Result<Vec<Voice>, AppError>;
+    // Blocks until the text has been spoken, or `stop` is set.
+    fn speak(&self, text: &str, voice: Option<&str>, stop: &AtomicBool) -> Result<(), AppError>;
+}
+
+static CONFIG: Lazy<Mutex<TtsConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// The utterance being spoken, to stop it.
+static CURRENT: Lazy<Mutex<Option<Arc<AtomicBool>>>> = Lazy::new(Mutex::default);
+// One utterance at a time; a new one waits for the stopped one to let go.
+static SPEAKING: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+
+fn failed(e: impl std::fmt::Display) -> AppError {
+    AppError::from(format!("Speech failed: {}", e))
+}
+
+// Feeds the text on stdin and waits for the program, killing it on stop.
+fn run(mut command: Command, text: &str, stop: &AtomicBool) -> Result<(), AppError> {
+    let child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Unsupported,
+                format!("Failed to run {:?}: {}", command.get_program(), e),
+            )
+        })?;
+    let mut child = ChildGuard::new(child);
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).map_err(failed)?;
+    }
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        match child.try_wait().map_err(failed)? {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => {
+                return Err(failed(format!(
+                    "{:?} exited with {}",
+                    command.get_program(),
+                    status
+                )))
+            }
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+fn output<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Vec<f32>>,
+    position: Arc<AtomicUsize>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = (config.channels as usize).max(1);
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut i = position.load(Ordering::Relaxed);
+            for frame in data.chunks_mut(channels) {
+                let sample = samples.get(i).copied().unwrap_or(0.0);
+                i = (i + 1).min(samples.len());
+                for out in frame {
+                    *out = T::from_sample(sample);
+                }
+            }
+            position.store(i, Ordering::Relaxed);
+        },
+        |error: cpal::StreamError| {
+            tracing::warn!(target: "tts", "Audio output failed: {}", error);
+        },
+        None,
+    )
+}
+
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let last = samples.len() - 1;
+    let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    (0..len)
+        .map(|i| {
+            let at = i as f64 * from as f64 / to as f64;
+            let j = (at as usize).min(last);
+            let t = (at - j as f64) as f32;
+            samples[j] + (samples[(j + 1).min(last)] - samples[j]) * t
+        })
+        .collect()
+}
+
+// Plays mono samples on the default output device. The stream is not Send
+// everywhere, so it stays on the calling thread.
+fn play(samples: &[f32], sample_rate: u32, stop: &AtomicBool) -> Result<(), AppError> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, "No default output device"))?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| AppError::from(format!("Failed to query the output device: {}", e)))?;
+    let config = supported.config();
+    let samples = Arc::new(resample(samples, sample_rate, config.sample_rate.0));
+    let position = Arc::new(AtomicUsize::new(0));
+    let (shared, at) = (samples.clone(), position.clone());
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => output::<f32>(&device, &config, shared, at),
+        SampleFormat::I16 => output::<i16>(&device, &config, shared, at),
+        SampleFormat::U16 => output::<u16>(&device, &config, shared, at),
+        other => {
+            return Err(AppError::new(
+                ErrorCode::Unsupported,
+                format!("Unsupported sample format {:?}", other),
+            ))
+        }
+    }
+    .map_err(|e| AppError::from(format!("Failed to open the output stream: {}", e)))?;
+    stream
+        .play()
+        .map_err(|e| AppError::from(format!("Failed to start the output stream: {}", e)))?;
+    while position.load(Ordering::Relaxed) < samples.len() && !stop.load(Ordering::SeqCst) {
+        thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+fn read_wav(path: &std::path::Path) -> Result<(Vec<f32>, u32), AppError> {
+    let reader = hound::WavReader::open(path).map_err(failed)?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(failed)?,
+        hound::SampleFormat::Int => {
+            let scale = (1u32 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(failed)?
+        }
+    };
+    let channels = (spec.channels as usize).max(1);
+    let mono = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    Ok((mono, spec.sample_rate))
+}
+
+struct Native;
+
+impl Native {
+    #[cfg(target_os = "macos")]
+    fn command(voice: Option<&str>) -> Command {
+        let mut command = Command::new("say");
+        if let Some(voice) = voice {
+            command.args(["-v", voice]);
+        }
+        command
+    }
+
+    #[cfg(windows)]
+    fn command(voice: Option<&str>) -> Command {
+        let mut command = Command::new("powershell");
+        command
+            .args([
+                "-NoProfile",
+                "-Command",
+                "Add-Type -AssemblyName System.Speech; \
+                 $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                 if ($env:VOICE_MCP_TTS_VOICE) { $s.SelectVoice($env:VOICE_MCP_TTS_VOICE) }; \
+                 $s.Speak([Console]::In.ReadToEnd())",
+            ])
+            .env("VOICE_MCP_TTS_VOICE", voice.unwrap_or_default());
+        command
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn command(voice: Option<&str>) -> Command {
+        let mut command = Command::new("espeak-ng");
+        if let Some(voice) = voice {
+            command.args(["-v", voice]);
+        }
+        command.arg("--stdin");
+        command
+    }
+
+    fn listing(command: &mut Command) -> Result<String, AppError> {
+        let output = command.output().map_err(|e| {
+            AppError::new(
+                ErrorCode::Unsupported,
+                format!("Failed to list voices: {}", e),
+            )
+        })?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl TtsEngine for Native {
+    // "Alex                en_US    # Most people recognize me by my voice."
+    #[cfg(target_os = "macos")]
+    fn voices(&self) -> Result<Vec<Voice>, AppError> {
+        let listing = Self::listing(Command::new("say").args(["-v", "?"]))?;
+        Ok(listing
+            .lines()
+            .filter_map(|line| {
+                let line = line.split('#').next()?.trim_end();
+                let (name, language) = line.rsplit_once(char::is_whitespace)?;
+                Some(Voice {
+                    id: name.trim().to_string(),
+                    name: name.trim().to_string(),
+                    language: Some(language.replace('_', "-")),
+                })
+            })
+            .collect())
+    }
+
+    // "Microsoft Zira Desktop|en-US"
+    #[cfg(windows)]
+    fn voices(&self) -> Result<Vec<Voice>, AppError> {
+        let listing = Self::listing(Command::new("powershell").args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | \
+             ForEach-Object { $_.VoiceInfo.Name + '|' + $_.VoiceInfo.Culture.Name }",
+        ]))?;
+        Ok(listing
+            .lines()
+            .filter_map(|line| {
+                let (name, language) = line.trim().split_once('|')?;
+                Some(Voice {
+                    id: name.to_string(),
+                    name: name.to_string(),
+                    language: Some(language.to_string()),
+                })
+            })
+            .collect())
+    }
+
+    // " 5  en-us           --/M      English_(America)  gmw/en-US  (en 2)"
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn voices(&self) -> Result<Vec<Voice>, AppError> {
+        let listing = Self::listing(Command::new("espeak-ng").arg("--voices"))?;
+        Ok(listing
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                let (language, name) = (columns.get(1)?, columns.get(3)?);
+                Some(Voice {
+                    id: language.to_string(),
+                    name: name.replace('_', " "),
+                    language: Some(language.to_string()),
+                })
+            })
+            .collect())
+    }
+
+    fn speak(&self, text: &str, voice: Option<&str>, stop: &AtomicBool) -> Result<(), AppError> {
+        run(Self::command(voice), text, stop)
+    }
+}
+
+struct Piper(PiperConfig);
+
+impl TtsEngine for Piper {
+    fn voices(&self) -> Result<Vec<Voice>, AppError> {
+        let entries = match std::fs::read_dir(self.0.voices_dir()) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let mut voices: Vec<Voice> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "onnx" {
+                    return None;
+                }
+                // "en_US-lessac-medium"
+                let id = path.file_stem()?.to_string_lossy().into_owned();
+                Some(Voice {
+                    name: id.replace(|c| c == '-' || c == '_', " "),
+                    language: id.split('-').next().map(|l| l.replace('_', "-")),
+                    id,
+                })
+            })
+            .collect();
+        voices.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(voices)
+    }
+
+    fn speak(&self, text: &str, voice: Option<&str>, stop: &AtomicBool) -> Result<(), AppError> {
+        let voice = match voice {
+            Some(voice) => voice.to_string(),
+            None => self
+                .voices()?
+                .into_iter()
+                .next()
+                .map(|v| v.id)
+                .ok_or_else(|| {
+                    AppError::new(
+                        ErrorCode::NotFound,
+                        format!("No Piper voices in {}", self.0.voices_dir().display()),
+                    )
+                })?,
+        };
+        let model = self.0.voices_dir().join(format!("{}.onnx", voice));
+        if !model.exists() {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No Piper voice named {}", voice),
+            ));
+        }
+        let wav = std::env::temp_dir().join(format!("voice-mcp-tts-{}.wav", uuid::Uuid::new_v4()));
+        let mut command = Command::new(&self.0.binary);
+        command
+            .arg("--model")
+            .arg(&model)
+            .arg("--output_file")
+            .arg(&wav);
+        let result = run(command, text, stop).and_then(|_| {
+            if stop.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            let (samples, sample_rate) = read_wav(&wav)?;
+            play(&samples, sample_rate, stop)
+        });
+        let _ = std::fs::remove_file(&wav);
+        result
+    }
+}
+
+struct OpenAi(OpenAiTtsConfig);
+
+impl TtsEngine for OpenAi {
+    fn voices(&self) -> Result<Vec<Voice>, AppError> {
+        Ok(OPENAI_VOICES
+            .iter()
+            .map(|voice| Voice {
+                id: voice.to_string(),
+                name: voice.to_string(),
+                language: None,
+            })
+            .collect())
+    }
+
+    fn speak(&self, text: &str, voice: Option<&str>, stop: &AtomicBool) -> Result<(), AppError> {
+        let api_key = secrets::api_key("openai")?.ok_or_else(|| {
+            AppError::new(ErrorCode::InvalidInput, "No OpenAI API key is configured")
+        })?;
+        let body = serde_json::json!({
+            "model": self.0.model,
+            "input": text,
+            "voice": voice.unwrap_or(OPENAI_VOICES[0]),
+            "response_format": "pcm",
+        });
+        let network = |e: reqwest::Error| {
+            AppError::new(ErrorCode::Network, format!("OpenAI speech failed: {}", e))
+        };
+        let url = format!("{}/audio/speech", self.0.base_url.trim_end_matches('/'));
+        let pcm = tauri::async_runtime::block_on(async {
+            reqwest::Client::new()
+                .post(url)
+                .bearer_auth(api_key)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())?
+                .bytes()
+                .await
+        })
+        .map_err(network)?;
+        let samples: Vec<f32> = pcm
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32)
+            .collect();
+        play(&samples, OPENAI_SAMPLE_RATE, stop)
+    }
+}
+
+fn engine(config: &TtsConfig) -> Box<dyn TtsEngine> {
+    match config.engine {
+        TtsEngineKind::Native => Box::new(Native),
+        TtsEngineKind::Piper => Box::new(Piper(config.piper.clone())),
+        TtsEngineKind::Openai => Box::new(OpenAi(config.openai.clone())),
+    }
+}
+
+// Native capture drops the microphone like a pause does, and a daemon reading
+// the microphone itself is told to drop its own. A user's pause is left alone.
+fn duck(state: &AppState, ducked: bool) {
+    audio::set_ducked(ducked);
+    if audio::is_paused() || daemon::input_source(state) != Some(InputSource::Microphone) {
+        return;
+    }
+    if let Err(e) = daemon::configure(state, serde_json::json!({ "paused": ducked })) {
+        tracing::warn!(target: "tts", "{}", e.message);
+    }
+}
+
+// Blocks until the text has been spoken, or another `speak` or
+// `stop_speaking` cuts it off.
+pub fn speak_now(state: &AppState, text: &str, voice: Option<&str>) -> Result<(), AppError> {
+    if text.trim().is_empty() {
+        return Err(AppError::new(ErrorCode::InvalidInput, "Nothing to speak"));
+    }
+    stop();
+    let stop = Arc::new(AtomicBool::new(false));
+    let _speaking = SPEAKING.lock();
+    *CURRENT.lock() = Some(stop.clone());
+    let config = CONFIG.lock().clone();
+    let voice = voice.map(str::to_string).or_else(|| config.voice.clone());
+    if config.duck_microphone {
+        duck(state, true);
+    }
+    state.bus.publish(AgentEvent::SpeakingChanged(true));
+    let result = engine(&config).speak(text, voice.as_deref(), &stop);
+    if config.duck_microphone {
+        if !stop.load(Ordering::SeqCst) {
+            thread::sleep(DUCK_RELEASE);
+        }
+        duck(state, false);
+    }
+    state.bus.publish(AgentEvent::SpeakingChanged(false));
+    let mut current = CURRENT.lock();
+    if current
+        .as_ref()
+        .map_or(false, |current| Arc::ptr_eq(current, &stop))
+    {
+        current.take();
+    }
+    result
+}
+
+pub fn stop() {
+    if let Some(current) = CURRENT.lock().take() {
+        current.store(true, Ordering::SeqCst);
+    }
+}
+
+// `speak_requested` from MQTT and the API goes out through the configured
+// engine.
+pub fn spawn(state: AppState) {
+    let mut events = state.bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::SpeakRequested(text)) => {
+                    let state = state.clone();
+                    thread::spawn(move || {
+                        if let Err(error) = speak_now(&state, &text, None) {
+                            state.bus.publish(AgentEvent::error("tts", error));
+                        }
+                    });
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn speak(
+    state: tauri::State<'_, AppState>,
+    text: String,
+    voice: Option<String>,
+) -> CommandResult<()> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || speak_now(&state, &text, voice.as_deref()))
+        .await
+        .map_err(|e| AppError::from(format!("Speech task failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn stop_speaking() {
+    stop();
+}
+
+#[tauri::command]
+pub async fn list_voices() -> CommandResult<Vec<Voice>> {
+    let config = CONFIG.lock().clone();
+    tauri::async_runtime::spawn_blocking(move || engine(&config).voices())
+        .await
+        .map_err(|e| AppError::from(format!("Voice listing failed: {}", e)))?
+}
+
+#[tauri::command]
+pub fn get_tts_config() -> TtsConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_tts_config(config: TtsConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, UpdaterEvent};
+
+use crate::backends;
+use crate::error::{AppError, CommandResult};
+use crate::events::{self, UiEvent};
+use crate::i18n;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "updater.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const RELEASES: &str = "https://github.com/delorenj/voice-mcp-agent/releases";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(&self) -> String {
+        match self {
+            UpdateChannel::Stable => format!("{}/latest/download/latest.json", RELEASES),
+            UpdateChannel::Beta => format!("{}/download/beta/latest.json", RELEASES),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    pub channel: UpdateChannel,
+    // Off leaves checking to `check_for_updates` and the tray.
+    pub check_on_startup: bool,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            check_on_startup: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub notes: Option<String>,
+    pub channel: UpdateChannel,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum UpdateProgress {
+    Downloading {
+        downloaded: u64,
+        // None when the server sends no length.
+        total: Option<u64>,
+    },
+    Installing,
+    Failed {
+        message: String,
+    },
+}
+
+pub struct UpdaterState {
+    config: Mutex<UpdateConfig>,
+    available: Mutex<Option<UpdateInfo>>,
+    downloaded: Mutex<u64>,
+}
+
+impl UpdaterState {
+    pub fn load() -> Self {
+        Self {
+            config: Mutex::new(store::load_json(STORE_FILE)),
+            available: Mutex::new(None),
+            downloaded: Mutex::new(0),
+        }
+    }
+}
+
+fn set_tray_update(app: &AppHandle, update: Option<&UpdateInfo>) {
+    let _ = app
+        .tray_handle()
+        .get_item("update")
+        .set_title(match update {
+            Some(update) => i18n::t_args("tray-install-update", &[("version", &update.version)]),
+            None => i18n::t("tray-check-updates"),
+        });
+}
+
+pub fn relabel_tray(app: &AppHandle) {
+    let update = app.state::<UpdaterState>().available.lock().clone();
+    set_tray_update(app, update.as_ref());
+}
+
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let updater = app.state::<UpdaterState>();
+    let channel = updater.config.lock().channel;
+    let response = tauri::updater::builder(app.clone())
+        .endpoints(&[channel.endpoint()])
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let update = if response.is_update_available() {
+        Some(UpdateInfo {
+            version: response.latest_version().to_string(),
+            notes: response.body().cloned(),
+            channel,
+        })
+    } else {
+        None
+    };
+    *updater.available.lock() = update.clone();
+    set_tray_update(app, update.as_ref());
+    if let Some(update) = &update {
+        events::emit(app, UiEvent::UpdateAvailable(update.clone()));
+    }
+    Ok(update)
+}
+
+// Nothing may still be writing when the binary is swapped out: listening is
+// stopped first so its session ends cleanly and every transcript has been
+// published before the installer runs.
+fn prepare_for_install(state: &AppState) {
+    if backends::is_running(state) {
+        if let Err(e) = backends::stop(state) {
+            tracing::warn!("Failed to stop listening before update: {}", e);
+        }
+    }
+}
+
+pub async fn install(app: &AppHandle) -> Result<(), String> {
+    let channel = app.state::<UpdaterState>().config.lock().channel;
+    let response = tauri::updater::builder(app.clone())
+        .endpoints(&[channel.endpoint()])
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    if !response.is_update_available() {
+        return Err("No update available".to_string());
+    }
+
+    let state = app.state::<AppState>().inner().clone();
+    tauri::async_runtime::spawn_blocking(move || prepare_for_install(&state))
+        .await
+        .map_err(|e| format!("Failed to prepare for update: {}", e))?;
+    tracing::info!("Installing update {}", response.latest_version());
+    *app.state::<UpdaterState>().downloaded.lock() = 0;
+    response
+        .download_and_install()
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+    app.restart();
+    Ok(())
+}
+
+// Reported to the UI as `update_progress` while an update downloads.
+pub fn on_event(app: &AppHandle, event: UpdaterEvent) {
+    let progress = match event {
+        UpdaterEvent::DownloadProgress {
+            chunk_length,
+            content_length,
+        } => {
+            let updater = app.state::<UpdaterState>();
+            let mut downloaded = updater.downloaded.lock();
+            *downloaded += chunk_length as u64;
+            UpdateProgress::Downloading {
+                downloaded: *downloaded,
+                total: content_length,
+            }
+        }
+        UpdaterEvent::Downloaded => UpdateProgress::Installing,
+        UpdaterEvent::Error(message) => UpdateProgress::Failed { message },
+        _ => return,
+    };
+    events::emit(app, UiEvent::UpdateProgress(progress));
+}
+
+// Re-reads the flag before each check, so turning it off also stops the
+// periodic checks.
+pub fn spawn_checker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let enabled = app.state::<UpdaterState>().config.lock().check_on_startup;
+            if enabled {
+                if let Err(e) = check(&app).await {
+                    tracing::warn!("{}", e);
+                }
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// The tray item doubles as the update indicator: it installs a known update
+// and otherwise checks for one.
+pub fn on_tray_click(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let pending = app.state::<UpdaterState>().available.lock().is_some();
+        let result = if pending {
+            install(&app).await
+        } else {
+            check(&app).await.map(|_| ())
+        };
+        if let Err(e) = result {
+            tracing::warn!("{}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_update_config(updater: tauri::State<'_, UpdaterState>) -> UpdateConfig {
+    updater.config.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_update_config(
+    updater: tauri::State<'_, UpdaterState>,
+    config: UpdateConfig,
+) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *updater.config.lock() = config;
+    *updater.available.lock() = None;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> CommandResult<Option<UpdateInfo>> {
+    check(&app).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> CommandResult<()> {
+    install(&app).await.map_err(AppError::from)
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio;
+use crate::bus::{AgentEvent, EventBus};
+use crate::daemon::{self, InputSource};
+use crate::error::CommandResult;
+use crate::store;
+use crate::transcript::now_millis;
+use crate::AppState;
+
+const STORE_FILE: &str = "usage.json";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+// Counts stay on this machine; nothing here is ever sent anywhere.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageMetrics {
+    pub enabled: bool,
+    pub since: u64,
+    pub features: BTreeMap<String, u64>,
+    pub errors: BTreeMap<String, u64>,
+}
+
+static USAGE: Lazy<Mutex<UsageMetrics>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+fn bump(key: String, errors: bool) {
+    let mut usage = USAGE.lock();
+    if !usage.enabled {
+        return;
+    }
+    let counts = if errors {
+        &mut usage.errors
+    } else {
+        &mut usage.features
+    };
+    *counts.entry(key).or_insert(0) += 1;
+    DIRTY.store(true, Ordering::Relaxed);
+}
+
+pub fn record(feature: &str) {
+    bump(feature.to_string(), false);
+}
+
+// `detail` narrows the count, e.g. which plugin failed.
+pub fn record_error(kind: &str, detail: Option<&str>) {
+    let key = match detail {
+        Some(detail) => format!("{}:{}", kind, detail),
+        None => kind.to_string(),
+    };
+    bump(key, true);
+}
+
+pub fn flush() {
+    if DIRTY.swap(false, Ordering::Relaxed) {
+        let usage = USAGE.lock().clone();
+        if let Err(e) = store::save_json(STORE_FILE, &usage) {
+            tracing::warn!("{}", e);
+        }
+    }
+}
+
+fn source_name(source: Option<InputSource>) -> &'static str {
+    match source {
+        Some(InputSource::Microphone) | None => "microphone",
+        Some(InputSource::Stream) => "stream",
+        Some(InputSource::Remote) => "remote",
+    }
+}
+
+pub fn spawn_recorder(bus: &EventBus, state: AppState) {
+    let mut rx = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut flush_timer = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            let event = tokio::select! {
+                _ = flush_timer.tick() => {
+                    flush();
+                    continue;
+                }
+                event = rx.recv() => event,
+            };
+            match event {
+                Ok(AgentEvent::Transcript(t)) if t.is_final => {
+                    let source = if t.speaker.as_deref() == Some(audio::SYSTEM_SPEAKER) {
+                        "system"
+                    } else if daemon::is_remote_speaker(&state, &t) {
+                        "room"
+                    } else {
+                        source_name(daemon::input_source(&state))
+                    };
+                    record(&format!("utterance.{}", source));
+                }
+                Ok(AgentEvent::ListeningChanged(true)) => record("listening.start"),
+                Ok(AgentEvent::ProfileChanged(_)) => record("profile.switch"),
+                Ok(AgentEvent::RepeatRequested(_)) => record("repeat"),
+                Ok(AgentEvent::SpeakRequested(_)) => record("speak"),
+                Ok(AgentEvent::MeetingPrompt { .. }) => record("calendar.prompt"),
+                Ok(AgentEvent::SessionTagged { .. }) => record("calendar.capture"),
+                Ok(AgentEvent::DaemonCrashed { .. }) => record_error("daemon.crash", None),
+                Ok(AgentEvent::Error { source, .. }) => record_error(&source, None),
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+        flush();
+    });
+}
+
+#[tauri::command]
+pub fn get_metrics() -> UsageMetrics {
+    USAGE.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_metrics_enabled(enabled: bool) -> CommandResult<()> {
+    let usage = {
+        let mut usage = USAGE.lock();
+        if enabled && !usage.enabled {
+            usage.since = now_millis();
+        }
+        usage.enabled = enabled;
+        usage.clone()
+    };
+    store::save_json(STORE_FILE, &usage)
+}
+
+#[tauri::command]
+pub fn clear_metrics() -> CommandResult<()> {
+    let usage = {
+        let mut usage = USAGE.lock();
+        *usage = UsageMetrics {
+            enabled: usage.enabled,
+            since: now_millis(),
+            ..Default::default()
+        };
+        usage.clone()
+    };
+    DIRTY.store(false, Ordering::Relaxed);
+    store::save_json(STORE_FILE, &usage)
+}
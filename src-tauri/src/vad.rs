@@ -0,0 +1,324 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use webrtc_vad::{SampleRate, Vad, VadMode};
+
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "vad.json";
+// 30 ms at 16 kHz, the longest frame WebRTC VAD accepts.
+const FRAME: usize = 480;
+const FRAME_MS: u32 = 30;
+const OFF: u8 = u8::MAX;
+const MAX_SENSITIVITY: u8 = 3;
+const MAX_PRE_ROLL_MS: u32 = 2_000;
+const SILENCE_TIMEOUT_MS: (u32, u32) = (100, 10_000);
+const MAX_UTTERANCE_MS: (u32, u32) = (1_000, 120_000);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    // 0 (only clear speech) to 3 (anything voice-like); None sends all audio.
+    pub sensitivity: Option<u8>,
+    pub capture: CaptureParams,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            sensitivity: Some(2),
+            capture: CaptureParams::default(),
+        }
+    }
+}
+
+// Where utterances start and end, both here and in the daemon, which segments
+// its own microphone capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureParams {
+    // Audio from just before speech is detected, so its first syllable is kept.
+    pub pre_roll_ms: u32,
+    // Quiet that ends an utterance; pauses shorter than this stay inside it.
+    pub silence_timeout_ms: u32,
+    // Speech after which the utterance is cut even without a pause.
+    pub max_utterance_ms: u32,
+}
+
+impl Default for CaptureParams {
+    fn default() -> Self {
+        Self {
+            pre_roll_ms: 300,
+            silence_timeout_ms: 700,
+            max_utterance_ms: 30_000,
+        }
+    }
+}
+
+impl CaptureParams {
+    fn seconds(ms: u32) -> String {
+        (f64::from(ms) / 1000.0).to_string()
+    }
+
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            "--pre-roll".to_string(),
+            Self::seconds(self.pre_roll_ms),
+            "--silence-timeout".to_string(),
+            Self::seconds(self.silence_timeout_ms),
+            "--max-utterance".to_string(),
+            Self::seconds(self.max_utterance_ms),
+        ]
+    }
+
+    // The daemon's `configure` takes seconds, like its flags.
+    fn daemon_params(&self) -> serde_json::Value {
+        serde_json::json!({
+            "pre_roll": f64::from(self.pre_roll_ms) / 1000.0,
+            "silence_timeout": f64::from(self.silence_timeout_ms) / 1000.0,
+            "max_utterance": f64::from(self.max_utterance_ms) / 1000.0,
+        })
+    }
+
+    fn validate(&self) -> CommandResult<()> {
+        let (min_silence, max_silence) = SILENCE_TIMEOUT_MS;
+        let (min_utterance, max_utterance) = MAX_UTTERANCE_MS;
+        let problem = if self.pre_roll_ms > MAX_PRE_ROLL_MS {
+            format!("Pre-roll is at most {} ms", MAX_PRE_ROLL_MS)
+        } else if self.silence_timeout_ms < min_silence || self.silence_timeout_ms > max_silence {
+            format!(
+                "The silence timeout is {} to {} ms",
+                min_silence, max_silence
+            )
+        } else if self.max_utterance_ms < min_utterance || self.max_utterance_ms > max_utterance {
+            format!(
+                "The longest utterance is {} to {} ms",
+                min_utterance, max_utterance
+            )
+        } else if self.max_utterance_ms <= self.silence_timeout_ms {
+            "The longest utterance must be longer than the silence timeout".to_string()
+        } else {
+            return Ok(());
+        };
+        Err(AppError::new(ErrorCode::InvalidInput, problem))
+    }
+}
+
+static CONFIG: Lazy<Mutex<VadConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+// Mirrors the config for the audio thread, which should not take locks.
+static LEVEL: Lazy<AtomicU8> =
+    Lazy::new(|| AtomicU8::new(CONFIG.lock().sensitivity.unwrap_or(OFF)));
+// The capture params in frames, for the same reason.
+static PRE_ROLL_FRAMES: Lazy<AtomicU32> =
+    Lazy::new(|| AtomicU32::new(frames(CONFIG.lock().capture.pre_roll_ms)));
+static HANGOVER_FRAMES: Lazy<AtomicU32> =
+    Lazy::new(|| AtomicU32::new(frames(CONFIG.lock().capture.silence_timeout_ms)));
+static MAX_UTTERANCE_FRAMES: Lazy<AtomicU32> =
+    Lazy::new(|| AtomicU32::new(frames(CONFIG.lock().capture.max_utterance_ms)));
+
+fn frames(ms: u32) -> u32 {
+    ms / FRAME_MS
+}
+
+pub fn capture_params() -> CaptureParams {
+    CONFIG.lock().capture
+}
+
+fn mode(level: u8) -> VadMode {
+    match level {
+        0 => VadMode::VeryAggressive,
+        1 => VadMode::Aggressive,
+        2 => VadMode::LowBitrate,
+        _ => VadMode::Quality,
+    }
+}
+
+// libfvad's handle is plain heap state with no thread affinity; it is only
+// ever used by one thread at a time, the one running the audio callback.
+struct Detector(Vad);
+
+unsafe impl Send for Detector {}
+
+impl Detector {
+    fn new(level: u8) -> Self {
+        Detector(Vad::new_with_rate_and_mode(
+            SampleRate::Rate16kHz,
+            mode(level),
+        ))
+    }
+}
+
+// Sits between capture and the sink: passes speech with the pre-roll before
+// it and the hangover after it, drops the rest, and reports where speech
+// starts and ends. Samples are delayed by at most one frame.
+pub struct Gate {
+    state: AppState,
+    // Off for a second stream alongside the main one.
+    announce: bool,
+    detector: Option<(u8, Detector)>,
+    frame: Vec<f32>,
+    pcm: Vec<i16>,
+    speaking: bool,
+    quiet_frames: u32,
+    // Frames passed since speech started.
+    spoken_frames: u32,
+    pre_roll: VecDeque<f32>,
+}
+
+impl Gate {
+    pub fn new(state: &AppState) -> Self {
+        Self {
+            state: state.clone(),
+            announce: true,
+            detector: None,
+            frame: Vec::with_capacity(FRAME),
+            pcm: Vec::with_capacity(FRAME),
+            speaking: false,
+            quiet_frames: 0,
+            spoken_frames: 0,
+            pre_roll: VecDeque::new(),
+        }
+    }
+
+    // Gates without the speech events.
+    pub fn silent(state: &AppState) -> Self {
+        let mut gate = Self::new(state);
+        gate.announce = false;
+        gate
+    }
+
+    fn set_speaking(&mut self, speaking: bool) {
+        if self.speaking == speaking {
+            return;
+        }
+        self.speaking = speaking;
+        if !self.announce {
+            return;
+        }
+        self.state.bus.publish(if speaking {
+            AgentEvent::SpeechStarted
+        } else {
+            AgentEvent::SpeechEnded
+        });
+    }
+
+    // Appends whatever should reach the sink to `out`.
+    pub fn process(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        let level = LEVEL.load(Ordering::Relaxed);
+        if level == OFF {
+            self.detector = None;
+            self.frame.clear();
+            self.pre_roll.clear();
+            self.set_speaking(false);
+            out.extend_from_slice(samples);
+            return;
+        }
+        if self.detector.as_ref().map(|(current, _)| *current) != Some(level) {
+            self.detector = Some((level, Detector::new(level)));
+        }
+        for &sample in samples {
+            self.frame.push(sample);
+            if self.frame.len() < FRAME {
+                continue;
+            }
+            self.pcm.clear();
+            self.pcm.extend(
+                self.frame
+                    .iter()
+                    .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+            );
+            let voiced = match self.detector.as_mut() {
+                Some((_, detector)) => detector.0.is_voice_segment(&self.pcm).unwrap_or(true),
+                None => true,
+            };
+            if voiced {
+                self.quiet_frames = 0;
+                if !self.speaking {
+                    self.spoken_frames = 0;
+                    out.extend(self.pre_roll.drain(..));
+                }
+                self.set_speaking(true);
+            } else if self.speaking {
+                self.quiet_frames += 1;
+                if self.quiet_frames > HANGOVER_FRAMES.load(Ordering::Relaxed) {
+                    self.set_speaking(false);
+                }
+            }
+            if self.speaking {
+                out.extend_from_slice(&self.frame);
+                self.spoken_frames += 1;
+                // The next voiced frame starts a new utterance.
+                if self.spoken_frames >= MAX_UTTERANCE_FRAMES.load(Ordering::Relaxed) {
+                    self.set_speaking(false);
+                }
+            } else {
+                let keep = PRE_ROLL_FRAMES.load(Ordering::Relaxed) as usize * FRAME;
+                self.pre_roll.extend(&self.frame);
+                let excess = self.pre_roll.len().saturating_sub(keep);
+                self.pre_roll.drain(..excess);
+            }
+            self.frame.clear();
+        }
+    }
+}
+
+impl Drop for Gate {
+    fn drop(&mut self) {
+        self.set_speaking(false);
+    }
+}
+
+#[tauri::command]
+pub fn get_vad_config() -> VadConfig {
+    CONFIG.lock().clone()
+}
+
+// Applies to running capture straight away.
+#[tauri::command]
+pub fn set_vad_sensitivity(level: Option<u8>) -> CommandResult<()> {
+    if level.map_or(false, |level| level > MAX_SENSITIVITY) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("VAD sensitivity must be between 0 and {}", MAX_SENSITIVITY),
+        ));
+    }
+    let mut config = CONFIG.lock().clone();
+    config.sensitivity = level;
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    LEVEL.store(level.unwrap_or(OFF), Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_capture_params() -> CaptureParams {
+    CONFIG.lock().capture
+}
+
+// Native capture applies them from its next frame, and a listening daemon
+// from its next block; neither is restarted.
+#[tauri::command]
+pub fn set_capture_params(
+    state: tauri::State<'_, AppState>,
+    params: CaptureParams,
+) -> CommandResult<()> {
+    params.validate()?;
+    let mut config = CONFIG.lock().clone();
+    config.capture = params;
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    PRE_ROLL_FRAMES.store(frames(params.pre_roll_ms), Ordering::Relaxed);
+    HANGOVER_FRAMES.store(frames(params.silence_timeout_ms), Ordering::Relaxed);
+    MAX_UTTERANCE_FRAMES.store(frames(params.max_utterance_ms), Ordering::Relaxed);
+    if daemon::is_running(&state) {
+        daemon::configure(&state, params.daemon_params())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use std::fs;
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "vocabulary.json";
+// Whisper reads at most 224 prompt tokens; this stays well under.
+const PROMPT_CHARS: usize = 600;
+const TRAILING: &[char] = &['.', ',', '!', '?', ';', ':'];
+// A near miss of a word the STT does know, e.g. "Kubernetis".
+const SPELLING_SIMILARITY: f64 = 0.8;
+// A different spelling that sounds the same, e.g. "Postgress".
+const SOUND_SIMILARITY: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabTerm {
+    // Written exactly as it should appear, e.g. "Tauri" or "LiveKit".
+    pub term: String,
+    // What the STT writes instead, e.g. "towery"; always replaced.
+    #[serde(default)]
+    pub sounds_like: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Vocabulary {
+    terms: Vec<VocabTerm>,
+}
+
+static VOCABULARY: Lazy<Mutex<Vocabulary>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+fn bare(token: &str) -> String {
+    token
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// The first letter, then the consonants, with spellings of the same sound
+// merged.
+fn sound(word: &str) -> String {
+    let word = word
+        .replace("ph", "f")
+        .replace("ck", "k")
+        .replace("qu", "kw");
+    let mut key = String::new();
+    for (i, c) in word.chars().enumerate() {
+        let c = match c {
+            'c' | 'q' => 'k',
+            'z' => 's',
+            'v' => 'f',
+            c => c,
+        };
+        if (i > 0 && "aeiouyhw".contains(c)) || key.ends_with(c) {
+            continue;
+        }
+        key.push(c);
+    }
+    key
+}
+
+// Short words are left alone; too many real words are a letter away.
+fn close(heard: &str, term: &str) -> bool {
+    if heard.chars().count() < 4 {
+        return false;
+    }
+    let similarity = strsim::normalized_levenshtein(heard, term);
+    if similarity >= SPELLING_SIMILARITY {
+        return true;
+    }
+    let key = sound(term);
+    key.len() >= 3 && sound(heard) == key && similarity >= SOUND_SIMILARITY
+}
+
+fn matches(heard: &str, term: &VocabTerm) -> bool {
+    let target = bare(&term.term);
+    heard == target
+        || term.sounds_like.iter().any(|alias| bare(alias) == heard)
+        || close(heard, &target)
+}
+
+// Up to this many tokens are tried as one term, so "live kit" can become
+// "LiveKit".
+fn window(terms: &[VocabTerm]) -> usize {
+    terms
+        .iter()
+        .flat_map(|term| std::iter::once(&term.term).chain(&term.sounds_like))
+        .map(|phrase| phrase.split_whitespace().count() + 1)
+        .max()
+        .unwrap_or(1)
+}
+
+// Rewrites misheard terms as written in the vocabulary, keeping the
+// punctuation after them.
+pub fn correct(text: &str) -> String {
+    let terms = VOCABULARY.lock().terms.clone();
+    if terms.is_empty() {
+        return text.to_string();
+    }
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let longest = window(&terms);
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let matched = (1..=longest.min(tokens.len() - i)).rev().find_map(|len| {
+            let phrase = &tokens[i..i + len];
+            // A phrase cannot run on past punctuation.
+            if phrase[..len - 1]
+                .iter()
+                .any(|token| token.ends_with(TRAILING))
+            {
+                return None;
+            }
+            let heard: String = phrase.iter().map(|token| bare(token)).collect();
+            terms
+                .iter()
+                .find(|term| matches(&heard, term))
+                .map(|term| (len, term))
+        });
+        match matched {
+            Some((len, term)) => {
+                let last = tokens[i + len - 1];
+                let suffix = &last[last.trim_end_matches(TRAILING).len()..];
+                out.push(format!("{}{}", term.term, suffix));
+                i += len;
+            }
+            None => {
+                out.push(tokens[i].to_string());
+                i += 1;
+            }
+        }
+    }
+    out.join(" ")
+}
+
+pub fn terms() -> Vec<String> {
+    VOCABULARY
+        .lock()
+        .terms
+        .iter()
+        .map(|term| term.term.clone())
+        .collect()
+}
+
+// For Whisper, which spells words the way its prompt does.
+pub fn prompt() -> Option<String> {
+    let mut prompt = String::new();
+    for term in terms() {
+        if prompt.len() + term.len() + 2 > PROMPT_CHARS {
+            break;
+        }
+        if !prompt.is_empty() {
+            prompt.push_str(", ");
+        }
+        prompt.push_str(&term);
+    }
+    if prompt.is_empty() {
+        None
+    } else {
+        Some(format!("{}.", prompt))
+    }
+}
+
+// A running daemon takes the new prompt from its next chunk.
+fn save(state: &AppState, vocabulary: Vocabulary) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &vocabulary)?;
+    *VOCABULARY.lock() = vocabulary;
+    if daemon::is_running(state) {
+        daemon::configure(state, serde_json::json!({ "prompt": prompt() }))?;
+    }
+    Ok(())
+}
+
+// For an imported settings file; duplicates and empty terms are skipped.
+pub fn replace(state: &AppState, terms: Vec<VocabTerm>) -> Result<(), AppError> {
+    let mut vocabulary = Vocabulary::default();
+    for term in terms {
+        insert(&mut vocabulary, term);
+    }
+    save(state, vocabulary)
+}
+
+fn insert(vocabulary: &mut Vocabulary, term: VocabTerm) -> bool {
+    let key = bare(&term.term);
+    if key.is_empty() || vocabulary.terms.iter().any(|t| bare(&t.term) == key) {
+        return false;
+    }
+    vocabulary.terms.push(term);
+    true
+}
+
+#[tauri::command]
+pub fn list_vocab_terms() -> Vec<VocabTerm> {
+    VOCABULARY.lock().terms.clone()
+}
+
+#[tauri::command]
+pub fn add_vocab_term(
+    state: tauri::State<'_, AppState>,
+    term: String,
+    sounds_like: Option<Vec<String>>,
+) -> CommandResult<VocabTerm> {
+    let term = VocabTerm {
+        term: term.trim().to_string(),
+        sounds_like: sounds_like
+            .unwrap_or_default()
+            .into_iter()
+            .map(|alias| alias.trim().to_string())
+            .filter(|alias| !bare(alias).is_empty())
+            .collect(),
+    };
+    if bare(&term.term).is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Vocabulary term cannot be empty",
+        ));
+    }
+    let mut vocabulary = VOCABULARY.lock().clone();
+    if !insert(&mut vocabulary, term.clone()) {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("\"{}\" is already in the vocabulary", term.term),
+        ));
+    }
+    save(&state, vocabulary)?;
+    Ok(term)
+}
+
+#[tauri::command]
+pub fn remove_vocab_term(state: tauri::State<'_, AppState>, term: String) -> CommandResult<()> {
+    let mut vocabulary = VOCABULARY.lock().clone();
+    let before = vocabulary.terms.len();
+    vocabulary.terms.retain(|t| bare(&t.term) != bare(&term));
+    if vocabulary.terms.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("\"{}\" is not in the vocabulary", term),
+        ));
+    }
+    save(&state, vocabulary)
+}
+
+// One term per line, optionally followed by `=` and what it sounds like:
+// `Tauri = towery, tory`. Blank lines and `#` comments are skipped, and so
+// are terms already in the vocabulary. Returns how many were added.
+#[tauri::command]
+pub fn import_vocab(state: tauri::State<'_, AppState>, path: PathBuf) -> CommandResult<usize> {
+    let contents = fs::read_to_string(&path).map_err(|e| {
+        let code = if e.kind() == std::io::ErrorKind::NotFound {
+            ErrorCode::NotFound
+        } else {
+            ErrorCode::Storage
+        };
+        AppError::new(code, format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let mut vocabulary = VOCABULARY.lock().clone();
+    let mut added = 0;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (term, aliases) = line.split_once('=').unwrap_or((line, ""));
+        let term = VocabTerm {
+            term: term.trim().to_string(),
+            sounds_like: aliases
+                .split(',')
+                .map(|alias| alias.trim().to_string())
+                .filter(|alias| !bare(alias).is_empty())
+                .collect(),
+        };
+        if insert(&mut vocabulary, term) {
+            added += 1;
+        }
+    }
+    save(&state, vocabulary)?;
+    Ok(added)
+}
@@ -0,0 +1,345 @@
+use std::process::Stdio;
+use std::thread;
+
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::bus::AgentEvent;
+use crate::children;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::sandbox::{self, ProcessKind};
+use crate::store;
+use crate::text_inject;
+use crate::transcript::Transcript;
+use crate::AppState;
+
+const STORE_FILE: &str = "voice_commands.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceMode {
+    // Final transcripts are typed and published as usual.
+    Dictation,
+    // Final transcripts are only matched against the rules; nothing is typed.
+    Command,
+}
+
+impl Default for VoiceMode {
+    fn default() -> Self {
+        VoiceMode::Dictation
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandAction {
+    // A chord such as `enter` or `ctrl+shift+t`.
+    Key {
+        keys: String,
+    },
+    // Runs under the hooks sandbox policy, without waiting for it to exit.
+    Shell {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    // Emitted to the frontend under `name`, with the spoken text as payload.
+    Event {
+        name: String,
+    },
+    // Removes the last dictation typed into the focused app.
+    Undo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandRule {
+    pub id: String,
+    // Matched against the whole utterance, ignoring case and punctuation.
+    pub pattern: String,
+    pub action: CommandAction,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct VoiceCommandConfig {
+    mode: VoiceMode,
+    rules: Vec<CommandRule>,
+}
+
+static CONFIG: Lazy<Mutex<VoiceCommandConfig>> =
+    Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+// While any rule is enabled, the daemon starts with --no-type and dictation
+// is typed by text_inject, so commands can be held back from the focused app.
+pub fn intercepts_typing() -> bool {
+    CONFIG.lock().rules.iter().any(|rule| rule.enabled)
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn key(name: &str) -> Option<Key> {
+    let key = match name {
+        "enter" | "return" => Key::Return,
+        "tab" => Key::Tab,
+        "esc" | "escape" => Key::Escape,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "ctrl" | "control" => Key::Control,
+        "shift" => Key::Shift,
+        "alt" | "option" => Key::Alt,
+        "meta" | "cmd" | "super" | "win" => Key::Meta,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Unicode(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+fn chord(keys: &str) -> Result<Vec<Key>, AppError> {
+    let chord: Option<Vec<Key>> = keys
+        .split('+')
+        .map(|name| key(&name.trim().to_lowercase()))
+        .collect();
+    chord.filter(|keys| !keys.is_empty()).ok_or_else(|| {
+        AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Unknown key chord: {}", keys),
+        )
+    })
+}
+
+fn failed(e: impl std::fmt::Display) -> AppError {
+    AppError::from(format!("Failed to press keys: {}", e))
+}
+
+// Holds every key but the last, clicks the last, then releases in reverse.
+fn press(keys: &str) -> Result<(), AppError> {
+    let keys = chord(keys)?;
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| {
+        AppError::new(
+            ErrorCode::Unsupported,
+            format!("Keyboard input is unavailable: {}", e),
+        )
+    })?;
+    let (last, modifiers) = keys.split_last().expect("chord is never empty");
+    for modifier in modifiers {
+        enigo.key(*modifier, Direction::Press).map_err(failed)?;
+    }
+    let clicked = enigo.key(*last, Direction::Click);
+    for modifier in modifiers.iter().rev() {
+        enigo.key(*modifier, Direction::Release).map_err(failed)?;
+    }
+    clicked.map_err(failed)
+}
+
+fn shell(command: &str, args: &[String]) -> Result<(), AppError> {
+    let policy = sandbox::policy(ProcessKind::Hook);
+    let mut child = sandbox::command(ProcessKind::Hook, &policy, command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| AppError::from(format!("Failed to run {}: {}", command, e)))?;
+    sandbox::confine(&policy, child.id());
+    let tracked = children::track(child.id());
+    thread::spawn(move || {
+        let _tracked = tracked;
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+fn execute(state: &AppState, rule: &CommandRule, text: &str) -> Result<(), AppError> {
+    match &rule.action {
+        CommandAction::Key { keys } => press(keys),
+        CommandAction::Shell { command, args } => shell(command, args),
+        CommandAction::Event { name } => {
+            state.bus.publish(AgentEvent::VoiceCommandEvent {
+                name: name.clone(),
+                text: text.to_string(),
+            });
+            Ok(())
+        }
+        CommandAction::Undo => text_inject::undo_last().map(|_| ()),
+    }
+}
+
+// Returns whether the transcript was taken as a command, in which case it is
+// not typed or published. In command mode that is every final transcript.
+pub fn route(state: &AppState, transcript: &Transcript) -> bool {
+    let config = CONFIG.lock().clone();
+    if config.mode != VoiceMode::Command || !transcript.is_final {
+        return false;
+    }
+    let spoken = normalize(&transcript.text);
+    let rule = match config
+        .rules
+        .into_iter()
+        .find(|rule| rule.enabled && normalize(&rule.pattern) == spoken)
+    {
+        Some(rule) => rule,
+        None => return true,
+    };
+    state.bus.publish(AgentEvent::IntentDetected {
+        intent: rule.pattern.clone(),
+        text: transcript.text.clone(),
+    });
+    // Key presses block, and the daemon's reader must not.
+    let state = state.clone();
+    let text = transcript.text.clone();
+    thread::spawn(move || {
+        if let Err(error) = execute(&state, &rule, &text) {
+            state.bus.publish(AgentEvent::error(
+                "voice_commands",
+                error.with_context("rule_id", rule.id.clone()),
+            ));
+        }
+    });
+    true
+}
+
+fn save(config: VoiceCommandConfig) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &config)?;
+    *CONFIG.lock() = config;
+    Ok(())
+}
+
+// For an imported settings file; the voice mode is left as it is.
+pub fn replace_rules(rules: Vec<CommandRule>) -> Result<(), AppError> {
+    let mut config = CONFIG.lock().clone();
+    config.rules = rules;
+    save(config)
+}
+
+#[tauri::command]
+pub fn list_command_rules() -> Vec<CommandRule> {
+    CONFIG.lock().rules.clone()
+}
+
+#[tauri::command]
+pub fn add_command_rule(pattern: String, action: CommandAction) -> CommandResult<CommandRule> {
+    if normalize(&pattern).is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Command pattern cannot be empty",
+        ));
+    }
+    match &action {
+        CommandAction::Key { keys } => {
+            chord(keys)?;
+        }
+        CommandAction::Shell { command, .. } if command.trim().is_empty() => {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Shell command cannot be empty",
+            ))
+        }
+        CommandAction::Event { name } if name.trim().is_empty() => {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "Event name cannot be empty",
+            ))
+        }
+        _ => {}
+    }
+    let mut config = CONFIG.lock().clone();
+    if config
+        .rules
+        .iter()
+        .any(|rule| normalize(&rule.pattern) == normalize(&pattern))
+    {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("A rule for \"{}\" already exists", pattern),
+        ));
+    }
+    let rule = CommandRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        pattern,
+        action,
+        enabled: true,
+    };
+    config.rules.push(rule.clone());
+    save(config)?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub fn remove_command_rule(id: String) -> CommandResult<()> {
+    let mut config = CONFIG.lock().clone();
+    let before = config.rules.len();
+    config.rules.retain(|rule| rule.id != id);
+    if config.rules.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No command rule with id {}", id),
+        ));
+    }
+    save(config)
+}
+
+#[tauri::command]
+pub fn set_command_rule_enabled(id: String, enabled: bool) -> CommandResult<()> {
+    let mut config = CONFIG.lock().clone();
+    match config.rules.iter_mut().find(|rule| rule.id == id) {
+        Some(rule) => rule.enabled = enabled,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No command rule with id {}", id),
+            ))
+        }
+    }
+    save(config)
+}
+
+#[tauri::command]
+pub fn get_voice_mode() -> VoiceMode {
+    CONFIG.lock().mode
+}
+
+#[tauri::command]
+pub fn set_voice_mode(state: tauri::State<'_, AppState>, mode: VoiceMode) -> CommandResult<()> {
+    let mut config = CONFIG.lock().clone();
+    config.mode = mode;
+    save(config)?;
+    state.bus.publish(AgentEvent::VoiceModeChanged(mode));
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use ndarray::{Array2, Array3, Array4};
+use once_cell::sync::Lazy;
+use ort::Session;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::audio;
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::schedule;
+use crate::store;
+use crate::AppState;
+
+const STORE_FILE: &str = "wake_word.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// openWakeWord's streaming shapes: 80 ms of audio in, 8 mel frames out; the
+// embedding model sees 76 frames and the classifier 16 embeddings.
+const CHUNK: usize = 1280;
+// The melspectrogram model needs three hops of context before each chunk.
+const MEL_CONTEXT: usize = 480;
+const MEL_FRAMES_PER_CHUNK: usize = 8;
+const EMBEDDING_WINDOW: usize = 76;
+const CLASSIFIER_WINDOW: usize = 16;
+// The rest of the utterance would otherwise fire again.
+const REFRACTORY_CHUNKS: usize = 25;
+const QUEUE_DEPTH: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WakeWordConfig {
+    pub enabled: bool,
+    // A model file name in the wakeword directory, without `.onnx`.
+    pub name: Option<String>,
+    pub threshold: f32,
+}
+
+impl Default for WakeWordConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: None,
+            threshold: 0.5,
+        }
+    }
+}
+
+static CONFIG: Lazy<Mutex<WakeWordConfig>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+
+fn models_dir() -> PathBuf {
+    store::data_dir().join("wakeword")
+}
+
+fn model_path(name: &str) -> PathBuf {
+    models_dir().join(format!("{}.onnx", name))
+}
+
+fn failed(e: ort::Error) -> AppError {
+    AppError::from(format!("Wake word model failed: {}", e))
+}
+
+fn session(path: PathBuf) -> Result<Session, AppError> {
+    if !path.is_file() {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No wake word model at {}", path.display()),
+        ));
+    }
+    Session::builder()
+        .and_then(|builder| builder.with_intra_threads(1))
+        .and_then(|builder| builder.commit_from_file(&path))
+        .map_err(failed)
+}
+
+fn first_output(outputs: ort::SessionOutputs) -> Result<Vec<f32>, AppError> {
+    let tensor = outputs[0].try_extract_tensor::<f32>().map_err(failed)?;
+    Ok(tensor.iter().copied().collect())
+}
+
+// The three-stage openWakeWord pipeline, fed 16 kHz audio.
+struct Detector {
+    melspectrogram: Session,
+    embedding: Session,
+    classifier: Session,
+    audio: Vec<f32>,
+    mel: VecDeque<[f32; 32]>,
+    embeddings: VecDeque<Vec<f32>>,
+    refractory: usize,
+}
+
+impl Detector {
+    fn load(name: &str) -> Result<Self, AppError> {
+        Ok(Self {
+            melspectrogram: session(models_dir().join("melspectrogram.onnx"))?,
+            embedding: session(models_dir().join("embedding_model.onnx"))?,
+            classifier: session(model_path(name))?,
+            audio: Vec::new(),
+            mel: VecDeque::new(),
+            embeddings: VecDeque::new(),
+            refractory: 0,
+        })
+    }
+
+    fn chunk_score(&mut self, chunk: &[f32]) -> Result<Option<f32>, AppError> {
+        // The models were trained on int16 magnitudes.
+        let samples: Vec<f32> = chunk.iter().map(|s| s * i16::MAX as f32).collect();
+        let input = Array2::from_shape_vec((1, samples.len()), samples)
+            .map_err(|e| AppError::from(e.to_string()))?;
+        let mel = first_output(
+            self.melspectrogram
+                .run(ort::inputs![input].map_err(failed)?)
+                .map_err(failed)?,
+        )?;
+        let frames = mel.chunks_exact(32).count();
+        for frame in mel
+            .chunks_exact(32)
+            .skip(frames.saturating_sub(MEL_FRAMES_PER_CHUNK))
+        {
+            let mut scaled = [0.0; 32];
+            for (out, value) in scaled.iter_mut().zip(frame) {
+                *out = value / 10.0 + 2.0;
+            }
+            self.mel.push_back(scaled);
+        }
+        while self.mel.len() > EMBEDDING_WINDOW {
+            self.mel.pop_front();
+        }
+        if self.mel.len() < EMBEDDING_WINDOW {
+            return Ok(None);
+        }
+
+        let window: Vec<f32> = self.mel.iter().flatten().copied().collect();
+        let input = Array4::from_shape_vec((1, EMBEDDING_WINDOW, 32, 1), window)
+            .map_err(|e| AppError::from(e.to_string()))?;
+        let embedding = first_output(
+            self.embedding
+                .run(ort::inputs![input].map_err(failed)?)
+                .map_err(failed)?,
+        )?;
+        let width = embedding.len();
+        self.embeddings.push_back(embedding);
+        if self.embeddings.len() > CLASSIFIER_WINDOW {
+            self.embeddings.pop_front();
+        }
+        if self.embeddings.len() < CLASSIFIER_WINDOW {
+            return Ok(None);
+        }
+
+        let features: Vec<f32> = self.embeddings.iter().flatten().copied().collect();
+        let input = Array3::from_shape_vec((1, CLASSIFIER_WINDOW, width), features)
+            .map_err(|e| AppError::from(e.to_string()))?;
+        let score = first_output(
+            self.classifier
+                .run(ort::inputs![input].map_err(failed)?)
+                .map_err(failed)?,
+        )?;
+        Ok(score.first().copied())
+    }
+
+    // Returns the score of the chunk that crossed `threshold`, if any.
+    fn push(&mut self, samples: &[f32], threshold: f32) -> Result<Option<f32>, AppError> {
+        self.audio.extend_from_slice(samples);
+        let mut detected = None;
+        while self.audio.len() >= MEL_CONTEXT + CHUNK {
+            let chunk: Vec<f32> = self.audio[..MEL_CONTEXT + CHUNK].to_vec();
+            self.audio.drain(..CHUNK);
+            let score = self.chunk_score(&chunk)?;
+            if self.refractory > 0 {
+                self.refractory -= 1;
+                continue;
+            }
+            if let Some(score) = score.filter(|&score| score >= threshold) {
+                self.refractory = REFRACTORY_CHUNKS;
+                detected = Some(score);
+            }
+        }
+        Ok(detected)
+    }
+}
+
+fn listen(state: &AppState, name: &str) -> Result<(), AppError> {
+    let mut detector = Detector::load(name)?;
+    let (tx, rx) = mpsc::sync_channel::<Vec<f32>>(QUEUE_DEPTH);
+    audio::start_background(
+        state,
+        Box::new(move |samples: &[f32]| {
+            let _ = tx.try_send(samples.to_vec());
+        }),
+    )?;
+    tracing::info!(target: "wake_word", name, "Listening for the wake word");
+    loop {
+        // Gated silence sends nothing, so settings are checked on a timer too.
+        let samples = match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(samples) => Some(samples),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            // Something else took the microphone.
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let config = CONFIG.lock().clone();
+        if !config.enabled
+            || config.name.as_deref() != Some(name)
+            || backends::is_running(state)
+            || schedule::quiet()
+        {
+            break;
+        }
+        let samples = match samples {
+            Some(samples) => samples,
+            None => continue,
+        };
+        let detected = match detector.push(&samples, config.threshold) {
+            Ok(detected) => detected,
+            Err(error) => {
+                audio::stop_background();
+                return Err(error);
+            }
+        };
+        if let Some(score) = detected {
+            tracing::info!(target: "wake_word", name, score, "Wake word detected");
+            audio::stop_background();
+            state.bus.publish(AgentEvent::WakeWordDetected {
+                name: name.to_string(),
+                score,
+            });
+            backends::start(state, InputSource::Microphone)?;
+            break;
+        }
+    }
+    audio::stop_background();
+    Ok(())
+}
+
+// Whether the loop below wants the microphone when it is free. Not during
+// quiet hours, so the lookback buffer can have it.
+pub fn wanted() -> bool {
+    let config = CONFIG.lock();
+    config.enabled && config.name.is_some() && !schedule::quiet()
+}
+
+// A low-priority loop that listens whenever the microphone is free: after a
+// dictation session ends, or once something else releases the mic.
+pub fn spawn(state: AppState) {
+    thread::spawn(move || {
+        let mut last_error: Option<String> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let config = CONFIG.lock().clone();
+            let name = match config.name {
+                Some(name) if config.enabled => name,
+                _ => continue,
+            };
+            if schedule::quiet() || backends::is_running(&state) || !audio::is_idle(&state) {
+                continue;
+            }
+            // Each failure is reported once, not every second.
+            match listen(&state, &name) {
+                Ok(()) => last_error = None,
+                Err(error) if last_error.as_deref() != Some(error.message.as_str()) => {
+                    last_error = Some(error.message.clone());
+                    state.bus.publish(AgentEvent::error("wake_word", error));
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_wake_word_config() -> WakeWordConfig {
+    CONFIG.lock().clone()
+}
+
+#[tauri::command]
+pub fn set_wake_word(name: String) -> CommandResult<()> {
+    if name.is_empty() || name.contains(['/', '\\']) || !model_path(&name).is_file() {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No wake word model {} in {}", name, models_dir().display()),
+        ));
+    }
+    let mut config = CONFIG.lock();
+    let mut updated = config.clone();
+    updated.name = Some(name);
+    store::save_json(STORE_FILE, &updated)?;
+    *config = updated;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn enable_wake_word(enabled: bool) -> CommandResult<()> {
+    let mut config = CONFIG.lock();
+    if enabled && config.name.is_none() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Choose a wake word with set_wake_word first",
+        ));
+    }
+    let mut updated = config.clone();
+    updated.enabled = enabled;
+    store::save_json(STORE_FILE, &updated)?;
+    *config = updated;
+    Ok(())
+}
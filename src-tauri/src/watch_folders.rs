@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::bus::AgentEvent;
+use crate::daemon;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::export::{self, ExportFormat};
+use crate::store;
+use crate::usage;
+use crate::AppState;
+
+const STORE_FILE: &str = "watch_folders.json";
+const PROCESSED_DIR: &str = "processed";
+const FAILED_DIR: &str = "failed";
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "wav", "mp3", "m4a", "aac", "flac", "ogg", "opus", "webm", "mp4", "mkv", "mov",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFolder {
+    pub path: String,
+    pub format: ExportFormat,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchStatus {
+    pub folders: Vec<WatchedFolder>,
+    pub queued: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct WatchState {
+    folders: Mutex<Vec<WatchedFolder>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    queued: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+fn is_media(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| MEDIA_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Files are often still being copied when the first event fires, so wait
+// until the size stops changing.
+fn wait_until_stable(path: &Path) -> bool {
+    let mut last_len = None;
+    for _ in 0..120 {
+        let len = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return false,
+        };
+        if last_len == Some(len) && len > 0 {
+            return true;
+        }
+        last_len = Some(len);
+        thread::sleep(Duration::from_secs(1));
+    }
+    false
+}
+
+fn move_into(path: &Path, subdir: &str) -> Result<PathBuf, String> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(".")).join(subdir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let target = dir.join(path.file_name().unwrap_or_default());
+    std::fs::rename(path, &target)
+        .map_err(|e| format!("Failed to move {}: {}", path.display(), e))?;
+    Ok(target)
+}
+
+fn process(state: &AppState, path: &Path, format: ExportFormat) -> Result<(), String> {
+    let segments = daemon::transcribe_segments(state, path)?;
+    daemon::join_segments(state, &segments);
+    usage::record("watch_folder.file");
+    let moved = move_into(path, PROCESSED_DIR)?;
+    let output = moved.with_extension(format.extension());
+    std::fs::write(&output, export::render(&segments, format))
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))
+}
+
+fn fail(path: &Path, message: &str) {
+    if let Ok(moved) = move_into(path, FAILED_DIR) {
+        let mut log = moved.into_os_string();
+        log.push(".error.txt");
+        let _ = std::fs::write(log, message);
+    }
+}
+
+impl WatchState {
+    pub fn load() -> Self {
+        Self {
+            folders: Mutex::new(store::load_json(STORE_FILE)),
+            ..Default::default()
+        }
+    }
+
+    fn format_for(folders: &[WatchedFolder], path: &Path) -> Option<ExportFormat> {
+        let parent = path.parent()?;
+        folders
+            .iter()
+            .find(|f| f.enabled && Path::new(&f.path) == parent)
+            .map(|f| f.format)
+    }
+
+    // Rebuilds the watcher and worker from the current folder list. The old
+    // worker exits once its queue drains.
+    pub fn apply(&self, state: &AppState) -> Result<(), String> {
+        self.watcher.lock().take();
+        let folders: Vec<WatchedFolder> = self
+            .folders
+            .lock()
+            .iter()
+            .filter(|f| f.enabled)
+            .cloned()
+            .collect();
+        if folders.is_empty() {
+            return Ok(());
+        }
+
+        let (tx, rx) = mpsc::channel::<(PathBuf, ExportFormat)>();
+        let worker_state = state.clone();
+        let queued = self.queued.clone();
+        thread::spawn(move || {
+            for (path, format) in rx {
+                if wait_until_stable(&path) {
+                    if let Err(message) = process(&worker_state, &path, format) {
+                        fail(&path, &message);
+                        worker_state
+                            .bus
+                            .publish(AgentEvent::error("watch_folders", message));
+                    }
+                }
+                queued.lock().remove(&path);
+            }
+        });
+
+        let enqueue = {
+            let queued = self.queued.clone();
+            let folders = folders.clone();
+            move |path: PathBuf| {
+                if !is_media(&path) || !path.is_file() {
+                    return;
+                }
+                if let Some(format) = Self::format_for(&folders, &path) {
+                    if queued.lock().insert(path.clone()) {
+                        let _ = tx.send((path, format));
+                    }
+                }
+            }
+        };
+
+        for folder in &folders {
+            if let Ok(entries) = std::fs::read_dir(&folder.path) {
+                for entry in entries.flatten() {
+                    enqueue(entry.path());
+                }
+            }
+        }
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            enqueue(path);
+                        }
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start folder watcher: {}", e))?;
+        for folder in &folders {
+            watcher
+                .watch(Path::new(&folder.path), RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch {}: {}", folder.path, e))?;
+        }
+        *self.watcher.lock() = Some(watcher);
+        Ok(())
+    }
+
+    fn update<F: FnOnce(&mut Vec<WatchedFolder>) -> CommandResult<()>>(
+        &self,
+        state: &AppState,
+        change: F,
+    ) -> CommandResult<()> {
+        {
+            let mut folders = self.folders.lock();
+            change(&mut folders)?;
+            store::save_json(STORE_FILE, &*folders)?;
+        }
+        self.apply(state).map_err(AppError::from)
+    }
+}
+
+#[tauri::command]
+pub fn get_watch_status(watch: tauri::State<'_, WatchState>) -> WatchStatus {
+    WatchStatus {
+        folders: watch.folders.lock().clone(),
+        queued: watch
+            .queued
+            .lock()
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub fn add_watch_folder(
+    state: tauri::State<'_, AppState>,
+    watch: tauri::State<'_, WatchState>,
+    path: String,
+    format: ExportFormat,
+) -> CommandResult<()> {
+    if !Path::new(&path).is_dir() {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No such folder: {}", path),
+        ));
+    }
+    watch.update(&state, |folders| {
+        folders.retain(|f| f.path != path);
+        folders.push(WatchedFolder {
+            path,
+            format,
+            enabled: true,
+        });
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn remove_watch_folder(
+    state: tauri::State<'_, AppState>,
+    watch: tauri::State<'_, WatchState>,
+    path: String,
+) -> CommandResult<()> {
+    watch.update(&state, |folders| {
+        let before = folders.len();
+        folders.retain(|f| f.path != path);
+        if folders.len() == before {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("Folder is not watched: {}", path),
+            ));
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn set_watch_folder_enabled(
+    state: tauri::State<'_, AppState>,
+    watch: tauri::State<'_, WatchState>,
+    path: String,
+    enabled: bool,
+) -> CommandResult<()> {
+    watch.update(&state, |folders| {
+        match folders.iter_mut().find(|f| f.path == path) {
+            Some(folder) => {
+                folder.enabled = enabled;
+                Ok(())
+            }
+            None => Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("Folder is not watched: {}", path),
+            )),
+        }
+    })
+}
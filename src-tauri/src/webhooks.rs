@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::{AgentEvent, EventBus};
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::transcript::now_millis;
+
+const STORE_FILE: &str = "webhooks.json";
+const MAX_ATTEMPTS: u32 = 5;
+const DELIVERY_LOG_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Transcript,
+    SessionEnded,
+    Error,
+}
+
+impl WebhookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Transcript => "transcript",
+            WebhookEvent::SessionEnded => "session_ended",
+            WebhookEvent::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub secret: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    pub attempt: u32,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct WebhookState {
+    hooks: Arc<Mutex<Vec<Webhook>>>,
+    log: Arc<Mutex<VecDeque<Delivery>>>,
+}
+
+impl WebhookState {
+    pub fn load() -> Self {
+        Self {
+            hooks: Arc::new(Mutex::new(store::load_json(STORE_FILE))),
+            log: Arc::default(),
+        }
+    }
+
+    fn record(&self, delivery: Delivery) {
+        let mut log = self.log.lock();
+        if log.len() == DELIVERY_LOG_LEN {
+            log.pop_front();
+        }
+        log.push_back(delivery);
+    }
+}
+
+fn event_kind(event: &AgentEvent) -> Option<WebhookEvent> {
+    match event {
+        AgentEvent::Transcript(t) if t.is_final => Some(WebhookEvent::Transcript),
+        AgentEvent::SessionEnded { .. } => Some(WebhookEvent::SessionEnded),
+        AgentEvent::Error { .. } => Some(WebhookEvent::Error),
+        _ => None,
+    }
+}
+
+pub fn spawn_dispatcher(state: WebhookState, bus: &EventBus) {
+    let mut rx = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let kind = match event_kind(&event) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            let mut body = serde_json::to_value(&event).unwrap_or_default();
+            body["timestamp"] = now_millis().into();
+            let body = body.to_string();
+
+            let hooks: Vec<Webhook> = state
+                .hooks
+                .lock()
+                .iter()
+                .filter(|h| h.enabled && h.events.contains(&kind))
+                .cloned()
+                .collect();
+            for hook in hooks {
+                tauri::async_runtime::spawn(deliver(
+                    client.clone(),
+                    state.clone(),
+                    hook,
+                    kind,
+                    body.clone(),
+                ));
+            }
+        }
+    });
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    state: WebhookState,
+    hook: Webhook,
+    kind: WebhookEvent,
+    body: String,
+) {
+    let signature = hook.secret.as_deref().map(|secret| sign(secret, &body));
+    let mut delay = Duration::from_secs(1);
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let mut request = client
+            .post(&hook.url)
+            .timeout(Duration::from_secs(10))
+            .header("Content-Type", "application/json")
+            .header("X-Voice-Agent-Event", kind.as_str())
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header("X-Voice-Agent-Signature", format!("sha256={}", signature));
+        }
+
+        let (status, error) = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!("HTTP {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let delivered = error.is_none();
+        state.record(Delivery {
+            webhook_id: hook.id.clone(),
+            event: kind,
+            attempt,
+            status,
+            error,
+            timestamp: now_millis(),
+        });
+
+        if delivered {
+            return;
+        }
+        // Client errors won't fix themselves, except rate limiting.
+        if let Some(code) = status {
+            if (400..500).contains(&code) && code != 429 {
+                return;
+            }
+        }
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[tauri::command]
+pub fn list_webhooks(state: tauri::State<'_, WebhookState>) -> Vec<Webhook> {
+    state.hooks.lock().clone()
+}
+
+#[tauri::command]
+pub fn add_webhook(
+    state: tauri::State<'_, WebhookState>,
+    url: String,
+    events: Vec<WebhookEvent>,
+    secret: Option<String>,
+) -> CommandResult<Webhook> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            format!("Invalid webhook URL: {}", url),
+        ));
+    }
+    let hook = Webhook {
+        id: uuid::Uuid::new_v4().to_string(),
+        url,
+        events,
+        secret: secret.filter(|s| !s.is_empty()),
+        enabled: true,
+    };
+    let mut hooks = state.hooks.lock();
+    hooks.push(hook.clone());
+    store::save_json(STORE_FILE, &*hooks)?;
+    Ok(hook)
+}
+
+#[tauri::command]
+pub fn remove_webhook(state: tauri::State<'_, WebhookState>, id: String) -> CommandResult<()> {
+    let mut hooks = state.hooks.lock();
+    let before = hooks.len();
+    hooks.retain(|h| h.id != id);
+    if hooks.len() == before {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No webhook with id {}", id),
+        ));
+    }
+    store::save_json(STORE_FILE, &*hooks)
+}
+
+#[tauri::command]
+pub fn set_webhook_enabled(
+    state: tauri::State<'_, WebhookState>,
+    id: String,
+    enabled: bool,
+) -> CommandResult<()> {
+    let mut hooks = state.hooks.lock();
+    match hooks.iter_mut().find(|h| h.id == id) {
+        Some(hook) => hook.enabled = enabled,
+        None => {
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("No webhook with id {}", id),
+            ))
+        }
+    }
+    store::save_json(STORE_FILE, &*hooks)
+}
+
+#[tauri::command]
+pub fn get_webhook_deliveries(
+    state: tauri::State<'_, WebhookState>,
+    limit: Option<usize>,
+) -> Vec<Delivery> {
+    let log = state.log.lock();
+    log.iter()
+        .rev()
+        .take(limit.unwrap_or(DELIVERY_LOG_LEN))
+        .cloned()
+        .collect()
+}
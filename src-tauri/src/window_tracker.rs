@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::audio;
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::formatting::{self, FormattingRule};
+use crate::profiles;
+use crate::store;
+use crate::text_inject::InjectMode;
+use crate::AppState;
+
+const STORE_FILE: &str = "app_profiles.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FocusedApp {
+    // The process name, e.g. "firefox" or "KeePassXC.exe".
+    pub app: String,
+    pub title: String,
+}
+
+// Unset fields fall back to the global settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppProfile {
+    // Capture is muted and nothing is typed while the app has focus.
+    pub disabled: bool,
+    pub inject_mode: Option<InjectMode>,
+    pub formatting: Option<Vec<FormattingRule>>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AppProfiles {
+    apps: BTreeMap<String, AppProfile>,
+}
+
+static PROFILES: Lazy<Mutex<AppProfiles>> = Lazy::new(|| Mutex::new(store::load_json(STORE_FILE)));
+static FOCUSED: Lazy<Mutex<Option<FocusedApp>>> = Lazy::new(Mutex::default);
+// Set while capture is paused for a disabled app rather than by the user.
+static MUTED: AtomicBool = AtomicBool::new(false);
+
+// "KeePassXC.exe", "keepassxc" and "KeePassXC" are the same app.
+fn key(app: &str) -> String {
+    let app = app.trim().to_lowercase();
+    app.strip_suffix(".exe").unwrap_or(&app).to_string()
+}
+
+fn profile_for(app: Option<&FocusedApp>) -> Option<AppProfile> {
+    let app = app?;
+    PROFILES.lock().apps.get(&key(&app.app)).cloned()
+}
+
+pub fn focused() -> Option<FocusedApp> {
+    FOCUSED.lock().clone()
+}
+
+pub fn focused_profile() -> Option<AppProfile> {
+    profile_for(FOCUSED.lock().as_ref())
+}
+
+pub fn has_profiles() -> bool {
+    !PROFILES.lock().apps.is_empty()
+}
+
+pub fn language() -> Option<String> {
+    focused_profile().and_then(|profile| profile.language)
+}
+
+pub fn formatting_rules() -> Option<Vec<FormattingRule>> {
+    focused_profile().and_then(|profile| profile.formatting)
+}
+
+// Typing is skipped, not just muted, so a transcript of speech from before
+// the switch never lands in a password field.
+pub fn dictation_disabled() -> bool {
+    focused_profile().map_or(false, |profile| profile.disabled)
+}
+
+#[cfg(target_os = "linux")]
+fn detect() -> Option<FocusedApp> {
+    // Wayland does not tell clients which window has focus.
+    std::env::var_os("DISPLAY")?;
+    let output = std::process::Command::new("xdotool")
+        .args(["getactivewindow", "getwindowpid", "getwindowname"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8_lossy(&output.stdout);
+    let mut lines = output.lines();
+    let pid = lines.next()?.trim();
+    let app = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(FocusedApp {
+        app: app.trim().to_string(),
+        title: lines.next().unwrap_or_default().to_string(),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> Option<FocusedApp> {
+    // The title needs the accessibility permission; the app name does not.
+    const SCRIPT: &str = r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set frontTitle to ""
+    try
+        set frontTitle to name of front window of frontApp
+    end try
+    return (name of frontApp) & linefeed & frontTitle
+end tell"#;
+    let output = std::process::Command::new("osascript")
+        .args(["-e", SCRIPT])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8_lossy(&output.stdout);
+    let mut lines = output.lines();
+    Some(FocusedApp {
+        app: lines.next()?.trim().to_string(),
+        title: lines.next().unwrap_or_default().trim().to_string(),
+    })
+}
+
+#[cfg(windows)]
+fn detect() -> Option<FocusedApp> {
+    use std::ffi::c_void;
+    use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> *mut c_void;
+        fn GetWindowTextW(window: *mut c_void, text: *mut u16, max: i32) -> i32;
+        fn GetWindowThreadProcessId(window: *mut c_void, pid: *mut u32) -> u32;
+    }
+
+    let (pid, title) = unsafe {
+        let window = GetForegroundWindow();
+        if window.is_null() {
+            return None;
+        }
+        let mut pid = 0;
+        GetWindowThreadProcessId(window, &mut pid);
+        let mut title = [0u16; 512];
+        let len = GetWindowTextW(window, title.as_mut_ptr(), title.len() as i32);
+        (pid, String::from_utf16_lossy(&title[..len.max(0) as usize]))
+    };
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    if !system.refresh_process(pid) {
+        return None;
+    }
+    Some(FocusedApp {
+        app: system.process(pid)?.name().to_string(),
+        title,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn detect() -> Option<FocusedApp> {
+    None
+}
+
+// Mutes capture for a disabled app, and moves the backends to the focused
+// app's language.
+fn apply(state: &AppState, before: Option<&AppProfile>, after: Option<&AppProfile>) {
+    let disabled = after.map_or(false, |profile| profile.disabled);
+    if disabled
+        && !MUTED.load(Ordering::SeqCst)
+        && backends::is_running(state)
+        && !audio::is_paused()
+    {
+        match backends::set_paused(state, true) {
+            Ok(_) => MUTED.store(true, Ordering::SeqCst),
+            Err(error) => state
+                .bus
+                .publish(AgentEvent::error("window_tracker", error)),
+        }
+    } else if !disabled && MUTED.swap(false, Ordering::SeqCst) && audio::is_paused() {
+        if let Err(error) = backends::set_paused(state, false) {
+            state
+                .bus
+                .publish(AgentEvent::error("window_tracker", error));
+        }
+    }
+    let language = |profile: Option<&AppProfile>| profile.and_then(|p| p.language.clone());
+    if language(before) != language(after) {
+        if let Err(error) = backends::set_language(state, profiles::stt_language(state)) {
+            state
+                .bus
+                .publish(AgentEvent::error("window_tracker", error));
+        }
+    }
+}
+
+// A failed lookup keeps the last app, so a window without a process, or a
+// brief gap while focus moves, does not unmute a disabled app.
+pub fn spawn(state: AppState) {
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = match detect() {
+            Some(current) => current,
+            None => continue,
+        };
+        let previous = {
+            let mut focused = FOCUSED.lock();
+            if focused.as_ref() == Some(&current) {
+                continue;
+            }
+            focused.replace(current.clone())
+        };
+        state
+            .bus
+            .publish(AgentEvent::FocusedAppChanged(current.clone()));
+        if previous.as_ref().map(|app| key(&app.app)) != Some(key(&current.app)) {
+            apply(
+                &state,
+                profile_for(previous.as_ref()).as_ref(),
+                profile_for(Some(&current)).as_ref(),
+            );
+        }
+    });
+}
+
+fn save(profiles: AppProfiles) -> CommandResult<()> {
+    store::save_json(STORE_FILE, &profiles)?;
+    *PROFILES.lock() = profiles;
+    Ok(())
+}
+
+// For an imported settings file, which may come from another OS.
+pub fn replace(apps: BTreeMap<String, AppProfile>) -> Result<(), AppError> {
+    save(AppProfiles {
+        apps: apps
+            .into_iter()
+            .map(|(app, profile)| (key(&app), profile))
+            .filter(|(app, _)| !app.is_empty())
+            .collect(),
+    })
+}
+
+#[tauri::command]
+pub fn get_focused_app() -> Option<FocusedApp> {
+    focused()
+}
+
+#[tauri::command]
+pub fn list_app_profiles() -> BTreeMap<String, AppProfile> {
+    PROFILES.lock().apps.clone()
+}
+
+// `app` is the process name `focused_app_changed` reports. Muting and the
+// language apply the next time the app gains focus.
+#[tauri::command]
+pub fn set_app_profile(app: String, profile: AppProfile) -> CommandResult<()> {
+    let app = key(&app);
+    if app.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "App name cannot be empty",
+        ));
+    }
+    if let Some(rules) = &profile.formatting {
+        formatting::validate(rules)?;
+    }
+    if let Some(language) = &profile.language {
+        if !profiles::is_language_code(language) {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Not a language code: {}", language),
+            ));
+        }
+    }
+    let mut profiles = PROFILES.lock().clone();
+    profiles.apps.insert(app, profile);
+    save(profiles)
+}
+
+#[tauri::command]
+pub fn remove_app_profile(app: String) -> CommandResult<()> {
+    let mut profiles = PROFILES.lock().clone();
+    if profiles.apps.remove(&key(&app)).is_none() {
+        return Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("No profile for {}", app),
+        ));
+    }
+    save(profiles)
+}
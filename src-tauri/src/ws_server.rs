@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use axum_server::Handle;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::agents;
+use crate::backends;
+use crate::bus::AgentEvent;
+use crate::daemon::InputSource;
+use crate::error::{AppError, CommandResult, ErrorCode};
+use crate::store;
+use crate::text_inject;
+use crate::usage;
+use crate::AppState;
+
+const STORE_FILE: &str = "ws_server.json";
+const DEFAULT_PORT: u16 = 8766;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WsServerConfig {
+    pub port: u16,
+    // Generated on the first start.
+    pub token: Option<String>,
+    // For the `/action` endpoints; generated on the first start too, and
+    // replaced by `rotate_webhook_token`.
+    pub action_token: Option<String>,
+    // Off, the server only listens on localhost.
+    pub allow_remote: bool,
+}
+
+impl Default for WsServerConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            token: None,
+            action_token: None,
+            allow_remote: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WsServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    // For pasting into the client.
+    pub token: Option<String>,
+    pub action_token: Option<String>,
+}
+
+#[derive(Default)]
+pub struct WsState {
+    server: Mutex<Option<(Handle, WsServerStatus)>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ActionRequest {
+    text: Option<String>,
+}
+
+// Read on every request, so a rotated token applies at once.
+static ACTION_TOKEN: Lazy<Mutex<Option<String>>> = Lazy::new(Mutex::default);
+
+// Browsers cannot set headers on a WebSocket, so the token can also come as
+// `?token=`.
+fn authorized(headers: &HeaderMap, query: &HashMap<String, String>, token: &str) -> bool {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| query.get("token").map(String::as_str)) == Some(token)
+}
+
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+    State((state, token)): State<(AppState, String)>,
+) -> Response {
+    if !authorized(&headers, &query, &token) {
+        return (StatusCode::UNAUTHORIZED, "Missing or wrong token").into_response();
+    }
+    ws.on_upgrade(move |socket| serve(socket, state))
+}
+
+fn error_reply(error: &AppError) -> Value {
+    json!({ "type": "result", "ok": false, "error": error.message, "code": error.code })
+}
+
+// Control messages are `{"type": "start"}` and `{"type": "stop"}`, or just
+// the bare word; each gets one `result` reply.
+async fn control(state: &AppState, text: &str) -> Value {
+    let command = match serde_json::from_str::<Value>(text) {
+        Ok(message) => message["type"].as_str().unwrap_or_default().to_string(),
+        Err(_) => text.trim().to_string(),
+    };
+    let state = state.clone();
+    let result = match command.as_str() {
+        "start" => {
+            tauri::async_runtime::spawn_blocking(move || {
+                backends::start(&state, InputSource::Microphone)
+            })
+            .await
+        }
+        "stop" => tauri::async_runtime::spawn_blocking(move || backends::stop(&state)).await,
+        other => {
+            return error_reply(&AppError::new(
+                ErrorCode::InvalidInput,
+                format!("Unknown command: {}", other),
+            ))
+        }
+    };
+    match result {
+        Ok(Ok(message)) => json!({ "type": "result", "ok": true, "message": message }),
+        Ok(Err(error)) => error_reply(&error),
+        Err(e) => error_reply(&AppError::from(format!("Command task failed: {}", e))),
+    }
+}
+
+fn action_error(error: AppError) -> Response {
+    let status = match error.code {
+        ErrorCode::InvalidInput => StatusCode::BAD_REQUEST,
+        ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        ErrorCode::AlreadyRunning | ErrorCode::NotRunning => StatusCode::CONFLICT,
+        ErrorCode::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let body = json!({ "ok": false, "error": error.message, "code": error.code });
+    (status, Json(body)).into_response()
+}
+
+// `POST /action/{toggle|start|stop|inject}`; `inject` types the body's
+// `text` into the focused app.
+async fn action(
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+    State(state): State<AppState>,
+    Path(action): Path<String>,
+    request: Option<Json<ActionRequest>>,
+) -> Response {
+    let token = ACTION_TOKEN.lock().clone();
+    if !token.map_or(false, |token| authorized(&headers, &query, &token)) {
+        return (StatusCode::UNAUTHORIZED, "Missing or wrong token").into_response();
+    }
+    let text = request.and_then(|Json(request)| request.text);
+    let result = tauri::async_runtime::spawn_blocking(move || match action.as_str() {
+        "toggle" => agents::toggle(&state, agents::DEFAULT_AGENT),
+        "start" => backends::start(&state, InputSource::Microphone),
+        "stop" => backends::stop(&state),
+        "inject" => match text.filter(|text| !text.is_empty()) {
+            Some(text) => text_inject::type_text(&state, &text, None)
+                .map(|_| format!("Typed {} characters", text.chars().count())),
+            None => Err(AppError::new(
+                ErrorCode::InvalidInput,
+                "inject needs a text field",
+            )),
+        },
+        other => Err(AppError::new(
+            ErrorCode::NotFound,
+            format!("Unknown action: {}", other),
+        )),
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::from(format!("Action task failed: {}", e))));
+    usage::record("ws_server.action");
+    match result {
+        Ok(message) => Json(json!({ "ok": true, "message": message })).into_response(),
+        Err(error) => action_error(error),
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: Value) -> bool {
+    socket
+        .send(Message::Text(message.to_string()))
+        .await
+        .is_ok()
+}
+
+async fn serve(mut socket: WebSocket, state: AppState) {
+    let mut events = state.bus.subscribe();
+    let hello = json!({ "type": "listening", "listening": backends::is_running(&state) });
+    if !send(&mut socket, hello).await {
+        return;
+    }
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let reply = control(&state, &text).await;
+                    if !send(&mut socket, reply).await {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => {
+                let message = match event {
+                    Ok(AgentEvent::Transcript(t)) => json!({ "type": "transcript", "transcript": t }),
+                    Ok(AgentEvent::ListeningChanged(listening)) => {
+                        json!({ "type": "listening", "listening": listening })
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if !send(&mut socket, message).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn action_token() -> String {
+    format!("vwa_{}", uuid::Uuid::new_v4().simple())
+}
+
+fn load_config() -> CommandResult<WsServerConfig> {
+    let mut config: WsServerConfig = store::load_json(STORE_FILE);
+    if config.token.is_none() || config.action_token.is_none() {
+        config.token = config
+            .token
+            .or_else(|| Some(format!("vws_{}", uuid::Uuid::new_v4().simple())));
+        config.action_token = config.action_token.or_else(|| Some(action_token()));
+        store::save_json(STORE_FILE, &config)?;
+    }
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn start_ws_server(
+    state: tauri::State<'_, AppState>,
+    ws: tauri::State<'_, WsState>,
+    port: Option<u16>,
+) -> CommandResult<WsServerStatus> {
+    let mut server = ws.server.lock();
+    if server.is_some() {
+        return Err(AppError::new(
+            ErrorCode::AlreadyRunning,
+            "WebSocket server is already running",
+        ));
+    }
+    let mut config = load_config()?;
+    if let Some(port) = port.filter(|&port| port != config.port) {
+        config.port = port;
+        store::save_json(STORE_FILE, &config)?;
+    }
+    let token = config.token.clone().unwrap_or_default();
+    let ip = if config.allow_remote {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let addr = SocketAddr::from((ip, config.port));
+    // Bound here so a port in use fails the command, not the task.
+    let listener = TcpListener::bind(addr)
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Failed,
+                format!("Failed to listen on {}: {}", addr, e),
+            )
+            .with_context("port", config.port)
+        })?;
+
+    usage::record("ws_server.start");
+    let handle = Handle::new();
+    let serve_handle = handle.clone();
+    let state = state.inner().clone();
+    *ACTION_TOKEN.lock() = config.action_token.clone();
+    let app = Router::new()
+        .route("/", get(upgrade))
+        .with_state((state.clone(), token.clone()))
+        .merge(
+            Router::new()
+                .route("/action/:action", post(action))
+                .with_state(state.clone()),
+        );
+    tauri::async_runtime::spawn(async move {
+        let result = axum_server::from_tcp(listener)
+            .handle(serve_handle)
+            .serve(app.into_make_service())
+            .await;
+        if let Err(e) = result {
+            state.bus.publish(AgentEvent::error(
+                "ws_server",
+                format!("WebSocket server failed: {}", e),
+            ));
+        }
+    });
+
+    let status = WsServerStatus {
+        running: true,
+        address: Some(format!("ws://{}", addr)),
+        token: Some(token),
+        action_token: config.action_token,
+    };
+    *server = Some((handle, status.clone()));
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn stop_ws_server(ws: tauri::State<'_, WsState>) -> CommandResult<String> {
+    match ws.server.lock().take() {
+        Some((handle, _)) => {
+            handle.graceful_shutdown(Some(Duration::from_secs(2)));
+            Ok("WebSocket server stopped".to_string())
+        }
+        None => Err(AppError::new(
+            ErrorCode::NotRunning,
+            "WebSocket server is not running",
+        )),
+    }
+}
+
+#[tauri::command]
+pub fn get_ws_server_status(ws: tauri::State<'_, WsState>) -> WsServerStatus {
+    match ws.server.lock().as_ref() {
+        Some((_, status)) => status.clone(),
+        None => WsServerStatus {
+            running: false,
+            address: None,
+            token: None,
+            action_token: None,
+        },
+    }
+}
+
+// The old token stops working at once, on a running server too.
+#[tauri::command]
+pub fn rotate_webhook_token(ws: tauri::State<'_, WsState>) -> CommandResult<String> {
+    let mut config = load_config()?;
+    let token = action_token();
+    config.action_token = Some(token.clone());
+    store::save_json(STORE_FILE, &config)?;
+    if let Some((_, status)) = ws.server.lock().as_mut() {
+        status.action_token = Some(token.clone());
+        *ACTION_TOKEN.lock() = Some(token.clone());
+    }
+    Ok(token)
+}
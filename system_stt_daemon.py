@@ -2,11 +2,13 @@
 """
 System-wide STT daemon that captures audio and types transcribed text
 """
+import argparse
 import asyncio
+import json
 import logging
 import sounddevice as sd
 import numpy as np
-from faster_whisper import WhisperModel
+from faster_whisper import BatchedInferencePipeline, WhisperModel, decode_audio, download_model
 import os
 
 # Check if we can import pyautogui safely
@@ -22,92 +24,489 @@ try:
 except (ImportError, Exception) as e:
     PYAUTOGUI_AVAILABLE = False
     print(f"⚠️  pyautogui not available ({e}). Text will be printed to console instead of typed")
+import gc
+import mmap
+import struct
+import sys
 import threading
 import queue
+import signal
 import time
+import uuid
+import wave
 from collections import deque
 
 logging.basicConfig(level=logging.INFO)
 logger = logging.getLogger(__name__)
 
+# RMS below which an idle daemon does not bother reloading its model
+SPEECH_RMS_THRESHOLD = 0.01
+
+# Shared ring written by the host (src-tauri/src/audio_ring.rs)
+RING_MAGIC = 0x52414D56
+RING_VERSION = 1
+RING_CLOSED_AT = 16
+RING_WRITE_AT = 64
+RING_READ_AT = 128
+RING_DATA_AT = 192
+
+# pyannote's speaker embedding model, used with --diarize
+SPEAKER_MODEL = "pyannote/wespeaker-voxceleb-resnet34-LM"
+# Cosine similarity above which a voice counts as one already heard
+SPEAKER_SIMILARITY = 0.5
+# Shorter stretches give embeddings too noisy to tell voices apart
+MIN_SPEAKER_SECONDS = 1.0
+
+def now_ms():
+    """Wall-clock milliseconds, comparable with the host process's clock"""
+    return time.time() * 1000
+
+class SpeakerTracker:
+    """Labels stretches of audio "Speaker 1", "Speaker 2", ... by voice
+
+    Each stretch's embedding is compared with the voices heard so far; one close
+    enough gets that voice's label, anything else becomes a new speaker.
+    """
+    def __init__(self, device="cpu"):
+        import torch
+        from pyannote.audio import Inference, Model
+        self.torch = torch
+        self.inference = Inference(Model.from_pretrained(SPEAKER_MODEL), window="whole",
+                                   device=torch.device(device))
+        # Running sum of each speaker's normalized embeddings
+        self.voices = []
+
+    def label(self, audio, sample_rate):
+        if len(audio) < MIN_SPEAKER_SECONDS * sample_rate:
+            return None
+        waveform = self.torch.from_numpy(np.asarray(audio, dtype=np.float32)).unsqueeze(0)
+        embedding = np.asarray(self.inference({"waveform": waveform,
+                                               "sample_rate": sample_rate})).reshape(-1)
+        embedding = embedding / (np.linalg.norm(embedding) or 1.0)
+        best, best_similarity = None, SPEAKER_SIMILARITY
+        for i, voice in enumerate(self.voices):
+            similarity = float(np.dot(embedding, voice / np.linalg.norm(voice)))
+            if similarity > best_similarity:
+                best, best_similarity = i, similarity
+        if best is None:
+            self.voices.append(embedding)
+            best = len(self.voices) - 1
+        else:
+            self.voices[best] = self.voices[best] + embedding
+        return f"Speaker {best + 1}"
+
 class SystemSTTDaemon:
-    def __init__(self, model_size="base", sample_rate=16000, chunk_duration=3.0):
-        self.model = WhisperModel(model_size, device="cpu", compute_type="int8")
+    def __init__(self, model_size="base", sample_rate=16000, pre_roll=0.3, silence_timeout=0.7,
+                 max_utterance=30.0,
+                 input_mode="mic", json_output=False, language="en", idle_unload=None,
+                 input_file=None, speed=1.0, no_type=False, device="cpu", compute_type="int8",
+                 threads=0, beam_size=5, batch_size=1, ring_path=None, input_device=None,
+                 rpc=False, save_audio=None, initial_prompt=None, diarize=False):
+        self.model_size = model_size
+        self.save_audio = save_audio
+        # Names and jargon Whisper should spell as the user does
+        self.initial_prompt = initial_prompt
+        self.rpc = rpc
+        # Set by the host's start request, or by stopping before it
+        self.started = threading.Event()
+        self.stop_requested = False
+        # Responses, audio status and transcripts are written from different threads
+        self.emit_lock = threading.Lock()
+        self.ring_path = ring_path
+        self.input_device = input_device
+        self.device = device
+        self.compute_type = compute_type
+        self.threads = threads
+        self.beam_size = beam_size
+        self.batch_size = batch_size
+        # With --rpc the model loads on the host's start request
+        self.model = None if rpc else self.load_model()
+        self.speakers = self.load_speakers() if diarize else None
+        self.idle_unload = idle_unload
+        self.last_activity = time.monotonic()
+        self.speech_at = None
+        # None lets Whisper detect the language of each chunk
+        self.language = None if language == "auto" else language
         self.sample_rate = sample_rate
-        self.chunk_duration = chunk_duration
-        self.chunk_samples = int(sample_rate * chunk_duration)
+        # Seconds of audio kept from before speech starts, of quiet that ends an
+        # utterance, and of speech after which an utterance is cut regardless
+        self.pre_roll = pre_roll
+        self.silence_timeout = silence_timeout
+        self.max_utterance = max_utterance
+        self.input_mode = input_mode
+        self.input_file = input_file
+        self.speed = speed
+        self.no_type = no_type
+        self.samples_seen = 0
+        self.json_output = json_output
         
-        # Audio buffer
-        self.audio_buffer = deque(maxlen=self.chunk_samples * 2)
+        # Audio before speech, and the blocks of the utterance being heard
+        self.pre_roll_buffer = deque(maxlen=int(sample_rate * pre_roll))
+        self.utterance = []
+        self.utterance_samples = 0
+        self.quiet_samples = 0
+        self.last_audio_at = time.monotonic()
         self.audio_queue = queue.Queue()
         
         # Control flags
         self.recording = False
         self.processing = False
+        # Set by the host to mute input without unloading the model
+        self.paused = False
         
         # Disable pyautogui failsafe if available
         if PYAUTOGUI_AVAILABLE:
             pyautogui.FAILSAFE = False
         
-    def audio_callback(self, indata, frames, time, status):
+    def audio_callback(self, indata, frames, time_info, status):
         """Callback for audio input"""
+        if self.paused:
+            return
         if status:
             logger.warning(f"Audio status: {status}")
+            if self.json_output:
+                self.emit({"type": "audio_status", "status": str(status)})
         
-        # Convert to mono and add to buffer
+        # Convert to mono
         audio_data = indata[:, 0] if indata.ndim > 1 else indata
-        self.audio_buffer.extend(audio_data)
-        
-        # If we have enough audio, queue it for processing
-        if len(self.audio_buffer) >= self.chunk_samples:
-            chunk = np.array(list(self.audio_buffer)[-self.chunk_samples:])
-            if not self.audio_queue.full():
-                self.audio_queue.put(chunk.copy())
-    
+        self.samples_seen += len(audio_data)
+        self.last_audio_at = time.monotonic()
+        loud = float(np.sqrt(np.mean(np.square(audio_data)))) >= SPEECH_RMS_THRESHOLD
+        if not self.utterance:
+            if not loud:
+                self.pre_roll_buffer.extend(audio_data)
+                return
+            # First loud block since the last utterance; the start of the VAD stage
+            self.speech_at = now_ms()
+            self.utterance = [np.array(self.pre_roll_buffer, dtype=np.float32)]
+            self.utterance_samples = len(self.pre_roll_buffer)
+            self.pre_roll_buffer.clear()
+        self.utterance.append(audio_data.copy())
+        self.utterance_samples += len(audio_data)
+        self.quiet_samples = 0 if loud else self.quiet_samples + len(audio_data)
+        if (self.quiet_samples >= self.silence_timeout * self.sample_rate
+                or self.utterance_samples >= self.max_utterance * self.sample_rate):
+            self.end_utterance()
+
+    def end_utterance(self):
+        """Queue the utterance heard so far for transcription"""
+        if not self.utterance:
+            return
+        chunk = np.concatenate(self.utterance)
+        self.utterance = []
+        self.utterance_samples = 0
+        self.quiet_samples = 0
+        self.audio_queue.put((chunk, self.samples_seen, time.monotonic(), self.speech_at,
+                              now_ms()))
+        self.speech_at = None
+
+    def end_utterance_if_stalled(self):
+        """End the utterance when gated input has sent nothing for the silence timeout"""
+        if self.utterance and time.monotonic() - self.last_audio_at >= self.silence_timeout:
+            self.end_utterance()
+
+    def load_model(self):
+        return WhisperModel(self.model_size, device=self.device, compute_type=self.compute_type,
+                            cpu_threads=self.threads)
+
+    def load_speakers(self):
+        """The speaker tracker, or None when pyannote.audio is unavailable"""
+        try:
+            return SpeakerTracker(self.device)
+        except Exception as e:
+            logger.warning(f"Speaker diarization unavailable ({e}); transcripts will have no speaker")
+            return None
+
+    def speaker_of(self, audio, start, end):
+        """The speaker of the stretch between start and end seconds, if diarizing"""
+        if self.speakers is None or start is None or end is None:
+            return None
+        try:
+            return self.speakers.label(
+                audio[int(start * self.sample_rate):int(end * self.sample_rate)],
+                self.sample_rate)
+        except Exception as e:
+            logger.warning(f"Speaker diarization error: {e}")
+            return None
+
+    def emit_model_status(self, status):
+        if self.json_output:
+            self.emit({"type": "model_status", "status": status, "model": self.model_size})
+
+    def unload_if_idle(self):
+        """Drop the model after idle_unload seconds without speech"""
+        if self.model is None or self.idle_unload is None:
+            return
+        if time.monotonic() - self.last_activity < self.idle_unload:
+            return
+        logger.info(f"Idle for {self.idle_unload}s, unloading {self.model_size} model")
+        self.model = None
+        gc.collect()
+        self.emit_model_status("unloaded")
+
+    def ensure_model(self, audio_chunk):
+        """Reload an unloaded model once the chunk sounds like speech"""
+        if self.model is not None:
+            return True
+        if float(np.sqrt(np.mean(np.square(audio_chunk)))) < SPEECH_RMS_THRESHOLD:
+            return False
+        logger.info(f"Speech detected, reloading {self.model_size} model")
+        self.model = self.load_model()
+        self.emit_model_status("loaded")
+        return True
+
     def transcribe_audio(self, audio_chunk):
         """Transcribe audio chunk using Whisper"""
         try:
             segments, info = self.model.transcribe(
                 audio_chunk,
-                language="en",
+                language=self.language,
+                beam_size=self.beam_size,
                 vad_filter=True,
-                word_timestamps=False
+                word_timestamps=True,
+                initial_prompt=self.initial_prompt
             )
             
+            segments = list(segments)
             text = " ".join(segment.text for segment in segments).strip()
-            return text
+            if not segments:
+                return text, None, None, None, None, []
+            confidence = float(np.exp(np.mean([s.avg_logprob for s in segments])))
+            words = [word for segment in segments for word in segment.words or []]
+            return text, confidence, segments[0].start, segments[-1].end, info.language, words
         except Exception as e:
             logger.error(f"Transcription error: {e}")
-            return ""
+            return "", None, None, None, None, []
+
+    def write(self, message):
+        with self.emit_lock:
+            sys.stdout.write(json.dumps(message) + "\n")
+            sys.stdout.flush()
+
+    def emit(self, message):
+        """Report a message to the host process as a JSON line, a notification with --rpc"""
+        if self.rpc:
+            params = dict(message)
+            message = {"jsonrpc": "2.0", "method": params.pop("type"), "params": params}
+        self.write(message)
+
+    def respond(self, request_id, result=None, error=None):
+        response = {"jsonrpc": "2.0", "id": request_id}
+        if error is None:
+            response["result"] = {} if result is None else result
+        else:
+            response["error"] = error
+        self.write(response)
+
+    def configure(self, params):
+        """Apply the host's settings; each applies from the next chunk"""
+        if "paused" in params:
+            self.paused = bool(params["paused"])
+            if self.paused:
+                # A half-heard utterance would be transcribed on resume otherwise
+                self.pre_roll_buffer.clear()
+                self.utterance = []
+                self.utterance_samples = 0
+                self.quiet_samples = 0
+                self.speech_at = None
+        if "pre_roll" in params:
+            self.pre_roll = float(params["pre_roll"])
+            self.pre_roll_buffer = deque(self.pre_roll_buffer,
+                                         maxlen=int(self.sample_rate * self.pre_roll))
+        if "silence_timeout" in params:
+            self.silence_timeout = float(params["silence_timeout"])
+        if "max_utterance" in params:
+            self.max_utterance = float(params["max_utterance"])
+        if "language" in params:
+            # null detects it
+            self.language = params["language"]
+        if "prompt" in params:
+            self.initial_prompt = params["prompt"]
+        if "protocol" not in params:
+            return {}
+        return {"daemon": "system_stt_daemon",
+                "protocol": min(int(params["protocol"]), PROTOCOL_VERSION)}
+
+    def load_and_start(self, request_id):
+        try:
+            if self.model is None:
+                self.model = self.load_model()
+                self.emit_model_status("loaded")
+        except Exception as e:
+            self.respond(request_id, error={"code": -32000, "message": f"Failed to load model: {e}"})
+            return
+        self.started.set()
+        self.respond(request_id)
+
+    def serve_rpc(self):
+        """Answer the host's JSON-RPC requests on stdin until it closes"""
+        for line in sys.stdin:
+            try:
+                request = json.loads(line)
+            except ValueError:
+                continue
+            request_id = request.get("id")
+            method = request.get("method")
+            params = request.get("params") or {}
+            if method == "configure":
+                self.respond(request_id, self.configure(params))
+            elif method == "start":
+                # Loading can take minutes; pings are still answered meanwhile
+                threading.Thread(target=self.load_and_start, args=(request_id,),
+                                 daemon=True).start()
+            elif method == "stop":
+                self.stop()
+                self.respond(request_id)
+            elif method == "ping":
+                self.respond(request_id)
+            else:
+                self.respond(request_id, error={"code": -32601,
+                                                "message": f"Unknown method {method}"})
+        # The host has gone
+        self.stop()
+
+    def serve(self):
+        """Answer requests, and run as start() does once the host sends start"""
+        threading.Thread(target=self.serve_rpc, daemon=True).start()
+        signal.signal(signal.SIGTERM, self.stop)
+        self.started.wait()
+        if not self.stop_requested:
+            self.start()
+
+    def emit_transcript(self, text, confidence, start=None, end=None, processing_ms=None,
+                        timings=None, audio=None, language=None, speaker=None, words=None,
+                        offset=0.0):
+        """Report a final transcript to the host process
+
+        start/end are seconds from the beginning of the audio stream or file.
+        timings holds wall-clock stamps (ms) of each stage for latency tracking.
+        audio is a WAV of the chunk, which the host takes over.
+        language is the code Whisper transcribed in, set or detected.
+        speaker is a label such as "Speaker 1", with --diarize.
+        words are Whisper's timed words, offset by offset seconds onto the stream.
+        """
+        words = [{"word": w.word.strip(), "start": offset + w.start, "end": offset + w.end,
+                  "probability": w.probability} for w in words or []]
+        self.emit({"type": "transcript", "text": text, "confidence": confidence,
+                   "start": start, "end": end, "processing_ms": processing_ms,
+                   "timings": timings, "audio": audio, "language": language,
+                   "speaker": speaker, "words": words})
+
+    def write_chunk(self, audio_chunk):
+        """Save a chunk as 16-bit WAV in the --save-audio folder and return its path"""
+        path = os.path.join(self.save_audio, f"{uuid.uuid4()}.wav")
+        pcm = (np.clip(audio_chunk, -1.0, 1.0) * 32767).astype("<i2")
+        with wave.open(path, "wb") as f:
+            f.setnchannels(1)
+            f.setsampwidth(2)
+            f.setframerate(self.sample_rate)
+            f.writeframes(pcm.tobytes())
+        return path
     
+    def transcribe_whole_file(self, path):
+        """Transcribe a file in one go, batching segments when batch_size > 1"""
+        if self.batch_size > 1:
+            return BatchedInferencePipeline(model=self.model).transcribe(
+                path, language=self.language, beam_size=self.beam_size, vad_filter=True,
+                batch_size=self.batch_size, initial_prompt=self.initial_prompt,
+                word_timestamps=True)
+        return self.model.transcribe(path, language=self.language, beam_size=self.beam_size,
+                                     vad_filter=True, initial_prompt=self.initial_prompt,
+                                     word_timestamps=True)
+
+    def transcribe_file(self, path):
+        """Transcribe an audio file and report each segment as a JSON line"""
+        segments, info = self.transcribe_whole_file(path)
+        audio = decode_audio(path, sampling_rate=self.sample_rate) if self.speakers else None
+        for segment in segments:
+            text = segment.text.strip()
+            if text:
+                self.emit_transcript(text, float(np.exp(segment.avg_logprob)),
+                                     segment.start, segment.end, language=info.language,
+                                     speaker=self.speaker_of(audio, segment.start, segment.end),
+                                     words=segment.words)
+
+    def benchmark_file(self, path, runs):
+        """Time repeated transcriptions of one file, one JSON line per run"""
+        for _ in range(runs):
+            started = time.monotonic()
+            segments, info = self.transcribe_whole_file(path)
+            text = " ".join(segment.text.strip() for segment in segments).strip()
+            self.emit({"type": "benchmark_run", "stt_ms": (time.monotonic() - started) * 1000,
+                       "text": text, "duration": info.duration})
+
     def type_text(self, text):
         """Type transcribed text into active application"""
         if text and len(text.strip()) > 2:  # Only type meaningful text
             logger.info(f"Typing: {text}")
+            started = time.monotonic()
             if PYAUTOGUI_AVAILABLE:
                 pyautogui.typewrite(text + " ")
             else:
                 print(f"[Transcribed]: {text}")
+            if self.json_output:
+                self.emit({"type": "injected", "ms": (time.monotonic() - started) * 1000})
     
     def process_audio_worker(self):
         """Worker thread for processing audio chunks"""
         while self.processing:
+            self.unload_if_idle()
             try:
                 # Get audio chunk with timeout
-                audio_chunk = self.audio_queue.get(timeout=1.0)
+                audio_chunk, end_sample, queued_at, speech_at, captured_at = \
+                    self.audio_queue.get(timeout=1.0)
+            except queue.Empty:
+                continue
+            try:
+                if not self.ensure_model(audio_chunk):
+                    continue
                 
                 # Transcribe
-                text = self.transcribe_audio(audio_chunk)
+                stt_started_at = now_ms()
+                text, confidence, start, end, language, words = self.transcribe_audio(audio_chunk)
+                stt_finished_at = now_ms()
                 
                 # Type if we got text
                 if text:
-                    self.type_text(text)
+                    self.last_activity = time.monotonic()
+                    if self.json_output:
+                        offset = (end_sample - len(audio_chunk)) / self.sample_rate
+                        processing_ms = (time.monotonic() - queued_at) * 1000
+                        timings = {"speech_at": speech_at, "captured_at": captured_at,
+                                   "stt_started_at": stt_started_at,
+                                   "stt_finished_at": stt_finished_at}
+                        audio = None
+                        if self.save_audio:
+                            try:
+                                audio = self.write_chunk(audio_chunk)
+                            except OSError as e:
+                                logger.warning(f"Could not save audio: {e}")
+                        speaker = self.speaker_of(audio_chunk, start, end)
+                        self.emit_transcript(text, confidence, offset + start, offset + end,
+                                             processing_ms, timings, audio, language, speaker,
+                                             words, offset)
+                    if not self.no_type:
+                        self.type_text(text)
                     
-            except queue.Empty:
-                continue
             except Exception as e:
                 logger.error(f"Processing error: {e}")
+            finally:
+                self.audio_queue.task_done()
     
+    def stop(self, *_):
+        """Stop capturing; audio already queued is still transcribed (SIGTERM)"""
+        logger.info("Stopping STT daemon...")
+        self.recording = False
+        # A --rpc daemon still waiting for start exits instead
+        self.stop_requested = True
+        self.started.set()
+
+    def finish(self, process_thread):
+        """Wait for queued chunks so no captured audio is dropped"""
+        self.audio_queue.join()
+        self.processing = False
+        process_thread.join()
+
     def start(self):
         """Start the STT daemon"""
         logger.info("Starting system STT daemon...")
@@ -116,13 +515,30 @@ class SystemSTTDaemon:
         self.recording = True
         self.processing = True
         
+        signal.signal(signal.SIGTERM, self.stop)
+
         # Start processing thread
         process_thread = threading.Thread(target=self.process_audio_worker, daemon=True)
         process_thread.start()
         
+        if self.input_mode == "stdin":
+            self.read_stdin_audio()
+        elif self.input_mode == "shm":
+            self.read_ring_audio()
+        elif self.input_mode == "file":
+            self.play_file_audio()
+        else:
+            self.read_mic_audio()
+        # Speech still going when input stopped
+        self.end_utterance()
+        self.finish(process_thread)
+
+    def read_mic_audio(self):
+        """Capture from the microphone until stopped"""
         # Start audio stream
         with sd.InputStream(
             samplerate=self.sample_rate,
+            device=self.input_device,
             channels=1,
             callback=self.audio_callback,
             blocksize=1024,
@@ -133,10 +549,164 @@ class SystemSTTDaemon:
                 while self.recording:
                     time.sleep(0.1)
             except KeyboardInterrupt:
-                logger.info("Stopping STT daemon...")
-                self.recording = False
-                self.processing = False
+                self.stop()
+
+    def read_stdin_audio(self):
+        """Feed 16 kHz mono s16le PCM from stdin through the audio callback"""
+        logger.info("STT daemon reading audio from stdin...")
+        frame_bytes = 1024 * 2
+        while self.recording:
+            data = sys.stdin.buffer.read(frame_bytes)
+            if not data:
+                break
+            samples = np.frombuffer(data[:len(data) - len(data) % 2], dtype=np.int16)
+            frames = (samples.astype(np.float32) / 32768.0).reshape(-1, 1)
+            self.audio_callback(frames, len(frames), None, None)
+        logger.info("Audio input closed, stopping STT daemon...")
+        self.recording = False
+
+    def read_ring_audio(self):
+        """Feed 16 kHz mono s16le PCM from the host's shared ring through the audio callback
+
+        Samples are read in place; only a block that wraps around the end is copied.
+        """
+        logger.info(f"STT daemon reading audio from {self.ring_path}...")
+        with open(self.ring_path, "r+b") as f:
+            ring = mmap.mmap(f.fileno(), 0)
+        magic, version, capacity = struct.unpack_from("<IIQ", ring, 0)
+        if magic != RING_MAGIC or version != RING_VERSION:
+            raise ValueError(f"{self.ring_path} is not an audio ring")
+        data = np.frombuffer(ring, dtype=np.int16, count=capacity // 2, offset=RING_DATA_AT)
+        block = 1024
+        while self.recording:
+            write, = struct.unpack_from("<Q", ring, RING_WRITE_AT)
+            read, = struct.unpack_from("<Q", ring, RING_READ_AT)
+            if (write - read) // 2 < block:
+                if struct.unpack_from("<I", ring, RING_CLOSED_AT)[0]:
+                    break
+                # The host gates silence out, so quiet shows up as no audio
+                self.end_utterance_if_stalled()
+                time.sleep(0.01)
+                continue
+            start = (read // 2) % len(data)
+            end = start + block
+            if end <= len(data):
+                samples = data[start:end]
+            else:
+                samples = np.concatenate((data[start:], data[:end - len(data)]))
+            frames = (samples.astype(np.float32) / 32768.0).reshape(-1, 1)
+            struct.pack_into("<Q", ring, RING_READ_AT, read + block * 2)
+            self.audio_callback(frames, len(frames), None, None)
+        logger.info("Audio ring closed, stopping STT daemon...")
+        self.recording = False
+
+    def play_file_audio(self):
+        """Feed a WAV/FLAC file through the audio callback, paced like a live device"""
+        logger.info(f"STT daemon playing {self.input_file} at {self.speed}x...")
+        audio = decode_audio(self.input_file, sampling_rate=self.sample_rate)
+        block = 1024
+        block_seconds = block / self.sample_rate / self.speed
+        for offset in range(0, len(audio), block):
+            if not self.recording:
+                break
+            frames = audio[offset:offset + block].reshape(-1, 1)
+            self.audio_callback(frames, len(frames), None, None)
+            time.sleep(block_seconds)
+        logger.info("Simulated input finished, stopping STT daemon...")
+        self.recording = False
 
 if __name__ == "__main__":
-    daemon = SystemSTTDaemon()
-    daemon.start()
+    parser = argparse.ArgumentParser(description="System-wide STT daemon")
+    parser.add_argument("--input", choices=["mic", "stdin", "shm", "file"], default="mic",
+                        help="Capture from the microphone, read PCM from stdin or --ring, "
+                             "or play --input-file")
+    parser.add_argument("--ring", metavar="PATH",
+                        help="Shared audio ring file for --input shm")
+    parser.add_argument("--input-file", metavar="PATH",
+                        help="WAV or FLAC file to play in place of the microphone")
+    parser.add_argument("--speed", type=float, default=1.0,
+                        help="Playback speed for --input file")
+    parser.add_argument("--json", action="store_true",
+                        help="Print transcripts to stdout as JSON lines")
+    parser.add_argument("--model", default="base", help="Whisper model size")
+    parser.add_argument("--language", default="en", help="Spoken language code, or auto to detect it")
+    parser.add_argument("--transcribe-file", metavar="PATH",
+                        help="Transcribe a single audio file and exit")
+    parser.add_argument("--idle-unload", type=float, metavar="SECONDS",
+                        help="Unload the model after this long without speech")
+    parser.add_argument("--pre-roll", type=float, default=0.3, metavar="SECONDS",
+                        help="Audio kept from before speech starts")
+    parser.add_argument("--silence-timeout", type=float, default=0.7, metavar="SECONDS",
+                        help="Quiet that ends an utterance")
+    parser.add_argument("--max-utterance", type=float, default=30.0, metavar="SECONDS",
+                        help="Speech after which an utterance is transcribed without a pause")
+    parser.add_argument("--benchmark", metavar="PATH",
+                        help="Time repeated transcriptions of an audio file and exit")
+    parser.add_argument("--runs", type=int, default=5, help="Benchmark repetitions")
+    parser.add_argument("--no-type", action="store_true",
+                        help="Leave typing transcripts to the host (e.g. via a Wayland portal)")
+    parser.add_argument("--list-devices", action="store_true",
+                        help="Print input device names as a JSON list and exit")
+    parser.add_argument("--prefetch", action="store_true",
+                        help="Download the model weights without loading them and exit")
+    parser.add_argument("--check-model", action="store_true",
+                        help="Print where the model is cached, or exit 1 if it is not")
+    parser.add_argument("--input-device", metavar="NAME",
+                        help="Microphone to capture from instead of the system default")
+    parser.add_argument("--device", choices=["cpu", "cuda"], default="cpu",
+                        help="Run inference on the CPU or an NVIDIA GPU")
+    parser.add_argument("--compute-type", default="int8",
+                        help="CTranslate2 compute type, e.g. int8, float16, int8_float16")
+    parser.add_argument("--threads", type=int, default=0,
+                        help="CPU threads for inference (0 lets CTranslate2 choose)")
+    parser.add_argument("--beam-size", type=int, default=5, help="Decoding beam width")
+    parser.add_argument("--batch-size", type=int, default=1,
+                        help="Segments decoded together when transcribing files")
+    parser.add_argument("--rpc", action="store_true",
+                        help="Take JSON-RPC requests on stdin and wait for start (needs --json)")
+    parser.add_argument("--save-audio", metavar="DIR",
+                        help="Write each transcribed chunk as a WAV file in DIR (needs --json)")
+    parser.add_argument("--initial-prompt", metavar="TEXT",
+                        help="Text Whisper reads before each chunk, e.g. a list of names")
+    parser.add_argument("--diarize", action="store_true",
+                        help="Label transcripts with who spoke (needs pyannote.audio)")
+    args = parser.parse_args()
+    if args.rpc and args.input == "stdin":
+        parser.error("--rpc needs stdin, which --input stdin uses for audio")
+
+    if args.list_devices:
+        devices = [d["name"] for d in sd.query_devices() if d["max_input_channels"] > 0]
+        print(json.dumps(devices))
+        sys.exit(0)
+    if args.prefetch:
+        download_model(args.model)
+        sys.exit(0)
+    if args.check_model:
+        try:
+            print(download_model(args.model, local_files_only=True))
+        except Exception as e:
+            print(f"Model {args.model} is not downloaded: {e}", file=sys.stderr)
+            sys.exit(1)
+        sys.exit(0)
+
+    load_started = time.monotonic()
+    daemon = SystemSTTDaemon(model_size=args.model, input_mode=args.input,
+                             json_output=args.json, language=args.language,
+                             idle_unload=args.idle_unload, input_file=args.input_file,
+                             speed=args.speed, no_type=args.no_type, device=args.device,
+                             compute_type=args.compute_type, threads=args.threads,
+                             beam_size=args.beam_size, batch_size=args.batch_size,
+                             ring_path=args.ring, input_device=args.input_device,
+                             rpc=args.rpc, save_audio=args.save_audio,
+                             initial_prompt=args.initial_prompt, diarize=args.diarize,
+                             pre_roll=args.pre_roll, silence_timeout=args.silence_timeout,
+                             max_utterance=args.max_utterance)
+    if args.benchmark:
+        daemon.emit({"type": "benchmark_load", "ms": (time.monotonic() - load_started) * 1000})
+        daemon.benchmark_file(args.benchmark, args.runs)
+    elif args.transcribe_file:
+        daemon.transcribe_file(args.transcribe_file)
+    elif args.rpc:
+        daemon.serve()
+    else:
+        daemon.start()